@@ -52,6 +52,17 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		client.clone(),
 	);
 
+	if let Some(path) = config.transaction_pool_persistence.clone() {
+		task_manager.spawn_handle().spawn(
+			"txpool-persistence",
+			sc_transaction_pool::persistence_future(
+				transaction_pool.clone(),
+				sp_runtime::generic::BlockId::Hash(client.chain_info().best_hash),
+				path,
+			),
+		);
+	}
+
 	let (grandpa_block_import, grandpa_link) = sc_finality_grandpa::block_import(
 		client.clone(), &(client.clone() as Arc<_>), select_chain.clone(),
 	)?;
@@ -113,6 +124,14 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 	let force_authoring = config.force_authoring;
 	let name = config.network.node_name.clone();
 	let enable_grandpa = !config.disable_grandpa;
+	let grandpa_stall_threshold = config.grandpa_stall_threshold;
+	// without GRANDPA the finalized head never moves, so backing off would
+	// eventually stop block authoring altogether.
+	let backoff_authoring_blocks = if enable_grandpa && config.backoff_authoring_blocks {
+		Some(sc_consensus_aura::BackoffAuthoringOnFinalizedHeadLagging::default())
+	} else {
+		None
+	};
 	let prometheus_registry = config.prometheus_registry().cloned();
 	let telemetry_connection_sinks = sc_service::TelemetryConnectionSinks::default();
 
@@ -155,7 +174,7 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 		let can_author_with =
 			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
 
-		let aura = sc_consensus_aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _>(
+		let aura = sc_consensus_aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _, _>(
 			sc_consensus_aura::slot_duration(&*client)?,
 			client.clone(),
 			select_chain,
@@ -164,6 +183,7 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 			network.clone(),
 			inherent_data_providers.clone(),
 			force_authoring,
+			backoff_authoring_blocks,
 			keystore_container.sync_keystore(),
 			can_author_with,
 		)?;
@@ -189,6 +209,7 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
 		observer_enabled: false,
 		keystore,
 		is_authority: role.is_network_authority(),
+		stall_threshold: grandpa_stall_threshold,
 	};
 
 	if enable_grandpa {
@@ -41,8 +41,10 @@ use sp_runtime::{
 };
 use sp_transaction_pool::{
 	ImportNotificationStream,
+	PoolEventStream,
 	PoolFuture,
 	PoolStatus,
+	PoolTransactionInfo,
 	TransactionFor,
 	TransactionSource,
 	TransactionStatusStreamFor,
@@ -294,4 +296,16 @@ impl sp_transaction_pool::TransactionPool for Transactions {
 	fn ready_transaction(&self, _hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
 		unimplemented!()
 	}
+
+	fn inspect(&self) -> Vec<PoolTransactionInfo<TxHash<Self>>> {
+		unimplemented!()
+	}
+
+	fn transaction_info(&self, _hash: &TxHash<Self>) -> Option<PoolTransactionInfo<TxHash<Self>>> {
+		unimplemented!()
+	}
+
+	fn pool_event_stream(&self) -> PoolEventStream<TxHash<Self>> {
+		unimplemented!()
+	}
 }
@@ -321,6 +321,7 @@ pub fn testnet_genesis(
 			max_members: 999,
 		}),
 		pallet_vesting: Some(Default::default()),
+		pallet_transaction_storage: Some(Default::default()),
 	}
 }
 
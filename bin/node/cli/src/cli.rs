@@ -28,6 +28,11 @@ pub struct Cli {
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	/// Seal a block as soon as a transaction enters the pool, instead of
+	/// authoring BABE slots and running GRANDPA. Only available with `--dev`.
+	#[structopt(long = "instant-seal")]
+	pub instant_seal: bool,
 }
 
 /// Possible subcommands of the main binary.
@@ -74,6 +79,9 @@ pub enum Subcommand {
 	/// Remove the whole chain.
 	PurgeChain(sc_cli::PurgeChainCmd),
 
+	/// Database maintenance utilities.
+	Db(sc_cli::DbSubcommand),
+
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 }
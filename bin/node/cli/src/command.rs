@@ -71,10 +71,16 @@ pub fn run() -> Result<()> {
 
 	match &cli.subcommand {
 		None => {
+			if cli.instant_seal && !cli.run.shared_params.dev {
+				return Err("`--instant-seal` can only be used with `--dev`.".into());
+			}
+
+			let instant_seal = cli.instant_seal;
 			let runner = cli.create_runner(&cli.run)?;
 			runner.run_node_until_exit(|config| async move {
 				match config.role {
 					Role::Light => service::new_light(config),
+					_ if instant_seal => service::new_full_instant_seal(config),
 					_ => service::new_full(config),
 				}
 			})
@@ -138,6 +144,26 @@ pub fn run() -> Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run(config.database))
 		},
+		Some(Subcommand::Db(sc_cli::DbSubcommand::Migrate(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run(config.database, config.informant_output_format))
+		},
+		Some(Subcommand::Db(sc_cli::DbSubcommand::Stats(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run::<Block>(config.database))
+		},
+		Some(Subcommand::Db(sc_cli::DbSubcommand::DumpTrie(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run::<Block>(config.database))
+		},
+		Some(Subcommand::Db(sc_cli::DbSubcommand::Orphans(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run::<Block>(config.database))
+		},
+		Some(Subcommand::Db(sc_cli::DbSubcommand::Check(cmd))) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| cmd.run::<Block>(config.database))
+		},
 		Some(Subcommand::Revert(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -33,8 +33,9 @@ use sp_inherents::InherentDataProviders;
 use sc_network::{Event, NetworkService};
 use sp_runtime::traits::Block as BlockT;
 use futures::prelude::*;
-use sc_client_api::{ExecutorProvider, RemoteBackend};
+use sc_client_api::{ExecutorProvider, RemoteBackend, Backend as _};
 use node_executor::Executor;
+use sc_consensus_manual_seal::consensus::babe::BabeConsensusDataProvider;
 
 type FullClient = sc_service::TFullClient<Block, RuntimeApi, Executor>;
 type FullBackend = sc_service::TFullBackend<Block>;
@@ -76,6 +77,17 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		client.clone(),
 	);
 
+	if let Some(path) = config.transaction_pool_persistence.clone() {
+		task_manager.spawn_handle().spawn(
+			"txpool-persistence",
+			sc_transaction_pool::persistence_future(
+				transaction_pool.clone(),
+				sp_runtime::generic::BlockId::Hash(client.chain_info().best_hash),
+				path,
+			),
+		);
+	}
+
 	let (grandpa_block_import, grandpa_link) = grandpa::block_import(
 		client.clone(), &(client.clone() as Arc<_>), select_chain.clone(),
 	)?;
@@ -88,6 +100,12 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 	)?;
 
 	let inherent_data_providers = sp_inherents::InherentDataProviders::new();
+	inherent_data_providers
+		.register_provider(sp_transaction_storage_proof::registration::InherentDataProvider::new(
+			client.clone(),
+		))
+		.map_err(Into::into)
+		.map_err(sp_consensus::Error::InherentData)?;
 
 	let import_queue = sc_consensus_babe::import_queue(
 		babe_link.clone(),
@@ -109,6 +127,8 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 
 		let justification_stream = grandpa_link.justification_stream();
 		let shared_authority_set = grandpa_link.shared_authority_set().clone();
+		let shared_voter_history = grandpa_link.voter_history();
+		let shared_finality_lag = grandpa_link.finality_lag();
 		let shared_voter_state = grandpa::SharedVoterState::empty();
 		let finality_proof_provider =
 			GrandpaFinalityProofProvider::new_for_service(backend.clone(), client.clone());
@@ -123,6 +143,7 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 		let select_chain = select_chain.clone();
 		let keystore = keystore_container.sync_keystore();
 		let chain_spec = config.chain_spec.cloned_box();
+		let offchain_db = backend.offchain_storage();
 
 		let rpc_extensions_builder = move |deny_unsafe, subscription_executor| {
 			let deps = node_rpc::FullDeps {
@@ -139,10 +160,13 @@ pub fn new_partial(config: &Configuration) -> Result<sc_service::PartialComponen
 				grandpa: node_rpc::GrandpaDeps {
 					shared_voter_state: shared_voter_state.clone(),
 					shared_authority_set: shared_authority_set.clone(),
+					shared_voter_history: shared_voter_history.clone(),
+					shared_finality_lag: shared_finality_lag.clone(),
 					justification_stream: justification_stream.clone(),
 					subscription_executor,
 					finality_provider: finality_proof_provider.clone(),
 				},
+				offchain_db: offchain_db.clone(),
 			};
 
 			node_rpc::create_full(deps)
@@ -206,6 +230,14 @@ pub fn new_full_base(
 	let force_authoring = config.force_authoring;
 	let name = config.network.node_name.clone();
 	let enable_grandpa = !config.disable_grandpa;
+	let grandpa_stall_threshold = config.grandpa_stall_threshold;
+	// without GRANDPA the finalized head never moves, so backing off would
+	// eventually stop block authoring altogether.
+	let backoff_authoring_blocks = if enable_grandpa && config.backoff_authoring_blocks {
+		Some(sc_consensus_babe::BackoffAuthoringOnFinalizedHeadLagging::default())
+	} else {
+		None
+	};
 	let prometheus_registry = config.prometheus_registry().cloned();
 	let telemetry_connection_sinks = sc_service::TelemetryConnectionSinks::default();
 
@@ -229,6 +261,13 @@ pub fn new_full_base(
 
 	(with_startup_data)(&block_import, &babe_link);
 
+	// used by GRANDPA to only vote on blocks whose epoch data is known.
+	let epoch_data = {
+		let client = client.clone();
+		let babe_link = babe_link.clone();
+		move |header: &<Block as BlockT>::Header| babe_link.has_epoch_data(&*client, header)
+	};
+
 	if let sc_service::config::Role::Authority { .. } = &role {
 		let proposer = sc_basic_authorship::ProposerFactory::new(
 			task_manager.spawn_handle(),
@@ -249,6 +288,7 @@ pub fn new_full_base(
 			sync_oracle: network.clone(),
 			inherent_data_providers: inherent_data_providers.clone(),
 			force_authoring,
+			backoff_authoring_blocks,
 			babe_link,
 			can_author_with,
 		};
@@ -294,6 +334,7 @@ pub fn new_full_base(
 		observer_enabled: false,
 		keystore,
 		is_authority: role.is_network_authority(),
+		stall_threshold: grandpa_stall_threshold,
 	};
 
 	if enable_grandpa {
@@ -308,7 +349,13 @@ pub fn new_full_base(
 			link: grandpa_link,
 			network: network.clone(),
 			telemetry_on_connect: Some(telemetry_connection_sinks.on_connect_stream()),
-			voting_rule: grandpa::VotingRulesBuilder::default().build(),
+			voting_rule: grandpa::VotingRulesBuilder::default()
+				.add(grandpa::KnownEpochData::new(epoch_data))
+				.add(grandpa::RuntimeVotingRule::new(client.clone(), {
+					let network = network.clone();
+					move || network.peers_best_numbers()
+				}))
+				.build(),
 			prometheus_registry,
 			shared_voter_state,
 		};
@@ -342,6 +389,101 @@ pub fn new_full(config: Configuration)
 	})
 }
 
+/// Builds a new service for a full client that seals a block as soon as a
+/// transaction enters the pool, using the BABE consensus data provider of manual
+/// seal instead of authoring BABE slots. GRANDPA is disabled.
+pub fn new_full_instant_seal(config: Configuration) -> Result<TaskManager, ServiceError> {
+	let sc_service::PartialComponents {
+		client, backend, mut task_manager, import_queue, keystore_container,
+		select_chain, transaction_pool, inherent_data_providers: _,
+		other: (rpc_extensions_builder, import_setup, rpc_setup),
+	} = new_partial(&config)?;
+
+	let (_, finality_proof_provider) = rpc_setup;
+
+	let (network, network_status_sinks, system_rpc_tx, network_starter) =
+		sc_service::build_network(sc_service::BuildNetworkParams {
+			config: &config,
+			client: client.clone(),
+			transaction_pool: transaction_pool.clone(),
+			spawn_handle: task_manager.spawn_handle(),
+			import_queue,
+			on_demand: None,
+			block_announce_validator_builder: None,
+			finality_proof_request_builder: None,
+			finality_proof_provider: Some(finality_proof_provider),
+		})?;
+
+	if config.offchain_worker.enabled {
+		sc_service::build_offchain_workers(
+			&config, backend.clone(), task_manager.spawn_handle(), client.clone(), network.clone(),
+		);
+	}
+
+	let prometheus_registry = config.prometheus_registry().cloned();
+
+	sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+		config,
+		backend,
+		client: client.clone(),
+		keystore: keystore_container.sync_keystore(),
+		network: network.clone(),
+		rpc_extensions_builder: Box::new(rpc_extensions_builder),
+		transaction_pool: transaction_pool.clone(),
+		task_manager: &mut task_manager,
+		on_demand: None,
+		remote_blockchain: None,
+		telemetry_connection_sinks: sc_service::TelemetryConnectionSinks::default(),
+		network_status_sinks,
+		system_rpc_tx,
+	})?;
+
+	let (block_import, _, babe_link) = import_setup;
+
+	let proposer = sc_basic_authorship::ProposerFactory::new(
+		task_manager.spawn_handle(),
+		client.clone(),
+		transaction_pool.clone(),
+		prometheus_registry.as_ref(),
+	);
+
+	// the timestamp is mocked by the consensus data provider, so it can't use the
+	// inherent data providers of the import queue which already provide one.
+	let inherent_data_providers = sp_inherents::InherentDataProviders::new();
+	inherent_data_providers
+		.register_provider(sp_transaction_storage_proof::registration::InherentDataProvider::new(
+			client.clone(),
+		))
+		.map_err(Into::into)
+		.map_err(sp_consensus::Error::InherentData)?;
+
+	let consensus_data_provider = BabeConsensusDataProvider::new(
+		client.clone(),
+		keystore_container.sync_keystore(),
+		&inherent_data_providers,
+		babe_link.epoch_changes().clone(),
+	).map_err(|e| ServiceError::Other(format!("{}", e)))?;
+
+	let authorship_future = sc_consensus_manual_seal::run_instant_seal(
+		sc_consensus_manual_seal::InstantSealParams {
+			block_import,
+			env: proposer,
+			client,
+			pool: transaction_pool.pool().clone(),
+			select_chain,
+			consensus_data_provider: Some(Box::new(consensus_data_provider)),
+			inherent_data_providers,
+		}
+	);
+
+	task_manager.spawn_essential_handle().spawn_blocking("instant-seal", authorship_future);
+
+	grandpa::setup_disabled_grandpa(network)?;
+
+	network_starter.start_network();
+	Ok(task_manager)
+}
+
 pub fn new_light_base(config: Configuration) -> Result<(
 	TaskManager, RpcHandlers, Arc<LightClient>,
 	Arc<NetworkService<Block, <Block as BlockT>::Hash>>,
@@ -38,7 +38,8 @@ use sc_consensus_babe::{Config, Epoch};
 use sc_consensus_babe_rpc::BabeRpcHandler;
 use sc_consensus_epochs::SharedEpochChanges;
 use sc_finality_grandpa::{
-	SharedVoterState, SharedAuthoritySet, FinalityProofProvider, GrandpaJustificationStream
+	SharedVoterState, SharedVoterHistory, SharedFinalityLag, SharedAuthoritySet,
+	FinalityProofProvider, GrandpaJustificationStream,
 };
 use sc_finality_grandpa_rpc::GrandpaRpcHandler;
 pub use sc_rpc_api::DenyUnsafe;
@@ -79,6 +80,10 @@ pub struct GrandpaDeps<B> {
 	pub shared_voter_state: SharedVoterState,
 	/// Authority set info.
 	pub shared_authority_set: SharedAuthoritySet<Hash, BlockNumber>,
+	/// Recent voter history.
+	pub shared_voter_history: SharedVoterHistory<Block>,
+	/// Finality lag info.
+	pub shared_finality_lag: SharedFinalityLag<Block>,
 	/// Receives notifications about justification events from Grandpa.
 	pub justification_stream: GrandpaJustificationStream<Block>,
 	/// Executor to drive the subscription manager in the Grandpa RPC handler.
@@ -88,7 +93,9 @@ pub struct GrandpaDeps<B> {
 }
 
 /// Full client dependencies.
-pub struct FullDeps<C, P, SC, B> {
+pub struct FullDeps<C, P, SC, B> where
+	B: sc_client_api::Backend<Block>,
+{
 	/// The client instance to use.
 	pub client: Arc<C>,
 	/// Transaction pool instance.
@@ -103,6 +110,8 @@ pub struct FullDeps<C, P, SC, B> {
 	pub babe: BabeDeps,
 	/// GRANDPA specific dependencies.
 	pub grandpa: GrandpaDeps<B>,
+	/// The offchain database, used to generate MMR proofs.
+	pub offchain_db: Option<B::OffchainStorage>,
 }
 
 /// A IO handler that uses all Full RPC extensions.
@@ -117,6 +126,7 @@ pub fn create_full<C, P, SC, B>(
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: pallet_mmr_rpc::MmrRuntimeApi<Block, Hash>,
 	C::Api: BabeApi<Block>,
 	C::Api: BlockBuilder<Block>,
 	P: TransactionPool + 'static,
@@ -127,6 +137,7 @@ pub fn create_full<C, P, SC, B>(
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 	use pallet_contracts_rpc::{Contracts, ContractsApi};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
+	use pallet_mmr_rpc::{Mmr, MmrApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
 	let FullDeps {
@@ -137,6 +148,7 @@ pub fn create_full<C, P, SC, B>(
 		deny_unsafe,
 		babe,
 		grandpa,
+		offchain_db,
 	} = deps;
 
 	let BabeDeps {
@@ -147,6 +159,8 @@ pub fn create_full<C, P, SC, B>(
 	let GrandpaDeps {
 		shared_voter_state,
 		shared_authority_set,
+		shared_voter_history,
+		shared_finality_lag,
 		justification_stream,
 		subscription_executor,
 		finality_provider,
@@ -164,6 +178,13 @@ pub fn create_full<C, P, SC, B>(
 	io.extend_with(
 		TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
 	);
+	// MMR proofs are generated from the offchain database, so the API is only
+	// available if the backend has one.
+	if let Some(offchain_db) = offchain_db {
+		io.extend_with(
+			MmrApi::to_delegate(Mmr::new(client.clone(), offchain_db))
+		);
+	}
 	io.extend_with(
 		sc_consensus_babe_rpc::BabeApi::to_delegate(
 			BabeRpcHandler::new(
@@ -181,9 +202,12 @@ pub fn create_full<C, P, SC, B>(
 			GrandpaRpcHandler::new(
 				shared_authority_set.clone(),
 				shared_voter_state,
+				shared_voter_history,
+				shared_finality_lag,
 				justification_stream,
 				subscription_executor,
 				finality_provider,
+				deny_unsafe,
 			)
 		)
 	);
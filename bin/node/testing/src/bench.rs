@@ -412,6 +412,8 @@ impl BenchDb {
 			state_cache_child_ratio: Some((0, 100)),
 			pruning: PruningMode::ArchiveAll,
 			source: database_type.into_settings(dir.into()),
+			transaction_storage: sc_client_db::TransactionStorageMode::BlockBody,
+			keep_blocks: sc_client_db::KeepBlocks::All,
 		};
 		let task_executor = TaskExecutor::new();
 
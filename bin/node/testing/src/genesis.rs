@@ -119,5 +119,6 @@ pub fn config_endowed(
 			max_members: 999,
 		}),
 		pallet_vesting: Some(Default::default()),
+		pallet_transaction_storage: Some(Default::default()),
 	}
 }
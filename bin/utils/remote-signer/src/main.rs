@@ -0,0 +1,84 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Reference signer for nodes started with `--keystore-uri unix://<socket>`.
+
+use std::path::PathBuf;
+
+use structopt::StructOpt;
+
+/// A signer that holds keys on behalf of nodes using a remote keystore.
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+struct RemoteSigner {
+	/// Path of the Unix domain socket to listen on.
+	#[structopt(long, parse(from_os_str))]
+	socket: PathBuf,
+	/// Keystore directory. Keys are only kept in memory if it is not given.
+	#[structopt(long, parse(from_os_str))]
+	keystore_path: Option<PathBuf>,
+	/// Password used by the keystore.
+	#[structopt(long)]
+	password: Option<String>,
+}
+
+#[cfg(unix)]
+fn main() -> Result<(), String> {
+	use std::{io::BufReader, os::unix::net::UnixListener, sync::Arc, thread};
+	use sc_keystore::{LocalKeystore, remote::{RemoteSigner as Signer, UNIX_SCHEME}};
+	use sp_core::crypto::SecretString;
+
+	let RemoteSigner { socket, keystore_path, password } = RemoteSigner::from_args();
+
+	let keystore = match keystore_path {
+		Some(path) => LocalKeystore::open(path, password.map(SecretString::new))
+			.map_err(|e| format!("Failed to open keystore: {}", e))?,
+		None => LocalKeystore::in_memory(),
+	};
+	let signer = Arc::new(Signer::new(Arc::new(keystore)));
+
+	let listener = UnixListener::bind(&socket)
+		.map_err(|e| format!("Failed to listen on {}: {}", socket.display(), e))?;
+	println!("Listening on {}{}", UNIX_SCHEME, socket.display());
+
+	for stream in listener.incoming() {
+		let stream = match stream {
+			Ok(stream) => stream,
+			Err(e) => {
+				eprintln!("Failed to accept connection: {}", e);
+				continue;
+			}
+		};
+		let signer = signer.clone();
+		thread::spawn(move || {
+			let result = stream.try_clone()
+				.and_then(|reader| signer.serve(BufReader::new(reader), stream));
+			if let Err(e) = result {
+				eprintln!("Connection closed: {}", e);
+			}
+		});
+	}
+
+	Ok(())
+}
+
+#[cfg(not(unix))]
+fn main() -> Result<(), String> {
+	let _ = RemoteSigner::from_args();
+	Err("The remote signer listens on a Unix domain socket, which this platform lacks.".into())
+}
@@ -386,6 +386,18 @@ impl<Block: BlockT> blockchain::Backend<Block> for Blockchain<Block> {
 	fn children(&self, _parent_hash: Block::Hash) -> sp_blockchain::Result<Vec<Block::Hash>> {
 		unimplemented!()
 	}
+
+	fn indexed_transaction(&self, _hash: &Block::Hash) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		// The in-mem backend doesn't keep a transaction index.
+		Ok(None)
+	}
+
+	fn block_indexed_body(
+		&self,
+		_id: BlockId<Block>,
+	) -> sp_blockchain::Result<Option<Vec<Vec<u8>>>> {
+		Ok(None)
+	}
 }
 
 impl<Block: BlockT> blockchain::ProvideCache<Block> for Blockchain<Block> {
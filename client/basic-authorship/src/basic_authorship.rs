@@ -22,7 +22,7 @@
 
 use std::{pin::Pin, time, sync::Arc};
 use sc_client_api::backend;
-use codec::Decode;
+use codec::{Decode, Encode};
 use sp_consensus::{evaluation, Proposal, RecordProof};
 use sp_core::traits::SpawnNamed;
 use sp_inherents::InherentData;
@@ -30,6 +30,7 @@ use log::{error, info, debug, trace, warn};
 use sp_runtime::{
 	generic::BlockId,
 	traits::{Block as BlockT, Hash as HashT, Header as HeaderT, DigestFor, BlakeTwo256},
+	Percent,
 };
 use sp_transaction_pool::{TransactionPool, InPoolTransaction};
 use sc_telemetry::{telemetry, CONSENSUS_INFO};
@@ -42,6 +43,12 @@ use std::marker::PhantomData;
 use prometheus_endpoint::Registry as PrometheusRegistry;
 use sc_proposer_metrics::MetricsLink as PrometheusMetrics;
 
+use crate::selection::{BlockUsage, Candidate, PriorityOrder, Selection, SelectionStrategy};
+
+/// Default share of the proposing time after which the proposer stops at the first
+/// transaction that doesn't make it into the block.
+pub const DEFAULT_SOFT_DEADLINE_PERCENT: Percent = Percent::from_percent(50);
+
 /// Proposer factory.
 pub struct ProposerFactory<A, B, C> {
 	spawn_handle: Box<dyn SpawnNamed>,
@@ -51,6 +58,12 @@ pub struct ProposerFactory<A, B, C> {
 	transaction_pool: Arc<A>,
 	/// Prometheus Link,
 	metrics: PrometheusMetrics,
+	/// Strategy of selecting transactions to be included in the block.
+	selection: Arc<dyn SelectionStrategy>,
+	/// Share of the proposing time after which the proposer gives up on the first skipped transaction.
+	soft_deadline_percent: Percent,
+	/// Maximal size of the storage proof, only enforced if the proof is recorded.
+	proof_size_limit: Option<usize>,
 	/// phantom member to pin the `Backend` type.
 	_phantom: PhantomData<B>,
 }
@@ -67,9 +80,34 @@ impl<A, B, C> ProposerFactory<A, B, C> {
 			client,
 			transaction_pool,
 			metrics: PrometheusMetrics::new(prometheus),
+			selection: Arc::new(PriorityOrder::default()),
+			soft_deadline_percent: DEFAULT_SOFT_DEADLINE_PERCENT,
+			proof_size_limit: None,
 			_phantom: PhantomData,
 		}
 	}
+
+	/// Sets the strategy of selecting transactions to be included in proposed blocks.
+	///
+	/// Defaults to [`PriorityOrder`].
+	pub fn set_selection_strategy(&mut self, selection: impl SelectionStrategy + 'static) {
+		self.selection = Arc::new(selection);
+	}
+
+	/// Sets the share of the proposing time after which the proposer stops pushing
+	/// transactions as soon as one of them is skipped.
+	///
+	/// Defaults to [`DEFAULT_SOFT_DEADLINE_PERCENT`].
+	pub fn set_soft_deadline(&mut self, percent: Percent) {
+		self.soft_deadline_percent = percent;
+	}
+
+	/// Sets the maximal size of the storage proof of proposed blocks.
+	///
+	/// The limit is only enforced for proposals that record the storage proof.
+	pub fn set_proof_size_limit(&mut self, limit: Option<usize>) {
+		self.proof_size_limit = limit;
+	}
 }
 
 impl<B, Block, C, A> ProposerFactory<A, B, C>
@@ -102,6 +140,9 @@ impl<B, Block, C, A> ProposerFactory<A, B, C>
 			transaction_pool: self.transaction_pool.clone(),
 			now,
 			metrics: self.metrics.clone(),
+			selection: self.selection.clone(),
+			soft_deadline_percent: self.soft_deadline_percent,
+			proof_size_limit: self.proof_size_limit,
 			_phantom: PhantomData,
 		};
 
@@ -142,6 +183,9 @@ pub struct Proposer<B, Block: BlockT, C, A: TransactionPool> {
 	transaction_pool: Arc<A>,
 	now: Box<dyn Fn() -> time::Instant + Send + Sync>,
 	metrics: PrometheusMetrics,
+	selection: Arc<dyn SelectionStrategy>,
+	soft_deadline_percent: Percent,
+	proof_size_limit: Option<usize>,
 	_phantom: PhantomData<B>,
 }
 
@@ -212,18 +256,15 @@ impl<A, B, Block, C> Proposer<B, Block, C, A>
 		deadline: time::Instant,
 		record_proof: RecordProof,
 	) -> Result<Proposal<Block, backend::TransactionFor<B, Block>>, sp_blockchain::Error> {
-		/// If the block is full we will attempt to push at most
-		/// this number of transactions before quitting for real.
-		/// It allows us to increase block utilization.
-		const MAX_SKIPPED_TRANSACTIONS: usize = 8;
-
 		let mut block_builder = self.client.new_block_at(
 			&self.parent_id,
 			inherent_digests,
 			record_proof,
 		)?;
 
+		let mut usage = BlockUsage::default();
 		for inherent in block_builder.create_inherents(inherent_data)? {
+			let size = inherent.encoded_size();
 			match block_builder.push(inherent) {
 				Err(ApplyExtrinsicFailed(Validity(e))) if e.exhausted_resources() =>
 					warn!("⚠️  Dropping non-mandatory inherent from overweight block."),
@@ -234,17 +275,22 @@ impl<A, B, Block, C> Proposer<B, Block, C, A>
 				Err(e) => {
 					warn!("❗️ Inherent extrinsic returned unexpected error: {}. Dropping.", e);
 				}
-				Ok(_) => {}
+				Ok(_) => usage.size += size,
 			}
 		}
 
 		// proceed with transactions
 		let block_timer = time::Instant::now();
-		let mut skipped = 0;
 		let mut unqueue_invalid = Vec::new();
 
+		let now = (self.now)();
+		let left = deadline.saturating_duration_since(now);
+		let soft_deadline = now + time::Duration::from_micros(
+			self.soft_deadline_percent.mul_floor(left.as_micros() as u64),
+		);
+
 		let mut t1 = self.transaction_pool.ready_at(self.parent_number).fuse();
-		let mut t2 = futures_timer::Delay::new(deadline.saturating_duration_since((self.now)()) / 8).fuse();
+		let mut t2 = futures_timer::Delay::new(left / 8).fuse();
 
 		let pending_iterator = select! {
 			res = t1 => res,
@@ -260,8 +306,10 @@ impl<A, B, Block, C> Proposer<B, Block, C, A>
 
 		debug!("Attempting to push transactions from the pool.");
 		debug!("Pool status: {:?}", self.transaction_pool.status());
+		let selection_timer = time::Instant::now();
 		for pending_tx in pending_iterator {
-			if (self.now)() > deadline {
+			let now = (self.now)();
+			if now > deadline {
 				debug!(
 					"Consensus deadline reached when pushing block transactions, \
 					proceeding with proposing."
@@ -271,25 +319,60 @@ impl<A, B, Block, C> Proposer<B, Block, C, A>
 
 			let pending_tx_data = pending_tx.data().clone();
 			let pending_tx_hash = pending_tx.hash().clone();
+			let candidate = Candidate {
+				size: pending_tx_data.encoded_size(),
+				priority: *pending_tx.priority(),
+			};
+
+			usage.proof_size = block_builder.estimated_proof_size();
+			let selection = match (self.proof_size_limit, usage.proof_size) {
+				(Some(limit), Some(proof_size)) if proof_size >= limit => Selection::Stop,
+				(Some(limit), Some(proof_size)) if proof_size + candidate.size > limit => Selection::Skip,
+				_ => self.selection.select(&usage, &candidate),
+			};
+			match selection {
+				Selection::Push => {},
+				Selection::Skip => {
+					usage.skipped += 1;
+					if now > soft_deadline {
+						debug!("Soft deadline reached when skipping a transaction, proceeding with proposing.");
+						break;
+					}
+					trace!("[{:?}] Skipped by the selection strategy.", pending_tx_hash);
+					continue;
+				},
+				Selection::Stop => {
+					debug!("Block is full, proceed with proposing.");
+					break;
+				},
+			}
+
 			trace!("[{:?}] Pushing to the block.", pending_tx_hash);
 			match sc_block_builder::BlockBuilder::push(&mut block_builder, pending_tx_data) {
 				Ok(()) => {
+					usage.included += 1;
+					usage.size += candidate.size;
 					debug!("[{:?}] Pushed to the block.", pending_tx_hash);
 				}
 				Err(ApplyExtrinsicFailed(Validity(e)))
 						if e.exhausted_resources() => {
-					if skipped < MAX_SKIPPED_TRANSACTIONS {
-						skipped += 1;
-						debug!(
-							"Block seems full, but will try {} more transactions before quitting.",
-							MAX_SKIPPED_TRANSACTIONS - skipped,
-						);
-					} else {
+					usage.skipped += 1;
+					usage.exhausted += 1;
+					usage.smallest_exhausted = Some(
+						usage.smallest_exhausted.map_or(candidate.size, |smallest| smallest.min(candidate.size)),
+					);
+					if now > soft_deadline {
+						debug!("Soft deadline reached and block seems full, proceeding with proposing.");
+						break;
+					}
+					if let Selection::Stop = self.selection.on_exhausted(&usage, &candidate) {
 						debug!("Block is full, proceed with proposing.");
 						break;
 					}
+					debug!("Block seems full, but will try more transactions before quitting.");
 				}
-				Err(e) if skipped > 0 => {
+				// transactions depending on a skipped one fail as well, they are still valid though
+				Err(e) if usage.skipped > 0 => {
 					trace!(
 						"[{:?}] Ignoring invalid transaction when skipping: {}",
 						pending_tx_hash,
@@ -302,6 +385,7 @@ impl<A, B, Block, C> Proposer<B, Block, C, A>
 				}
 			}
 		}
+		let selection_time = selection_timer.elapsed();
 
 		self.transaction_pool.remove_invalid(&unqueue_invalid);
 
@@ -310,10 +394,21 @@ impl<A, B, Block, C> Proposer<B, Block, C, A>
 		self.metrics.report(
 			|metrics| {
 				metrics.number_of_transactions.set(block.extrinsics().len() as u64);
+				metrics.included_transactions.set(usage.included as u64);
+				metrics.skipped_transactions.set(usage.skipped as u64);
+				metrics.transaction_selection_time.observe(selection_time.as_secs_f64());
 				metrics.block_constructed.observe(block_timer.elapsed().as_secs_f64());
 			}
 		);
 
+		debug!(
+			"Selected transactions in {:?}: {} included, {} skipped ({} exhausted the block).",
+			selection_time,
+			usage.included,
+			usage.skipped,
+			usage.exhausted,
+		);
+
 		info!("🎁 Prepared block for proposing at {} [hash: {:?}; parent_hash: {}; extrinsics ({}): [{}]]",
 			block.header().number(),
 			<Block as BlockT>::Hash::from(block.header().hash()),
@@ -543,6 +638,57 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn should_stop_when_proof_size_limit_is_reached() {
+		// given
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let txpool = BasicPool::new_full(
+			Default::default(),
+			None,
+			spawner.clone(),
+			client.clone(),
+		);
+
+		futures::executor::block_on(
+			txpool.submit_at(&BlockId::number(0), SOURCE, vec![extrinsic(0)])
+		).unwrap();
+
+		futures::executor::block_on(
+			txpool.maintain(chain_event(
+				client.header(&BlockId::Number(0u64))
+					.expect("header get error")
+					.expect("there should be header")
+			))
+		);
+
+		let mut proposer_factory = ProposerFactory::new(
+			spawner.clone(),
+			client.clone(),
+			txpool.clone(),
+			None,
+		);
+		proposer_factory.set_proof_size_limit(Some(1));
+
+		let propose = |proposer_factory: &mut ProposerFactory<_, _, _>, record_proof| {
+			let proposer = proposer_factory.init_with_now(
+				&client.header(&BlockId::number(0)).unwrap().unwrap(),
+				Box::new(move || time::Instant::now()),
+			);
+			let deadline = time::Duration::from_secs(9);
+			futures::executor::block_on(
+				proposer.propose(Default::default(), Default::default(), deadline, record_proof)
+			).map(|r| r.block).unwrap()
+		};
+
+		// when & then
+		// the limit is exceeded by the initialization of the block already
+		assert_eq!(propose(&mut proposer_factory, RecordProof::Yes).extrinsics().len(), 0);
+		// and it is not enforced if the proof is not recorded
+		assert_eq!(propose(&mut proposer_factory, RecordProof::No).extrinsics().len(), 1);
+		assert_eq!(txpool.ready().count(), 1);
+	}
+
 	#[test]
 	fn should_not_remove_invalid_transactions_when_skipping() {
 		// given
@@ -632,4 +778,69 @@ mod tests {
 		let block = propose_block(&client, 1, 2, 5);
 		client.import(BlockOrigin::Own, block).unwrap();
 	}
+
+	#[test]
+	fn should_not_remove_dependent_transactions_when_skipping() {
+		struct SkipFirst(Mutex<bool>);
+
+		impl SelectionStrategy for SkipFirst {
+			fn select(&self, _usage: &BlockUsage, _candidate: &Candidate) -> Selection {
+				if std::mem::replace(&mut *self.0.lock(), false) {
+					Selection::Skip
+				} else {
+					Selection::Push
+				}
+			}
+
+			fn on_exhausted(&self, _usage: &BlockUsage, _candidate: &Candidate) -> Selection {
+				Selection::Skip
+			}
+		}
+
+		// given
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let txpool = BasicPool::new_full(
+			Default::default(),
+			None,
+			spawner.clone(),
+			client.clone(),
+		);
+
+		futures::executor::block_on(
+			txpool.submit_at(&BlockId::number(0), SOURCE, vec![extrinsic(0), extrinsic(1), extrinsic(2)])
+		).unwrap();
+
+		futures::executor::block_on(
+			txpool.maintain(chain_event(
+				client.header(&BlockId::Number(0u64))
+					.expect("header get error")
+					.expect("there should be header")
+			))
+		);
+
+		let mut proposer_factory = ProposerFactory::new(
+			spawner.clone(),
+			client.clone(),
+			txpool.clone(),
+			None,
+		);
+		proposer_factory.set_selection_strategy(SkipFirst(Mutex::new(true)));
+
+		let proposer = proposer_factory.init_with_now(
+			&client.header(&BlockId::number(0)).unwrap().unwrap(),
+			Box::new(move || time::Instant::now()),
+		);
+
+		// when
+		let deadline = time::Duration::from_secs(9);
+		let block = futures::executor::block_on(
+			proposer.propose(Default::default(), Default::default(), deadline, RecordProof::No)
+		).map(|r| r.block).unwrap();
+
+		// then
+		// the transactions depending on the skipped one can't be included, but stay in the pool
+		assert_eq!(block.extrinsics().len(), 0);
+		assert_eq!(txpool.ready().count(), 3);
+	}
 }
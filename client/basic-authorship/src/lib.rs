@@ -70,5 +70,6 @@
 //!
 
 mod basic_authorship;
+pub mod selection;
 
-pub use crate::basic_authorship::{ProposerFactory, Proposer};
+pub use crate::basic_authorship::{ProposerFactory, Proposer, DEFAULT_SOFT_DEADLINE_PERCENT};
@@ -0,0 +1,180 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Strategies of selecting ready transactions to be included in a proposed block.
+
+use sp_runtime::transaction_validity::TransactionPriority;
+
+/// Usage of the block being built, as seen by a [`SelectionStrategy`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BlockUsage {
+	/// Number of pool transactions pushed to the block.
+	pub included: usize,
+	/// Number of pool transactions that were not pushed to the block, for any reason.
+	pub skipped: usize,
+	/// Number of pool transactions the runtime rejected because they exhausted block resources.
+	pub exhausted: usize,
+	/// Encoded size of the smallest transaction that exhausted block resources so far.
+	pub smallest_exhausted: Option<usize>,
+	/// Encoded size of all extrinsics in the block, including inherents.
+	pub size: usize,
+	/// Estimated size of the storage proof recorded so far, if proof recording is enabled.
+	pub proof_size: Option<usize>,
+}
+
+/// A ready transaction the proposer is about to push to the block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+	/// Encoded size of the transaction.
+	pub size: usize,
+	/// Priority of the transaction in the pool.
+	pub priority: TransactionPriority,
+}
+
+/// Decision of a [`SelectionStrategy`] about the next transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+	/// Try to push the transaction to the block.
+	Push,
+	/// Leave the transaction in the pool and continue with the next one.
+	Skip,
+	/// Stop pushing transactions and propose the block.
+	Stop,
+}
+
+/// Strategy of selecting ready transactions to be included in a proposed block.
+///
+/// The proposer iterates over the ready transactions in priority order and consults
+/// the strategy before pushing every one of them and after the runtime reported that
+/// a transaction exhausts the block resources.
+pub trait SelectionStrategy: Send + Sync {
+	/// Decides what to do with the next ready transaction.
+	fn select(&self, usage: &BlockUsage, candidate: &Candidate) -> Selection;
+
+	/// Decides whether to continue after `candidate` exhausted the block resources.
+	///
+	/// `usage` already accounts for the exhausting transaction. Returning
+	/// [`Selection::Push`] is treated the same as [`Selection::Skip`].
+	fn on_exhausted(&self, usage: &BlockUsage, candidate: &Candidate) -> Selection;
+}
+
+/// Pushes transactions in priority order and stops after a few of them exhausted the block.
+#[derive(Debug, Clone)]
+pub struct PriorityOrder {
+	/// Number of transactions that may exhaust the block resources before the proposer gives up.
+	pub max_exhausted: usize,
+}
+
+impl Default for PriorityOrder {
+	fn default() -> Self {
+		PriorityOrder {
+			max_exhausted: 8,
+		}
+	}
+}
+
+impl SelectionStrategy for PriorityOrder {
+	fn select(&self, _usage: &BlockUsage, _candidate: &Candidate) -> Selection {
+		Selection::Push
+	}
+
+	fn on_exhausted(&self, usage: &BlockUsage, _candidate: &Candidate) -> Selection {
+		if usage.exhausted <= self.max_exhausted {
+			Selection::Skip
+		} else {
+			Selection::Stop
+		}
+	}
+}
+
+/// Packs the block greedily, skipping transactions that are not expected to fit.
+///
+/// Once a transaction exhausted the block resources, only transactions smaller than
+/// the smallest exhausting one are attempted, so that a large transaction at the front
+/// of the queue doesn't leave the block under-filled.
+#[derive(Debug, Clone)]
+pub struct GreedyPacking {
+	/// Maximal encoded size of the block.
+	pub max_block_size: Option<usize>,
+	/// Number of transactions that may exhaust the block resources before the proposer gives up.
+	pub max_exhausted: usize,
+}
+
+impl Default for GreedyPacking {
+	fn default() -> Self {
+		GreedyPacking {
+			max_block_size: None,
+			max_exhausted: 64,
+		}
+	}
+}
+
+impl SelectionStrategy for GreedyPacking {
+	fn select(&self, usage: &BlockUsage, candidate: &Candidate) -> Selection {
+		if self.max_block_size.map_or(false, |max| usage.size + candidate.size > max) {
+			return Selection::Skip;
+		}
+
+		if usage.smallest_exhausted.map_or(false, |smallest| candidate.size >= smallest) {
+			return Selection::Skip;
+		}
+
+		Selection::Push
+	}
+
+	fn on_exhausted(&self, usage: &BlockUsage, _candidate: &Candidate) -> Selection {
+		if usage.exhausted <= self.max_exhausted {
+			Selection::Skip
+		} else {
+			Selection::Stop
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn candidate(size: usize) -> Candidate {
+		Candidate { size, priority: 0 }
+	}
+
+	#[test]
+	fn priority_order_stops_after_max_exhausted() {
+		let strategy = PriorityOrder { max_exhausted: 2 };
+		let mut usage = BlockUsage::default();
+
+		usage.exhausted = 2;
+		assert_eq!(strategy.on_exhausted(&usage, &candidate(10)), Selection::Skip);
+		usage.exhausted = 3;
+		assert_eq!(strategy.on_exhausted(&usage, &candidate(10)), Selection::Stop);
+	}
+
+	#[test]
+	fn greedy_packing_skips_transactions_that_do_not_fit() {
+		let strategy = GreedyPacking { max_block_size: Some(100), max_exhausted: 8 };
+		let mut usage = BlockUsage { size: 80, ..Default::default() };
+
+		assert_eq!(strategy.select(&usage, &candidate(30)), Selection::Skip);
+		assert_eq!(strategy.select(&usage, &candidate(20)), Selection::Push);
+
+		usage.smallest_exhausted = Some(15);
+		assert_eq!(strategy.select(&usage, &candidate(15)), Selection::Skip);
+		assert_eq!(strategy.select(&usage, &candidate(14)), Selection::Push);
+	}
+}
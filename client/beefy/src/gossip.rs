@@ -0,0 +1,322 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Gossip of BEEFY votes.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use log::{debug, trace};
+use parking_lot::RwLock;
+use parity_scale_codec::{Decode, Encode};
+use sc_network::PeerId;
+use sc_network_gossip::{MessageIntent, ValidationResult, Validator, ValidatorContext};
+use sp_application_crypto::RuntimeAppPublic;
+use sp_beefy::{AuthorityId, AuthoritySignature, Commitment, MmrRootHash, ValidatorSet};
+use sp_runtime::traits::{
+	AtLeast32BitUnsigned, Block as BlockT, Hash, Header as HeaderT, NumberFor, Saturating,
+};
+
+use crate::LOG_TARGET;
+
+/// Number of rounds whose votes are kept and propagated.
+const MAX_LIVE_GOSSIP_ROUNDS: usize = 3;
+
+/// Number of rounds, of `min_block_delta` blocks each, past the most recent live round whose
+/// votes are already accepted.
+const MAX_FUTURE_GOSSIP_ROUNDS: u32 = 3;
+
+/// Gossip engine messages topic.
+pub(crate) fn topic<B: BlockT>() -> B::Hash {
+	<<B::Header as HeaderT>::Hashing as Hash>::hash(b"beefy")
+}
+
+/// The vote of a validator on a commitment.
+#[derive(Clone, Debug, Encode, Decode)]
+pub(crate) struct VoteMessage<Number> {
+	/// The commitment voted on.
+	pub commitment: Commitment<Number, MmrRootHash>,
+	/// Identity of the voter.
+	pub id: AuthorityId,
+	/// Signature of the voter on the SCALE-encoded commitment.
+	pub signature: AuthoritySignature,
+}
+
+impl<Number: Encode> VoteMessage<Number> {
+	/// Whether the signature matches the commitment and the voter.
+	pub(crate) fn verify(&self) -> bool {
+		self.id.verify(&self.commitment.encode(), &self.signature)
+	}
+}
+
+/// The votes known to the gossip validator.
+struct KnownVotes<N> {
+	/// The rounds noted as live, most recent last.
+	live_rounds: VecDeque<N>,
+	/// The voters whose vote was kept, by round. Only a single vote per voter is kept in each
+	/// round.
+	voters: BTreeMap<N, Vec<AuthorityId>>,
+	/// The validator sets whose votes are accepted, i.e. the current and the next one.
+	validator_sets: Vec<ValidatorSet<AuthorityId>>,
+}
+
+impl<N: AtLeast32BitUnsigned + Copy> KnownVotes<N> {
+	fn is_expired(&self, round: N) -> bool {
+		// Votes of rounds we did not note yet are still accepted, since other validators might
+		// see a block finalized before we do.
+		self.live_rounds.front().map_or(false, |oldest| round < *oldest)
+	}
+
+	fn is_too_far_ahead(&self, round: N, min_block_delta: N) -> bool {
+		// Nothing is accepted until the first round is noted, the validator sets are not known
+		// before that either.
+		self.live_rounds.back().map_or(true, |best| {
+			round > best.saturating_add(min_block_delta.saturating_mul(MAX_FUTURE_GOSSIP_ROUNDS.into()))
+		})
+	}
+
+	fn is_known(&self, round: N, id: &AuthorityId) -> bool {
+		self.voters.get(&round).map_or(false, |voters| voters.contains(id))
+	}
+
+	fn is_voter(&self, vote: &VoteMessage<N>) -> bool {
+		self.validator_sets.iter().any(|validator_set| {
+			validator_set.id == vote.commitment.validator_set_id &&
+				validator_set.validators.contains(&vote.id)
+		})
+	}
+}
+
+/// BEEFY gossip validator.
+///
+/// Validates votes and only keeps the ones of the most recent rounds, noted by the worker as it
+/// starts new rounds. Votes of rounds older than the live ones are considered expired, votes of
+/// rounds too far ahead of them are discarded, and so are votes from signers that are neither in
+/// the current nor in the next validator set.
+pub(crate) struct GossipValidator<B: BlockT> {
+	topic: B::Hash,
+	min_block_delta: NumberFor<B>,
+	known_votes: RwLock<KnownVotes<NumberFor<B>>>,
+}
+
+impl<B: BlockT> GossipValidator<B> {
+	pub(crate) fn new(min_block_delta: NumberFor<B>) -> Self {
+		GossipValidator {
+			topic: topic::<B>(),
+			min_block_delta,
+			known_votes: RwLock::new(KnownVotes {
+				live_rounds: VecDeque::new(),
+				voters: BTreeMap::new(),
+				validator_sets: Vec::new(),
+			}),
+		}
+	}
+
+	/// Note a voting round as live, possibly expiring the oldest live round.
+	pub(crate) fn note_round(&self, round: NumberFor<B>) {
+		let mut known_votes = self.known_votes.write();
+		if known_votes.live_rounds.back().map_or(false, |best| round <= *best) {
+			return;
+		}
+
+		known_votes.live_rounds.push_back(round);
+		while known_votes.live_rounds.len() > MAX_LIVE_GOSSIP_ROUNDS {
+			known_votes.live_rounds.pop_front();
+		}
+
+		let oldest = *known_votes.live_rounds.front().expect("a round was just pushed; qed");
+		known_votes.voters = known_votes.voters.split_off(&oldest);
+	}
+
+	/// Note the current and the next validator sets, only their votes are accepted.
+	pub(crate) fn note_validator_sets(
+		&self,
+		current: ValidatorSet<AuthorityId>,
+		next: ValidatorSet<AuthorityId>,
+	) {
+		let mut validator_sets = vec![current];
+		if next.id != validator_sets[0].id {
+			validator_sets.push(next);
+		}
+
+		self.known_votes.write().validator_sets = validator_sets;
+	}
+}
+
+impl<B: BlockT> Validator<B> for GossipValidator<B> {
+	fn validate(
+		&self,
+		_context: &mut dyn ValidatorContext<B>,
+		sender: &PeerId,
+		mut data: &[u8],
+	) -> ValidationResult<B::Hash> {
+		let vote = match VoteMessage::<NumberFor<B>>::decode(&mut data) {
+			Ok(vote) => vote,
+			Err(_) => return ValidationResult::Discard,
+		};
+		let round = vote.commitment.block_number;
+
+		{
+			let known_votes = self.known_votes.read();
+			if known_votes.is_expired(round) ||
+				known_votes.is_too_far_ahead(round, self.min_block_delta) ||
+				known_votes.is_known(round, &vote.id)
+			{
+				return ValidationResult::Discard;
+			}
+
+			if !known_votes.is_voter(&vote) {
+				debug!(
+					target: LOG_TARGET,
+					"🥩 Vote from {:?} (peer {}) who is not in validator set #{}",
+					vote.id,
+					sender,
+					vote.commitment.validator_set_id,
+				);
+				return ValidationResult::Discard;
+			}
+		}
+
+		if !vote.verify() {
+			debug!(target: LOG_TARGET, "🥩 Bad signature on vote from {:?} (peer {})", vote.id, sender);
+			return ValidationResult::Discard;
+		}
+
+		{
+			let mut known_votes = self.known_votes.write();
+			// the round might have expired, or the vote been kept, while verifying the signature
+			if known_votes.is_expired(round) || known_votes.is_known(round, &vote.id) {
+				return ValidationResult::Discard;
+			}
+
+			known_votes.voters.entry(round).or_default().push(vote.id.clone());
+		}
+
+		trace!(target: LOG_TARGET, "🥩 Valid vote from {:?} for round {:?}", vote.id, round);
+		ValidationResult::ProcessAndKeep(self.topic)
+	}
+
+	fn message_expired<'a>(&'a self) -> Box<dyn FnMut(B::Hash, &[u8]) -> bool + 'a> {
+		let known_votes = self.known_votes.read();
+		Box::new(move |_topic, mut data| {
+			VoteMessage::<NumberFor<B>>::decode(&mut data)
+				.map_or(true, |vote| known_votes.is_expired(vote.commitment.block_number))
+		})
+	}
+
+	fn message_allowed<'a>(&'a self) -> Box<dyn FnMut(&PeerId, MessageIntent, &B::Hash, &[u8]) -> bool + 'a> {
+		let known_votes = self.known_votes.read();
+		Box::new(move |_who, _intent, _topic, mut data| {
+			VoteMessage::<NumberFor<B>>::decode(&mut data)
+				.map_or(false, |vote| !known_votes.is_expired(vote.commitment.block_number))
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{ecdsa, Pair};
+	use substrate_test_runtime_client::runtime::Block;
+
+	struct NoopContext;
+
+	impl ValidatorContext<Block> for NoopContext {
+		fn broadcast_topic(&mut self, _: <Block as BlockT>::Hash, _: bool) {}
+		fn broadcast_message(&mut self, _: <Block as BlockT>::Hash, _: Vec<u8>, _: bool) {}
+		fn send_message(&mut self, _: &PeerId, _: Vec<u8>) {}
+		fn send_topic(&mut self, _: &PeerId, _: <Block as BlockT>::Hash, _: bool) {}
+	}
+
+	fn pair(seed: u8) -> ecdsa::Pair {
+		ecdsa::Pair::from_seed(&[seed; 32])
+	}
+
+	fn vote_of(seed: u8, block_number: u64, validator_set_id: u64) -> VoteMessage<u64> {
+		let pair = pair(seed);
+		let commitment = Commitment { payload: MmrRootHash::repeat_byte(1), block_number, validator_set_id };
+		let signature = pair.sign(&commitment.encode()).into();
+		VoteMessage { commitment, id: pair.public().into(), signature }
+	}
+
+	fn vote(block_number: u64) -> VoteMessage<u64> {
+		vote_of(1, block_number, 0)
+	}
+
+	fn validator() -> GossipValidator<Block> {
+		let validator = GossipValidator::<Block>::new(1);
+		validator.note_validator_sets(
+			ValidatorSet { validators: vec![pair(1).public().into()], id: 0 },
+			ValidatorSet { validators: vec![pair(2).public().into()], id: 1 },
+		);
+		validator
+	}
+
+	#[test]
+	fn validates_and_expires_votes() {
+		let validator = validator();
+		let sender = PeerId::random();
+		let validate = |data: &[u8]| validator.validate(&mut NoopContext, &sender, data);
+
+		// nothing is accepted before the first round is noted
+		assert!(matches!(validate(&vote(1).encode()), ValidationResult::Discard));
+
+		validator.note_round(1);
+
+		let mut bad_signature = vote(1);
+		bad_signature.commitment.block_number = 2;
+		assert!(matches!(validate(&bad_signature.encode()), ValidationResult::Discard));
+		assert!(matches!(validate(b"garbage"), ValidationResult::Discard));
+
+		assert!(matches!(validate(&vote(1).encode()), ValidationResult::ProcessAndKeep(_)));
+		assert!(matches!(validate(&vote(1).encode()), ValidationResult::Discard), "duplicate vote");
+
+		let mut other_payload = vote(1);
+		other_payload.commitment.payload = MmrRootHash::repeat_byte(2);
+		other_payload.signature = pair(1).sign(&other_payload.commitment.encode()).into();
+		assert!(matches!(validate(&other_payload.encode()), ValidationResult::Discard), "second vote");
+
+		for round in 2..=4 {
+			validator.note_round(round);
+		}
+		assert!(matches!(validate(&vote(1).encode()), ValidationResult::Discard), "expired vote");
+		assert!(validator.message_expired()(topic::<Block>(), &vote(1).encode()));
+		assert!(!validator.message_expired()(topic::<Block>(), &vote(5).encode()));
+		assert!(matches!(validate(&vote(5).encode()), ValidationResult::ProcessAndKeep(_)));
+
+		// votes too far ahead of the most recent live round are discarded
+		assert!(matches!(validate(&vote(7).encode()), ValidationResult::ProcessAndKeep(_)));
+		assert!(matches!(validate(&vote(8).encode()), ValidationResult::Discard), "future vote");
+
+		// the voters of expired rounds are forgotten
+		validator.note_round(5);
+		assert_eq!(validator.known_votes.read().voters.keys().cloned().collect::<Vec<_>>(), vec![5, 7]);
+	}
+
+	#[test]
+	fn only_accepts_votes_of_current_and_next_validator_sets() {
+		let validator = validator();
+		let sender = PeerId::random();
+		let validate = |data: &[u8]| validator.validate(&mut NoopContext, &sender, data);
+		validator.note_round(1);
+
+		assert!(matches!(validate(&vote_of(3, 1, 0).encode()), ValidationResult::Discard), "unknown signer");
+		assert!(matches!(validate(&vote_of(2, 1, 0).encode()), ValidationResult::Discard), "wrong set");
+		assert!(matches!(validate(&vote_of(2, 1, 1).encode()), ValidationResult::ProcessAndKeep(_)));
+		assert!(matches!(validate(&vote_of(1, 1, 0).encode()), ValidationResult::ProcessAndKeep(_)));
+	}
+}
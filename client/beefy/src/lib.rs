@@ -0,0 +1,134 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! BEEFY, a secondary finality gadget running on top of GRANDPA.
+//!
+//! GRANDPA justifications are expensive to verify outside of Substrate. With BEEFY, validators
+//! additionally sign a compact [`sp_beefy::Commitment`] to the MMR root of a subset of the
+//! blocks finalized by GRANDPA, using the ECDSA keys of the [`sp_beefy::KEY_TYPE`] in their
+//! keystore. The votes are gossiped to the other validators, and as soon as more than two thirds
+//! of the validator set signed a commitment, the [`SignedCommitment`] is sent to the
+//! [`BeefySignedCommitmentStream`] subscribers, e.g. the RPC used by bridge relayers.
+//!
+//! Validators vote on the finalized blocks whose number is a multiple of the configured
+//! `min_block_delta`, so that validators who learn about finality at different heights still
+//! vote on the same blocks. Blocks changing the validator set are always voted on, so that the
+//! handover to every new set can be followed from the signed commitments.
+
+#![warn(missing_docs)]
+
+use std::sync::Arc;
+
+use log::warn;
+use prometheus_endpoint::Registry;
+use sc_client_api::BlockchainEvents;
+use sc_network_gossip::{GossipEngine, Network as GossipNetwork};
+use sp_api::ProvideRuntimeApi;
+use sp_beefy::{AuthoritySignature, BeefyApi, MmrRootHash, BEEFY_ENGINE_ID};
+use sp_blockchain::HeaderBackend;
+use sp_keystore::SyncCryptoStorePtr;
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+
+mod gossip;
+mod metrics;
+mod notification;
+mod round;
+mod worker;
+
+pub use notification::{BeefySignedCommitmentSender, BeefySignedCommitmentStream};
+
+/// Log target of the BEEFY gadget.
+pub(crate) const LOG_TARGET: &str = "beefy";
+
+/// Name of the notifications protocol used by BEEFY.
+pub const BEEFY_PROTOCOL_NAME: &str = "/paritytech/beefy/1";
+
+/// A commitment to the MMR root of a block, signed by more than two thirds of the validators.
+pub type SignedCommitment<Block> =
+	sp_beefy::SignedCommitment<NumberFor<Block>, MmrRootHash, AuthoritySignature>;
+
+/// A client with all the traits required by the BEEFY gadget.
+pub trait Client<B: BlockT>:
+	BlockchainEvents<B> + HeaderBackend<B> + ProvideRuntimeApi<B> + Send + Sync
+{}
+
+impl<B, T> Client<B> for T
+where
+	B: BlockT,
+	T: BlockchainEvents<B> + HeaderBackend<B> + ProvideRuntimeApi<B> + Send + Sync,
+{}
+
+/// Parameters of the BEEFY gadget.
+pub struct BeefyParams<B: BlockT, C, N> {
+	/// The client to follow finality and read the validator sets with.
+	pub client: Arc<C>,
+	/// The keystore holding the BEEFY key of this node, `None` if not a validator.
+	pub key_store: Option<SyncCryptoStorePtr>,
+	/// Network to gossip the votes on.
+	pub network: N,
+	/// Sending half of the channel the signed commitments are sent to.
+	pub signed_commitment_sender: BeefySignedCommitmentSender<B>,
+	/// Minimal distance, in blocks, between two signed commitments.
+	pub min_block_delta: u32,
+	/// Registry of the Prometheus metrics.
+	pub prometheus_registry: Option<Registry>,
+}
+
+/// Run the BEEFY gadget until the finality notification stream ends.
+pub async fn start_beefy_gadget<B, C, N>(params: BeefyParams<B, C, N>)
+where
+	B: BlockT,
+	C: Client<B> + 'static,
+	<C as ProvideRuntimeApi<B>>::Api: BeefyApi<B, Error = sp_blockchain::Error>,
+	N: GossipNetwork<B> + Clone + Send + 'static,
+{
+	let BeefyParams {
+		client,
+		key_store,
+		network,
+		signed_commitment_sender,
+		min_block_delta,
+		prometheus_registry,
+	} = params;
+
+	let gossip_validator = Arc::new(gossip::GossipValidator::new(min_block_delta.max(1).into()));
+	let gossip_engine = GossipEngine::new(
+		network,
+		BEEFY_ENGINE_ID,
+		BEEFY_PROTOCOL_NAME,
+		gossip_validator.clone(),
+	);
+
+	let metrics = prometheus_registry.as_ref().and_then(|registry| {
+		metrics::Metrics::register(registry)
+			.map_err(|e| warn!(target: LOG_TARGET, "🥩 Failed to register metrics: {:?}", e))
+			.ok()
+	});
+
+	let worker = worker::BeefyWorker::new(worker::WorkerParams {
+		client,
+		key_store,
+		signed_commitment_sender,
+		gossip_engine,
+		gossip_validator,
+		min_block_delta,
+		metrics,
+	});
+
+	worker.run().await
+}
@@ -0,0 +1,83 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use parking_lot::Mutex;
+
+use sp_runtime::traits::Block as BlockT;
+use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
+
+use crate::SignedCommitment;
+
+// Stream of signed commitments returned when subscribing.
+type SignedCommitmentStream<Block> = TracingUnboundedReceiver<SignedCommitment<Block>>;
+
+// Sending endpoint for notifying about signed commitments.
+type SignedCommitmentSender<Block> = TracingUnboundedSender<SignedCommitment<Block>>;
+
+// Collection of channel sending endpoints shared with the receiver side so they can register
+// themselves.
+type SharedSignedCommitmentSenders<Block> = Arc<Mutex<Vec<SignedCommitmentSender<Block>>>>;
+
+/// The sending half of the BEEFY signed commitment channel(s).
+///
+/// Used to send notifications about commitments signed by more than two thirds of the
+/// validator set.
+#[derive(Clone)]
+pub struct BeefySignedCommitmentSender<Block: BlockT> {
+	subscribers: SharedSignedCommitmentSenders<Block>,
+}
+
+impl<Block: BlockT> BeefySignedCommitmentSender<Block> {
+	/// Send out a notification to all subscribers that a new signed commitment is available.
+	pub fn notify(&self, signed_commitment: SignedCommitment<Block>) {
+		let mut subscribers = self.subscribers.lock();
+
+		// do an initial prune on closed subscriptions
+		subscribers.retain(|n| !n.is_closed());
+
+		subscribers.retain(|n| n.unbounded_send(signed_commitment.clone()).is_ok());
+	}
+}
+
+/// The receiving half of the BEEFY signed commitment channel.
+///
+/// The `BeefySignedCommitmentStream` entity stores the shared sending endpoints so it can be
+/// used to add more subscriptions.
+#[derive(Clone)]
+pub struct BeefySignedCommitmentStream<Block: BlockT> {
+	subscribers: SharedSignedCommitmentSenders<Block>,
+}
+
+impl<Block: BlockT> BeefySignedCommitmentStream<Block> {
+	/// Creates a new pair of receiver and sender of signed commitment notifications.
+	pub fn channel() -> (BeefySignedCommitmentSender<Block>, Self) {
+		let subscribers = Arc::new(Mutex::new(vec![]));
+		let receiver = BeefySignedCommitmentStream { subscribers: subscribers.clone() };
+		let sender = BeefySignedCommitmentSender { subscribers };
+		(sender, receiver)
+	}
+
+	/// Subscribe to a channel through which signed commitments are sent as soon as they
+	/// collect enough signatures.
+	pub fn subscribe(&self) -> SignedCommitmentStream<Block> {
+		let (sender, receiver) = tracing_unbounded("mpsc_signed_commitments_notification_stream");
+		self.subscribers.lock().push(sender);
+		receiver
+	}
+}
@@ -0,0 +1,139 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Collection of the votes of the validators on BEEFY commitments.
+
+use std::collections::BTreeMap;
+
+use sp_beefy::{AuthorityId, AuthoritySignature, ValidatorSet, ValidatorSetId};
+
+/// The votes of a single round.
+#[derive(Default)]
+struct RoundTracker {
+	votes: Vec<(AuthorityId, AuthoritySignature)>,
+}
+
+impl RoundTracker {
+	fn add_vote(&mut self, vote: (AuthorityId, AuthoritySignature)) -> bool {
+		if self.votes.iter().any(|(id, _)| *id == vote.0) {
+			return false;
+		}
+
+		self.votes.push(vote);
+		true
+	}
+
+	fn is_done(&self, threshold: usize) -> bool {
+		self.votes.len() >= threshold
+	}
+}
+
+/// The ongoing rounds of a validator set.
+///
+/// A round is identified by the payload and the block number of a commitment, validators that
+/// voted on different payloads for the same block are counted in different rounds.
+pub(crate) struct Rounds<Payload, Number> {
+	rounds: BTreeMap<(Payload, Number), RoundTracker>,
+	validator_set: ValidatorSet<AuthorityId>,
+}
+
+impl<Payload, Number> Rounds<Payload, Number>
+where
+	Payload: Ord,
+	Number: Ord + Copy,
+{
+	pub(crate) fn new(validator_set: ValidatorSet<AuthorityId>) -> Self {
+		Rounds {
+			rounds: BTreeMap::new(),
+			validator_set,
+		}
+	}
+
+	pub(crate) fn validator_set_id(&self) -> ValidatorSetId {
+		self.validator_set.id
+	}
+
+	pub(crate) fn validators(&self) -> &[AuthorityId] {
+		&self.validator_set.validators
+	}
+
+	/// Note the vote of a validator of the set, returns `false` if the vote is not from a
+	/// validator of the set or is a duplicate.
+	pub(crate) fn add_vote(&mut self, round: (Payload, Number), vote: (AuthorityId, AuthoritySignature)) -> bool {
+		if !self.validator_set.validators.contains(&vote.0) {
+			return false;
+		}
+
+		self.rounds.entry(round).or_default().add_vote(vote)
+	}
+
+	/// Whether the round collected enough votes to conclude.
+	pub(crate) fn is_done(&self, round: &(Payload, Number)) -> bool {
+		let threshold = self.validator_set.threshold();
+		self.rounds.get(round).map_or(false, |tracker| tracker.is_done(threshold))
+	}
+
+	/// Stop tracking a round, returning the signatures collected in the order of the set.
+	pub(crate) fn drop(&mut self, round: &(Payload, Number)) -> Option<Vec<Option<AuthoritySignature>>> {
+		let tracker = self.rounds.remove(round)?;
+		Some(self.validator_set.validators.iter().map(|id| {
+			tracker.votes.iter().find(|(voter, _)| voter == id).map(|(_, signature)| signature.clone())
+		}).collect())
+	}
+
+	/// Stop tracking the rounds of blocks up to and including `number`.
+	pub(crate) fn prune(&mut self, number: Number) {
+		self.rounds.retain(|(_, round), _| *round > number);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{ecdsa, Pair};
+
+	fn vote(seed: u8) -> (AuthorityId, AuthoritySignature) {
+		let pair = ecdsa::Pair::from_seed(&[seed; 32]);
+		(pair.public().into(), pair.sign(b"commitment").into())
+	}
+
+	#[test]
+	fn round_concludes_with_two_thirds_of_the_set() {
+		let validators = (1..=4).map(|seed| vote(seed).0).collect();
+		let mut rounds = Rounds::<u8, u64>::new(ValidatorSet { validators, id: 1 });
+		let round = (0, 5);
+
+		assert!(rounds.add_vote(round, vote(3)));
+		assert!(!rounds.add_vote(round, vote(3)), "duplicate votes are ignored");
+		assert!(!rounds.add_vote(round, vote(5)), "votes from outside of the set are ignored");
+		assert!(rounds.add_vote((1, 5), vote(1)));
+		assert!(rounds.add_vote(round, vote(1)));
+		assert!(!rounds.is_done(&round));
+
+		assert!(rounds.add_vote(round, vote(2)));
+		assert!(rounds.is_done(&round));
+		assert!(!rounds.is_done(&(1, 5)));
+
+		let signatures = rounds.drop(&round).unwrap();
+		assert_eq!(signatures, vec![Some(vote(1).1), Some(vote(2).1), Some(vote(3).1), None]);
+		assert!(rounds.drop(&round).is_none());
+
+		rounds.prune(5);
+		assert!(rounds.drop(&(1, 5)).is_none());
+	}
+}
@@ -0,0 +1,388 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The BEEFY worker, voting on finalized blocks and collecting the votes of the other validators.
+
+use std::{convert::TryInto, sync::Arc};
+
+use futures::{future, FutureExt, StreamExt};
+use log::{debug, error, info, trace, warn};
+use parking_lot::Mutex;
+use parity_scale_codec::{Decode, Encode};
+use sc_client_api::{FinalityNotification, FinalityNotifications};
+use sc_network_gossip::GossipEngine;
+use sp_api::ProvideRuntimeApi;
+use sp_beefy::{
+	AuthorityId, AuthoritySignature, BeefyApi, Commitment, ConsensusLog, MmrRootHash, ValidatorSet,
+	BEEFY_ENGINE_ID, KEY_TYPE,
+};
+use sp_core::crypto::Public;
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::generic::{BlockId, OpaqueDigestItemId};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor, SaturatedConversion, Zero};
+
+use crate::{
+	gossip::{topic, GossipValidator, VoteMessage},
+	metrics::Metrics,
+	notification::BeefySignedCommitmentSender,
+	round::Rounds,
+	Client, SignedCommitment, LOG_TARGET,
+};
+
+pub(crate) struct WorkerParams<B: BlockT, C> {
+	pub client: Arc<C>,
+	pub key_store: Option<SyncCryptoStorePtr>,
+	pub signed_commitment_sender: BeefySignedCommitmentSender<B>,
+	pub gossip_engine: GossipEngine<B>,
+	pub gossip_validator: Arc<GossipValidator<B>>,
+	pub min_block_delta: u32,
+	pub metrics: Option<Metrics>,
+}
+
+/// A BEEFY worker plays the BEEFY protocol.
+pub(crate) struct BeefyWorker<B: BlockT, C> {
+	client: Arc<C>,
+	key_store: Option<SyncCryptoStorePtr>,
+	signed_commitment_sender: BeefySignedCommitmentSender<B>,
+	gossip_engine: Arc<Mutex<GossipEngine<B>>>,
+	gossip_validator: Arc<GossipValidator<B>>,
+	min_block_delta: NumberFor<B>,
+	metrics: Option<Metrics>,
+	/// Rounds of the current validator set, `None` until the first vote target is finalized.
+	rounds: Option<Rounds<MmrRootHash, NumberFor<B>>>,
+	finality_notifications: FinalityNotifications<B>,
+	/// Block the most recent round was started for.
+	best_round: NumberFor<B>,
+	/// Best block a signed commitment was produced for.
+	best_beefy_block: Option<NumberFor<B>>,
+}
+
+impl<B, C> BeefyWorker<B, C>
+where
+	B: BlockT,
+	C: Client<B>,
+	<C as ProvideRuntimeApi<B>>::Api: BeefyApi<B, Error = sp_blockchain::Error>,
+{
+	pub(crate) fn new(params: WorkerParams<B, C>) -> Self {
+		let WorkerParams {
+			client,
+			key_store,
+			signed_commitment_sender,
+			gossip_engine,
+			gossip_validator,
+			min_block_delta,
+			metrics,
+		} = params;
+
+		BeefyWorker {
+			finality_notifications: client.finality_notification_stream(),
+			client,
+			key_store,
+			signed_commitment_sender,
+			gossip_engine: Arc::new(Mutex::new(gossip_engine)),
+			gossip_validator,
+			min_block_delta: min_block_delta.max(1).into(),
+			metrics,
+			rounds: None,
+			best_round: Zero::zero(),
+			best_beefy_block: None,
+		}
+	}
+
+	/// Return the BEEFY authority id of this node in the given set, if any.
+	fn local_id(&self, validator_set: &ValidatorSet<AuthorityId>) -> Option<AuthorityId> {
+		let key_store = self.key_store.as_ref()?;
+		let keys = SyncCryptoStore::ecdsa_public_keys(&**key_store, KEY_TYPE);
+		validator_set.validators.iter()
+			.find(|id| keys.iter().any(|key| AuthorityId::from(key.clone()) == **id))
+			.cloned()
+	}
+
+	fn sign_commitment(
+		&self,
+		id: &AuthorityId,
+		commitment: &Commitment<NumberFor<B>, MmrRootHash>,
+	) -> Option<AuthoritySignature> {
+		let key_store = self.key_store.as_ref()?;
+		let signature = SyncCryptoStore::sign_with(
+			&**key_store,
+			KEY_TYPE,
+			&id.to_public_crypto_pair(),
+			&commitment.encode(),
+		).map_err(|e| warn!(target: LOG_TARGET, "🥩 Error signing commitment: {:?}", e)).ok()?;
+
+		signature.try_into()
+			.map_err(|_| warn!(target: LOG_TARGET, "🥩 Invalid signature of commitment"))
+			.ok()
+	}
+
+	/// Return the lowest block between the block of the last round (excluded) and the given
+	/// finalized block that changes the validator set, if any.
+	fn find_set_change(&self, finalized: &B::Header) -> Option<NumberFor<B>> {
+		// Before the first round, we don't know which validator set changes were signed already.
+		if self.best_round.is_zero() {
+			return None;
+		}
+
+		let mut set_change = None;
+		let mut header = finalized.clone();
+		while *header.number() > self.best_round {
+			if find_authorities_change::<B>(&header).is_some() {
+				set_change = Some(*header.number());
+			}
+
+			header = match self.client.header(BlockId::Hash(*header.parent_hash())) {
+				Ok(Some(header)) => header,
+				Ok(None) => {
+					debug!(target: LOG_TARGET, "🥩 Missing header of finalized block {:?}", header.parent_hash());
+					break;
+				},
+				Err(e) => {
+					debug!(target: LOG_TARGET, "🥩 Error reading header of block {:?}: {:?}", header.parent_hash(), e);
+					break;
+				},
+			};
+		}
+
+		set_change
+	}
+
+	fn handle_finality_notification(&mut self, notification: FinalityNotification<B>) {
+		trace!(target: LOG_TARGET, "🥩 Finality notification: {:?}", notification.header.number());
+
+		let set_change = self.find_set_change(&notification.header);
+		let target = match vote_target(
+			*notification.header.number(),
+			self.best_round,
+			self.min_block_delta,
+			set_change,
+		) {
+			Some(target) => target,
+			None => return,
+		};
+		let header = match self.client.header(BlockId::Number(target)) {
+			Ok(Some(header)) => header,
+			Ok(None) => {
+				debug!(target: LOG_TARGET, "🥩 Missing header of finalized block #{}", target);
+				return;
+			},
+			Err(e) => {
+				debug!(target: LOG_TARGET, "🥩 Error reading header of block #{}: {:?}", target, e);
+				return;
+			},
+		};
+
+		let at = BlockId::hash(header.hash());
+		let api = self.client.runtime_api();
+		let validator_sets = api.validator_set(&at)
+			.and_then(|validator_set| Ok((validator_set, api.next_validator_set(&at)?)));
+		let (validator_set, next_validator_set) = match validator_sets {
+			Ok(validator_sets) => validator_sets,
+			Err(e) => {
+				debug!(target: LOG_TARGET, "🥩 Error reading validator sets at #{}: {:?}", target, e);
+				return;
+			},
+		};
+		if validator_set.validators.is_empty() {
+			trace!(target: LOG_TARGET, "🥩 Empty validator set at #{}, not voting", target);
+			return;
+		}
+
+		if self.rounds.as_ref().map_or(true, |rounds| rounds.validator_set_id() != validator_set.id) {
+			info!(
+				target: LOG_TARGET,
+				"🥩 New validator set #{} with {} validators",
+				validator_set.id,
+				validator_set.validators.len(),
+			);
+			if let Some(metrics) = self.metrics.as_ref() {
+				metrics.beefy_validator_set_id.set(validator_set.id);
+			}
+			self.rounds = Some(Rounds::new(validator_set.clone()));
+		}
+
+		self.best_round = target;
+		self.gossip_validator.note_round(target);
+		self.gossip_validator.note_validator_sets(validator_set.clone(), next_validator_set);
+
+		let mmr_root = match pallet_mmr_primitives::find_mmr_root::<MmrRootHash, _>(header.digest()) {
+			Some(mmr_root) => mmr_root,
+			None => {
+				warn!(target: LOG_TARGET, "🥩 No MMR root digest found for block #{}", target);
+				return;
+			},
+		};
+		let commitment = Commitment {
+			payload: mmr_root,
+			block_number: target,
+			validator_set_id: validator_set.id,
+		};
+
+		let id = match self.local_id(&validator_set) {
+			Some(id) => id,
+			None => {
+				trace!(target: LOG_TARGET, "🥩 Not a validator of set #{}, not voting", validator_set.id);
+				return;
+			},
+		};
+		let signature = match self.sign_commitment(&id, &commitment) {
+			Some(signature) => signature,
+			None => return,
+		};
+
+		let message = VoteMessage { commitment, id, signature };
+		debug!(target: LOG_TARGET, "🥩 Sent vote for block #{}", target);
+		if let Some(metrics) = self.metrics.as_ref() {
+			metrics.beefy_votes_sent.inc();
+		}
+		self.gossip_engine.lock().gossip_message(topic::<B>(), message.encode(), false);
+		self.handle_vote(message);
+	}
+
+	fn handle_vote(&mut self, vote: VoteMessage<NumberFor<B>>) {
+		let VoteMessage { commitment, id, signature } = vote;
+		let number = commitment.block_number;
+		if self.best_beefy_block.map_or(false, |best| number <= best) {
+			return;
+		}
+
+		let rounds = match self.rounds.as_mut() {
+			Some(rounds) if rounds.validator_set_id() == commitment.validator_set_id => rounds,
+			_ => {
+				trace!(
+					target: LOG_TARGET,
+					"🥩 Ignoring vote of {:?} for unknown validator set #{}",
+					id,
+					commitment.validator_set_id,
+				);
+				return;
+			},
+		};
+
+		let round = (commitment.payload, number);
+		if !rounds.add_vote(round, (id, signature)) || !rounds.is_done(&round) {
+			return;
+		}
+
+		let signatures = match rounds.drop(&round) {
+			Some(signatures) => signatures,
+			None => return,
+		};
+		rounds.prune(number);
+
+		let signed_commitment = SignedCommitment::<B> { commitment, signatures };
+		info!(
+			target: LOG_TARGET,
+			"🥩 Round #{} concluded with {} of {} signatures",
+			number,
+			signed_commitment.no_of_signatures(),
+			rounds.validators().len(),
+		);
+
+		self.best_beefy_block = Some(number);
+		if let Some(metrics) = self.metrics.as_ref() {
+			metrics.beefy_best_block.set(number.saturated_into());
+		}
+		self.signed_commitment_sender.notify(signed_commitment);
+	}
+
+	pub(crate) async fn run(mut self) {
+		let mut votes = Box::pin(self.gossip_engine.lock().messages_for(topic::<B>())
+			.filter_map(|notification| async move {
+				VoteMessage::<NumberFor<B>>::decode(&mut &notification.message[..]).ok()
+			}));
+
+		loop {
+			let engine = self.gossip_engine.clone();
+			let gossip_engine = future::poll_fn(|cx| engine.lock().poll_unpin(cx));
+
+			futures::select! {
+				notification = self.finality_notifications.next().fuse() => {
+					match notification {
+						Some(notification) => self.handle_finality_notification(notification),
+						None => return,
+					}
+				},
+				vote = votes.next().fuse() => {
+					match vote {
+						Some(vote) => self.handle_vote(vote),
+						None => return,
+					}
+				},
+				_ = gossip_engine.fuse() => {
+					error!(target: LOG_TARGET, "🥩 Gossip engine has terminated.");
+					return;
+				}
+			}
+		}
+	}
+}
+
+/// Return the validator set announced in the digest of the given header, if any.
+fn find_authorities_change<B: BlockT>(header: &B::Header) -> Option<ValidatorSet<AuthorityId>> {
+	let id = OpaqueDigestItemId::Consensus(&BEEFY_ENGINE_ID);
+	header.digest().convert_first(|log| match log.try_to(id) {
+		Some(ConsensusLog::<AuthorityId>::AuthoritiesChange(validator_set)) => Some(validator_set),
+		_ => None,
+	})
+}
+
+/// Return the block to vote on after `best_grandpa` was finalized, given the block the last
+/// round was started for and the lowest block since then changing the validator set.
+///
+/// Validators vote on the multiples of `min_block_delta`, the most recent one is picked when
+/// several of them were finalized at once. Blocks changing the validator set are mandatory
+/// though, and picked before any other block, so that the handover to every new set is signed.
+fn vote_target<N>(best_grandpa: N, best_round: N, min_block_delta: N, set_change: Option<N>) -> Option<N>
+where
+	N: sp_runtime::traits::AtLeast32BitUnsigned + Copy,
+{
+	if let Some(set_change) = set_change.filter(|n| *n > best_round && *n <= best_grandpa) {
+		return Some(set_change);
+	}
+
+	let target = best_grandpa - best_grandpa % min_block_delta;
+	if target.is_zero() || target <= best_round {
+		None
+	} else {
+		Some(target)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::vote_target;
+
+	#[test]
+	fn vote_target_is_latest_multiple_of_delta() {
+		assert_eq!(vote_target(0u64, 0, 1, None), None);
+		assert_eq!(vote_target(1u64, 0, 1, None), Some(1));
+		assert_eq!(vote_target(1u64, 1, 1, None), None);
+		assert_eq!(vote_target(7u64, 0, 4, None), Some(4));
+		assert_eq!(vote_target(7u64, 4, 4, None), None);
+		assert_eq!(vote_target(13u64, 4, 4, None), Some(12));
+	}
+
+	#[test]
+	fn vote_target_is_mandatory_set_change() {
+		assert_eq!(vote_target(13u64, 4, 4, Some(6)), Some(6));
+		assert_eq!(vote_target(7u64, 4, 4, Some(6)), Some(6));
+		assert_eq!(vote_target(13u64, 6, 4, Some(6)), Some(12));
+		assert_eq!(vote_target(5u64, 4, 4, Some(6)), None);
+	}
+}
@@ -179,6 +179,13 @@ where
 		})
 	}
 
+	/// Returns the estimated size of the storage proof recorded so far.
+	///
+	/// Returns `None` when proof recording is disabled.
+	pub fn estimated_proof_size(&self) -> Option<usize> {
+		self.api.estimated_proof_size()
+	}
+
 	/// Consume the builder to build a valid `Block` containing all pushed extrinsics.
 	///
 	/// Returns the build `Block`, the changes to the storage and an optional `StorageProof`
@@ -0,0 +1,336 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Database maintenance CLI utilities
+
+use crate::error;
+use crate::params::{BlockNumberOrHash, DatabaseParams, SharedParams};
+use crate::CliConfiguration;
+use sc_client_db::inspect::{self, DbInspector, TrieNodeInfo};
+use sc_client_db::DatabaseType;
+use sc_client_db::migration::{self, MigrationConfig};
+use sc_informant::{MigrationInformant, OutputFormat};
+use sc_service::DatabaseConfig;
+use sp_core::hexdisplay::HexDisplay;
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+use std::fmt::Debug;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+/// Database utilities for the cli.
+#[derive(Debug, StructOpt)]
+pub enum DbSubcommand {
+	/// Run the pending database migrations without starting the node.
+	Migrate(MigrateCmd),
+
+	/// Print the number and size of the entries of every database column.
+	Stats(StatsCmd),
+
+	/// Print the nodes of the state trie of a block.
+	DumpTrie(DumpTrieCmd),
+
+	/// Find state nodes that are not reachable from any state kept in the database.
+	Orphans(OrphansCmd),
+
+	/// Check that the headers, bodies, justifications and states of the best chain line up.
+	Check(CheckCmd),
+}
+
+/// The `db migrate` command used to upgrade the database to the current version.
+#[derive(Debug, StructOpt)]
+pub struct MigrateCmd {
+	/// Only report what would be migrated, without writing to the database.
+	#[structopt(long = "dry-run")]
+	pub dry_run: bool,
+
+	/// Number of entries migrated in a single database transaction.
+	#[structopt(long = "batch-size", value_name = "COUNT", default_value = "10000")]
+	pub batch_size: usize,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl MigrateCmd {
+	/// Run the migrate command
+	pub fn run(&self, database_config: DatabaseConfig, output_format: OutputFormat) -> error::Result<()> {
+		let config = MigrationConfig {
+			dry_run: self.dry_run,
+			batch_size: self.batch_size,
+		};
+		let mut informant = MigrationInformant::new(output_format);
+		let summary = migration::migrate_database(
+			&database_config,
+			DatabaseType::Full,
+			&config,
+			&mut |progress| informant.display(progress),
+		)?;
+
+		if summary.from == summary.to {
+			println!("Database is up to date (version {}).", summary.to);
+		} else if self.dry_run {
+			println!(
+				"Migrating from version {} to {} would change {} of {} entries.",
+				summary.from,
+				summary.to,
+				summary.changes,
+				summary.entries,
+			);
+		} else {
+			println!(
+				"Migrated from version {} to {}, {} changes in {} entries.",
+				summary.from,
+				summary.to,
+				summary.changes,
+				summary.entries,
+			);
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for MigrateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// The `db stats` command used to print per-column statistics.
+#[derive(Debug, StructOpt)]
+pub struct StatsCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl StatsCmd {
+	/// Run the stats command
+	pub fn run<B: BlockT>(&self, database_config: DatabaseConfig) -> error::Result<()> {
+		let inspector = DbInspector::<B>::open(&database_config)?;
+		println!(
+			"{:<14} {:>12} {:>14} {:>16} {:>14}",
+			"column", "keys", "key bytes", "value bytes", "max value",
+		);
+		for (col, stats) in inspector.column_stats()? {
+			println!(
+				"{:<14} {:>12} {:>14} {:>16} {:>14}",
+				inspect::column_name(col),
+				stats.keys,
+				stats.key_bytes,
+				stats.value_bytes,
+				stats.max_value_bytes,
+			);
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for StatsCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// The `db dump-trie` command used to print the state trie of a block.
+#[derive(Debug, StructOpt)]
+pub struct DumpTrieCmd {
+	/// Block hash or number, the best block if omitted.
+	#[structopt(value_name = "HASH or NUMBER")]
+	pub input: Option<BlockNumberOrHash>,
+
+	/// Only print the summary, without the individual nodes.
+	#[structopt(long = "summary")]
+	pub summary: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl DumpTrieCmd {
+	/// Run the dump-trie command
+	pub fn run<B>(&self, database_config: DatabaseConfig) -> error::Result<()>
+	where
+		B: BlockT,
+		B::Hash: FromStr,
+		<B::Hash as FromStr>::Err: Debug,
+		NumberFor<B>: FromStr,
+		<NumberFor<B> as FromStr>::Err: Debug,
+	{
+		let block_id = self.input.as_ref().map(|b| b.parse()).transpose()?;
+		let inspector = DbInspector::<B>::open(&database_config)?;
+		let summary = inspector.dump_trie(block_id, &mut |node| if !self.summary {
+			println!("{}", display_node(node));
+		})?;
+
+		println!(
+			"{} nodes ({} bytes), {} inline nodes, {} values ({} bytes), {} child tries, {} missing nodes",
+			summary.nodes,
+			summary.node_bytes,
+			summary.inline_nodes,
+			summary.values,
+			summary.value_bytes,
+			summary.child_tries,
+			summary.missing,
+		);
+		Ok(())
+	}
+}
+
+fn display_node(node: &TrieNodeInfo) -> String {
+	let child_trie = node.child_trie.as_ref()
+		.map(|key| format!("[0x{}] ", HexDisplay::from(key)))
+		.unwrap_or_default();
+	let path: String = node.path.iter().map(|nibble| format!("{:x}", nibble)).collect();
+	format!(
+		"{}{}{:?} {} bytes{}{}",
+		child_trie,
+		if path.is_empty() { String::new() } else { format!("{} ", path) },
+		node.kind,
+		node.size,
+		node.value_size.map(|size| format!(", value {} bytes", size)).unwrap_or_default(),
+		if node.inline { ", inline" } else { "" },
+	)
+}
+
+impl CliConfiguration for DumpTrieCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// The `db orphans` command used to find orphaned state nodes.
+#[derive(Debug, StructOpt)]
+pub struct OrphansCmd {
+	/// Maximum number of orphaned node keys to print.
+	#[structopt(long = "limit", value_name = "COUNT", default_value = "100")]
+	pub limit: u64,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl OrphansCmd {
+	/// Run the orphans command
+	pub fn run<B: BlockT>(&self, database_config: DatabaseConfig) -> error::Result<()> {
+		let inspector = DbInspector::<B>::open(&database_config)?;
+		let mut printed = 0;
+		let summary = inspector.find_orphans(&mut |key, size| {
+			if printed < self.limit {
+				println!("0x{} {} bytes", HexDisplay::from(&key), size);
+				printed += 1;
+			}
+		})?;
+
+		println!(
+			"{} of {} state nodes are orphaned ({} bytes), {} states checked, {} missing nodes",
+			summary.orphans,
+			summary.nodes,
+			summary.orphan_bytes,
+			summary.states,
+			summary.missing,
+		);
+		Ok(())
+	}
+}
+
+impl CliConfiguration for OrphansCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
+
+/// The `db check` command used to check the consistency of the database.
+#[derive(Debug, StructOpt)]
+pub struct CheckCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl CheckCmd {
+	/// Run the check command
+	pub fn run<B: BlockT>(&self, database_config: DatabaseConfig) -> error::Result<()> {
+		let inspector = DbInspector::<B>::open(&database_config)?;
+		let summary = inspector.check(&mut |inconsistency| println!("{}", inconsistency))?;
+
+		println!(
+			"Checked {} blocks (best #{}, finalized #{}): {} states, {} justifications, {} pruned bodies",
+			summary.blocks,
+			summary.best,
+			summary.finalized,
+			summary.states,
+			summary.justifications,
+			summary.pruned_bodies,
+		);
+		if summary.inconsistencies > 0 {
+			return Err(error::Error::Client(sp_blockchain::Error::Backend(
+				format!("Found {} inconsistencies", summary.inconsistencies)
+			)));
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for CheckCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
@@ -27,11 +27,14 @@ use crate::params::TransactionPoolParams;
 use crate::CliConfiguration;
 use regex::Regex;
 use sc_service::{
-	config::{BasePath, MultiaddrWithPeerId, PrometheusConfig, TransactionPoolOptions},
+	config::{
+		BasePath, MultiaddrWithPeerId, PrometheusConfig, RateLimit, RpcLimits, TransactionPoolOptions,
+	},
 	ChainSpec, Role,
 };
 use sc_telemetry::TelemetryEndpoints;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
 use structopt::StructOpt;
 
 /// The `run` command used to run a node.
@@ -68,6 +71,11 @@ pub struct RunCmd {
 	#[structopt(long)]
 	pub no_grandpa: bool,
 
+	/// Number of blocks finality may lag behind the best block before GRANDPA
+	/// considers it stalled. `0` disables stall detection.
+	#[structopt(long = "grandpa-stall-threshold", value_name = "BLOCKS", default_value = "256")]
+	pub grandpa_stall_threshold: u32,
+
 	/// Experimental: Run in light client mode.
 	#[structopt(long = "light", conflicts_with = "sentry")]
 	pub light: bool,
@@ -138,6 +146,41 @@ pub struct RunCmd {
 	#[structopt(long = "ws-max-connections", value_name = "COUNT")]
 	pub ws_max_connections: Option<usize>,
 
+	/// Maximum size of an HTTP or WS RPC request in bytes.
+	#[structopt(long = "rpc-max-request-size", value_name = "BYTES")]
+	pub rpc_max_request_size: Option<usize>,
+
+	/// Maximum size of the response to a single RPC call in bytes.
+	///
+	/// Larger responses are replaced with an error.
+	#[structopt(long = "rpc-max-response-size", value_name = "BYTES")]
+	pub rpc_max_response_size: Option<usize>,
+
+	/// Maximum number of calls in an RPC batch request.
+	#[structopt(long = "rpc-max-batch-len", value_name = "COUNT")]
+	pub rpc_max_batch_len: Option<usize>,
+
+	/// Maximum number of subscriptions a single RPC connection may have open.
+	#[structopt(long = "rpc-max-subscriptions-per-connection", value_name = "COUNT")]
+	pub rpc_max_subscriptions_per_connection: Option<usize>,
+
+	/// Limit the rate of calls to RPC methods.
+	///
+	/// Takes `METHOD=RATE[:BURST]`, where `METHOD` is a method name or a prefix followed by
+	/// `*`, `RATE` the number of calls allowed per second and `BURST` the number of calls
+	/// allowed at once, defaulting to `RATE`. Can be passed multiple times, a call is limited
+	/// by the first matching rule. Limits apply to every WS connection separately, and to every
+	/// HTTP peer separately if `--rpc-http-peer-header` is given.
+	#[structopt(long = "rpc-rate-limit", value_name = "METHOD=RATE[:BURST]")]
+	pub rpc_rate_limit: Vec<RateLimit>,
+
+	/// Header identifying the peer of HTTP RPC calls, e.g. `X-Forwarded-For`.
+	///
+	/// Only use it behind a reverse proxy setting the header, clients can set it to anything
+	/// otherwise. Without it, HTTP calls share the rate limits of the server.
+	#[structopt(long = "rpc-http-peer-header", value_name = "HEADER")]
+	pub rpc_http_peer_header: Option<String>,
+
 	/// Specify browser Origins allowed to access the HTTP & WS RPC servers.
 	///
 	/// A comma-separated list of origins (protocol://domain or special `null`
@@ -234,6 +277,13 @@ pub struct RunCmd {
 	#[structopt(long = "force-authoring")]
 	pub force_authoring: bool,
 
+	/// Keep authoring blocks at full speed even when finality lags behind.
+	///
+	/// By default block authoring backs off when GRANDPA is enabled, skipping an
+	/// increasing share of the slots as the number of unfinalized blocks grows.
+	#[structopt(long = "no-authoring-backoff")]
+	pub no_authoring_backoff: bool,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub keystore_params: KeystoreParams,
@@ -382,6 +432,10 @@ impl CliConfiguration for RunCmd {
 		Ok(self.shared_params.dev || self.force_authoring)
 	}
 
+	fn backoff_authoring_blocks(&self) -> Result<bool> {
+		Ok(!self.no_authoring_backoff)
+	}
+
 	fn prometheus_config(&self, default_listen_port: u16) -> Result<Option<PrometheusConfig>> {
 		Ok(if self.no_prometheus {
 			None
@@ -405,10 +459,25 @@ impl CliConfiguration for RunCmd {
 		Ok(self.no_grandpa)
 	}
 
+	fn grandpa_stall_threshold(&self) -> Result<Option<u32>> {
+		Ok(Some(self.grandpa_stall_threshold).filter(|threshold| *threshold > 0))
+	}
+
 	fn rpc_ws_max_connections(&self) -> Result<Option<usize>> {
 		Ok(self.ws_max_connections)
 	}
 
+	fn rpc_limits(&self) -> Result<RpcLimits> {
+		Ok(RpcLimits {
+			max_request_size: self.rpc_max_request_size,
+			max_response_size: self.rpc_max_response_size,
+			max_batch_len: self.rpc_max_batch_len,
+			max_subscriptions_per_connection: self.rpc_max_subscriptions_per_connection,
+			rate_limits: self.rpc_rate_limit.clone(),
+			http_peer_header: self.rpc_http_peer_header.clone(),
+		})
+	}
+
 	fn rpc_cors(&self, is_dev: bool) -> Result<Option<Vec<String>>> {
 		Ok(self
 			.rpc_cors
@@ -464,6 +533,10 @@ impl CliConfiguration for RunCmd {
 		Ok(self.pool_config.transaction_pool())
 	}
 
+	fn transaction_pool_persistence(&self, config_dir: &PathBuf) -> Result<Option<PathBuf>> {
+		Ok(self.pool_config.persistence_path(config_dir))
+	}
+
 	fn max_runtime_instances(&self) -> Result<Option<usize>> {
 		Ok(self.max_runtime_instances.map(|x| x.min(256)))
 	}
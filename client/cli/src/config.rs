@@ -29,10 +29,10 @@ use names::{Generator, Name};
 use sc_client_api::execution_extensions::ExecutionStrategies;
 use sc_service::config::{
 	BasePath, Configuration, DatabaseConfig, ExtTransport, KeystoreConfig, NetworkConfiguration,
-	NodeKeyConfig, OffchainWorkerConfig, PrometheusConfig, PruningMode, Role, RpcMethods,
+	NodeKeyConfig, OffchainWorkerConfig, PrometheusConfig, PruningMode, Role, RpcLimits, RpcMethods,
 	TaskExecutor, TelemetryEndpoints, TransactionPoolOptions, WasmExecutionMethod,
 };
-use sc_service::{ChainSpec, TracingReceiver};
+use sc_service::{ChainSpec, TracingReceiver, TransactionStorageMode, KeepBlocks};
 use std::net::SocketAddr;
 use std::path::PathBuf;
 
@@ -149,6 +149,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(Default::default())
 	}
 
+	/// Get the path the transaction pool is persisted at across restarts.
+	///
+	/// By default this is `None`, i.e. the pool is not persisted.
+	fn transaction_pool_persistence(&self, _config_dir: &PathBuf) -> Result<Option<PathBuf>> {
+		Ok(None)
+	}
+
 	/// Get the network configuration
 	///
 	/// By default this is retrieved from `NetworkParams` if it is available otherwise it creates
@@ -194,6 +201,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			.unwrap_or(Ok(KeystoreConfig::InMemory))
 	}
 
+	/// Get the remote keystore URI.
+	///
+	/// By default this is retrieved from `KeystoreParams` if it is available. Otherwise its `None`.
+	fn keystore_remote(&self) -> Result<Option<String>> {
+		Ok(self.keystore_params().and_then(|x| x.keystore_uri.clone()))
+	}
+
 	/// Get the database cache size.
 	///
 	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise its `None`.
@@ -210,6 +224,16 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(self.database_params().and_then(|x| x.database()))
 	}
 
+	/// Get the block body/transaction storage scheme.
+	///
+	/// By default this is retrieved from `DatabaseParams` if it is available. Otherwise its
+	/// `TransactionStorageMode::BlockBody`.
+	fn transaction_storage(&self) -> Result<TransactionStorageMode> {
+		Ok(self.database_params()
+			.map(|x| x.transaction_storage())
+			.unwrap_or(TransactionStorageMode::BlockBody))
+	}
+
 	/// Get the database configuration object for the parameters provided
 	fn database_config(
 		&self,
@@ -254,6 +278,16 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			.unwrap_or_else(|| Ok(Default::default()))
 	}
 
+	/// Get the block pruning mode.
+	///
+	/// By default this is retrieved from `PruningParams` if it is available. Otherwise its
+	/// `KeepBlocks::All`.
+	fn keep_blocks(&self) -> Result<KeepBlocks> {
+		Ok(self.pruning_params()
+			.map(|x| x.keep_blocks())
+			.unwrap_or(KeepBlocks::All))
+	}
+
 	/// Get the chain ID (string).
 	///
 	/// By default this is retrieved from `SharedParams`.
@@ -338,6 +372,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(None)
 	}
 
+	/// Get the limits enforced by the RPC servers.
+	///
+	/// By default the limits are the defaults of the RPC servers.
+	fn rpc_limits(&self) -> Result<RpcLimits> {
+		Ok(Default::default())
+	}
+
 	/// Get the RPC cors (`None` if disabled)
 	///
 	/// By default this is `Some(Vec::new())`.
@@ -392,6 +433,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(Default::default())
 	}
 
+	/// Returns `Ok(true)` if block authoring should back off when finality lags
+	///
+	/// By default this is `true`.
+	fn backoff_authoring_blocks(&self) -> Result<bool> {
+		Ok(true)
+	}
+
 	/// Returns `Ok(true)` if grandpa should be disabled
 	///
 	/// By default this is `false`.
@@ -399,6 +447,14 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(Default::default())
 	}
 
+	/// Get the number of blocks finality may lag behind before GRANDPA considers it
+	/// stalled, `None` to disable stall detection.
+	///
+	/// By default this is `Some(256)`.
+	fn grandpa_stall_threshold(&self) -> Result<Option<u32>> {
+		Ok(Some(256))
+	}
+
 	/// Get the development key seed from the current object
 	///
 	/// By default this is `None`.
@@ -486,6 +542,7 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			impl_version: C::impl_version(),
 			task_executor,
 			transaction_pool: self.transaction_pool()?,
+			transaction_pool_persistence: self.transaction_pool_persistence(&config_dir)?,
 			network: self.network_config(
 				&chain_spec,
 				is_dev,
@@ -496,10 +553,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 				DCV::p2p_listen_port(),
 			)?,
 			keystore: self.keystore_config(&config_dir)?,
+			keystore_remote: self.keystore_remote()?,
 			database: self.database_config(&config_dir, database_cache_size, database)?,
 			state_cache_size: self.state_cache_size()?,
 			state_cache_child_ratio: self.state_cache_child_ratio()?,
 			pruning: self.pruning(unsafe_pruning, &role)?,
+			transaction_storage: self.transaction_storage()?,
+			keep_blocks: self.keep_blocks()?,
 			wasm_method: self.wasm_method()?,
 			wasm_runtime_overrides: self.wasm_runtime_overrides(),
 			execution_strategies: self.execution_strategies(is_dev, is_validator)?,
@@ -508,6 +568,7 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			rpc_ipc: self.rpc_ipc()?,
 			rpc_methods: self.rpc_methods()?,
 			rpc_ws_max_connections: self.rpc_ws_max_connections()?,
+			rpc_limits: self.rpc_limits()?,
 			rpc_cors: self.rpc_cors(is_dev)?,
 			prometheus_config: self.prometheus_config(DCV::prometheus_listen_port())?,
 			telemetry_endpoints: self.telemetry_endpoints(&chain_spec)?,
@@ -515,7 +576,9 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			default_heap_pages: self.default_heap_pages()?,
 			offchain_worker: self.offchain_worker(&role)?,
 			force_authoring: self.force_authoring()?,
+			backoff_authoring_blocks: self.backoff_authoring_blocks()?,
 			disable_grandpa: self.disable_grandpa()?,
+			grandpa_stall_threshold: self.grandpa_stall_threshold()?,
 			dev_key_seed: self.dev_key_seed(is_dev)?,
 			tracing_targets: self.tracing_targets()?,
 			tracing_receiver: self.tracing_receiver()?,
@@ -31,6 +31,10 @@ const DEFAULT_KEYSTORE_CONFIG_PATH: &'static str = "keystore";
 /// Parameters of the keystore
 #[derive(Debug, StructOpt)]
 pub struct KeystoreParams {
+	/// Specify the URI of a remote signer that holds the keys, e.g. `unix:///run/signer.sock`.
+	#[structopt(long = "keystore-uri", value_name = "URI", conflicts_with = "keystore-path")]
+	pub keystore_uri: Option<String>,
+
 	/// Specify custom keystore path.
 	#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
 	pub keystore_path: Option<PathBuf>,
@@ -17,7 +17,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::error;
-use sc_service::{PruningMode, Role};
+use sc_service::{PruningMode, Role, KeepBlocks};
 use structopt::StructOpt;
 
 /// Parameters to define the pruning mode
@@ -30,6 +30,32 @@ pub struct PruningParams {
 	/// 256 blocks.
 	#[structopt(long = "pruning", value_name = "PRUNING_MODE")]
 	pub pruning: Option<String>,
+
+	/// Maximum memory in MiB used to track the state pruning window.
+	///
+	/// Older block states are pruned earlier when the limit is exceeded.
+	/// Ignored in archive mode.
+	#[structopt(long = "pruning-max-mem", value_name = "MiB")]
+	pub pruning_max_mem: Option<usize>,
+
+	/// Count the blocks to keep from the last finalized block rather than the best block.
+	///
+	/// Ignored in archive mode.
+	#[structopt(long = "pruning-from-finalized")]
+	pub pruning_from_finalized: bool,
+
+	/// Keep the state of every block with a number divisible by this value indefinitely.
+	///
+	/// Ignored in archive mode.
+	#[structopt(long = "pruning-retain-every", value_name = "BLOCKS")]
+	pub pruning_retain_every: Option<u64>,
+
+	/// Specify the number of finalized blocks to keep the bodies of.
+	///
+	/// Bodies of older finalized blocks are deleted, while headers and justifications are kept.
+	/// Default is to keep all block bodies.
+	#[structopt(long = "blocks-pruning", value_name = "COUNT")]
+	pub blocks_pruning: Option<u32>,
 }
 
 impl PruningParams {
@@ -39,7 +65,7 @@ impl PruningParams {
 		// `ArchiveAll`), otherwise we keep state for the last 256 blocks. if the
 		// node is an authority and pruning is enabled explicitly, then we error
 		// unless `unsafe_pruning` is set.
-		Ok(match &self.pruning {
+		let mode = match &self.pruning {
 			Some(ref s) if s == "archive" => PruningMode::ArchiveAll,
 			None if role.is_network_authority() => PruningMode::ArchiveAll,
 			None => PruningMode::default(),
@@ -56,6 +82,24 @@ impl PruningParams {
 					error::Error::Input("Invalid pruning mode specified".to_string())
 				})?)
 			}
+		};
+
+		Ok(match mode {
+			PruningMode::Constrained(mut constraints) => {
+				constraints.max_mem = self.pruning_max_mem.map(|mib| mib.saturating_mul(1024 * 1024));
+				constraints.from_finalized = self.pruning_from_finalized;
+				constraints.retain_every = self.pruning_retain_every;
+				PruningMode::Constrained(constraints)
+			},
+			mode => mode,
 		})
 	}
+
+	/// Get the block pruning value from the parameters
+	pub fn keep_blocks(&self) -> KeepBlocks {
+		match self.blocks_pruning {
+			Some(n) => KeepBlocks::Some(n),
+			None => KeepBlocks::All,
+		}
+	}
 }
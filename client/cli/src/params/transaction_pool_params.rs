@@ -16,9 +16,14 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use sc_service::config::TransactionPoolOptions;
+use sc_service::config::{TransactionPoolOptions, TransactionPoolSenderLimit};
+use sp_runtime::Percent;
+use std::{path::PathBuf, time::Duration};
 use structopt::StructOpt;
 
+/// Default sub directory to persist the transaction pool to.
+const DEFAULT_TRANSACTION_POOL_PATH: &'static str = "txpool";
+
 /// Parameters used to create the pool configuration.
 #[derive(Debug, StructOpt)]
 pub struct TransactionPoolParams {
@@ -29,6 +34,31 @@ pub struct TransactionPoolParams {
 	/// Maximum number of kilobytes of all transactions stored in the pool.
 	#[structopt(long = "pool-kbytes", value_name = "COUNT", default_value = "20480")]
 	pub pool_kbytes: usize,
+
+	/// Maximum number of transactions of a single sender in the transaction pool.
+	///
+	/// The sender is derived from the tags provided by the transaction. Unlimited by default.
+	#[structopt(long = "pool-sender-limit", value_name = "COUNT")]
+	pub pool_sender_limit: Option<usize>,
+
+	/// Number of trailing bytes of a transaction tag that encode the nonce of the sender.
+	#[structopt(long = "pool-sender-nonce-bytes", value_name = "COUNT", default_value = "4")]
+	pub pool_sender_nonce_bytes: usize,
+
+	/// Minimal priority bump (in percent) required to replace a transaction already in the pool.
+	#[structopt(long = "pool-replacement-bump", value_name = "PERCENT", default_value = "0")]
+	pub pool_replacement_bump: u8,
+
+	/// Number of seconds transactions removed from the pool are banned from re-entering it.
+	#[structopt(long = "pool-ban-seconds", value_name = "SECONDS", default_value = "1800")]
+	pub pool_ban_seconds: u64,
+
+	/// Persist the transaction pool across node restarts.
+	///
+	/// Transactions are written to the base path periodically and on shutdown,
+	/// and validated again before entering the pool on startup.
+	#[structopt(long = "pool-persist")]
+	pub pool_persist: bool,
 }
 
 impl TransactionPoolParams {
@@ -45,6 +75,25 @@ impl TransactionPoolParams {
 		opts.future.count = self.pool_limit / factor;
 		opts.future.total_bytes = self.pool_kbytes * 1024 / factor;
 
+		// per-sender limits
+		opts.sender = self.pool_sender_limit.map(|count| TransactionPoolSenderLimit {
+			count,
+			nonce_len: self.pool_sender_nonce_bytes,
+		});
+
+		// replacement and bans
+		opts.min_priority_bump = Percent::from_percent(self.pool_replacement_bump);
+		opts.rotation.ban_time = Duration::from_secs(self.pool_ban_seconds);
+
 		opts
 	}
+
+	/// Returns the path the pool is persisted at, if persistence is enabled.
+	pub fn persistence_path(&self, config_dir: &PathBuf) -> Option<PathBuf> {
+		if self.pool_persist {
+			Some(config_dir.join(DEFAULT_TRANSACTION_POOL_PATH))
+		} else {
+			None
+		}
+	}
 }
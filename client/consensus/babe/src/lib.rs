@@ -74,6 +74,7 @@ pub use sp_consensus_babe::{
 	},
 };
 pub use sp_consensus::SyncOracle;
+pub use sc_consensus_slots::BackoffAuthoringOnFinalizedHeadLagging;
 use std::{
 	collections::HashMap, sync::Arc, u64, pin::Pin, time::{Instant, Duration},
 	any::Any, borrow::Cow, convert::TryInto,
@@ -87,7 +88,7 @@ use sp_application_crypto::AppKey;
 use sp_keystore::{SyncCryptoStorePtr, SyncCryptoStore};
 use sp_runtime::{
 	generic::{BlockId, OpaqueDigestItemId}, Justification,
-	traits::{Block as BlockT, Header, DigestItemFor, Zero},
+	traits::{Block as BlockT, Header, DigestItemFor, One, Zero},
 };
 use sp_api::{ProvideRuntimeApi, NumberFor};
 use parking_lot::Mutex;
@@ -114,6 +115,7 @@ use log::{debug, info, log, trace, warn};
 use prometheus_endpoint::Registry;
 use sc_consensus_slots::{
 	SlotInfo, SlotCompatible, StorageChanges, CheckedHeader, check_equivocation,
+	BackoffAuthoringBlocksStrategy,
 };
 use sc_consensus_epochs::{
 	descendent_query, SharedEpochChanges, EpochChangesFor, Epoch as EpochT, ViableEpochDescriptor,
@@ -197,6 +199,27 @@ impl Epoch {
 			},
 		}
 	}
+
+	/// Clone this epoch, adjusting its index and start slot to the epoch the
+	/// given slot belongs to.
+	///
+	/// If no block was produced during one or more epochs, the data announced
+	/// for the epoch following the last one with blocks is reused for the epoch
+	/// of the given slot, which is reflected by its index and start slot.
+	pub fn clone_for_slot(&self, slot_number: SlotNumber) -> Epoch {
+		let mut epoch = self.clone();
+
+		let skipped_epochs = slot_number.saturating_sub(self.start_slot) / self.duration;
+
+		epoch.epoch_index = self.epoch_index.checked_add(skipped_epochs)
+			.expect("epoch indices will never reach 2^64 before the death of the universe; qed");
+		epoch.start_slot = skipped_epochs.checked_mul(self.duration)
+			.and_then(|skipped_slots| self.start_slot.checked_add(skipped_slots))
+			.expect("skipped slots are at most `slot_number - start_slot`; \
+				the new start slot is at most `slot_number`; qed");
+
+		epoch
+	}
 }
 
 #[derive(derive_more::Display, Debug)]
@@ -326,7 +349,7 @@ impl std::ops::Deref for Config {
 }
 
 /// Parameters for BABE.
-pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW> {
+pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW, BS> {
 	/// The keystore that manages the keys of the node.
 	pub keystore: SyncCryptoStorePtr,
 
@@ -353,6 +376,10 @@ pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW> {
 	/// Force authoring of blocks even if we are offline
 	pub force_authoring: bool,
 
+	/// Strategy and parameters for backing off block production when finality
+	/// lags behind, `None` never backs off.
+	pub backoff_authoring_blocks: Option<BS>,
+
 	/// The source of timestamps for relative slots
 	pub babe_link: BabeLink<B>,
 
@@ -361,7 +388,7 @@ pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW> {
 }
 
 /// Start the babe worker.
-pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
+pub fn start_babe<B, C, SC, E, I, SO, CAW, BS, Error>(BabeParams {
 	keystore,
 	client,
 	select_chain,
@@ -370,9 +397,10 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
 	sync_oracle,
 	inherent_data_providers,
 	force_authoring,
+	backoff_authoring_blocks,
 	babe_link,
 	can_author_with,
-}: BabeParams<B, C, E, I, SO, SC, CAW>) -> Result<
+}: BabeParams<B, C, E, I, SO, SC, CAW, BS>) -> Result<
 	BabeWorker<B>,
 	sp_consensus::Error,
 > where
@@ -388,6 +416,7 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
 	Error: std::error::Error + Send + From<ConsensusError> + From<I::Error> + 'static,
 	SO: SyncOracle + Send + Sync + Clone + 'static,
 	CAW: CanAuthorWith<B> + Send + 'static,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + 'static,
 {
 	let config = babe_link.config;
 	let slot_notification_sinks = Arc::new(Mutex::new(Vec::new()));
@@ -398,6 +427,7 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
 		env,
 		sync_oracle: sync_oracle.clone(),
 		force_authoring,
+		backoff_authoring_blocks,
 		keystore,
 		epoch_changes: babe_link.epoch_changes.clone(),
 		slot_notification_sinks: slot_notification_sinks.clone(),
@@ -462,19 +492,22 @@ impl<B: BlockT> futures::Future for BabeWorker<B> {
 /// Slot notification sinks.
 type SlotNotificationSinks<B> = Arc<Mutex<Vec<Sender<(u64, ViableEpochDescriptor<<B as BlockT>::Hash, NumberFor<B>, Epoch>)>>>>;
 
-struct BabeSlotWorker<B: BlockT, C, E, I, SO> {
+struct BabeSlotWorker<B: BlockT, C, E, I, SO, BS> {
 	client: Arc<C>,
 	block_import: Arc<Mutex<I>>,
 	env: E,
 	sync_oracle: SO,
 	force_authoring: bool,
+	backoff_authoring_blocks: Option<BS>,
 	keystore: SyncCryptoStorePtr,
 	epoch_changes: SharedEpochChanges<B, Epoch>,
 	slot_notification_sinks: SlotNotificationSinks<B>,
 	config: Config,
 }
 
-impl<B, C, E, I, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for BabeSlotWorker<B, C, E, I, SO> where
+impl<B, C, E, I, Error, SO, BS> sc_consensus_slots::SimpleSlotWorker<B>
+	for BabeSlotWorker<B, C, E, I, SO, BS>
+where
 	B: BlockT,
 	C: ProvideRuntimeApi<B> +
 		ProvideCache<B> +
@@ -485,6 +518,7 @@ impl<B, C, E, I, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for BabeSlot
 	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
 	I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync + 'static,
 	SO: SyncOracle + Send + Clone,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>>,
 	Error: std::error::Error + Send + From<ConsensusError> + From<I::Error> + 'static,
 {
 	type EpochData = ViableEpochDescriptor<B::Hash, NumberFor<B>, Epoch>;
@@ -534,10 +568,10 @@ impl<B, C, E, I, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for BabeSlot
 		debug!(target: "babe", "Attempting to claim slot {}", slot_number);
 		let s = authorship::claim_slot(
 			slot_number,
-			self.epoch_changes.lock().viable_epoch(
+			&self.epoch_changes.lock().viable_epoch(
 				&epoch_descriptor,
 				|slot| Epoch::genesis(&self.config, slot)
-			)?.as_ref(),
+			)?.as_ref().clone_for_slot(slot_number),
 			&self.keystore,
 		);
 
@@ -570,6 +604,23 @@ impl<B, C, E, I, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for BabeSlot
 			});
 	}
 
+	fn should_backoff(&self, slot_number: u64, chain_head: &B::Header) -> bool {
+		if let Some(ref strategy) = self.backoff_authoring_blocks {
+			if let Ok(chain_head_slot) = find_pre_digest::<B>(chain_head)
+				.map(|digest| digest.slot_number())
+			{
+				return strategy.should_backoff(
+					*chain_head.number(),
+					chain_head_slot,
+					self.client.info().finalized_number,
+					slot_number,
+					self.logging_target(),
+				);
+			}
+		}
+		false
+	}
+
 	fn pre_digest_data(
 		&self,
 		_slot_number: u64,
@@ -765,6 +816,38 @@ impl<Block: BlockT> BabeLink<Block> {
 	pub fn config(&self) -> &Config {
 		&self.config
 	}
+
+	/// Whether the data of the epoch the given block belongs to is known,
+	/// i.e. whether the block could be verified (and its descendants authored)
+	/// with the epoch changes tracked by this link.
+	pub fn has_epoch_data<C>(&self, client: &C, header: &Block::Header) -> bool where
+		C: HeaderBackend<Block> + HeaderMetadata<Block, Error = sp_blockchain::Error>,
+	{
+		if header.number().is_zero() {
+			return true;
+		}
+
+		let slot_number = match find_pre_digest::<Block>(header) {
+			Ok(pre_digest) => pre_digest.slot_number(),
+			Err(_) => return false,
+		};
+
+		let epoch_changes = self.epoch_changes.lock();
+		let epoch_descriptor = epoch_changes.epoch_descriptor_for_child_of(
+			descendent_query(client),
+			header.parent_hash(),
+			*header.number() - One::one(),
+			slot_number,
+		);
+
+		match epoch_descriptor {
+			Ok(Some(epoch_descriptor)) => epoch_changes.viable_epoch(
+				&epoch_descriptor,
+				|slot| Epoch::genesis(&self.config, slot),
+			).is_some(),
+			_ => false,
+		}
+	}
 }
 
 /// A verifier for Babe blocks.
@@ -956,6 +1039,7 @@ where
 			&epoch_descriptor,
 			|slot| Epoch::genesis(&self.config, slot)
 		).ok_or_else(|| Error::<Block>::FetchEpoch(parent_hash))?;
+		let epoch = viable_epoch.as_ref().clone_for_slot(pre_digest.slot_number());
 
 		// We add one to the current slot to allow for some small drift.
 		// FIXME #1019 in the future, alter this queue to allow deferring of headers
@@ -963,7 +1047,7 @@ where
 			header: header.clone(),
 			pre_digest: Some(pre_digest),
 			slot_now: slot_now + 1,
-			epoch: viable_epoch.as_ref(),
+			epoch: &epoch,
 		};
 
 		match verification::check_header::<Block>(v_params)? {
@@ -1212,17 +1296,38 @@ impl<Block, Client, Inner> BlockImport<Block> for BabeBlockImport<Block, Client,
 		if let Some(next_epoch_descriptor) = next_epoch_digest {
 			old_epoch_changes = Some(epoch_changes.clone());
 
-			let viable_epoch = epoch_changes.viable_epoch(
+			let mut viable_epoch = epoch_changes.viable_epoch(
 				&epoch_descriptor,
 				|slot| Epoch::genesis(&self.config, slot)
 			).ok_or_else(|| {
 				ConsensusError::ClientImport(Error::<Block>::FetchEpoch(parent_hash).into())
-			})?;
+			})?.into_cloned();
 
 			let epoch_config = next_config_digest.map(Into::into).unwrap_or_else(
 				|| viable_epoch.as_ref().config.clone()
 			);
 
+			if viable_epoch.as_ref().end_slot() <= slot_number {
+				// no block was produced in one or more epochs, so the block belongs to a
+				// later epoch than the one that was announced. the announced epoch data is
+				// reused for the epoch of the block.
+				//
+				// NOTE: only our copy of the epoch is updated, so that the epoch that will be
+				// imported below gets the right index and start slot. the announced epoch is
+				// kept as is since there might be other forks where it wasn't skipped, and
+				// it is still found for slots in the later epoch as we search the tree
+				// with the predicate `epoch.start_slot <= slot`.
+				let epoch = viable_epoch.as_ref().clone_for_slot(slot_number);
+
+				warn!(target: "babe",
+					"👶 Epoch(s) skipped: from {} to {}",
+					viable_epoch.as_ref().epoch_index,
+					epoch.epoch_index,
+				);
+
+				*viable_epoch.as_mut() = epoch;
+			}
+
 			// restrict info logging during initial sync to avoid spam
 			let log_level = if block.origin == BlockOrigin::NetworkInitialSync {
 				log::Level::Debug
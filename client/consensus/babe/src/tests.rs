@@ -434,6 +434,7 @@ fn run_one_test(
 			sync_oracle: DummyOracle,
 			inherent_data_providers: data.inherent_data_providers.clone(),
 			force_authoring: false,
+			backoff_authoring_blocks: Some(BackoffAuthoringOnFinalizedHeadLagging::default()),
 			babe_link: data.link.clone(),
 			keystore,
 			can_author_with: sp_consensus::AlwaysCanAuthor,
@@ -786,6 +787,90 @@ fn importing_epoch_change_block_prunes_tree() {
 	);
 }
 
+#[test]
+fn allows_skipping_epochs() {
+	let mut net = BabeTestNet::new(1);
+
+	let peer = net.peer(0);
+	let data = peer.data.as_ref().expect("babe link set up during initialization");
+
+	let client = peer.client().as_full().expect("Only full clients are used in tests").clone();
+	let mut block_import = data.block_import.lock().take().expect("import set up during init");
+	let epoch_changes = data.link.epoch_changes.clone();
+	let epoch_length = data.link.config.epoch_length;
+
+	let mut proposer_factory = DummyFactory {
+		client: client.clone(),
+		config: data.link.config.clone(),
+		epoch_changes: data.link.epoch_changes.clone(),
+		mutator: Arc::new(|_, _| ()),
+	};
+
+	let mut propose_and_import_blocks = |parent_hash, slots: Vec<Option<SlotNumber>>| {
+		let mut parent_header = client.header(&BlockId::Hash(parent_hash)).unwrap().unwrap();
+		let mut hash = parent_hash;
+
+		for slot_number in slots {
+			hash = propose_and_import_block(
+				&parent_header,
+				slot_number,
+				&mut proposer_factory,
+				&mut block_import,
+			);
+			parent_header = client.header(&BlockId::Hash(hash)).unwrap().unwrap();
+		}
+
+		hash
+	};
+
+	let epoch_for_child_of = |hash, number, slot_number| {
+		epoch_changes.lock().epoch_data_for_child_of(
+			descendent_query(&*client),
+			&hash,
+			number,
+			slot_number,
+			|slot| Epoch::genesis(&data.link.config, slot),
+		).unwrap().unwrap()
+	};
+
+	// we author all blocks of epoch #0 (which starts at slot 1) and the first block
+	// of epoch #1, which announces epoch #2.
+	let genesis_hash = client.chain_info().genesis_hash;
+	let block = propose_and_import_blocks(genesis_hash, vec![None; epoch_length as usize + 1]);
+	let number = epoch_length + 1;
+
+	let epoch_2 = epoch_for_child_of(block, number, epoch_length * 2 + 1);
+	assert_eq!(epoch_2.epoch_index, 2);
+	assert_eq!(epoch_2.start_slot, epoch_length * 2 + 1);
+
+	// the chain stalls during epochs #2, #3 and #4, and we author the next block
+	// in epoch #5.
+	let block = propose_and_import_blocks(block, vec![Some(epoch_length * 5 + 1)]);
+	let number = number + 1;
+
+	// the data announced for epoch #2 is used during epoch #5.
+	let epoch_5 = epoch_for_child_of(block, number, epoch_length * 5 + 2);
+	assert_eq!(epoch_5, epoch_2);
+
+	let epoch_5 = epoch_5.clone_for_slot(epoch_length * 5 + 2);
+	assert_eq!(epoch_5.epoch_index, 5);
+	assert_eq!(epoch_5.start_slot, epoch_length * 5 + 1);
+	assert_eq!(epoch_5.randomness, epoch_2.randomness);
+
+	// the first block in epoch #5 announced epoch #6.
+	let epoch_6 = epoch_for_child_of(block, number, epoch_length * 6 + 1);
+	assert_eq!(epoch_6.epoch_index, 6);
+	assert_eq!(epoch_6.start_slot, epoch_length * 6 + 1);
+
+	// and the chain can progress through the following epoch changes.
+	let block = propose_and_import_blocks(block, vec![None; epoch_length as usize * 2]);
+	let number = number + epoch_length * 2;
+
+	let epoch_8 = epoch_for_child_of(block, number, epoch_length * 8 + 1);
+	assert_eq!(epoch_8.epoch_index, 8);
+	assert_eq!(epoch_8.start_slot, epoch_length * 8 + 1);
+}
+
 #[test]
 #[should_panic]
 fn verify_slots_are_strictly_increasing() {
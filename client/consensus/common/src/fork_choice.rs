@@ -0,0 +1,564 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Pluggable fork-choice rules.
+//!
+//! A [`ForkChoiceRule`] decides whether a block that is being imported should
+//! become the new best block, given the current best block. Rules can be
+//! composed (e.g. [`FinalityRespectingRule`] wraps another rule) and are
+//! evaluated through a [`ForkChoiceSelector`], which logs every decision
+//! together with its reason and records it in Prometheus.
+//!
+//! Consensus engines that pick their own fork choice (e.g. PoW) can be
+//! configured with a selector directly. Engines that defer to the client's
+//! longest chain fork choice can be wrapped with a [`ForkChoiceBlockImport`].
+
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+use log::{debug, warn};
+use prometheus_endpoint::{register, CounterVec, Opts, PrometheusError, Registry, U64};
+use sp_blockchain::{well_known_cache_keys::Id as CacheKeyId, HeaderBackend, HeaderMetadata};
+use sp_consensus::{
+	BlockCheckParams, BlockImport, BlockImportParams, Error as ConsensusError, ForkChoiceStrategy,
+	ImportResult,
+};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Header as HeaderT},
+};
+
+/// The outcome of a fork-choice rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForkChoiceDecision {
+	/// Whether the candidate block should become the new best block.
+	pub import_as_best: bool,
+	/// Human readable explanation of the decision, used for logging.
+	pub reason: String,
+}
+
+impl ForkChoiceDecision {
+	/// The candidate should become the new best block.
+	pub fn switch(reason: impl Into<String>) -> Self {
+		ForkChoiceDecision { import_as_best: true, reason: reason.into() }
+	}
+
+	/// The current best block should be kept.
+	pub fn keep(reason: impl Into<String>) -> Self {
+		ForkChoiceDecision { import_as_best: false, reason: reason.into() }
+	}
+}
+
+/// A rule deciding whether a candidate block should replace the current best block.
+pub trait ForkChoiceRule<Block: BlockT>: Send + Sync {
+	/// Short name of the rule, used as a label in logs and metrics.
+	fn name(&self) -> &'static str;
+
+	/// Decide whether `candidate` should be imported as the new best block
+	/// instead of `best`. The parent of `candidate` is always already imported.
+	fn decide(
+		&self,
+		candidate: &Block::Header,
+		best: &Block::Header,
+	) -> Result<ForkChoiceDecision, ConsensusError>;
+}
+
+impl<Block: BlockT, R: ForkChoiceRule<Block> + ?Sized> ForkChoiceRule<Block> for Arc<R> {
+	fn name(&self) -> &'static str {
+		(**self).name()
+	}
+
+	fn decide(
+		&self,
+		candidate: &Block::Header,
+		best: &Block::Header,
+	) -> Result<ForkChoiceDecision, ConsensusError> {
+		(**self).decide(candidate, best)
+	}
+}
+
+impl<Block: BlockT, R: ForkChoiceRule<Block> + ?Sized> ForkChoiceRule<Block> for Box<R> {
+	fn name(&self) -> &'static str {
+		(**self).name()
+	}
+
+	fn decide(
+		&self,
+		candidate: &Block::Header,
+		best: &Block::Header,
+	) -> Result<ForkChoiceDecision, ConsensusError> {
+		(**self).decide(candidate, best)
+	}
+}
+
+/// Prefers the block with the highest number, keeping the current best block on ties.
+///
+/// This is the rule the client applies for `ForkChoiceStrategy::LongestChain`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LongestChainRule;
+
+impl<Block: BlockT> ForkChoiceRule<Block> for LongestChainRule {
+	fn name(&self) -> &'static str {
+		"longest_chain"
+	}
+
+	fn decide(
+		&self,
+		candidate: &Block::Header,
+		best: &Block::Header,
+	) -> Result<ForkChoiceDecision, ConsensusError> {
+		Ok(if candidate.number() > best.number() {
+			ForkChoiceDecision::switch(format!(
+				"higher block number ({} > {})",
+				candidate.number(),
+				best.number(),
+			))
+		} else {
+			ForkChoiceDecision::keep(format!(
+				"block number not higher ({} <= {})",
+				candidate.number(),
+				best.number(),
+			))
+		})
+	}
+}
+
+/// Prefers the block with the highest score, delegating ties to another rule.
+///
+/// The score is computed from the header alone, e.g. the number of authority
+/// signatures carried in its digest.
+pub struct ScoreRule<F, R> {
+	name: &'static str,
+	score: F,
+	tie_breaker: R,
+}
+
+impl<F, R> ScoreRule<F, R> {
+	/// Create a new rule with the given name, scoring function and tie breaker.
+	pub fn new(name: &'static str, score: F, tie_breaker: R) -> Self {
+		ScoreRule { name, score, tie_breaker }
+	}
+}
+
+impl<Block, F, R> ForkChoiceRule<Block> for ScoreRule<F, R> where
+	Block: BlockT,
+	F: Fn(&Block::Header) -> Result<u128, ConsensusError> + Send + Sync,
+	R: ForkChoiceRule<Block>,
+{
+	fn name(&self) -> &'static str {
+		self.name
+	}
+
+	fn decide(
+		&self,
+		candidate: &Block::Header,
+		best: &Block::Header,
+	) -> Result<ForkChoiceDecision, ConsensusError> {
+		let candidate_score = (self.score)(candidate)?;
+		let best_score = (self.score)(best)?;
+
+		Ok(if candidate_score > best_score {
+			ForkChoiceDecision::switch(format!("higher score ({} > {})", candidate_score, best_score))
+		} else if candidate_score < best_score {
+			ForkChoiceDecision::keep(format!("lower score ({} < {})", candidate_score, best_score))
+		} else {
+			let decision = self.tie_breaker.decide(candidate, best)?;
+			ForkChoiceDecision {
+				import_as_best: decision.import_as_best,
+				reason: format!(
+					"equal score ({}), {}: {}",
+					candidate_score,
+					self.tie_breaker.name(),
+					decision.reason,
+				),
+			}
+		})
+	}
+}
+
+/// Never switches to a block that does not build on the last finalized block,
+/// delegating all other decisions to the inner rule.
+///
+/// The last finalized block acts as a checkpoint that the best chain can never
+/// be reverted below.
+pub struct FinalityRespectingRule<Block, C, R> {
+	client: Arc<C>,
+	inner: R,
+	_phantom: PhantomData<Block>,
+}
+
+impl<Block, C, R> FinalityRespectingRule<Block, C, R> {
+	/// Create a new rule wrapping `inner`.
+	pub fn new(client: Arc<C>, inner: R) -> Self {
+		FinalityRespectingRule { client, inner, _phantom: PhantomData }
+	}
+}
+
+impl<Block, C, R> ForkChoiceRule<Block> for FinalityRespectingRule<Block, C, R> where
+	Block: BlockT,
+	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = sp_blockchain::Error> + Send + Sync,
+	R: ForkChoiceRule<Block>,
+{
+	fn name(&self) -> &'static str {
+		"finality_respecting"
+	}
+
+	fn decide(
+		&self,
+		candidate: &Block::Header,
+		best: &Block::Header,
+	) -> Result<ForkChoiceDecision, ConsensusError> {
+		let info = self.client.info();
+
+		if *candidate.number() <= info.finalized_number {
+			return Ok(ForkChoiceDecision::keep(format!(
+				"block number {} not above finalized block #{}",
+				candidate.number(),
+				info.finalized_number,
+			)));
+		}
+
+		let parent_hash = *candidate.parent_hash();
+		if parent_hash != info.finalized_hash {
+			let ancestor = sp_blockchain::lowest_common_ancestor(
+				&*self.client,
+				parent_hash,
+				info.finalized_hash,
+			).map_err(|e| ConsensusError::ChainLookup(e.to_string()))?;
+
+			if ancestor.hash != info.finalized_hash {
+				return Ok(ForkChoiceDecision::keep(format!(
+					"not a descendant of finalized block #{} ({:?})",
+					info.finalized_number,
+					info.finalized_hash,
+				)));
+			}
+		}
+
+		self.inner.decide(candidate, best)
+	}
+}
+
+/// Fork-choice metrics.
+struct Metrics {
+	decisions: CounterVec<U64>,
+}
+
+impl Metrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			decisions: register(
+				CounterVec::new(
+					Opts::new(
+						"fork_choice_decisions_total",
+						"Number of fork-choice decisions made, by rule and outcome",
+					),
+					&["rule", "outcome"],
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+/// Evaluates a fork-choice rule, logging and recording every decision.
+pub struct ForkChoiceSelector<Block: BlockT> {
+	rule: Box<dyn ForkChoiceRule<Block>>,
+	metrics: Option<Metrics>,
+}
+
+impl<Block: BlockT> ForkChoiceSelector<Block> {
+	/// Create a new selector for the given rule, registering its metrics in `registry`.
+	pub fn new<R: ForkChoiceRule<Block> + 'static>(rule: R, registry: Option<&Registry>) -> Self {
+		let metrics = registry.and_then(|registry| {
+			Metrics::register(registry)
+				.map_err(|err| warn!(target: "fork-choice", "Failed to register metrics: {}", err))
+				.ok()
+		});
+
+		ForkChoiceSelector { rule: Box::new(rule), metrics }
+	}
+
+	/// Returns whether `candidate` should be imported as the new best block instead of `best`.
+	pub fn import_as_best(
+		&self,
+		candidate: &Block::Header,
+		best: &Block::Header,
+	) -> Result<bool, ConsensusError> {
+		let decision = self.rule.decide(candidate, best)?;
+		let outcome = if decision.import_as_best { "switch" } else { "keep" };
+
+		debug!(
+			target: "fork-choice",
+			"[{}] {} #{} ({:?}) over best #{} ({:?}): {}",
+			self.rule.name(),
+			if decision.import_as_best { "Importing as best" } else { "Not importing as best" },
+			candidate.number(),
+			candidate.hash(),
+			best.number(),
+			best.hash(),
+			decision.reason,
+		);
+
+		if let Some(metrics) = &self.metrics {
+			metrics.decisions.with_label_values(&[self.rule.name(), outcome]).inc();
+		}
+
+		Ok(decision.import_as_best)
+	}
+}
+
+/// A block import that applies a fork-choice rule to blocks imported with the
+/// longest chain fork choice (or without any fork choice).
+///
+/// Blocks whose fork choice was already decided by the consensus engine
+/// (`ForkChoiceStrategy::Custom`) are passed through untouched.
+pub struct ForkChoiceBlockImport<Block: BlockT, I, C> {
+	inner: I,
+	client: Arc<C>,
+	selector: Arc<ForkChoiceSelector<Block>>,
+}
+
+impl<Block: BlockT, I: Clone, C> Clone for ForkChoiceBlockImport<Block, I, C> {
+	fn clone(&self) -> Self {
+		ForkChoiceBlockImport {
+			inner: self.inner.clone(),
+			client: self.client.clone(),
+			selector: self.selector.clone(),
+		}
+	}
+}
+
+impl<Block: BlockT, I, C> ForkChoiceBlockImport<Block, I, C> {
+	/// Wrap `inner`, deciding fork choice with the given selector.
+	pub fn new(inner: I, client: Arc<C>, selector: Arc<ForkChoiceSelector<Block>>) -> Self {
+		ForkChoiceBlockImport { inner, client, selector }
+	}
+}
+
+impl<Block, I, C> BlockImport<Block> for ForkChoiceBlockImport<Block, I, C> where
+	Block: BlockT,
+	I: BlockImport<Block>,
+	I::Error: Into<ConsensusError>,
+	C: HeaderBackend<Block>,
+{
+	type Error = ConsensusError;
+	type Transaction = I::Transaction;
+
+	fn check_block(
+		&mut self,
+		block: BlockCheckParams<Block>,
+	) -> Result<ImportResult, Self::Error> {
+		self.inner.check_block(block).map_err(Into::into)
+	}
+
+	fn import_block(
+		&mut self,
+		mut block: BlockImportParams<Block, Self::Transaction>,
+		new_cache: HashMap<CacheKeyId, Vec<u8>>,
+	) -> Result<ImportResult, Self::Error> {
+		match block.fork_choice {
+			None | Some(ForkChoiceStrategy::LongestChain) => {
+				let best_hash = self.client.info().best_hash;
+				let best_header = self.client.header(BlockId::Hash(best_hash))
+					.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?
+					.ok_or_else(|| ConsensusError::ChainLookup(
+						format!("Best header {:?} not found", best_hash),
+					))?;
+
+				let import_as_best = self.selector.import_as_best(&block.header, &best_header)?;
+				block.fork_choice = Some(ForkChoiceStrategy::Custom(import_as_best));
+			},
+			Some(ForkChoiceStrategy::Custom(_)) => {},
+		}
+
+		self.inner.import_block(block, new_cache).map_err(Into::into)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_block_builder::BlockBuilderProvider;
+	use sp_consensus::BlockOrigin;
+	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper, Header};
+	use substrate_test_runtime_client::{
+		prelude::*, runtime::{Block as TestBlock, Hash, Transfer}, DefaultTestClientBuilderExt,
+	};
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+	fn build_at(client: &TestClient, parent: Hash, nonce: Option<u64>) -> TestBlock {
+		let mut builder = client.new_block_at(&BlockId::Hash(parent), Default::default(), false)
+			.unwrap();
+		// the transfer is required as otherwise forks have the same hash.
+		if let Some(nonce) = nonce {
+			builder.push_transfer(Transfer {
+				from: AccountKeyring::Alice.into(),
+				to: AccountKeyring::Ferdie.into(),
+				amount: 41,
+				nonce,
+			}).unwrap();
+		}
+		builder.build().unwrap().block
+	}
+
+	/// Never switches to the candidate.
+	struct KeepBestRule;
+
+	impl ForkChoiceRule<TestBlock> for KeepBestRule {
+		fn name(&self) -> &'static str {
+			"keep_best"
+		}
+
+		fn decide(
+			&self,
+			_candidate: &<TestBlock as BlockT>::Header,
+			_best: &<TestBlock as BlockT>::Header,
+		) -> Result<ForkChoiceDecision, ConsensusError> {
+			Ok(ForkChoiceDecision::keep("always keep"))
+		}
+	}
+
+	fn header(number: u64, state_root: u8) -> Header {
+		Header::new(
+			number,
+			Default::default(),
+			[state_root; 32].into(),
+			Default::default(),
+			Default::default(),
+		)
+	}
+
+	#[test]
+	fn longest_chain_rule_keeps_best_on_ties() {
+		let rule = LongestChainRule;
+
+		let decide = |candidate, best| {
+			ForkChoiceRule::<Block>::decide(&rule, &candidate, &best).unwrap().import_as_best
+		};
+
+		assert!(decide(header(2, 0), header(1, 1)));
+		assert!(!decide(header(2, 0), header(2, 1)));
+		assert!(!decide(header(1, 0), header(2, 1)));
+	}
+
+	#[test]
+	fn score_rule_delegates_ties() {
+		// score blocks by the first byte of their state root.
+		let rule = ScoreRule::new(
+			"state_root",
+			|header: &Header| Ok(header.state_root.as_bytes()[0] as u128),
+			LongestChainRule,
+		);
+
+		let decide = |candidate, best| {
+			ForkChoiceRule::<Block>::decide(&rule, &candidate, &best).unwrap().import_as_best
+		};
+
+		assert!(decide(header(1, 2), header(5, 1)));
+		assert!(!decide(header(5, 1), header(1, 2)));
+		assert!(decide(header(2, 1), header(1, 1)));
+		assert!(!decide(header(1, 1), header(2, 1)));
+	}
+
+	#[test]
+	fn finality_respecting_rule_only_switches_to_descendants_of_finalized() {
+		// block tree:
+		// G -> A1 -> A2 -> A3 -> A4 (not imported)
+		//      A1 -> B2 -> B3 (not imported)
+		// G -> C1 -> C2 (not imported)
+		// with A2 finalized.
+		let mut client = TestClientBuilder::new().build();
+
+		let genesis_hash = client.chain_info().genesis_hash;
+		let a1 = build_at(&client, genesis_hash, None);
+		client.import(BlockOrigin::Own, a1.clone()).unwrap();
+		let a2 = build_at(&client, a1.hash(), None);
+		client.import(BlockOrigin::Own, a2.clone()).unwrap();
+		let b2 = build_at(&client, a1.hash(), Some(0));
+		client.import(BlockOrigin::Own, b2.clone()).unwrap();
+		let b3 = build_at(&client, b2.hash(), Some(1));
+		let c1 = build_at(&client, genesis_hash, Some(0));
+		client.import(BlockOrigin::Own, c1.clone()).unwrap();
+		let c2 = build_at(&client, c1.hash(), None);
+
+		client.finalize_block(BlockId::Hash(a2.hash()), None).unwrap();
+
+		let a3 = build_at(&client, a2.hash(), None);
+		client.import(BlockOrigin::Own, a3.clone()).unwrap();
+		let a4 = build_at(&client, a3.hash(), None);
+
+		let client = Arc::new(client);
+		let rule = FinalityRespectingRule::new(client.clone(), LongestChainRule);
+		assert_eq!(ForkChoiceRule::<TestBlock>::name(&rule), "finality_respecting");
+
+		let decide = |candidate: &TestBlock, best: &TestBlock| {
+			rule.decide(&candidate.header, &best.header).unwrap()
+		};
+
+		// children of the finalized block and its descendants are left to the inner rule.
+		assert!(decide(&a3, &a2).import_as_best);
+		assert!(decide(&a4, &a3).import_as_best);
+		assert!(!decide(&a3, &a3).import_as_best);
+
+		// B3 is above the finalized block, but on a fork below it.
+		let decision = decide(&b3, &a2);
+		assert!(!decision.import_as_best);
+		assert!(decision.reason.starts_with("not a descendant of finalized block #2"));
+
+		// C2 is not above the finalized block.
+		let decision = decide(&c2, &a1);
+		assert!(!decision.import_as_best);
+		assert!(decision.reason.starts_with("block number 2 not above finalized block #2"));
+	}
+
+	#[test]
+	fn fork_choice_block_import_applies_rule_to_longest_chain_imports() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let selector = Arc::new(ForkChoiceSelector::new(KeepBestRule, None));
+		let mut block_import = ForkChoiceBlockImport::new(client.clone(), client.clone(), selector);
+
+		let mut import = |block: TestBlock, fork_choice| {
+			let (header, extrinsics) = block.deconstruct();
+			let mut import = BlockImportParams::new(BlockOrigin::Own, header);
+			import.body = Some(extrinsics);
+			import.fork_choice = fork_choice;
+			block_import.import_block(import, HashMap::new()).unwrap()
+		};
+
+		let genesis_hash = client.chain_info().genesis_hash;
+
+		// blocks imported with the longest chain fork choice, or without any, are
+		// decided by the rule.
+		let a1 = build_at(&client, genesis_hash, None);
+		assert!(matches!(
+			import(a1.clone(), Some(ForkChoiceStrategy::LongestChain)),
+			ImportResult::Imported(_),
+		));
+		assert_eq!(client.chain_info().best_hash, genesis_hash);
+
+		let a2 = build_at(&client, a1.hash(), None);
+		import(a2.clone(), None);
+		assert_eq!(client.chain_info().best_hash, genesis_hash);
+
+		// blocks whose fork choice is already decided are passed through.
+		let a3 = build_at(&client, a2.hash(), None);
+		import(a3.clone(), Some(ForkChoiceStrategy::Custom(true)));
+		assert_eq!(client.chain_info().best_hash, a3.hash());
+	}
+}
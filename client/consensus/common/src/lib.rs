@@ -16,5 +16,9 @@
 
 //! Collection of common consensus specific implementations
 mod longest_chain;
+pub mod fork_choice;
 
 pub use longest_chain::LongestChain;
+pub use fork_choice::{
+	ForkChoiceBlockImport, ForkChoiceDecision, ForkChoiceRule, ForkChoiceSelector,
+};
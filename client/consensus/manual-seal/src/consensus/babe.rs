@@ -17,6 +17,11 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! BABE consensus data provider
+//!
+//! Blocks are authored as secondary plain slots by an authority whose key is
+//! in the keystore, so no VRF slot claiming is involved. Epoch changes are
+//! announced by the runtime as usual, as the `SlotTimestampProvider` makes
+//! every sealed block use the slot following the one of the best block.
 
 use super::ConsensusDataProvider;
 use crate::Error;
@@ -24,12 +29,13 @@ use crate::Error;
 use std::{
 	any::Any,
 	borrow::Cow,
-	sync::{Arc, atomic},
+	marker::PhantomData,
+	sync::Arc,
 	time::SystemTime,
 };
 use sc_client_api::AuxStore;
 use sc_consensus_babe::{
-	Config, Epoch, authorship, CompatibleDigestItem, BabeIntermediate,
+	Config, Epoch, CompatibleDigestItem, BabeIntermediate,
 	register_babe_inherent_data_provider, INTERMEDIATE_KEY,
 };
 use sc_consensus_epochs::{SharedEpochChanges, descendent_query};
@@ -37,12 +43,16 @@ use sc_consensus_epochs::{SharedEpochChanges, descendent_query};
 use sp_api::{ProvideRuntimeApi, TransactionFor};
 use sp_blockchain::{HeaderBackend, HeaderMetadata};
 use sp_consensus::BlockImportParams;
-use sp_consensus_babe::{BabeApi, inherents::BabeInherentData};
-use sp_keystore::SyncCryptoStorePtr;
+use sp_consensus_babe::{
+	BabeApi, KEY_TYPE, SlotNumber, inherents::BabeInherentData,
+	digests::{PreDigest, SecondaryPlainPreDigest},
+};
+use sp_core::crypto::Public;
+use sp_keystore::{SyncCryptoStorePtr, SyncCryptoStore};
 use sp_inherents::{InherentDataProviders, InherentData, ProvideInherentData, InherentIdentifier};
 use sp_runtime::{
-	traits::{DigestItemFor, DigestFor, Block as BlockT, Header as _},
-	generic::Digest,
+	traits::{DigestItemFor, DigestFor, Block as BlockT, Header as _, Zero},
+	generic::{Digest, BlockId},
 };
 use sp_timestamp::{InherentType, InherentError, INHERENT_IDENTIFIER};
 
@@ -65,9 +75,11 @@ pub struct BabeConsensusDataProvider<B: BlockT, C> {
 impl<B, C> BabeConsensusDataProvider<B, C>
 	where
 		B: BlockT,
-		C: AuxStore + ProvideRuntimeApi<B>,
+		C: AuxStore + HeaderBackend<B> + ProvideRuntimeApi<B> + Send + Sync + 'static,
 		C::Api: BabeApi<B, Error = sp_blockchain::Error>,
 {
+	/// Create a new BABE consensus data provider, registering the
+	/// `SlotTimestampProvider` and the BABE inherent data provider.
 	pub fn new(
 		client: Arc<C>,
 		keystore: SyncCryptoStorePtr,
@@ -75,7 +87,7 @@ impl<B, C> BabeConsensusDataProvider<B, C>
 		epoch_changes: SharedEpochChanges<B, Epoch>,
 	) -> Result<Self, Error> {
 		let config = Config::get_or_compute(&*client)?;
-		let timestamp_provider = SlotTimestampProvider::new(config.slot_duration)?;
+		let timestamp_provider = SlotTimestampProvider::new(client.clone())?;
 
 		provider.register_provider(timestamp_provider)?;
 		register_babe_inherent_data_provider(provider, config.slot_duration)?;
@@ -121,9 +133,21 @@ impl<B, C> ConsensusDataProvider<B> for BabeConsensusDataProvider<B, C>
 				sp_consensus::Error::InvalidAuthoritiesSet
 			})?;
 
-		// this is a dev node environment, we should always be able to claim a slot.
-		let (predigest, _) = authorship::claim_slot(slot_number, epoch.as_ref(), &self.keystore)
-			.ok_or_else(|| Error::StringError("failed to claim slot for authorship".into()))?;
+		// author a secondary plain slot with the first authority of the epoch we have a key for,
+		// the runtime does not check that the slot was assigned to it.
+		let authority_index = epoch.as_ref().authorities.iter()
+			.position(|(authority, _)| SyncCryptoStore::has_keys(
+				&*self.keystore,
+				&[(authority.to_raw_vec(), KEY_TYPE)],
+			))
+			.ok_or_else(|| Error::StringError(
+				"no key for any of the epoch authorities in the keystore".into(),
+			))?;
+
+		let predigest = PreDigest::SecondaryPlain(SecondaryPlainPreDigest {
+			authority_index: authority_index as u32,
+			slot_number,
+		});
 
 		Ok(Digest {
 			logs: vec![
@@ -159,35 +183,75 @@ impl<B, C> ConsensusDataProvider<B> for BabeConsensusDataProvider<B, C>
 	}
 }
 
-/// Provide duration since unix epoch in millisecond for timestamp inherent.
-/// Mocks the timestamp inherent to always produce the timestamp for the next babe slot.
-struct SlotTimestampProvider {
-	time: atomic::AtomicU64,
-	slot_duration: u64
+/// Provides the timestamp inherent at the start of the slot following the slot
+/// of the best block, so that time advances by exactly one slot per sealed block
+/// regardless of the wall clock. The first block uses the slot that was current
+/// when the provider was created.
+///
+/// The BABE slot inherent is derived from the timestamp, so both stay consistent
+/// with the slots checked by the runtime.
+pub struct SlotTimestampProvider<B, C> {
+	client: Arc<C>,
+	slot_duration: u64,
+	genesis_slot: SlotNumber,
+	_phantom: PhantomData<B>,
 }
 
-impl SlotTimestampProvider {
-	/// create a new mocked time stamp provider.
-	fn new(slot_duration: u64) -> Result<Self, Error> {
-		let now = SystemTime::now();
-		let duration = now.duration_since(SystemTime::UNIX_EPOCH)
+impl<B, C> SlotTimestampProvider<B, C>
+	where
+		B: BlockT,
+		C: AuxStore + HeaderBackend<B> + ProvideRuntimeApi<B>,
+		C::Api: BabeApi<B, Error = sp_blockchain::Error>,
+{
+	/// Create a new mocked timestamp provider.
+	pub fn new(client: Arc<C>) -> Result<Self, Error> {
+		let slot_duration = Config::get_or_compute(&*client)?.slot_duration;
+		let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)
 			.map_err(|err| Error::StringError(format!("{}", err)))?;
+
 		Ok(Self {
-			time: atomic::AtomicU64::new(duration.as_millis() as u64),
+			client,
 			slot_duration,
+			genesis_slot: now.as_millis() as u64 / slot_duration,
+			_phantom: PhantomData,
 		})
 	}
+
+	/// The slot of the next block to be sealed on top of the best block.
+	fn next_slot(&self) -> Result<SlotNumber, Error> {
+		let info = self.client.info();
+		if info.best_number.is_zero() {
+			return Ok(self.genesis_slot);
+		}
+
+		let header = self.client.header(BlockId::Hash(info.best_hash))?
+			.ok_or_else(|| sp_blockchain::Error::UnknownBlock(format!("{}", info.best_hash)))?;
+		let slot_number = header.digest().logs().iter()
+			.find_map(|log| log.as_babe_pre_digest())
+			.map(|pre_digest| pre_digest.slot_number())
+			.ok_or_else(|| Error::StringError(
+				format!("best block {} has no BABE pre-digest", info.best_hash),
+			))?;
+
+		Ok(slot_number + 1)
+	}
 }
 
-impl ProvideInherentData for SlotTimestampProvider {
+impl<B, C> ProvideInherentData for SlotTimestampProvider<B, C>
+	where
+		B: BlockT,
+		C: AuxStore + HeaderBackend<B> + ProvideRuntimeApi<B> + Send + Sync,
+		C::Api: BabeApi<B, Error = sp_blockchain::Error>,
+{
 	fn inherent_identifier(&self) -> &'static InherentIdentifier {
 		&INHERENT_IDENTIFIER
 	}
 
 	fn provide_inherent_data(&self, inherent_data: &mut InherentData) -> Result<(), sp_inherents::Error> {
-		// we update the time here.
-		let duration: InherentType = self.time.fetch_add(self.slot_duration, atomic::Ordering::SeqCst);
-		inherent_data.put_data(INHERENT_IDENTIFIER, &duration)?;
+		let slot_number = self.next_slot()
+			.map_err(|e| sp_inherents::Error::from(format!("{}", e)))?;
+		let timestamp: InherentType = slot_number * self.slot_duration;
+		inherent_data.put_data(INHERENT_IDENTIFIER, &timestamp)?;
 		Ok(())
 	}
 
@@ -195,3 +259,226 @@ impl ProvideInherentData for SlotTimestampProvider {
 		InherentError::try_from(&INHERENT_IDENTIFIER, error).map(|e| format!("{:?}", e))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{seal_block, SealBlockParams};
+	use codec::Encode;
+	use futures::prelude::*;
+	use sc_basic_authorship::ProposerFactory;
+	use sc_client_api::BlockBackend;
+	use sc_consensus_babe::Epoch;
+	use sc_keystore::LocalKeystore;
+	use sc_transaction_pool::{BasicPool, RevalidationType, txpool::Options};
+	use sp_consensus::{Environment, Proposal, Proposer, RecordProof};
+	use sp_consensus_babe::{BABE_ENGINE_ID, ConsensusLog, digests::NextEpochDescriptor};
+	use sp_timestamp::TimestampInherentData;
+	use sp_transaction_pool::{ChainEvent, MaintainedTransactionPool};
+	use std::{pin::Pin, time::Duration};
+	use substrate_test_runtime_client::{
+		DefaultTestClientBuilderExt, Sr25519Keyring, TestClient, TestClientBuilder,
+		TestClientBuilderExt,
+		runtime::{Block, Header},
+	};
+	use substrate_test_runtime_transaction_pool::TestApi;
+
+	/// Adds the epoch change digest to the first block of every epoch, since the
+	/// test runtime doesn't announce epoch changes by itself.
+	struct AnnounceEpochChanges<E> {
+		inner: E,
+		client: Arc<TestClient>,
+		epoch_changes: SharedEpochChanges<Block, Epoch>,
+		config: Config,
+	}
+
+	struct AnnounceEpochChangesProposer<P> {
+		inner: P,
+		parent: Header,
+		client: Arc<TestClient>,
+		epoch_changes: SharedEpochChanges<Block, Epoch>,
+		config: Config,
+	}
+
+	impl<E: Environment<Block>> Environment<Block> for AnnounceEpochChanges<E> {
+		type Proposer = AnnounceEpochChangesProposer<E::Proposer>;
+		type CreateProposer = Pin<Box<
+			dyn Future<Output = Result<Self::Proposer, Self::Error>> + Send + 'static
+		>>;
+		type Error = E::Error;
+
+		fn init(&mut self, parent_header: &Header) -> Self::CreateProposer {
+			let parent = parent_header.clone();
+			let client = self.client.clone();
+			let epoch_changes = self.epoch_changes.clone();
+			let config = self.config.clone();
+
+			Box::pin(self.inner.init(parent_header).map_ok(move |inner| AnnounceEpochChangesProposer {
+				inner,
+				parent,
+				client,
+				epoch_changes,
+				config,
+			}))
+		}
+	}
+
+	impl<P: Proposer<Block>> Proposer<Block> for AnnounceEpochChangesProposer<P> {
+		type Error = P::Error;
+		type Transaction = P::Transaction;
+		type Proposal = Pin<Box<
+			dyn Future<Output = Result<Proposal<Block, P::Transaction>, P::Error>> + Send + 'static
+		>>;
+
+		fn propose(
+			self,
+			inherent_data: InherentData,
+			inherent_digests: DigestFor<Block>,
+			max_duration: Duration,
+			record_proof: RecordProof,
+		) -> Self::Proposal {
+			let Self { inner, parent, client, epoch_changes, config } = self;
+			let slot_of = |header: &Header| header.digest().logs().iter()
+				.find_map(|log| log.as_babe_pre_digest())
+				.map(|pre_digest| pre_digest.slot_number());
+
+			Box::pin(inner.propose(inherent_data, inherent_digests, max_duration, record_proof)
+				.map_ok(move |mut proposal| {
+					let slot_number = slot_of(proposal.block.header())
+						.expect("manual seal adds a BABE pre-digest; qed");
+					let epoch = epoch_changes.lock()
+						.epoch_data_for_child_of(
+							descendent_query(&*client),
+							&parent.hash(),
+							*parent.number(),
+							slot_number,
+							|slot| Epoch::genesis(&config, slot),
+						)
+						.expect("client has data to find epoch")
+						.expect("can compute epoch for sealed block");
+
+					// the genesis block has no pre-digest.
+					if slot_of(&parent).unwrap_or(0) < epoch.start_slot {
+						let log = ConsensusLog::NextEpochData(NextEpochDescriptor {
+							authorities: epoch.authorities.clone(),
+							randomness: epoch.randomness,
+						});
+						proposal.block.header.digest_mut()
+							.push(DigestItemFor::<Block>::Consensus(BABE_ENGINE_ID, log.encode()));
+					}
+
+					proposal
+				}))
+		}
+	}
+
+	#[test]
+	fn timestamp_only_advances_with_sealed_blocks() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let provider = SlotTimestampProvider::new(client).unwrap();
+
+		let timestamp = || {
+			let mut inherent_data = InherentData::new();
+			provider.provide_inherent_data(&mut inherent_data).unwrap();
+			inherent_data.timestamp_inherent_data().unwrap()
+		};
+
+		let first = timestamp();
+		assert_eq!(first, provider.genesis_slot * provider.slot_duration);
+		assert_eq!(timestamp(), first);
+	}
+
+	#[test]
+	fn seals_blocks_across_epoch_changes() {
+		let (client, select_chain) = TestClientBuilder::new().build_with_longest_chain();
+		let client = Arc::new(client);
+		let config = Config::get_or_compute(&*client).unwrap();
+		let (mut block_import, link) = sc_consensus_babe::block_import(
+			config.clone(),
+			client.clone(),
+			client.clone(),
+		).unwrap();
+
+		let keystore: SyncCryptoStorePtr = Arc::new(LocalKeystore::in_memory());
+		SyncCryptoStore::sr25519_generate_new(
+			&*keystore,
+			KEY_TYPE,
+			Some(&Sr25519Keyring::Alice.to_seed()),
+		).unwrap();
+
+		let inherent_data_providers = InherentDataProviders::new();
+		let consensus_data_provider = BabeConsensusDataProvider::new(
+			client.clone(),
+			keystore,
+			&inherent_data_providers,
+			link.epoch_changes().clone(),
+		).unwrap();
+
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let pool_api = Arc::new(TestApi::empty());
+		let pool = Arc::new(BasicPool::with_revalidation_type(
+			Options::default(), pool_api.clone(), None, RevalidationType::Full, spawner.clone(),
+		));
+		let mut env = AnnounceEpochChanges {
+			inner: ProposerFactory::new(spawner, client.clone(), pool.clone(), None),
+			client: client.clone(),
+			epoch_changes: link.epoch_changes().clone(),
+			config: config.clone(),
+		};
+
+		// seal blocks up to the first slot of the third epoch.
+		let blocks = 2 * config.epoch_length + 1;
+		for number in 1..=blocks {
+			let (sender, receiver) = futures::channel::oneshot::channel();
+			futures::executor::block_on(seal_block(SealBlockParams {
+				create_empty: true,
+				finalize: false,
+				parent_hash: None,
+				sender: Some(sender),
+				pool: pool.pool().clone(),
+				client: client.clone(),
+				env: &mut env,
+				select_chain: &select_chain,
+				consensus_data_provider: Some(&consensus_data_provider),
+				block_import: &mut block_import,
+				inherent_data_provider: &inherent_data_providers,
+			}));
+			let created_block = futures::executor::block_on(receiver).unwrap().unwrap();
+			assert_eq!(client.info().best_number, number);
+
+			let block = client.block(&BlockId::Hash(created_block.hash)).unwrap().unwrap().block;
+			pool_api.add_block(block, true);
+			futures::executor::block_on(pool.maintain(ChainEvent::NewBestBlock {
+				hash: created_block.hash,
+				tree_route: None,
+			}));
+		}
+
+		let slot_of = |number| client.header(BlockId::Number(number)).unwrap().unwrap()
+			.digest().logs().iter()
+			.find_map(|log| log.as_babe_pre_digest())
+			.unwrap()
+			.slot_number();
+
+		// every block uses the slot following the one of its parent.
+		let first_slot = slot_of(1);
+		for number in 2..=blocks {
+			assert_eq!(slot_of(number), first_slot + number - 1);
+		}
+
+		// and the epoch changes were imported along.
+		let best_hash = client.info().best_hash;
+		let epoch = link.epoch_changes().lock()
+			.epoch_data_for_child_of(
+				descendent_query(&*client),
+				&best_hash,
+				blocks,
+				slot_of(blocks) + 1,
+				|slot| Epoch::genesis(&config, slot),
+			)
+			.unwrap()
+			.unwrap();
+		assert_eq!(epoch.epoch_index, 2);
+		assert_eq!(epoch.start_slot, first_slot + 2 * config.epoch_length);
+	}
+}
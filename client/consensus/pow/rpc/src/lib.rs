@@ -0,0 +1,254 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Mining RPC and Stratum server for PoW.
+//!
+//! Both expose the work packages of a `MiningWorker` to external miners and
+//! accept their seals. For chains mined with several algorithms, miners select
+//! the algorithm they mine with by name, and their seals are wrapped in a
+//! `MultiAlgoSeal` before being submitted.
+
+#![warn(missing_docs)]
+
+pub mod stratum;
+
+use std::sync::Arc;
+
+use codec::Encode;
+use jsonrpc_derive::rpc;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sc_consensus_pow::{MiningMetadata, MiningWorker, PowAlgorithm};
+use sc_rpc_api::DenyUnsafe;
+use sp_consensus_pow::{MultiAlgoSeal, PowAlgorithmId, Seal};
+use sp_core::Bytes;
+use sp_runtime::traits::Block as BlockT;
+
+pub use self::gen_client::Client as PowClient;
+
+/// Error code for rpc
+mod codes {
+	pub const NO_WORK: i64 = 20_000;
+	pub const STALE_WORK: i64 = 20_001;
+	pub const INVALID_ALGORITHM: i64 = 20_002;
+}
+
+/// Mining errors.
+#[derive(Debug, derive_more::Display)]
+pub enum Error {
+	/// No work package is available, e.g. because the node is syncing.
+	#[display(fmt = "No work available")]
+	NoWork,
+	/// The submitted work is not for the current work package.
+	#[display(fmt = "Work is stale")]
+	StaleWork,
+	/// The algorithm name is not a valid algorithm id.
+	#[display(fmt = "Invalid algorithm name: {}", _0)]
+	InvalidAlgorithm(String),
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+	fn to_code(&self) -> i64 {
+		match self {
+			Error::NoWork => codes::NO_WORK,
+			Error::StaleWork => codes::STALE_WORK,
+			Error::InvalidAlgorithm(_) => codes::INVALID_ALGORITHM,
+		}
+	}
+}
+
+impl From<Error> for jsonrpc_core::Error {
+	fn from(error: Error) -> Self {
+		jsonrpc_core::Error {
+			code: jsonrpc_core::ErrorCode::ServerError(error.to_code()),
+			message: error.to_string(),
+			data: None,
+		}
+	}
+}
+
+/// Parse an algorithm name of up to 8 ASCII characters into an algorithm id.
+///
+/// Shorter names are right-padded with zeroes.
+pub fn parse_algorithm_id(name: &str) -> Result<PowAlgorithmId, Error> {
+	if name.is_empty() || name.len() > 8 || !name.is_ascii() {
+		return Err(Error::InvalidAlgorithm(name.into()));
+	}
+
+	let mut id = PowAlgorithmId::default();
+	id[..name.len()].copy_from_slice(name.as_bytes());
+	Ok(id)
+}
+
+/// A work package for external miners.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Work<Hash, Difficulty> {
+	/// Currently known best hash which the pre-hash is built on.
+	pub best_hash: Hash,
+	/// Mining pre-hash.
+	pub pre_hash: Hash,
+	/// Pre-runtime digest item.
+	pub pre_runtime: Option<Bytes>,
+	/// Mining target difficulty.
+	pub difficulty: Difficulty,
+}
+
+impl<Hash, Difficulty> From<MiningMetadata<Hash, Difficulty>> for Work<Hash, Difficulty> {
+	fn from(metadata: MiningMetadata<Hash, Difficulty>) -> Self {
+		Work {
+			best_hash: metadata.best_hash,
+			pre_hash: metadata.pre_hash,
+			pre_runtime: metadata.pre_runtime.map(Into::into),
+			difficulty: metadata.difficulty,
+		}
+	}
+}
+
+/// A shared mining worker, as returned by `sc_consensus_pow::start_mining_worker`.
+pub type SharedMiningWorker<Block, Algorithm, C> = Arc<Mutex<MiningWorker<Block, Algorithm, C>>>;
+
+/// Get the current work package of the worker, for the given algorithm if any.
+fn current_work<Block, Algorithm, C>(
+	worker: &MiningWorker<Block, Algorithm, C>,
+	algorithm: Option<&PowAlgorithmId>,
+) -> Option<MiningMetadata<Block::Hash, Algorithm::Difficulty>> where
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block>,
+	Algorithm::Difficulty: 'static,
+	C: sp_api::ProvideRuntimeApi<Block>,
+{
+	match algorithm {
+		Some(algorithm) => worker.metadata_for(algorithm),
+		None => worker.metadata(),
+	}
+}
+
+/// Submit a seal for the work package with the given pre-hash.
+///
+/// Returns true if the mined block was imported.
+fn submit_work<Block, Algorithm, C>(
+	worker: &mut MiningWorker<Block, Algorithm, C>,
+	pre_hash: Block::Hash,
+	seal: Seal,
+	algorithm: Option<PowAlgorithmId>,
+) -> Result<bool, Error> where
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block>,
+	Algorithm::Difficulty: 'static,
+	C: sp_api::ProvideRuntimeApi<Block>,
+{
+	match worker.metadata() {
+		Some(metadata) if metadata.pre_hash == pre_hash => {},
+		Some(_) => return Err(Error::StaleWork),
+		None => return Err(Error::NoWork),
+	}
+
+	let seal = match algorithm {
+		Some(algorithm) => MultiAlgoSeal { algorithm, seal }.encode(),
+		None => seal,
+	};
+
+	Ok(worker.submit(seal))
+}
+
+/// Provides rpc methods for external PoW miners.
+#[rpc]
+pub trait PowApi<Hash, Difficulty> {
+	/// Returns the current work package, for the given algorithm on chains
+	/// mined with several algorithms. `None` if the node is not ready to mine.
+	#[rpc(name = "pow_getWork")]
+	fn get_work(&self, algorithm: Option<String>) -> jsonrpc_core::Result<Option<Work<Hash, Difficulty>>>;
+
+	/// Submits a seal for the work package with the given pre-hash, produced by
+	/// the given algorithm on chains mined with several algorithms. Returns
+	/// true if the mined block was imported.
+	///
+	/// This is an unsafe RPC, only allowed for local connections by default.
+	#[rpc(name = "pow_submitWork")]
+	fn submit_work(
+		&self,
+		pre_hash: Hash,
+		seal: Bytes,
+		algorithm: Option<String>,
+	) -> jsonrpc_core::Result<bool>;
+}
+
+/// Implements the PowApi rpc trait for interacting with a mining worker.
+pub struct PowRpcHandler<Block: BlockT, Algorithm: PowAlgorithm<Block>, C: sp_api::ProvideRuntimeApi<Block>> {
+	worker: SharedMiningWorker<Block, Algorithm, C>,
+	deny_unsafe: DenyUnsafe,
+}
+
+impl<Block, Algorithm, C> PowRpcHandler<Block, Algorithm, C> where
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block>,
+	C: sp_api::ProvideRuntimeApi<Block>,
+{
+	/// Creates a new instance of the PoW rpc handler.
+	pub fn new(worker: SharedMiningWorker<Block, Algorithm, C>, deny_unsafe: DenyUnsafe) -> Self {
+		Self { worker, deny_unsafe }
+	}
+}
+
+impl<Block, Algorithm, C> PowApi<Block::Hash, Algorithm::Difficulty> for PowRpcHandler<Block, Algorithm, C> where
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block> + Send + 'static,
+	Algorithm::Difficulty: Serialize + Send + Sync + 'static,
+	C: sp_api::ProvideRuntimeApi<Block> + Send + 'static,
+	sp_api::TransactionFor<C, Block>: Send,
+{
+	fn get_work(
+		&self,
+		algorithm: Option<String>,
+	) -> jsonrpc_core::Result<Option<Work<Block::Hash, Algorithm::Difficulty>>> {
+		let algorithm = algorithm.as_deref().map(parse_algorithm_id).transpose()?;
+		let worker = self.worker.lock();
+
+		Ok(current_work(&worker, algorithm.as_ref()).map(Into::into))
+	}
+
+	fn submit_work(
+		&self,
+		pre_hash: Block::Hash,
+		seal: Bytes,
+		algorithm: Option<String>,
+	) -> jsonrpc_core::Result<bool> {
+		self.deny_unsafe.check_if_safe()?;
+		let algorithm = algorithm.as_deref().map(parse_algorithm_id).transpose()?;
+		let mut worker = self.worker.lock();
+
+		submit_work(&mut worker, pre_hash, seal.0, algorithm).map_err(Into::into)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_algorithm_ids() {
+		assert_eq!(parse_algorithm_id("sha3").unwrap(), *b"sha3\0\0\0\0");
+		assert_eq!(parse_algorithm_id("blake2bx").unwrap(), *b"blake2bx");
+		assert!(parse_algorithm_id("").is_err());
+		assert!(parse_algorithm_id("too-long-name").is_err());
+		assert!(parse_algorithm_id("näme").is_err());
+	}
+}
@@ -0,0 +1,334 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Stratum v1 server.
+//!
+//! Miners connect over TCP and exchange newline-delimited JSON-RPC messages:
+//!
+//! - `mining.subscribe` subscribes the connection to new jobs;
+//! - `mining.authorize` is accepted for any worker name;
+//! - `mining.submit` with `[worker, job_id, seal]` submits a hex-encoded seal.
+//!
+//! Subscribed connections receive `mining.set_difficulty` with `[difficulty]`
+//! followed by `mining.notify` with `[job_id, pre_hash, best_hash, pre_runtime,
+//! clean_jobs]` whenever the work package changes. The job id is the
+//! hex-encoded pre-hash.
+//!
+//! Request lines are limited to `MAX_LINE_LENGTH` bytes and the number of
+//! connections is limited by `StratumConfig::max_connections`.
+
+use std::{
+	io,
+	net::SocketAddr,
+	sync::{Arc, atomic::{AtomicUsize, Ordering}},
+	time::Duration,
+};
+
+use async_std::{
+	io::{BufRead, BufReader},
+	net::{TcpListener, TcpStream},
+	prelude::*,
+};
+use futures::future;
+use futures_timer::Delay;
+use log::{debug, info, warn};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sc_consensus_pow::PowAlgorithm;
+use sp_consensus_pow::PowAlgorithmId;
+use sp_runtime::traits::Block as BlockT;
+
+use crate::{current_work, submit_work, SharedMiningWorker, Error};
+
+/// Maximum length of a request line, in bytes.
+const MAX_LINE_LENGTH: usize = 16 * 1024;
+
+/// Configuration of the Stratum server.
+#[derive(Clone, Debug)]
+pub struct StratumConfig {
+	/// Address to listen on.
+	pub listen_addr: SocketAddr,
+	/// Algorithm mined by the connected miners, on chains mined with several algorithms.
+	pub algorithm: Option<PowAlgorithmId>,
+	/// Interval at which connections check for a new work package.
+	pub poll_interval: Duration,
+	/// Maximum number of miners connected at the same time.
+	pub max_connections: usize,
+}
+
+/// A request received from a miner.
+#[derive(Debug, PartialEq)]
+enum Request {
+	Subscribe,
+	Authorize,
+	Submit { job_id: String, seal: Vec<u8> },
+}
+
+/// Parse a Stratum request, returning its id and the request.
+fn parse_request(line: &str) -> Result<(Value, Request), String> {
+	let message: Value = serde_json::from_str(line)
+		.map_err(|e| format!("Invalid JSON: {}", e))?;
+	let id = message.get("id").cloned().unwrap_or(Value::Null);
+	let method = message.get("method").and_then(Value::as_str)
+		.ok_or_else(|| "Missing method".to_string())?;
+	let params = message.get("params").and_then(Value::as_array).cloned().unwrap_or_default();
+
+	let request = match method {
+		"mining.subscribe" => Request::Subscribe,
+		"mining.authorize" => Request::Authorize,
+		"mining.submit" => {
+			let param = |index: usize| params.get(index).and_then(Value::as_str)
+				.ok_or_else(|| format!("Missing parameter {}", index));
+			let job_id = param(1)?.to_string();
+			let seal = sp_core::bytes::from_hex(param(2)?)
+				.map_err(|e| format!("Invalid seal: {}", e))?;
+
+			Request::Submit { job_id, seal }
+		},
+		method => return Err(format!("Unknown method {}", method)),
+	};
+
+	Ok((id, request))
+}
+
+fn response(id: Value, result: Value, error: Value) -> String {
+	json!({ "id": id, "result": result, "error": error }).to_string()
+}
+
+fn notification(method: &str, params: Value) -> String {
+	json!({ "id": Value::Null, "method": method, "params": params }).to_string()
+}
+
+/// Read the next line, without its line ending.
+///
+/// The bytes read so far are kept in `buffer`, so that the future can be dropped
+/// and the line resumed by the next call. Returns `None` once the miner closed the
+/// connection.
+async fn read_line<R: BufRead + Unpin>(
+	reader: &mut R,
+	buffer: &mut Vec<u8>,
+) -> io::Result<Option<String>> {
+	// one byte past the maximum, to tell a line that is too long from one that fits.
+	let limit = (MAX_LINE_LENGTH + 1).saturating_sub(buffer.len()) as u64;
+	let read = (&mut *reader).take(limit).read_until(b'\n', buffer).await?;
+
+	if read == 0 && buffer.is_empty() {
+		return Ok(None)
+	}
+
+	if buffer.len() > MAX_LINE_LENGTH {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("Request longer than {} bytes", MAX_LINE_LENGTH),
+		))
+	}
+
+	let mut line = String::from_utf8(std::mem::take(buffer))
+		.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+	if line.ends_with('\n') {
+		line.pop();
+		if line.ends_with('\r') {
+			line.pop();
+		}
+	}
+
+	Ok(Some(line))
+}
+
+fn job_id<Hash: AsRef<[u8]>>(pre_hash: &Hash) -> String {
+	sp_core::bytes::to_hex(pre_hash.as_ref(), false)
+}
+
+/// Run the Stratum server until an I/O error occurs while accepting connections.
+pub async fn run_stratum_server<Block, Algorithm, C>(
+	config: StratumConfig,
+	worker: SharedMiningWorker<Block, Algorithm, C>,
+) -> io::Result<()> where
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block> + Send + 'static,
+	Algorithm::Difficulty: Serialize + Send + Sync + 'static,
+	C: sp_api::ProvideRuntimeApi<Block> + Send + 'static,
+	sp_api::TransactionFor<C, Block>: Send,
+{
+	let listener = TcpListener::bind(&config.listen_addr).await?;
+	info!(target: "stratum", "Stratum server started at {}", config.listen_addr);
+
+	let connections = Arc::new(AtomicUsize::new(0));
+	let mut incoming = listener.incoming();
+	while let Some(stream) = incoming.next().await {
+		let stream = stream?;
+		let peer = stream.peer_addr().ok();
+
+		if connections.fetch_add(1, Ordering::SeqCst) >= config.max_connections {
+			connections.fetch_sub(1, Ordering::SeqCst);
+			debug!(
+				target: "stratum",
+				"Rejected connection from {:?}: {} miners already connected",
+				peer,
+				config.max_connections,
+			);
+			continue
+		}
+
+		let connection = handle_connection(stream, config.clone(), worker.clone());
+		let connections = connections.clone();
+
+		async_std::task::spawn(async move {
+			if let Err(e) = connection.await {
+				debug!(target: "stratum", "Connection with {:?} closed: {}", peer, e);
+			}
+			connections.fetch_sub(1, Ordering::SeqCst);
+		});
+	}
+
+	Ok(())
+}
+
+async fn handle_connection<Block, Algorithm, C>(
+	stream: TcpStream,
+	config: StratumConfig,
+	worker: SharedMiningWorker<Block, Algorithm, C>,
+) -> io::Result<()> where
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block> + Send + 'static,
+	Algorithm::Difficulty: Serialize + 'static,
+	C: sp_api::ProvideRuntimeApi<Block> + Send + 'static,
+	sp_api::TransactionFor<C, Block>: Send,
+{
+	let mut reader = BufReader::new(&stream);
+	let mut buffer = Vec::new();
+	let mut writer = &stream;
+	let mut subscribed = false;
+	let mut last_job = None;
+
+	loop {
+		let next_line = future::select(
+			Box::pin(read_line(&mut reader, &mut buffer)),
+			Delay::new(config.poll_interval),
+		);
+
+		if let future::Either::Left((line, _)) = next_line.await {
+			let line = match line? {
+				Some(line) => line,
+				None => return Ok(()),
+			};
+
+			let reply = match parse_request(&line) {
+				Ok((id, Request::Subscribe)) => {
+					subscribed = true;
+					response(id, json!([[["mining.notify", "0"]], "", 0]), Value::Null)
+				},
+				Ok((id, Request::Authorize)) => response(id, json!(true), Value::Null),
+				Ok((id, Request::Submit { job_id: submitted, seal })) => {
+					// Verifying and importing the block is synchronous, keep it and the
+					// worker lock off the async executor.
+					let worker = worker.clone();
+					let algorithm = config.algorithm.clone();
+					let result = async_std::task::spawn_blocking(move || {
+						let mut worker = worker.lock();
+						match current_work(&worker, algorithm.as_ref()) {
+							Some(work) if job_id(&work.pre_hash) == submitted =>
+								submit_work(&mut worker, work.pre_hash, seal, algorithm),
+							Some(_) => Err(Error::StaleWork),
+							None => Err(Error::NoWork),
+						}
+					}).await;
+
+					match result {
+						Ok(imported) => response(id, json!(imported), Value::Null),
+						Err(e) => response(id, json!(false), json!([21, e.to_string(), null])),
+					}
+				},
+				Err(e) => {
+					warn!(target: "stratum", "Invalid request: {}", e);
+					response(Value::Null, Value::Null, json!([20, e, null]))
+				},
+			};
+
+			writer.write_all(format!("{}\n", reply).as_bytes()).await?;
+		}
+
+		if !subscribed {
+			continue
+		}
+
+		let work = current_work(&worker.lock(), config.algorithm.as_ref());
+		if let Some(work) = work {
+			if last_job.as_ref() == Some(&work.pre_hash) {
+				continue
+			}
+
+			let difficulty = notification("mining.set_difficulty", json!([work.difficulty]));
+			let notify = notification("mining.notify", json!([
+				job_id(&work.pre_hash),
+				job_id(&work.pre_hash),
+				job_id(&work.best_hash),
+				work.pre_runtime.as_ref().map(|p| sp_core::bytes::to_hex(p, false)),
+				true,
+			]));
+
+			writer.write_all(format!("{}\n{}\n", difficulty, notify).as_bytes()).await?;
+			last_job = Some(work.pre_hash);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_requests() {
+		let (id, request) = parse_request(
+			r#"{"id": 1, "method": "mining.subscribe", "params": ["miner/1.0"]}"#,
+		).unwrap();
+		assert_eq!(id, json!(1));
+		assert_eq!(request, Request::Subscribe);
+
+		let (_, request) = parse_request(
+			r#"{"id": 2, "method": "mining.submit", "params": ["worker", "0x01", "0x0203"]}"#,
+		).unwrap();
+		assert_eq!(request, Request::Submit { job_id: "0x01".into(), seal: vec![2, 3] });
+
+		assert!(parse_request(r#"{"id": 3, "method": "mining.submit", "params": []}"#).is_err());
+		assert!(parse_request(r#"{"id": 4, "method": "mining.unknown"}"#).is_err());
+		assert!(parse_request("not json").is_err());
+	}
+
+	#[test]
+	fn reads_bounded_lines() {
+		let long_line = format!("{}\n", "a".repeat(MAX_LINE_LENGTH + 1));
+		let input = format!("first\r\nsecond\n{}", long_line);
+		let mut reader = input.as_bytes();
+		let mut buffer = Vec::new();
+
+		futures::executor::block_on(async {
+			assert_eq!(read_line(&mut reader, &mut buffer).await.unwrap(), Some("first".into()));
+			assert_eq!(read_line(&mut reader, &mut buffer).await.unwrap(), Some("second".into()));
+			assert!(read_line(&mut reader, &mut buffer).await.is_err());
+		});
+
+		let mut reader = &b"last"[..];
+		let mut buffer = Vec::new();
+
+		futures::executor::block_on(async {
+			assert_eq!(read_line(&mut reader, &mut buffer).await.unwrap(), Some("last".into()));
+			assert_eq!(read_line(&mut reader, &mut buffer).await.unwrap(), None);
+		});
+	}
+}
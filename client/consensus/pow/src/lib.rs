@@ -23,7 +23,17 @@
 //! with other necessary client references to `import_queue` to setup
 //! the queue. Use the `start_mine` function for basic CPU mining.
 //!
-//! The auxiliary storage for PoW engine only stores the total difficulty.
+//! By default, the block with the highest total difficulty is chosen as the
+//! best block. A different fork-choice rule can be configured with
+//! `PowBlockImport::with_fork_choice`, and `TotalDifficultyRule` allows
+//! composing the default rule with others (e.g. to respect finality).
+//!
+//! Chains can be mined with several algorithms by using a `MultiAlgorithm`,
+//! which keys each seal by the id of the algorithm that produced it. The
+//! difficulty of each algorithm is then tracked separately.
+//!
+//! The auxiliary storage for PoW engine only stores the total difficulty and
+//! the latest difficulty of each algorithm.
 //! For other storage requirements for particular PoW algorithm (such as
 //! the actual difficulty for each particular blocks), you can take a client
 //! reference in your `PowAlgorithm` implementation, and use a separate prefix
@@ -31,12 +41,14 @@
 //! as the storage, but it is not recommended as it won't work well with light
 //! clients.
 
+mod multi;
 mod worker;
 
+pub use crate::multi::MultiAlgorithm;
 pub use crate::worker::{MiningWorker, MiningMetadata, MiningBuild};
 
 use std::{
-	sync::Arc, any::Any, borrow::Cow, collections::HashMap, marker::PhantomData,
+	sync::Arc, any::Any, borrow::Cow, collections::{BTreeMap, HashMap}, marker::PhantomData,
 	cmp::Ordering, time::Duration,
 };
 use futures::{prelude::*, future::Either};
@@ -48,7 +60,8 @@ use sp_runtime::{Justification, RuntimeString};
 use sp_runtime::generic::{BlockId, Digest, DigestItem};
 use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
 use sp_api::ProvideRuntimeApi;
-use sp_consensus_pow::{Seal, TotalDifficulty, POW_ENGINE_ID};
+use sp_consensus_pow::{Seal, TotalDifficulty, PowAlgorithmId, POW_ENGINE_ID};
+use sc_consensus::{ForkChoiceDecision, ForkChoiceRule, ForkChoiceSelector};
 use sp_inherents::{InherentDataProviders, InherentData};
 use sp_consensus::{
 	BlockImportParams, BlockOrigin, ForkChoiceStrategy, SyncOracle, Environment, Proposer,
@@ -96,6 +109,8 @@ pub enum Error<B: BlockT> {
 	CheckInherents(String),
 	#[display(fmt = "Multiple pre-runtime digests")]
 	MultiplePreRuntimeDigests,
+	#[display(fmt = "Unknown PoW algorithm {:?}", _0)]
+	UnknownAlgorithm(PowAlgorithmId),
 	Client(sp_blockchain::Error),
 	Codec(codec::Error),
 	Environment(String),
@@ -134,16 +149,34 @@ pub struct PowIntermediate<Difficulty> {
 pub static INTERMEDIATE_KEY: &[u8] = b"pow1";
 
 /// Auxiliary storage data for PoW.
-#[derive(Encode, Decode, Clone, Debug, Default)]
+#[derive(Encode, Clone, Debug, Default)]
 pub struct PowAux<Difficulty> {
 	/// Difficulty of the current block.
 	pub difficulty: Difficulty,
 	/// Total difficulty up to current block.
 	pub total_difficulty: Difficulty,
+	/// Difficulty of the latest block sealed by each algorithm, up to current
+	/// block. Empty for chains mined with a single algorithm.
+	pub algorithm_difficulties: BTreeMap<PowAlgorithmId, Difficulty>,
+}
+
+// Entries written before multiple algorithms were supported end after the total
+// difficulty, so the algorithm difficulties are decoded only if present.
+impl<Difficulty: Decode + Ord> Decode for PowAux<Difficulty> {
+	fn decode<I: codec::Input>(input: &mut I) -> Result<Self, codec::Error> {
+		let difficulty = Difficulty::decode(input)?;
+		let total_difficulty = Difficulty::decode(input)?;
+		let algorithm_difficulties = match input.remaining_len()? {
+			Some(0) => BTreeMap::new(),
+			_ => Decode::decode(input)?,
+		};
+
+		Ok(PowAux { difficulty, total_difficulty, algorithm_difficulties })
+	}
 }
 
 impl<Difficulty> PowAux<Difficulty> where
-	Difficulty: Decode + Default,
+	Difficulty: Decode + Default + Ord,
 {
 	/// Read the auxiliary from client.
 	pub fn read<C: AuxStore, B: BlockT>(client: &C, hash: &B::Hash) -> Result<Self, Error<B>> {
@@ -156,6 +189,18 @@ impl<Difficulty> PowAux<Difficulty> where
 	}
 }
 
+impl<Difficulty: TotalDifficulty + Ord + Copy> PowAux<Difficulty> {
+	/// Account for a child block imported with the given difficulty, sealed by the
+	/// given algorithm.
+	pub fn note_block(&mut self, difficulty: Difficulty, algorithm: Option<PowAlgorithmId>) {
+		self.difficulty = difficulty;
+		self.total_difficulty.increment(difficulty);
+		if let Some(algorithm) = algorithm {
+			self.algorithm_difficulties.insert(algorithm, difficulty);
+		}
+	}
+}
+
 /// Algorithm used for proof of work.
 pub trait PowAlgorithm<B: BlockT> {
 	/// Difficulty for the algorithm.
@@ -189,6 +234,23 @@ pub trait PowAlgorithm<B: BlockT> {
 	) -> bool {
 		false
 	}
+	/// Get the algorithm that produced the given seal.
+	///
+	/// None means that the chain is mined with a single algorithm.
+	fn seal_algorithm(&self, _seal: &Seal) -> Option<PowAlgorithmId> {
+		None
+	}
+	/// Get the next block's difficulty for the given algorithm.
+	///
+	/// Only called for chains mined with several algorithms. By default this is the
+	/// same as `difficulty`.
+	fn algorithm_difficulty(
+		&self,
+		parent: B::Hash,
+		_algorithm: &PowAlgorithmId,
+	) -> Result<Self::Difficulty, Error<B>> {
+		self.difficulty(parent)
+	}
 	/// Verify that the difficulty is valid against given seal.
 	fn verify(
 		&self,
@@ -200,6 +262,58 @@ pub trait PowAlgorithm<B: BlockT> {
 	) -> Result<bool, Error<B>>;
 }
 
+/// Fork-choice rule preferring the block with the highest total difficulty.
+///
+/// This is the rule `PowBlockImport` applies by default. Unlike the default,
+/// ties are always resolved by keeping the current best block, since the seal
+/// of the candidate block is not part of its header at import time.
+pub struct TotalDifficultyRule<B: BlockT, C, Algorithm> {
+	client: Arc<C>,
+	algorithm: Algorithm,
+	_marker: PhantomData<B>,
+}
+
+impl<B: BlockT, C, Algorithm> TotalDifficultyRule<B, C, Algorithm> {
+	/// Create a new total difficulty rule.
+	pub fn new(client: Arc<C>, algorithm: Algorithm) -> Self {
+		Self { client, algorithm, _marker: PhantomData }
+	}
+}
+
+impl<B, C, Algorithm> ForkChoiceRule<B> for TotalDifficultyRule<B, C, Algorithm> where
+	B: BlockT,
+	C: AuxStore + Send + Sync,
+	Algorithm: PowAlgorithm<B> + Send + Sync,
+{
+	fn name(&self) -> &'static str {
+		"total_difficulty"
+	}
+
+	fn decide(
+		&self,
+		candidate: &B::Header,
+		best: &B::Header,
+	) -> Result<ForkChoiceDecision, ConsensusError> {
+		let parent_hash = *candidate.parent_hash();
+		let mut candidate_aux = PowAux::<Algorithm::Difficulty>::read::<_, B>(
+			self.client.as_ref(),
+			&parent_hash,
+		)?;
+		candidate_aux.total_difficulty.increment(self.algorithm.difficulty(parent_hash)?);
+
+		let best_aux = PowAux::<Algorithm::Difficulty>::read::<_, B>(
+			self.client.as_ref(),
+			&best.hash(),
+		)?;
+
+		Ok(match candidate_aux.total_difficulty.cmp(&best_aux.total_difficulty) {
+			Ordering::Greater => ForkChoiceDecision::switch("higher total difficulty"),
+			Ordering::Less => ForkChoiceDecision::keep("lower total difficulty"),
+			Ordering::Equal => ForkChoiceDecision::keep("equal total difficulty"),
+		})
+	}
+}
+
 /// A block importer for PoW.
 pub struct PowBlockImport<B: BlockT, I, C, S, Algorithm, CAW> {
 	algorithm: Algorithm,
@@ -209,6 +323,7 @@ pub struct PowBlockImport<B: BlockT, I, C, S, Algorithm, CAW> {
 	inherent_data_providers: sp_inherents::InherentDataProviders,
 	check_inherents_after: <<B as BlockT>::Header as HeaderT>::Number,
 	can_author_with: CAW,
+	fork_choice: Option<Arc<ForkChoiceSelector<B>>>,
 }
 
 impl<B: BlockT, I: Clone, C, S: Clone, Algorithm: Clone, CAW: Clone> Clone
@@ -223,6 +338,7 @@ impl<B: BlockT, I: Clone, C, S: Clone, Algorithm: Clone, CAW: Clone> Clone
 			inherent_data_providers: self.inherent_data_providers.clone(),
 			check_inherents_after: self.check_inherents_after.clone(),
 			can_author_with: self.can_author_with.clone(),
+			fork_choice: self.fork_choice.clone(),
 		}
 	}
 }
@@ -254,9 +370,18 @@ impl<B, I, C, S, Algorithm, CAW> PowBlockImport<B, I, C, S, Algorithm, CAW> wher
 			select_chain,
 			inherent_data_providers,
 			can_author_with,
+			fork_choice: None,
 		}
 	}
 
+	/// Use the given fork-choice rule instead of comparing total difficulty.
+	///
+	/// Blocks that were imported with a fork choice already set are not affected.
+	pub fn with_fork_choice(mut self, fork_choice: Arc<ForkChoiceSelector<B>>) -> Self {
+		self.fork_choice = Some(fork_choice);
+		self
+	}
+
 	fn check_inherents(
 		&self,
 		block: B,
@@ -365,9 +490,11 @@ impl<B, I, C, S, Algorithm, CAW> BlockImport<B> for PowBlockImport<B, I, C, S, A
 			INTERMEDIATE_KEY
 		)?;
 
-		let difficulty = match intermediate.difficulty {
-			Some(difficulty) => difficulty,
-			None => self.algorithm.difficulty(parent_hash)?,
+		let seal_algorithm = self.algorithm.seal_algorithm(&inner_seal);
+		let difficulty = match (intermediate.difficulty, &seal_algorithm) {
+			(Some(difficulty), _) => difficulty,
+			(None, Some(algorithm)) => self.algorithm.algorithm_difficulty(parent_hash, algorithm)?,
+			(None, None) => self.algorithm.difficulty(parent_hash)?,
 		};
 
 		let pre_hash = block.header.hash();
@@ -382,14 +509,14 @@ impl<B, I, C, S, Algorithm, CAW> BlockImport<B> for PowBlockImport<B, I, C, S, A
 			return Err(Error::<B>::InvalidSeal.into())
 		}
 
-		aux.difficulty = difficulty;
-		aux.total_difficulty.increment(difficulty);
+		aux.note_block(difficulty, seal_algorithm);
 
 		let key = aux_key(&block.post_hash());
 		block.auxiliary.push((key, Some(aux.encode())));
 		if block.fork_choice.is_none() {
-			block.fork_choice = Some(ForkChoiceStrategy::Custom(
-				match aux.total_difficulty.cmp(&best_aux.total_difficulty) {
+			block.fork_choice = Some(ForkChoiceStrategy::Custom(match &self.fork_choice {
+				Some(fork_choice) => fork_choice.import_as_best(&block.header, &best_header)?,
+				None => match aux.total_difficulty.cmp(&best_aux.total_difficulty) {
 					Ordering::Less => false,
 					Ordering::Greater => true,
 					Ordering::Equal => {
@@ -400,8 +527,8 @@ impl<B, I, C, S, Algorithm, CAW> BlockImport<B> for PowBlockImport<B, I, C, S, A
 
 						self.algorithm.break_tie(&best_inner_seal, &inner_seal)
 					},
-				}
-			));
+				},
+			}));
 		}
 
 		self.inner.import_block(block, new_cache).map_err(Into::into)
@@ -732,3 +859,140 @@ fn fetch_seal<B: BlockT>(
 		_ => return Err(Error::<B>::HeaderUnsealed(hash).into()),
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper, Header};
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+	#[derive(Default)]
+	struct TestAuxStore(Mutex<HashMap<Vec<u8>, Vec<u8>>>);
+
+	impl AuxStore for TestAuxStore {
+		fn insert_aux<
+			'a,
+			'b: 'a,
+			'c: 'a,
+			I: IntoIterator<Item=&'a(&'c [u8], &'c [u8])>,
+			D: IntoIterator<Item=&'a &'b [u8]>,
+		>(&self, insert: I, delete: D) -> sp_blockchain::Result<()> {
+			let mut aux = self.0.lock();
+			for (key, value) in insert {
+				aux.insert(key.to_vec(), value.to_vec());
+			}
+			for key in delete {
+				aux.remove(*key);
+			}
+			Ok(())
+		}
+
+		fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			Ok(self.0.lock().get(key).cloned())
+		}
+	}
+
+	/// Mines every block with the same difficulty.
+	struct FixedDifficulty(u128);
+
+	impl PowAlgorithm<Block> for FixedDifficulty {
+		type Difficulty = u128;
+
+		fn difficulty(&self, _parent: H256) -> Result<u128, Error<Block>> {
+			Ok(self.0)
+		}
+
+		fn verify(
+			&self,
+			_parent: &BlockId<Block>,
+			_pre_hash: &H256,
+			_pre_digest: Option<&[u8]>,
+			_seal: &Seal,
+			_difficulty: u128,
+		) -> Result<bool, Error<Block>> {
+			Ok(true)
+		}
+	}
+
+	fn header(number: u64, parent_hash: H256) -> Header {
+		Header::new(
+			number,
+			Default::default(),
+			Default::default(),
+			parent_hash,
+			Default::default(),
+		)
+	}
+
+	fn store_total_difficulty(client: &TestAuxStore, hash: H256, total_difficulty: u128) {
+		let aux = PowAux { difficulty: 0, total_difficulty, algorithm_difficulties: BTreeMap::new() };
+		let value = aux.encode();
+		client.insert_aux(&[(&aux_key(&hash)[..], &value[..])], &[]).unwrap();
+	}
+
+	#[test]
+	fn decodes_aux_without_algorithm_difficulties() {
+		let old = (5u128, 42u128).encode();
+		let aux = PowAux::<u128>::decode(&mut &old[..]).unwrap();
+
+		assert_eq!(aux.difficulty, 5);
+		assert_eq!(aux.total_difficulty, 42);
+		assert!(aux.algorithm_difficulties.is_empty());
+	}
+
+	#[test]
+	fn tracks_difficulty_of_each_algorithm() {
+		let sha3 = *b"sha3____";
+		let ethash = *b"ethash__";
+		let mut aux = PowAux::<u128>::default();
+
+		aux.note_block(10, Some(sha3));
+		aux.note_block(20, Some(ethash));
+		aux.note_block(15, Some(sha3));
+
+		let aux = PowAux::<u128>::decode(&mut &aux.encode()[..]).unwrap();
+		assert_eq!(aux.difficulty, 15);
+		assert_eq!(aux.total_difficulty, 45);
+		assert_eq!(
+			aux.algorithm_difficulties,
+			vec![(sha3, 15), (ethash, 20)].into_iter().collect::<BTreeMap<_, _>>(),
+		);
+
+		// chains mined with a single algorithm don't track any
+		let mut aux = PowAux::<u128>::default();
+		aux.note_block(10, None);
+		assert_eq!(aux.total_difficulty, 10);
+		assert!(aux.algorithm_difficulties.is_empty());
+	}
+
+	#[test]
+	fn total_difficulty_rule_prefers_heavier_chain() {
+		// the candidate builds on a block with a total difficulty of 20, the
+		// best block has a total difficulty of 30.
+		let client = Arc::new(TestAuxStore::default());
+		let parent = header(1, Default::default());
+		let best = header(2, [1; 32].into());
+		store_total_difficulty(&client, parent.hash(), 20);
+		store_total_difficulty(&client, best.hash(), 30);
+
+		let candidate = header(2, parent.hash());
+		let decide = |difficulty| {
+			TotalDifficultyRule::<Block, _, _>::new(client.clone(), FixedDifficulty(difficulty))
+				.decide(&candidate, &best)
+				.unwrap()
+		};
+
+		assert_eq!(decide(15), ForkChoiceDecision::switch("higher total difficulty"));
+		assert_eq!(decide(10), ForkChoiceDecision::keep("equal total difficulty"));
+		assert_eq!(decide(5), ForkChoiceDecision::keep("lower total difficulty"));
+
+		// blocks without any auxiliary data have no total difficulty yet.
+		let orphan = header(2, [2; 32].into());
+		let rule = TotalDifficultyRule::<Block, _, _>::new(client.clone(), FixedDifficulty(31));
+		assert!(rule.decide(&orphan, &best).unwrap().import_as_best);
+		let rule = TotalDifficultyRule::<Block, _, _>::new(client.clone(), FixedDifficulty(30));
+		assert!(!rule.decide(&orphan, &best).unwrap().import_as_best);
+	}
+}
@@ -0,0 +1,230 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Mining a chain with several PoW algorithms.
+
+use std::{collections::BTreeMap, sync::Arc};
+use codec::{Encode, Decode};
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::Block as BlockT;
+use sp_consensus_pow::{MultiAlgoSeal, PowAlgorithmId, Seal};
+
+use crate::{Error, PowAlgorithm};
+
+type BoxPowAlgorithm<B, Difficulty> =
+	Box<dyn PowAlgorithm<B, Difficulty = Difficulty> + Send + Sync>;
+
+/// A PoW algorithm dispatching to one of several algorithms, keyed by the
+/// algorithm id stored in each seal as a `MultiAlgoSeal`.
+///
+/// Difficulties of all algorithms share the same unit, so that the total
+/// difficulty of chains mined with different algorithms can be compared. Each
+/// algorithm is responsible for adjusting its own difficulty, for which the
+/// latest difficulty of every algorithm is available in `PowAux`.
+pub struct MultiAlgorithm<B: BlockT, Difficulty> {
+	algorithms: Arc<BTreeMap<PowAlgorithmId, BoxPowAlgorithm<B, Difficulty>>>,
+}
+
+impl<B: BlockT, Difficulty> Clone for MultiAlgorithm<B, Difficulty> {
+	fn clone(&self) -> Self {
+		Self { algorithms: self.algorithms.clone() }
+	}
+}
+
+impl<B: BlockT, Difficulty> MultiAlgorithm<B, Difficulty> {
+	/// Create a new multi algorithm from the given algorithms.
+	pub fn new(algorithms: BTreeMap<PowAlgorithmId, BoxPowAlgorithm<B, Difficulty>>) -> Self {
+		Self { algorithms: Arc::new(algorithms) }
+	}
+
+	/// Ids of all the algorithms that can seal blocks.
+	pub fn algorithm_ids(&self) -> Vec<PowAlgorithmId> {
+		self.algorithms.keys().copied().collect()
+	}
+
+	fn algorithm(
+		&self,
+		algorithm: &PowAlgorithmId,
+	) -> Result<&BoxPowAlgorithm<B, Difficulty>, Error<B>> {
+		self.algorithms.get(algorithm).ok_or(Error::UnknownAlgorithm(*algorithm))
+	}
+
+	fn decode_seal(seal: &Seal) -> Result<MultiAlgoSeal, Error<B>> {
+		MultiAlgoSeal::decode(&mut &seal[..]).map_err(Error::Codec)
+	}
+}
+
+impl<B, Difficulty> PowAlgorithm<B> for MultiAlgorithm<B, Difficulty> where
+	B: BlockT,
+	Difficulty: sp_consensus_pow::TotalDifficulty + Default + Encode + Decode + Ord + Clone + Copy,
+{
+	type Difficulty = Difficulty;
+
+	/// The lowest difficulty among all algorithms.
+	fn difficulty(&self, parent: B::Hash) -> Result<Difficulty, Error<B>> {
+		let mut lowest = None;
+		for algorithm in self.algorithms.values() {
+			let difficulty = algorithm.difficulty(parent)?;
+			if lowest.map_or(true, |lowest| difficulty < lowest) {
+				lowest = Some(difficulty);
+			}
+		}
+
+		lowest.ok_or_else(|| Error::Other("No PoW algorithm configured".into()))
+	}
+
+	fn preliminary_verify(
+		&self,
+		pre_hash: &B::Hash,
+		seal: &Seal,
+	) -> Result<Option<bool>, Error<B>> {
+		let seal = Self::decode_seal(seal)?;
+		self.algorithm(&seal.algorithm)?.preliminary_verify(pre_hash, &seal.seal)
+	}
+
+	fn break_tie(&self, own_seal: &Seal, new_seal: &Seal) -> bool {
+		match (Self::decode_seal(own_seal), Self::decode_seal(new_seal)) {
+			(Ok(own_seal), Ok(new_seal)) if own_seal.algorithm == new_seal.algorithm => {
+				self.algorithms.get(&own_seal.algorithm)
+					.map_or(false, |algorithm| algorithm.break_tie(&own_seal.seal, &new_seal.seal))
+			},
+			_ => false,
+		}
+	}
+
+	fn seal_algorithm(&self, seal: &Seal) -> Option<PowAlgorithmId> {
+		Self::decode_seal(seal).ok().map(|seal| seal.algorithm)
+	}
+
+	fn algorithm_difficulty(
+		&self,
+		parent: B::Hash,
+		algorithm: &PowAlgorithmId,
+	) -> Result<Difficulty, Error<B>> {
+		self.algorithm(algorithm)?.difficulty(parent)
+	}
+
+	fn verify(
+		&self,
+		parent: &BlockId<B>,
+		pre_hash: &B::Hash,
+		pre_digest: Option<&[u8]>,
+		seal: &Seal,
+		difficulty: Difficulty,
+	) -> Result<bool, Error<B>> {
+		let seal = Self::decode_seal(seal)?;
+		self.algorithm(&seal.algorithm)?
+			.verify(parent, pre_hash, pre_digest, &seal.seal, difficulty)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper};
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+	const SHA3: PowAlgorithmId = *b"sha3____";
+	const ETHASH: PowAlgorithmId = *b"ethash__";
+
+	/// Accepts seals equal to its nonce, at a fixed difficulty.
+	struct DummyAlgorithm {
+		nonce: u8,
+		difficulty: u128,
+	}
+
+	impl PowAlgorithm<Block> for DummyAlgorithm {
+		type Difficulty = u128;
+
+		fn difficulty(&self, _parent: H256) -> Result<u128, Error<Block>> {
+			Ok(self.difficulty)
+		}
+
+		fn verify(
+			&self,
+			_parent: &BlockId<Block>,
+			_pre_hash: &H256,
+			_pre_digest: Option<&[u8]>,
+			seal: &Seal,
+			difficulty: u128,
+		) -> Result<bool, Error<Block>> {
+			Ok(seal[..] == [self.nonce] && difficulty == self.difficulty)
+		}
+	}
+
+	fn multi_algorithm() -> MultiAlgorithm<Block, u128> {
+		let mut algorithms = BTreeMap::<_, BoxPowAlgorithm<Block, u128>>::new();
+		algorithms.insert(SHA3, Box::new(DummyAlgorithm { nonce: 1, difficulty: 10 }));
+		algorithms.insert(ETHASH, Box::new(DummyAlgorithm { nonce: 2, difficulty: 20 }));
+		MultiAlgorithm::new(algorithms)
+	}
+
+	fn seal(algorithm: PowAlgorithmId, nonce: u8) -> Seal {
+		MultiAlgoSeal { algorithm, seal: vec![nonce] }.encode()
+	}
+
+	fn verify(
+		algorithm: &MultiAlgorithm<Block, u128>,
+		seal: &Seal,
+		difficulty: u128,
+	) -> Result<bool, Error<Block>> {
+		algorithm.verify(&BlockId::Number(0), &Default::default(), None, seal, difficulty)
+	}
+
+	#[test]
+	fn verifies_seals_of_each_algorithm() {
+		let algorithm = multi_algorithm();
+		let parent = H256::default();
+
+		assert_eq!(algorithm.algorithm_ids(), vec![ETHASH, SHA3]);
+		assert_eq!(algorithm.difficulty(parent).unwrap(), 10);
+
+		for (id, nonce, difficulty) in vec![(SHA3, 1, 10), (ETHASH, 2, 20)] {
+			let seal = seal(id, nonce);
+			assert_eq!(algorithm.seal_algorithm(&seal), Some(id));
+			assert_eq!(algorithm.algorithm_difficulty(parent, &id).unwrap(), difficulty);
+			assert!(verify(&algorithm, &seal, difficulty).unwrap());
+		}
+
+		// seals are only valid for the algorithm that produced them
+		assert!(!verify(&algorithm, &seal(SHA3, 2), 10).unwrap());
+		assert!(!verify(&algorithm, &seal(ETHASH, 1), 20).unwrap());
+		assert!(!verify(&algorithm, &seal(ETHASH, 2), 10).unwrap());
+	}
+
+	#[test]
+	fn rejects_unknown_algorithm() {
+		let algorithm = multi_algorithm();
+		let unknown = *b"unknown_";
+
+		assert!(matches!(
+			verify(&algorithm, &seal(unknown, 1), 10),
+			Err(Error::UnknownAlgorithm(id)) if id == unknown,
+		));
+		assert!(matches!(
+			algorithm.algorithm_difficulty(Default::default(), &unknown),
+			Err(Error::UnknownAlgorithm(id)) if id == unknown,
+		));
+
+		// seals not wrapped in a `MultiAlgoSeal` can't be dispatched either
+		assert!(matches!(verify(&algorithm, &vec![1], 10), Err(Error::Codec(_))));
+		assert_eq!(algorithm.seal_algorithm(&vec![1]), None);
+	}
+}
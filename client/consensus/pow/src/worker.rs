@@ -24,7 +24,7 @@ use futures::{prelude::*, task::{Context, Poll}};
 use futures_timer::Delay;
 use log::*;
 
-use crate::{INTERMEDIATE_KEY, POW_ENGINE_ID, Seal, PowAlgorithm, PowIntermediate};
+use crate::{INTERMEDIATE_KEY, POW_ENGINE_ID, Seal, PowAlgorithm, PowAlgorithmId, PowIntermediate};
 
 /// Mining metadata. This is the information needed to start an actual mining loop.
 #[derive(Clone, Eq, PartialEq)]
@@ -82,25 +82,52 @@ impl<Block, Algorithm, C> MiningWorker<Block, Algorithm, C> where
 		self.build.as_ref().map(|b| b.metadata.clone())
 	}
 
+	/// Get a copy of the current mining metadata for the given algorithm, if available.
+	///
+	/// Only useful for chains mined with several algorithms, the target difficulty
+	/// is the one of the given algorithm.
+	pub fn metadata_for(
+		&self,
+		algorithm: &PowAlgorithmId,
+	) -> Option<MiningMetadata<Block::Hash, Algorithm::Difficulty>> {
+		let mut metadata = self.metadata()?;
+		match self.algorithm.algorithm_difficulty(metadata.best_hash, algorithm) {
+			Ok(difficulty) => {
+				metadata.difficulty = difficulty;
+				Some(metadata)
+			},
+			Err(err) => {
+				warn!(
+					target: "pow",
+					"Unable to fetch difficulty of algorithm {:?}: {:?}",
+					algorithm,
+					err,
+				);
+				None
+			},
+		}
+	}
+
 	/// Submit a mined seal. The seal will be validated again. Returns true if the submission is
 	/// successful.
+	///
+	/// The current build is only consumed once the seal is valid, so that invalid submissions
+	/// don't discard the work of the other miners.
 	pub fn submit(&mut self, seal: Seal) -> bool {
-		if let Some(build) = self.build.take() {
-			match self.algorithm.verify(
-				&BlockId::Hash(build.metadata.best_hash),
-				&build.metadata.pre_hash,
-				build.metadata.pre_runtime.as_ref().map(|v| &v[..]),
-				&seal,
-				build.metadata.difficulty,
-			) {
-				Ok(true) => (),
-				Ok(false) => {
-					warn!(
-						target: "pow",
-						"Unable to import mined block: seal is invalid",
-					);
-					return false
-				},
+		let metadata = match self.build.as_ref() {
+			Some(build) => &build.metadata,
+			None => {
+				warn!(
+					target: "pow",
+					"Unable to import mined block: build does not exist",
+				);
+				return false
+			},
+		};
+
+		let difficulty = match self.algorithm.seal_algorithm(&seal) {
+			Some(algorithm) => match self.algorithm.algorithm_difficulty(metadata.best_hash, &algorithm) {
+				Ok(difficulty) => difficulty,
 				Err(err) => {
 					warn!(
 						target: "pow",
@@ -109,49 +136,70 @@ impl<Block, Algorithm, C> MiningWorker<Block, Algorithm, C> where
 					);
 					return false
 				},
-			}
+			},
+			None => metadata.difficulty,
+		};
+
+		match self.algorithm.verify(
+			&BlockId::Hash(metadata.best_hash),
+			&metadata.pre_hash,
+			metadata.pre_runtime.as_ref().map(|v| &v[..]),
+			&seal,
+			difficulty,
+		) {
+			Ok(true) => (),
+			Ok(false) => {
+				warn!(
+					target: "pow",
+					"Unable to import mined block: seal is invalid",
+				);
+				return false
+			},
+			Err(err) => {
+				warn!(
+					target: "pow",
+					"Unable to import mined block: {:?}",
+					err,
+				);
+				return false
+			},
+		}
 
-			let seal = DigestItem::Seal(POW_ENGINE_ID, seal);
-			let (header, body) = build.proposal.block.deconstruct();
+		let build = self.build.take().expect("build was checked to exist above; qed");
+		let seal = DigestItem::Seal(POW_ENGINE_ID, seal);
+		let (header, body) = build.proposal.block.deconstruct();
 
-			let mut import_block = BlockImportParams::new(BlockOrigin::Own, header);
-			import_block.post_digests.push(seal);
-			import_block.body = Some(body);
-			import_block.storage_changes = Some(build.proposal.storage_changes);
+		let mut import_block = BlockImportParams::new(BlockOrigin::Own, header);
+		import_block.post_digests.push(seal);
+		import_block.body = Some(body);
+		import_block.storage_changes = Some(build.proposal.storage_changes);
 
-			let intermediate = PowIntermediate::<Algorithm::Difficulty> {
-				difficulty: Some(build.metadata.difficulty),
-			};
+		let intermediate = PowIntermediate::<Algorithm::Difficulty> {
+			difficulty: Some(difficulty),
+		};
 
-			import_block.intermediates.insert(
-				Cow::from(INTERMEDIATE_KEY),
-				Box::new(intermediate) as Box<dyn Any>
-			);
+		import_block.intermediates.insert(
+			Cow::from(INTERMEDIATE_KEY),
+			Box::new(intermediate) as Box<dyn Any>
+		);
 
-			match self.block_import.import_block(import_block, HashMap::default()) {
-				Ok(_) => {
-					info!(
-						target: "pow",
-						"✅ Successfully mined block on top of: {}",
-						build.metadata.best_hash
-					);
-					true
-				},
-				Err(err) => {
-					warn!(
-						target: "pow",
-						"Unable to import mined block: {:?}",
-						err,
-					);
-					false
-				},
-			}
-		} else {
-			warn!(
-				target: "pow",
-				"Unable to import mined block: build does not exist",
-			);
-			false
+		match self.block_import.import_block(import_block, HashMap::default()) {
+			Ok(_) => {
+				info!(
+					target: "pow",
+					"✅ Successfully mined block on top of: {}",
+					build.metadata.best_hash
+				);
+				true
+			},
+			Err(err) => {
+				warn!(
+					target: "pow",
+					"Unable to import mined block: {:?}",
+					err,
+				);
+				false
+			},
 		}
 	}
 }
@@ -36,7 +36,10 @@ use futures_timer::Delay;
 use sp_inherents::{InherentData, InherentDataProviders};
 use log::{debug, error, info, warn};
 use sp_runtime::generic::BlockId;
-use sp_runtime::traits::{Block as BlockT, Header, HashFor, NumberFor};
+use sp_runtime::traits::{
+	AtLeast32BitUnsigned, Block as BlockT, CheckedDiv, Header, HashFor, NumberFor, Saturating,
+	UniqueSaturatedInto,
+};
 use sp_api::{ProvideRuntimeApi, ApiRef};
 use std::{fmt::Debug, ops::Deref, pin::Pin, sync::Arc, time::{Instant, Duration}};
 use sc_telemetry::{telemetry, CONSENSUS_DEBUG, CONSENSUS_WARN, CONSENSUS_INFO};
@@ -133,6 +136,12 @@ pub trait SimpleSlotWorker<B: BlockT> {
 		_epoch_data: &Self::EpochData,
 	) {}
 
+	/// Whether block authoring should back off in the given slot, e.g. because finality is
+	/// lagging behind the chain head. Checked before trying to claim the slot.
+	fn should_backoff(&self, _slot_number: u64, _chain_head: &B::Header) -> bool {
+		false
+	}
+
 	/// Return the pre digest data to include in a block authored with the given claim.
 	fn pre_digest_data(
 		&self,
@@ -244,6 +253,16 @@ pub trait SimpleSlotWorker<B: BlockT> {
 			return Box::pin(future::ready(None));
 		}
 
+		if self.should_backoff(slot_number, &chain_head) {
+			telemetry!(
+				CONSENSUS_DEBUG;
+				"slots.backing_off_authoring";
+				"slot" => slot_number,
+			);
+
+			return Box::pin(future::ready(None));
+		}
+
 		let claim = match self.claim_slot(&chain_head, slot_number, &epoch_data) {
 			None => return Box::pin(future::ready(None)),
 			Some(claim) => claim,
@@ -583,6 +602,102 @@ pub fn slot_lenience_linear(parent_slot: u64, slot_info: &SlotInfo) -> Option<Du
 	}
 }
 
+/// A strategy deciding when to back off authoring blocks.
+pub trait BackoffAuthoringBlocksStrategy<N> {
+	/// Returns `true` if authoring a block in `slot_now` should be skipped, given the number
+	/// and slot of the chain head and the number of the last finalized block.
+	fn should_backoff(
+		&self,
+		chain_head_number: N,
+		chain_head_slot: u64,
+		finalized_number: N,
+		slot_now: u64,
+		logging_target: &str,
+	) -> bool;
+}
+
+/// The default strategy for backing off authoring blocks when finality lags behind.
+///
+/// Once the number of unfinalized blocks goes over `unfinalized_slack`, authoring is skipped
+/// in the slots right after the chain head. The number of slots skipped is the number of
+/// unfinalized blocks over the slack divided by `authoring_bias`, capped at `max_interval`,
+/// so the share of slots skipped grows with the finality lag. Since a block is still authored
+/// once the interval passes, the chain never stops growing and finality can eventually catch
+/// up, e.g. with a forced authority set change.
+#[derive(Clone)]
+pub struct BackoffAuthoringOnFinalizedHeadLagging<N> {
+	/// The maximum number of slots to skip after the chain head.
+	pub max_interval: N,
+	/// The number of unfinalized blocks allowed before starting to back off.
+	pub unfinalized_slack: N,
+	/// The number of unfinalized blocks over the slack needed to skip one more slot.
+	pub authoring_bias: N,
+}
+
+impl<N: AtLeast32BitUnsigned> Default for BackoffAuthoringOnFinalizedHeadLagging<N> {
+	fn default() -> Self {
+		Self {
+			// never wait more than 100 slots before authoring blocks, regardless of the lag.
+			max_interval: 100u32.into(),
+			// start backing off once there are more than 50 unfinalized blocks.
+			unfinalized_slack: 50u32.into(),
+			// skip one more slot for every 2 unfinalized blocks over the slack.
+			authoring_bias: 2u32.into(),
+		}
+	}
+}
+
+impl<N> BackoffAuthoringBlocksStrategy<N> for BackoffAuthoringOnFinalizedHeadLagging<N>
+where
+	N: AtLeast32BitUnsigned + Copy,
+{
+	fn should_backoff(
+		&self,
+		chain_head_number: N,
+		chain_head_slot: u64,
+		finalized_number: N,
+		slot_now: u64,
+		logging_target: &str,
+	) -> bool {
+		// the chain head can't be behind the finalized block, e.g. when authoring on
+		// top of an old block during a reorg, in which case we don't back off.
+		let unfinalized_blocks = chain_head_number.saturating_sub(finalized_number);
+
+		if unfinalized_blocks <= self.unfinalized_slack {
+			return false;
+		}
+
+		let interval = unfinalized_blocks.saturating_sub(self.unfinalized_slack)
+			.checked_div(&self.authoring_bias)
+			.unwrap_or(self.max_interval)
+			.min(self.max_interval);
+		let interval: u64 = interval.unique_saturated_into();
+
+		if slot_now <= chain_head_slot.saturating_add(interval) {
+			info!(
+				target: logging_target,
+				"Backing off claiming new slot for block authorship: finality is lagging.",
+			);
+			true
+		} else {
+			false
+		}
+	}
+}
+
+impl<N> BackoffAuthoringBlocksStrategy<N> for () {
+	fn should_backoff(
+		&self,
+		_chain_head_number: N,
+		_chain_head_slot: u64,
+		_finalized_number: N,
+		_slot_now: u64,
+		_logging_target: &str,
+	) -> bool {
+		false
+	}
+}
+
 #[cfg(test)]
 mod test {
 	use std::time::{Duration, Instant};
@@ -644,4 +759,44 @@ mod test {
 			Some(SLOT_DURATION * 2u32.pow(7)),
 		);
 	}
+
+	#[test]
+	fn backoff_authoring_on_finalized_head_lagging() {
+		use super::{BackoffAuthoringBlocksStrategy, BackoffAuthoringOnFinalizedHeadLagging};
+
+		let strategy = BackoffAuthoringOnFinalizedHeadLagging::<u64> {
+			max_interval: 100,
+			unfinalized_slack: 5,
+			authoring_bias: 2,
+		};
+
+		let should_backoff = |head_number, head_slot, finalized_number, slot_now| {
+			strategy.should_backoff(head_number, head_slot, finalized_number, slot_now, "slots")
+		};
+
+		// no back off while finality is within the slack
+		assert!(!should_backoff(15, 20, 10, 21));
+
+		// otherwise one slot is skipped for every two unfinalized blocks over the slack
+		assert!(should_backoff(20, 20, 10, 22));
+		assert!(!should_backoff(20, 20, 10, 23));
+
+		// so more slots are skipped as finality lags further behind
+		assert!(should_backoff(40, 20, 10, 32));
+		assert!(!should_backoff(40, 20, 10, 33));
+
+		// but never more than the max interval
+		assert!(should_backoff(10_000, 20, 10, 120));
+		assert!(!should_backoff(10_000, 20, 10, 121));
+
+		// a chain head behind the finalized block never backs off
+		assert!(!should_backoff(5, 20, 10, 21));
+	}
+
+	#[test]
+	fn no_backoff_strategy_never_backs_off() {
+		use super::BackoffAuthoringBlocksStrategy;
+
+		assert!(!BackoffAuthoringBlocksStrategy::<u64>::should_backoff(&(), 10_000, 20, 10, 21, "slots"));
+	}
 }
@@ -17,12 +17,14 @@
 //! Uncles functionality for Substrate.
 #![forbid(unsafe_code, missing_docs)]
 
-use sp_consensus::SelectChain;
+use sp_consensus::{SelectChain, Error as ConsensusError};
 use sp_inherents::{InherentDataProviders};
 use log::warn;
-use sc_client_api::ProvideUncles;
-use sp_runtime::traits::{Block as BlockT, Header};
-use std::sync::Arc;
+use sc_client_api::{Backend, ProvideUncles};
+use sc_consensus::{ForkChoiceDecision, ForkChoiceRule};
+use sp_blockchain::{Backend as _, HeaderBackend as _};
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Header, UniqueSaturatedInto}};
+use std::{cmp::Ordering, marker::PhantomData, sync::Arc};
 use sp_authorship;
 
 /// Maximum uncles generations we may provide to the runtime.
@@ -63,3 +65,184 @@ pub fn register_uncles_inherent_data_provider<B, C, SC>(
 	Ok(())
 }
 
+/// GHOST-like fork-choice rule, weighting a chain by its length plus the number
+/// of uncles that a block built on top of it could still include.
+///
+/// The uncles are looked up in the blockchain backend rather than through
+/// `ProvideUncles`, since the candidate block is not imported yet when the
+/// rule is evaluated. Ties are resolved by keeping the current best block.
+pub struct UncleWeightedRule<B, BE> {
+	backend: Arc<BE>,
+	max_generations: u32,
+	_phantom: PhantomData<B>,
+}
+
+impl<B, BE> UncleWeightedRule<B, BE> {
+	/// Create a new rule looking back the maximum number of uncle generations
+	/// that may be provided to the runtime.
+	pub fn new(backend: Arc<BE>) -> Self {
+		UncleWeightedRule { backend, max_generations: MAX_UNCLE_GENERATIONS, _phantom: PhantomData }
+	}
+
+	/// Number of uncles a child of `header` could include, i.e. the children of
+	/// the last `max_generations` ancestors of the child that are not on its chain.
+	fn uncles(&self, header: &B::Header) -> sp_blockchain::Result<u128> where
+		B: BlockT,
+		BE: Backend<B>,
+	{
+		let blockchain = self.backend.blockchain();
+		let genesis_hash = blockchain.info().genesis_hash;
+
+		let mut current_hash = header.hash();
+		if current_hash == genesis_hash { return Ok(0); }
+
+		let mut ancestor_hash = *header.parent_hash();
+		let mut uncles = 0u128;
+
+		for _generation in 0..self.max_generations {
+			uncles += blockchain.children(ancestor_hash)?
+				.into_iter()
+				.filter(|hash| hash != &current_hash)
+				.count() as u128;
+
+			current_hash = ancestor_hash;
+			if current_hash == genesis_hash { break; }
+			ancestor_hash = *blockchain.expect_header(BlockId::Hash(current_hash))?.parent_hash();
+		}
+
+		Ok(uncles)
+	}
+
+	fn weight(&self, header: &B::Header) -> Result<u128, ConsensusError> where
+		B: BlockT,
+		BE: Backend<B>,
+	{
+		let uncles = self.uncles(header)
+			.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?;
+		let number: u128 = (*header.number()).unique_saturated_into();
+
+		Ok(number.saturating_add(uncles))
+	}
+}
+
+impl<B, BE> ForkChoiceRule<B> for UncleWeightedRule<B, BE> where
+	B: BlockT,
+	BE: Backend<B>,
+{
+	fn name(&self) -> &'static str {
+		"uncle_weighted"
+	}
+
+	fn decide(
+		&self,
+		candidate: &B::Header,
+		best: &B::Header,
+	) -> Result<ForkChoiceDecision, ConsensusError> {
+		let candidate_weight = self.weight(candidate)?;
+		let best_weight = self.weight(best)?;
+
+		Ok(match candidate_weight.cmp(&best_weight) {
+			Ordering::Greater => ForkChoiceDecision::switch(
+				format!("higher uncle weight ({} > {})", candidate_weight, best_weight),
+			),
+			Ordering::Less => ForkChoiceDecision::keep(
+				format!("lower uncle weight ({} < {})", candidate_weight, best_weight),
+			),
+			Ordering::Equal => ForkChoiceDecision::keep(
+				format!("equal uncle weight ({})", candidate_weight),
+			),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_block_builder::BlockBuilderProvider;
+	use sp_consensus::BlockOrigin;
+	use substrate_test_runtime_client::{
+		prelude::*, runtime::{Block, Hash, Transfer}, DefaultTestClientBuilderExt,
+	};
+
+	fn build_at(client: &TestClient, parent: Hash, nonce: Option<u64>) -> Block {
+		let mut builder = client.new_block_at(&BlockId::Hash(parent), Default::default(), false)
+			.unwrap();
+		// the transfer is required as otherwise forks have the same hash.
+		if let Some(nonce) = nonce {
+			builder.push_transfer(Transfer {
+				from: AccountKeyring::Alice.into(),
+				to: AccountKeyring::Ferdie.into(),
+				amount: 41,
+				nonce,
+			}).unwrap();
+		}
+		builder.build().unwrap().block
+	}
+
+	#[test]
+	fn uncle_weight_counts_uncles_of_child() {
+		// block tree:
+		// G -> A1 -> A2 -> A3
+		//      A1 -> B2 -> C3
+		//            B2 -> B3 (not imported)
+		let (mut client, backend) = TestClientBuilder::new().build_with_backend();
+		let rule = UncleWeightedRule::<Block, _>::new(backend);
+
+		let a1 = build_at(&client, client.chain_info().genesis_hash, None);
+		client.import(BlockOrigin::Own, a1.clone()).unwrap();
+		let a2 = build_at(&client, a1.hash(), None);
+		client.import(BlockOrigin::Own, a2.clone()).unwrap();
+		let a3 = build_at(&client, a2.hash(), None);
+		client.import(BlockOrigin::Own, a3.clone()).unwrap();
+		let b2 = build_at(&client, a1.hash(), Some(0));
+		client.import(BlockOrigin::Own, b2.clone()).unwrap();
+		let c3 = build_at(&client, b2.hash(), None);
+		client.import(BlockOrigin::Own, c3.clone()).unwrap();
+		let b3 = build_at(&client, b2.hash(), Some(1));
+
+		// a child of A3 could include B2 as an uncle.
+		assert_eq!(rule.uncles(&a3.header).unwrap(), 1);
+		assert_eq!(
+			client.uncles(a3.hash(), MAX_UNCLE_GENERATIONS.into()).unwrap(),
+			vec![b2.hash()],
+		);
+
+		// a child of B3 could include C3 and A2, even though B3 is not imported yet.
+		assert_eq!(rule.uncles(&b3.header).unwrap(), 2);
+		assert_eq!(rule.weight(&a3.header).unwrap(), 4);
+		assert_eq!(rule.weight(&b3.header).unwrap(), 5);
+
+		assert!(rule.decide(&b3.header, &a3.header).unwrap().import_as_best);
+		assert!(!rule.decide(&a3.header, &b3.header).unwrap().import_as_best);
+		// C3 and A3 weigh the same, keep the best block.
+		assert!(!rule.decide(&c3.header, &a3.header).unwrap().import_as_best);
+	}
+
+	#[test]
+	fn uncle_weight_is_bounded_by_max_generations() {
+		// block tree:
+		// G -> A1 -> A2 -> A3
+		// G -> B1
+		let (mut client, backend) = TestClientBuilder::new().build_with_backend();
+		let mut rule = UncleWeightedRule::<Block, _>::new(backend);
+
+		let genesis_hash = client.chain_info().genesis_hash;
+		let mut parent = genesis_hash;
+		let mut chain = Vec::new();
+		for _ in 0..3 {
+			let block = build_at(&client, parent, None);
+			client.import(BlockOrigin::Own, block.clone()).unwrap();
+			parent = block.hash();
+			chain.push(block);
+		}
+
+		let b1 = build_at(&client, genesis_hash, Some(0));
+		client.import(BlockOrigin::Own, b1).unwrap();
+
+		// B1 is the third generation uncle of a child of A3.
+		assert_eq!(rule.uncles(&chain[2].header).unwrap(), 1);
+		rule.max_generations = 2;
+		assert_eq!(rule.uncles(&chain[2].header).unwrap(), 0);
+		assert_eq!(rule.uncles(&chain[1].header).unwrap(), 1);
+	}
+}
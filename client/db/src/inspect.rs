@@ -0,0 +1,813 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Offline inspection of the database of a stopped node.
+//!
+//! The database is opened directly, without a client on top of it, and is never written to.
+//! Column statistics and the search for orphaned state nodes enumerate the keys of a column,
+//! which is only supported by RocksDB. Trie dumps and consistency checks read entries by key
+//! and work with any database.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use codec::Decode;
+use sp_core::storage::well_known_keys;
+use sp_database::ColumnId;
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::{
+	Block as BlockT, Header as HeaderT, NumberFor, HashFor, One, Zero, SaturatedConversion,
+};
+use sp_trie::prefixed_key;
+use sc_state_db::{PruningMode, StateDb};
+use trie_db::NodeCodec as NodeCodecT;
+use trie_db::node::{Node, NodeHandle};
+use crate::{columns, DatabaseSettingsSrc};
+use crate::migration::MigrationDb;
+use crate::utils::{self, meta_keys, DatabaseType};
+
+pub use crate::stats::ColumnStats;
+
+type NodeCodec<Block> = sp_trie::NodeCodec<HashFor<Block>>;
+
+/// Returns a human readable name of a database column.
+pub fn column_name(col: ColumnId) -> &'static str {
+	match col {
+		columns::META => "meta",
+		columns::STATE => "state",
+		columns::STATE_META => "state_meta",
+		columns::KEY_LOOKUP => "key_lookup",
+		columns::HEADER => "header",
+		columns::BODY => "body",
+		columns::JUSTIFICATION => "justification",
+		columns::CHANGES_TRIE => "changes_trie",
+		columns::AUX => "aux",
+		columns::OFFCHAIN => "offchain",
+		columns::CACHE => "cache",
+		columns::TRANSACTION => "transaction",
+		_ => "unknown",
+	}
+}
+
+/// Kind of a trie node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrieNodeKind {
+	/// The empty node.
+	Empty,
+	/// A leaf node.
+	Leaf,
+	/// An extension node.
+	Extension,
+	/// A branch node.
+	Branch,
+}
+
+/// A state trie node visited by [`DbInspector::dump_trie`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrieNodeInfo {
+	/// Storage key of the child trie the node belongs to, `None` for the main trie.
+	pub child_trie: Option<Vec<u8>>,
+	/// Nibbles on the path from the root of the trie to the node.
+	pub path: Vec<u8>,
+	/// Kind of the node.
+	pub kind: TrieNodeKind,
+	/// Encoded size of the node.
+	pub size: usize,
+	/// Whether the node is inlined in its parent rather than stored in the database.
+	pub inline: bool,
+	/// Size of the value stored in the node, if any.
+	pub value_size: Option<usize>,
+}
+
+/// Summary of a state trie dump.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TrieSummary {
+	/// Number of nodes stored in the database.
+	pub nodes: u64,
+	/// Total size of the nodes stored in the database.
+	pub node_bytes: u64,
+	/// Number of nodes inlined in their parents.
+	pub inline_nodes: u64,
+	/// Number of values.
+	pub values: u64,
+	/// Total size of the values.
+	pub value_bytes: u64,
+	/// Number of child tries.
+	pub child_tries: u64,
+	/// Number of nodes referenced by their parent but missing from the database.
+	pub missing: u64,
+}
+
+impl TrieSummary {
+	fn tally(&mut self, info: &TrieNodeInfo) {
+		if info.inline {
+			self.inline_nodes += 1;
+		} else {
+			self.nodes += 1;
+			self.node_bytes += info.size as u64;
+			if info.path.is_empty() && info.child_trie.is_some() {
+				self.child_tries += 1;
+			}
+		}
+		if let Some(size) = info.value_size {
+			self.values += 1;
+			self.value_bytes += size as u64;
+		}
+	}
+}
+
+/// Summary of the search for orphaned state nodes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OrphanSummary {
+	/// Number of block states that are not pruned.
+	pub states: u64,
+	/// Number of nodes in the state column.
+	pub nodes: u64,
+	/// Number of nodes that are not reachable from any of the states.
+	pub orphans: u64,
+	/// Total size of the orphaned nodes.
+	pub orphan_bytes: u64,
+	/// Number of nodes referenced by the states but missing from the database.
+	pub missing: u64,
+}
+
+/// An inconsistency found by [`DbInspector::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inconsistency<N, H> {
+	/// A block number on the best chain is not mapped to a block.
+	MissingHash(N),
+	/// The hash of a block on the best chain is not mapped to its lookup key.
+	MissingLookup(N, H),
+	/// The header of a block on the best chain is missing.
+	MissingHeader(N, H),
+	/// The header of a block can't be decoded or doesn't match the number and hash it is stored under.
+	InvalidHeader(N, H),
+	/// The parent hash of a header doesn't match the hash of the previous block.
+	BrokenParent(N, H),
+	/// The body of a block that is not finalized is missing.
+	MissingBody(N, H),
+	/// A block that is not finalized has a justification.
+	UnexpectedJustification(N, H),
+	/// The root node of a state that is not pruned is missing.
+	MissingState(N, H),
+	/// An entry of a column is stored for a block without a header.
+	DanglingEntry(ColumnId, Vec<u8>),
+	/// A metadata entry doesn't match the blocks in the database.
+	InvalidMeta(&'static str),
+}
+
+impl<N: fmt::Display, H: fmt::Debug> fmt::Display for Inconsistency<N, H> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Inconsistency::MissingHash(n) => write!(f, "Block #{} is not mapped to a hash", n),
+			Inconsistency::MissingLookup(n, h) => write!(f, "Block #{} ({:?}) has no lookup key", n, h),
+			Inconsistency::MissingHeader(n, h) => write!(f, "Header of block #{} ({:?}) is missing", n, h),
+			Inconsistency::InvalidHeader(n, h) => write!(f, "Header of block #{} ({:?}) is invalid", n, h),
+			Inconsistency::BrokenParent(n, h) =>
+				write!(f, "Parent of block #{} ({:?}) is not the previous block", n, h),
+			Inconsistency::MissingBody(n, h) => write!(f, "Body of unfinalized block #{} ({:?}) is missing", n, h),
+			Inconsistency::UnexpectedJustification(n, h) =>
+				write!(f, "Unfinalized block #{} ({:?}) has a justification", n, h),
+			Inconsistency::MissingState(n, h) => write!(f, "State of block #{} ({:?}) is missing", n, h),
+			Inconsistency::DanglingEntry(col, key) => write!(
+				f,
+				"Entry 0x{} of column {} has no header",
+				sp_core::hexdisplay::HexDisplay::from(key),
+				column_name(*col),
+			),
+			Inconsistency::InvalidMeta(desc) => write!(f, "Invalid {} in metadata", desc),
+		}
+	}
+}
+
+/// Inconsistency of a database of given block type.
+pub type BlockInconsistency<Block> = Inconsistency<NumberFor<Block>, <Block as BlockT>::Hash>;
+
+/// Summary of a consistency check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckSummary<N> {
+	/// Best block number.
+	pub best: N,
+	/// Finalized block number.
+	pub finalized: N,
+	/// Number of blocks checked.
+	pub blocks: u64,
+	/// Number of finalized blocks whose body was pruned.
+	pub pruned_bodies: u64,
+	/// Number of justifications.
+	pub justifications: u64,
+	/// Number of block states that are not pruned.
+	pub states: u64,
+	/// Number of inconsistencies found.
+	pub inconsistencies: u64,
+}
+
+/// Read-only access to a database opened offline, as required by the state database.
+struct Offline<'a>(&'a dyn MigrationDb);
+
+impl<'a> sc_state_db::MetaDb for Offline<'a> {
+	type Error = sp_blockchain::Error;
+
+	fn get_meta(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.0.get(columns::STATE_META, key)
+	}
+}
+
+impl<'a> sc_state_db::NodeDb for Offline<'a> {
+	type Error = sp_blockchain::Error;
+	type Key = [u8];
+
+	fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.0.get(columns::STATE, key)
+	}
+}
+
+fn state_db_err(e: sc_state_db::Error<sp_blockchain::Error>) -> sp_blockchain::Error {
+	sp_blockchain::Error::Backend(format!("State database error: {:?}", e))
+}
+
+/// Trie node reference, either by hash or inlined in its parent.
+enum Handle<H> {
+	Hash(H),
+	Inline(Vec<u8>),
+}
+
+/// Trie prefix of a node with given nibble path, with the keyspace of its child trie prepended.
+fn node_prefix(keyspace: &[u8], path: &[u8]) -> (Vec<u8>, Option<u8>) {
+	let mut prefix = keyspace.to_vec();
+	prefix.extend(path.chunks(2).filter(|pair| pair.len() == 2).map(|pair| pair[0] << 4 | pair[1]));
+	let last = if path.len() % 2 == 1 { Some(path[path.len() - 1] << 4) } else { None };
+	(prefix, last)
+}
+
+/// Inspects the database of a stopped node.
+pub struct DbInspector<Block: BlockT> {
+	db: Box<dyn MigrationDb>,
+	state_db: StateDb<Block::Hash, Vec<u8>>,
+}
+
+impl<Block: BlockT> DbInspector<Block> {
+	/// Open the database for inspection. The database must be at the current version.
+	pub fn open(source: &DatabaseSettingsSrc) -> sp_blockchain::Result<Self> {
+		if source.path().map_or(false, crate::migration::is_empty) {
+			return Err(sp_blockchain::Error::Backend(format!("No {} database found", source)));
+		}
+		let db = match source {
+			#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+			DatabaseSettingsSrc::RocksDb { path, .. } => crate::upgrade::open_rocksdb(path)?,
+			#[cfg(feature = "with-parity-db")]
+			DatabaseSettingsSrc::ParityDb { path } => crate::parity_db::open_offline(path, DatabaseType::Full)?,
+			_ => return Err(sp_blockchain::Error::Backend(
+				format!("Inspecting {} database is not supported", source)
+			)),
+		};
+		Self::new(db)
+	}
+
+	fn new(db: Box<dyn MigrationDb>) -> sp_blockchain::Result<Self> {
+		match db.get(columns::META, meta_keys::TYPE)? {
+			Some(db_type) if db_type != DatabaseType::Full.as_str().as_bytes() => return Err(
+				sp_blockchain::Error::Backend("Only full node databases can be inspected".into())
+			),
+			_ => {},
+		}
+
+		let state_db = {
+			let meta_db = Offline(&*db);
+			let mode = PruningMode::from_db(&meta_db).map_err(state_db_err)?
+				.unwrap_or(PruningMode::ArchiveAll);
+			StateDb::new(mode, !db.supports_ref_counting(), &meta_db).map_err(state_db_err)?
+		};
+		Ok(DbInspector { db, state_db })
+	}
+
+	/// Number and size of the entries of every column.
+	pub fn column_stats(&self) -> sp_blockchain::Result<Vec<(ColumnId, ColumnStats)>> {
+		self.require_iteration("Column statistics")?;
+		(0..self.db.num_columns()).map(|col| {
+			let mut stats = ColumnStats::default();
+			self.db.iter_column(col, None, &mut |key, value| {
+				stats.tally(key, value);
+				Ok(true)
+			})?;
+			Ok((col, stats))
+		}).collect()
+	}
+
+	/// Best block, if any block was imported.
+	pub fn best_block(&self) -> sp_blockchain::Result<Option<(NumberFor<Block>, Block::Hash)>> {
+		self.meta_block(meta_keys::BEST_BLOCK)
+	}
+
+	/// Last finalized block, if any block was finalized.
+	pub fn finalized_block(&self) -> sp_blockchain::Result<Option<(NumberFor<Block>, Block::Hash)>> {
+		self.meta_block(meta_keys::FINALIZED_BLOCK)
+	}
+
+	/// Calls `f` with every node of the state of given block, or of the best block if `None`,
+	/// including the nodes of child tries.
+	pub fn dump_trie(
+		&self,
+		block: Option<BlockId<Block>>,
+		f: &mut dyn FnMut(&TrieNodeInfo),
+	) -> sp_blockchain::Result<TrieSummary> {
+		let hash = match block {
+			Some(BlockId::Hash(hash)) => hash,
+			Some(BlockId::Number(number)) => match self.canonical_lookup_key(number)? {
+				Some(lookup_key) => hash_from_lookup_key::<Block>(&lookup_key)?,
+				None => return Err(sp_blockchain::Error::UnknownBlock(format!("#{}", number))),
+			},
+			None => self.best_block()?
+				.map(|(_, hash)| hash)
+				.ok_or_else(|| sp_blockchain::Error::Backend("The database contains no blocks".into()))?,
+		};
+		let lookup_key = self.db.get(columns::KEY_LOOKUP, hash.as_ref())?
+			.ok_or_else(|| sp_blockchain::Error::UnknownBlock(format!("{:?}", hash)))?;
+		let number = utils::lookup_key_to_number::<NumberFor<Block>>(&lookup_key)?;
+		let header = self.read_header(&lookup_key)?
+			.ok_or_else(|| sp_blockchain::Error::UnknownBlock(format!("Missing header of {:?}", hash)))?;
+		if self.state_db.is_pruned(&hash, number.saturated_into()) {
+			return Err(sp_blockchain::Error::Backend(format!("State of block #{} is pruned", number)));
+		}
+
+		let mut summary = TrieSummary::default();
+		let missing = self.walk_state(header.state_root(), &mut |_, info| {
+			summary.tally(info);
+			f(info);
+			true
+		})?;
+		summary.missing = missing;
+		Ok(summary)
+	}
+
+	/// Calls `f` with the key and value size of every node of the state column that is not
+	/// reachable from the state of any block on the best chain that is not pruned.
+	///
+	/// Nodes of non-canonical states are kept in the state database journal until their block is
+	/// canonicalized, so only the best chain has to be considered. All reachable node keys are
+	/// kept in memory.
+	pub fn find_orphans(&self, f: &mut dyn FnMut(&[u8], usize)) -> sp_blockchain::Result<OrphanSummary> {
+		self.require_iteration("Finding orphaned state nodes")?;
+		if self.db.supports_ref_counting() {
+			return Err(sp_blockchain::Error::Backend(
+				"Finding orphaned state nodes is not supported by reference counted databases".into()
+			));
+		}
+
+		let mut summary = OrphanSummary::default();
+		let mut reachable = HashSet::new();
+		let best = self.best_block()?.map_or_else(Zero::zero, |(number, _)| number);
+		let mut number = Zero::zero();
+		while number <= best {
+			if let Some(lookup_key) = self.canonical_lookup_key(number)? {
+				let hash = hash_from_lookup_key::<Block>(&lookup_key)?;
+				if let Some(header) = self.read_header(&lookup_key)? {
+					if !self.state_db.is_pruned(&hash, number.saturated_into()) {
+						summary.states += 1;
+						// Subtrees of nodes visited before are shared with a previous state.
+						summary.missing += self.walk_state(header.state_root(), &mut |key, _| match key {
+							Some(key) => reachable.insert(key.to_vec()),
+							None => true,
+						})?;
+					}
+				}
+			}
+			number = number + One::one();
+		}
+
+		self.db.iter_column(columns::STATE, None, &mut |key, value| {
+			summary.nodes += 1;
+			if !reachable.contains(key) {
+				summary.orphans += 1;
+				summary.orphan_bytes += value.len() as u64;
+				f(key, value.len());
+			}
+			Ok(true)
+		})?;
+		Ok(summary)
+	}
+
+	/// Checks that the headers, bodies, justifications and states of the blocks on the best chain
+	/// line up, calling `f` with every inconsistency found.
+	///
+	/// Entries of blocks without a header are only found in databases that support iteration.
+	pub fn check(
+		&self,
+		f: &mut dyn FnMut(&BlockInconsistency<Block>),
+	) -> sp_blockchain::Result<CheckSummary<NumberFor<Block>>> {
+		let mut summary = CheckSummary {
+			best: Zero::zero(),
+			finalized: Zero::zero(),
+			blocks: 0,
+			pruned_bodies: 0,
+			justifications: 0,
+			states: 0,
+			inconsistencies: 0,
+		};
+		let mut report = |inconsistency: BlockInconsistency<Block>, summary: &mut CheckSummary<_>| {
+			summary.inconsistencies += 1;
+			f(&inconsistency);
+		};
+
+		let best = match self.best_block()? {
+			Some((number, _)) => number,
+			None => return Ok(summary),
+		};
+		let finalized = self.finalized_block()?.map_or_else(Zero::zero, |(number, _)| number);
+		summary.best = best;
+		summary.finalized = finalized;
+		if finalized > best {
+			report(Inconsistency::InvalidMeta("finalized block"), &mut summary);
+		}
+
+		let mut parent: Option<Block::Hash> = None;
+		let mut number: NumberFor<Block> = Zero::zero();
+		while number <= best {
+			let block = self.check_block(number, parent.take(), finalized, &mut summary, &mut report)?;
+			if number.is_zero() {
+				let genesis = self.db.get(columns::META, meta_keys::GENESIS_HASH)?
+					.and_then(|hash| Block::Hash::decode(&mut &hash[..]).ok());
+				if block.is_none() || genesis != block {
+					report(Inconsistency::InvalidMeta("genesis hash"), &mut summary);
+				}
+			}
+			parent = block;
+			summary.blocks += 1;
+			number = number + One::one();
+		}
+
+		if self.db.can_iterate() {
+			for col in [columns::HEADER, columns::BODY, columns::JUSTIFICATION].iter() {
+				let mut dangling = Vec::new();
+				self.db.iter_column(*col, None, &mut |key, _| {
+					let has_header = match hash_from_lookup_key::<Block>(key) {
+						Ok(hash) if *col == columns::HEADER =>
+							self.db.get(columns::KEY_LOOKUP, hash.as_ref())?.is_some(),
+						Ok(_) => self.db.get(columns::HEADER, key)?.is_some(),
+						Err(_) => false,
+					};
+					if !has_header {
+						dangling.push(key.to_vec());
+					}
+					Ok(true)
+				})?;
+				for key in dangling {
+					report(Inconsistency::DanglingEntry(*col, key), &mut summary);
+				}
+			}
+		}
+		Ok(summary)
+	}
+
+	/// Checks a single block on the best chain, returning its hash if it is known.
+	fn check_block(
+		&self,
+		number: NumberFor<Block>,
+		parent: Option<Block::Hash>,
+		finalized: NumberFor<Block>,
+		summary: &mut CheckSummary<NumberFor<Block>>,
+		report: &mut dyn FnMut(BlockInconsistency<Block>, &mut CheckSummary<NumberFor<Block>>),
+	) -> sp_blockchain::Result<Option<Block::Hash>> {
+		let lookup_key = match self.canonical_lookup_key(number)? {
+			Some(lookup_key) => lookup_key,
+			None => {
+				report(Inconsistency::MissingHash(number), summary);
+				return Ok(None);
+			},
+		};
+		let hash = match hash_from_lookup_key::<Block>(&lookup_key) {
+			Ok(hash) if utils::lookup_key_to_number::<NumberFor<Block>>(&lookup_key)? == number => hash,
+			_ => {
+				report(Inconsistency::MissingHash(number), summary);
+				return Ok(None);
+			},
+		};
+
+		if self.db.get(columns::KEY_LOOKUP, hash.as_ref())?.as_ref() != Some(&lookup_key) {
+			report(Inconsistency::MissingLookup(number, hash), summary);
+		}
+
+		match self.db.get(columns::HEADER, &lookup_key)? {
+			None => report(Inconsistency::MissingHeader(number, hash), summary),
+			Some(header) => match Block::Header::decode(&mut &header[..]) {
+				Ok(header) if header.hash() == hash && *header.number() == number => {
+					if parent.map_or(false, |parent| *header.parent_hash() != parent) {
+						report(Inconsistency::BrokenParent(number, hash), summary);
+					}
+					if !self.state_db.is_pruned(&hash, number.saturated_into()) {
+						summary.states += 1;
+						if !self.has_state_root(header.state_root())? {
+							report(Inconsistency::MissingState(number, hash), summary);
+						}
+					}
+				},
+				_ => report(Inconsistency::InvalidHeader(number, hash), summary),
+			},
+		}
+
+		if self.db.get(columns::BODY, &lookup_key)?.is_none() {
+			if number > finalized {
+				report(Inconsistency::MissingBody(number, hash), summary);
+			} else {
+				summary.pruned_bodies += 1;
+			}
+		}
+
+		if self.db.get(columns::JUSTIFICATION, &lookup_key)?.is_some() {
+			summary.justifications += 1;
+			if number > finalized {
+				report(Inconsistency::UnexpectedJustification(number, hash), summary);
+			}
+		}
+
+		Ok(Some(hash))
+	}
+
+	fn require_iteration(&self, what: &str) -> sp_blockchain::Result<()> {
+		if self.db.can_iterate() {
+			Ok(())
+		} else {
+			Err(sp_blockchain::Error::Backend(format!("{} is only supported for RocksDB databases", what)))
+		}
+	}
+
+	fn meta_block(&self, key: &[u8]) -> sp_blockchain::Result<Option<(NumberFor<Block>, Block::Hash)>> {
+		match self.db.get(columns::META, key)? {
+			Some(lookup_key) => Ok(Some((
+				utils::lookup_key_to_number(&lookup_key)?,
+				hash_from_lookup_key::<Block>(&lookup_key)?,
+			))),
+			None => Ok(None),
+		}
+	}
+
+	fn canonical_lookup_key(&self, number: NumberFor<Block>) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		self.db.get(columns::KEY_LOOKUP, &utils::number_index_key(number)?)
+	}
+
+	fn read_header(&self, lookup_key: &[u8]) -> sp_blockchain::Result<Option<Block::Header>> {
+		match self.db.get(columns::HEADER, lookup_key)? {
+			Some(header) => Block::Header::decode(&mut &header[..])
+				.map(Some)
+				.map_err(|_| sp_blockchain::Error::Backend("Error decoding header".into())),
+			None => Ok(None),
+		}
+	}
+
+	fn has_state_root(&self, root: &Block::Hash) -> sp_blockchain::Result<bool> {
+		if *root == NodeCodec::<Block>::hashed_null_node() {
+			return Ok(true);
+		}
+		let key = self.node_key(root, None, &[]);
+		Ok(self.state_db.get(&key[..], &Offline(&*self.db)).map_err(state_db_err)?.is_some())
+	}
+
+	/// Database key of the state node with given hash and path.
+	fn node_key(&self, hash: &Block::Hash, child_trie: Option<&[u8]>, path: &[u8]) -> Vec<u8> {
+		if self.db.supports_ref_counting() {
+			return hash.as_ref().to_vec();
+		}
+		let (prefix, last) = node_prefix(child_trie.unwrap_or(&[]), path);
+		prefixed_key::<HashFor<Block>>(hash, (&prefix, last))
+	}
+
+	/// Walks the main trie of a state and all of its child tries, returning the number of
+	/// missing nodes.
+	fn walk_state(
+		&self,
+		root: &Block::Hash,
+		visit: &mut dyn FnMut(Option<&[u8]>, &TrieNodeInfo) -> bool,
+	) -> sp_blockchain::Result<u64> {
+		let mut child_roots = Vec::new();
+		let mut missing = self.walk_trie(root, None, visit, &mut child_roots)?;
+		for (storage_key, child_root) in child_roots {
+			missing += self.walk_trie(&child_root, Some(&storage_key), visit, &mut Vec::new())?;
+		}
+		Ok(missing)
+	}
+
+	/// Walks a trie depth first, calling `visit` with the database key of every stored node.
+	///
+	/// The children of a node are skipped when `visit` returns `false`. Roots of the child tries
+	/// found in the main trie are added to `child_roots`.
+	fn walk_trie(
+		&self,
+		root: &Block::Hash,
+		child_trie: Option<&[u8]>,
+		visit: &mut dyn FnMut(Option<&[u8]>, &TrieNodeInfo) -> bool,
+		child_roots: &mut Vec<(Vec<u8>, Block::Hash)>,
+	) -> sp_blockchain::Result<u64> {
+		let decode_err = |e: &dyn fmt::Display| sp_blockchain::Error::Backend(
+			format!("Error decoding trie node: {}", e)
+		);
+		let null_node = NodeCodec::<Block>::hashed_null_node();
+		let mut missing = 0;
+		let mut stack = vec![(Vec::new(), Handle::Hash(root.clone()))];
+		while let Some((path, handle)) = stack.pop() {
+			let (key, data) = match handle {
+				Handle::Hash(hash) if hash == null_node => continue,
+				Handle::Hash(hash) => {
+					let key = self.node_key(&hash, child_trie, &path);
+					match self.state_db.get(&key[..], &Offline(&*self.db)).map_err(state_db_err)? {
+						Some(data) => (Some(key), data),
+						None => {
+							missing += 1;
+							continue;
+						},
+					}
+				},
+				Handle::Inline(data) => (None, data),
+			};
+
+			let node = NodeCodec::<Block>::decode(&data).map_err(|e| decode_err(&e))?;
+			let mut children = Vec::new();
+			let (kind, partial, value) = match node {
+				Node::Empty => (TrieNodeKind::Empty, None, None),
+				Node::Leaf(partial, value) => (TrieNodeKind::Leaf, Some(partial), Some(value)),
+				Node::Extension(partial, child) => {
+					children.push((None, child));
+					(TrieNodeKind::Extension, Some(partial), None)
+				},
+				Node::Branch(branch, value) => {
+					children.extend(branch.iter().enumerate()
+						.filter_map(|(i, child)| child.clone().map(|child| (Some(i as u8), child))));
+					(TrieNodeKind::Branch, None, value)
+				},
+				Node::NibbledBranch(partial, branch, value) => {
+					children.extend(branch.iter().enumerate()
+						.filter_map(|(i, child)| child.clone().map(|child| (Some(i as u8), child))));
+					(TrieNodeKind::Branch, Some(partial), value)
+				},
+			};
+
+			let info = TrieNodeInfo {
+				child_trie: child_trie.map(|storage_key| storage_key.to_vec()),
+				path: path.clone(),
+				kind,
+				size: data.len(),
+				inline: key.is_none(),
+				value_size: value.map(|value| value.len()),
+			};
+			if !visit(key.as_ref().map(|key| &key[..]), &info) {
+				continue;
+			}
+
+			let mut node_path = path;
+			if let Some(partial) = partial {
+				node_path.extend((0..partial.len()).map(|i| partial.at(i)));
+			}
+
+			if let (None, Some(value)) = (child_trie, value) {
+				let (storage_key, odd) = node_prefix(&[], &node_path);
+				let prefix = well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX;
+				if odd.is_none() && storage_key.starts_with(prefix) {
+					let child_root = Block::Hash::decode(&mut &value[..]).map_err(|e| decode_err(&e.what()))?;
+					child_roots.push((storage_key[prefix.len()..].to_vec(), child_root));
+				}
+			}
+
+			for (index, child) in children.into_iter().rev() {
+				let mut child_path = node_path.clone();
+				child_path.extend(index);
+				let handle = match child {
+					NodeHandle::Hash(hash) => Handle::Hash(
+						Block::Hash::decode(&mut &hash[..]).map_err(|e| decode_err(&e.what()))?
+					),
+					NodeHandle::Inline(data) => Handle::Inline(data.to_vec()),
+				};
+				stack.push((child_path, handle));
+			}
+		}
+		Ok(missing)
+	}
+}
+
+/// Extracts the block hash from a block lookup key.
+fn hash_from_lookup_key<Block: BlockT>(lookup_key: &[u8]) -> sp_blockchain::Result<Block::Hash> {
+	if lookup_key.len() < 4 {
+		return Err(sp_blockchain::Error::Backend("Invalid block key".into()));
+	}
+	Block::Hash::decode(&mut &lookup_key[4..])
+		.map_err(|_| sp_blockchain::Error::Backend("Invalid block key".into()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_client_api::backend::{Backend as BTrait, BlockImportOperation as Op, NewBlockState};
+	use sp_database::Transaction;
+	use sp_runtime::Storage;
+	use sp_runtime::testing::Header;
+	use sp_state_machine::backend::Backend as StateBackend;
+	use crate::{Backend, DatabaseSettings, KeepBlocks, TransactionStorageMode};
+	use crate::tests::Block;
+
+	fn import_blocks(path: &std::path::Path) {
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 0,
+			state_cache_child_ratio: None,
+			pruning: PruningMode::ArchiveAll,
+			source: DatabaseSettingsSrc::RocksDb { path: path.to_owned(), cache_size: 128 },
+			transaction_storage: TransactionStorageMode::BlockBody,
+			keep_blocks: KeepBlocks::All,
+		}, 0).unwrap();
+
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, BlockId::Hash(Default::default())).unwrap();
+		let storage = vec![
+			(vec![1, 3, 5], vec![2, 4, 6]),
+			(vec![1, 2, 3], vec![9, 9, 9]),
+		];
+		let mut header = Header {
+			number: 0,
+			parent_hash: Default::default(),
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		header.state_root = op.old_state.storage_root(storage
+			.iter()
+			.map(|(k, v)| (&k[..], Some(&v[..])))
+		).0.into();
+		let genesis = header.hash();
+		op.reset_storage(Storage {
+			top: storage.into_iter().collect(),
+			children_default: Default::default(),
+		}).unwrap();
+		op.set_block_data(header, Some(vec![]), None, NewBlockState::Final).unwrap();
+		backend.commit_operation(op).unwrap();
+
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, BlockId::Number(0)).unwrap();
+		let changes = vec![
+			(vec![1, 3, 5], None),
+			(vec![5, 5, 5], Some(vec![4, 5, 6])),
+		];
+		let (root, overlay) = op.old_state.storage_root(
+			changes.iter().map(|(k, v)| (&k[..], v.as_ref().map(|v| &v[..])))
+		);
+		op.update_db_storage(overlay).unwrap();
+		op.update_storage(changes, Vec::new()).unwrap();
+		let header = Header {
+			number: 1,
+			parent_hash: genesis,
+			state_root: root.into(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		op.set_block_data(header, Some(vec![]), Some(vec![42]), NewBlockState::Final).unwrap();
+		backend.commit_operation(op).unwrap();
+	}
+
+	#[test]
+	fn inspects_stopped_database() {
+		let db_dir = tempfile::TempDir::new().unwrap();
+		import_blocks(db_dir.path());
+		let source = DatabaseSettingsSrc::RocksDb { path: db_dir.path().to_owned(), cache_size: 128 };
+		let inspector = DbInspector::<Block>::open(&source).unwrap();
+
+		let mut inconsistencies = Vec::new();
+		let summary = inspector.check(&mut |i| inconsistencies.push(i.clone())).unwrap();
+		assert_eq!(inconsistencies, Vec::new());
+		assert_eq!((summary.best, summary.finalized), (1, 1));
+		assert_eq!((summary.blocks, summary.states, summary.justifications), (2, 2, 1));
+
+		let mut values = 0;
+		let trie = inspector.dump_trie(None, &mut |node| if node.value_size.is_some() { values += 1 }).unwrap();
+		assert_eq!((values, trie.values, trie.missing), (2, 2, 0));
+
+		let stats = inspector.column_stats().unwrap();
+		assert_eq!(stats[columns::HEADER as usize].1.keys, 2);
+		assert_eq!(stats[columns::JUSTIFICATION as usize].1.keys, 1);
+
+		assert_eq!(inspector.find_orphans(&mut |_, _| {}).unwrap().orphans, 0);
+		let mut transaction = Transaction::new();
+		transaction.set(columns::STATE, b"orphan", &[1, 2, 3]);
+		inspector.db.commit(transaction).unwrap();
+		let mut orphans = Vec::new();
+		let summary = inspector.find_orphans(&mut |key, size| orphans.push((key.to_vec(), size))).unwrap();
+		assert_eq!(orphans, vec![(b"orphan".to_vec(), 3)]);
+		assert_eq!(summary.states, 2);
+	}
+
+	#[test]
+	fn node_prefix_packs_nibbles() {
+		assert_eq!(node_prefix(&[], &[]), (vec![], None));
+		assert_eq!(node_prefix(&[7], &[1, 2, 3]), (vec![7, 0x12], Some(0x30)));
+	}
+}
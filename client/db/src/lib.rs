@@ -28,7 +28,9 @@
 
 #![warn(missing_docs)]
 
+pub mod inspect;
 pub mod light;
+pub mod migration;
 pub mod offchain;
 
 #[cfg(any(feature = "with-kvdb-rocksdb", test))]
@@ -62,10 +64,13 @@ use sp_blockchain::{
 use codec::{Decode, Encode};
 use hash_db::Prefix;
 use sp_trie::{MemoryDB, PrefixedMemoryDB, prefixed_key};
-use sp_database::Transaction;
+use sp_database::{Change, Transaction};
 use parking_lot::RwLock;
 use sp_core::ChangesTrieConfiguration;
-use sp_core::offchain::storage::{OffchainOverlayedChange, OffchainOverlayedChanges};
+use sp_core::offchain::{
+	STORAGE_PREFIX, LOCAL_STORAGE_PREFIX,
+	storage::{OffchainOverlayedChange, OffchainOverlayedChanges},
+};
 use sp_core::storage::{well_known_keys, ChildInfo};
 use sp_arithmetic::traits::Saturating;
 use sp_runtime::{generic::{DigestItem, BlockId}, Justification, Storage};
@@ -74,7 +79,7 @@ use sp_runtime::traits::{
 };
 use sp_state_machine::{
 	DBValue, ChangesTrieTransaction, ChangesTrieCacheAction, UsageInfo as StateUsageInfo,
-	StorageCollection, ChildStorageCollection,
+	StorageCollection, ChildStorageCollection, IndexOperation,
 	backend::Backend as StateBackend, StateMachineStats,
 };
 use crate::utils::{DatabaseType, Meta, meta_keys, read_db, read_meta};
@@ -88,12 +93,17 @@ use log::{trace, debug, warn};
 // Re-export the Database trait so that one can pass an implementation of it.
 pub use sp_database::Database;
 pub use sc_state_db::PruningMode;
+pub use utils::DatabaseType;
 
 #[cfg(any(feature = "with-kvdb-rocksdb", test))]
 pub use bench::BenchmarkingState;
 
 const MIN_BLOCKS_TO_KEEP_CHANGES_TRIES_FOR: u32 = 32768;
 
+/// Maximum number of block bodies pruned when a block is finalized. Bodies that fell out of
+/// the pruning window beyond that are pruned when the next blocks are finalized.
+const MAX_PRUNED_BODIES_PER_FINALIZATION: u32 = 32;
+
 /// Default value for storage cache child ratio.
 const DEFAULT_CHILD_RATIO: (usize, usize) = (1, 10);
 
@@ -266,6 +276,42 @@ pub struct DatabaseSettings {
 	pub pruning: PruningMode,
 	/// Where to find the database.
 	pub source: DatabaseSettingsSrc,
+	/// Block body/Transaction storage scheme.
+	pub transaction_storage: TransactionStorageMode,
+	/// Block pruning mode.
+	pub keep_blocks: KeepBlocks,
+}
+
+/// Block pruning settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepBlocks {
+	/// Keep full block history.
+	All,
+	/// Keep bodies of the given number of finalized blocks. Headers, justifications and
+	/// the hash-to-number lookup are always kept.
+	Some(u32),
+}
+
+impl Default for KeepBlocks {
+	fn default() -> Self {
+		KeepBlocks::All
+	}
+}
+
+/// Block body/Transaction storage scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionStorageMode {
+	/// Store block body as an encoded list of full transactions in the BODY column
+	BlockBody,
+	/// Store a list of hashes in the BODY column and each transaction individually
+	/// in the TRANSACTION column.
+	StorageChain,
+}
+
+impl Default for TransactionStorageMode {
+	fn default() -> Self {
+		TransactionStorageMode::BlockBody
+	}
 }
 
 /// Where to find the database..
@@ -332,6 +378,8 @@ pub(crate) mod columns {
 	/// Offchain workers local storage
 	pub const OFFCHAIN: u32 = 9;
 	pub const CACHE: u32 = 10;
+	/// Transactions
+	pub const TRANSACTION: u32 = 11;
 }
 
 struct PendingBlock<Block: BlockT> {
@@ -341,6 +389,139 @@ struct PendingBlock<Block: BlockT> {
 	leaf_state: NewBlockState,
 }
 
+/// Block body entry as stored in the BODY column in `TransactionStorageMode::StorageChain` mode.
+#[derive(Debug, Encode, Decode)]
+enum DbExtrinsic<B: BlockT> {
+	/// Extrinsic that contains indexed data.
+	Indexed {
+		/// Hash of the indexed part.
+		hash: DbHash,
+		/// Extrinsic header.
+		header: Vec<u8>,
+	},
+	/// Complete extrinsic data.
+	Full(B::Extrinsic),
+}
+
+/// Suffix of the key that holds the reference counter of an indexed transaction.
+const INDEXED_REFS_SUFFIX: &[u8] = b":refs";
+
+fn indexed_refs_key(hash: &[u8]) -> Vec<u8> {
+	let mut key = hash.to_vec();
+	key.extend_from_slice(INDEXED_REFS_SUFFIX);
+	key
+}
+
+fn read_indexed_refs(db: &dyn Database<DbHash>, hash: &[u8]) -> u32 {
+	db.get(columns::TRANSACTION, &indexed_refs_key(hash))
+		.and_then(|v| Decode::decode(&mut &v[..]).ok())
+		.unwrap_or(0)
+}
+
+/// Convert a block body into the `StorageChain` representation, applying the given index
+/// operations. Indexed data is written to the TRANSACTION column and reference counted.
+fn apply_index_ops<Block: BlockT>(
+	transaction: &mut Transaction<DbHash>,
+	db: &dyn Database<DbHash>,
+	refs: &mut HashMap<DbHash, u32>,
+	body: Vec<Block::Extrinsic>,
+	ops: Vec<IndexOperation>,
+) -> Vec<u8> {
+	let mut extrinsic_headers: Vec<DbExtrinsic<Block>> = Vec::with_capacity(body.len());
+	let mut index_map = HashMap::new();
+	let mut renewed_map = HashMap::new();
+	for op in ops {
+		match op {
+			IndexOperation::Insert { extrinsic, hash, size } => {
+				index_map.insert(extrinsic, (hash, size));
+			}
+			IndexOperation::Renew { extrinsic, hash } => {
+				renewed_map.insert(extrinsic, hash);
+			}
+		}
+	}
+	let mut add_ref = |transaction: &mut Transaction<DbHash>, hash: DbHash, data: Option<&[u8]>| {
+		let count = refs.entry(hash).or_insert_with(|| read_indexed_refs(db, &hash));
+		if *count == 0 {
+			if let Some(data) = data {
+				transaction.set(columns::TRANSACTION, &hash, data);
+			}
+		}
+		*count += 1;
+		transaction.set_from_vec(columns::TRANSACTION, &indexed_refs_key(&hash), count.encode());
+	};
+	for (index, extrinsic) in body.into_iter().enumerate() {
+		let index = index as u32;
+		let db_extrinsic = if let Some(hash) = renewed_map.get(&index).and_then(|h| to_db_hash(h)) {
+			// Bump ref counter
+			add_ref(transaction, hash, None);
+			DbExtrinsic::Indexed { hash, header: extrinsic.encode() }
+		} else {
+			match index_map.get(&index) {
+				Some((hash, size)) => match to_db_hash(hash) {
+					Some(hash) => {
+						let encoded = extrinsic.encode();
+						let size = *size as usize;
+						if size <= encoded.len() {
+							let offset = encoded.len() - size;
+							add_ref(transaction, hash, Some(&encoded[offset..]));
+							DbExtrinsic::Indexed { hash, header: encoded[..offset].to_vec() }
+						} else {
+							// Invalid indexed slice. Just store full data and don't index anything.
+							DbExtrinsic::Full(extrinsic)
+						}
+					},
+					None => DbExtrinsic::Full(extrinsic),
+				},
+				_ => DbExtrinsic::Full(extrinsic),
+			}
+		};
+		extrinsic_headers.push(db_extrinsic);
+	}
+	debug!(
+		target: "db",
+		"DB transaction index: {} inserted, {} renewed",
+		index_map.len(),
+		renewed_map.len(),
+	);
+	extrinsic_headers.encode()
+}
+
+/// Drop one reference of every transaction indexed by the given `StorageChain` block body.
+/// Indexed data is removed when the last reference is gone.
+fn release_indexed_body<Block: BlockT>(
+	transaction: &mut Transaction<DbHash>,
+	db: &dyn Database<DbHash>,
+	refs: &mut HashMap<DbHash, u32>,
+	body: &[u8],
+) -> ClientResult<()> {
+	let body: Vec<DbExtrinsic<Block>> = Decode::decode(&mut &body[..]).map_err(|err|
+		sp_blockchain::Error::Backend(format!("Error decoding body list: {}", err))
+	)?;
+	for extrinsic in body {
+		if let DbExtrinsic::Indexed { hash, .. } = extrinsic {
+			let count = refs.entry(hash).or_insert_with(|| read_indexed_refs(db, &hash));
+			*count = count.saturating_sub(1);
+			if *count == 0 {
+				transaction.remove(columns::TRANSACTION, &hash);
+				transaction.remove(columns::TRANSACTION, &indexed_refs_key(&hash));
+			} else {
+				transaction.set_from_vec(columns::TRANSACTION, &indexed_refs_key(&hash), count.encode());
+			}
+		}
+	}
+	Ok(())
+}
+
+fn to_db_hash(hash: &[u8]) -> Option<DbHash> {
+	if hash.len() != DB_HASH_LEN {
+		return None;
+	}
+	let mut db_hash = DbHash::default();
+	db_hash.copy_from_slice(hash);
+	Some(db_hash)
+}
+
 // wrapper that implements trait required for state_db
 struct StateMetaDb<'a>(&'a dyn Database<DbHash>);
 
@@ -358,10 +539,14 @@ pub struct BlockchainDb<Block: BlockT> {
 	meta: Arc<RwLock<Meta<NumberFor<Block>, Block::Hash>>>,
 	leaves: RwLock<LeafSet<Block::Hash, NumberFor<Block>>>,
 	header_metadata_cache: Arc<HeaderMetadataCache<Block>>,
+	transaction_storage: TransactionStorageMode,
 }
 
 impl<Block: BlockT> BlockchainDb<Block> {
-	fn new(db: Arc<dyn Database<DbHash>>) -> ClientResult<Self> {
+	fn new(
+		db: Arc<dyn Database<DbHash>>,
+		transaction_storage: TransactionStorageMode,
+	) -> ClientResult<Self> {
 		let meta = read_meta::<Block>(&*db, columns::HEADER)?;
 		let leaves = LeafSet::read_from_db(&*db, columns::META, meta_keys::LEAF_PREFIX)?;
 		Ok(BlockchainDb {
@@ -369,6 +554,7 @@ impl<Block: BlockT> BlockchainDb<Block> {
 			leaves: RwLock::new(leaves),
 			meta: Arc::new(RwLock::new(meta)),
 			header_metadata_cache: Arc::new(HeaderMetadataCache::default()),
+			transaction_storage,
 		})
 	}
 
@@ -452,14 +638,47 @@ impl<Block: BlockT> sc_client_api::blockchain::HeaderBackend<Block> for Blockcha
 
 impl<Block: BlockT> sc_client_api::blockchain::Backend<Block> for BlockchainDb<Block> {
 	fn body(&self, id: BlockId<Block>) -> ClientResult<Option<Vec<Block::Extrinsic>>> {
-		match read_db(&*self.db, columns::KEY_LOOKUP, columns::BODY, id)? {
-			Some(body) => match Decode::decode(&mut &body[..]) {
+		let body = match read_db(&*self.db, columns::KEY_LOOKUP, columns::BODY, id)? {
+			Some(body) => body,
+			None => return Ok(None),
+		};
+		match self.transaction_storage {
+			TransactionStorageMode::BlockBody => match Decode::decode(&mut &body[..]) {
 				Ok(body) => Ok(Some(body)),
-				Err(err) => return Err(sp_blockchain::Error::Backend(
+				Err(err) => Err(sp_blockchain::Error::Backend(
 					format!("Error decoding body: {}", err)
 				)),
+			},
+			TransactionStorageMode::StorageChain => {
+				match Vec::<DbExtrinsic<Block>>::decode(&mut &body[..]) {
+					Ok(index) => {
+						let extrinsics: ClientResult<Vec<Block::Extrinsic>> = index.into_iter().map(
+							|ex| match ex {
+								DbExtrinsic::Indexed { hash, header } => {
+									match self.db.get(columns::TRANSACTION, hash.as_ref()) {
+										Some(t) => {
+											let mut input = utils::join_input(header.as_ref(), t.as_ref());
+											Block::Extrinsic::decode(&mut input).map_err(|err|
+												sp_blockchain::Error::Backend(
+													format!("Error decoding indexed extrinsic: {}", err)
+												)
+											)
+										},
+										None => Err(sp_blockchain::Error::Backend(
+											format!("Missing indexed transaction {:?}", hash))
+										)
+									}
+								},
+								DbExtrinsic::Full(ex) => Ok(ex),
+							}
+						).collect();
+						Ok(Some(extrinsics?))
+					}
+					Err(err) => Err(sp_blockchain::Error::Backend(
+						format!("Error decoding body list: {}", err)
+					)),
+				}
 			}
-			None => Ok(None),
 		}
 	}
 
@@ -490,6 +709,41 @@ impl<Block: BlockT> sc_client_api::blockchain::Backend<Block> for BlockchainDb<B
 	fn children(&self, parent_hash: Block::Hash) -> ClientResult<Vec<Block::Hash>> {
 		children::read_children(&*self.db, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash)
 	}
+
+	fn indexed_transaction(&self, hash: &Block::Hash) -> ClientResult<Option<Vec<u8>>> {
+		Ok(self.db.get(columns::TRANSACTION, hash.as_ref()))
+	}
+
+	fn block_indexed_body(&self, id: BlockId<Block>) -> ClientResult<Option<Vec<Vec<u8>>>> {
+		match self.transaction_storage {
+			TransactionStorageMode::BlockBody => Ok(None),
+			TransactionStorageMode::StorageChain => {
+				let body = match read_db(&*self.db, columns::KEY_LOOKUP, columns::BODY, id)? {
+					Some(body) => body,
+					None => return Ok(None),
+				};
+				match Vec::<DbExtrinsic<Block>>::decode(&mut &body[..]) {
+					Ok(index) => {
+						let mut transactions = Vec::new();
+						for ex in index.into_iter() {
+							if let DbExtrinsic::Indexed { hash, .. } = ex {
+								match self.db.get(columns::TRANSACTION, hash.as_ref()) {
+									Some(t) => transactions.push(t),
+									None => return Err(sp_blockchain::Error::Backend(
+										format!("Missing indexed transaction {:?}", hash))
+									)
+								}
+							}
+						}
+						Ok(Some(transactions))
+					}
+					Err(err) => Err(sp_blockchain::Error::Backend(
+						format!("Error decoding body list: {}", err)
+					)),
+				}
+			}
+		}
+	}
 }
 
 impl<Block: BlockT> sc_client_api::blockchain::ProvideCache<Block> for BlockchainDb<Block> {
@@ -590,21 +844,34 @@ pub struct BlockImportOperation<Block: BlockT> {
 	finalized_blocks: Vec<(BlockId<Block>, Option<Justification>)>,
 	set_head: Option<BlockId<Block>>,
 	commit_state: bool,
+	index_ops: Vec<IndexOperation>,
 }
 
 impl<Block: BlockT> BlockImportOperation<Block> {
-	fn apply_offchain(&mut self, transaction: &mut Transaction<DbHash>) {
+	/// Write the persistent offchain changes and return the fork-aware ones, which are
+	/// journaled against the imported block.
+	fn apply_offchain(
+		&mut self,
+		transaction: &mut Transaction<DbHash>,
+	) -> Vec<(Vec<u8>, Option<Vec<u8>>)> {
+		let mut fork_aware = Vec::new();
 		for ((prefix, key), value_operation) in self.offchain_storage_updates.drain() {
-			let key: Vec<u8> = prefix
-				.into_iter()
-				.chain(sp_core::sp_std::iter::once(b'/'))
-				.chain(key.into_iter())
-				.collect();
-			match value_operation {
-				OffchainOverlayedChange::SetValue(val) => transaction.set_from_vec(columns::OFFCHAIN, &key, val),
-				OffchainOverlayedChange::Remove => transaction.remove(columns::OFFCHAIN, &key),
+			let value = match value_operation {
+				OffchainOverlayedChange::SetValue(val) => Some(val),
+				OffchainOverlayedChange::Remove => None,
+			};
+			if prefix == LOCAL_STORAGE_PREFIX {
+				fork_aware.push((STORAGE_PREFIX.iter().cloned().chain(key).collect(), value));
+				continue
+			}
+
+			let key: Vec<u8> = prefix.into_iter().chain(key.into_iter()).collect();
+			match value {
+				Some(val) => transaction.set_from_vec(columns::OFFCHAIN, &key, val),
+				None => transaction.remove(columns::OFFCHAIN, &key),
 			}
 		}
+		fork_aware
 	}
 
 	fn apply_aux(&mut self, transaction: &mut Transaction<DbHash>) {
@@ -720,6 +987,11 @@ impl<Block: BlockT> sc_client_api::backend::BlockImportOperation<Block> for Bloc
 		Ok(())
 	}
 
+	fn update_transaction_index(&mut self, index_ops: Vec<IndexOperation>) -> ClientResult<()> {
+		self.index_ops = index_ops;
+		Ok(())
+	}
+
 	fn mark_finalized(
 		&mut self,
 		block: BlockId<Block>,
@@ -832,6 +1104,7 @@ pub struct Backend<Block: BlockT> {
 	shared_cache: SharedCache<Block>,
 	import_lock: Arc<RwLock<()>>,
 	is_archive: bool,
+	keep_blocks: KeepBlocks,
 	io_stats: FrozenForDuration<(kvdb::IoStats, StateUsageInfo)>,
 	state_usage: Arc<StateUsageStats>,
 }
@@ -848,6 +1121,41 @@ impl<Block: BlockT> Backend<Block> {
 	/// Create new memory-backed client backend for tests.
 	#[cfg(any(test, feature = "test-helpers"))]
 	pub fn new_test(keep_blocks: u32, canonicalization_delay: u64) -> Self {
+		Self::new_test_with_tx_storage(
+			keep_blocks,
+			canonicalization_delay,
+			TransactionStorageMode::BlockBody,
+		)
+	}
+
+	/// Create new memory-backed client backend for tests that prunes block bodies.
+	#[cfg(any(test, feature = "test-helpers"))]
+	pub fn new_test_with_block_pruning(
+		keep_blocks: u32,
+		keep_bodies: u32,
+		canonicalization_delay: u64,
+	) -> Self {
+		let db = kvdb_memorydb::create(crate::utils::NUM_COLUMNS);
+		let db = sp_database::as_database(db);
+		let db_setting = DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::keep_blocks(keep_blocks),
+			source: DatabaseSettingsSrc::Custom(db),
+			transaction_storage: TransactionStorageMode::BlockBody,
+			keep_blocks: KeepBlocks::Some(keep_bodies),
+		};
+
+		Self::new(db_setting, canonicalization_delay).expect("failed to create test-db")
+	}
+
+	/// Create new memory-backed client backend for tests.
+	#[cfg(any(test, feature = "test-helpers"))]
+	pub fn new_test_with_tx_storage(
+		keep_blocks: u32,
+		canonicalization_delay: u64,
+		transaction_storage: TransactionStorageMode,
+	) -> Self {
 		let db = kvdb_memorydb::create(crate::utils::NUM_COLUMNS);
 		let db = sp_database::as_database(db);
 		let db_setting = DatabaseSettings {
@@ -855,6 +1163,8 @@ impl<Block: BlockT> Backend<Block> {
 			state_cache_child_ratio: Some((50, 100)),
 			pruning: PruningMode::keep_blocks(keep_blocks),
 			source: DatabaseSettingsSrc::Custom(db),
+			transaction_storage,
+			keep_blocks: KeepBlocks::All,
 		};
 
 		Self::new(db_setting, canonicalization_delay).expect("failed to create test-db")
@@ -866,7 +1176,7 @@ impl<Block: BlockT> Backend<Block> {
 		config: &DatabaseSettings,
 	) -> ClientResult<Self> {
 		let is_archive_pruning = config.pruning.is_archive();
-		let blockchain = BlockchainDb::new(db.clone())?;
+		let blockchain = BlockchainDb::new(db.clone(), config.transaction_storage)?;
 		let meta = blockchain.meta.clone();
 		let map_e = |e: sc_state_db::Error<io::Error>| sp_blockchain::Error::from(
 			format!("State database error: {:?}", e)
@@ -910,11 +1220,140 @@ impl<Block: BlockT> Backend<Block> {
 			),
 			import_lock: Default::default(),
 			is_archive: is_archive_pruning,
+			keep_blocks: config.keep_blocks,
 			io_stats: FrozenForDuration::new(std::time::Duration::from_secs(1)),
 			state_usage: Arc::new(StateUsageStats::new()),
 		})
 	}
 
+	/// Retains the state of a canonical block that has not been pruned yet until it is
+	/// released with `release_state`.
+	///
+	/// Takes the import lock, so it must not be called while holding it.
+	pub fn retain_state(&self, hash: &Block::Hash) -> ClientResult<()> {
+		let _import_lock = self.import_lock.write();
+		let commit = self.storage.state_db.retain(hash).map_err(|_| sp_blockchain::Error::UnknownBlock(
+			format!("State of {:?} is not available for retention", hash)
+		))?;
+		self.commit_state_db(commit)
+	}
+
+	/// Releases a state retained with `retain_state`, deleting the nodes that no other
+	/// retained state needs. Fails if the state is pinned.
+	///
+	/// Takes the import lock, so it must not be called while holding it.
+	pub fn release_state(&self, hash: &Block::Hash) -> ClientResult<()> {
+		let _import_lock = self.import_lock.write();
+		let commit = self.storage.state_db.release(hash).map_err(|_| sp_blockchain::Error::UnknownBlock(
+			format!("State of {:?} is not retained or is in use", hash)
+		))?;
+		self.commit_state_db(commit)
+	}
+
+	/// Write a state db commit on its own and apply or revert the pending state db changes.
+	fn commit_state_db(&self, commit: sc_state_db::CommitSet<Vec<u8>>) -> ClientResult<()> {
+		let mut transaction = Transaction::new();
+		apply_state_commit(&mut transaction, commit);
+		match self.storage.db.commit(transaction) {
+			Ok(()) => {
+				self.storage.state_db.apply_pending();
+				Ok(())
+			},
+			Err(e) => {
+				self.storage.state_db.revert_pending();
+				Err(e.into())
+			},
+		}
+	}
+
+	/// Remove the bodies of the canonical blocks that fell out of the block pruning window
+	/// after finalizing block `finalized`.
+	///
+	/// The number of the last pruned block is persisted, so that the blocks that fell out of
+	/// the window while nothing was pruned, e.g. when several blocks got finalized at once or
+	/// pruning was enabled on an existing database, are pruned as well. At most
+	/// `MAX_PRUNED_BODIES_PER_FINALIZATION` bodies are pruned at once, so such a backlog is
+	/// caught up with over the next finalizations.
+	///
+	/// `indexed_refs` caches the reference counters of indexed transactions changed by
+	/// `transaction` and must be shared by everything that changes them in it.
+	fn prune_blocks(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		indexed_refs: &mut HashMap<DbHash, u32>,
+		finalized: NumberFor<Block>,
+	) -> ClientResult<()> {
+		if let KeepBlocks::Some(keep_blocks) = self.keep_blocks {
+			// Always keep the body of the last finalized block.
+			let keep = NumberFor::<Block>::from(std::cmp::max(keep_blocks, 1));
+			if finalized < keep {
+				return Ok(());
+			}
+
+			let mut number = match self.last_pruned_block(transaction)? {
+				Some(last_pruned) => last_pruned + One::one(),
+				None => Zero::zero(),
+			};
+			let max_pruned = NumberFor::<Block>::from(MAX_PRUNED_BODIES_PER_FINALIZATION);
+			let prune_to = std::cmp::min(finalized - keep, number + max_pruned - One::one());
+			if number > prune_to {
+				return Ok(());
+			}
+
+			trace!(target: "db", "Pruning bodies of blocks #{} to #{}", number, prune_to);
+			while number <= prune_to {
+				self.prune_block_body(transaction, indexed_refs, BlockId::Number(number))?;
+				number += One::one();
+			}
+			transaction.set_from_vec(columns::META, meta_keys::PRUNED_BODIES, prune_to.encode());
+		}
+		Ok(())
+	}
+
+	/// Number of the last block whose body was pruned, including by the pending `transaction`,
+	/// since blocks may be finalized several times before it is committed.
+	fn last_pruned_block(
+		&self,
+		transaction: &Transaction<DbHash>,
+	) -> ClientResult<Option<NumberFor<Block>>> {
+		let pending = transaction.0.iter().rev().find_map(|change| match change {
+			Change::Set(col, key, value)
+				if *col == columns::META && key[..] == meta_keys::PRUNED_BODIES[..] => Some(value.clone()),
+			_ => None,
+		});
+		match pending.or_else(|| self.storage.db.get(columns::META, meta_keys::PRUNED_BODIES)) {
+			Some(encoded) => NumberFor::<Block>::decode(&mut &encoded[..])
+				.map(Some)
+				.map_err(|e| sp_blockchain::Error::Backend(format!("Error decoding pruned block number: {}", e))),
+			None => Ok(None),
+		}
+	}
+
+	/// Remove the body of the given block, releasing the indexed transactions it references.
+	fn prune_block_body(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		indexed_refs: &mut HashMap<DbHash, u32>,
+		id: BlockId<Block>,
+	) -> ClientResult<()> {
+		let lookup_key = match utils::block_id_to_lookup_key(&*self.storage.db, columns::KEY_LOOKUP, id)? {
+			Some(key) => key,
+			None => return Ok(()),
+		};
+		if let TransactionStorageMode::StorageChain = self.blockchain.transaction_storage {
+			if let Some(body) = self.storage.db.get(columns::BODY, &lookup_key) {
+				release_indexed_body::<Block>(
+					transaction,
+					&*self.storage.db,
+					indexed_refs,
+					&body,
+				)?;
+			}
+		}
+		transaction.remove(columns::BODY, &lookup_key);
+		Ok(())
+	}
+
 	/// Handle setting head within a transaction. `route_to` should be the last
 	/// block that existed in the database. `best_to` should be the best block
 	/// to be set.
@@ -1008,6 +1447,7 @@ impl<Block: BlockT> Backend<Block> {
 		justification: Option<Justification>,
 		changes_trie_cache_ops: &mut Option<DbChangesTrieStorageTransaction<Block>>,
 		finalization_displaced: &mut Option<FinalizationDisplaced<Block::Hash, NumberFor<Block>>>,
+		indexed_refs: &mut HashMap<DbHash, u32>,
 	) -> ClientResult<(Block::Hash, <Block::Header as HeaderT>::Number, bool, bool)> {
 		// TODO: ensure best chain contains this block.
 		let number = *header.number();
@@ -1020,6 +1460,7 @@ impl<Block: BlockT> Backend<Block> {
 			*hash,
 			changes_trie_cache_ops,
 			finalization_displaced,
+			indexed_refs,
 		)?;
 
 		if let Some(justification) = justification {
@@ -1074,12 +1515,13 @@ impl<Block: BlockT> Backend<Block> {
 		let mut finalization_displaced_leaves = None;
 
 		operation.apply_aux(&mut transaction);
-		operation.apply_offchain(&mut transaction);
+		let offchain_fork_aware_changes = operation.apply_offchain(&mut transaction);
 
 		let mut meta_updates = Vec::with_capacity(operation.finalized_blocks.len());
 		let mut last_finalized_hash = self.blockchain.meta.read().finalized_hash;
 
 		let mut changes_trie_cache_ops = None;
+		let mut indexed_refs = HashMap::new();
 		for (block, justification) in operation.finalized_blocks {
 			let block_hash = self.blockchain.expect_block_hash_from_id(&block)?;
 			let block_header = self.blockchain.expect_header(BlockId::Hash(block_hash))?;
@@ -1092,6 +1534,7 @@ impl<Block: BlockT> Backend<Block> {
 				justification,
 				&mut changes_trie_cache_ops,
 				&mut finalization_displaced_leaves,
+				&mut indexed_refs,
 			)?);
 			last_finalized_hash = block_hash;
 		}
@@ -1124,8 +1567,29 @@ impl<Block: BlockT> Backend<Block> {
 			);
 
 			transaction.set_from_vec(columns::HEADER, &lookup_key, pending_block.header.encode());
-			if let Some(body) = &pending_block.body {
-				transaction.set_from_vec(columns::BODY, &lookup_key, body.encode());
+			self.offchain_storage.note_block(
+				&mut transaction,
+				hash.as_ref(),
+				parent_hash.as_ref(),
+				number.saturated_into::<u64>(),
+				offchain_fork_aware_changes.into_iter(),
+			);
+			if let Some(body) = pending_block.body {
+				match self.blockchain.transaction_storage {
+					TransactionStorageMode::BlockBody => {
+						transaction.set_from_vec(columns::BODY, &lookup_key, body.encode());
+					},
+					TransactionStorageMode::StorageChain => {
+						let body = apply_index_ops::<Block>(
+							&mut transaction,
+							&*self.storage.db,
+							&mut indexed_refs,
+							body,
+							operation.index_ops,
+						);
+						transaction.set_from_vec(columns::BODY, &lookup_key, body);
+					},
+				}
 			}
 			if let Some(justification) = pending_block.justification {
 				transaction.set_from_vec(columns::JUSTIFICATION, &lookup_key, justification.encode());
@@ -1238,6 +1702,7 @@ impl<Block: BlockT> Backend<Block> {
 					hash,
 					&mut changes_trie_cache_ops,
 					&mut finalization_displaced_leaves,
+					&mut indexed_refs,
 				)?;
 			} else {
 				// canonicalize blocks which are old enough, regardless of finality.
@@ -1343,7 +1808,8 @@ impl<Block: BlockT> Backend<Block> {
 		f_header: &Block::Header,
 		f_hash: Block::Hash,
 		changes_trie_cache_ops: &mut Option<DbChangesTrieStorageTransaction<Block>>,
-		displaced: &mut Option<FinalizationDisplaced<Block::Hash, NumberFor<Block>>>
+		displaced: &mut Option<FinalizationDisplaced<Block::Hash, NumberFor<Block>>>,
+		indexed_refs: &mut HashMap<DbHash, u32>,
 	) -> ClientResult<()> {
 		let f_num = f_header.number().clone();
 
@@ -1368,6 +1834,12 @@ impl<Block: BlockT> Backend<Block> {
 			}
 		}
 
+		let commit = self.storage.state_db.note_finalized(f_num.saturated_into::<u64>());
+		apply_state_commit(transaction, commit);
+		self.prune_blocks(transaction, indexed_refs, f_num)?;
+
+		self.offchain_storage.canonicalize_block(transaction, f_hash.as_ref());
+
 		let new_displaced = self.blockchain.leaves.write().finalize_height(f_num);
 		match displaced {
 			x @ &mut None => *x = Some(new_displaced),
@@ -1441,6 +1913,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			finalized_blocks: Vec::new(),
 			set_head: None,
 			commit_state: false,
+			index_ops: Default::default(),
 		})
 	}
 
@@ -1466,10 +1939,12 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		match self.try_commit_operation(operation) {
 			Ok(_) => {
 				self.storage.state_db.apply_pending();
+				self.offchain_storage.apply_pending();
 				Ok(())
 			},
 			e @ Err(_) => {
 				self.storage.state_db.revert_pending();
+				self.offchain_storage.revert_pending();
 				e
 			}
 		}
@@ -1486,7 +1961,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		let mut displaced = None;
 
 		let mut changes_trie_cache_ops = None;
-		let (hash, number, is_best, is_finalized) = self.finalize_block_with_transaction(
+		let finalized = self.finalize_block_with_transaction(
 			&mut transaction,
 			&hash,
 			&header,
@@ -1494,8 +1969,23 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			justification,
 			&mut changes_trie_cache_ops,
 			&mut displaced,
-		)?;
-		self.storage.db.commit(transaction)?;
+			&mut HashMap::new(),
+		).and_then(|finalized| {
+			self.storage.db.commit(transaction)?;
+			Ok(finalized)
+		});
+		let (hash, number, is_best, is_finalized) = match finalized {
+			Ok(finalized) => {
+				self.storage.state_db.apply_pending();
+				self.offchain_storage.apply_pending();
+				finalized
+			},
+			Err(e) => {
+				self.storage.state_db.revert_pending();
+				self.offchain_storage.revert_pending();
+				return Err(e);
+			},
+		};
 		self.blockchain.update_meta(hash, number, is_best, is_finalized);
 		self.changes_tries_storage.post_commit(changes_trie_cache_ops);
 		Ok(())
@@ -1612,9 +2102,15 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 							reverted_finalized.insert(removed_hash);
 						}
 						transaction.set_from_vec(columns::META, meta_keys::BEST_BLOCK, key);
+						self.prune_block_body(&mut transaction, BlockId::Hash(removed_hash))?;
 						transaction.remove(columns::KEY_LOOKUP, removed.hash().as_ref());
+						self.offchain_storage.revert_block(&mut transaction, removed_hash.as_ref());
 						children::remove_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, best_hash);
-						self.storage.db.commit(transaction)?;
+						if let Err(e) = self.storage.db.commit(transaction) {
+							self.offchain_storage.revert_pending();
+							return Err(e.into());
+						}
+						self.offchain_storage.apply_pending();
 						self.changes_tries_storage.post_commit(Some(changes_trie_cache_ops));
 						self.blockchain.update_meta(best_hash, best_number, true, update_finalized);
 					}
@@ -1814,6 +2310,36 @@ pub(crate) mod tests {
 		header_hash
 	}
 
+	fn insert_block(
+		backend: &Backend<Block>,
+		number: u64,
+		parent_hash: H256,
+		body: Vec<ExtrinsicWrapper<u64>>,
+		index_ops: Vec<IndexOperation>,
+	) -> H256 {
+		let header = Header {
+			number,
+			parent_hash,
+			state_root: BlakeTwo256::trie_root(Vec::new()),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		let header_hash = header.hash();
+
+		let block_id = if number == 0 {
+			BlockId::Hash(Default::default())
+		} else {
+			BlockId::Number(number - 1)
+		};
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, block_id).unwrap();
+		op.set_block_data(header, Some(body), None, NewBlockState::Best).unwrap();
+		op.update_transaction_index(index_ops).unwrap();
+		backend.commit_operation(op).unwrap();
+
+		header_hash
+	}
+
 	#[test]
 	fn block_hash_inserted_correctly() {
 		let backing = {
@@ -1861,6 +2387,8 @@ pub(crate) mod tests {
 			state_cache_child_ratio: Some((50, 100)),
 			pruning: PruningMode::keep_blocks(1),
 			source: DatabaseSettingsSrc::Custom(backing),
+			transaction_storage: TransactionStorageMode::BlockBody,
+			keep_blocks: KeepBlocks::All,
 		}, 0).unwrap();
 		assert_eq!(backend.blockchain().info().best_number, 9);
 		for i in 0..10 {
@@ -2380,6 +2908,51 @@ pub(crate) mod tests {
 		}
 	}
 
+	#[test]
+	fn offchain_indexed_changes_follow_their_fork() {
+		use sp_core::offchain::OffchainStorage;
+
+		let backend = Backend::<Block>::new_test(10, 10);
+		let insert = |number, parent_hash, extrinsics_root, state, value: &[u8]| {
+			let header = Header {
+				number,
+				parent_hash,
+				state_root: BlakeTwo256::trie_root(Vec::new()),
+				digest: Default::default(),
+				extrinsics_root,
+			};
+			let hash = header.hash();
+			let mut changes = OffchainOverlayedChanges::enabled();
+			changes.set(LOCAL_STORAGE_PREFIX, b"key", value);
+			changes.set(STORAGE_PREFIX, b"persistent", value);
+
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(parent_hash)).unwrap();
+			op.update_offchain_storage(changes).unwrap();
+			op.set_block_data(header, Some(Vec::new()), None, state).unwrap();
+			backend.commit_operation(op).unwrap();
+			hash
+		};
+
+		let block0 = insert(0, Default::default(), Default::default(), NewBlockState::Final, b"0");
+		let block1_a = insert(1, block0, H256::from([1; 32]), NewBlockState::Best, b"a");
+		let block1_b = insert(1, block0, H256::from([2; 32]), NewBlockState::Normal, b"b");
+
+		let storage = backend.offchain_storage().unwrap();
+		let get_at = |block: H256| storage.get_at(block.as_ref(), STORAGE_PREFIX, b"key");
+		assert_eq!(get_at(block0), Some(b"0".to_vec()));
+		assert_eq!(get_at(block1_a), Some(b"a".to_vec()));
+		assert_eq!(get_at(block1_b), Some(b"b".to_vec()));
+		assert_eq!(storage.get(STORAGE_PREFIX, b"key"), None);
+
+		// persistent indexed changes are not fork-aware.
+		assert_eq!(storage.get(STORAGE_PREFIX, b"persistent"), Some(b"b".to_vec()));
+
+		backend.finalize_block(BlockId::Hash(block1_b), None).unwrap();
+		assert_eq!(get_at(block1_a), Some(b"b".to_vec()));
+		assert_eq!(get_at(block1_b), Some(b"b".to_vec()));
+	}
+
 	#[test]
 	fn header_cht_root_works() {
 		use sc_client_api::ProvideChtRoots;
@@ -2404,4 +2977,225 @@ pub(crate) mod tests {
 		assert_eq!(cht_root_1, cht_root_2);
 		assert_eq!(cht_root_2, cht_root_3);
 	}
+
+	#[test]
+	fn indexed_data_block_body() {
+		let backend = Backend::<Block>::new_test_with_tx_storage(1, 10, TransactionStorageMode::StorageChain);
+
+		let x0 = ExtrinsicWrapper::from(0u64).encode();
+		let x1 = ExtrinsicWrapper::from(1u64).encode();
+		let x0_hash = <HashFor<Block> as sp_core::Hasher>::hash(&x0[1..]);
+		let x1_hash = <HashFor<Block> as sp_core::Hasher>::hash(&x1[1..]);
+		let index = vec![
+			IndexOperation::Insert {
+				extrinsic: 0,
+				hash: x0_hash.as_ref().to_vec(),
+				size: (x0.len() - 1) as u32,
+			},
+			IndexOperation::Insert {
+				extrinsic: 1,
+				hash: x1_hash.as_ref().to_vec(),
+				size: (x1.len() - 1) as u32,
+			},
+		];
+		let hash = insert_block(
+			&backend,
+			0,
+			Default::default(),
+			vec![0u64.into(), 1u64.into()],
+			index,
+		);
+		let bc = backend.blockchain();
+		assert_eq!(bc.indexed_transaction(&x0_hash).unwrap().unwrap(), &x0[1..]);
+		assert_eq!(bc.indexed_transaction(&x1_hash).unwrap().unwrap(), &x1[1..]);
+		assert_eq!(
+			bc.body(BlockId::hash(hash)).unwrap().unwrap(),
+			vec![ExtrinsicWrapper::from(0u64), ExtrinsicWrapper::from(1u64)],
+		);
+		assert_eq!(
+			bc.block_indexed_body(BlockId::hash(hash)).unwrap().unwrap(),
+			vec![x0[1..].to_vec(), x1[1..].to_vec()],
+		);
+	}
+
+	#[test]
+	fn renewed_index_is_released_on_revert() {
+		let backend = Backend::<Block>::new_test_with_tx_storage(10, 10, TransactionStorageMode::StorageChain);
+
+		let x1 = ExtrinsicWrapper::from(1u64).encode();
+		let x1_hash = <HashFor<Block> as sp_core::Hasher>::hash(&x1[1..]);
+		let genesis = insert_block(&backend, 0, Default::default(), Vec::new(), Vec::new());
+		let block1 = insert_block(
+			&backend,
+			1,
+			genesis,
+			vec![1u64.into()],
+			vec![IndexOperation::Insert {
+				extrinsic: 0,
+				hash: x1_hash.as_ref().to_vec(),
+				size: (x1.len() - 1) as u32,
+			}],
+		);
+		let block2 = insert_block(
+			&backend,
+			2,
+			block1,
+			vec![2u64.into()],
+			vec![IndexOperation::Renew {
+				extrinsic: 0,
+				hash: x1_hash.as_ref().to_vec(),
+			}],
+		);
+		let bc = backend.blockchain();
+		assert_eq!(bc.body(BlockId::hash(block2)).unwrap().unwrap(), vec![ExtrinsicWrapper::from(2u64)]);
+		assert_eq!(bc.indexed_transaction(&x1_hash).unwrap().unwrap(), &x1[1..]);
+
+		// reverting the renewal keeps the data referenced by block 1.
+		assert_eq!(backend.revert(1, false).unwrap().0, 1);
+		assert_eq!(bc.indexed_transaction(&x1_hash).unwrap().unwrap(), &x1[1..]);
+		assert_eq!(bc.body(BlockId::hash(block1)).unwrap().unwrap(), vec![ExtrinsicWrapper::from(1u64)]);
+
+		// reverting the block that introduced the data removes it.
+		assert_eq!(backend.revert(1, false).unwrap().0, 1);
+		assert_eq!(bc.indexed_transaction(&x1_hash).unwrap(), None);
+	}
+
+	#[test]
+	fn prunes_block_bodies_after_finalization() {
+		let backend = Backend::<Block>::new_test_with_block_pruning(10, 2, 10);
+		let mut blocks = Vec::new();
+		let mut prev_hash = Default::default();
+		for i in 0..5 {
+			let hash = insert_block(&backend, i, prev_hash, vec![i.into()], Vec::new());
+			blocks.push(hash);
+			prev_hash = hash;
+		}
+
+		for hash in blocks.iter() {
+			backend.finalize_block(BlockId::Hash(*hash), Some(vec![1, 2, 3])).unwrap();
+		}
+
+		let bc = backend.blockchain();
+		for (i, hash) in blocks.iter().enumerate() {
+			assert!(bc.header(BlockId::hash(*hash)).unwrap().is_some());
+			assert_eq!(bc.hash(i as u64).unwrap(), Some(*hash));
+			assert_eq!(bc.justification(BlockId::hash(*hash)).unwrap(), Some(vec![1, 2, 3]));
+		}
+		assert_eq!(bc.body(BlockId::hash(blocks[0])).unwrap(), None);
+		assert_eq!(bc.body(BlockId::hash(blocks[2])).unwrap(), None);
+		assert_eq!(bc.body(BlockId::hash(blocks[3])).unwrap(), Some(vec![3u64.into()]));
+		assert_eq!(bc.body(BlockId::hash(blocks[4])).unwrap(), Some(vec![4u64.into()]));
+	}
+
+	#[test]
+	fn prunes_all_block_bodies_out_of_window() {
+		// blocks are canonicalized on import, so that finalization may skip blocks.
+		let backend = Backend::<Block>::new_test_with_block_pruning(10, 2, 0);
+		let mut blocks = Vec::new();
+		let mut prev_hash = Default::default();
+		for i in 0..8 {
+			let hash = insert_block(&backend, i, prev_hash, vec![i.into()], Vec::new());
+			blocks.push(hash);
+			prev_hash = hash;
+		}
+
+		// finalizing several blocks at once prunes everything out of the window.
+		let mut op = backend.begin_operation().unwrap();
+		backend.begin_state_operation(&mut op, BlockId::Hash(blocks[4])).unwrap();
+		for hash in &blocks[1..5] {
+			op.mark_finalized(BlockId::Hash(*hash), None).unwrap();
+		}
+		backend.commit_operation(op).unwrap();
+
+		let bc = backend.blockchain();
+		for hash in &blocks[..3] {
+			assert_eq!(bc.body(BlockId::hash(*hash)).unwrap(), None);
+		}
+		assert_eq!(bc.body(BlockId::hash(blocks[3])).unwrap(), Some(vec![3u64.into()]));
+
+		// and finalizing far ahead prunes the whole range.
+		backend.finalize_block(BlockId::Hash(blocks[7]), None).unwrap();
+		for hash in &blocks[..6] {
+			assert_eq!(bc.body(BlockId::hash(*hash)).unwrap(), None);
+		}
+		assert_eq!(bc.body(BlockId::hash(blocks[6])).unwrap(), Some(vec![6u64.into()]));
+		assert_eq!(
+			backend.storage.db.get(columns::META, meta_keys::PRUNED_BODIES),
+			Some(5u64.encode()),
+		);
+	}
+
+	#[test]
+	fn prunes_block_bodies_in_batches() {
+		let max = MAX_PRUNED_BODIES_PER_FINALIZATION as u64;
+		// blocks are canonicalized on import, so that finalization may skip blocks.
+		let backend = Backend::<Block>::new_test_with_block_pruning(100, 2, 0);
+		let mut blocks = Vec::new();
+		let mut prev_hash = Default::default();
+		for i in 0..max + 8 {
+			let hash = insert_block(&backend, i, prev_hash, vec![i.into()], Vec::new());
+			blocks.push(hash);
+			prev_hash = hash;
+		}
+
+		// at most `MAX_PRUNED_BODIES_PER_FINALIZATION` bodies are pruned at once.
+		backend.finalize_block(BlockId::Hash(blocks[max as usize + 6]), None).unwrap();
+		let bc = backend.blockchain();
+		assert_eq!(bc.body(BlockId::hash(blocks[max as usize - 1])).unwrap(), None);
+		assert_eq!(bc.body(BlockId::hash(blocks[max as usize])).unwrap(), Some(vec![max.into()]));
+
+		// the rest is pruned when the next block is finalized.
+		backend.finalize_block(BlockId::Hash(blocks[max as usize + 7]), None).unwrap();
+		assert_eq!(bc.body(BlockId::hash(blocks[max as usize + 5])).unwrap(), None);
+		assert_eq!(
+			bc.body(BlockId::hash(blocks[max as usize + 6])).unwrap(),
+			Some(vec![(max + 6).into()]),
+		);
+	}
+
+	#[test]
+	fn prunes_indexed_transaction_referenced_by_several_bodies() {
+		let db = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		// blocks are canonicalized on import, so that finalization may skip blocks.
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			pruning: PruningMode::keep_blocks(10),
+			source: DatabaseSettingsSrc::Custom(db),
+			transaction_storage: TransactionStorageMode::StorageChain,
+			keep_blocks: KeepBlocks::Some(1),
+		}, 0).unwrap();
+
+		let x1 = ExtrinsicWrapper::from(1u64).encode();
+		let x1_hash = <HashFor<Block> as sp_core::Hasher>::hash(&x1[1..]);
+		let genesis = insert_block(&backend, 0, Default::default(), Vec::new(), Vec::new());
+		let block1 = insert_block(
+			&backend,
+			1,
+			genesis,
+			vec![1u64.into()],
+			vec![IndexOperation::Insert {
+				extrinsic: 0,
+				hash: x1_hash.as_ref().to_vec(),
+				size: (x1.len() - 1) as u32,
+			}],
+		);
+		let block2 = insert_block(
+			&backend,
+			2,
+			block1,
+			vec![2u64.into()],
+			vec![IndexOperation::Renew {
+				extrinsic: 0,
+				hash: x1_hash.as_ref().to_vec(),
+			}],
+		);
+		let block3 = insert_block(&backend, 3, block2, Vec::new(), Vec::new());
+
+		// both bodies referencing the data are pruned in the same transaction.
+		backend.finalize_block(BlockId::Hash(block3), None).unwrap();
+		let bc = backend.blockchain();
+		assert_eq!(bc.body(BlockId::hash(block2)).unwrap(), None);
+		assert_eq!(bc.indexed_transaction(&x1_hash).unwrap(), None);
+	}
 }
@@ -0,0 +1,638 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Versioned database migrations.
+//!
+//! The database version is kept in a file next to the database. Every migration step upgrades
+//! the database by one version: it first adds the columns the new layout requires and then
+//! rewrites the entries of the columns it is interested in. Entries are migrated in batches and
+//! the position of the last migrated entry is committed together with every batch, so that an
+//! interrupted migration resumes where it stopped.
+
+use std::fs;
+use std::io::{Read, Write, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use codec::{Decode, Encode};
+use log::info;
+use sp_database::{ColumnId, Transaction};
+use crate::{DatabaseSettingsSrc, DatabaseType, DbHash};
+use crate::utils::COLUMN_META;
+
+/// Version file name.
+const VERSION_FILE_NAME: &'static str = "db_version";
+
+/// Current db version.
+pub const CURRENT_VERSION: u32 = 2;
+
+/// Number of columns in v1.
+pub const V1_NUM_COLUMNS: u32 = 11;
+
+/// Key of the migration checkpoint in the META column.
+const CHECKPOINT_KEY: &[u8] = b"migration";
+
+/// A database migrations are applied to.
+pub trait MigrationDb {
+	/// Number of columns the database currently has.
+	fn num_columns(&self) -> u32;
+
+	/// Adds a column after the existing ones.
+	fn add_column(&mut self) -> sp_blockchain::Result<()>;
+
+	/// Iterates over the entries of a column in key order, starting after `start_after`,
+	/// for as long as `f` returns `true`.
+	///
+	/// Backends that can't enumerate the keys of a column return an error, so that steps
+	/// migrating column entries fail loudly instead of leaving the data unmigrated.
+	fn iter_column(
+		&self,
+		col: ColumnId,
+		start_after: Option<&[u8]>,
+		f: &mut dyn FnMut(&[u8], &[u8]) -> sp_blockchain::Result<bool>,
+	) -> sp_blockchain::Result<()>;
+
+	/// Reads a single entry.
+	fn get(&self, col: ColumnId, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>>;
+
+	/// Atomically applies a transaction.
+	fn commit(&self, transaction: Transaction<DbHash>) -> sp_blockchain::Result<()>;
+
+	/// Whether `iter_column` is supported for all the columns.
+	fn can_iterate(&self) -> bool {
+		true
+	}
+
+	/// Whether the state column is reference counted, in which case state nodes are keyed by
+	/// their hash only.
+	fn supports_ref_counting(&self) -> bool {
+		false
+	}
+}
+
+/// A single step upgrading the database to the next version.
+pub trait MigrationStep: Send + Sync {
+	/// Version of the database after the step is applied.
+	fn version(&self) -> u32;
+
+	/// Short description of the step.
+	fn name(&self) -> &'static str;
+
+	/// Number of columns of the database after the step is applied, if the step adds columns.
+	fn num_columns(&self) -> Option<u32> {
+		None
+	}
+
+	/// Columns whose entries are passed to `migrate_entry`.
+	fn columns(&self) -> Vec<ColumnId> {
+		Vec::new()
+	}
+
+	/// Migrates a single entry of one of `columns` by adding changes to `transaction`.
+	fn migrate_entry(
+		&self,
+		_col: ColumnId,
+		_key: &[u8],
+		_value: &[u8],
+		_transaction: &mut Transaction<DbHash>,
+	) -> sp_blockchain::Result<()> {
+		Ok(())
+	}
+}
+
+/// Migration settings.
+#[derive(Debug, Clone)]
+pub struct MigrationConfig {
+	/// Only report what would be migrated, without writing anything.
+	pub dry_run: bool,
+	/// Number of entries migrated in a single database transaction.
+	pub batch_size: usize,
+}
+
+impl Default for MigrationConfig {
+	fn default() -> Self {
+		MigrationConfig {
+			dry_run: false,
+			batch_size: 10_000,
+		}
+	}
+}
+
+/// Progress of a running migration step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationProgress {
+	/// Version the step upgrades the database to.
+	pub version: u32,
+	/// Name of the step.
+	pub step: &'static str,
+	/// Column being migrated, `None` when the step has finished.
+	pub column: Option<ColumnId>,
+	/// Number of entries of the column migrated so far.
+	pub entries: u64,
+	/// Number of database changes the step made (or would make in a dry run) so far.
+	pub changes: u64,
+	/// Whether this is a dry run.
+	pub dry_run: bool,
+}
+
+/// Outcome of running the migrations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationSummary {
+	/// Version of the database before the migration.
+	pub from: u32,
+	/// Version of the database after the migration.
+	pub to: u32,
+	/// Number of entries migrated.
+	pub entries: u64,
+	/// Number of database changes made (or that would be made in a dry run).
+	pub changes: u64,
+}
+
+/// Position of the last migrated entry, committed together with every batch.
+#[derive(Debug, Encode, Decode)]
+struct Checkpoint {
+	version: u32,
+	column: ColumnId,
+	last_key: Vec<u8>,
+}
+
+/// Adds columns to the database.
+pub struct AddColumns {
+	version: u32,
+	name: &'static str,
+	num_columns: u32,
+}
+
+impl MigrationStep for AddColumns {
+	fn version(&self) -> u32 {
+		self.version
+	}
+
+	fn name(&self) -> &'static str {
+		self.name
+	}
+
+	fn num_columns(&self) -> Option<u32> {
+		Some(self.num_columns)
+	}
+}
+
+/// All known migration steps, ordered by version.
+pub fn steps() -> Vec<Box<dyn MigrationStep>> {
+	vec![
+		Box::new(AddColumns { version: 2, name: "Add transactions column", num_columns: 12 }),
+	]
+}
+
+/// Number of columns of the database at given version.
+pub fn num_columns(version: u32, steps: &[Box<dyn MigrationStep>]) -> u32 {
+	steps.iter()
+		.filter(|step| step.version() <= version)
+		.filter_map(|step| step.num_columns())
+		.last()
+		.unwrap_or(V1_NUM_COLUMNS)
+}
+
+/// Checks that the database at given version can be migrated.
+pub fn check_version(version: u32) -> sp_blockchain::Result<u32> {
+	match version {
+		0 => Err(sp_blockchain::Error::Backend(format!("Unsupported database version: {}", version))),
+		v if v > CURRENT_VERSION => Err(sp_blockchain::Error::Backend(format!("Future database version: {}", v))),
+		v => Ok(v),
+	}
+}
+
+/// Checks that the database at given version doesn't need to be migrated.
+pub fn check_current(version: u32) -> sp_blockchain::Result<()> {
+	match check_version(version)? {
+		CURRENT_VERSION => Ok(()),
+		v => Err(sp_blockchain::Error::Backend(
+			format!("Database version {} is outdated, run `db migrate` first", v)
+		)),
+	}
+}
+
+/// Applies all steps above `version` to the database at `path`.
+///
+/// The version file is updated after every step, unless this is a dry run.
+pub fn migrate(
+	db: &mut dyn MigrationDb,
+	path: &Path,
+	version: u32,
+	steps: &[Box<dyn MigrationStep>],
+	config: &MigrationConfig,
+	report: &mut dyn FnMut(&MigrationProgress),
+) -> sp_blockchain::Result<MigrationSummary> {
+	let mut summary = MigrationSummary { from: version, to: version, entries: 0, changes: 0 };
+	for step in steps.iter().filter(|step| step.version() > version) {
+		if step.version() != summary.to + 1 {
+			return Err(sp_blockchain::Error::Backend(
+				format!("Missing database migration to version {}", summary.to + 1)
+			));
+		}
+
+		info!(
+			target: "db",
+			"{}Migrating database to version {}: {}",
+			if config.dry_run { "[dry run] " } else { "" },
+			step.version(),
+			step.name(),
+		);
+		let (entries, changes) = run_step(db, &**step, config, report)?;
+		if !config.dry_run {
+			update_version(path, step.version())?;
+		}
+		summary.to = step.version();
+		summary.entries += entries;
+		summary.changes += changes;
+	}
+	Ok(summary)
+}
+
+fn run_step(
+	db: &mut dyn MigrationDb,
+	step: &dyn MigrationStep,
+	config: &MigrationConfig,
+	report: &mut dyn FnMut(&MigrationProgress),
+) -> sp_blockchain::Result<(u64, u64)> {
+	let checkpoint = match db.get(COLUMN_META, CHECKPOINT_KEY)? {
+		Some(encoded) => Some(Checkpoint::decode(&mut &encoded[..])
+			.map_err(|e| sp_blockchain::Error::Backend(format!("Invalid migration checkpoint: {}", e)))?)
+			.filter(|checkpoint| checkpoint.version == step.version()),
+		None => None,
+	};
+	if let Some(checkpoint) = &checkpoint {
+		info!(target: "db", "Resuming migration from column {}", checkpoint.column);
+	}
+
+	let mut changes = 0u64;
+	if let Some(num_columns) = step.num_columns() {
+		let missing = num_columns.saturating_sub(db.num_columns());
+		changes += missing as u64;
+		if config.dry_run {
+			if missing > 0 {
+				info!(target: "db", "[dry run] Would add {} column(s)", missing);
+			}
+		} else {
+			for _ in 0..missing {
+				db.add_column()?;
+			}
+		}
+	}
+
+	let mut columns = step.columns();
+	columns.sort();
+	let mut total_entries = 0u64;
+	for col in columns {
+		if checkpoint.as_ref().map_or(false, |checkpoint| col < checkpoint.column) {
+			continue;
+		}
+		if col >= db.num_columns() {
+			// Only happens in a dry run, when the column has not been added.
+			continue;
+		}
+
+		let start_after = checkpoint.as_ref()
+			.filter(|checkpoint| checkpoint.column == col)
+			.map(|checkpoint| checkpoint.last_key.clone());
+		let mut progress = MigrationProgress {
+			version: step.version(),
+			step: step.name(),
+			column: Some(col),
+			entries: 0,
+			changes,
+			dry_run: config.dry_run,
+		};
+		let mut transaction = Transaction::new();
+		let mut batch = 0;
+		let mut last_key = None;
+		{
+			let db = &*db;
+			db.iter_column(col, start_after.as_ref().map(|key| &key[..]), &mut |key, value| {
+				let before = transaction.0.len();
+				step.migrate_entry(col, key, value, &mut transaction)?;
+				progress.changes += (transaction.0.len() - before) as u64;
+				progress.entries += 1;
+				batch += 1;
+				if batch >= config.batch_size {
+					let transaction = std::mem::replace(&mut transaction, Transaction::new());
+					commit_batch(db, transaction, step.version(), col, key, config.dry_run)?;
+					batch = 0;
+					report(&progress);
+				}
+				last_key = Some(key.to_vec());
+				Ok(true)
+			})?;
+			if let Some(key) = last_key {
+				commit_batch(db, transaction, step.version(), col, &key, config.dry_run)?;
+			}
+		}
+		report(&progress);
+		total_entries += progress.entries;
+		changes = progress.changes;
+	}
+
+	if !config.dry_run {
+		let mut transaction = Transaction::new();
+		transaction.remove(COLUMN_META, CHECKPOINT_KEY);
+		db.commit(transaction)?;
+	}
+	report(&MigrationProgress {
+		version: step.version(),
+		step: step.name(),
+		column: None,
+		entries: total_entries,
+		changes,
+		dry_run: config.dry_run,
+	});
+	Ok((total_entries, changes))
+}
+
+fn commit_batch(
+	db: &dyn MigrationDb,
+	mut transaction: Transaction<DbHash>,
+	version: u32,
+	column: ColumnId,
+	last_key: &[u8],
+	dry_run: bool,
+) -> sp_blockchain::Result<()> {
+	if dry_run {
+		return Ok(());
+	}
+	let checkpoint = Checkpoint { version, column, last_key: last_key.to_vec() };
+	transaction.set_from_vec(COLUMN_META, CHECKPOINT_KEY, checkpoint.encode());
+	db.commit(transaction)
+}
+
+/// Logs migration progress.
+///
+/// Used when the database is opened with migrations pending, nodes run the migrations
+/// beforehand with `migrate_database` to display their progress with the informant.
+pub fn log_progress(progress: &MigrationProgress) {
+	match progress.column {
+		Some(col) => info!(
+			target: "db",
+			"Migrating to version {}: column {}, {} entries, {} changes",
+			progress.version,
+			col,
+			progress.entries,
+			progress.changes,
+		),
+		None => info!(
+			target: "db",
+			"Migrated to version {}: {} entries, {} changes",
+			progress.version,
+			progress.entries,
+			progress.changes,
+		),
+	}
+}
+
+/// Runs all pending migrations of a database without opening it for the node.
+#[allow(unused_variables)]
+pub fn migrate_database(
+	source: &DatabaseSettingsSrc,
+	db_type: DatabaseType,
+	config: &MigrationConfig,
+	report: &mut dyn FnMut(&MigrationProgress),
+) -> sp_blockchain::Result<MigrationSummary> {
+	match source {
+		#[cfg(any(feature = "with-kvdb-rocksdb", test))]
+		DatabaseSettingsSrc::RocksDb { path, .. } => crate::upgrade::migrate_rocksdb(path, config, report),
+		#[cfg(feature = "with-parity-db")]
+		DatabaseSettingsSrc::ParityDb { path } =>
+			crate::parity_db::migrate(path, db_type, config, report),
+		_ => Err(sp_blockchain::Error::Backend(
+			format!("Database migrations are not supported for {} database", source)
+		)),
+	}
+}
+
+/// Reads current database version from the file at given path.
+/// If the file does not exist returns 0.
+pub(crate) fn current_version(path: &Path) -> sp_blockchain::Result<u32> {
+	let unknown_version_err = || sp_blockchain::Error::Backend("Unknown database version".into());
+
+	match fs::File::open(version_file_path(path)) {
+		Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(0),
+		Err(_) => Err(unknown_version_err()),
+		Ok(mut file) => {
+			let mut s = String::new();
+			file.read_to_string(&mut s).map_err(|_| unknown_version_err())?;
+			u32::from_str_radix(&s, 10).map_err(|_| unknown_version_err())
+		},
+	}
+}
+
+/// Maps database error to client error
+pub(crate) fn db_err(err: std::io::Error) -> sp_blockchain::Error {
+	sp_blockchain::Error::Backend(format!("{}", err))
+}
+
+/// Writes the database version to the file.
+/// Creates a new file if the version file does not exist yet.
+pub(crate) fn update_version(path: &Path, version: u32) -> sp_blockchain::Result<()> {
+	fs::create_dir_all(path).map_err(db_err)?;
+	let mut file = fs::File::create(version_file_path(path)).map_err(db_err)?;
+	file.write_all(format!("{}", version).as_bytes()).map_err(db_err)?;
+	Ok(())
+}
+
+/// Returns the version file path.
+pub(crate) fn version_file_path(path: &Path) -> PathBuf {
+	let mut file_path = path.to_owned();
+	file_path.push(VERSION_FILE_NAME);
+	file_path
+}
+
+/// Returns true if there is no database at given path.
+pub(crate) fn is_empty(path: &Path) -> bool {
+	path.read_dir().map_or(true, |mut d| d.next().is_none())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::BTreeMap;
+	use parking_lot::RwLock;
+	use sp_database::Change;
+
+	struct TestDb {
+		columns: RwLock<Vec<BTreeMap<Vec<u8>, Vec<u8>>>>,
+		fail_after: Option<usize>,
+		commits: RwLock<usize>,
+	}
+
+	impl TestDb {
+		fn new(num_columns: u32) -> Self {
+			TestDb {
+				columns: RwLock::new(vec![BTreeMap::new(); num_columns as usize]),
+				fail_after: None,
+				commits: RwLock::new(0),
+			}
+		}
+	}
+
+	impl MigrationDb for TestDb {
+		fn num_columns(&self) -> u32 {
+			self.columns.read().len() as u32
+		}
+
+		fn add_column(&mut self) -> sp_blockchain::Result<()> {
+			self.columns.write().push(BTreeMap::new());
+			Ok(())
+		}
+
+		fn iter_column(
+			&self,
+			col: ColumnId,
+			start_after: Option<&[u8]>,
+			f: &mut dyn FnMut(&[u8], &[u8]) -> sp_blockchain::Result<bool>,
+		) -> sp_blockchain::Result<()> {
+			let entries = self.columns.read()[col as usize].clone();
+			for (key, value) in entries {
+				if start_after.map_or(false, |start| &key[..] <= start) {
+					continue;
+				}
+				if !f(&key, &value)? {
+					break;
+				}
+			}
+			Ok(())
+		}
+
+		fn get(&self, col: ColumnId, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			Ok(self.columns.read()[col as usize].get(key).cloned())
+		}
+
+		fn commit(&self, transaction: Transaction<DbHash>) -> sp_blockchain::Result<()> {
+			let mut commits = self.commits.write();
+			if self.fail_after.map_or(false, |max| *commits >= max) {
+				return Err(sp_blockchain::Error::Backend("Interrupted".into()));
+			}
+			*commits += 1;
+			let mut columns = self.columns.write();
+			for change in transaction.0 {
+				match change {
+					Change::Set(col, key, value) => { columns[col as usize].insert(key, value); },
+					Change::Remove(col, key) => { columns[col as usize].remove(&key); },
+					_ => unimplemented!(),
+				}
+			}
+			Ok(())
+		}
+	}
+
+	/// Doubles every value of column 1.
+	struct DoubleValues;
+
+	impl MigrationStep for DoubleValues {
+		fn version(&self) -> u32 {
+			3
+		}
+
+		fn name(&self) -> &'static str {
+			"Double values"
+		}
+
+		fn columns(&self) -> Vec<ColumnId> {
+			vec![1]
+		}
+
+		fn migrate_entry(
+			&self,
+			col: ColumnId,
+			key: &[u8],
+			value: &[u8],
+			transaction: &mut Transaction<DbHash>,
+		) -> sp_blockchain::Result<()> {
+			transaction.set_from_vec(col, key, value.iter().map(|v| v * 2).collect());
+			Ok(())
+		}
+	}
+
+	fn test_steps() -> Vec<Box<dyn MigrationStep>> {
+		vec![
+			Box::new(AddColumns { version: 2, name: "Add column", num_columns: 3 }),
+			Box::new(DoubleValues),
+		]
+	}
+
+	fn fill(db: &TestDb) {
+		let mut transaction = Transaction::new();
+		for i in 0u8..10 {
+			transaction.set(1, &[i], &[i]);
+		}
+		db.commit(transaction).unwrap();
+		*db.commits.write() = 0;
+	}
+
+	fn values(db: &TestDb) -> Vec<u8> {
+		db.columns.read()[1].values().map(|v| v[0]).collect()
+	}
+
+	#[test]
+	fn migrates_and_updates_version() {
+		let dir = tempfile::TempDir::new().unwrap();
+		let mut db = TestDb::new(2);
+		fill(&db);
+		let config = MigrationConfig { dry_run: false, batch_size: 3 };
+		let mut reports = Vec::new();
+		let summary = migrate(&mut db, dir.path(), 1, &test_steps(), &config, &mut |p| reports.push(p.clone()))
+			.unwrap();
+
+		assert_eq!(summary, MigrationSummary { from: 1, to: 3, entries: 10, changes: 11 });
+		assert_eq!(db.num_columns(), 3);
+		assert_eq!(values(&db), (0u8..10).map(|v| v * 2).collect::<Vec<_>>());
+		assert_eq!(db.get(COLUMN_META, CHECKPOINT_KEY).unwrap(), None);
+		assert_eq!(current_version(dir.path()).unwrap(), 3);
+		assert_eq!(reports.last().unwrap().column, None);
+		assert_eq!(num_columns(1, &test_steps()), V1_NUM_COLUMNS);
+		assert_eq!(num_columns(3, &test_steps()), 3);
+	}
+
+	#[test]
+	fn dry_run_changes_nothing() {
+		let dir = tempfile::TempDir::new().unwrap();
+		let mut db = TestDb::new(3);
+		fill(&db);
+		let config = MigrationConfig { dry_run: true, batch_size: 3 };
+		let summary = migrate(&mut db, dir.path(), 2, &test_steps(), &config, &mut |_| {}).unwrap();
+
+		assert_eq!(summary, MigrationSummary { from: 2, to: 3, entries: 10, changes: 10 });
+		assert_eq!(values(&db), (0u8..10).collect::<Vec<_>>());
+		assert_eq!(current_version(dir.path()).unwrap(), 0);
+	}
+
+	#[test]
+	fn interrupted_migration_resumes() {
+		let dir = tempfile::TempDir::new().unwrap();
+		let mut db = TestDb::new(3);
+		fill(&db);
+		db.fail_after = Some(2);
+		let config = MigrationConfig { dry_run: false, batch_size: 3 };
+		assert!(migrate(&mut db, dir.path(), 2, &test_steps(), &config, &mut |_| {}).is_err());
+		assert_eq!(values(&db), vec![0, 2, 4, 6, 8, 10, 6, 7, 8, 9]);
+		assert!(db.get(COLUMN_META, CHECKPOINT_KEY).unwrap().is_some());
+
+		db.fail_after = None;
+		let summary = migrate(&mut db, dir.path(), 2, &test_steps(), &config, &mut |_| {}).unwrap();
+		assert_eq!(summary.entries, 4);
+		assert_eq!(values(&db), (0u8..10).map(|v| v * 2).collect::<Vec<_>>());
+		assert_eq!(current_version(dir.path()).unwrap(), 3);
+	}
+}
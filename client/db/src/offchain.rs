@@ -17,21 +17,41 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! RocksDB-based offchain workers local storage.
+//!
+//! Besides the persistent values, the storage keeps fork-aware values (see
+//! [`StorageKind::LOCAL`](sp_core::offchain::StorageKind::LOCAL)). Every fork-aware write is
+//! journaled against the block it was made at, similar to the non-canonical overlay of
+//! `sc-state-db`. Finality applies the journals of the finalized chain to the canonical
+//! fork-aware values and discards the journals of the abandoned forks.
+//!
+//! Like in `sc-state-db`, the journal changes made by the backend are pending until the backend
+//! commits them and calls [`LocalStorage::apply_pending`], or drops them with
+//! [`LocalStorage::revert_pending`].
 
 use std::{
-	collections::HashMap,
+	collections::{BTreeMap, BTreeSet, HashMap, HashSet},
 	sync::Arc,
 };
 
 use crate::{columns, Database, DbHash, Transaction};
+use codec::{Decode, Encode};
 use parking_lot::Mutex;
-use log::error;
+use log::{error, trace, warn};
+use sp_core::offchain::FORK_AWARE_PREFIX;
+
+/// Key of the range of numbers of the journaled blocks.
+const JOURNAL_RANGE_KEY: &[u8] = b"fork_aware_journal_range";
+/// Prefix of the lists of journaled blocks, per block number.
+const JOURNAL_NUMBER_PREFIX: &[u8] = b"fork_aware_journal_number/";
+/// Prefix of the per-block journals.
+const JOURNAL_PREFIX: &[u8] = b"fork_aware_journal/";
 
 /// Offchain local storage
 #[derive(Clone)]
 pub struct LocalStorage {
 	db: Arc<dyn Database<DbHash>>,
 	locks: Arc<Mutex<HashMap<Vec<u8>, Arc<Mutex<()>>>>>,
+	journal: Arc<Mutex<Journal>>,
 }
 
 impl std::fmt::Debug for LocalStorage {
@@ -52,13 +72,358 @@ impl LocalStorage {
 
 	/// Create offchain local storage with given `KeyValueDB` backend.
 	pub fn new(db: Arc<dyn Database<DbHash>>) -> Self {
+		let journal = Journal::load(&*db);
 		Self {
 			db,
 			locks: Default::default(),
+			journal: Arc::new(Mutex::new(journal)),
+		}
+	}
+
+	/// Journal the fork-aware changes made by the import of given block.
+	///
+	/// Every imported block needs to be noted, even without any changes, so that the journal
+	/// can find the ancestors of the blocks imported on top of it.
+	pub(crate) fn note_block(
+		&self,
+		transaction: &mut Transaction<DbHash>,
+		hash: &[u8],
+		parent_hash: &[u8],
+		number: u64,
+		changes: impl Iterator<Item = (Vec<u8>, Option<Vec<u8>>)>,
+	) {
+		let mut journal = self.journal.lock();
+		let entry = JournalEntry {
+			parent_hash: parent_hash.to_vec(),
+			number,
+			changes: changes.collect(),
+		};
+		journal.insert(transaction, hash, entry);
+		journal.write_range(transaction);
+	}
+
+	/// Make the fork-aware changes of given block and of all its journaled ancestors canonical.
+	///
+	/// Journals of blocks that are not descendants of the given block are discarded.
+	pub(crate) fn canonicalize_block(&self, transaction: &mut Transaction<DbHash>, hash: &[u8]) {
+		let mut journal = self.journal.lock();
+		if journal.canonicalize(transaction, hash) {
+			journal.write_range(transaction);
+		}
+	}
+
+	/// Discard the fork-aware changes made at given block.
+	pub(crate) fn revert_block(&self, transaction: &mut Transaction<DbHash>, hash: &[u8]) {
+		let mut journal = self.journal.lock();
+		if journal.remove(transaction, hash).is_some() {
+			journal.write_range(transaction);
+		}
+	}
+
+	/// Apply the pending journal changes, once the transaction they were made with is committed.
+	pub(crate) fn apply_pending(&self) {
+		self.journal.lock().apply_pending();
+	}
+
+	/// Drop the pending journal changes, if the transaction they were made with failed.
+	pub(crate) fn revert_pending(&self) {
+		self.journal.lock().revert_pending();
+	}
+
+	/// Write a fork-aware value at given block (`None` to clear it).
+	///
+	/// Writes at blocks that are not journaled but finalized change the canonical value
+	/// directly. Writes at any other block (i.e. unknown or discarded) are rejected.
+	///
+	/// Returns `true` if the value has been written.
+	fn write_at(
+		&self,
+		journal: &mut Journal,
+		at: &[u8],
+		key: Vec<u8>,
+		value: Option<Vec<u8>>,
+	) -> bool {
+		let mut tx = Transaction::new();
+		// journals being discarded by the backend only accept writes once canonical.
+		let entry = match journal.blocks.get(at).filter(|_| !journal.pending.contains_key(at)) {
+			Some(entry) => {
+				let mut entry = entry.clone();
+				entry.changes.insert(key, value);
+				tx.set_from_vec(columns::OFFCHAIN, &journal_key(at), entry.encode());
+				Some(entry)
+			},
+			None if self.is_canonical(at) => {
+				match value {
+					Some(value) => tx.set_from_vec(columns::OFFCHAIN, &canonical_key(&key), value),
+					None => tx.remove(columns::OFFCHAIN, &canonical_key(&key)),
+				}
+				None
+			},
+			None => {
+				warn!("Rejected fork-aware write on local storage at unknown or discarded block {:?}", at);
+				return false
+			},
+		};
+
+		if let Err(err) = self.db.commit(tx) {
+			error!("Error writing fork-aware value on local storage: {}", err);
+			return false
+		}
+		// The journal only changes once the write is committed.
+		if let Some(entry) = entry {
+			journal.blocks.insert(at.to_vec(), entry);
+		}
+		true
+	}
+
+	/// Whether given block is on the canonical chain, according to the key lookup column.
+	fn is_canonical(&self, hash: &[u8]) -> bool {
+		let lookup_key = match self.db.get(columns::KEY_LOOKUP, hash) {
+			Some(lookup_key) => lookup_key,
+			None => return false,
+		};
+		// Lookup keys start with the big-endian block number, which is also the key the
+		// canonical block of that number is looked up with.
+		lookup_key.len() >= 4 && self.db.get(columns::KEY_LOOKUP, &lookup_key[..4])
+			.map_or(false, |canonical| canonical == lookup_key)
+	}
+
+	/// Read a fork-aware value as seen from given block.
+	fn read_at(&self, journal: &Journal, at: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		match journal.get(at, key) {
+			Some(value) => value.clone(),
+			None => self.db.get(columns::OFFCHAIN, &canonical_key(key)),
 		}
 	}
 }
 
+/// Fork-aware changes made at a single non-canonical block.
+#[derive(Clone, Encode, Decode)]
+struct JournalEntry {
+	parent_hash: Vec<u8>,
+	number: u64,
+	changes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+/// Journals of all the non-canonical blocks, keyed by block hash.
+#[derive(Default)]
+struct Journal {
+	/// Committed journals. The reads only see these.
+	blocks: HashMap<Vec<u8>, JournalEntry>,
+	/// Hashes of the committed journals, by block number.
+	numbers: BTreeMap<u64, BTreeSet<Vec<u8>>>,
+	/// Journals added, or discarded for `None`, since the last commit.
+	pending: HashMap<Vec<u8>, Option<JournalEntry>>,
+	/// Hashes of the journals by block number, for the numbers changed since the last commit.
+	pending_numbers: BTreeMap<u64, BTreeSet<Vec<u8>>>,
+}
+
+impl Journal {
+	/// Load the journals persisted in the database.
+	fn load(db: &dyn Database<DbHash>) -> Self {
+		let mut journal = Journal::default();
+		let (first, last) = match db.get(columns::OFFCHAIN, JOURNAL_RANGE_KEY) {
+			Some(range) => match <(u64, u64)>::decode(&mut &range[..]) {
+				Ok(range) => range,
+				Err(err) => {
+					error!("Error decoding offchain journal range: {}", err);
+					return journal
+				},
+			},
+			None => return journal,
+		};
+
+		for number in first..=last {
+			let hashes = db.get(columns::OFFCHAIN, &number_key(number))
+				.map(|hashes| BTreeSet::<Vec<u8>>::decode(&mut &hashes[..]));
+			let hashes = match hashes {
+				Some(Ok(hashes)) => hashes,
+				Some(Err(err)) => {
+					error!("Error decoding offchain journal blocks of #{}: {}", number, err);
+					continue
+				},
+				None => continue,
+			};
+			for hash in hashes {
+				let entry = db.get(columns::OFFCHAIN, &journal_key(&hash))
+					.map(|entry| JournalEntry::decode(&mut &entry[..]));
+				match entry {
+					Some(Ok(entry)) => {
+						journal.numbers.entry(number).or_default().insert(hash.clone());
+						journal.blocks.insert(hash, entry);
+					},
+					Some(Err(err)) => error!("Error decoding offchain journal of {:?}: {}", hash, err),
+					None => error!("Missing offchain journal of {:?}", hash),
+				}
+			}
+		}
+		journal
+	}
+
+	/// Find the latest journaled change of `key` on the chain ending at block `at`.
+	///
+	/// Returns `None` if no journaled block on that chain has changed the key.
+	fn get(&self, at: &[u8], key: &[u8]) -> Option<&Option<Vec<u8>>> {
+		let mut cursor = at;
+		while let Some(entry) = self.blocks.get(cursor) {
+			if let Some(value) = entry.changes.get(key) {
+				return Some(value)
+			}
+			cursor = &entry.parent_hash;
+		}
+		None
+	}
+
+	/// The journal of given block, including the pending changes.
+	fn entry(&self, hash: &[u8]) -> Option<&JournalEntry> {
+		match self.pending.get(hash) {
+			Some(entry) => entry.as_ref(),
+			None => self.blocks.get(hash),
+		}
+	}
+
+	/// Hashes of the journals of given number, including the pending changes.
+	fn hashes(&self, number: u64) -> Option<&BTreeSet<Vec<u8>>> {
+		self.pending_numbers.get(&number).or_else(|| self.numbers.get(&number))
+	}
+
+	/// Hashes of the journals of given number, including the pending changes, for a change.
+	fn hashes_mut(&mut self, number: u64) -> &mut BTreeSet<Vec<u8>> {
+		let numbers = &self.numbers;
+		self.pending_numbers.entry(number)
+			.or_insert_with(|| numbers.get(&number).cloned().unwrap_or_default())
+	}
+
+	/// Write the list of journals of given number.
+	fn write_number(&mut self, transaction: &mut Transaction<DbHash>, number: u64) {
+		let hashes = self.hashes_mut(number);
+		if hashes.is_empty() {
+			transaction.remove(columns::OFFCHAIN, &number_key(number));
+		} else {
+			transaction.set_from_vec(columns::OFFCHAIN, &number_key(number), hashes.encode());
+		}
+	}
+
+	/// Add the journal of given block.
+	fn insert(&mut self, transaction: &mut Transaction<DbHash>, hash: &[u8], entry: JournalEntry) {
+		let number = entry.number;
+		transaction.set_from_vec(columns::OFFCHAIN, &journal_key(hash), entry.encode());
+		self.pending.insert(hash.to_vec(), Some(entry));
+		self.hashes_mut(number).insert(hash.to_vec());
+		self.write_number(transaction, number);
+	}
+
+	/// Discard the journal of given block, returning it.
+	fn remove(&mut self, transaction: &mut Transaction<DbHash>, hash: &[u8]) -> Option<JournalEntry> {
+		let entry = self.entry(hash)?.clone();
+		transaction.remove(columns::OFFCHAIN, &journal_key(hash));
+		self.pending.insert(hash.to_vec(), None);
+		self.hashes_mut(entry.number).remove(hash);
+		self.write_number(transaction, entry.number);
+		Some(entry)
+	}
+
+	/// Write the range of numbers of the journaled blocks.
+	fn write_range(&self, transaction: &mut Transaction<DbHash>) {
+		let is_journaled = |number: &&u64| self.hashes(**number).map_or(false, |hashes| !hashes.is_empty());
+		let first = self.numbers.keys().find(is_journaled).into_iter()
+			.chain(self.pending_numbers.keys().find(is_journaled))
+			.min();
+		let last = self.numbers.keys().rev().find(is_journaled).into_iter()
+			.chain(self.pending_numbers.keys().rev().find(is_journaled))
+			.max();
+		match (first, last) {
+			(Some(first), Some(last)) =>
+				transaction.set_from_vec(columns::OFFCHAIN, JOURNAL_RANGE_KEY, (*first, *last).encode()),
+			_ => transaction.remove(columns::OFFCHAIN, JOURNAL_RANGE_KEY),
+		}
+	}
+
+	/// Apply the journals of `hash` and its ancestors to the canonical values and discard
+	/// the journals of the blocks not descending from `hash`.
+	///
+	/// Returns `false` if `hash` is not journaled.
+	fn canonicalize(&mut self, transaction: &mut Transaction<DbHash>, hash: &[u8]) -> bool {
+		let mut route = Vec::new();
+		let mut cursor = hash.to_vec();
+		while let Some(entry) = self.remove(transaction, &cursor) {
+			let parent_hash = entry.parent_hash.clone();
+			route.push((cursor, entry));
+			cursor = parent_hash;
+		}
+		let number = match route.first() {
+			Some((_, entry)) => entry.number,
+			None => return false,
+		};
+
+		// Oldest first, so that the later changes win.
+		for (block, entry) in route.into_iter().rev() {
+			trace!(target: "db", "Canonicalize offchain journal of #{} ({:?})", entry.number, block);
+			for (key, value) in entry.changes {
+				match value {
+					Some(value) => transaction.set_from_vec(columns::OFFCHAIN, &canonical_key(&key), value),
+					None => transaction.remove(columns::OFFCHAIN, &canonical_key(&key)),
+				}
+			}
+		}
+
+		// Parents come before their children, so a single pass over the numbers finds all
+		// the descendants.
+		let numbers: BTreeSet<u64> = self.numbers.keys().chain(self.pending_numbers.keys()).cloned().collect();
+		let mut descendants = HashSet::new();
+		descendants.insert(hash.to_vec());
+		for block_number in numbers {
+			let blocks: Vec<_> = self.hashes(block_number).into_iter().flatten().cloned().collect();
+			for block in blocks {
+				let is_descendant = block_number > number && self.entry(&block)
+					.map_or(false, |entry| descendants.contains(&entry.parent_hash));
+				if is_descendant {
+					descendants.insert(block);
+				} else {
+					trace!(target: "db", "Discard offchain journal of #{} ({:?})", block_number, block);
+					self.remove(transaction, &block);
+				}
+			}
+		}
+		true
+	}
+
+	/// Apply the pending changes, once committed.
+	fn apply_pending(&mut self) {
+		for (hash, entry) in self.pending.drain() {
+			match entry {
+				Some(entry) => self.blocks.insert(hash, entry),
+				None => self.blocks.remove(&hash),
+			};
+		}
+		for (number, hashes) in std::mem::take(&mut self.pending_numbers) {
+			if hashes.is_empty() {
+				self.numbers.remove(&number);
+			} else {
+				self.numbers.insert(number, hashes);
+			}
+		}
+	}
+
+	/// Drop the pending changes.
+	fn revert_pending(&mut self) {
+		self.pending.clear();
+		self.pending_numbers.clear();
+	}
+}
+
+fn journal_key(hash: &[u8]) -> Vec<u8> {
+	JOURNAL_PREFIX.iter().chain(hash).cloned().collect()
+}
+
+fn number_key(number: u64) -> Vec<u8> {
+	JOURNAL_NUMBER_PREFIX.iter().cloned().chain(number.to_be_bytes().iter().cloned()).collect()
+}
+
+fn canonical_key(key: &[u8]) -> Vec<u8> {
+	FORK_AWARE_PREFIX.iter().chain(key).cloned().collect()
+}
+
 impl sp_core::offchain::OffchainStorage for LocalStorage {
 	fn set(&mut self, prefix: &[u8], key: &[u8], value: &[u8]) {
 		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
@@ -120,11 +485,44 @@ impl sp_core::offchain::OffchainStorage for LocalStorage {
 		}
 		is_set
 	}
+
+	fn set_at(&mut self, at: &[u8], prefix: &[u8], key: &[u8], value: &[u8]) {
+		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
+		let mut journal = self.journal.lock();
+		self.write_at(&mut journal, at, key, Some(value.to_vec()));
+	}
+
+	fn remove_at(&mut self, at: &[u8], prefix: &[u8], key: &[u8]) {
+		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
+		let mut journal = self.journal.lock();
+		self.write_at(&mut journal, at, key, None);
+	}
+
+	fn get_at(&self, at: &[u8], prefix: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
+		self.read_at(&self.journal.lock(), at, &key)
+	}
+
+	fn compare_and_set_at(
+		&mut self,
+		at: &[u8],
+		prefix: &[u8],
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool {
+		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
+		// the journal lock is held for the whole operation, so it's atomic.
+		let mut journal = self.journal.lock();
+		let is_set = self.read_at(&journal, at, &key).as_ref().map(|x| &**x) == old_value;
+		is_set && self.write_at(&mut journal, at, key, Some(new_value.to_vec()))
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::utils;
 	use sp_core::offchain::OffchainStorage;
 
 	#[test]
@@ -153,4 +551,195 @@ mod tests {
 		assert!(storage.locks.lock().is_empty(), "Locks map should be empty!");
 	}
 
+	fn note_block(storage: &LocalStorage, hash: &[u8], parent_hash: &[u8], number: u64) {
+		let mut tx = Transaction::new();
+		utils::insert_hash_to_key_mapping(&mut tx, columns::KEY_LOOKUP, number, hash).unwrap();
+		storage.note_block(&mut tx, hash, parent_hash, number, std::iter::empty());
+		storage.db.commit(tx).unwrap();
+		storage.apply_pending();
+	}
+
+	fn canonicalize_block(storage: &LocalStorage, hash: &[u8]) {
+		let mut tx = Transaction::new();
+		// mark the finalized route as canonical, like the backend does.
+		{
+			let journal = storage.journal.lock();
+			let mut cursor = hash;
+			while let Some(entry) = journal.blocks.get(cursor) {
+				utils::insert_number_to_key_mapping(&mut tx, columns::KEY_LOOKUP, entry.number, cursor)
+					.unwrap();
+				cursor = &entry.parent_hash;
+			}
+		}
+		storage.canonicalize_block(&mut tx, hash);
+		storage.db.commit(tx).unwrap();
+		storage.apply_pending();
+	}
+
+	// Builds:
+	// 0 - a1 - a2 - a3
+	//        \
+	//         b2
+	fn storage_with_forks() -> LocalStorage {
+		let storage = LocalStorage::new_test();
+		note_block(&storage, b"0", b"", 0);
+		canonicalize_block(&storage, b"0");
+		note_block(&storage, b"a1", b"0", 1);
+		note_block(&storage, b"a2", b"a1", 2);
+		note_block(&storage, b"a3", b"a2", 3);
+		note_block(&storage, b"b2", b"a1", 2);
+		storage
+	}
+
+	#[test]
+	fn fork_aware_values_are_only_visible_on_their_fork() {
+		let mut storage = storage_with_forks();
+		let prefix = b"prefix";
+
+		storage.set_at(b"a1", prefix, b"common", b"a1");
+		storage.set_at(b"a2", prefix, b"key", b"a2");
+		storage.set_at(b"b2", prefix, b"key", b"b2");
+		storage.remove_at(b"a3", prefix, b"common");
+
+		assert_eq!(storage.get_at(b"a2", prefix, b"key"), Some(b"a2".to_vec()));
+		assert_eq!(storage.get_at(b"a3", prefix, b"key"), Some(b"a2".to_vec()));
+		assert_eq!(storage.get_at(b"b2", prefix, b"key"), Some(b"b2".to_vec()));
+		assert_eq!(storage.get_at(b"a1", prefix, b"key"), None);
+
+		assert_eq!(storage.get_at(b"a2", prefix, b"common"), Some(b"a1".to_vec()));
+		assert_eq!(storage.get_at(b"b2", prefix, b"common"), Some(b"a1".to_vec()));
+		assert_eq!(storage.get_at(b"a3", prefix, b"common"), None);
+
+		// fork-aware and persistent values don't mix.
+		assert_eq!(storage.get(prefix, b"key"), None);
+	}
+
+	#[test]
+	fn canonicalization_discards_abandoned_forks() {
+		let mut storage = storage_with_forks();
+		let prefix = b"prefix";
+		storage.set_at(b"a1", prefix, b"key", b"a1");
+		storage.set_at(b"a3", prefix, b"key", b"a3");
+		storage.set_at(b"b2", prefix, b"key", b"b2");
+		storage.set_at(b"b2", prefix, b"other", b"b2");
+
+		canonicalize_block(&storage, b"a2");
+
+		{
+			let journal = storage.journal.lock();
+			let mut blocks: Vec<_> = journal.blocks.keys().cloned().collect();
+			blocks.sort();
+			assert_eq!(blocks, vec![b"a3".to_vec()]);
+		}
+		assert_eq!(storage.get_at(b"a2", prefix, b"key"), Some(b"a1".to_vec()));
+		assert_eq!(storage.get_at(b"a3", prefix, b"key"), Some(b"a3".to_vec()));
+		assert_eq!(storage.get_at(b"b2", prefix, b"other"), None);
+
+		// writes at canonical blocks go straight to the canonical values.
+		storage.set_at(b"a2", prefix, b"key", b"a2");
+		assert_eq!(storage.get_at(b"a3", prefix, b"key"), Some(b"a3".to_vec()));
+		canonicalize_block(&storage, b"a3");
+		assert_eq!(storage.get_at(b"a3", prefix, b"key"), Some(b"a3".to_vec()));
+		assert!(storage.journal.lock().blocks.is_empty());
+	}
+
+	#[test]
+	fn reverted_block_changes_are_discarded() {
+		let mut storage = storage_with_forks();
+		let prefix = b"prefix";
+		storage.set_at(b"a2", prefix, b"key", b"a2");
+		storage.set_at(b"a3", prefix, b"key", b"a3");
+
+		let mut tx = Transaction::new();
+		storage.revert_block(&mut tx, b"a3");
+		storage.db.commit(tx).unwrap();
+		storage.apply_pending();
+
+		assert_eq!(storage.get_at(b"a3", prefix, b"key"), None);
+		assert_eq!(storage.get_at(b"a2", prefix, b"key"), Some(b"a2".to_vec()));
+	}
+
+	#[test]
+	fn journal_is_restored_from_the_database() {
+		let mut storage = storage_with_forks();
+		let prefix = b"prefix";
+		storage.set_at(b"a2", prefix, b"key", b"a2");
+		storage.set_at(b"b2", prefix, b"key", b"b2");
+
+		let restored = LocalStorage::new(storage.db.clone());
+		assert_eq!(restored.get_at(b"a3", prefix, b"key"), Some(b"a2".to_vec()));
+		assert_eq!(restored.get_at(b"b2", prefix, b"key"), Some(b"b2".to_vec()));
+
+		canonicalize_block(&restored, b"b2");
+		let restored = LocalStorage::new(storage.db.clone());
+		assert!(restored.journal.lock().blocks.is_empty());
+		assert_eq!(restored.get_at(b"a3", prefix, b"key"), Some(b"b2".to_vec()));
+	}
+
+	#[test]
+	fn pending_changes_are_only_visible_once_applied() {
+		let mut storage = storage_with_forks();
+		let prefix = b"prefix";
+		storage.set_at(b"b2", prefix, b"key", b"b2");
+
+		// a failed commit leaves the journal untouched.
+		let mut tx = Transaction::new();
+		storage.canonicalize_block(&mut tx, b"a2");
+		assert_eq!(storage.get_at(b"b2", prefix, b"key"), Some(b"b2".to_vec()));
+		storage.revert_pending();
+		assert_eq!(storage.get_at(b"b2", prefix, b"key"), Some(b"b2".to_vec()));
+		assert_eq!(storage.journal.lock().blocks.len(), 4);
+
+		let mut tx = Transaction::new();
+		storage.canonicalize_block(&mut tx, b"a2");
+		storage.db.commit(tx).unwrap();
+		storage.apply_pending();
+		assert_eq!(storage.get_at(b"b2", prefix, b"key"), None);
+		assert_eq!(storage.journal.lock().blocks.len(), 1);
+	}
+
+	#[test]
+	fn noting_a_block_only_writes_its_number() {
+		let storage = storage_with_forks();
+		let mut tx = Transaction::new();
+		storage.note_block(&mut tx, b"a4", b"a3", 4, std::iter::empty());
+		let keys: Vec<_> = tx.0.iter().map(|change| match change {
+			sp_database::Change::Set(_, key, _) | sp_database::Change::Remove(_, key) => key.clone(),
+			_ => Vec::new(),
+		}).collect();
+		assert_eq!(keys, vec![journal_key(b"a4"), number_key(4), JOURNAL_RANGE_KEY.to_vec()]);
+	}
+
+	#[test]
+	fn writes_at_unknown_or_discarded_blocks_are_rejected() {
+		let mut storage = storage_with_forks();
+		let prefix = b"prefix";
+
+		storage.set_at(b"unknown", prefix, b"key", b"unknown");
+		assert_eq!(storage.get_at(b"0", prefix, b"key"), None);
+		assert_eq!(storage.compare_and_set_at(b"unknown", prefix, b"key", None, b"unknown"), false);
+
+		// `b2` is discarded by the finalization of `a2`.
+		canonicalize_block(&storage, b"a2");
+		storage.set_at(b"b2", prefix, b"key", b"b2");
+		assert_eq!(storage.compare_and_set_at(b"b2", prefix, b"key", None, b"b2"), false);
+		assert_eq!(storage.get_at(b"a2", prefix, b"key"), None);
+		assert_eq!(storage.get_at(b"a3", prefix, b"key"), None);
+
+		// writes at finalized blocks are still accepted.
+		assert_eq!(storage.compare_and_set_at(b"a1", prefix, b"key", None, b"a1"), true);
+		assert_eq!(storage.get_at(b"a3", prefix, b"key"), Some(b"a1".to_vec()));
+	}
+
+	#[test]
+	fn should_compare_and_set_fork_aware_values() {
+		let mut storage = storage_with_forks();
+		let prefix = b"prefix";
+		storage.set_at(b"a1", prefix, b"key", b"a1");
+
+		assert_eq!(storage.compare_and_set_at(b"b2", prefix, b"key", None, b"b2"), false);
+		assert_eq!(storage.compare_and_set_at(b"b2", prefix, b"key", Some(b"a1"), b"b2"), true);
+		assert_eq!(storage.get_at(b"b2", prefix, b"key"), Some(b"b2".to_vec()));
+		assert_eq!(storage.get_at(b"a2", prefix, b"key"), Some(b"a1".to_vec()));
+	}
 }
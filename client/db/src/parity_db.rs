@@ -17,9 +17,17 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 /// A `Database` adapter for parity-db.
 
+use std::path::{Path, PathBuf};
+
+use codec::Decode;
 use sp_database::{Database, Change, ColumnId, Transaction, error::DatabaseError};
-use crate::utils::{DatabaseType, NUM_COLUMNS};
-use crate::columns;
+use crate::utils::{self, meta_keys, DatabaseType, NUM_COLUMNS};
+use crate::migration::{self, MigrationConfig, MigrationDb, MigrationProgress, MigrationSummary};
+use crate::{columns, DbHash};
+
+/// Version of the column layout ParityDb databases were created with before they got
+/// a version file.
+const INITIAL_VERSION: u32 = 2;
 
 struct DbAdapter(parity_db::Db);
 
@@ -36,15 +44,188 @@ fn handle_err<T>(result: parity_db::Result<T>) -> T {
 pub fn open<H: Clone>(path: &std::path::Path, db_type: DatabaseType)
 	-> parity_db::Result<std::sync::Arc<dyn Database<H>>>
 {
-	let mut config = parity_db::Options::with_columns(path, NUM_COLUMNS as u8);
+	let db = parity_db::Db::open(&options(path, db_type, NUM_COLUMNS))?;
+	Ok(std::sync::Arc::new(DbAdapter(db)))
+}
+
+fn options(path: &Path, db_type: DatabaseType, num_columns: u32) -> parity_db::Options {
+	let mut config = parity_db::Options::with_columns(path, num_columns as u8);
 	if db_type == DatabaseType::Full {
 		let mut state_col = &mut config.columns[columns::STATE as usize];
 		state_col.ref_counted = true;
 		state_col.preimage = true;
 		state_col.uniform = true;
 	}
-	let db = parity_db::Db::open(&config)?;
-	Ok(std::sync::Arc::new(DbAdapter(db)))
+	config
+}
+
+/// Run all pending migrations of the parity-db database at given path.
+pub fn migrate(
+	path: &Path,
+	db_type: DatabaseType,
+	config: &MigrationConfig,
+	report: &mut dyn FnMut(&MigrationProgress),
+) -> sp_blockchain::Result<MigrationSummary> {
+	let version = match migration::current_version(path)? {
+		0 => INITIAL_VERSION,
+		version => version,
+	};
+	if migration::is_empty(path) || version == migration::CURRENT_VERSION {
+		if !config.dry_run {
+			migration::update_version(path, migration::CURRENT_VERSION)?;
+		}
+		let version = migration::CURRENT_VERSION;
+		return Ok(MigrationSummary { from: version, to: version, entries: 0, changes: 0 });
+	}
+
+	let version = migration::check_version(version)?;
+	let steps = migration::steps();
+	let mut db = MigrationAdapter::open(path, db_type, migration::num_columns(version, &steps))?;
+	migration::migrate(&mut db, path, version, &steps, config, report)
+}
+
+/// Open the parity-db database at given path for offline inspection.
+///
+/// The database must be at the current version.
+pub(crate) fn open_offline(path: &Path, db_type: DatabaseType) -> sp_blockchain::Result<Box<dyn MigrationDb>> {
+	let version = match migration::current_version(path)? {
+		0 => INITIAL_VERSION,
+		version => version,
+	};
+	migration::check_current(version)?;
+	let num_columns = migration::num_columns(version, &migration::steps());
+	Ok(Box::new(MigrationAdapter::open(path, db_type, num_columns)?))
+}
+
+fn parity_err(e: parity_db::Error) -> sp_blockchain::Error {
+	sp_blockchain::Error::Backend(format!("{:?}", e))
+}
+
+/// Columns keyed by block lookup key.
+const BLOCK_COLUMNS: [ColumnId; 3] = [columns::HEADER, columns::BODY, columns::JUSTIFICATION];
+
+/// parity-db database opened offline, for migration or inspection.
+///
+/// parity-db indexes entries by key hash and can't enumerate the original keys, so only the
+/// columns keyed by block can be iterated over, by walking the tree of blocks from genesis.
+struct MigrationAdapter {
+	/// Only `None` while the database is reopened.
+	db: Option<parity_db::Db>,
+	path: PathBuf,
+	db_type: DatabaseType,
+	num_columns: u32,
+}
+
+impl MigrationAdapter {
+	fn open(path: &Path, db_type: DatabaseType, num_columns: u32) -> sp_blockchain::Result<Self> {
+		let db = parity_db::Db::open(&options(path, db_type, num_columns)).map_err(parity_err)?;
+		Ok(MigrationAdapter { db: Some(db), path: path.to_owned(), db_type, num_columns })
+	}
+
+	fn db(&self) -> &parity_db::Db {
+		self.db.as_ref().expect("Only taken while reopening the database; qed")
+	}
+
+	/// Calls `f` with the lookup key of every known block in key order, i.e. by number and then
+	/// by hash, for as long as it returns `true`.
+	///
+	/// Blocks are found through the children of their parent, starting at genesis.
+	fn for_each_block(
+		&self,
+		f: &mut dyn FnMut(&[u8]) -> sp_blockchain::Result<bool>,
+	) -> sp_blockchain::Result<()> {
+		let genesis = match self.get(columns::META, meta_keys::GENESIS_HASH)? {
+			Some(hash) => decode::<DbHash>(&hash)?,
+			None => return Ok(()),
+		};
+
+		let mut number = 0u32;
+		let mut blocks = vec![genesis];
+		while !blocks.is_empty() {
+			blocks.sort();
+			let mut children = Vec::new();
+			for hash in &blocks {
+				if !f(&utils::number_and_hash_to_lookup_key(number, hash)?)? {
+					return Ok(());
+				}
+
+				let mut key = meta_keys::CHILDREN_PREFIX.to_vec();
+				key.extend_from_slice(hash.as_ref());
+				if let Some(encoded) = self.get(columns::META, &key)? {
+					children.extend(decode::<Vec<DbHash>>(&encoded)?);
+				}
+			}
+			blocks = children;
+			number += 1;
+		}
+		Ok(())
+	}
+}
+
+fn decode<T: Decode>(encoded: &[u8]) -> sp_blockchain::Result<T> {
+	T::decode(&mut &encoded[..]).map_err(|e| sp_blockchain::Error::Backend(format!("{}", e)))
+}
+
+impl MigrationDb for MigrationAdapter {
+	fn num_columns(&self) -> u32 {
+		self.num_columns
+	}
+
+	fn add_column(&mut self) -> sp_blockchain::Result<()> {
+		// Columns are created from the options when the database is opened.
+		self.db = None;
+		*self = Self::open(&self.path, self.db_type, self.num_columns + 1)?;
+		Ok(())
+	}
+
+	fn iter_column(
+		&self,
+		col: ColumnId,
+		start_after: Option<&[u8]>,
+		f: &mut dyn FnMut(&[u8], &[u8]) -> sp_blockchain::Result<bool>,
+	) -> sp_blockchain::Result<()> {
+		if !BLOCK_COLUMNS.contains(&col) {
+			return Err(sp_blockchain::Error::Backend(format!(
+				"Iterating over column {} is not supported by parity-db", col,
+			)));
+		}
+
+		self.for_each_block(&mut |key| {
+			if start_after.map_or(false, |start| key <= start) {
+				return Ok(true);
+			}
+			match self.get(col, key)? {
+				Some(value) => f(key, &value),
+				None => Ok(true),
+			}
+		})
+	}
+
+	fn can_iterate(&self) -> bool {
+		false
+	}
+
+	fn supports_ref_counting(&self) -> bool {
+		true
+	}
+
+	fn get(&self, col: ColumnId, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		self.db().get(col as u8, key).map_err(parity_err)
+	}
+
+	fn commit(&self, transaction: Transaction<DbHash>) -> sp_blockchain::Result<()> {
+		let mut changes = Vec::with_capacity(transaction.0.len());
+		for change in transaction.0.into_iter() {
+			match change {
+				Change::Set(col, key, value) => changes.push((col as u8, key, Some(value))),
+				Change::Remove(col, key) => changes.push((col as u8, key, None)),
+				_ => return Err(sp_blockchain::Error::Backend(
+					"Reference counted changes are not supported in migrations".into()
+				)),
+			}
+		}
+		self.db().commit(changes).map_err(parity_err)
+	}
 }
 
 impl<H: Clone> Database<H> for DbAdapter {
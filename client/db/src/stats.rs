@@ -138,3 +138,26 @@ impl StateUsageStats {
 		}
 	}
 }
+
+/// Number and size of the entries of a database column.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ColumnStats {
+	/// Number of keys.
+	pub keys: u64,
+	/// Total size of the keys.
+	pub key_bytes: u64,
+	/// Total size of the values.
+	pub value_bytes: u64,
+	/// Size of the largest value.
+	pub max_value_bytes: u64,
+}
+
+impl ColumnStats {
+	/// Tally one entry of the column.
+	pub fn tally(&mut self, key: &[u8], value: &[u8]) {
+		self.keys += 1;
+		self.key_bytes += key.len() as u64;
+		self.value_bytes += value.len() as u64;
+		self.max_value_bytes = self.max_value_bytes.max(value.len() as u64);
+	}
+}
@@ -16,77 +16,151 @@
 
 //! Database upgrade logic.
 
-use std::fs;
-use std::io::{Read, Write, ErrorKind};
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
+use sp_database::{Change, ColumnId, Transaction};
 use sp_runtime::traits::Block as BlockT;
+use crate::DbHash;
+use crate::migration::{
+	self, MigrationConfig, MigrationDb, MigrationProgress, MigrationSummary, CURRENT_VERSION, db_err,
+};
 use crate::utils::DatabaseType;
-
-/// Version file name.
-const VERSION_FILE_NAME: &'static str = "db_version";
-
-/// Current db version.
-const CURRENT_VERSION: u32 = 1;
+use kvdb_rocksdb::{Database, DatabaseConfig};
 
 /// Upgrade database to current version.
 pub fn upgrade_db<Block: BlockT>(db_path: &Path, _db_type: DatabaseType) -> sp_blockchain::Result<()> {
-	let is_empty = db_path.read_dir().map_or(true, |mut d| d.next().is_none());
-	if !is_empty {
-		let db_version = current_version(db_path)?;
-		match db_version {
-			0 => Err(sp_blockchain::Error::Backend(format!("Unsupported database version: {}", db_version)))?,
-			1 => (),
-			_ => Err(sp_blockchain::Error::Backend(format!("Future database version: {}", db_version)))?,
+	migrate_rocksdb(db_path, &Default::default(), &mut migration::log_progress).map(|_| ())
+}
+
+/// Run all pending migrations of the RocksDB database at given path.
+pub fn migrate_rocksdb(
+	db_path: &Path,
+	config: &MigrationConfig,
+	report: &mut dyn FnMut(&MigrationProgress),
+) -> sp_blockchain::Result<MigrationSummary> {
+	if migration::is_empty(db_path) {
+		if !config.dry_run {
+			migration::update_version(db_path, CURRENT_VERSION)?;
 		}
+		return Ok(MigrationSummary { from: CURRENT_VERSION, to: CURRENT_VERSION, entries: 0, changes: 0 });
 	}
 
-	update_version(db_path)
+	let version = migration::check_version(migration::current_version(db_path)?)?;
+	let steps = migration::steps();
+	let mut db = RocksDbMigration::open(db_path, migration::num_columns(version, &steps))?;
+	migration::migrate(&mut db, db_path, version, &steps, config, report)
 }
 
+/// Open the RocksDB database at given path for offline inspection.
+///
+/// The database must be at the current version.
+pub(crate) fn open_rocksdb(db_path: &Path) -> sp_blockchain::Result<Box<dyn MigrationDb>> {
+	migration::check_current(migration::current_version(db_path)?)?;
+	let num_columns = migration::num_columns(CURRENT_VERSION, &migration::steps());
+	Ok(Box::new(RocksDbMigration::open(db_path, num_columns)?))
+}
 
-/// Reads current database version from the file at given path.
-/// If the file does not exist returns 0.
-fn current_version(path: &Path) -> sp_blockchain::Result<u32> {
-	let unknown_version_err = || sp_blockchain::Error::Backend("Unknown database version".into());
+/// RocksDB database opened with the column layout of its current version.
+struct RocksDbMigration {
+	db: Database,
+	num_columns: u32,
+}
 
-	match fs::File::open(version_file_path(path)) {
-		Err(ref err) if err.kind() == ErrorKind::NotFound => Ok(0),
-		Err(_) => Err(unknown_version_err()),
-		Ok(mut file) => {
-			let mut s = String::new();
-			file.read_to_string(&mut s).map_err(|_| unknown_version_err())?;
-			u32::from_str_radix(&s, 10).map_err(|_| unknown_version_err())
-		},
+impl RocksDbMigration {
+	fn open(db_path: &Path, num_columns: u32) -> sp_blockchain::Result<Self> {
+		let db_path = db_path.to_str()
+			.ok_or_else(|| sp_blockchain::Error::Backend("Invalid database path".into()))?;
+		let db_cfg = DatabaseConfig::with_columns(num_columns);
+		let db = Database::open(&db_cfg, db_path).map_err(db_err)?;
+		Ok(RocksDbMigration { db, num_columns })
 	}
 }
 
-/// Maps database error to client error
-fn db_err(err: std::io::Error) -> sp_blockchain::Error {
-	sp_blockchain::Error::Backend(format!("{}", err))
-}
+impl MigrationDb for RocksDbMigration {
+	fn num_columns(&self) -> u32 {
+		self.num_columns
+	}
+
+	fn add_column(&mut self) -> sp_blockchain::Result<()> {
+		self.db.add_column().map_err(db_err)?;
+		self.num_columns += 1;
+		Ok(())
+	}
+
+	fn iter_column(
+		&self,
+		col: ColumnId,
+		start_after: Option<&[u8]>,
+		f: &mut dyn FnMut(&[u8], &[u8]) -> sp_blockchain::Result<bool>,
+	) -> sp_blockchain::Result<()> {
+		// The iterators read from a snapshot, so committing while iterating is fine.
+		let start = match start_after {
+			Some(start) => start,
+			None => {
+				for (key, value) in self.db.iter(col) {
+					if !f(&key, &value)? {
+						break;
+					}
+				}
+				return Ok(());
+			},
+		};
+
+		// Every prefix iteration seeks to its prefix, so the keys up to `start` are not read.
+		for prefix in prefixes_after(start) {
+			for (key, value) in self.db.iter_with_prefix(col, &prefix) {
+				if &*key == start {
+					continue;
+				}
+				if !f(&key, &value)? {
+					return Ok(());
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn get(&self, col: ColumnId, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		self.db.get(col, key).map_err(db_err)
+	}
 
-/// Writes current database version to the file.
-/// Creates a new file if the version file does not exist yet.
-fn update_version(path: &Path) -> sp_blockchain::Result<()> {
-	fs::create_dir_all(path).map_err(db_err)?;
-	let mut file = fs::File::create(version_file_path(path)).map_err(db_err)?;
-	file.write_all(format!("{}", CURRENT_VERSION).as_bytes()).map_err(db_err)?;
-	Ok(())
+	fn commit(&self, transaction: Transaction<DbHash>) -> sp_blockchain::Result<()> {
+		let mut tx = kvdb::DBTransaction::new();
+		for change in transaction.0.into_iter() {
+			match change {
+				Change::Set(col, key, value) => tx.put_vec(col, &key, value),
+				Change::Remove(col, key) => tx.delete(col, &key),
+				_ => return Err(sp_blockchain::Error::Backend(
+					"Reference counted changes are not supported in migrations".into()
+				)),
+			}
+		}
+		self.db.write(tx).map_err(db_err)
+	}
 }
 
-/// Returns the version file path.
-fn version_file_path(path: &Path) -> PathBuf {
-	let mut file_path = path.to_owned();
-	file_path.push(VERSION_FILE_NAME);
-	file_path
+/// Prefixes covering all the keys greater than `key`, in key order.
+///
+/// These are `key` itself, followed by the prefixes made of the first bytes of `key` and
+/// a greater next byte, longest first.
+fn prefixes_after(key: &[u8]) -> impl Iterator<Item = Vec<u8>> + '_ {
+	std::iter::once(key.to_vec()).chain((0..key.len()).rev().flat_map(move |len| {
+		(key[len] as u16 + 1..=u8::max_value() as u16).map(move |next| {
+			let mut prefix = key[..len].to_vec();
+			prefix.push(next as u8);
+			prefix
+		})
+	}))
 }
 
 #[cfg(test)]
 mod tests {
 	use sc_state_db::PruningMode;
-	use crate::{DatabaseSettings, DatabaseSettingsSrc};
+	use crate::{DatabaseSettings, DatabaseSettingsSrc, KeepBlocks, TransactionStorageMode};
 	use crate::tests::Block;
+	use crate::migration::{V1_NUM_COLUMNS, current_version, version_file_path};
+	use std::fs;
+	use std::io::Write;
 	use super::*;
 
 	fn create_db(db_path: &Path, version: Option<u32>) {
@@ -103,6 +177,8 @@ mod tests {
 			state_cache_child_ratio: None,
 			pruning: PruningMode::ArchiveAll,
 			source: DatabaseSettingsSrc::RocksDb { path: db_path.to_owned(), cache_size: 128 },
+			transaction_storage: TransactionStorageMode::BlockBody,
+			keep_blocks: KeepBlocks::All,
 		}, DatabaseType::Full).map(|_| ())
 	}
 
@@ -120,4 +196,53 @@ mod tests {
 		open_database(db_dir.path()).unwrap();
 		assert_eq!(current_version(db_dir.path()).unwrap(), CURRENT_VERSION);
 	}
+
+	#[test]
+	fn prefixes_after_cover_greater_keys_in_order() {
+		let keys = vec![
+			vec![], vec![0], vec![1], vec![1, 0], vec![1, 2], vec![1, 2, 0], vec![1, 2, 3],
+			vec![1, 3], vec![1, 255], vec![2], vec![255, 255],
+		];
+		let start = [1, 2];
+
+		let all = &keys;
+		let found = prefixes_after(&start)
+			.flat_map(move |prefix| all.iter().filter(move |key| key.starts_with(&prefix)))
+			.filter(|key| &key[..] != &start[..])
+			.cloned()
+			.collect::<Vec<_>>();
+		let expected = keys.iter().filter(|key| &key[..] > &start[..]).cloned().collect::<Vec<_>>();
+		assert_eq!(found, expected);
+	}
+
+	#[test]
+	fn migration_resumes_after_checkpoint() {
+		let db_dir = tempfile::TempDir::new().unwrap();
+		let db = RocksDbMigration::open(db_dir.path(), 1).unwrap();
+		let mut transaction = Transaction::new();
+		for key in &[&[0u8][..], &[1], &[1, 0], &[1, 1], &[2], &[2, 0]] {
+			transaction.set(0, key, key);
+		}
+		db.commit(transaction).unwrap();
+
+		let mut keys = Vec::new();
+		db.iter_column(0, Some(&[1, 0]), &mut |key, _| {
+			keys.push(key.to_vec());
+			Ok(keys.len() < 3)
+		}).unwrap();
+		assert_eq!(keys, vec![vec![1, 1], vec![2], vec![2, 0]]);
+	}
+
+	#[test]
+	fn upgrade_from_1_to_2_works() {
+		let db_dir = tempfile::TempDir::new().unwrap();
+		let db_path = db_dir.path();
+		{
+			let db_cfg = DatabaseConfig::with_columns(V1_NUM_COLUMNS);
+			Database::open(&db_cfg, db_path.to_str().unwrap()).unwrap();
+		}
+		create_db(db_path, Some(1));
+		open_database(db_path).unwrap();
+		assert_eq!(current_version(db_path).unwrap(), CURRENT_VERSION);
+	}
 }
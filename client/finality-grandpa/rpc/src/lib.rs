@@ -29,6 +29,7 @@ use jsonrpc_core::futures::{
 	stream::Stream as Stream01,
 	future::Future as Future01,
 	future::Executor as Executor01,
+	future::err as err01,
 };
 
 mod error;
@@ -37,10 +38,14 @@ mod notification;
 mod report;
 
 use sc_finality_grandpa::GrandpaJustificationStream;
+use sc_rpc::DenyUnsafe;
 use sp_runtime::traits::Block as BlockT;
 
 use finality::{EncodedFinalityProofs, RpcFinalityProofProvider};
-use report::{ReportAuthoritySet, ReportVoterState, ReportedRoundStates};
+use report::{
+	ReportAuthoritySet, ReportAuthoritySetChanges, ReportFinalityLag, ReportVoterHistory,
+	ReportVoterState, ReportedFinalityLag, ReportedRoundStates, ReportedVoterHistory,
+};
 use notification::JustificationNotification;
 
 type FutureResult<T> =
@@ -57,6 +62,20 @@ pub trait GrandpaApi<Notification, Hash> {
 	#[rpc(name = "grandpa_roundState")]
 	fn round_state(&self) -> FutureResult<ReportedRoundStates>;
 
+	/// Returns the prevotes and precommits of the last completed rounds (all
+	/// the ones kept if no number is given), the equivocations seen by the
+	/// voter, the authority set change history, the pending forced changes and
+	/// the view each connected peer has announced to us. This is an unsafe RPC,
+	/// as it reveals the connected peers.
+	#[rpc(name = "grandpa_voterHistory")]
+	fn voter_history(&self, rounds: Option<u32>) -> FutureResult<ReportedVoterHistory<Hash>>;
+
+	/// Returns how far finality lags behind the best block and whether the
+	/// voter considers finality stalled. The finalized block number is the one
+	/// to provide when scheduling a forced change to recover from a stall.
+	#[rpc(name = "grandpa_finalityLag")]
+	fn finality_lag(&self) -> FutureResult<ReportedFinalityLag<Hash>>;
+
 	/// Returns the block most recently finalized by Grandpa, alongside
 	/// side its justification.
 	#[pubsub(
@@ -94,24 +113,30 @@ pub trait GrandpaApi<Notification, Hash> {
 }
 
 /// Implements the GrandpaApi RPC trait for interacting with GRANDPA.
-pub struct GrandpaRpcHandler<AuthoritySet, VoterState, Block: BlockT, ProofProvider> {
+pub struct GrandpaRpcHandler<AuthoritySet, VoterState, VoterHistory, FinalityLag, Block: BlockT, ProofProvider> {
 	authority_set: AuthoritySet,
 	voter_state: VoterState,
+	voter_history: VoterHistory,
+	finality_lag: FinalityLag,
 	justification_stream: GrandpaJustificationStream<Block>,
 	manager: SubscriptionManager,
 	finality_proof_provider: Arc<ProofProvider>,
+	deny_unsafe: DenyUnsafe,
 }
 
-impl<AuthoritySet, VoterState, Block: BlockT, ProofProvider>
-	GrandpaRpcHandler<AuthoritySet, VoterState, Block, ProofProvider>
+impl<AuthoritySet, VoterState, VoterHistory, FinalityLag, Block: BlockT, ProofProvider>
+	GrandpaRpcHandler<AuthoritySet, VoterState, VoterHistory, FinalityLag, Block, ProofProvider>
 {
 	/// Creates a new GrandpaRpcHandler instance.
 	pub fn new<E>(
 		authority_set: AuthoritySet,
 		voter_state: VoterState,
+		voter_history: VoterHistory,
+		finality_lag: FinalityLag,
 		justification_stream: GrandpaJustificationStream<Block>,
 		executor: E,
 		finality_proof_provider: Arc<ProofProvider>,
+		deny_unsafe: DenyUnsafe,
 	) -> Self
 	where
 		E: Executor01<Box<dyn Future01<Item = (), Error = ()> + Send>> + Send + Sync + 'static,
@@ -120,18 +145,24 @@ impl<AuthoritySet, VoterState, Block: BlockT, ProofProvider>
 		Self {
 			authority_set,
 			voter_state,
+			voter_history,
+			finality_lag,
 			justification_stream,
 			manager,
 			finality_proof_provider,
+			deny_unsafe,
 		}
 	}
 }
 
-impl<AuthoritySet, VoterState, Block, ProofProvider> GrandpaApi<JustificationNotification, Block::Hash>
-	for GrandpaRpcHandler<AuthoritySet, VoterState, Block, ProofProvider>
+impl<AuthoritySet, VoterState, VoterHistory, FinalityLag, Block, ProofProvider>
+	GrandpaApi<JustificationNotification, Block::Hash>
+	for GrandpaRpcHandler<AuthoritySet, VoterState, VoterHistory, FinalityLag, Block, ProofProvider>
 where
 	VoterState: ReportVoterState + Send + Sync + 'static,
-	AuthoritySet: ReportAuthoritySet + Send + Sync + 'static,
+	VoterHistory: ReportVoterHistory<Block::Hash> + Send + Sync + 'static,
+	FinalityLag: ReportFinalityLag<Block::Hash> + Send + Sync + 'static,
+	AuthoritySet: ReportAuthoritySet + ReportAuthoritySetChanges<Block::Hash> + Send + Sync + 'static,
 	Block: BlockT,
 	ProofProvider: RpcFinalityProofProvider<Block> + Send + Sync + 'static,
 {
@@ -143,6 +174,23 @@ where
 		Box::new(future.map_err(jsonrpc_core::Error::from).compat())
 	}
 
+	fn voter_history(&self, rounds: Option<u32>) -> FutureResult<ReportedVoterHistory<Block::Hash>> {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			return Box::new(err01(err.into()));
+		}
+
+		let rounds = rounds.map(|n| n as usize).unwrap_or(usize::max_value());
+		let history = ReportedVoterHistory::from(&self.authority_set, &self.voter_history, rounds);
+		let future = async move { history }.boxed();
+		Box::new(future.map_err(jsonrpc_core::Error::from).compat())
+	}
+
+	fn finality_lag(&self) -> FutureResult<ReportedFinalityLag<Block::Hash>> {
+		let finality_lag = ReportedFinalityLag::from(&self.finality_lag);
+		let future = async move { finality_lag }.boxed();
+		Box::new(future.map_err(jsonrpc_core::Error::from).compat())
+	}
+
 	fn subscribe_justifications(
 		&self,
 		_metadata: Self::Metadata,
@@ -221,6 +269,9 @@ mod tests {
 	struct TestAuthoritySet;
 	struct TestVoterState;
 	struct EmptyVoterState;
+	struct TestVoterHistory;
+	struct EmptyVoterHistory;
+	struct TestFinalityLag;
 
 	struct TestFinalityProofProvider {
 		finality_proofs: Vec<FinalityProofFragment<Header>>,
@@ -239,6 +290,83 @@ mod tests {
 		}
 	}
 
+	impl ReportAuthoritySetChanges<H256> for TestAuthoritySet {
+		fn authority_set_changes(&self) -> Vec<crate::report::AuthoritySetChange> {
+			vec![crate::report::AuthoritySetChange { set_id: 0, last_block: 10 }]
+		}
+
+		fn pending_forced_changes(&self) -> Vec<crate::report::PendingForcedChange<H256>> {
+			Vec::new()
+		}
+	}
+
+	impl ReportVoterHistory<H256> for TestVoterHistory {
+		fn rounds(&self, n: usize) -> Vec<crate::report::RoundVotes<H256>> {
+			let prevote = crate::report::Vote {
+				voter: AuthorityId::from_slice(&[1; 32]),
+				target_hash: H256::from_low_u64_be(1),
+				target_number: 1,
+			};
+
+			let rounds = vec![
+				crate::report::RoundVotes {
+					set_id: 1,
+					round: 2,
+					prevotes: Vec::new(),
+					precommits: Vec::new(),
+				},
+				crate::report::RoundVotes {
+					set_id: 1,
+					round: 1,
+					prevotes: vec![prevote],
+					precommits: Vec::new(),
+				},
+			];
+
+			rounds.into_iter().take(n).collect()
+		}
+
+		fn equivocations(&self) -> Vec<crate::report::Equivocation<H256>> {
+			Vec::new()
+		}
+
+		fn peers(&self) -> Option<Vec<crate::report::PeerView>> {
+			Some(vec![crate::report::PeerView {
+				peer_id: "QmTestPeer".into(),
+				set_id: 1,
+				round: 3,
+				last_commit: Some(10),
+				authority: true,
+			}])
+		}
+	}
+
+	impl ReportVoterHistory<H256> for EmptyVoterHistory {
+		fn rounds(&self, _n: usize) -> Vec<crate::report::RoundVotes<H256>> {
+			Vec::new()
+		}
+
+		fn equivocations(&self) -> Vec<crate::report::Equivocation<H256>> {
+			Vec::new()
+		}
+
+		fn peers(&self) -> Option<Vec<crate::report::PeerView>> {
+			None
+		}
+	}
+
+	impl ReportFinalityLag<H256> for TestFinalityLag {
+		fn get(&self) -> Option<crate::report::ReportedFinalityLag<H256>> {
+			Some(crate::report::ReportedFinalityLag {
+				best_number: 300,
+				finalized_hash: H256::from_low_u64_be(10),
+				finalized_number: 10,
+				lag: 290,
+				stalled: true,
+			})
+		}
+	}
+
 	impl ReportVoterState for EmptyVoterState {
 		fn get(&self) -> Option<report::VoterState<AuthorityId>> {
 			None
@@ -308,17 +436,34 @@ mod tests {
 	) where
 		VoterState: ReportVoterState + Send + Sync + 'static,
 	{
-		setup_io_handler_with_finality_proofs(voter_state, Default::default())
+		setup_io_handler_with_finality_proofs(voter_state, TestVoterHistory, Default::default())
+	}
+
+	fn setup_io_handler_with_finality_proofs<VoterState, VoterHistory>(
+		voter_state: VoterState,
+		voter_history: VoterHistory,
+		finality_proofs: Vec<FinalityProofFragment<Header>>,
+	) -> (
+		jsonrpc_core::MetaIoHandler<sc_rpc::Metadata>,
+		GrandpaJustificationSender<Block>,
+	) where
+		VoterState: ReportVoterState + Send + Sync + 'static,
+		VoterHistory: ReportVoterHistory<H256> + Send + Sync + 'static,
+	{
+		setup_io_handler_with_deny_unsafe(voter_state, voter_history, finality_proofs, DenyUnsafe::No)
 	}
 
-	fn setup_io_handler_with_finality_proofs<VoterState>(
+	fn setup_io_handler_with_deny_unsafe<VoterState, VoterHistory>(
 		voter_state: VoterState,
+		voter_history: VoterHistory,
 		finality_proofs: Vec<FinalityProofFragment<Header>>,
+		deny_unsafe: DenyUnsafe,
 	) -> (
 		jsonrpc_core::MetaIoHandler<sc_rpc::Metadata>,
 		GrandpaJustificationSender<Block>,
 	) where
 		VoterState: ReportVoterState + Send + Sync + 'static,
+		VoterHistory: ReportVoterHistory<H256> + Send + Sync + 'static,
 	{
 		let (justification_sender, justification_stream) = GrandpaJustificationStream::channel();
 		let finality_proof_provider = Arc::new(TestFinalityProofProvider { finality_proofs });
@@ -326,9 +471,12 @@ mod tests {
 		let handler = GrandpaRpcHandler::new(
 			TestAuthoritySet,
 			voter_state,
+			voter_history,
+			TestFinalityLag,
 			justification_stream,
 			sc_rpc::testing::TaskExecutor,
 			finality_proof_provider,
+			deny_unsafe,
 		);
 
 		let mut io = jsonrpc_core::MetaIoHandler::default();
@@ -371,6 +519,86 @@ mod tests {
 		assert_eq!(io.handle_request_sync(request, meta), Some(response.into()));
 	}
 
+	#[test]
+	fn uninitialized_voter_history() {
+		let (io, _) = setup_io_handler_with_finality_proofs(
+			TestVoterState,
+			EmptyVoterHistory,
+			Default::default(),
+		);
+
+		let request = r#"{"jsonrpc":"2.0","method":"grandpa_voterHistory","params":[],"id":1}"#;
+		let response = r#"{"jsonrpc":"2.0","error":{"code":1,"message":"GRANDPA RPC endpoint not ready"},"id":1}"#;
+
+		let meta = sc_rpc::Metadata::default();
+		assert_eq!(Some(response.into()), io.handle_request_sync(request, meta));
+	}
+
+	#[test]
+	fn voter_history_is_unsafe() {
+		let (io, _) = setup_io_handler_with_deny_unsafe(
+			TestVoterState,
+			TestVoterHistory,
+			Default::default(),
+			DenyUnsafe::Yes,
+		);
+
+		let request = r#"{"jsonrpc":"2.0","method":"grandpa_voterHistory","params":[],"id":1}"#;
+		let response = r#"{"jsonrpc":"2.0","error":{"code":-32601,"message":"Method not found"},"id":1}"#;
+
+		let meta = sc_rpc::Metadata::default();
+		assert_eq!(Some(response.into()), io.handle_request_sync(request, meta));
+	}
+
+	#[test]
+	fn working_voter_history() {
+		let (io, _) = setup_io_handler(TestVoterState);
+
+		let request = r#"{"jsonrpc":"2.0","method":"grandpa_voterHistory","params":[1],"id":1}"#;
+		let response = "{\"jsonrpc\":\"2.0\",\"result\":{\
+			\"authoritySetChanges\":[{\"lastBlock\":10,\"setId\":0}],\
+			\"equivocations\":[],\
+			\"peers\":[{\
+				\"authority\":true,\"lastCommit\":10,\"peerId\":\"QmTestPeer\",\"round\":3,\"setId\":1\
+			}],\
+			\"pendingForcedChanges\":[],\
+			\"rounds\":[{\"precommits\":[],\"prevotes\":[],\"round\":2,\"setId\":1}]\
+		},\"id\":1}";
+
+		let meta = sc_rpc::Metadata::default();
+		assert_eq!(io.handle_request_sync(request, meta), Some(response.into()));
+
+		let request = r#"{"jsonrpc":"2.0","method":"grandpa_voterHistory","params":[],"id":1}"#;
+		let meta = sc_rpc::Metadata::default();
+		let resp = io.handle_request_sync(request, meta);
+		let resp: serde_json::Value = serde_json::from_str(&resp.unwrap()).unwrap();
+		assert_eq!(
+			resp["result"]["rounds"][1]["prevotes"],
+			serde_json::json!([{
+				"targetHash": "0x0000000000000000000000000000000000000000000000000000000000000001",
+				"targetNumber": 1,
+				"voter": "5C62Ck4UrFPiBtoCmeSrgF7x9yv9mn38446dhCpsi2mLHiFT",
+			}]),
+		);
+	}
+
+	#[test]
+	fn working_finality_lag() {
+		let (io, _) = setup_io_handler(TestVoterState);
+
+		let request = r#"{"jsonrpc":"2.0","method":"grandpa_finalityLag","params":[],"id":1}"#;
+		let response = "{\"jsonrpc\":\"2.0\",\"result\":{\
+			\"bestNumber\":300,\
+			\"finalizedHash\":\"0x000000000000000000000000000000000000000000000000000000000000000a\",\
+			\"finalizedNumber\":10,\
+			\"lag\":290,\
+			\"stalled\":true\
+		},\"id\":1}";
+
+		let meta = sc_rpc::Metadata::default();
+		assert_eq!(io.handle_request_sync(request, meta), Some(response.into()));
+	}
+
 	fn setup_session() -> (sc_rpc::Metadata, jsonrpc_core::futures::sync::mpsc::Receiver<String>) {
 		let (tx, rx) = jsonrpc_core::futures::sync::mpsc::channel(1);
 		let meta = sc_rpc::Metadata::new(tx);
@@ -528,6 +756,7 @@ mod tests {
 		}];
 		let (io,  _) = setup_io_handler_with_finality_proofs(
 			TestVoterState,
+			TestVoterHistory,
 			finality_proofs.clone(),
 		);
 
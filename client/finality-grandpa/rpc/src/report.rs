@@ -24,7 +24,11 @@ use std::{
 
 use serde::{Deserialize, Serialize};
 
-use sc_finality_grandpa::{report, AuthorityId, SharedAuthoritySet, SharedVoterState};
+use sc_finality_grandpa::{
+	report, AuthorityId, EquivocationStage, SharedAuthoritySet, SharedFinalityLag, SharedVoterHistory,
+	SharedVoterState,
+};
+use sp_runtime::traits::{Block as BlockT, NumberFor, UniqueSaturatedInto};
 
 use crate::error::Error;
 
@@ -38,6 +42,24 @@ pub trait ReportVoterState {
 	fn get(&self) -> Option<report::VoterState<AuthorityId>>;
 }
 
+/// Utility trait to get reporting data for the GRANDPA authority set changes.
+pub trait ReportAuthoritySetChanges<Hash> {
+	fn authority_set_changes(&self) -> Vec<AuthoritySetChange>;
+	fn pending_forced_changes(&self) -> Vec<PendingForcedChange<Hash>>;
+}
+
+/// Utility trait to get reporting data for the recent GRANDPA voter history.
+pub trait ReportVoterHistory<Hash> {
+	fn rounds(&self, n: usize) -> Vec<RoundVotes<Hash>>;
+	fn equivocations(&self) -> Vec<Equivocation<Hash>>;
+	fn peers(&self) -> Option<Vec<PeerView>>;
+}
+
+/// Utility trait to get reporting data for the GRANDPA finality lag.
+pub trait ReportFinalityLag<Hash> {
+	fn get(&self) -> Option<ReportedFinalityLag<Hash>>;
+}
+
 impl<H, N> ReportAuthoritySet for SharedAuthoritySet<H, N>
 where
 	N: Add<Output = N> + Ord + Clone + Debug,
@@ -60,6 +82,118 @@ impl ReportVoterState for SharedVoterState {
 	}
 }
 
+impl<H, N> ReportAuthoritySetChanges<H> for SharedAuthoritySet<H, N>
+where
+	N: Add<Output = N> + Ord + Clone + Debug + UniqueSaturatedInto<u64>,
+	H: Clone + Debug + Eq,
+{
+	fn authority_set_changes(&self) -> Vec<AuthoritySetChange> {
+		SharedAuthoritySet::authority_set_changes(self)
+			.iter()
+			.map(|(set_id, last_block)| AuthoritySetChange {
+				set_id: *set_id,
+				last_block: last_block.clone().unique_saturated_into(),
+			})
+			.collect()
+	}
+
+	fn pending_forced_changes(&self) -> Vec<PendingForcedChange<H>> {
+		SharedAuthoritySet::pending_forced_changes(self)
+			.iter()
+			.map(|change| {
+				let (canon_hash, canon_height) = change.canon();
+
+				PendingForcedChange {
+					next_authorities: change.next_authorities().clone(),
+					canon_hash: canon_hash.clone(),
+					canon_height: canon_height.clone().unique_saturated_into(),
+					effective_number: change.effective_number().unique_saturated_into(),
+					median_last_finalized: change
+						.median_last_finalized()
+						.map(|n| n.clone().unique_saturated_into()),
+				}
+			})
+			.collect()
+	}
+}
+
+impl<Block: BlockT> ReportVoterHistory<Block::Hash> for SharedVoterHistory<Block> {
+	fn rounds(&self, n: usize) -> Vec<RoundVotes<Block::Hash>> {
+		let votes = |votes: Vec<sc_finality_grandpa::Vote<Block::Hash, NumberFor<Block>>>| {
+			votes
+				.into_iter()
+				.map(|(voter, target_hash, target_number)| Vote {
+					voter,
+					target_hash,
+					target_number: target_number.unique_saturated_into(),
+				})
+				.collect()
+		};
+
+		SharedVoterHistory::rounds(self, n)
+			.into_iter()
+			.map(|round| RoundVotes {
+				set_id: round.set_id,
+				round: round.round,
+				prevotes: votes(round.prevotes),
+				precommits: votes(round.precommits),
+			})
+			.collect()
+	}
+
+	fn equivocations(&self) -> Vec<Equivocation<Block::Hash>> {
+		SharedVoterHistory::equivocations(self)
+			.into_iter()
+			.map(|equivocation| Equivocation {
+				set_id: equivocation.set_id,
+				round: equivocation.round,
+				stage: match equivocation.stage {
+					EquivocationStage::Prevote => Stage::Prevote,
+					EquivocationStage::Precommit => Stage::Precommit,
+				},
+				offender: equivocation.offender,
+				first: Target {
+					hash: equivocation.first.0,
+					number: equivocation.first.1.unique_saturated_into(),
+				},
+				second: Target {
+					hash: equivocation.second.0,
+					number: equivocation.second.1.unique_saturated_into(),
+				},
+			})
+			.collect()
+	}
+
+	fn peers(&self) -> Option<Vec<PeerView>> {
+		let peers = self.peer_views()?
+			.into_iter()
+			.map(|(peer_id, view)| PeerView {
+				peer_id: peer_id.to_base58(),
+				set_id: view.set_id,
+				round: view.round,
+				last_commit: view.last_commit.map(|n| n.unique_saturated_into()),
+				authority: view.authority,
+			})
+			.collect();
+
+		Some(peers)
+	}
+}
+
+impl<Block: BlockT> ReportFinalityLag<Block::Hash> for SharedFinalityLag<Block> {
+	fn get(&self) -> Option<ReportedFinalityLag<Block::Hash>> {
+		let finality_lag = SharedFinalityLag::get(self)?;
+
+		Some(ReportedFinalityLag {
+			best_number: finality_lag.best_number.unique_saturated_into(),
+			finalized_hash: finality_lag.finalized_hash,
+			finalized_number: finality_lag.finalized_number.unique_saturated_into(),
+			lag: finality_lag.lag().unique_saturated_into(),
+			stalled: finality_lag.stalled,
+		})
+	}
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Prevotes {
@@ -159,3 +293,136 @@ impl ReportedRoundStates {
 		})
 	}
 }
+
+/// The last block of a finished authority set.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthoritySetChange {
+	pub set_id: u64,
+	pub last_block: u64,
+}
+
+/// A forced authority set change that hasn't been applied yet.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingForcedChange<Hash> {
+	pub next_authorities: Vec<(AuthorityId, u64)>,
+	pub canon_hash: Hash,
+	pub canon_height: u64,
+	pub effective_number: u64,
+	pub median_last_finalized: Option<u64>,
+}
+
+/// A vote cast by an authority.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Vote<Hash> {
+	pub voter: AuthorityId,
+	pub target_hash: Hash,
+	pub target_number: u64,
+}
+
+/// The votes seen in a completed round.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundVotes<Hash> {
+	pub set_id: u64,
+	pub round: u64,
+	pub prevotes: Vec<Vote<Hash>>,
+	pub precommits: Vec<Vote<Hash>>,
+}
+
+/// The voting stage an equivocation happened in.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+	Prevote,
+	Precommit,
+}
+
+/// The target block of a vote.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Target<Hash> {
+	pub hash: Hash,
+	pub number: u64,
+}
+
+/// An equivocation detected by the voter.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Equivocation<Hash> {
+	pub set_id: u64,
+	pub round: u64,
+	pub stage: Stage,
+	pub offender: AuthorityId,
+	pub first: Target<Hash>,
+	pub second: Target<Hash>,
+}
+
+/// The view a peer has announced in its last neighbor packet.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerView {
+	pub peer_id: String,
+	pub set_id: u64,
+	pub round: u64,
+	pub last_commit: Option<u64>,
+	pub authority: bool,
+}
+
+/// The recent history of the voter, the authority set changes and the view of
+/// each peer in a form suitable for serialization.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportedVoterHistory<Hash> {
+	rounds: Vec<RoundVotes<Hash>>,
+	equivocations: Vec<Equivocation<Hash>>,
+	authority_set_changes: Vec<AuthoritySetChange>,
+	pending_forced_changes: Vec<PendingForcedChange<Hash>>,
+	peers: Vec<PeerView>,
+}
+
+impl<Hash> ReportedVoterHistory<Hash> {
+	pub fn from<AuthoritySet, VoterHistory>(
+		authority_set: &AuthoritySet,
+		voter_history: &VoterHistory,
+		rounds: usize,
+	) -> Result<Self, Error>
+	where
+		AuthoritySet: ReportAuthoritySetChanges<Hash>,
+		VoterHistory: ReportVoterHistory<Hash>,
+	{
+		let peers = voter_history.peers().ok_or(Error::EndpointNotReady)?;
+
+		Ok(Self {
+			rounds: voter_history.rounds(rounds),
+			equivocations: voter_history.equivocations(),
+			authority_set_changes: authority_set.authority_set_changes(),
+			pending_forced_changes: authority_set.pending_forced_changes(),
+			peers,
+		})
+	}
+}
+
+/// How far finality lags behind the best block in a form suitable for
+/// serialization. The finalized block is the one to use as the best finalized
+/// block when recovering from a stall with a forced authority set change.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportedFinalityLag<Hash> {
+	pub best_number: u64,
+	pub finalized_hash: Hash,
+	pub finalized_number: u64,
+	pub lag: u64,
+	pub stalled: bool,
+}
+
+impl<Hash> ReportedFinalityLag<Hash> {
+	pub fn from<FinalityLag>(finality_lag: &FinalityLag) -> Result<Self, Error>
+	where
+		FinalityLag: ReportFinalityLag<Hash>,
+	{
+		finality_lag.get().ok_or(Error::EndpointNotReady)
+	}
+}
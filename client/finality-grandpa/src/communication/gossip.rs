@@ -91,10 +91,11 @@ use sp_finality_grandpa::AuthorityId;
 use sc_telemetry::{telemetry, CONSENSUS_DEBUG};
 use log::{trace, debug};
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
-use prometheus_endpoint::{CounterVec, Opts, PrometheusError, register, Registry, U64};
+use prometheus_endpoint::{CounterVec, GaugeVec, Opts, PrometheusError, register, Registry, U64};
 use rand::seq::SliceRandom;
 
 use crate::{environment, CatchUp, CompactCommit, SignedMessage};
+use crate::history::PeerView;
 use super::{cost, benefit, Round, SetId};
 
 use std::collections::{HashMap, VecDeque, HashSet};
@@ -740,6 +741,20 @@ impl<Block: BlockT> Inner<Block> {
 		self.multicast_neighbor_packet()
 	}
 
+	/// Count the peers whose view is behind, at or ahead of our local set and
+	/// round, in that order. All peers count as current if we have no local view.
+	fn peer_view_counts(&self) -> (u64, u64, u64) {
+		let local = self.local_view.as_ref().map(|v| (v.set_id, v.round));
+
+		self.peers.inner.values().fold((0, 0, 0), |(behind, current, ahead), info| {
+			match local.map(|local| (info.view.set_id, info.view.round).cmp(&local)) {
+				Some(std::cmp::Ordering::Less) => (behind + 1, current, ahead),
+				Some(std::cmp::Ordering::Greater) => (behind, current, ahead + 1),
+				_ => (behind, current + 1, ahead),
+			}
+		})
+	}
+
 	/// Note that a voter set with given ID has started. Does nothing if the last
 	/// call to the function was with the same `set_id`.
 	fn note_set(&mut self, set_id: SetId, authorities: Vec<AuthorityId>) -> MaybeMessage<Block> {
@@ -1255,6 +1270,7 @@ impl<Block: BlockT> Inner<Block> {
 // Prometheus metrics for [`GossipValidator`].
 pub(crate) struct Metrics {
 	messages_validated: CounterVec<U64>,
+	peer_views: GaugeVec<U64>,
 }
 
 impl Metrics {
@@ -1270,6 +1286,17 @@ impl Metrics {
 				)?,
 				registry,
 			)?,
+			peer_views: register(
+				GaugeVec::new(
+					Opts::new(
+						"finality_grandpa_communication_gossip_validator_peer_views",
+						"Number of peers whose last neighbor packet is behind, at or ahead of \
+						the local set and round."
+					),
+					&["view"]
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
@@ -1362,6 +1389,23 @@ impl<Block: BlockT> GossipValidator<Block> {
 		let _ = self.report_sender.unbounded_send(PeerReport { who, cost_benefit });
 	}
 
+	/// Get the view each connected peer has announced to us in its last
+	/// neighbor packet.
+	pub(super) fn peer_views(&self) -> Vec<(PeerId, PeerView<NumberFor<Block>>)> {
+		self.inner.read().peers.inner.iter()
+			.map(|(who, info)| {
+				let view = PeerView {
+					set_id: info.view.set_id.0,
+					round: info.view.round.0,
+					last_commit: info.view.last_commit,
+					authority: matches!(info.roles, ObservedRole::Authority),
+				};
+
+				(who.clone(), view)
+			})
+			.collect()
+	}
+
 	pub(super) fn do_validate(&self, who: &PeerId, mut data: &[u8])
 		-> (Action<Block::Hash>, Vec<Block::Hash>, Option<GossipMessage<Block>>)
 	{
@@ -1392,6 +1436,13 @@ impl<Block: BlockT> GossipValidator<Block> {
 						self.report(peer, cost_benefit);
 					}
 
+					if let Some(metrics) = &self.metrics {
+						let (behind, current, ahead) = self.inner.read().peer_view_counts();
+						metrics.peer_views.with_label_values(&["behind"]).set(behind);
+						metrics.peer_views.with_label_values(&["current"]).set(current);
+						metrics.peer_views.with_label_values(&["ahead"]).set(ahead);
+					}
+
 					broadcast_topics = topics;
 					peer_reply = catch_up;
 					action
@@ -1632,6 +1683,7 @@ mod tests {
 			keystore: None,
 			name: None,
 			is_authority: true,
+			stall_threshold: None,
 			observer_enabled: true,
 		}
 	}
@@ -2634,4 +2686,43 @@ mod tests {
 
 		assert_eq!(val.inner().read().authorities, a2);
 	}
+
+	#[test]
+	fn reports_peer_views_from_neighbor_packets() {
+		let (val, _) = GossipValidator::<Block>::new(
+			config(),
+			voter_set_state(),
+			None,
+		);
+
+		val.note_set(SetId(1), Vec::new(), |_, _| {});
+		val.note_round(Round(10), |_, _| {});
+
+		let authority = PeerId::random();
+		let full = PeerId::random();
+		val.inner.write().peers.new_peer(authority.clone(), ObservedRole::Authority);
+		val.inner.write().peers.new_peer(full.clone(), ObservedRole::Full);
+
+		let _ = val.inner.write().import_neighbor_message(
+			&authority,
+			NeighborPacket {
+				round: Round(11),
+				set_id: SetId(1),
+				commit_finalized_height: 42,
+			},
+		);
+
+		let mut views = val.peer_views();
+		views.sort_by_key(|(_, view)| view.round);
+
+		assert_eq!(
+			views,
+			vec![
+				(full, PeerView { set_id: 0, round: 1, last_commit: None, authority: false }),
+				(authority, PeerView { set_id: 1, round: 11, last_commit: Some(42), authority: true }),
+			],
+		);
+
+		assert_eq!(val.inner.read().peer_view_counts(), (1, 0, 1));
+	}
 }
@@ -49,6 +49,7 @@ use crate::{
 	CompactCommit, Error, Message, SignedMessage,
 };
 use crate::environment::HasVoted;
+use crate::history::PeerView;
 use gossip::{
 	FullCatchUpMessage,
 	FullCommitMessage,
@@ -270,6 +271,15 @@ impl<B: BlockT, N: Network<B>> NetworkBridge<B, N> {
 		}
 	}
 
+	/// Get a source for the view each connected peer has announced to us in
+	/// its last neighbor packet.
+	pub(crate) fn peer_views(
+		&self,
+	) -> impl Fn() -> Vec<(sc_network::PeerId, PeerView<NumberFor<B>>)> + Send + Sync + 'static {
+		let validator = self.validator.clone();
+		move || validator.peer_views()
+	}
+
 	/// Note the beginning of a new round to the `GossipValidator`.
 	pub(crate) fn note_round(
 		&self,
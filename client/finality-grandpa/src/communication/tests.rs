@@ -133,6 +133,7 @@ fn config() -> crate::Config {
 		keystore: None,
 		name: None,
 		is_authority: true,
+		stall_threshold: None,
 		observer_enabled: true,
 	}
 }
@@ -51,6 +51,7 @@ use sp_consensus::SelectChain;
 use crate::authorities::{AuthoritySet, SharedAuthoritySet};
 use crate::communication::Network as NetworkT;
 use crate::consensus_changes::SharedConsensusChanges;
+use crate::history::{EquivocationRecord, EquivocationStage, SharedVoterHistory};
 use crate::notification::GrandpaJustificationSender;
 use crate::justification::GrandpaJustification;
 use crate::until_imported::UntilVoteTargetImported;
@@ -59,7 +60,7 @@ use sp_finality_grandpa::{
 	AuthorityId, AuthoritySignature, Equivocation, EquivocationProof,
 	GrandpaApi, RoundNumber, SetId,
 };
-use prometheus_endpoint::{register, Counter, Gauge, PrometheusError, U64};
+use prometheus_endpoint::{register, Counter, CounterVec, Gauge, Opts, PrometheusError, U64};
 
 type HistoricalVotes<Block> = finality_grandpa::HistoricalVotes<
 	<Block as BlockT>::Hash,
@@ -380,6 +381,11 @@ pub(crate) struct Metrics {
 	finality_grandpa_round: Gauge<U64>,
 	finality_grandpa_prevotes: Counter<U64>,
 	finality_grandpa_precommits: Counter<U64>,
+	finality_grandpa_round_prevotes: Gauge<U64>,
+	finality_grandpa_round_precommits: Gauge<U64>,
+	finality_grandpa_equivocations: CounterVec<U64>,
+	finality_grandpa_authority_set_changes: Gauge<U64>,
+	finality_grandpa_pending_forced_changes: Gauge<U64>,
 }
 
 impl Metrics {
@@ -405,6 +411,44 @@ impl Metrics {
 				)?,
 				registry,
 			)?,
+			finality_grandpa_round_prevotes: register(
+				Gauge::new(
+					"finality_grandpa_round_prevotes",
+					"Number of prevotes seen in the last completed GRANDPA round.",
+				)?,
+				registry,
+			)?,
+			finality_grandpa_round_precommits: register(
+				Gauge::new(
+					"finality_grandpa_round_precommits",
+					"Number of precommits seen in the last completed GRANDPA round.",
+				)?,
+				registry,
+			)?,
+			finality_grandpa_equivocations: register(
+				CounterVec::new(
+					Opts::new(
+						"finality_grandpa_equivocations_total",
+						"Total number of GRANDPA equivocations detected by the voter.",
+					),
+					&["stage"],
+				)?,
+				registry,
+			)?,
+			finality_grandpa_authority_set_changes: register(
+				Gauge::new(
+					"finality_grandpa_authority_set_changes",
+					"Number of finalized GRANDPA authority set changes.",
+				)?,
+				registry,
+			)?,
+			finality_grandpa_pending_forced_changes: register(
+				Gauge::new(
+					"finality_grandpa_pending_forced_changes",
+					"Number of pending forced GRANDPA authority set changes.",
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
@@ -423,6 +467,7 @@ pub(crate) struct Environment<Backend, Block: BlockT, C, N: NetworkT<Block>, SC,
 	pub(crate) voting_rule: VR,
 	pub(crate) metrics: Option<Metrics>,
 	pub(crate) justification_sender: Option<GrandpaJustificationSender<Block>>,
+	pub(crate) voter_history: SharedVoterHistory<Block>,
 	pub(crate) _phantom: PhantomData<Backend>,
 }
 
@@ -453,6 +498,59 @@ impl<BE, Block: BlockT, C, N: NetworkT<Block>, SC, VR> Environment<BE, Block, C,
 	}
 }
 
+impl<BE, Block: BlockT, C, N: NetworkT<Block>, SC, VR> Environment<BE, Block, C, N, SC, VR> {
+	/// Records the votes seen in the given round in the voter history and
+	/// updates the related metrics.
+	fn note_round_votes(&self, round: RoundNumber, historical_votes: &HistoricalVotes<Block>) {
+		self.voter_history.note_round(self.set_id, round, historical_votes.seen());
+
+		if let Some(metrics) = self.metrics.as_ref() {
+			let (prevotes, precommits) = historical_votes.seen().iter()
+				.fold((0, 0), |(prevotes, precommits), vote| match vote.message {
+					finality_grandpa::Message::Prevote(_) => (prevotes + 1, precommits),
+					finality_grandpa::Message::Precommit(_) => (prevotes, precommits + 1),
+					finality_grandpa::Message::PrimaryPropose(_) => (prevotes, precommits),
+				});
+
+			metrics.finality_grandpa_round_prevotes.set(prevotes);
+			metrics.finality_grandpa_round_precommits.set(precommits);
+			metrics.finality_grandpa_authority_set_changes
+				.set(self.authority_set.authority_set_changes().iter().count() as u64);
+			metrics.finality_grandpa_pending_forced_changes
+				.set(self.authority_set.pending_forced_changes().len() as u64);
+		}
+	}
+
+	/// Records an equivocation detected by the voter in the voter history and
+	/// updates the related metrics.
+	fn note_equivocation(
+		&self,
+		round: RoundNumber,
+		stage: EquivocationStage,
+		offender: AuthorityId,
+		first: (Block::Hash, NumberFor<Block>),
+		second: (Block::Hash, NumberFor<Block>),
+	) {
+		if let Some(metrics) = self.metrics.as_ref() {
+			let stage = match stage {
+				EquivocationStage::Prevote => "prevote",
+				EquivocationStage::Precommit => "precommit",
+			};
+
+			metrics.finality_grandpa_equivocations.with_label_values(&[stage]).inc();
+		}
+
+		self.voter_history.note_equivocation(EquivocationRecord {
+			set_id: self.set_id,
+			round,
+			stage,
+			offender,
+			first,
+			second,
+		});
+	}
+}
+
 impl<BE, Block, C, N, SC, VR> Environment<BE, Block, C, N, SC, VR>
 where
 	Block: BlockT,
@@ -1010,6 +1108,8 @@ where
 			Ok(Some(set_state))
 		})?;
 
+		self.note_round_votes(round, historical_votes);
+
 		Ok(())
 	}
 
@@ -1066,6 +1166,8 @@ where
 			Ok(Some(set_state))
 		})?;
 
+		self.note_round_votes(round, historical_votes);
+
 		Ok(())
 	}
 
@@ -1099,10 +1201,17 @@ where
 
 	fn prevote_equivocation(
 		&self,
-		_round: RoundNumber,
+		round: RoundNumber,
 		equivocation: finality_grandpa::Equivocation<Self::Id, Prevote<Block>, Self::Signature>,
 	) {
 		warn!(target: "afg", "Detected prevote equivocation in the finality worker: {:?}", equivocation);
+		self.note_equivocation(
+			round,
+			EquivocationStage::Prevote,
+			equivocation.identity.clone(),
+			(equivocation.first.0.target_hash, equivocation.first.0.target_number),
+			(equivocation.second.0.target_hash, equivocation.second.0.target_number),
+		);
 		if let Err(err) = self.report_equivocation(equivocation.into()) {
 			warn!(target: "afg", "Error reporting prevote equivocation: {:?}", err);
 		}
@@ -1110,10 +1219,17 @@ where
 
 	fn precommit_equivocation(
 		&self,
-		_round: RoundNumber,
+		round: RoundNumber,
 		equivocation: finality_grandpa::Equivocation<Self::Id, Precommit<Block>, Self::Signature>,
 	) {
 		warn!(target: "afg", "Detected precommit equivocation in the finality worker: {:?}", equivocation);
+		self.note_equivocation(
+			round,
+			EquivocationStage::Precommit,
+			equivocation.identity.clone(),
+			(equivocation.first.0.target_hash, equivocation.first.0.target_number),
+			(equivocation.second.0.target_hash, equivocation.second.0.target_number),
+		);
 		if let Err(err) = self.report_equivocation(equivocation.into()) {
 			warn!(target: "afg", "Error reporting precommit equivocation: {:?}", err);
 		}
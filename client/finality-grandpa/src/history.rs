@@ -0,0 +1,301 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Recent history of the GRANDPA voter, kept in memory for debugging.
+//!
+//! The voter records the votes seen in each completed round and any
+//! equivocations it detects, while the view that each connected peer has
+//! announced through neighbor packets is queried from the gossip validator on
+//! demand. None of this is persisted, it is only meant to be exposed through
+//! RPC so that finality stalls can be diagnosed from a single node.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use sc_network::PeerId;
+use sp_finality_grandpa::{AuthorityId, RoundNumber, SetId};
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+
+use crate::SignedMessage;
+
+/// The maximum number of completed rounds kept in the history.
+pub const MAX_ROUNDS_KEPT: usize = 32;
+
+/// The maximum number of equivocations kept in the history.
+pub const MAX_EQUIVOCATIONS_KEPT: usize = 64;
+
+/// A vote cast by an authority, i.e. the voter and the target block.
+pub type Vote<H, N> = (AuthorityId, H, N);
+
+/// The prevotes and precommits seen during a completed round.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundVotes<H, N> {
+	/// The set id the round belongs to.
+	pub set_id: SetId,
+	/// The round number.
+	pub round: RoundNumber,
+	/// All prevotes seen in the round, including equivocations.
+	pub prevotes: Vec<Vote<H, N>>,
+	/// All precommits seen in the round, including equivocations.
+	pub precommits: Vec<Vote<H, N>>,
+}
+
+/// The voting stage an equivocation happened in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EquivocationStage {
+	/// Two different prevotes were cast.
+	Prevote,
+	/// Two different precommits were cast.
+	Precommit,
+}
+
+/// An equivocation detected by the local voter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquivocationRecord<H, N> {
+	/// The set id the equivocation happened in.
+	pub set_id: SetId,
+	/// The round the equivocation happened in.
+	pub round: RoundNumber,
+	/// The stage the equivocation happened in.
+	pub stage: EquivocationStage,
+	/// The authority that equivocated.
+	pub offender: AuthorityId,
+	/// The target of the first vote.
+	pub first: (H, N),
+	/// The target of the second vote.
+	pub second: (H, N),
+}
+
+/// The view a peer has announced to us in its last neighbor packet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerView<N> {
+	/// The set id the peer is at.
+	pub set_id: SetId,
+	/// The round the peer is at.
+	pub round: RoundNumber,
+	/// The height of the last block finalized by a commit the peer has seen.
+	pub last_commit: Option<N>,
+	/// Whether the peer connected to us as an authority.
+	pub authority: bool,
+}
+
+type PeerViews<N> = Box<dyn Fn() -> Vec<(PeerId, PeerView<N>)> + Send + Sync>;
+
+struct Inner<Block: BlockT> {
+	rounds: VecDeque<RoundVotes<Block::Hash, NumberFor<Block>>>,
+	equivocations: VecDeque<EquivocationRecord<Block::Hash, NumberFor<Block>>>,
+	peer_views: Option<PeerViews<NumberFor<Block>>>,
+}
+
+/// Shared history of the GRANDPA voter for querying.
+pub struct SharedVoterHistory<Block: BlockT> {
+	inner: Arc<RwLock<Inner<Block>>>,
+}
+
+impl<Block: BlockT> Clone for SharedVoterHistory<Block> {
+	fn clone(&self) -> Self {
+		SharedVoterHistory { inner: self.inner.clone() }
+	}
+}
+
+impl<Block: BlockT> SharedVoterHistory<Block> {
+	/// Create a new empty `SharedVoterHistory` instance.
+	pub fn empty() -> Self {
+		SharedVoterHistory {
+			inner: Arc::new(RwLock::new(Inner {
+				rounds: VecDeque::new(),
+				equivocations: VecDeque::new(),
+				peer_views: None,
+			})),
+		}
+	}
+
+	/// Set the source of the peer views, replacing any previous one.
+	pub(crate) fn reset_peer_views(&self, peer_views: PeerViews<NumberFor<Block>>) {
+		self.inner.write().peer_views = Some(peer_views);
+	}
+
+	/// Record the votes seen in the given round. If the round has already been
+	/// recorded its votes are replaced, since votes may still be received after
+	/// a round is completed and until it is concluded.
+	pub(crate) fn note_round(
+		&self,
+		set_id: SetId,
+		round: RoundNumber,
+		votes: &[SignedMessage<Block>],
+	) {
+		let mut prevotes = Vec::new();
+		let mut precommits = Vec::new();
+
+		for vote in votes {
+			match vote.message {
+				finality_grandpa::Message::Prevote(ref prevote) => prevotes.push(
+					(vote.id.clone(), prevote.target_hash, prevote.target_number),
+				),
+				finality_grandpa::Message::Precommit(ref precommit) => precommits.push(
+					(vote.id.clone(), precommit.target_hash, precommit.target_number),
+				),
+				finality_grandpa::Message::PrimaryPropose(_) => {},
+			}
+		}
+
+		let round_votes = RoundVotes { set_id, round, prevotes, precommits };
+
+		let mut inner = self.inner.write();
+		if let Some(existing) = inner.rounds.iter_mut()
+			.find(|r| r.set_id == set_id && r.round == round)
+		{
+			*existing = round_votes;
+			return;
+		}
+
+		if inner.rounds.len() == MAX_ROUNDS_KEPT {
+			inner.rounds.pop_front();
+		}
+		inner.rounds.push_back(round_votes);
+	}
+
+	/// Record an equivocation detected by the voter.
+	pub(crate) fn note_equivocation(
+		&self,
+		equivocation: EquivocationRecord<Block::Hash, NumberFor<Block>>,
+	) {
+		let mut inner = self.inner.write();
+		if inner.equivocations.len() == MAX_EQUIVOCATIONS_KEPT {
+			inner.equivocations.pop_front();
+		}
+		inner.equivocations.push_back(equivocation);
+	}
+
+	/// Get the votes of the last `n` completed rounds, most recent first.
+	pub fn rounds(&self, n: usize) -> Vec<RoundVotes<Block::Hash, NumberFor<Block>>> {
+		self.inner.read().rounds.iter().rev().take(n).cloned().collect()
+	}
+
+	/// Get the equivocations detected by the voter, most recent first.
+	pub fn equivocations(&self) -> Vec<EquivocationRecord<Block::Hash, NumberFor<Block>>> {
+		self.inner.read().equivocations.iter().rev().cloned().collect()
+	}
+
+	/// Get the view each connected peer has announced to us. Returns `None`
+	/// if the voter hasn't started yet.
+	pub fn peer_views(&self) -> Option<Vec<(PeerId, PeerView<NumberFor<Block>>)>> {
+		self.inner.read().peer_views.as_ref().map(|peer_views| peer_views())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+	use sp_keyring::Ed25519Keyring;
+	use substrate_test_runtime_client::runtime::Block;
+
+	fn signed(
+		keyring: Ed25519Keyring,
+		message: finality_grandpa::Message<H256, u64>,
+	) -> SignedMessage<Block> {
+		finality_grandpa::SignedMessage {
+			message,
+			signature: Default::default(),
+			id: keyring.public().into(),
+		}
+	}
+
+	fn prevote(target_hash: H256, target_number: u64) -> finality_grandpa::Message<H256, u64> {
+		finality_grandpa::Message::Prevote(finality_grandpa::Prevote { target_hash, target_number })
+	}
+
+	fn precommit(target_hash: H256, target_number: u64) -> finality_grandpa::Message<H256, u64> {
+		finality_grandpa::Message::Precommit(finality_grandpa::Precommit { target_hash, target_number })
+	}
+
+	#[test]
+	fn keeps_a_bounded_number_of_rounds() {
+		let history = SharedVoterHistory::<Block>::empty();
+
+		for round in 1..=(MAX_ROUNDS_KEPT as u64 + 10) {
+			history.note_round(0, round, &[]);
+		}
+
+		let rounds = history.rounds(usize::max_value());
+		assert_eq!(rounds.len(), MAX_ROUNDS_KEPT);
+		assert_eq!(rounds[0].round, MAX_ROUNDS_KEPT as u64 + 10);
+		assert_eq!(rounds[MAX_ROUNDS_KEPT - 1].round, 11);
+
+		assert_eq!(history.rounds(3).iter().map(|r| r.round).collect::<Vec<_>>(), vec![42, 41, 40]);
+	}
+
+	#[test]
+	fn concluding_a_round_replaces_its_votes() {
+		let history = SharedVoterHistory::<Block>::empty();
+		let hash = H256::random();
+
+		let mut votes = vec![
+			signed(Ed25519Keyring::Alice, prevote(hash, 1)),
+			signed(Ed25519Keyring::Bob, prevote(hash, 1)),
+			signed(Ed25519Keyring::Alice, precommit(hash, 1)),
+		];
+
+		// completed
+		history.note_round(0, 1, &votes);
+
+		// concluded, with the votes seen afterwards
+		votes.push(signed(Ed25519Keyring::Bob, precommit(hash, 1)));
+		history.note_round(0, 1, &votes);
+
+		let rounds = history.rounds(10);
+		assert_eq!(rounds.len(), 1);
+		assert_eq!(
+			rounds[0].prevotes,
+			vec![
+				(Ed25519Keyring::Alice.public().into(), hash, 1),
+				(Ed25519Keyring::Bob.public().into(), hash, 1),
+			],
+		);
+		assert_eq!(
+			rounds[0].precommits,
+			vec![
+				(Ed25519Keyring::Alice.public().into(), hash, 1),
+				(Ed25519Keyring::Bob.public().into(), hash, 1),
+			],
+		);
+
+		// same round number in a different set is a different round
+		history.note_round(1, 1, &[]);
+		assert_eq!(history.rounds(10).len(), 2);
+	}
+
+	#[test]
+	fn peer_views_are_only_available_once_set() {
+		let history = SharedVoterHistory::<Block>::empty();
+		assert_eq!(history.peer_views(), None);
+
+		let peer = PeerId::random();
+		let view = PeerView { set_id: 1, round: 5, last_commit: Some(10), authority: true };
+
+		{
+			let peer = peer.clone();
+			let view = view.clone();
+			history.reset_peer_views(Box::new(move || vec![(peer.clone(), view.clone())]));
+		}
+
+		assert_eq!(history.peer_views(), Some(vec![(peer, view)]));
+	}
+}
@@ -115,22 +115,30 @@ mod communication;
 mod consensus_changes;
 mod environment;
 mod finality_proof;
+mod history;
 mod import;
 mod justification;
 mod light_import;
 mod notification;
 mod observer;
+mod stall;
 mod until_imported;
 mod voting_rule;
 
 pub use authorities::{SharedAuthoritySet, AuthoritySet};
 pub use finality_proof::{FinalityProofFragment, FinalityProofProvider, StorageAndProofProvider};
+pub use history::{
+	EquivocationRecord, EquivocationStage, PeerView, RoundVotes, SharedVoterHistory, Vote,
+};
+pub use stall::{FinalityLag, SharedFinalityLag};
 pub use notification::{GrandpaJustificationSender, GrandpaJustificationStream};
 pub use import::GrandpaBlockImport;
 pub use justification::GrandpaJustification;
 pub use light_import::{light_block_import, GrandpaLightBlockImport};
 pub use voting_rule::{
-	BeforeBestBlockBy, ThreeQuartersOfTheUnfinalizedChain, VotingRule, VotingRulesBuilder
+	AvailableToPeers, BeforeBestBlockBy, EpochDataAvailability, KnownEpochData,
+	MaxUnfinalizedBlocksPerRound, PeerBestBlocks, RuntimeVotingRule,
+	ThreeQuartersOfTheUnfinalizedChain, VotingRule, VotingRulesBuilder,
 };
 pub use finality_grandpa::voter::report;
 
@@ -272,6 +280,9 @@ pub struct Config {
 	pub name: Option<String>,
 	/// The keystore that manages the keys of this node.
 	pub keystore: Option<SyncCryptoStorePtr>,
+	/// The number of blocks finality may lag behind the best block before it is
+	/// considered stalled. `None` disables stall detection.
+	pub stall_threshold: Option<u32>,
 }
 
 impl Config {
@@ -451,6 +462,8 @@ pub struct LinkHalf<Block: BlockT, C, SC> {
 	voter_commands_rx: TracingUnboundedReceiver<VoterCommand<Block::Hash, NumberFor<Block>>>,
 	justification_sender: GrandpaJustificationSender<Block>,
 	justification_stream: GrandpaJustificationStream<Block>,
+	voter_history: SharedVoterHistory<Block>,
+	finality_lag: SharedFinalityLag<Block>,
 }
 
 impl<Block: BlockT, C, SC> LinkHalf<Block, C, SC> {
@@ -463,6 +476,16 @@ impl<Block: BlockT, C, SC> LinkHalf<Block, C, SC> {
 	pub fn justification_stream(&self) -> GrandpaJustificationStream<Block> {
 		self.justification_stream.clone()
 	}
+
+	/// Get the recent history of the voter.
+	pub fn voter_history(&self) -> SharedVoterHistory<Block> {
+		self.voter_history.clone()
+	}
+
+	/// Get how far finality lags behind the best block.
+	pub fn finality_lag(&self) -> SharedFinalityLag<Block> {
+		self.finality_lag.clone()
+	}
 }
 
 /// Provider for the Grandpa authority set configured on the genesis block.
@@ -599,6 +622,8 @@ where
 			voter_commands_rx,
 			justification_sender,
 			justification_stream,
+			voter_history: SharedVoterHistory::empty(),
+			finality_lag: SharedFinalityLag::empty(),
 		},
 	))
 }
@@ -706,6 +731,8 @@ where
 		voter_commands_rx,
 		justification_sender,
 		justification_stream: _,
+		voter_history,
+		finality_lag,
 	} = link;
 
 	let network = NetworkBridge::new(
@@ -715,6 +742,10 @@ where
 		prometheus_registry.as_ref(),
 	);
 
+	// Repoint the peer views of the voter history so that the RPC endpoint can
+	// query what each peer has announced to us.
+	voter_history.reset_peer_views(Box::new(network.peer_views()));
+
 	let conf = config.clone();
 	let telemetry_task = if let Some(telemetry_on_connect) = telemetry_on_connect {
 		let authorities = persistent_data.authority_set.clone();
@@ -748,6 +779,12 @@ where
 		future::Either::Right(future::pending())
 	};
 
+	let finality_lag_task = stall::track_finality_lag(
+		client.clone(),
+		finality_lag,
+		config.stall_threshold.map(Into::into),
+	);
+
 	let voter_work = VoterWork::new(
 		client,
 		config,
@@ -759,6 +796,7 @@ where
 		prometheus_registry,
 		shared_voter_state,
 		justification_sender,
+		voter_history,
 	);
 
 	let voter_work = voter_work.map(|res| match res {
@@ -772,6 +810,13 @@ where
 	let telemetry_task = telemetry_task
 		.then(|_| future::pending::<()>());
 
+	// Neither should the finality lag tracking, which ends when the client
+	// stops sending notifications.
+	let finality_lag_task = Box::pin(finality_lag_task
+		.then(|_| future::pending::<()>()));
+
+	let voter_work = future::select(voter_work, finality_lag_task).map(drop);
+
 	Ok(future::select(voter_work, telemetry_task).map(drop))
 }
 
@@ -824,6 +869,7 @@ where
 		prometheus_registry: Option<prometheus_endpoint::Registry>,
 		shared_voter_state: SharedVoterState,
 		justification_sender: GrandpaJustificationSender<Block>,
+		voter_history: SharedVoterHistory<Block>,
 	) -> Self {
 		let metrics = match prometheus_registry.as_ref().map(Metrics::register) {
 			Some(Ok(metrics)) => Some(metrics),
@@ -848,6 +894,7 @@ where
 			voter_set_state: persistent_data.set_state,
 			metrics: metrics.as_ref().map(|m| m.environment.clone()),
 			justification_sender: Some(justification_sender),
+			voter_history,
 			_phantom: PhantomData,
 		});
 
@@ -994,6 +1041,7 @@ where
 					voting_rule: self.env.voting_rule.clone(),
 					metrics: self.env.metrics.clone(),
 					justification_sender: self.env.justification_sender.clone(),
+					voter_history: self.env.voter_history.clone(),
 					_phantom: PhantomData,
 				});
 
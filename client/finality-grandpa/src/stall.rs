@@ -0,0 +1,185 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Detection of finality stalls.
+//!
+//! The voter tracks how far finality lags behind the best block and considers
+//! finality stalled once the lag goes over the configured threshold. The best
+//! finalized block is what should be used as the median last finalized block
+//! when signaling a forced authority set change to recover from the stall
+//! (e.g. through `pallet_grandpa::recover_from_stall`), so it is exposed along
+//! with the lag for querying.
+
+use std::sync::Arc;
+
+use futures::{prelude::*, stream};
+use log::{info, warn};
+use parking_lot::RwLock;
+use sc_client_api::BlockchainEvents;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::traits::{Block as BlockT, NumberFor, Saturating};
+
+/// How far finality lags behind the best block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinalityLag<H, N> {
+	/// The number of the best block.
+	pub best_number: N,
+	/// The hash of the best finalized block.
+	pub finalized_hash: H,
+	/// The number of the best finalized block.
+	pub finalized_number: N,
+	/// Whether the lag is over the configured stall threshold.
+	pub stalled: bool,
+}
+
+impl<H, N: Saturating + Clone> FinalityLag<H, N> {
+	/// The number of unfinalized blocks on the best chain.
+	pub fn lag(&self) -> N {
+		self.best_number.clone().saturating_sub(self.finalized_number.clone())
+	}
+}
+
+/// Shared view of the finality lag for querying.
+pub struct SharedFinalityLag<Block: BlockT> {
+	inner: Arc<RwLock<Option<FinalityLag<Block::Hash, NumberFor<Block>>>>>,
+}
+
+impl<Block: BlockT> Clone for SharedFinalityLag<Block> {
+	fn clone(&self) -> Self {
+		SharedFinalityLag { inner: self.inner.clone() }
+	}
+}
+
+impl<Block: BlockT> SharedFinalityLag<Block> {
+	/// Create a new empty `SharedFinalityLag` instance.
+	pub fn empty() -> Self {
+		SharedFinalityLag { inner: Arc::new(RwLock::new(None)) }
+	}
+
+	/// Get the last observed finality lag. Returns `None` if the voter hasn't
+	/// started yet.
+	pub fn get(&self) -> Option<FinalityLag<Block::Hash, NumberFor<Block>>> {
+		self.inner.read().clone()
+	}
+
+	/// Update the finality lag given the current chain info, logging whenever
+	/// finality stalls or recovers.
+	pub(crate) fn update(
+		&self,
+		info: &sp_blockchain::Info<Block>,
+		threshold: Option<NumberFor<Block>>,
+	) {
+		let mut lag = FinalityLag {
+			best_number: info.best_number,
+			finalized_hash: info.finalized_hash,
+			finalized_number: info.finalized_number,
+			stalled: false,
+		};
+
+		lag.stalled = threshold.map_or(false, |threshold| lag.lag() > threshold);
+
+		let mut inner = self.inner.write();
+		let was_stalled = inner.as_ref().map_or(false, |lag| lag.stalled);
+
+		if lag.stalled && !was_stalled {
+			warn!(target: "afg",
+				"🚨 Finality has stalled: best block #{} is {} blocks ahead of the last finalized block #{} ({})",
+				lag.best_number,
+				lag.lag(),
+				lag.finalized_number,
+				lag.finalized_hash,
+			);
+		} else if !lag.stalled && was_stalled {
+			info!(target: "afg",
+				"Finality has recovered: last finalized block is #{} ({})",
+				lag.finalized_number,
+				lag.finalized_hash,
+			);
+		}
+
+		*inner = Some(lag);
+	}
+}
+
+/// Keep the shared finality lag up to date with the chain, for as long as the
+/// client keeps sending notifications.
+pub(crate) async fn track_finality_lag<Block, C>(
+	client: Arc<C>,
+	finality_lag: SharedFinalityLag<Block>,
+	threshold: Option<NumberFor<Block>>,
+) where
+	Block: BlockT,
+	C: BlockchainEvents<Block> + HeaderBackend<Block>,
+{
+	let imported = client.import_notification_stream().map(|_| ());
+	let finalized = client.finality_notification_stream().map(|_| ());
+	let mut events = stream::select(imported, finalized);
+
+	finality_lag.update(&client.info(), threshold);
+
+	while let Some(()) = events.next().await {
+		finality_lag.update(&client.info(), threshold);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+	use substrate_test_runtime_client::runtime::Block;
+
+	fn info(best_number: u64, finalized_number: u64) -> sp_blockchain::Info<Block> {
+		sp_blockchain::Info {
+			best_hash: Default::default(),
+			best_number,
+			genesis_hash: Default::default(),
+			finalized_hash: H256::from_low_u64_be(finalized_number),
+			finalized_number,
+			number_leaves: 1,
+		}
+	}
+
+	#[test]
+	fn stalls_when_lag_is_over_threshold() {
+		let finality_lag = SharedFinalityLag::<Block>::empty();
+		assert_eq!(finality_lag.get(), None);
+
+		finality_lag.update(&info(10, 5), Some(5));
+		assert!(!finality_lag.get().unwrap().stalled);
+
+		finality_lag.update(&info(11, 5), Some(5));
+		assert_eq!(
+			finality_lag.get(),
+			Some(FinalityLag {
+				best_number: 11,
+				finalized_hash: H256::from_low_u64_be(5),
+				finalized_number: 5,
+				stalled: true,
+			}),
+		);
+		assert_eq!(finality_lag.get().unwrap().lag(), 6);
+
+		// finality catching up recovers from the stall
+		finality_lag.update(&info(11, 10), Some(5));
+		assert!(!finality_lag.get().unwrap().stalled);
+
+		// without a threshold finality is never considered stalled
+		finality_lag.update(&info(1000, 10), None);
+		assert!(!finality_lag.get().unwrap().stalled);
+	}
+}
@@ -367,6 +367,7 @@ fn run_to_completion_with<F>(
 				keystore: Some(keystore),
 				name: Some(format!("peer#{}", peer_id)),
 				is_authority: true,
+				stall_threshold: None,
 				observer_enabled: true,
 			},
 			link: link,
@@ -498,6 +499,7 @@ fn finalize_3_voters_1_full_observer() {
 				keystore,
 				name: Some(format!("peer#{}", peer_id)),
 				is_authority: true,
+				stall_threshold: None,
 				observer_enabled: true,
 			},
 			link: link,
@@ -661,6 +663,7 @@ fn transition_3_voters_twice_1_full_observer() {
 				keystore: Some(keystore),
 				name: Some(format!("peer#{}", peer_id)),
 				is_authority: true,
+				stall_threshold: None,
 				observer_enabled: true,
 			},
 			link: link,
@@ -908,6 +911,94 @@ fn force_change_to_new_set() {
 	run_to_completion(&mut runtime, 25, net, peers_a);
 }
 
+#[test]
+fn voter_detects_stall_and_recovers_with_forced_change() {
+	sp_tracing::try_init_simple();
+	let mut runtime = Runtime::new().unwrap();
+	// bob and charlie are offline so alice can't finalize on her own.
+	let genesis_authorities = &[Ed25519Keyring::Alice, Ed25519Keyring::Bob, Ed25519Keyring::Charlie];
+	let api = TestApi::new(make_ids(genesis_authorities));
+
+	let net = GrandpaTestNet::new(api, 1);
+	let net = Arc::new(Mutex::new(net));
+
+	net.lock().peer(0).push_blocks(10, false);
+
+	let (keystore, _keystore_path) = create_keystore(Ed25519Keyring::Alice);
+	let (client, net_service, link) = {
+		let net = net.lock();
+		let link = net.peers[0].data.lock().take().expect("link initialized at startup; qed");
+		(net.peers[0].client().clone(), net.peers[0].network_service().clone(), link)
+	};
+
+	let finality_lag = link.finality_lag();
+	assert_eq!(finality_lag.get(), None);
+
+	let grandpa_params = GrandpaParams {
+		config: Config {
+			gossip_duration: TEST_GOSSIP_DURATION,
+			justification_period: 32,
+			keystore: Some(keystore),
+			name: Some("peer#0".to_string()),
+			is_authority: true,
+			stall_threshold: Some(5),
+			observer_enabled: true,
+		},
+		link,
+		network: net_service,
+		telemetry_on_connect: None,
+		voting_rule: (),
+		prometheus_registry: None,
+		shared_voter_state: SharedVoterState::empty(),
+	};
+
+	runtime.spawn(run_grandpa_voter(grandpa_params).expect("all in order with client and network"));
+
+	let wait_for_lag = |
+		runtime: &mut Runtime,
+		condition: Box<dyn Fn(&FinalityLag<Hash, BlockNumber>) -> bool>,
+	| {
+		let finality_lag = finality_lag.clone();
+		let wait = futures::future::poll_fn(move |cx| {
+			match finality_lag.get() {
+				Some(ref lag) if condition(lag) => Poll::Ready(()),
+				_ => {
+					// keep polling until the tracker has seen the expected lag.
+					cx.waker().wake_by_ref();
+					Poll::Pending
+				},
+			}
+		});
+		block_until_complete(wait, &net, runtime);
+	};
+
+	wait_for_lag(&mut runtime, Box::new(|lag| lag.stalled));
+	assert_eq!(
+		finality_lag.get(),
+		Some(FinalityLag {
+			best_number: 10,
+			finalized_hash: client.info().genesis_hash,
+			finalized_number: 0,
+			stalled: true,
+		}),
+	);
+
+	// schedule a forced change to alice alone, building on the best finalized
+	// block reported by the finality lag.
+	net.lock().peer(0).generate_blocks(1, BlockOrigin::File, |builder| {
+		let mut block = builder.build().unwrap().block;
+		add_forced_change(&mut block, 0, ScheduledChange {
+			next_authorities: make_ids(&[Ed25519Keyring::Alice]),
+			delay: 1,
+		});
+		block
+	});
+	net.lock().peer(0).push_blocks(2, false);
+
+	wait_for_lag(&mut runtime, Box::new(|lag| lag.finalized_number == 13));
+	assert!(!finality_lag.get().unwrap().stalled);
+}
+
 #[test]
 fn allows_reimporting_change_blocks() {
 	let peers_a = &[Ed25519Keyring::Alice, Ed25519Keyring::Bob, Ed25519Keyring::Charlie];
@@ -1085,6 +1176,7 @@ fn voter_persists_its_votes() {
 								keystore: Some(this.keystore.clone()),
 								name: Some(format!("peer#{}", 0)),
 								is_authority: true,
+								stall_threshold: None,
 								observer_enabled: true,
 							},
 							link,
@@ -1142,6 +1234,7 @@ fn voter_persists_its_votes() {
 			keystore: Some(keystore.clone()),
 			name: Some(format!("peer#{}", 1)),
 			is_authority: true,
+			stall_threshold: None,
 			observer_enabled: true,
 		};
 
@@ -1302,6 +1395,7 @@ fn finalize_3_voters_1_light_observer() {
 					keystore: None,
 					name: Some("observer".to_string()),
 					is_authority: false,
+					stall_threshold: None,
 					observer_enabled: true,
 				},
 				link,
@@ -1430,6 +1524,7 @@ fn voter_catches_up_to_latest_round_when_behind() {
 				keystore,
 				name: Some(format!("peer#{}", peer_id)),
 				is_authority: true,
+				stall_threshold: None,
 				observer_enabled: true,
 			},
 			link,
@@ -1553,6 +1648,7 @@ where
 		keystore,
 		name: None,
 		is_authority: true,
+		stall_threshold: None,
 		observer_enabled: true,
 	};
 
@@ -1576,6 +1672,7 @@ where
 		voting_rule,
 		metrics: None,
 		justification_sender: None,
+		voter_history: link.voter_history.clone(),
 		_phantom: PhantomData,
 	}
 }
@@ -1859,3 +1956,68 @@ fn grandpa_environment_doesnt_send_equivocation_reports_for_itself() {
 	let equivocation_proof = sp_finality_grandpa::Equivocation::Prevote(equivocation);
 	assert!(environment.report_equivocation(equivocation_proof).is_ok());
 }
+
+#[test]
+fn grandpa_environment_records_voter_history() {
+	use finality_grandpa::voter::Environment;
+
+	let alice = Ed25519Keyring::Alice;
+	let voters = make_ids(&[alice]);
+
+	let mut net = GrandpaTestNet::new(TestApi::new(voters), 1);
+	let peer = net.peer(0);
+	let network_service = peer.network_service().clone();
+	let link = peer.data.lock().take().unwrap();
+	let (keystore, _keystore_path) = create_keystore(alice);
+	let environment = test_environment(&link, Some(keystore), network_service.clone(), ());
+
+	let first = finality_grandpa::Prevote { target_hash: H256::random(), target_number: 1 };
+	let second = finality_grandpa::Prevote { target_hash: H256::random(), target_number: 1 };
+	let signature: AuthoritySignature = alice.sign(&[]).into();
+
+	let historical_votes = finality_grandpa::HistoricalVotes::new_with(
+		vec![finality_grandpa::SignedMessage {
+			message: finality_grandpa::Message::Prevote(first.clone()),
+			signature: signature.clone(),
+			id: alice.public().into(),
+		}],
+		None,
+		None,
+	);
+
+	environment
+		.completed(
+			1,
+			finality_grandpa::round::State::genesis(Default::default()),
+			Default::default(),
+			&historical_votes,
+		)
+		.unwrap();
+
+	// the votes of the completed round are kept in the history shared with the link
+	let rounds = link.voter_history().rounds(10);
+	assert_eq!(rounds.len(), 1);
+	assert_eq!(rounds[0].round, 1);
+	assert_eq!(rounds[0].prevotes, vec![(alice.public().into(), first.target_hash, 1)]);
+	assert!(rounds[0].precommits.is_empty());
+
+	// as well as any equivocation detected, even if it isn't reported
+	environment.prevote_equivocation(1, finality_grandpa::Equivocation {
+		round_number: 1,
+		identity: alice.public().into(),
+		first: (first.clone(), signature.clone()),
+		second: (second.clone(), signature),
+	});
+
+	assert_eq!(
+		link.voter_history().equivocations(),
+		vec![EquivocationRecord {
+			set_id: 0,
+			round: 1,
+			stage: EquivocationStage::Prevote,
+			offender: alice.public().into(),
+			first: (first.target_hash, 1),
+			second: (second.target_hash, 1),
+		}],
+	);
+}
@@ -20,13 +20,20 @@
 //!
 //! This exposes the `VotingRule` trait used to implement arbitrary voting
 //! restrictions that are taken into account by the GRANDPA environment when
-//! selecting a finality target to vote on.
+//! selecting a finality target to vote on, along with a set of rules that can
+//! be composed with the `VotingRulesBuilder`. The `RuntimeVotingRule` applies
+//! rules whose parameters are read from the runtime through the
+//! `GrandpaVotingRulesApi`, so that they can be tuned on-chain.
 
 use std::sync::Arc;
 
+use log::debug;
 use sc_client_api::blockchain::HeaderBackend;
+use sp_api::ProvideRuntimeApi;
+use sp_finality_grandpa::GrandpaVotingRulesApi;
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{Block as BlockT, Header, NumberFor, One, Zero};
+use sp_runtime::{Percent, PerThing};
 
 /// A trait for custom voting rules in GRANDPA.
 pub trait VotingRule<Block, B>: Send + Sync where
@@ -150,6 +157,270 @@ impl<Block, B> VotingRule<Block, B> for ThreeQuartersOfTheUnfinalizedChain where
 	}
 }
 
+/// A custom voting rule that limits the number of unfinalized blocks voted on
+/// in a single round, i.e. our vote is never more than N blocks ahead of the
+/// given `base`.
+#[derive(Clone)]
+pub struct MaxUnfinalizedBlocksPerRound<N>(pub N);
+
+impl<Block, B> VotingRule<Block, B> for MaxUnfinalizedBlocksPerRound<NumberFor<Block>> where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+{
+	fn restrict_vote(
+		&self,
+		backend: &B,
+		base: &Block::Header,
+		_best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)> {
+		use sp_arithmetic::traits::Saturating;
+
+		let target_number = base.number().saturating_add(self.0);
+
+		// our current target is already lower than this rule would restrict
+		if target_number >= *current_target.number() {
+			return None;
+		}
+
+		find_target(
+			backend,
+			target_number,
+			current_target,
+		)
+	}
+}
+
+/// Source of the best block announced by each connected peer.
+pub trait PeerBestBlocks<Block: BlockT>: Send + Sync {
+	/// Get the number of the best block announced by each connected peer.
+	fn peer_best_blocks(&self) -> Vec<NumberFor<Block>>;
+}
+
+impl<Block: BlockT> PeerBestBlocks<Block> for () {
+	fn peer_best_blocks(&self) -> Vec<NumberFor<Block>> {
+		Vec::new()
+	}
+}
+
+impl<Block, F> PeerBestBlocks<Block> for F where
+	Block: BlockT,
+	F: Fn() -> Vec<NumberFor<Block>> + Send + Sync,
+{
+	fn peer_best_blocks(&self) -> Vec<NumberFor<Block>> {
+		(self)()
+	}
+}
+
+/// A custom voting rule that never votes on blocks that are not yet available
+/// to the given share of the connected peers, as given by the best block they
+/// have announced. Votes are never restricted below the given `base`, and if
+/// there are no connected peers the vote isn't restricted.
+pub struct AvailableToPeers<P> {
+	peers: Arc<P>,
+	min_availability: Percent,
+}
+
+impl<P> AvailableToPeers<P> {
+	/// Create a new rule restricting votes to the blocks available to at least
+	/// `min_availability` of the peers returned by `peers`.
+	pub fn new(peers: P, min_availability: Percent) -> Self {
+		AvailableToPeers { peers: Arc::new(peers), min_availability }
+	}
+}
+
+impl<P> Clone for AvailableToPeers<P> {
+	fn clone(&self) -> Self {
+		AvailableToPeers { peers: self.peers.clone(), min_availability: self.min_availability }
+	}
+}
+
+impl<Block, B, P> VotingRule<Block, B> for AvailableToPeers<P> where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+	P: PeerBestBlocks<Block>,
+{
+	fn restrict_vote(
+		&self,
+		backend: &B,
+		base: &Block::Header,
+		_best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)> {
+		let mut best_blocks = self.peers.peer_best_blocks();
+
+		// the number of peers the block must be available to (rounding up)
+		let percent = self.min_availability.deconstruct() as usize;
+		let required = (best_blocks.len() * percent + 99) / 100;
+
+		if required == 0 {
+			return None;
+		}
+
+		// the highest block that at least `required` peers have announced
+		best_blocks.sort_unstable_by(|a, b| b.cmp(a));
+		let target_number = best_blocks[required - 1].max(*base.number());
+
+		// our current target is already lower than this rule would restrict
+		if target_number >= *current_target.number() {
+			return None;
+		}
+
+		find_target(
+			backend,
+			target_number,
+			current_target,
+		)
+	}
+}
+
+/// Source of the availability of epoch data (e.g. BABE epoch changes) for
+/// blocks.
+pub trait EpochDataAvailability<Block: BlockT>: Send + Sync {
+	/// Whether the epoch data of the given block is known.
+	fn has_epoch_data(&self, header: &Block::Header) -> bool;
+}
+
+impl<Block, F> EpochDataAvailability<Block> for F where
+	Block: BlockT,
+	F: Fn(&Block::Header) -> bool + Send + Sync,
+{
+	fn has_epoch_data(&self, header: &Block::Header) -> bool {
+		(self)(header)
+	}
+}
+
+/// A custom voting rule that only votes on blocks whose epoch data is known,
+/// restricting the vote to the highest such ancestor of the current target.
+/// Votes are never restricted below the given `base`.
+pub struct KnownEpochData<E>(Arc<E>);
+
+impl<E> KnownEpochData<E> {
+	/// Create a new rule using the given source of epoch data availability.
+	pub fn new(epoch_data: E) -> Self {
+		KnownEpochData(Arc::new(epoch_data))
+	}
+}
+
+impl<E> Clone for KnownEpochData<E> {
+	fn clone(&self) -> Self {
+		KnownEpochData(self.0.clone())
+	}
+}
+
+impl<Block, B, E> VotingRule<Block, B> for KnownEpochData<E> where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+	E: EpochDataAvailability<Block>,
+{
+	fn restrict_vote(
+		&self,
+		backend: &B,
+		base: &Block::Header,
+		_best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)> {
+		if self.0.has_epoch_data(current_target) {
+			return None;
+		}
+
+		let mut target_header = current_target.clone();
+
+		while target_header.number() > base.number() {
+			target_header = backend.header(BlockId::Hash(*target_header.parent_hash())).ok()?
+				.expect("Header known to exist due to the existence of one of its descendents; qed");
+
+			if self.0.has_epoch_data(&target_header) {
+				break;
+			}
+		}
+
+		Some((target_header.hash(), *target_header.number()))
+	}
+}
+
+/// A custom voting rule that applies the rules configured in the runtime
+/// through the `GrandpaVotingRulesApi`, read at the given `best_target` so that
+/// updated parameters take effect without having to finalize them first. No
+/// votes are restricted if the runtime at `best_target` doesn't implement the
+/// API yet. Parameters that would prevent any progress past `base` (i.e. zero
+/// unfinalized blocks per round or a 100% peer availability) are ignored. The
+/// peer availability rule has no effect if `()` is given as the source of peer
+/// best blocks.
+pub struct RuntimeVotingRule<C, P> {
+	client: Arc<C>,
+	peers: Arc<P>,
+}
+
+impl<C, P> RuntimeVotingRule<C, P> {
+	/// Create a new rule reading its parameters with the given client.
+	pub fn new(client: Arc<C>, peers: P) -> Self {
+		RuntimeVotingRule { client, peers: Arc::new(peers) }
+	}
+}
+
+impl<C, P> Clone for RuntimeVotingRule<C, P> {
+	fn clone(&self) -> Self {
+		RuntimeVotingRule { client: self.client.clone(), peers: self.peers.clone() }
+	}
+}
+
+impl<Block, B, C, P> VotingRule<Block, B> for RuntimeVotingRule<C, P> where
+	Block: BlockT,
+	B: HeaderBackend<Block>,
+	C: ProvideRuntimeApi<Block> + Send + Sync,
+	C::Api: GrandpaVotingRulesApi<Block, Error = sp_blockchain::Error>,
+	P: PeerBestBlocks<Block> + 'static,
+{
+	fn restrict_vote(
+		&self,
+		backend: &B,
+		base: &Block::Header,
+		best_target: &Block::Header,
+		current_target: &Block::Header,
+	) -> Option<(Block::Hash, NumberFor<Block>)> {
+		let at = BlockId::Hash(best_target.hash());
+		let api = self.client.runtime_api();
+
+		let parameters = match api.has_api::<dyn GrandpaVotingRulesApi<Block, Error = ()>>(&at) {
+			Ok(true) => api.voting_rule_parameters(&at),
+			Ok(false) => return None,
+			Err(err) => Err(err),
+		};
+
+		let parameters = match parameters {
+			Ok(parameters) => parameters,
+			Err(err) => {
+				debug!(target: "afg", "Failed to read voting rule parameters at {:?}: {:?}", at, err);
+				return None;
+			},
+		};
+
+		let mut rules: Vec<Box<dyn VotingRule<Block, B>>> = Vec::new();
+
+		if let Some(n) = parameters.before_best_block_by {
+			rules.push(Box::new(BeforeBestBlockBy(n)));
+		}
+
+		if let Some(n) = parameters.max_unfinalized_blocks_per_round.filter(|n| !n.is_zero()) {
+			rules.push(Box::new(MaxUnfinalizedBlocksPerRound(n)));
+		}
+
+		let min_peer_availability = parameters.min_peer_availability
+			.filter(|min_availability| !min_availability.is_one());
+
+		if let Some(min_availability) = min_peer_availability {
+			rules.push(Box::new(AvailableToPeers {
+				peers: self.peers.clone(),
+				min_availability,
+			}));
+		}
+
+		VotingRules { rules: Arc::new(rules) }
+			.restrict_vote(backend, base, best_target, current_target)
+	}
+}
+
 // walk backwards until we find the target block
 fn find_target<Block, B>(
 	backend: &B,
@@ -296,3 +567,84 @@ impl<Block, B> VotingRule<Block, B> for Box<dyn VotingRule<Block, B>> where
 		(**self).restrict_vote(backend, base, best_target, current_target)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_block_builder::BlockBuilderProvider;
+	use sp_consensus::BlockOrigin;
+	use substrate_test_runtime_client::{
+		prelude::*,
+		runtime::{Block, Header},
+		Client,
+	};
+
+	fn client_with_blocks(n: u64) -> Client<Backend> {
+		let mut client = TestClientBuilder::new().build();
+
+		for _ in 0..n {
+			let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+			client.import(BlockOrigin::Own, block).unwrap();
+		}
+
+		client
+	}
+
+	fn header(client: &Client<Backend>, number: u64) -> Header {
+		client.header(&BlockId::Number(number)).unwrap().unwrap()
+	}
+
+	fn restrict<R: VotingRule<Block, Client<Backend>>>(
+		rule: &R,
+		client: &Client<Backend>,
+		base: u64,
+		target: u64,
+	) -> Option<u64> {
+		let best = header(client, client.info().best_number);
+		rule.restrict_vote(client, &header(client, base), &best, &header(client, target))
+			.map(|(hash, number)| {
+				assert_eq!(hash, header(client, number).hash());
+				number
+			})
+	}
+
+	#[test]
+	fn max_unfinalized_blocks_per_round_limits_vote_from_base() {
+		let client = client_with_blocks(20);
+		let rule = MaxUnfinalizedBlocksPerRound(5);
+
+		assert_eq!(restrict(&rule, &client, 10, 20), Some(15));
+		assert_eq!(restrict(&rule, &client, 10, 15), None);
+		assert_eq!(restrict(&rule, &client, 10, 12), None);
+	}
+
+	#[test]
+	fn available_to_peers_votes_on_blocks_most_peers_have() {
+		let client = client_with_blocks(20);
+
+		let rule = AvailableToPeers::new(|| vec![18u64, 12, 20, 15], Percent::from_percent(50));
+		// two of the four peers have block 18
+		assert_eq!(restrict(&rule, &client, 5, 20), Some(18));
+
+		let rule = AvailableToPeers::new(|| vec![18u64, 12, 20, 15], Percent::from_percent(75));
+		// rounding up, three of the four peers have block 15
+		assert_eq!(restrict(&rule, &client, 5, 20), Some(15));
+		// but votes are never restricted below the base
+		assert_eq!(restrict(&rule, &client, 16, 20), Some(16));
+
+		// without peers the vote isn't restricted
+		let rule = AvailableToPeers::new(|| Vec::<u64>::new(), Percent::from_percent(100));
+		assert_eq!(restrict(&rule, &client, 5, 20), None);
+	}
+
+	#[test]
+	fn known_epoch_data_votes_on_highest_block_with_epoch_data() {
+		let client = client_with_blocks(20);
+
+		let rule = KnownEpochData::new(|header: &Header| header.number % 10 == 0);
+		assert_eq!(restrict(&rule, &client, 5, 17), Some(10));
+		assert_eq!(restrict(&rule, &client, 5, 10), None);
+		// votes are never restricted below the base
+		assert_eq!(restrict(&rule, &client, 12, 17), Some(12));
+	}
+}
@@ -31,6 +31,9 @@ use sp_utils::{status_sinks, mpsc::tracing_unbounded};
 use std::{fmt::Display, sync::Arc, time::Duration, collections::VecDeque};
 
 mod display;
+mod migration;
+
+pub use migration::MigrationInformant;
 
 /// The format to print telemetry output in.
 #[derive(Clone, Debug)]
@@ -0,0 +1,99 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::OutputFormat;
+use ansi_term::Colour;
+use log::info;
+use sc_client_db::migration::MigrationProgress;
+use std::time::Duration;
+use wasm_timer::Instant;
+
+/// Minimal interval between two progress lines of the same column.
+const DISPLAY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Displays the progress of database migrations.
+///
+/// Pass `MigrationInformant::display` as the progress callback of the migration. Progress
+/// within a column is printed at most every few seconds, the end of every step is always printed.
+pub struct MigrationInformant {
+	/// The last time a progress line has been printed.
+	last_update: Option<Instant>,
+	/// When the current step started.
+	step_started: Instant,
+	/// The format to print output in.
+	format: OutputFormat,
+}
+
+impl MigrationInformant {
+	/// Builds a new migration informant.
+	pub fn new(format: OutputFormat) -> Self {
+		MigrationInformant {
+			last_update: None,
+			step_started: Instant::now(),
+			format,
+		}
+	}
+
+	/// Displays the migration progress by calling `info!`.
+	pub fn display(&mut self, progress: &MigrationProgress) {
+		let now = Instant::now();
+		let prefix = if progress.dry_run { "[dry run] " } else { "" };
+		match progress.column {
+			Some(column) => {
+				if self.last_update.map_or(false, |last| now - last < DISPLAY_INTERVAL) {
+					return;
+				}
+				self.last_update = Some(now);
+				let entries = format!("{} entries", progress.entries);
+				info!(
+					target: "db",
+					"🔧 {}Migrating to v{} ({}): column {}, {}, {} changes",
+					prefix,
+					progress.version,
+					progress.step,
+					column,
+					self.paint(Colour::White.bold(), &entries),
+					progress.changes,
+				);
+			},
+			None => {
+				let elapsed = (now - self.step_started).as_secs();
+				self.step_started = now;
+				self.last_update = None;
+				info!(
+					target: "db",
+					"✅ {}Migrated to v{} ({}): {} entries, {} changes in {}s",
+					prefix,
+					self.paint(Colour::Green.bold(), &progress.version.to_string()),
+					progress.step,
+					progress.entries,
+					progress.changes,
+					elapsed,
+				);
+			},
+		}
+	}
+
+	fn paint(&self, style: ansi_term::Style, text: &str) -> String {
+		if self.format.enable_color {
+			style.paint(text).to_string()
+		} else {
+			text.to_string()
+		}
+	}
+}
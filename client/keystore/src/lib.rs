@@ -24,6 +24,9 @@ use sp_keystore::Error as TraitError;
 /// Local keystore implementation
 mod local;
 pub use local::LocalKeystore;
+/// Remote keystore implementation
+pub mod remote;
+pub use remote::RemoteKeystore;
 
 /// Keystore error.
 #[derive(Debug, derive_more::Display, derive_more::From)]
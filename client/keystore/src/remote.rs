@@ -0,0 +1,919 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//
+//! Remote keystore implementation
+//!
+//! [`RemoteKeystore`] holds no keys itself. Every request is forwarded to an external signer
+//! that is reached over a Unix domain socket. Both sides speak JSON-RPC 2.0 with one request or
+//! response object per line. Key types, public keys, messages and signatures are hex encoded.
+//! Requests time out after [`REQUEST_TIMEOUT`]. They are all made one after the other by a
+//! single worker thread, so that a hung signer doesn't stall the executor of the async
+//! [`CryptoStore`] methods.
+//!
+//! [`RemoteSigner`] implements the signer side of the protocol on top of any
+//! [`SyncCryptoStore`].
+
+use std::{
+	collections::HashSet,
+	convert::TryFrom,
+	io::{self, BufRead, Write},
+	path::PathBuf,
+	sync::{Arc, mpsc},
+	time::Duration,
+};
+use async_trait::async_trait;
+use futures::channel::oneshot;
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize, de::DeserializeOwned};
+use serde_json::{json, Value};
+use sp_core::{
+	Bytes,
+	crypto::{CryptoTypeId, CryptoTypePublicPair, KeyTypeId, Public},
+	ed25519, sr25519, ecdsa,
+};
+use sp_keystore::{
+	CryptoStore,
+	SyncCryptoStorePtr,
+	Error as TraitError,
+	SyncCryptoStore,
+	vrf::{VRFTranscriptData, VRFTranscriptValue, VRFSignature},
+};
+
+use crate::Result;
+
+/// URI scheme of a signer listening on a Unix domain socket.
+pub const UNIX_SCHEME: &str = "unix://";
+
+/// Maximum time to wait for the signer to accept a request or to respond to it.
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of distinct VRF transcript labels a [`RemoteSigner`] accepts.
+///
+/// Transcript labels are `'static`, so each new label is leaked once. Honest clients only
+/// ever use a handful of them.
+const MAX_TRANSCRIPT_LABELS: usize = 256;
+
+/// Error codes used in responses, next to the ones reserved by JSON-RPC 2.0.
+mod code {
+	pub const PARSE_ERROR: i64 = -32700;
+	pub const METHOD_NOT_FOUND: i64 = -32601;
+	pub const INVALID_PARAMS: i64 = -32602;
+	pub const KEY_NOT_SUPPORTED: i64 = 1;
+	pub const PAIR_NOT_FOUND: i64 = 2;
+	pub const VALIDATION_ERROR: i64 = 3;
+	pub const UNAVAILABLE: i64 = 4;
+	pub const OTHER: i64 = 5;
+}
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+	jsonrpc: String,
+	id: u64,
+	method: String,
+	#[serde(default)]
+	params: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Response {
+	jsonrpc: String,
+	id: Option<u64>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	result: Option<Value>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	error: Option<RpcError>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RpcError {
+	code: i64,
+	message: String,
+}
+
+impl RpcError {
+	fn new(code: i64, message: impl Into<String>) -> Self {
+		RpcError { code, message: message.into() }
+	}
+
+	fn invalid_params(message: impl Into<String>) -> Self {
+		Self::new(code::INVALID_PARAMS, message)
+	}
+
+	fn into_trait_error(self, key_type: KeyTypeId) -> TraitError {
+		match self.code {
+			code::KEY_NOT_SUPPORTED => TraitError::KeyNotSupported(key_type),
+			code::PAIR_NOT_FOUND => TraitError::PairNotFound(self.message),
+			code::VALIDATION_ERROR => TraitError::ValidationError(self.message),
+			code::UNAVAILABLE => TraitError::Unavailable,
+			_ => TraitError::Other(self.message),
+		}
+	}
+}
+
+impl From<TraitError> for RpcError {
+	fn from(error: TraitError) -> Self {
+		let code = match error {
+			TraitError::KeyNotSupported(_) => code::KEY_NOT_SUPPORTED,
+			TraitError::PairNotFound(_) => code::PAIR_NOT_FOUND,
+			TraitError::ValidationError(_) => code::VALIDATION_ERROR,
+			TraitError::Unavailable => code::UNAVAILABLE,
+			TraitError::Other(_) => code::OTHER,
+		};
+		RpcError::new(code, error.to_string())
+	}
+}
+
+/// A public key tagged with its crypto type.
+#[derive(Serialize, Deserialize)]
+struct PublicPair(Bytes, Bytes);
+
+impl From<CryptoTypePublicPair> for PublicPair {
+	fn from(pair: CryptoTypePublicPair) -> Self {
+		PublicPair(Bytes((pair.0).0.to_vec()), Bytes(pair.1))
+	}
+}
+
+impl TryFrom<PublicPair> for CryptoTypePublicPair {
+	type Error = ();
+
+	fn try_from(pair: PublicPair) -> std::result::Result<Self, ()> {
+		Ok(CryptoTypePublicPair(CryptoTypeId(array(&pair.0)?), (pair.1).0))
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+struct Transcript {
+	label: Bytes,
+	items: Vec<(String, TranscriptValue)>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum TranscriptValue {
+	Bytes(Bytes),
+	U64(u64),
+}
+
+impl From<VRFTranscriptData> for Transcript {
+	fn from(data: VRFTranscriptData) -> Self {
+		Transcript {
+			label: Bytes(data.label.to_vec()),
+			items: data.items.into_iter()
+				.map(|(label, value)| (label.to_string(), match value {
+					VRFTranscriptValue::Bytes(bytes) => TranscriptValue::Bytes(Bytes(bytes)),
+					VRFTranscriptValue::U64(val) => TranscriptValue::U64(val),
+				}))
+				.collect(),
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+struct VrfSignature {
+	output: Bytes,
+	proof: Bytes,
+}
+
+fn array(bytes: &[u8]) -> std::result::Result<[u8; 4], ()> {
+	<[u8; 4]>::try_from(bytes).map_err(|_| ())
+}
+
+fn key_type(id: KeyTypeId) -> Bytes {
+	Bytes(id.0.to_vec())
+}
+
+fn sign_method(crypto_id: CryptoTypeId) -> Option<&'static str> {
+	match crypto_id {
+		sr25519::CRYPTO_ID => Some("sr25519_sign"),
+		ed25519::CRYPTO_ID => Some("ed25519_sign"),
+		ecdsa::CRYPTO_ID => Some("ecdsa_sign"),
+		_ => None,
+	}
+}
+
+/// Open connection to a signer.
+struct Connection {
+	reader: Box<dyn BufRead + Send>,
+	writer: Box<dyn Write + Send>,
+	next_id: u64,
+}
+
+impl Connection {
+	#[cfg(unix)]
+	fn open(path: &PathBuf, timeout: Duration) -> io::Result<Self> {
+		let stream = std::os::unix::net::UnixStream::connect(path)?;
+		stream.set_read_timeout(Some(timeout))?;
+		stream.set_write_timeout(Some(timeout))?;
+		Ok(Connection {
+			reader: Box::new(io::BufReader::new(stream.try_clone()?)),
+			writer: Box::new(stream),
+			next_id: 0,
+		})
+	}
+
+	#[cfg(not(unix))]
+	fn open(_: &PathBuf, _: Duration) -> io::Result<Self> {
+		Err(io::Error::new(
+			io::ErrorKind::Other,
+			"Unix domain sockets are not supported on this platform",
+		))
+	}
+
+	fn request(&mut self, method: &str, params: Value) -> io::Result<Response> {
+		self.next_id += 1;
+		let request = Request {
+			jsonrpc: "2.0".into(),
+			id: self.next_id,
+			method: method.into(),
+			params,
+		};
+		let mut line = serde_json::to_string(&request)?;
+		line.push('\n');
+		self.writer.write_all(line.as_bytes())?;
+		self.writer.flush()?;
+
+		line.clear();
+		if self.reader.read_line(&mut line)? == 0 {
+			return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Signer closed the connection"));
+		}
+		let response: Response = serde_json::from_str(&line)?;
+		if response.id != Some(self.next_id) {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "Unexpected response id"));
+		}
+		Ok(response)
+	}
+}
+
+/// Outcome of a request made by the worker thread.
+type Outcome = std::result::Result<Response, TraitError>;
+
+/// Request for the worker thread, along with the channel to send its outcome to.
+struct Job {
+	method: String,
+	params: Value,
+	outcome: oneshot::Sender<Outcome>,
+}
+
+/// Makes the requests of a [`RemoteKeystore`] and its clones on a dedicated thread.
+struct Worker {
+	path: PathBuf,
+	timeout: Duration,
+	connection: Option<Connection>,
+}
+
+impl Worker {
+	/// Make the requests until every sender of `jobs` is dropped.
+	fn run(mut self, jobs: mpsc::Receiver<Job>) {
+		for job in jobs {
+			let outcome = self.request(&job.method, job.params);
+			let _ = job.outcome.send(outcome);
+		}
+	}
+
+	fn request(&mut self, method: &str, params: Value) -> Outcome {
+		if self.connection.is_none() {
+			self.connection = Some(
+				Connection::open(&self.path, self.timeout).map_err(|_| TraitError::Unavailable)?
+			);
+		}
+		let response = self.connection.as_mut()
+			.expect("Connection was established above; qed")
+			.request(method, params);
+		response.map_err(|_| {
+			self.connection = None;
+			TraitError::Unavailable
+		})
+	}
+}
+
+/// A keystore that forwards all requests to an external signer.
+///
+/// The connection is re-established on the next request if the signer goes away or doesn't
+/// respond in time. Requests fail with [`TraitError::Unavailable`] while the signer can not be
+/// reached.
+#[derive(Clone)]
+pub struct RemoteKeystore {
+	jobs: Arc<Mutex<mpsc::Sender<Job>>>,
+}
+
+impl RemoteKeystore {
+	/// Connect to the signer at the given URI.
+	///
+	/// Only `unix://<path>` URIs are supported.
+	pub fn open(uri: &str) -> Result<Self> {
+		Self::open_with_timeout(uri, REQUEST_TIMEOUT)
+	}
+
+	fn open_with_timeout(uri: &str, timeout: Duration) -> Result<Self> {
+		let path = uri.strip_prefix(UNIX_SCHEME)
+			.filter(|path| !path.is_empty())
+			.ok_or_else(|| io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("Unsupported keystore URI `{}`, expected `{}<path>`", uri, UNIX_SCHEME),
+			))?;
+		let path = PathBuf::from(path);
+		let connection = Connection::open(&path, timeout)?;
+
+		let (sender, jobs) = mpsc::channel();
+		let worker = Worker { path, timeout, connection: Some(connection) };
+		std::thread::Builder::new()
+			.name("remote-keystore".into())
+			.spawn(move || worker.run(jobs))?;
+
+		Ok(RemoteKeystore { jobs: Arc::new(Mutex::new(sender)) })
+	}
+
+	/// Queue a request for the worker thread.
+	fn send(&self, method: &str, params: Value) -> oneshot::Receiver<Outcome> {
+		let (outcome, receiver) = oneshot::channel();
+		// the outcome is cancelled if the worker is gone.
+		let _ = self.jobs.lock().send(Job { method: method.into(), params, outcome });
+		receiver
+	}
+
+	/// Make a request, blocking until its response is received.
+	fn call<R: DeserializeOwned>(
+		&self,
+		id: KeyTypeId,
+		method: &str,
+		params: Value,
+	) -> std::result::Result<R, TraitError> {
+		decode(id, method, futures::executor::block_on(self.send(method, params)))
+	}
+
+	/// Make a request, without blocking the executor while waiting for its response.
+	async fn call_async<R: DeserializeOwned>(
+		&self,
+		id: KeyTypeId,
+		method: &str,
+		params: Value,
+	) -> std::result::Result<R, TraitError> {
+		decode(id, method, self.send(method, params).await)
+	}
+}
+
+/// Decode the result of a request, once made.
+fn decode<R: DeserializeOwned>(
+	id: KeyTypeId,
+	method: &str,
+	outcome: std::result::Result<Outcome, oneshot::Canceled>,
+) -> std::result::Result<R, TraitError> {
+	let response = outcome.map_err(|_| TraitError::Unavailable)??;
+	if let Some(error) = response.error {
+		return Err(error.into_trait_error(id));
+	}
+	serde_json::from_value(response.result.unwrap_or(Value::Null))
+		.map_err(|e| TraitError::Other(format!("Invalid `{}` response: {}", method, e)))
+}
+
+fn public_pairs(
+	method: &str,
+	pairs: Vec<PublicPair>,
+) -> std::result::Result<Vec<CryptoTypePublicPair>, TraitError> {
+	pairs.into_iter()
+		.map(|pair| CryptoTypePublicPair::try_from(pair)
+			.map_err(|()| TraitError::Other(format!("Invalid `{}` crypto type", method)))
+		)
+		.collect()
+}
+
+fn public_keys<P: Public>(keys: std::result::Result<Vec<Bytes>, TraitError>) -> Vec<P> {
+	keys.map(|keys| keys.iter().filter_map(|k| P::try_from(&k[..]).ok()).collect())
+		.unwrap_or_default()
+}
+
+fn generated<P: Public>(
+	method: &str,
+	public: std::result::Result<Bytes, TraitError>,
+) -> std::result::Result<P, TraitError> {
+	P::try_from(&public?[..])
+		.map_err(|_| TraitError::Other(format!("Invalid `{}` public key", method)))
+}
+
+fn sign_params(
+	id: KeyTypeId,
+	key: &CryptoTypePublicPair,
+	msg: &[u8],
+) -> std::result::Result<(&'static str, Value), TraitError> {
+	let method = sign_method(key.0).ok_or(TraitError::KeyNotSupported(id))?;
+	Ok((method, json!([key_type(id), Bytes(key.1.clone()), Bytes(msg.to_vec())])))
+}
+
+fn has_keys_params(public_keys: &[(Vec<u8>, KeyTypeId)]) -> Value {
+	let keys = public_keys.iter()
+		.map(|(public, id)| (Bytes(public.clone()), key_type(*id)))
+		.collect::<Vec<_>>();
+	json!([keys])
+}
+
+fn vrf_sign_params(
+	id: KeyTypeId,
+	public: &sr25519::Public,
+	transcript_data: VRFTranscriptData,
+) -> Value {
+	json!([key_type(id), Bytes(public.to_raw_vec()), Transcript::from(transcript_data)])
+}
+
+fn vrf_signature(
+	signature: std::result::Result<VrfSignature, TraitError>,
+) -> std::result::Result<VRFSignature, TraitError> {
+	let signature = signature?;
+	let invalid = |e| TraitError::Other(format!("Invalid `sr25519_vrf_sign` response: {:?}", e));
+	Ok(VRFSignature {
+		output: schnorrkel::vrf::VRFOutput::from_bytes(&signature.output).map_err(invalid)?,
+		proof: schnorrkel::vrf::VRFProof::from_bytes(&signature.proof).map_err(invalid)?,
+	})
+}
+
+#[async_trait]
+impl CryptoStore for RemoteKeystore {
+	async fn keys(&self, id: KeyTypeId) -> std::result::Result<Vec<CryptoTypePublicPair>, TraitError> {
+		public_pairs("keys", self.call_async(id, "keys", json!([key_type(id)])).await?)
+	}
+
+	async fn sr25519_public_keys(&self, id: KeyTypeId) -> Vec<sr25519::Public> {
+		public_keys(self.call_async(id, "sr25519_public_keys", json!([key_type(id)])).await)
+	}
+
+	async fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<sr25519::Public, TraitError> {
+		let method = "sr25519_generate_new";
+		generated(method, self.call_async(id, method, json!([key_type(id), seed])).await)
+	}
+
+	async fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public> {
+		public_keys(self.call_async(id, "ed25519_public_keys", json!([key_type(id)])).await)
+	}
+
+	async fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ed25519::Public, TraitError> {
+		let method = "ed25519_generate_new";
+		generated(method, self.call_async(id, method, json!([key_type(id), seed])).await)
+	}
+
+	async fn ecdsa_public_keys(&self, id: KeyTypeId) -> Vec<ecdsa::Public> {
+		public_keys(self.call_async(id, "ecdsa_public_keys", json!([key_type(id)])).await)
+	}
+
+	async fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ecdsa::Public, TraitError> {
+		let method = "ecdsa_generate_new";
+		generated(method, self.call_async(id, method, json!([key_type(id), seed])).await)
+	}
+
+	async fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8]) -> std::result::Result<(), ()> {
+		let params = json!([key_type(id), suri, Bytes(public.to_vec())]);
+		self.call_async::<()>(id, "insert_unknown", params).await.map_err(|_| ())
+	}
+
+	async fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let params = has_keys_params(public_keys);
+		self.call_async::<bool>(Default::default(), "has_keys", params).await.unwrap_or(false)
+	}
+
+	async fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>,
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, TraitError> {
+		let keys = keys.into_iter().map(PublicPair::from).collect::<Vec<_>>();
+		let method = "supported_keys";
+		public_pairs(method, self.call_async(id, method, json!([key_type(id), keys])).await?)
+	}
+
+	async fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> std::result::Result<Vec<u8>, TraitError> {
+		let (method, params) = sign_params(id, key, msg)?;
+		self.call_async::<Bytes>(id, method, params).await.map(|signature| signature.0)
+	}
+
+	async fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> std::result::Result<VRFSignature, TraitError> {
+		let params = vrf_sign_params(key_type, public, transcript_data);
+		vrf_signature(self.call_async(key_type, "sr25519_vrf_sign", params).await)
+	}
+}
+
+impl SyncCryptoStore for RemoteKeystore {
+	fn keys(
+		&self,
+		id: KeyTypeId
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, TraitError> {
+		public_pairs("keys", self.call(id, "keys", json!([key_type(id)]))?)
+	}
+
+	fn supported_keys(
+		&self,
+		id: KeyTypeId,
+		keys: Vec<CryptoTypePublicPair>
+	) -> std::result::Result<Vec<CryptoTypePublicPair>, TraitError> {
+		let keys = keys.into_iter().map(PublicPair::from).collect::<Vec<_>>();
+		let method = "supported_keys";
+		public_pairs(method, self.call(id, method, json!([key_type(id), keys]))?)
+	}
+
+	fn sign_with(
+		&self,
+		id: KeyTypeId,
+		key: &CryptoTypePublicPair,
+		msg: &[u8],
+	) -> std::result::Result<Vec<u8>, TraitError> {
+		let (method, params) = sign_params(id, key, msg)?;
+		self.call::<Bytes>(id, method, params).map(|signature| signature.0)
+	}
+
+	fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		public_keys(self.call(key_type, "sr25519_public_keys", json!([self::key_type(key_type)])))
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<sr25519::Public, TraitError> {
+		let method = "sr25519_generate_new";
+		generated(method, self.call(id, method, json!([key_type(id), seed])))
+	}
+
+	fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		public_keys(self.call(key_type, "ed25519_public_keys", json!([self::key_type(key_type)])))
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ed25519::Public, TraitError> {
+		let method = "ed25519_generate_new";
+		generated(method, self.call(id, method, json!([key_type(id), seed])))
+	}
+
+	fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		public_keys(self.call(key_type, "ecdsa_public_keys", json!([self::key_type(key_type)])))
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ecdsa::Public, TraitError> {
+		let method = "ecdsa_generate_new";
+		generated(method, self.call(id, method, json!([key_type(id), seed])))
+	}
+
+	fn insert_unknown(&self, id: KeyTypeId, suri: &str, public: &[u8])
+		-> std::result::Result<(), ()>
+	{
+		self.call::<()>(id, "insert_unknown", json!([key_type(id), suri, Bytes(public.to_vec())]))
+			.map_err(|_| ())
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		self.call::<bool>(Default::default(), "has_keys", has_keys_params(public_keys)).unwrap_or(false)
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		transcript_data: VRFTranscriptData,
+	) -> std::result::Result<VRFSignature, TraitError> {
+		let params = vrf_sign_params(key_type, public, transcript_data);
+		vrf_signature(self.call(key_type, "sr25519_vrf_sign", params))
+	}
+}
+
+/// Signer side of the remote keystore protocol.
+///
+/// Serves the requests of [`RemoteKeystore`] clients from the wrapped keystore.
+pub struct RemoteSigner {
+	keystore: SyncCryptoStorePtr,
+	labels: Mutex<HashSet<&'static [u8]>>,
+}
+
+impl RemoteSigner {
+	/// Create a signer that serves requests from the given keystore.
+	pub fn new(keystore: SyncCryptoStorePtr) -> Self {
+		RemoteSigner { keystore, labels: Mutex::new(HashSet::new()) }
+	}
+
+	/// Serve the requests read from `reader` until it is closed.
+	///
+	/// Responses are written to `writer` in the order of the requests.
+	pub fn serve<R: BufRead, W: Write>(&self, reader: R, mut writer: W) -> io::Result<()> {
+		for line in reader.lines() {
+			let line = line?;
+			if line.trim().is_empty() {
+				continue;
+			}
+
+			let (id, outcome) = match serde_json::from_str::<Request>(&line) {
+				Ok(request) => (Some(request.id), self.handle(&request.method, request.params)),
+				Err(e) => (None, Err(RpcError::new(code::PARSE_ERROR, e.to_string()))),
+			};
+			let (result, error) = match outcome {
+				Ok(result) => (Some(result), None),
+				Err(error) => (None, Some(error)),
+			};
+			let response = Response { jsonrpc: "2.0".into(), id, result, error };
+
+			serde_json::to_writer(&mut writer, &response)?;
+			writer.write_all(b"\n")?;
+			writer.flush()?;
+		}
+		Ok(())
+	}
+
+	fn handle(&self, method: &str, params: Value) -> std::result::Result<Value, RpcError> {
+		let keystore = &*self.keystore;
+		match method {
+			"keys" => {
+				let (id,): (Bytes,) = parse_params(params)?;
+				let keys = SyncCryptoStore::keys(keystore, parse_key_type(&id)?)?;
+				to_value(keys.into_iter().map(PublicPair::from).collect::<Vec<_>>())
+			},
+			"supported_keys" => {
+				let (id, keys): (Bytes, Vec<PublicPair>) = parse_params(params)?;
+				let keys = keys.into_iter()
+					.map(CryptoTypePublicPair::try_from)
+					.collect::<std::result::Result<Vec<_>, _>>()
+					.map_err(|()| RpcError::invalid_params("Invalid crypto type"))?;
+				let keys = SyncCryptoStore::supported_keys(keystore, parse_key_type(&id)?, keys)?;
+				to_value(keys.into_iter().map(PublicPair::from).collect::<Vec<_>>())
+			},
+			"sr25519_public_keys" => {
+				let (id,): (Bytes,) = parse_params(params)?;
+				to_value(raw_keys(&SyncCryptoStore::sr25519_public_keys(keystore, parse_key_type(&id)?)))
+			},
+			"ed25519_public_keys" => {
+				let (id,): (Bytes,) = parse_params(params)?;
+				to_value(raw_keys(&SyncCryptoStore::ed25519_public_keys(keystore, parse_key_type(&id)?)))
+			},
+			"ecdsa_public_keys" => {
+				let (id,): (Bytes,) = parse_params(params)?;
+				to_value(raw_keys(&SyncCryptoStore::ecdsa_public_keys(keystore, parse_key_type(&id)?)))
+			},
+			"sr25519_generate_new" => {
+				let (id, seed): (Bytes, Option<String>) = parse_params(params)?;
+				let public = SyncCryptoStore::sr25519_generate_new(
+					keystore,
+					parse_key_type(&id)?,
+					seed.as_deref(),
+				)?;
+				to_value(Bytes(public.to_raw_vec()))
+			},
+			"ed25519_generate_new" => {
+				let (id, seed): (Bytes, Option<String>) = parse_params(params)?;
+				let public = SyncCryptoStore::ed25519_generate_new(
+					keystore,
+					parse_key_type(&id)?,
+					seed.as_deref(),
+				)?;
+				to_value(Bytes(public.to_raw_vec()))
+			},
+			"ecdsa_generate_new" => {
+				let (id, seed): (Bytes, Option<String>) = parse_params(params)?;
+				let public = SyncCryptoStore::ecdsa_generate_new(
+					keystore,
+					parse_key_type(&id)?,
+					seed.as_deref(),
+				)?;
+				to_value(Bytes(public.to_raw_vec()))
+			},
+			"insert_unknown" => {
+				let (id, suri, public): (Bytes, String, Bytes) = parse_params(params)?;
+				SyncCryptoStore::insert_unknown(keystore, parse_key_type(&id)?, &suri, &public)
+					.map_err(|()| RpcError::new(code::OTHER, "Failed to insert key"))?;
+				Ok(Value::Null)
+			},
+			"has_keys" => {
+				let (keys,): (Vec<(Bytes, Bytes)>,) = parse_params(params)?;
+				let keys = keys.into_iter()
+					.map(|(public, id)| Ok((public.0, parse_key_type(&id)?)))
+					.collect::<std::result::Result<Vec<_>, RpcError>>()?;
+				to_value(SyncCryptoStore::has_keys(keystore, &keys))
+			},
+			"sr25519_sign" => self.sign::<sr25519::Public>(sr25519::CRYPTO_ID, params),
+			"ed25519_sign" => self.sign::<ed25519::Public>(ed25519::CRYPTO_ID, params),
+			"ecdsa_sign" => self.sign::<ecdsa::Public>(ecdsa::CRYPTO_ID, params),
+			"sr25519_vrf_sign" => {
+				let (id, public, transcript): (Bytes, Bytes, Transcript) = parse_params(params)?;
+				let public = sr25519::Public::try_from(&public[..])
+					.map_err(|_| RpcError::invalid_params("Invalid public key"))?;
+				let transcript_data = self.transcript_data(transcript)?;
+				let signature = SyncCryptoStore::sr25519_vrf_sign(
+					keystore,
+					parse_key_type(&id)?,
+					&public,
+					transcript_data,
+				)?;
+				to_value(VrfSignature {
+					output: Bytes(signature.output.to_bytes().to_vec()),
+					proof: Bytes(signature.proof.to_bytes().to_vec()),
+				})
+			},
+			_ => Err(RpcError::new(code::METHOD_NOT_FOUND, format!("Unknown method `{}`", method))),
+		}
+	}
+
+	fn sign<P: Public>(
+		&self,
+		crypto_id: CryptoTypeId,
+		params: Value,
+	) -> std::result::Result<Value, RpcError> {
+		let (id, public, msg): (Bytes, Bytes, Bytes) = parse_params(params)?;
+		// Keystores expect well formed public keys.
+		P::try_from(&public[..]).map_err(|_| RpcError::invalid_params("Invalid public key"))?;
+		let key = CryptoTypePublicPair(crypto_id, public.0);
+		let signature = SyncCryptoStore::sign_with(&*self.keystore, parse_key_type(&id)?, &key, &msg)?;
+		to_value(Bytes(signature))
+	}
+
+	fn transcript_data(&self, transcript: Transcript) -> std::result::Result<VRFTranscriptData, RpcError> {
+		let label = self.intern(&transcript.label)?;
+		let items = transcript.items.into_iter()
+			.map(|(label, value)| {
+				let label = std::str::from_utf8(self.intern(label.as_bytes())?)
+					.expect("Interned bytes are equal to the bytes of a `str`; qed");
+				let value = match value {
+					TranscriptValue::Bytes(bytes) => VRFTranscriptValue::Bytes(bytes.0),
+					TranscriptValue::U64(val) => VRFTranscriptValue::U64(val),
+				};
+				Ok((label, value))
+			})
+			.collect::<std::result::Result<Vec<_>, RpcError>>()?;
+		Ok(VRFTranscriptData { label, items })
+	}
+
+	fn intern(&self, label: &[u8]) -> std::result::Result<&'static [u8], RpcError> {
+		let mut labels = self.labels.lock();
+		if let Some(label) = labels.get(label) {
+			return Ok(*label);
+		}
+		if labels.len() >= MAX_TRANSCRIPT_LABELS {
+			return Err(RpcError::new(code::VALIDATION_ERROR, "Too many distinct transcript labels"));
+		}
+		let label: &'static [u8] = Box::leak(label.to_vec().into_boxed_slice());
+		labels.insert(label);
+		Ok(label)
+	}
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> std::result::Result<T, RpcError> {
+	serde_json::from_value(params).map_err(|e| RpcError::invalid_params(e.to_string()))
+}
+
+fn parse_key_type(id: &Bytes) -> std::result::Result<KeyTypeId, RpcError> {
+	array(id).map(KeyTypeId).map_err(|()| RpcError::invalid_params("Invalid key type"))
+}
+
+fn raw_keys<P: Public>(keys: &[P]) -> Vec<Bytes> {
+	keys.iter().map(|k| Bytes(k.to_raw_vec())).collect()
+}
+
+fn to_value<T: Serialize>(value: T) -> std::result::Result<Value, RpcError> {
+	serde_json::to_value(value).map_err(|e| RpcError::new(code::OTHER, e.to_string()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+	use super::*;
+	use std::{io::BufReader, os::unix::net::UnixListener, sync::Arc};
+	use sp_core::{Decode, Pair, testing::SR25519};
+	use tempfile::TempDir;
+	use crate::LocalKeystore;
+
+	fn start_signer(temp_dir: &TempDir, keystore: SyncCryptoStorePtr) -> String {
+		let socket = temp_dir.path().join("signer.sock");
+		let listener = UnixListener::bind(&socket).unwrap();
+		let signer = RemoteSigner::new(keystore);
+		std::thread::spawn(move || {
+			for stream in listener.incoming() {
+				let stream = stream.unwrap();
+				let reader = BufReader::new(stream.try_clone().unwrap());
+				let _ = signer.serve(reader, stream);
+			}
+		});
+		format!("{}{}", UNIX_SCHEME, socket.display())
+	}
+
+	#[test]
+	fn signs_through_remote_signer() {
+		let temp_dir = TempDir::new().unwrap();
+		let local: SyncCryptoStorePtr = Arc::new(LocalKeystore::in_memory());
+		let remote = RemoteKeystore::open(&start_signer(&temp_dir, local.clone())).unwrap();
+
+		let public = SyncCryptoStore::sr25519_generate_new(&remote, SR25519, Some("//Alice")).unwrap();
+		assert_eq!(SyncCryptoStore::sr25519_public_keys(&*local, SR25519), vec![public]);
+		assert_eq!(SyncCryptoStore::sr25519_public_keys(&remote, SR25519), vec![public]);
+		assert!(SyncCryptoStore::has_keys(&remote, &[(public.to_raw_vec(), SR25519)]));
+
+		let key = CryptoTypePublicPair(sr25519::CRYPTO_ID, public.to_raw_vec());
+		assert_eq!(SyncCryptoStore::keys(&remote, SR25519).unwrap(), SyncCryptoStore::keys(&*local, SR25519).unwrap());
+
+		let signature = SyncCryptoStore::sign_with(&remote, SR25519, &key, b"message").unwrap();
+		let signature = sr25519::Signature::decode(&mut &signature[..]).unwrap();
+		assert!(sr25519::Pair::verify(&signature, b"message", &public));
+
+		let transcript_data = VRFTranscriptData {
+			label: b"Test",
+			items: vec![
+				("one", VRFTranscriptValue::U64(1)),
+				("two", VRFTranscriptValue::Bytes(vec![2])),
+			],
+		};
+		let remote_signature = SyncCryptoStore::sr25519_vrf_sign(
+			&remote,
+			SR25519,
+			&public,
+			transcript_data.clone(),
+		).unwrap();
+		let local_signature = SyncCryptoStore::sr25519_vrf_sign(
+			&*local,
+			SR25519,
+			&public,
+			transcript_data,
+		).unwrap();
+		assert_eq!(remote_signature.output, local_signature.output);
+	}
+
+	#[test]
+	fn signer_errors_are_forwarded() {
+		let temp_dir = TempDir::new().unwrap();
+		let local: SyncCryptoStorePtr = Arc::new(LocalKeystore::in_memory());
+		let remote = RemoteKeystore::open(&start_signer(&temp_dir, local)).unwrap();
+
+		let unknown = CryptoTypePublicPair(ed25519::CRYPTO_ID, vec![1; 32]);
+		assert!(SyncCryptoStore::sign_with(&remote, SR25519, &unknown, b"message").is_err());
+
+		let malformed = CryptoTypePublicPair(ed25519::CRYPTO_ID, vec![1; 3]);
+		assert!(matches!(
+			SyncCryptoStore::sign_with(&remote, SR25519, &malformed, b"message"),
+			Err(TraitError::Other(_))
+		));
+
+		let unsupported = CryptoTypePublicPair(CryptoTypeId(*b"none"), vec![1; 32]);
+		assert!(matches!(
+			SyncCryptoStore::sign_with(&remote, SR25519, &unsupported, b"message"),
+			Err(TraitError::KeyNotSupported(SR25519))
+		));
+	}
+
+	#[test]
+	fn unresponsive_signer_times_out() {
+		let temp_dir = TempDir::new().unwrap();
+		let socket = temp_dir.path().join("signer.sock");
+		let listener = UnixListener::bind(&socket).unwrap();
+		// accept connections, but never respond
+		std::thread::spawn(move || {
+			let _streams = listener.incoming().collect::<Vec<_>>();
+		});
+
+		let uri = format!("{}{}", UNIX_SCHEME, socket.display());
+		let remote = RemoteKeystore::open_with_timeout(&uri, Duration::from_millis(100)).unwrap();
+		assert!(matches!(SyncCryptoStore::keys(&remote, SR25519), Err(TraitError::Unavailable)));
+		assert!(matches!(
+			futures::executor::block_on(CryptoStore::keys(&remote, SR25519)),
+			Err(TraitError::Unavailable),
+		));
+	}
+
+	#[test]
+	fn unreachable_signer() {
+		let temp_dir = TempDir::new().unwrap();
+		let uri = format!("{}{}", UNIX_SCHEME, temp_dir.path().join("missing.sock").display());
+		assert!(RemoteKeystore::open(&uri).is_err());
+		assert!(RemoteKeystore::open("ws://127.0.0.1:9944").is_err());
+	}
+}
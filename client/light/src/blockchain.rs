@@ -128,6 +128,14 @@ impl<S, Block> BlockchainBackend<Block> for Blockchain<S> where Block: BlockT, S
 	fn children(&self, _parent_hash: Block::Hash) -> ClientResult<Vec<Block::Hash>> {
 		Err(ClientError::NotAvailableOnLightClient)
 	}
+
+	fn indexed_transaction(&self, _hash: &Block::Hash) -> ClientResult<Option<Vec<u8>>> {
+		Err(ClientError::NotAvailableOnLightClient)
+	}
+
+	fn block_indexed_body(&self, _id: BlockId<Block>) -> ClientResult<Option<Vec<Vec<u8>>>> {
+		Err(ClientError::NotAvailableOnLightClient)
+	}
 }
 
 impl<S: Storage<Block>, Block: BlockT> ProvideCache<Block> for Blockchain<S> {
@@ -414,7 +414,14 @@ where
 						.map(|extrinsic| extrinsic.encode())
 						.collect(),
 					None => {
-						log::trace!(target: "sync", "Missing data for block request.");
+						// The body may have been pruned. Never pretend the block is empty, stop
+						// the response so that the requester asks somebody else for the rest.
+						log::debug!(
+							target: "sync",
+							"Missing body of block #{} ({:?}) for block request, truncating response.",
+							number,
+							hash,
+						);
 						break;
 					}
 				}
@@ -89,6 +89,8 @@ pub struct NetworkService<B: BlockT + 'static, H: ExHashT> {
 	external_addresses: Arc<Mutex<Vec<Multiaddr>>>,
 	/// Are we actively catching up with the chain?
 	is_major_syncing: Arc<AtomicBool>,
+	/// The best block number announced by each of the peers we're connected to.
+	peers_best_numbers: Arc<Mutex<Vec<NumberFor<B>>>>,
 	/// Local copy of the `PeerId` of the local node.
 	local_peer_id: PeerId,
 	/// Bandwidth logging system. Can be queried to know the average bandwidth consumed.
@@ -383,6 +385,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 		}
 
 		let external_addresses = Arc::new(Mutex::new(Vec::new()));
+		let peers_best_numbers = Arc::new(Mutex::new(Vec::new()));
 		let peers_notifications_sinks = Arc::new(Mutex::new(HashMap::new()));
 		let protocol_name_by_engine = Mutex::new({
 			params.network_config.notifications_protocols.iter().cloned().collect()
@@ -393,6 +396,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			external_addresses: external_addresses.clone(),
 			num_connected: num_connected.clone(),
 			is_major_syncing: is_major_syncing.clone(),
+			peers_best_numbers: peers_best_numbers.clone(),
 			peerset: peerset_handle,
 			local_peer_id,
 			to_worker,
@@ -407,6 +411,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			external_addresses,
 			num_connected,
 			is_major_syncing,
+			peers_best_numbers,
 			network_service: swarm,
 			service,
 			import_queue: params.import_queue,
@@ -1043,6 +1048,12 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 		self.num_connected.load(Ordering::Relaxed)
 	}
 
+	/// Returns the best block number announced by each of the peers we're
+	/// connected to.
+	pub fn peers_best_numbers(&self) -> Vec<NumberFor<B>> {
+		self.peers_best_numbers.lock().clone()
+	}
+
 	/// This function should be called when blocks are added to the chain by something other
 	/// than the import queue.
 	///
@@ -1228,6 +1239,8 @@ pub struct NetworkWorker<B: BlockT + 'static, H: ExHashT> {
 	num_connected: Arc<AtomicUsize>,
 	/// Updated by the `NetworkWorker` and loaded by the `NetworkService`.
 	is_major_syncing: Arc<AtomicBool>,
+	/// Updated by the `NetworkWorker` and loaded by the `NetworkService`.
+	peers_best_numbers: Arc<Mutex<Vec<NumberFor<B>>>>,
 	/// The network service that can be extracted and shared through the codebase.
 	service: Arc<NetworkService<B, H>>,
 	/// The *actual* network.
@@ -1717,6 +1730,13 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 			let external_addresses = Swarm::<B, H>::external_addresses(&this.network_service).cloned().collect();
 			*this.external_addresses.lock() = external_addresses;
 		}
+		{
+			let peers_best_numbers = this.network_service.user_protocol_mut()
+				.peers_info()
+				.map(|(_, info)| info.best_number)
+				.collect();
+			*this.peers_best_numbers.lock() = peers_best_numbers;
+		}
 
 		let is_major_syncing = match this.network_service.user_protocol_mut().sync_state() {
 			SyncState::Idle => false,
@@ -24,7 +24,6 @@ use std::{
 
 use crate::NetworkProvider;
 use futures::Future;
-use log::error;
 use sc_network::{PeerId, Multiaddr};
 use codec::{Encode, Decode};
 use sp_core::OpaquePeerId;
@@ -51,6 +50,8 @@ mod timestamp;
 pub(crate) struct Api<Storage> {
 	/// Offchain Workers database.
 	db: Storage,
+	/// Hash of the block the offchain workers run at, used for the fork-aware storage.
+	at: Vec<u8>,
 	/// A provider for substrate networking.
 	network_provider: Arc<dyn NetworkProvider + Send + Sync>,
 	/// Is this node a potential validator?
@@ -59,16 +60,6 @@ pub(crate) struct Api<Storage> {
 	http: http::HttpApi,
 }
 
-fn unavailable_yet<R: Default>(name: &str) -> R {
-	error!(
-		"The {:?} API is not available for offchain workers yet. Follow \
-		https://github.com/paritytech/substrate/issues/1458 for details", name
-	);
-	Default::default()
-}
-
-const LOCAL_DB: &str = "LOCAL (fork-aware) DB";
-
 impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 	fn is_validator(&self) -> bool {
 		self.is_validator
@@ -99,14 +90,14 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 	fn local_storage_set(&mut self, kind: StorageKind, key: &[u8], value: &[u8]) {
 		match kind {
 			StorageKind::PERSISTENT => self.db.set(STORAGE_PREFIX, key, value),
-			StorageKind::LOCAL => unavailable_yet(LOCAL_DB),
+			StorageKind::LOCAL => self.db.set_at(&self.at, STORAGE_PREFIX, key, value),
 		}
 	}
 
 	fn local_storage_clear(&mut self, kind: StorageKind, key: &[u8]) {
 		match kind {
 			StorageKind::PERSISTENT => self.db.remove(STORAGE_PREFIX, key),
-			StorageKind::LOCAL => unavailable_yet(LOCAL_DB),
+			StorageKind::LOCAL => self.db.remove_at(&self.at, STORAGE_PREFIX, key),
 		}
 	}
 
@@ -121,14 +112,16 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 			StorageKind::PERSISTENT => {
 				self.db.compare_and_set(STORAGE_PREFIX, key, old_value, new_value)
 			},
-			StorageKind::LOCAL => unavailable_yet(LOCAL_DB),
+			StorageKind::LOCAL => {
+				self.db.compare_and_set_at(&self.at, STORAGE_PREFIX, key, old_value, new_value)
+			},
 		}
 	}
 
 	fn local_storage_get(&mut self, kind: StorageKind, key: &[u8]) -> Option<Vec<u8>> {
 		match kind {
 			StorageKind::PERSISTENT => self.db.get(STORAGE_PREFIX, key),
-			StorageKind::LOCAL => unavailable_yet(LOCAL_DB),
+			StorageKind::LOCAL => self.db.get_at(&self.at, STORAGE_PREFIX, key),
 		}
 	}
 
@@ -270,6 +263,7 @@ impl AsyncApi {
 	/// Creates new Offchain extensions API implementation an the asynchronous processing part.
 	pub fn new<S: OffchainStorage>(
 		db: S,
+		at: Vec<u8>,
 		network_provider: Arc<dyn NetworkProvider + Send + Sync>,
 		is_validator: bool,
 		shared_client: SharedClient,
@@ -278,6 +272,7 @@ impl AsyncApi {
 
 		let api = Api {
 			db,
+			at,
 			network_provider,
 			is_validator,
 			http: http_api,
@@ -335,6 +330,7 @@ mod tests {
 
 		AsyncApi::new(
 			db,
+			b"block".to_vec(),
 			mock,
 			false,
 			shared_client,
@@ -390,6 +386,24 @@ mod tests {
 		assert_eq!(api.local_storage_get(kind, key), Some(b"value".to_vec()));
 	}
 
+	#[test]
+	fn should_keep_fork_aware_local_storage_apart() {
+		// given
+		let mut api = offchain_api().0;
+		let key = b"test";
+
+		// when
+		api.local_storage_set(StorageKind::LOCAL, key, b"value");
+
+		// then
+		assert_eq!(api.local_storage_get(StorageKind::LOCAL, key), Some(b"value".to_vec()));
+		assert_eq!(api.local_storage_get(StorageKind::PERSISTENT, key), None);
+		assert_eq!(
+			api.db.get_at(b"block", STORAGE_PREFIX, key),
+			Some(b"value".to_vec()),
+		);
+	}
+
 	#[test]
 	fn should_compare_and_set_local_storage() {
 		// given
@@ -149,6 +149,7 @@ impl<Client, Storage, Block> OffchainWorkers<
 		if version > 0 {
 			let (api, runner) = api::AsyncApi::new(
 				self.db.clone(),
+				header.hash().as_ref().to_vec(),
 				network_provider,
 				is_validator,
 				self.shared_client.clone(),
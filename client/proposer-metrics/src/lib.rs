@@ -43,6 +43,9 @@ impl MetricsLink {
 pub struct Metrics {
 	pub block_constructed: Histogram,
 	pub number_of_transactions: Gauge<U64>,
+	pub included_transactions: Gauge<U64>,
+	pub skipped_transactions: Gauge<U64>,
+	pub transaction_selection_time: Histogram,
 }
 
 impl Metrics {
@@ -62,6 +65,27 @@ impl Metrics {
 				)?,
 				registry,
 			)?,
+			included_transactions: register(
+				Gauge::new(
+					"proposer_included_transactions",
+					"Number of pool transactions included in the last proposed block",
+				)?,
+				registry,
+			)?,
+			skipped_transactions: register(
+				Gauge::new(
+					"proposer_skipped_transactions",
+					"Number of pool transactions skipped when proposing the last block",
+				)?,
+				registry,
+			)?,
+			transaction_selection_time: register(
+				Histogram::with_opts(HistogramOpts::new(
+					"proposer_transaction_selection_time",
+					"Histogram of time taken to select and push transactions to new block",
+				))?,
+				registry,
+			)?,
 		})
 	}
 }
@@ -0,0 +1,66 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Transaction pool inspection types for author RPC module.
+
+use sp_core::Bytes;
+use sp_runtime::transaction_validity::{
+	TransactionLongevity, TransactionPriority, TransactionSource,
+};
+use sp_transaction_pool::{PoolTransactionInfo, TransactionQueue};
+use serde::{Serialize, Deserialize};
+
+/// RPC representation of a transaction in the pool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionInfo<Hash> {
+	/// Hash of the transaction.
+	pub hash: Hash,
+	/// The queue the transaction is in.
+	pub queue: TransactionQueue,
+	/// Priority of the transaction.
+	pub priority: TransactionPriority,
+	/// Block number at which the transaction stops being valid.
+	pub valid_till: TransactionLongevity,
+	/// Tags required by the transaction.
+	pub requires: Vec<Bytes>,
+	/// Tags provided by the transaction.
+	pub provides: Vec<Bytes>,
+	/// Required tags that are not provided yet, keeping the transaction in the future queue.
+	pub missing_tags: Vec<Bytes>,
+	/// Source the transaction was submitted from.
+	pub source: TransactionSource,
+	/// Milliseconds elapsed since the transaction was submitted to the pool.
+	pub age_ms: u64,
+}
+
+impl<Hash> From<PoolTransactionInfo<Hash>> for TransactionInfo<Hash> {
+	fn from(info: PoolTransactionInfo<Hash>) -> Self {
+		TransactionInfo {
+			hash: info.hash,
+			queue: info.queue,
+			priority: info.priority,
+			valid_till: info.valid_till,
+			requires: info.requires.into_iter().map(Into::into).collect(),
+			provides: info.provides.into_iter().map(Into::into).collect(),
+			missing_tags: info.missing_tags.into_iter().map(Into::into).collect(),
+			source: info.source,
+			age_ms: info.age.as_millis() as u64,
+		}
+	}
+}
@@ -20,11 +20,12 @@
 
 pub mod error;
 pub mod hash;
+pub mod inspect;
 
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use sp_core::Bytes;
-use sp_transaction_pool::TransactionStatus;
+use sp_transaction_pool::{PoolEvent, TransactionStatus};
 use self::error::{FutureResult, Result};
 
 pub use self::gen_client::Client as AuthorClient;
@@ -70,6 +71,18 @@ pub trait AuthorApi<Hash, BlockHash> {
 	#[rpc(name = "author_pendingExtrinsics")]
 	fn pending_extrinsics(&self) -> Result<Vec<Bytes>>;
 
+	/// Returns details of all transactions in the pool.
+	///
+	/// Ready transactions come first, ordered by priority, followed by the future ones.
+	/// This is an unsafe RPC, as it reveals the source of each transaction.
+	#[rpc(name = "author_inspectPool")]
+	fn inspect_pool(&self) -> Result<Vec<inspect::TransactionInfo<Hash>>>;
+
+	/// Returns details of the transaction with given hash, or `None` if it is not in the pool.
+	/// This is an unsafe RPC.
+	#[rpc(name = "author_transactionInfo")]
+	fn transaction_info(&self, hash: Hash) -> Result<Option<inspect::TransactionInfo<Hash>>>;
+
 	/// Remove given extrinsic from the pool and temporarily ban it to prevent reimporting.
 	#[rpc(name = "author_removeExtrinsic")]
 	fn remove_extrinsic(&self,
@@ -101,4 +114,27 @@ pub trait AuthorApi<Hash, BlockHash> {
 		metadata: Option<Self::Metadata>,
 		id: SubscriptionId
 	) -> Result<bool>;
+
+	/// Subscribe to events of all transactions entering or leaving the pool.
+	/// This is an unsafe RPC.
+	#[pubsub(
+		subscription = "author_poolEvent",
+		subscribe,
+		name = "author_subscribePoolEvents"
+	)]
+	fn subscribe_pool_events(&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<PoolEvent<Hash>>,
+	);
+
+	/// Unsubscribe from pool events.
+	#[pubsub(
+		subscription = "author_poolEvent",
+		unsubscribe,
+		name = "author_unsubscribePoolEvents"
+	)]
+	fn unsubscribe_pool_events(&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId
+	) -> Result<bool>;
 }
@@ -27,7 +27,7 @@ mod policy;
 
 pub use helpers::Receiver;
 pub use jsonrpc_core::IoHandlerExtension as RpcExtension;
-pub use metadata::Metadata;
+pub use metadata::{Metadata, SubscriptionEndHandler};
 pub use policy::DenyUnsafe;
 
 pub mod author;
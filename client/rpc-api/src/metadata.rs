@@ -20,7 +20,10 @@
 use std::sync::Arc;
 
 use jsonrpc_core::futures::sync::mpsc;
-use jsonrpc_pubsub::{Session, PubSubMetadata};
+use jsonrpc_pubsub::{Session, PubSubMetadata, SubscriptionId};
+
+/// Handler called when the server ends a subscription of a session.
+pub type SubscriptionEndHandler = Arc<dyn Fn(&SubscriptionId) + Send + Sync>;
 
 /// RPC Metadata.
 ///
@@ -30,6 +33,8 @@ use jsonrpc_pubsub::{Session, PubSubMetadata};
 #[derive(Default, Clone)]
 pub struct Metadata {
 	session: Option<Arc<Session>>,
+	peer: Option<String>,
+	on_subscription_end: Option<SubscriptionEndHandler>,
 }
 
 impl jsonrpc_core::Metadata for Metadata {}
@@ -44,6 +49,33 @@ impl Metadata {
 	pub fn new(transport: mpsc::Sender<String>) -> Self {
 		Metadata {
 			session: Some(Arc::new(Session::new(transport))),
+			..Default::default()
+		}
+	}
+
+	/// Create new `Metadata` of a call made by given peer over a transport without sessions.
+	pub fn with_peer(peer: String) -> Self {
+		Metadata {
+			peer: Some(peer),
+			..Default::default()
+		}
+	}
+
+	/// The peer that made the call, if the transport identified it.
+	pub fn peer(&self) -> Option<&str> {
+		self.peer.as_deref()
+	}
+
+	/// Sets the handler to call when the server ends a subscription of the session.
+	pub fn on_subscription_end(&mut self, handler: SubscriptionEndHandler) {
+		self.on_subscription_end = Some(handler);
+	}
+
+	/// Notes that the server ended given subscription, e.g. because the watched
+	/// extrinsic reached a final status, as opposed to the client unsubscribing.
+	pub fn note_subscription_end(&self, id: &SubscriptionId) {
+		if let Some(handler) = &self.on_subscription_end {
+			handler(id);
 		}
 	}
 
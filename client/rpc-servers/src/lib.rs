@@ -25,7 +25,6 @@ mod middleware;
 use std::io;
 use jsonrpc_core::{IoHandlerExtension, MetaIoHandler};
 use log::error;
-use pubsub::PubSubMetadata;
 
 /// Maximal payload accepted by RPC servers.
 const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
@@ -37,16 +36,20 @@ const WS_MAX_CONNECTIONS: usize = 100;
 pub type RpcHandler<T> = pubsub::PubSubHandler<T, RpcMiddleware>;
 
 pub use self::inner::*;
-pub use middleware::{RpcMiddleware, RpcMetrics};
+pub use middleware::{
+	RpcMiddleware, RpcMetrics, RpcLimits, RpcMetadata, RateLimit, SubscriptionMethods,
+};
 
 /// Construct rpc `IoHandler`
-pub fn rpc_handler<M: PubSubMetadata>(
+pub fn rpc_handler<M: RpcMetadata>(
 	extension: impl IoHandlerExtension<M>,
 	rpc_middleware: RpcMiddleware,
 ) -> RpcHandler<M> {
+	let subscription_methods = rpc_middleware.subscription_methods();
 	let io_handler = MetaIoHandler::with_middleware(rpc_middleware);
 	let mut io = pubsub::PubSubHandler::new(io_handler);
 	extension.augment(&mut io);
+	*subscription_methods.write() = SubscriptionMethods::from_methods(io.iter().map(|x| x.0.as_str()));
 
 	// add an endpoint to list all available methods.
 	let mut methods = io.iter().map(|x| x.0.clone()).collect::<Vec<String>>();
@@ -76,13 +79,27 @@ mod inner {
 
 	/// Start HTTP server listening on given address.
 	///
+	/// The peer of a call is taken from the last value of `peer_header`, if given.
+	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
-	pub fn start_http<M: pubsub::PubSubMetadata + Default>(
+	pub fn start_http<M: RpcMetadata>(
 		addr: &std::net::SocketAddr,
 		cors: Option<&Vec<String>>,
+		max_request_size: Option<usize>,
+		peer_header: Option<&str>,
 		io: RpcHandler<M>,
 	) -> io::Result<http::Server> {
-		http::ServerBuilder::new(io)
+		let peer_header = peer_header.map(String::from);
+		let meta_extractor = move |request: &http::hyper::Request<http::hyper::Body>| peer_header
+			.as_ref()
+			.and_then(|header| request.headers().get(header.as_str()))
+			.and_then(|value| value.to_str().ok())
+			// proxies append to the header, the last value is the one set by the trusted proxy.
+			.and_then(|value| value.rsplit(',').next())
+			.map(|peer| M::from_peer(peer.trim().into()))
+			.unwrap_or_default();
+
+		http::ServerBuilder::with_meta_extractor(io, meta_extractor)
 			.threads(4)
 			.health_api(("/health", "system_health"))
 			.allowed_hosts(hosts_filtering(cors.is_some()))
@@ -92,14 +109,14 @@ mod inner {
 				http::RestApi::Unsecure
 			})
 			.cors(map_cors::<http::AccessControlAllowOrigin>(cors))
-			.max_request_body_size(MAX_PAYLOAD)
+			.max_request_body_size(max_request_size.unwrap_or(MAX_PAYLOAD))
 			.start_http(addr)
 	}
 
 	/// Start IPC server listening on given path.
 	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
-	pub fn start_ipc<M: pubsub::PubSubMetadata + Default>(
+	pub fn start_ipc<M: RpcMetadata>(
 		addr: &str,
 		io: RpcHandler<M>,
 	) -> io::Result<ipc::Server> {
@@ -116,14 +133,15 @@ mod inner {
 	/// Start WS server listening on given address.
 	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
-	pub fn start_ws<M: pubsub::PubSubMetadata + From<jsonrpc_core::futures::sync::mpsc::Sender<String>>> (
+	pub fn start_ws<M: RpcMetadata + From<jsonrpc_core::futures::sync::mpsc::Sender<String>>> (
 		addr: &std::net::SocketAddr,
 		max_connections: Option<usize>,
 		cors: Option<&Vec<String>>,
+		max_request_size: Option<usize>,
 		io: RpcHandler<M>,
 	) -> io::Result<ws::Server> {
 		ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| context.sender().into())
-			.max_payload(MAX_PAYLOAD)
+			.max_payload(max_request_size.unwrap_or(MAX_PAYLOAD))
 			.max_connections(max_connections.unwrap_or(WS_MAX_CONNECTIONS))
 			.allowed_origins(map_cors(cors))
 			.allowed_hosts(hosts_filtering(cors.is_some()))
@@ -17,22 +17,48 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! Middleware for RPC requests.
+//!
+//! Besides collecting metrics, the middleware enforces the [`RpcLimits`] of a server: the
+//! length of batch requests, the size of responses, the number of subscriptions a connection
+//! may open and per-method rate limits.
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
+use std::time::Instant;
 
 use jsonrpc_core::{
-	Middleware as RequestMiddleware, Metadata,
-	Request, Response, FutureResponse, FutureOutput
+	Middleware as RequestMiddleware, Call, Error, ErrorCode, Output, Version, Value,
+	Request, Response, FutureResponse, FutureOutput, Params,
 };
+use parking_lot::{Mutex, RwLock};
 use prometheus_endpoint::{
-	Registry, CounterVec, PrometheusError,
+	Registry, CounterVec, GaugeVec, PrometheusError,
 	Opts, register, U64
 };
+use pubsub::{PubSubMetadata, Session, SubscriptionId};
+use sc_rpc_api::SubscriptionEndHandler;
+
+use futures::{future::{self, Either}, Future};
+
+/// Error code returned when a batch request has too many calls.
+const BATCH_TOO_LARGE: i64 = -32001;
+/// Error code returned when a connection has too many open subscriptions.
+const TOO_MANY_SUBSCRIPTIONS: i64 = -32002;
+/// Error code returned when the rate limit of a method is exceeded.
+const RATE_LIMITED: i64 = -32003;
+/// Error code returned instead of a response that is too big.
+const RESPONSE_TOO_BIG: i64 = -32004;
 
-use futures::{future::Either, Future};
+/// Maximum number of peers the rate limits of calls without a session are tracked for.
+const MAX_PEERS: usize = 4096;
 
 /// Metrics for RPC middleware
 #[derive(Debug, Clone)]
 pub struct RpcMetrics {
 	rpc_calls: CounterVec<U64>,
+	rpc_limits_exceeded: CounterVec<U64>,
+	rpc_subscriptions: GaugeVec<U64>,
 }
 
 impl RpcMetrics {
@@ -47,29 +73,503 @@ impl RpcMetrics {
 					),
 					&["protocol"]
 				).ok()?, r).ok()?,
+				rpc_limits_exceeded: register(CounterVec::new(
+					Opts::new(
+						"rpc_limits_exceeded_total",
+						"Number of rpc requests and calls rejected because they exceeded a limit",
+					),
+					&["protocol", "limit"]
+				).ok()?, r).ok()?,
+				rpc_subscriptions: register(GaugeVec::new(
+					Opts::new(
+						"rpc_subscriptions_open",
+						"Number of open rpc subscriptions counted against the per-connection limit",
+					),
+					&["protocol"]
+				).ok()?, r).ok()?,
 			})
 		}).ok_or(PrometheusError::Msg("Cannot register metric".to_string()))
 	}
 }
 
+/// Limits enforced by an RPC server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RpcLimits {
+	/// Maximum size of a request in bytes, `None` for the default.
+	///
+	/// Enforced by the HTTP and WS transports, before the request reaches the middleware.
+	pub max_request_size: Option<usize>,
+	/// Maximum size of the result of a single call in bytes. Larger responses are replaced
+	/// with an error.
+	pub max_response_size: Option<usize>,
+	/// Maximum number of calls in a batch request.
+	pub max_batch_len: Option<usize>,
+	/// Maximum number of subscriptions a single connection may have open.
+	///
+	/// Only the subscriptions of the node's RPC APIs are counted, see [`SubscriptionMethods`].
+	pub max_subscriptions_per_connection: Option<usize>,
+	/// Rate limits of the methods, enforced for every connection separately. A call is limited
+	/// by the first matching rule only.
+	///
+	/// Calls made over HTTP are limited per peer if [`Self::http_peer_header`] is set, and share
+	/// the limits of the server otherwise.
+	pub rate_limits: Vec<RateLimit>,
+	/// Header identifying the peer of an HTTP call, set by a trusted reverse proxy, e.g.
+	/// `X-Forwarded-For`. The last value of the header is used.
+	///
+	/// The HTTP server doesn't know the address of the peer, so it can't be used directly.
+	pub http_peer_header: Option<String>,
+}
+
+/// Token bucket rate limit of the methods matching a pattern.
+///
+/// All the methods matching the pattern share a single bucket per connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+	/// Method name, or a method name prefix followed by `*`.
+	pub method: String,
+	/// Number of calls allowed per second on average.
+	pub per_second: u32,
+	/// Number of calls allowed in a burst.
+	pub burst: u32,
+}
+
+impl RateLimit {
+	/// Whether the rate limit applies to given method.
+	pub fn matches(&self, method: &str) -> bool {
+		if self.method.ends_with('*') {
+			method.starts_with(&self.method[..self.method.len() - 1])
+		} else {
+			self.method == method
+		}
+	}
+}
+
+impl FromStr for RateLimit {
+	type Err = String;
+
+	/// Parses `METHOD=RATE` or `METHOD=RATE:BURST`. The burst defaults to the rate.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = s.splitn(2, '=');
+		let method = parts.next().unwrap_or_default();
+		let limit = parts.next()
+			.ok_or_else(|| format!("Expected METHOD=RATE[:BURST], got `{}`", s))?;
+		let mut limit = limit.splitn(2, ':');
+		let parse = |value: &str| value.parse::<u32>()
+			.ok()
+			.filter(|value| *value > 0)
+			.ok_or_else(|| format!("Invalid rate limit `{}` of method `{}`", value, method));
+		let per_second = parse(limit.next().unwrap_or_default())?;
+		let burst = limit.next().map(parse).transpose()?.unwrap_or(per_second);
+		if method.is_empty() {
+			return Err(format!("Missing method name in rate limit `{}`", s));
+		}
+		Ok(RateLimit { method: method.into(), per_second, burst })
+	}
+}
+
+/// Token bucket refilled at a constant rate.
+#[derive(Debug)]
+struct TokenBucket {
+	tokens: f64,
+	capacity: f64,
+	per_second: f64,
+	updated: Instant,
+}
+
+impl TokenBucket {
+	fn new(limit: &RateLimit, now: Instant) -> Self {
+		TokenBucket {
+			tokens: limit.burst as f64,
+			capacity: limit.burst as f64,
+			per_second: limit.per_second as f64,
+			updated: now,
+		}
+	}
+
+	/// Takes a token if there is one.
+	fn try_take(&mut self, now: Instant) -> bool {
+		let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+		self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+		self.updated = now;
+		if self.tokens >= 1.0 {
+			self.tokens -= 1.0;
+			true
+		} else {
+			false
+		}
+	}
+}
+
+/// Metadata of the calls the middleware handles.
+pub trait RpcMetadata: PubSubMetadata + Default {
+	/// Metadata of a call made by given peer over a transport without sessions.
+	fn from_peer(peer: String) -> Self;
+
+	/// The peer that made a call without a session, if the transport identified it.
+	fn peer(&self) -> Option<&str>;
+
+	/// Sets the handler to call when the server ends a subscription of the session.
+	fn on_subscription_end(&mut self, handler: SubscriptionEndHandler);
+}
+
+impl RpcMetadata for sc_rpc_api::Metadata {
+	fn from_peer(peer: String) -> Self {
+		Self::with_peer(peer)
+	}
+
+	fn peer(&self) -> Option<&str> {
+		sc_rpc_api::Metadata::peer(self)
+	}
+
+	fn on_subscription_end(&mut self, handler: SubscriptionEndHandler) {
+		sc_rpc_api::Metadata::on_subscription_end(self, handler)
+	}
+}
+
+/// Whether a method opens or closes a subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SubscriptionCall {
+	Subscribe,
+	Unsubscribe,
+}
+
+/// Methods opening and closing subscriptions among the methods of a server.
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionMethods {
+	subscribe: HashSet<String>,
+	unsubscribe: HashSet<String>,
+}
+
+impl SubscriptionMethods {
+	/// Picks the pubsub methods out of the methods registered with a server.
+	///
+	/// The handler doesn't tell pubsub methods apart, so they are recognized by the naming
+	/// convention of the RPC APIs: a part of the name, split on `_`, is or starts with the
+	/// word `subscribe` or `watch`, or contains `AndWatch`. The same goes for `unsubscribe`
+	/// and `unwatch`.
+	pub fn from_methods<'a>(methods: impl IntoIterator<Item = &'a str>) -> Self {
+		fn has_word(part: &str, word: &str) -> bool {
+			part.starts_with(word) &&
+				part[word.len()..].chars().next().map_or(true, char::is_uppercase)
+		}
+
+		let mut this = Self::default();
+		for method in methods {
+			let parts = || method.split('_');
+			if parts().any(|part| has_word(part, "unsubscribe") || has_word(part, "unwatch")) {
+				this.unsubscribe.insert(method.into());
+			} else if parts().any(|part|
+				has_word(part, "subscribe") || has_word(part, "watch") || part.contains("AndWatch")
+			) {
+				this.subscribe.insert(method.into());
+			}
+		}
+		this
+	}
+
+	/// Whether the method opens a subscription.
+	pub fn is_subscribe(&self, method: &str) -> bool {
+		self.subscribe.contains(method)
+	}
+
+	/// Whether the method closes a subscription.
+	pub fn is_unsubscribe(&self, method: &str) -> bool {
+		self.unsubscribe.contains(method)
+	}
+
+	fn of(&self, method: &str) -> Option<SubscriptionCall> {
+		if self.is_subscribe(method) {
+			Some(SubscriptionCall::Subscribe)
+		} else if self.is_unsubscribe(method) {
+			Some(SubscriptionCall::Unsubscribe)
+		} else {
+			None
+		}
+	}
+}
+
+/// Buckets of the rate limits a connection or peer made calls for, keyed by rule index.
+type RateLimiters = HashMap<usize, TokenBucket>;
+
+/// Identifies a connection: the key of its session and the id telling it apart from a later
+/// connection whose session gets the same key.
+type ConnectionKey = (usize, u64);
+
+/// Limits state of a single connection.
+#[derive(Debug, Default)]
+struct Connection {
+	id: u64,
+	/// Number of subscriptions counted against the limit, including the requested ones whose
+	/// response wasn't seen yet.
+	subscriptions: usize,
+	/// Subscriptions known to be open.
+	open: HashSet<SubscriptionId>,
+	/// Subscriptions the server ended before their response was seen.
+	ended: HashSet<SubscriptionId>,
+	rate_limiters: RateLimiters,
+}
+
+impl Connection {
+	/// Stops counting a subscription, returning the number of subscriptions closed.
+	fn uncount(&mut self) -> usize {
+		if self.subscriptions > 0 {
+			self.subscriptions -= 1;
+			1
+		} else {
+			0
+		}
+	}
+}
+
+/// Limits state of every connection, keyed by session.
+#[derive(Clone)]
+struct Connections {
+	state: Arc<Mutex<HashMap<usize, Connection>>>,
+	next_id: Arc<AtomicU64>,
+	/// Rate limiters of the calls made without a session by an identified peer, along with
+	/// the time of the last call.
+	peers: Arc<Mutex<HashMap<String, (Instant, RateLimiters)>>>,
+	/// Rate limiters shared by all the other calls made without a session.
+	sessionless: Arc<Mutex<RateLimiters>>,
+	metrics: Option<RpcMetrics>,
+	transport_label: String,
+}
+
+impl Connections {
+	/// Runs `f` on the state of the connection of given session, tracking the session until it
+	/// is dropped.
+	fn with<R>(&self, session: &Arc<Session>, f: impl FnOnce(&mut Connection) -> R) -> R {
+		let key = Arc::as_ptr(session) as usize;
+		let mut state = self.state.lock();
+		let connection = state.entry(key).or_insert_with(|| {
+			let this = self.clone();
+			session.on_drop(move || {
+				let closed = this.state.lock().remove(&key).map_or(0, |c| c.subscriptions);
+				this.note_closed(closed);
+			});
+			Connection {
+				id: self.next_id.fetch_add(1, Ordering::Relaxed),
+				..Default::default()
+			}
+		});
+		f(connection)
+	}
+
+	/// Runs `f` on the state of the connection, if it is still open, and notes the subscriptions
+	/// `f` closed.
+	fn close_with(&self, key: ConnectionKey, f: impl FnOnce(&mut Connection) -> usize) {
+		let closed = match self.state.lock().get_mut(&key.0) {
+			Some(connection) if connection.id == key.1 => f(connection),
+			_ => 0,
+		};
+		self.note_closed(closed);
+	}
+
+	fn key(&self, session: &Arc<Session>) -> ConnectionKey {
+		(Arc::as_ptr(session) as usize, self.with(session, |connection| connection.id))
+	}
+
+	/// Runs `f` on the rate limiters of the connection of given session, of given peer or shared
+	/// by all the other calls.
+	fn with_rate_limiters<R>(
+		&self,
+		session: Option<&Arc<Session>>,
+		peer: Option<&str>,
+		now: Instant,
+		f: impl FnOnce(&mut RateLimiters) -> R,
+	) -> R {
+		let peer = match (session, peer) {
+			(Some(session), _) => return self.with(session, |connection| f(&mut connection.rate_limiters)),
+			(None, Some(peer)) => peer,
+			(None, None) => return f(&mut self.sessionless.lock()),
+		};
+
+		let mut peers = self.peers.lock();
+		if !peers.contains_key(peer) && peers.len() >= MAX_PEERS {
+			// forget the peer idle for the longest, its buckets are most likely full again.
+			let idle = peers.iter().min_by_key(|(_, (last_call, _))| *last_call).map(|(peer, _)| peer.clone());
+			if let Some(idle) = idle {
+				peers.remove(&idle);
+			}
+		}
+		let (last_call, rate_limiters) = peers.entry(peer.into()).or_insert_with(|| (now, Default::default()));
+		*last_call = now;
+		f(rate_limiters)
+	}
+
+	/// Counts a new subscription of the session, unless it already has `max` open ones.
+	fn try_open(&self, session: &Arc<Session>, max: usize) -> Option<ConnectionKey> {
+		let key = Arc::as_ptr(session) as usize;
+		let opened = self.with(session, |connection| {
+			if connection.subscriptions >= max {
+				return None;
+			}
+			connection.subscriptions += 1;
+			Some((key, connection.id))
+		});
+		if opened.is_some() {
+			if let Some(ref metrics) = self.metrics {
+				metrics.rpc_subscriptions.with_label_values(&[self.transport_label.as_str()]).inc();
+			}
+		}
+		opened
+	}
+
+	/// Notes that a counted subscription was opened with given id.
+	fn opened(&self, key: ConnectionKey, id: SubscriptionId) {
+		self.close_with(key, |connection| if connection.ended.remove(&id) {
+			connection.uncount()
+		} else {
+			connection.open.insert(id);
+			0
+		});
+	}
+
+	/// Stops counting a subscription that failed to open.
+	fn failed(&self, key: ConnectionKey) {
+		self.close_with(key, Connection::uncount);
+	}
+
+	/// Stops counting a subscription closed by the client or ended by the server.
+	///
+	/// The server may end a subscription before its response was seen, it is then closed as soon
+	/// as it is opened.
+	fn close(&self, key: ConnectionKey, id: &SubscriptionId, by_server: bool) {
+		self.close_with(key, |connection| if connection.open.remove(id) {
+			connection.uncount()
+		} else {
+			if by_server {
+				connection.ended.insert(id.clone());
+			}
+			0
+		});
+	}
+
+	fn note_closed(&self, closed: usize) {
+		if closed == 0 {
+			return;
+		}
+		if let Some(ref metrics) = self.metrics {
+			metrics.rpc_subscriptions.with_label_values(&[self.transport_label.as_str()]).sub(closed as u64);
+		}
+	}
+}
+
+/// Size of `value` serialized as compact JSON, or `None` if it is over `max` bytes.
+///
+/// Counting stops as soon as the size goes over `max`, so this is cheap even for large values.
+fn json_size(value: &Value, max: usize) -> Option<usize> {
+	fn string_size(s: &str) -> usize {
+		2 + s.chars().map(|c| match c {
+			'"' | '\\' | '\n' | '\r' | '\t' | '\u{8}' | '\u{c}' => 2,
+			c if (c as u32) < 0x20 => 6,
+			c => c.len_utf8(),
+		}).sum::<usize>()
+	}
+
+	fn count(value: &Value, size: &mut usize, max: usize) -> Option<()> {
+		let mut add = |bytes: usize| {
+			*size += bytes;
+			Some(()).filter(|_| *size <= max)
+		};
+		match value {
+			Value::Null => add(4),
+			Value::Bool(value) => add(if *value { 4 } else { 5 }),
+			Value::Number(value) => add(value.to_string().len()),
+			Value::String(value) => add(string_size(value)),
+			Value::Array(values) => {
+				add(2 + values.len().saturating_sub(1))?;
+				values.iter().try_for_each(|value| count(value, size, max))
+			},
+			Value::Object(values) => {
+				add(2 + values.len().saturating_sub(1))?;
+				values.iter().try_for_each(|(key, value)| {
+					*size += string_size(key) + 1;
+					count(value, size, max)
+				})
+			},
+		}
+	}
+
+	let mut size = 0;
+	count(value, &mut size, max).map(|_| size)
+}
+
+fn limit_error(code: i64, message: String) -> Error {
+	Error {
+		code: ErrorCode::ServerError(code),
+		message,
+		data: None,
+	}
+}
+
 /// Middleware for RPC calls
 pub struct RpcMiddleware {
 	metrics: Option<RpcMetrics>,
+	limits: RpcLimits,
+	subscription_methods: Arc<RwLock<SubscriptionMethods>>,
+	connections: Connections,
 	transport_label: String,
 }
 
 impl RpcMiddleware {
-	/// Create an instance of middleware with provided metrics
+	/// Create an instance of middleware with provided metrics and limits
 	/// transport_label is used as a label for Prometheus collector
-	pub fn new(metrics: Option<RpcMetrics>, transport_label: &str) -> Self {
+	pub fn new(metrics: Option<RpcMetrics>, limits: RpcLimits, transport_label: &str) -> Self {
 		RpcMiddleware {
+			subscription_methods: Default::default(),
+			connections: Connections {
+				state: Default::default(),
+				next_id: Default::default(),
+				peers: Default::default(),
+				sessionless: Default::default(),
+				metrics: metrics.clone(),
+				transport_label: String::from(transport_label),
+			},
 			metrics,
+			limits,
 			transport_label: String::from(transport_label),
 		}
 	}
+
+	/// Handle to the subscription methods the middleware recognizes, to be set once the methods
+	/// of the server are known.
+	pub fn subscription_methods(&self) -> Arc<RwLock<SubscriptionMethods>> {
+		self.subscription_methods.clone()
+	}
+
+	fn note_exceeded(&self, limit: &str) {
+		if let Some(ref metrics) = self.metrics {
+			metrics.rpc_limits_exceeded.with_label_values(&[self.transport_label.as_str(), limit]).inc();
+		}
+	}
+
+	/// Takes a token from the bucket of the connection or peer for the first rate limit matching
+	/// the method.
+	fn check_rate(&self, method: &str, session: Option<&Arc<Session>>, peer: Option<&str>) -> bool {
+		let (index, limit) = match self.limits.rate_limits.iter().enumerate()
+			.find(|(_, limit)| limit.matches(method))
+		{
+			Some(rule) => rule,
+			None => return true,
+		};
+
+		let now = Instant::now();
+		self.connections.with_rate_limiters(session, peer, now, |rate_limiters| rate_limiters
+			.entry(index)
+			.or_insert_with(|| TokenBucket::new(limit, now))
+			.try_take(now))
+	}
+}
+
+/// A subscription call counted against the limit of a connection.
+enum Tracked {
+	Subscribe(ConnectionKey),
+	Unsubscribe(ConnectionKey, SubscriptionId),
 }
 
-impl<M: Metadata> RequestMiddleware<M> for RpcMiddleware {
+impl<M: RpcMetadata> RequestMiddleware<M> for RpcMiddleware {
 	type Future = FutureResponse;
 	type CallFuture = FutureOutput;
 
@@ -82,6 +582,248 @@ impl<M: Metadata> RequestMiddleware<M> for RpcMiddleware {
 			metrics.rpc_calls.with_label_values(&[self.transport_label.as_str()]).inc();
 		}
 
+		if let (Request::Batch(calls), Some(max)) = (&request, self.limits.max_batch_len) {
+			if calls.len() > max {
+				self.note_exceeded("batch");
+				let error = limit_error(BATCH_TOO_LARGE, format!("Batch requests are limited to {} calls", max));
+				return Either::A(Box::new(future::ok(Some(Response::from(error, Some(Version::V2))))));
+			}
+		}
+
 		Either::B(next(request, meta))
 	}
+
+	fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> Either<FutureOutput, X>
+	where
+		F: Fn(Call, M) -> X + Send + Sync,
+		X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+	{
+		let (method, id) = match &call {
+			Call::MethodCall(call) => (call.method.clone(), Some(call.id.clone())),
+			Call::Notification(notification) => (notification.method.clone(), None),
+			Call::Invalid { .. } => return Either::B(next(call, meta)),
+		};
+		let reject = |code, message| -> Either<FutureOutput, X> {
+			let output = id.clone().map(|id| Output::from(Err(limit_error(code, message)), id, Some(Version::V2)));
+			Either::A(Box::new(future::ok(output)))
+		};
+
+		let session = meta.session();
+		if !self.check_rate(&method, session.as_ref(), meta.peer()) {
+			self.note_exceeded("rate");
+			return reject(RATE_LIMITED, format!("Rate limit of {} exceeded", method));
+		}
+
+		let mut meta = meta;
+		let call_kind = self.subscription_methods.read().of(&method);
+		let tracked = match (self.limits.max_subscriptions_per_connection, session, call_kind) {
+			(Some(max), Some(session), Some(SubscriptionCall::Subscribe)) => {
+				let key = match self.connections.try_open(&session, max) {
+					Some(key) => key,
+					None => {
+						self.note_exceeded("subscriptions");
+						return reject(
+							TOO_MANY_SUBSCRIPTIONS,
+							format!("Connections are limited to {} subscriptions", max),
+						);
+					},
+				};
+				let connections = self.connections.clone();
+				meta.on_subscription_end(Arc::new(move |id| connections.close(key, id, true)));
+				Some(Tracked::Subscribe(key))
+			},
+			(Some(_), Some(session), Some(SubscriptionCall::Unsubscribe)) => match &call {
+				Call::MethodCall(call) => match &call.params {
+					Params::Array(params) => params.first()
+						.and_then(SubscriptionId::parse_value)
+						.map(|id| Tracked::Unsubscribe(self.connections.key(&session), id)),
+					_ => None,
+				},
+				_ => None,
+			},
+			_ => None,
+		};
+
+		let max_response_size = self.limits.max_response_size;
+		if tracked.is_none() && max_response_size.is_none() {
+			return Either::B(next(call, meta));
+		}
+
+		let connections = self.connections.clone();
+		let metrics = self.metrics.clone();
+		let transport_label = self.transport_label.clone();
+		Either::A(Box::new(next(call, meta).map(move |output| {
+			match (tracked, &output) {
+				(Some(Tracked::Subscribe(key)), Some(Output::Success(success))) => {
+					// ids of the node's subscriptions are always strings or numbers.
+					if let Some(id) = SubscriptionId::parse_value(&success.result) {
+						connections.opened(key, id);
+					}
+				},
+				(Some(Tracked::Subscribe(key)), _) => connections.failed(key),
+				(Some(Tracked::Unsubscribe(key, id)), Some(Output::Success(success)))
+					if success.result == Value::Bool(true) => connections.close(key, &id, false),
+				_ => {},
+			}
+
+			let output = output?;
+			let too_big = match (max_response_size, &output) {
+				(Some(max), Output::Success(success)) => json_size(&success.result, max).is_none(),
+				_ => false,
+			};
+			if !too_big {
+				return Some(output);
+			}
+
+			if let Some(ref metrics) = metrics {
+				metrics.rpc_limits_exceeded.with_label_values(&[transport_label.as_str(), "response"]).inc();
+			}
+			let error = limit_error(RESPONSE_TOO_BIG, format!(
+				"Response exceeds the limit of {} bytes",
+				max_response_size.unwrap_or_default(),
+			));
+			Some(Output::from(Err(error), output.id().clone(), Some(Version::V2)))
+		})))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[test]
+	fn parses_rate_limits() {
+		assert_eq!(
+			"state_getStorage=10".parse::<RateLimit>().unwrap(),
+			RateLimit { method: "state_getStorage".into(), per_second: 10, burst: 10 },
+		);
+		assert_eq!(
+			"state_*=10:50".parse::<RateLimit>().unwrap(),
+			RateLimit { method: "state_*".into(), per_second: 10, burst: 50 },
+		);
+		assert!("state_getStorage".parse::<RateLimit>().is_err());
+		assert!("=10".parse::<RateLimit>().is_err());
+		assert!("state_getStorage=0".parse::<RateLimit>().is_err());
+
+		let limit = "state_*=1".parse::<RateLimit>().unwrap();
+		assert!(limit.matches("state_getStorage"));
+		assert!(!limit.matches("chain_getBlock"));
+	}
+
+	#[test]
+	fn token_bucket_refills() {
+		let start = Instant::now();
+		let mut bucket = TokenBucket::new(&RateLimit { method: "a".into(), per_second: 2, burst: 2 }, start);
+		assert!(bucket.try_take(start));
+		assert!(bucket.try_take(start));
+		assert!(!bucket.try_take(start));
+		assert!(bucket.try_take(start + Duration::from_millis(500)));
+		assert!(!bucket.try_take(start + Duration::from_millis(500)));
+		assert!(bucket.try_take(start + Duration::from_secs(10)));
+		assert!(bucket.try_take(start + Duration::from_secs(10)));
+		assert!(!bucket.try_take(start + Duration::from_secs(10)));
+	}
+
+	#[test]
+	fn rate_limits_apply_per_connection() {
+		let limits = RpcLimits {
+			rate_limits: vec!["state_*=1".parse().unwrap()],
+			..Default::default()
+		};
+		let middleware = RpcMiddleware::new(None, limits, "test");
+		let session = || {
+			let (sender, _) = jsonrpc_core::futures::sync::mpsc::channel(1);
+			Arc::new(Session::new(sender))
+		};
+		let (first, second) = (session(), session());
+
+		assert!(middleware.check_rate("state_getStorage", Some(&first), None));
+		assert!(!middleware.check_rate("state_getKeys", Some(&first), None));
+		assert!(middleware.check_rate("chain_getBlock", Some(&first), None));
+		assert!(middleware.check_rate("state_getStorage", Some(&second), None));
+
+		// calls without a session are limited per peer if it is known
+		assert!(middleware.check_rate("state_getStorage", None, Some("10.0.0.1")));
+		assert!(!middleware.check_rate("state_getStorage", None, Some("10.0.0.1")));
+		assert!(middleware.check_rate("state_getStorage", None, Some("10.0.0.2")));
+
+		// and share a single bucket otherwise
+		assert!(middleware.check_rate("state_getStorage", None, None));
+		assert!(!middleware.check_rate("state_getStorage", None, None));
+
+		// the state of a connection is removed with its session
+		drop(first);
+		assert_eq!(middleware.connections.state.lock().len(), 1);
+	}
+
+	#[test]
+	fn recognizes_subscriptions() {
+		let methods = SubscriptionMethods::from_methods(vec![
+			"chain_subscribeNewHeads",
+			"author_submitAndWatchExtrinsic",
+			"author_unwatchExtrinsic",
+			"subscribe_newHead",
+			"unsubscribe_newHead",
+			"system_subscribers",
+			"chain_getBlock",
+		]);
+
+		assert_eq!(methods.of("chain_subscribeNewHeads"), Some(SubscriptionCall::Subscribe));
+		assert_eq!(methods.of("author_submitAndWatchExtrinsic"), Some(SubscriptionCall::Subscribe));
+		assert_eq!(methods.of("author_unwatchExtrinsic"), Some(SubscriptionCall::Unsubscribe));
+		assert_eq!(methods.of("subscribe_newHead"), Some(SubscriptionCall::Subscribe));
+		assert_eq!(methods.of("unsubscribe_newHead"), Some(SubscriptionCall::Unsubscribe));
+		assert_eq!(methods.of("system_subscribers"), None);
+		assert_eq!(methods.of("chain_getBlock"), None);
+		// only the registered methods are recognized
+		assert_eq!(methods.of("state_subscribeStorage"), None);
+	}
+
+	#[test]
+	fn subscriptions_ended_by_the_server_are_uncounted() {
+		let middleware = RpcMiddleware::new(None, Default::default(), "test");
+		let connections = &middleware.connections;
+		let (sender, _) = jsonrpc_core::futures::sync::mpsc::channel(1);
+		let session = Arc::new(Session::new(sender));
+		let subscriptions = || connections.with(&session, |connection| connection.subscriptions);
+
+		let first = connections.try_open(&session, 2).unwrap();
+		let second = connections.try_open(&session, 2).unwrap();
+		assert!(connections.try_open(&session, 2).is_none());
+		connections.opened(first, SubscriptionId::Number(1));
+
+		// ended by the server before its response was seen
+		connections.close(second, &SubscriptionId::Number(2), true);
+		assert_eq!(subscriptions(), 2);
+		connections.opened(second, SubscriptionId::Number(2));
+		assert_eq!(subscriptions(), 1);
+
+		// ended by the server, then unsubscribed by the client
+		connections.close(first, &SubscriptionId::Number(1), true);
+		assert_eq!(subscriptions(), 0);
+		connections.close(first, &SubscriptionId::Number(1), false);
+		assert_eq!(subscriptions(), 0);
+
+		// a failed subscription is uncounted
+		let third = connections.try_open(&session, 2).unwrap();
+		assert_eq!(subscriptions(), 1);
+		connections.failed(third);
+		assert_eq!(subscriptions(), 0);
+	}
+
+	#[test]
+	fn json_size_matches_serialization() {
+		let value = serde_json::json!({
+			"number": 42,
+			"float": -1.5,
+			"string": "quote \" backslash \\ newline \n control \u{1} unicode \u{e9}",
+			"array": [null, true, false, [], {}],
+		});
+		let size = serde_json::to_vec(&value).unwrap().len();
+
+		assert_eq!(json_size(&value, size), Some(size));
+		assert_eq!(json_size(&value, size - 1), None);
+		assert_eq!(json_size(&Value::String("0x00".into()), 5), None);
+	}
 }
@@ -29,6 +29,7 @@ use sp_blockchain::{Error as ClientError, HeaderBackend};
 use rpc::futures::{
 	Sink, Future,
 	future::result,
+	sync::oneshot,
 };
 use futures::{StreamExt as _, compat::Compat};
 use futures::future::{ready, FutureExt, TryFutureExt};
@@ -41,7 +42,7 @@ use sp_api::ProvideRuntimeApi;
 use sp_runtime::generic;
 use sp_transaction_pool::{
 	TransactionPool, InPoolTransaction, TransactionStatus, TransactionSource,
-	BlockHash, TxHash, TransactionFor, error::IntoPoolError,
+	BlockHash, TxHash, TransactionFor, PoolEvent, error::IntoPoolError,
 };
 use sp_session::SessionKeys;
 
@@ -160,6 +161,18 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 		Ok(self.pool.ready().map(|tx| tx.data().encode().into()).collect())
 	}
 
+	fn inspect_pool(&self) -> Result<Vec<inspect::TransactionInfo<TxHash<P>>>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		Ok(self.pool.inspect().into_iter().map(Into::into).collect())
+	}
+
+	fn transaction_info(&self, hash: TxHash<P>) -> Result<Option<inspect::TransactionInfo<TxHash<P>>>> {
+		self.deny_unsafe.check_if_safe()?;
+
+		Ok(self.pool.transaction_info(&hash).map(Into::into))
+	}
+
 	fn remove_extrinsic(
 		&self,
 		bytes_or_hash: Vec<hash::ExtrinsicOrHash<TxHash<P>>>,
@@ -186,7 +199,7 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 	}
 
 	fn watch_extrinsic(&self,
-		_metadata: Self::Metadata,
+		metadata: Self::Metadata,
 		subscriber: Subscriber<TransactionStatus<TxHash<P>, BlockHash<P>>>,
 		xt: Bytes,
 	) {
@@ -213,12 +226,18 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 			// start a new subscrition
 			.map(move |result| match result {
 				Ok(watcher) => {
-					subscriptions.add(subscriber, move |sink| {
+					// the watcher ends on its own once the extrinsic reaches a final status,
+					// let the server know the subscription is over by then.
+					let (id_tx, id_rx) = oneshot::channel();
+					let id = subscriptions.add(subscriber, move |sink| {
 						sink
 							.sink_map_err(|_| unimplemented!())
 							.send_all(Compat::new(watcher))
-							.map(|_| ())
+							.then(move |_: std::result::Result<_, ()>| id_rx)
+							.map(move |id| metadata.note_subscription_end(&id))
+							.map_err(|_| ())
 					});
+					let _ = id_tx.send(id);
 				},
 				Err(err) => {
 					warn!("Failed to submit extrinsic: {}", err);
@@ -237,4 +256,26 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 	fn unwatch_extrinsic(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
 		Ok(self.subscriptions.cancel(id))
 	}
+
+	fn subscribe_pool_events(&self,
+		_metadata: Self::Metadata,
+		subscriber: Subscriber<PoolEvent<TxHash<P>>>,
+	) {
+		if let Err(err) = self.deny_unsafe.check_if_safe() {
+			let _ = subscriber.reject(err.into());
+			return;
+		}
+
+		let stream = self.pool.pool_event_stream().map(|event| Ok::<_, ()>(Ok(event)));
+		self.subscriptions.add(subscriber, move |sink| {
+			sink
+				.sink_map_err(|e| warn!("Error sending pool events: {:?}", e))
+				.send_all(Compat::new(stream))
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_pool_events(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
 }
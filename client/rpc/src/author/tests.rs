@@ -33,6 +33,7 @@ use substrate_test_runtime_client::{
 	DefaultTestClientBuilderExt, TestClientBuilderExt, Backend, Client,
 };
 use sc_transaction_pool::{BasicPool, FullChainApi};
+use sp_transaction_pool::TransactionQueue;
 use futures::{executor, compat::Future01CompatExt};
 
 fn uxt(sender: AccountKeyring, nonce: u64) -> Extrinsic {
@@ -223,6 +224,60 @@ fn should_remove_extrinsics() {
 	assert_eq!(removed.len(), 3);
 }
 
+#[test]
+fn should_inspect_pool() {
+	let p = TestSetup::default().author();
+
+	let ready = p.submit_extrinsic(uxt(AccountKeyring::Alice, 0).encode().into()).wait().unwrap();
+	let future = p.submit_extrinsic(uxt(AccountKeyring::Alice, 2).encode().into()).wait().unwrap();
+
+	let pool = p.inspect_pool().unwrap();
+	assert_eq!(pool.len(), 2);
+	assert_eq!(pool[0].hash, ready);
+	assert_eq!(pool[0].queue, TransactionQueue::Ready);
+	assert!(pool[0].missing_tags.is_empty());
+	assert_eq!(pool[1].hash, future);
+	assert_eq!(pool[1].queue, TransactionQueue::Future);
+	assert_eq!(pool[1].missing_tags, pool[1].requires);
+	assert_eq!(pool[1].source, TransactionSource::External);
+
+	assert_eq!(p.transaction_info(future).unwrap().as_ref(), Some(&pool[1]));
+	assert_eq!(p.transaction_info(Default::default()).unwrap(), None);
+}
+
+#[test]
+fn should_subscribe_to_pool_events() {
+	let p = TestSetup::default().author();
+	let (subscriber, id_rx, data) = jsonrpc_pubsub::typed::Subscriber::new_test("test");
+
+	p.subscribe_pool_events(Default::default(), subscriber);
+	let id = match executor::block_on(id_rx.compat()).unwrap().unwrap() {
+		SubscriptionId::String(id) => id,
+		_ => unreachable!(),
+	};
+
+	let h = p.submit_extrinsic(uxt(AccountKeyring::Alice, 0).encode().into()).wait().unwrap();
+	let res = executor::block_on(data.into_future().compat()).unwrap().0;
+	assert_eq!(res, Some(format!(
+		r#"{{"jsonrpc":"2.0","method":"test","params":{{"result":{{"ready":"0x{}"}},"subscription":"{}"}}}}"#,
+		HexDisplay::from(&h.0),
+		id,
+	)));
+}
+
+#[test]
+fn should_deny_pool_inspection_when_unsafe() {
+	let p = Author { deny_unsafe: DenyUnsafe::Yes, ..TestSetup::default().author() };
+	p.submit_extrinsic(uxt(AccountKeyring::Alice, 0).encode().into()).wait().unwrap();
+
+	assert!(p.inspect_pool().is_err());
+	assert!(p.transaction_info(Default::default()).is_err());
+
+	let (subscriber, id_rx, _data) = jsonrpc_pubsub::typed::Subscriber::new_test("test");
+	p.subscribe_pool_events(Default::default(), subscriber);
+	assert!(executor::block_on(id_rx.compat()).unwrap().is_err());
+}
+
 #[test]
 fn should_insert_key() {
 	let setup = TestSetup::default();
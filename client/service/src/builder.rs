@@ -39,7 +39,7 @@ use futures::{
 	future::ready,
 	channel::oneshot,
 };
-use sc_keystore::LocalKeystore;
+use sc_keystore::{LocalKeystore, RemoteKeystore};
 use log::{info, warn};
 use sc_network::config::{Role, FinalityProofProvider, OnDemand, BoxFinalityProofRequestBuilder};
 use sc_network::NetworkService;
@@ -59,7 +59,7 @@ use sp_core::traits::{
 	CodeExecutor,
 	SpawnNamed,
 };
-use sp_keystore::{CryptoStore, SyncCryptoStorePtr};
+use sp_keystore::{CryptoStore, SyncCryptoStore, SyncCryptoStorePtr};
 use sp_runtime::BuildStorage;
 use sc_client_api::{
 	BlockBackend, BlockchainEvents,
@@ -229,6 +229,33 @@ impl KeystoreContainer {
 		})
 	}
 
+	/// Construct KeystoreContainer for the given configuration, connecting to the remote
+	/// keystore if one is configured. The local keystore is not opened in that case.
+	pub fn from_config(config: &Configuration) -> Result<Self, Error> {
+		let uri = match &config.keystore_remote {
+			Some(uri) => uri,
+			None => return Self::new(&config.keystore),
+		};
+		let remote = Arc::new(RemoteKeystore::open(uri).map_err(|e| Error::Other(
+			format!("Failed to connect to remote keystore `{}`: {}", uri, e),
+		))?);
+
+		Ok(Self {
+			keystore: remote.clone(),
+			sync_keystore: remote as SyncCryptoStorePtr,
+		})
+	}
+
+	/// Set the remote keystore.
+	///
+	/// All keystore requests are served by the remote keystore afterwards.
+	pub fn set_remote_keystore<T>(&mut self, remote: Arc<T>)
+		where T: SyncCryptoStore + 'static
+	{
+		self.keystore = remote.clone();
+		self.sync_keystore = remote as SyncCryptoStorePtr;
+	}
+
 	/// Returns an adapter to the asynchronous keystore that implements `CryptoStore`
 	pub fn keystore(&self) -> Arc<dyn CryptoStore> {
 		self.keystore.clone()
@@ -250,6 +277,30 @@ pub fn new_full_client<TBl, TRtApi, TExecDisp>(
 	new_full_parts(config).map(|parts| parts.0)
 }
 
+/// Run the pending migrations of the configured database, displaying their progress
+/// with the informant, before the database is opened.
+#[cfg(feature = "db")]
+fn migrate_database(
+	config: &Configuration,
+	db_type: sc_client_db::DatabaseType,
+) -> Result<(), Error> {
+	if let crate::config::DatabaseConfig::Custom(_) = config.database {
+		return Ok(())
+	}
+
+	let mut informant = sc_informant::MigrationInformant::new(
+		config.informant_output_format.clone(),
+	);
+	sc_client_db::migration::migrate_database(
+		&config.database,
+		db_type,
+		&Default::default(),
+		&mut |progress| informant.display(progress),
+	)?;
+
+	Ok(())
+}
+
 /// Create the initial parts of a full node.
 pub fn new_full_parts<TBl, TRtApi, TExecDisp>(
 	config: &Configuration,
@@ -257,7 +308,7 @@ pub fn new_full_parts<TBl, TRtApi, TExecDisp>(
 	TBl: BlockT,
 	TExecDisp: NativeExecutionDispatch + 'static,
 {
-	let keystore_container = KeystoreContainer::new(&config.keystore)?;
+	let keystore_container = KeystoreContainer::from_config(config)?;
 
 	let task_manager = {
 		let registry = config.prometheus_config.as_ref().map(|cfg| &cfg.registry);
@@ -279,6 +330,9 @@ pub fn new_full_parts<TBl, TRtApi, TExecDisp>(
 		.cloned()
 		.unwrap_or_default();
 
+	#[cfg(feature = "db")]
+	migrate_database(config, sc_client_db::DatabaseType::Full)?;
+
 	let (client, backend) = {
 		let db_config = sc_client_db::DatabaseSettings {
 			state_cache_size: config.state_cache_size,
@@ -286,6 +340,8 @@ pub fn new_full_parts<TBl, TRtApi, TExecDisp>(
 			config.state_cache_child_ratio.map(|v| (v, 100)),
 			pruning: config.pruning.clone(),
 			source: config.database.clone(),
+			transaction_storage: config.transaction_storage,
+			keep_blocks: config.keep_blocks,
 		};
 
 		let extensions = sc_client_api::execution_extensions::ExecutionExtensions::new(
@@ -325,7 +381,7 @@ pub fn new_light_parts<TBl, TRtApi, TExecDisp>(
 	TBl: BlockT,
 	TExecDisp: NativeExecutionDispatch + 'static,
 {
-	let keystore_container = KeystoreContainer::new(&config.keystore)?;
+	let keystore_container = KeystoreContainer::from_config(config)?;
 	let task_manager = {
 		let registry = config.prometheus_config.as_ref().map(|cfg| &cfg.registry);
 		TaskManager::new(config.task_executor.clone(), registry)?
@@ -337,6 +393,9 @@ pub fn new_light_parts<TBl, TRtApi, TExecDisp>(
 		config.max_runtime_instances,
 	);
 
+	#[cfg(feature = "db")]
+	migrate_database(config, sc_client_db::DatabaseType::Light)?;
+
 	let db_storage = {
 		let db_settings = sc_client_db::DatabaseSettings {
 			state_cache_size: config.state_cache_size,
@@ -344,6 +403,8 @@ pub fn new_light_parts<TBl, TRtApi, TExecDisp>(
 				config.state_cache_child_ratio.map(|v| (v, 100)),
 			pruning: config.pruning.clone(),
 			source: config.database.clone(),
+			transaction_storage: config.transaction_storage,
+			keep_blocks: config.keep_blocks,
 		};
 		sc_client_db::light::LightStorage::new(db_settings)?
 	};
@@ -593,7 +654,7 @@ pub fn spawn_tasks<TBl, TBackend, TExPool, TRpc, TCl>(
 	// This is used internally, so don't restrict access to unsafe RPC
 	let rpc_handlers = RpcHandlers(Arc::new(gen_handler(
 		sc_rpc::DenyUnsafe::No,
-		sc_rpc_server::RpcMiddleware::new(rpc_metrics.as_ref().cloned(), "inbrowser")
+		sc_rpc_server::RpcMiddleware::new(rpc_metrics.as_ref().cloned(), Default::default(), "inbrowser")
 	).into()));
 
 	// Telemetry
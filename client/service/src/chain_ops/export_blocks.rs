@@ -84,6 +84,11 @@ where
 						.map_err(|e| format!("Error writing JSON: {}", e))?;
 				}
 		},
+			// The header is known, but the body was pruned.
+			None if client.block_hash(block)?.is_some() => return Poll::Ready(Err(format!(
+				"Body of block #{} is not available, it was likely deleted by `--blocks-pruning`",
+				block,
+			).into())),
 			// Reached end of the chain.
 			None => return Poll::Ready(Ok(())),
 		}
@@ -18,17 +18,22 @@
 
 //! Service configuration.
 
-pub use sc_client_db::{Database, PruningMode, DatabaseSettingsSrc as DatabaseConfig};
+pub use sc_client_db::{
+	Database, PruningMode, TransactionStorageMode, DatabaseSettingsSrc as DatabaseConfig, KeepBlocks,
+};
 pub use sc_network::Multiaddr;
 pub use sc_network::config::{ExtTransport, MultiaddrWithPeerId, NetworkConfiguration, Role, NodeKeyConfig};
 pub use sc_executor::WasmExecutionMethod;
 use sc_client_api::execution_extensions::ExecutionStrategies;
 
 use std::{io, future::Future, path::{PathBuf, Path}, pin::Pin, net::SocketAddr, sync::Arc};
-pub use sc_transaction_pool::txpool::Options as TransactionPoolOptions;
+pub use sc_transaction_pool::txpool::{
+	Options as TransactionPoolOptions, base_pool::SenderLimit as TransactionPoolSenderLimit,
+};
 use sc_chain_spec::ChainSpec;
 use sp_core::crypto::SecretString;
 pub use sc_telemetry::TelemetryEndpoints;
+pub use sc_rpc_server::{RpcLimits, RateLimit};
 use prometheus_endpoint::Registry;
 #[cfg(not(target_os = "unknown"))]
 use tempfile::TempDir;
@@ -46,10 +51,14 @@ pub struct Configuration {
 	pub task_executor: TaskExecutor,
 	/// Extrinsic pool configuration.
 	pub transaction_pool: TransactionPoolOptions,
+	/// Path to persist the transaction pool at across restarts, if any.
+	pub transaction_pool_persistence: Option<PathBuf>,
 	/// Network configuration.
 	pub network: NetworkConfiguration,
 	/// Configuration for the keystore.
 	pub keystore: KeystoreConfig,
+	/// URI of a remote signer that holds the keys instead of `keystore`.
+	pub keystore_remote: Option<String>,
 	/// Configuration for the database.
 	pub database: DatabaseConfig,
 	/// Size of internal state cache in Bytes
@@ -58,6 +67,10 @@ pub struct Configuration {
 	pub state_cache_child_ratio: Option<usize>,
 	/// Pruning settings.
 	pub pruning: PruningMode,
+	/// Block body/transaction storage scheme.
+	pub transaction_storage: TransactionStorageMode,
+	/// Block pruning settings.
+	pub keep_blocks: KeepBlocks,
 	/// Chain configuration.
 	pub chain_spec: Box<dyn ChainSpec>,
 	/// Wasm execution method.
@@ -78,6 +91,8 @@ pub struct Configuration {
 	pub rpc_ws_max_connections: Option<usize>,
 	/// CORS settings for HTTP & WS servers. `None` if all origins are allowed.
 	pub rpc_cors: Option<Vec<String>>,
+	/// Limits enforced by the HTTP, WS and IPC RPC servers.
+	pub rpc_limits: RpcLimits,
 	/// RPC methods to expose (by default only a safe subset or all of them).
 	pub rpc_methods: RpcMethods,
 	/// Prometheus endpoint configuration. `None` if disabled.
@@ -93,8 +108,14 @@ pub struct Configuration {
 	pub offchain_worker: OffchainWorkerConfig,
 	/// Enable authoring even when offline.
 	pub force_authoring: bool,
+	/// Back off block authoring when finality lags behind the chain head. Only
+	/// applies when GRANDPA is enabled.
+	pub backoff_authoring_blocks: bool,
 	/// Disable GRANDPA when running in validator mode
 	pub disable_grandpa: bool,
+	/// Number of blocks finality may lag behind the best block before GRANDPA
+	/// considers it stalled. `None` disables stall detection.
+	pub grandpa_stall_threshold: Option<u32>,
 	/// Development key seed.
 	///
 	/// When running in development mode, the seed will be used to generate authority keys by the keystore.
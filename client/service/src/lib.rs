@@ -60,6 +60,7 @@ pub use self::builder::{
 };
 pub use config::{
 	BasePath, Configuration, DatabaseConfig, PruningMode, Role, RpcMethods, TaskExecutor, TaskType,
+	TransactionStorageMode, KeepBlocks,
 };
 pub use sc_chain_spec::{
 	ChainSpec, GenericChainSpec, Properties, RuntimeGenesis, Extension as ChainSpecExtension,
@@ -433,7 +434,7 @@ fn start_rpc_servers<
 		config.rpc_ipc.as_ref().map(|path| sc_rpc_server::start_ipc(
 			&*path, gen_handler(
 				sc_rpc::DenyUnsafe::No,
-				sc_rpc_server::RpcMiddleware::new(rpc_metrics.cloned(), "ipc")
+				sc_rpc_server::RpcMiddleware::new(rpc_metrics.cloned(), config.rpc_limits.clone(), "ipc")
 			)
 		)),
 		maybe_start_server(
@@ -441,9 +442,11 @@ fn start_rpc_servers<
 			|address| sc_rpc_server::start_http(
 				address,
 				config.rpc_cors.as_ref(),
+				config.rpc_limits.max_request_size,
+				config.rpc_limits.http_peer_header.as_deref(),
 				gen_handler(
 					deny_unsafe(&address, &config.rpc_methods),
-					sc_rpc_server::RpcMiddleware::new(rpc_metrics.cloned(), "http")
+					sc_rpc_server::RpcMiddleware::new(rpc_metrics.cloned(), config.rpc_limits.clone(), "http")
 				),
 			),
 		)?.map(|s| waiting::HttpServer(Some(s))),
@@ -453,9 +456,10 @@ fn start_rpc_servers<
 				address,
 				config.rpc_ws_max_connections,
 				config.rpc_cors.as_ref(),
+				config.rpc_limits.max_request_size,
 				gen_handler(
 					deny_unsafe(&address, &config.rpc_methods),
-					sc_rpc_server::RpcMiddleware::new(rpc_metrics.cloned(), "ws")
+					sc_rpc_server::RpcMiddleware::new(rpc_metrics.cloned(), config.rpc_limits.clone(), "ws")
 				),
 			),
 		)?.map(|s| waiting::WsServer(Some(s))),
@@ -31,7 +31,9 @@ use substrate_test_runtime_client::{
 use sc_client_api::{
 	StorageProvider, BlockBackend, in_mem, BlockchainEvents,
 };
-use sc_client_db::{Backend, DatabaseSettings, DatabaseSettingsSrc, PruningMode};
+use sc_client_db::{
+	Backend, DatabaseSettings, DatabaseSettingsSrc, PruningMode, TransactionStorageMode, KeepBlocks
+};
 use sc_block_builder::BlockBuilderProvider;
 use sc_service::client::{self, Client, LocalCallExecutor, new_in_mem};
 use sp_runtime::traits::{
@@ -1280,6 +1282,8 @@ fn doesnt_import_blocks_that_revert_finality() {
 				path: tmp.path().into(),
 				cache_size: 1024,
 			},
+			transaction_storage: TransactionStorageMode::BlockBody,
+			keep_blocks: KeepBlocks::All,
 		},
 		u64::max_value(),
 	).unwrap());
@@ -1481,6 +1485,8 @@ fn returns_status_for_pruned_blocks() {
 				path: tmp.path().into(),
 				cache_size: 1024,
 			},
+			transaction_storage: TransactionStorageMode::BlockBody,
+			keep_blocks: KeepBlocks::All,
 		},
 		u64::max_value(),
 	).unwrap());
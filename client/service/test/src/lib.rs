@@ -238,11 +238,13 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		role,
 		task_executor,
 		transaction_pool: Default::default(),
+		transaction_pool_persistence: None,
 		network: network_config,
 		keystore: KeystoreConfig::Path {
 			path: root.join("key"),
 			password: None
 		},
+		keystore_remote: None,
 		database: DatabaseConfig::RocksDb {
 			path: root.join("db"),
 			cache_size: 128,
@@ -250,6 +252,8 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		state_cache_size: 16777216,
 		state_cache_child_ratio: None,
 		pruning: Default::default(),
+		transaction_storage: Default::default(),
+		keep_blocks: Default::default(),
 		chain_spec: Box::new((*spec).clone()),
 		wasm_method: sc_service::config::WasmExecutionMethod::Interpreted,
 		wasm_runtime_overrides: Default::default(),
@@ -258,6 +262,7 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		rpc_ipc: None,
 		rpc_ws: None,
 		rpc_ws_max_connections: None,
+		rpc_limits: Default::default(),
 		rpc_cors: None,
 		rpc_methods: Default::default(),
 		prometheus_config: None,
@@ -266,7 +271,9 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		default_heap_pages: None,
 		offchain_worker: Default::default(),
 		force_authoring: false,
+		backoff_authoring_blocks: true,
 		disable_grandpa: false,
+		grandpa_stall_threshold: None,
 		dev_key_seed: key_seed,
 		tracing_targets: None,
 		tracing_receiver: Default::default(),
@@ -139,8 +139,14 @@ pub struct CommitSet<H: Hash> {
 pub struct Constraints {
 	/// Maximum blocks. Defaults to 0 when unspecified, effectively keeping only non-canonical states.
 	pub max_blocks: Option<u32>,
-	/// Maximum memory in the pruning overlay.
+	/// Maximum memory in the pruning overlay. When exceeded, blocks are pruned regardless of
+	/// `max_blocks`. If only the memory limit is set, the number of blocks is not constrained.
 	pub max_mem: Option<usize>,
+	/// Count `max_blocks` from the last finalized block rather than the last canonicalized one.
+	/// Nothing is pruned by the number of blocks until the finalized block is noted.
+	pub from_finalized: bool,
+	/// Retain the state of every canonicalized block with number divisible by this value.
+	pub retain_every: Option<u64>,
 }
 
 /// Pruning mode.
@@ -160,6 +166,8 @@ impl PruningMode {
 		PruningMode::Constrained(Constraints {
 			max_blocks: Some(n),
 			max_mem: None,
+			from_finalized: false,
+			retain_every: None,
 		})
 	}
 
@@ -179,6 +187,20 @@ impl PruningMode {
 			PruningMode::Constrained(_) => PRUNING_MODE_CONSTRAINED,
 		}
 	}
+
+	/// Read the pruning mode the database was created with, if any block was written to it.
+	///
+	/// Only the kind of the mode is stored, so a constrained mode is returned with default constraints.
+	pub fn from_db<D: MetaDb>(db: &D) -> Result<Option<PruningMode>, Error<D::Error>> {
+		let db_mode = db.get_meta(&to_meta_key(PRUNING_MODE, &())).map_err(Error::Db)?;
+		match db_mode.as_ref().map(|v| v.as_slice()) {
+			None => Ok(None),
+			Some(PRUNING_MODE_ARCHIVE) => Ok(Some(PruningMode::ArchiveAll)),
+			Some(PRUNING_MODE_ARCHIVE_CANON) => Ok(Some(PruningMode::ArchiveCanonical)),
+			Some(PRUNING_MODE_CONSTRAINED) => Ok(Some(PruningMode::Constrained(Default::default()))),
+			Some(v) => Err(Error::InvalidPruningMode(String::from_utf8_lossy(v).into())),
+		}
+	}
 }
 
 impl Default for PruningMode {
@@ -198,6 +220,10 @@ struct StateDbSync<BlockHash: Hash, Key: Hash> {
 	non_canonical: NonCanonicalOverlay<BlockHash, Key>,
 	pruning: Option<RefWindow<BlockHash, Key>>,
 	pinned: HashMap<BlockHash, u32>,
+	finalized: Option<u64>,
+	/// Number of the last finalized block noted after the last call of `apply_pending`
+	/// or `revert_pending`.
+	pending_finalized: Option<u64>,
 }
 
 impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDbSync<BlockHash, Key> {
@@ -213,10 +239,6 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDbSync<Block
 
 		let non_canonical: NonCanonicalOverlay<BlockHash, Key> = NonCanonicalOverlay::new(db)?;
 		let pruning: Option<RefWindow<BlockHash, Key>> = match mode {
-			PruningMode::Constrained(Constraints {
-				max_mem: Some(_),
-				..
-			}) => unimplemented!(),
 			PruningMode::Constrained(_) => Some(RefWindow::new(db, ref_counting)?),
 			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => None,
 		};
@@ -226,6 +248,8 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDbSync<Block
 			non_canonical,
 			pruning,
 			pinned: Default::default(),
+			finalized: None,
+			pending_finalized: None,
 		})
 	}
 
@@ -291,12 +315,62 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDbSync<Block
 			Err(e) => return Err(e),
 		};
 		if let Some(ref mut pruning) = self.pruning {
-			pruning.note_canonical(&hash, &mut commit);
+			let number = pruning.note_canonical(&hash, &mut commit);
+			if let PruningMode::Constrained(Constraints { retain_every: Some(every), .. }) = self.mode {
+				if every != 0 && number % every == 0 {
+					pruning.retain(&hash, &mut commit);
+				}
+			}
 		}
 		self.prune(&mut commit);
 		Ok(commit)
 	}
 
+	fn note_finalized(&mut self, number: u64) -> CommitSet<Key> {
+		let mut commit = CommitSet::default();
+		if self.last_finalized().map_or(true, |finalized| number > finalized) {
+			self.pending_finalized = Some(number);
+			self.prune(&mut commit);
+		}
+		commit
+	}
+
+	fn last_finalized(&self) -> Option<u64> {
+		self.pending_finalized.or(self.finalized)
+	}
+
+	fn retain(&mut self, hash: &BlockHash) -> Result<CommitSet<Key>, PinError> {
+		let mut commit = CommitSet::default();
+		match self.pruning {
+			Some(ref mut pruning) => {
+				if pruning.retain(hash, &mut commit) {
+					Ok(commit)
+				} else {
+					Err(PinError::InvalidBlock)
+				}
+			},
+			// Archive modes keep all canonical states.
+			None => Ok(commit),
+		}
+	}
+
+	fn release(&mut self, hash: &BlockHash) -> Result<CommitSet<Key>, PinError> {
+		let mut commit = CommitSet::default();
+		if self.pinned.contains_key(hash) {
+			return Err(PinError::InvalidBlock);
+		}
+		match self.pruning {
+			Some(ref mut pruning) => {
+				if pruning.release(hash, &mut commit) {
+					Ok(commit)
+				} else {
+					Err(PinError::InvalidBlock)
+				}
+			},
+			None => Ok(commit),
+		}
+	}
+
 	fn best_canonical(&self) -> Option<u64> {
 		return self.non_canonical.last_canonicalized_block_number()
 	}
@@ -312,7 +386,8 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDbSync<Block
 						.as_ref()
 						.map_or(
 							false,
-							|pruning| number < pruning.pending() || !pruning.have_block(hash),
+							|pruning| !pruning.is_retained(hash) &&
+								(number < pruning.pending() || !pruning.have_block(hash)),
 						)
 				}
 			}
@@ -321,12 +396,23 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDbSync<Block
 
 	fn prune(&mut self, commit: &mut CommitSet<Key>) {
 		if let (&mut Some(ref mut pruning), &PruningMode::Constrained(ref constraints)) = (&mut self.pruning, &self.mode) {
+			let finalized = self.pending_finalized.or(self.finalized);
 			loop {
-				if pruning.window_size() <= constraints.max_blocks.unwrap_or(0) as u64 {
+				if pruning.window_size() == 0 {
 					break;
 				}
 
-				if constraints.max_mem.map_or(false, |m| pruning.mem_used() > m) {
+				let exceeds_blocks = |max_blocks: u64| if constraints.from_finalized {
+					finalized.map_or(false, |finalized| pruning.pending() + max_blocks <= finalized)
+				} else {
+					pruning.window_size() > max_blocks
+				};
+				let exceeds_blocks = match constraints.max_blocks {
+					Some(max_blocks) => exceeds_blocks(max_blocks as u64),
+					None => constraints.max_mem.is_none() && exceeds_blocks(0),
+				};
+				let exceeds_mem = constraints.max_mem.map_or(false, |m| pruning.mem_used() > m);
+				if !exceeds_blocks && !exceeds_mem {
 					break;
 				}
 
@@ -358,7 +444,7 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDbSync<Block
 			PruningMode::ArchiveAll => Ok(()),
 			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
 				if self.non_canonical.have_block(hash) ||
-					self.pruning.as_ref().map_or(false, |pruning| pruning.have_block(hash))
+					self.pruning.as_ref().map_or(false, |pruning| pruning.have_block(hash) || pruning.is_retained(hash))
 				{
 					let refs = self.pinned.entry(hash.clone()).or_default();
 					if *refs == 0 {
@@ -407,6 +493,9 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDbSync<Block
 		if let Some(pruning) = &mut self.pruning {
 			pruning.apply_pending();
 		}
+		if let Some(finalized) = self.pending_finalized.take() {
+			self.finalized = Some(finalized);
+		}
 		trace!(
 			target: "forks",
 			"First available: {:?} ({}), Last canon: {:?} ({}), Best forks: {:?}",
@@ -423,6 +512,7 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDbSync<Block
 			pruning.revert_pending();
 		}
 		self.non_canonical.revert_pending();
+		self.pending_finalized = None;
 	}
 
 	fn memory_info(&self) -> StateDbMemoryInfo {
@@ -481,6 +571,30 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDb<BlockHash
 		self.db.write().unpin(hash)
 	}
 
+	/// Notes the number of the last finalized block. When pruning is constrained relative
+	/// to the finalized block, returns a commit pruning the blocks that fell out of the window.
+	/// The number is pending until `apply_pending` like the returned commit.
+	pub fn note_finalized(&self, number: u64) -> CommitSet<Key> {
+		self.db.write().note_finalized(number)
+	}
+
+	/// Retains the state of a canonical block that is not pruned yet until it is released.
+	///
+	/// The returned commit must be written to the database and followed by `apply_pending`,
+	/// or by `revert_pending` if writing fails.
+	pub fn retain(&self, hash: &BlockHash) -> Result<CommitSet<Key>, PinError> {
+		self.db.write().retain(hash)
+	}
+
+	/// Releases a retained state, deleting the nodes no other retained state needs.
+	///
+	/// Fails if the state is not retained or the block is pinned. The returned commit must be
+	/// written to the database and followed by `apply_pending`, or by `revert_pending` if
+	/// writing fails.
+	pub fn release(&self, hash: &BlockHash) -> Result<CommitSet<Key>, PinError> {
+		self.db.write().release(hash)
+	}
+
 	/// Get a value from non-canonical/pruning overlay or the backing DB.
 	pub fn get<D: NodeDb, Q: ?Sized>(&self, key: &Q, db: &D) -> Result<Option<DBValue>, Error<D::Error>>
 		where
@@ -615,6 +729,8 @@ mod tests {
 		let (db, _) = make_test_db(PruningMode::Constrained(Constraints {
 			max_blocks: Some(0),
 			max_mem: None,
+			from_finalized: false,
+			retain_every: None,
 		}));
 		assert!(db.data_eq(&make_db(&[21, 3, 922, 94])));
 	}
@@ -624,6 +740,8 @@ mod tests {
 		let (db, sdb) = make_test_db(PruningMode::Constrained(Constraints {
 			max_blocks: Some(1),
 			max_mem: None,
+			from_finalized: false,
+			retain_every: None,
 		}));
 		assert!(sdb.is_pruned(&H256::from_low_u64_be(0), 0));
 		assert!(sdb.is_pruned(&H256::from_low_u64_be(1), 1));
@@ -637,6 +755,8 @@ mod tests {
 		let (db, sdb) = make_test_db(PruningMode::Constrained(Constraints {
 			max_blocks: Some(2),
 			max_mem: None,
+			from_finalized: false,
+			retain_every: None,
 		}));
 		assert!(sdb.is_pruned(&H256::from_low_u64_be(0), 0));
 		assert!(sdb.is_pruned(&H256::from_low_u64_be(1), 1));
@@ -659,8 +779,114 @@ mod tests {
 			)
 			.unwrap(),
 		);
-		let new_mode = PruningMode::Constrained(Constraints { max_blocks: Some(2), max_mem: None });
+		assert_eq!(PruningMode::from_db(&db).unwrap(), Some(PruningMode::ArchiveAll));
+		let new_mode = PruningMode::Constrained(Constraints {
+			max_blocks: Some(2),
+			max_mem: None,
+			from_finalized: false,
+			retain_every: None,
+		});
 		let state_db: Result<StateDb<H256, H256>, _> = StateDb::new(new_mode, false, &db);
 		assert!(state_db.is_err());
 	}
+
+	#[test]
+	fn prune_by_memory_only() {
+		let (db, _) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: None,
+			max_mem: Some(0),
+			from_finalized: false,
+			retain_every: None,
+		}));
+		assert!(db.data_eq(&make_db(&[21, 3, 922, 94])));
+
+		let (db, _) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: None,
+			max_mem: Some(usize::max_value()),
+			from_finalized: false,
+			retain_every: None,
+		}));
+		assert!(db.data_eq(&make_db(&[1, 21, 3, 91, 921, 922, 93, 94])));
+	}
+
+	#[test]
+	fn prune_window_from_finalized() {
+		let (mut db, sdb) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: Some(1),
+			max_mem: None,
+			from_finalized: true,
+			retain_every: None,
+		}));
+		// nothing is pruned until finality is known
+		assert!(db.data_eq(&make_db(&[1, 21, 3, 91, 921, 922, 93, 94])));
+
+		db.commit(&sdb.note_finalized(1));
+		sdb.apply_pending();
+		assert!(sdb.is_pruned(&H256::from_low_u64_be(1), 1));
+		assert!(!sdb.is_pruned(&H256::from_low_u64_be(21), 2));
+		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
+
+		db.commit(&sdb.note_finalized(2));
+		sdb.apply_pending();
+		assert!(sdb.is_pruned(&H256::from_low_u64_be(21), 2));
+		assert!(db.data_eq(&make_db(&[21, 3, 922, 93, 94])));
+	}
+
+	#[test]
+	fn retained_state_is_not_pruned() {
+		let (mut db, sdb) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: None,
+			max_mem: Some(usize::max_value()),
+			from_finalized: false,
+			retain_every: None,
+		}));
+		db.commit(&sdb.retain(&H256::from_low_u64_be(1)).unwrap());
+		assert!(sdb.retain(&H256::from_low_u64_be(22)).is_err());
+		sdb.apply_pending();
+
+		let state_db: StateDb<H256, H256> = StateDb::new(PruningMode::keep_blocks(0), false, &db).unwrap();
+		db.commit(
+			&state_db
+				.insert_block::<io::Error>(
+					&H256::from_low_u64_be(5),
+					5,
+					&H256::from_low_u64_be(4),
+					make_changeset(&[], &[]),
+				)
+				.unwrap(),
+		);
+		db.commit(&state_db.canonicalize_block::<io::Error>(&H256::from_low_u64_be(4)).unwrap());
+		state_db.apply_pending();
+		assert!(!state_db.is_pruned(&H256::from_low_u64_be(1), 1));
+		assert!(state_db.is_pruned(&H256::from_low_u64_be(21), 2));
+		assert!(state_db.pin(&H256::from_low_u64_be(1)).is_ok());
+		// nodes of block 1 deleted later are kept, nodes inserted after it are pruned
+		assert!(db.data_eq(&make_db(&[1, 3, 4, 21, 921, 922, 93, 94])));
+
+		// a pinned state can't be released
+		assert!(state_db.release(&H256::from_low_u64_be(1)).is_err());
+		state_db.unpin(&H256::from_low_u64_be(1));
+		db.commit(&state_db.release(&H256::from_low_u64_be(1)).unwrap());
+		state_db.apply_pending();
+		assert!(state_db.is_pruned(&H256::from_low_u64_be(1), 1));
+		assert!(db.data_eq(&make_db(&[3, 4, 21, 922])));
+	}
+
+	#[test]
+	fn reverted_finalization_is_forgotten() {
+		let (mut db, sdb) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: Some(1),
+			max_mem: None,
+			from_finalized: true,
+			retain_every: None,
+		}));
+		let _ = sdb.note_finalized(1);
+		sdb.revert_pending();
+		assert!(!sdb.is_pruned(&H256::from_low_u64_be(1), 1));
+
+		db.commit(&sdb.note_finalized(1));
+		sdb.apply_pending();
+		assert!(sdb.is_pruned(&H256::from_low_u64_be(1), 1));
+		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
+	}
 }
@@ -23,14 +23,28 @@
 //! If a node is re-inserted into the window it gets removed from
 //! the death list.
 //! The changes are journaled in the DB.
+//!
+//! The state of selected canonical blocks may be retained until it is released. A node deleted by
+//! block `b` belonged to the state of a retained block `k < b` unless it was inserted after `k`, so
+//! while there are retained blocks the window tracks the block number each node was inserted at
+//! and keeps the nodes that were not inserted after the closest retained block. Kept nodes are
+//! attributed to that block and deleted once it is released, unless another retained block below
+//! it still needs them. The journal records of the blocks pruned after the closest retained block
+//! are kept, so that the insertions can be replayed when loading the window, and are deleted once
+//! the next retained block is pruned or the retained block is released.
+//!
+//! Changes to the retained blocks and kept nodes are pending until `apply_pending` like the rest
+//! of the window and are undone by `revert_pending`.
 
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use codec::{Encode, Decode};
 use crate::{CommitSet, Error, MetaDb, to_meta_key, Hash};
 use log::{trace, warn};
 
 const LAST_PRUNED: &[u8] = b"last_pruned";
 const PRUNING_JOURNAL: &[u8] = b"pruning_journal";
+const RETAINED_BLOCKS: &[u8] = b"retained_blocks";
+const RETAINED_JOURNAL: &[u8] = b"retained_journal";
 
 /// See module documentation.
 #[derive(parity_util_mem_derive::MallocSizeOf)]
@@ -51,6 +65,21 @@ pub struct RefWindow<BlockHash: Hash, Key: Hash> {
 	/// Setting this to false requires backend that supports reference
 	/// counting.
 	count_insertions: bool,
+	/// Estimated memory used by `death_rows` and `death_index`, excluding pending prunings.
+	mem_used: usize,
+	/// Canonical blocks whose state is retained until released, ordered by block number.
+	retained: Vec<(u64, BlockHash)>,
+	/// Block number each node was inserted at, tracked while there are retained blocks.
+	inserted_at: HashMap<Key, u64>,
+	/// Nodes that were kept to preserve the retained states, with the number of the retained
+	/// block they are kept for and the number of deletions that were skipped.
+	///
+	/// Bounded by the retained states, as the nodes of a state are deleted once it is released.
+	retained_keys: HashMap<Key, (u64, u32)>,
+	/// Number of journal records of `retained_keys` for each retained block.
+	retained_journals: HashMap<u64, u64>,
+	/// Changes to the retained blocks and nodes after last call `apply_pending` or `revert_pending`.
+	pending_retained: Option<PendingRetained<BlockHash, Key>>,
 }
 
 #[derive(Debug, PartialEq, Eq, parity_util_mem_derive::MallocSizeOf)]
@@ -67,10 +96,38 @@ struct JournalRecord<BlockHash: Hash, Key: Hash> {
 	deleted: Vec<Key>,
 }
 
+/// Changes to the nodes kept for a retained block.
+#[derive(Encode, Decode)]
+struct RetainedRecord<Key: Hash> {
+	/// Nodes kept for the block, once for each skipped deletion.
+	kept: Vec<Key>,
+	/// Nodes that are no longer kept for the block. Applied before `kept`.
+	released: Vec<Key>,
+}
+
+impl<Key: Hash> Default for RetainedRecord<Key> {
+	fn default() -> Self {
+		RetainedRecord { kept: Vec::new(), released: Vec::new() }
+	}
+}
+
+/// State of the retained blocks and nodes before the pending changes.
+#[derive(parity_util_mem_derive::MallocSizeOf)]
+struct PendingRetained<BlockHash: Hash, Key: Hash> {
+	retained: Vec<(u64, BlockHash)>,
+	retained_journals: HashMap<u64, u64>,
+	/// Previous values of the changed `retained_keys` entries, in order of change.
+	retained_keys: Vec<(Key, Option<(u64, u32)>)>,
+}
+
 fn to_journal_key(block: u64) -> Vec<u8> {
 	to_meta_key(PRUNING_JOURNAL, &block)
 }
 
+fn to_retained_journal_key(block: u64, index: u64) -> Vec<u8> {
+	to_meta_key(RETAINED_JOURNAL, &(block, index))
+}
+
 impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 	pub fn new<D: MetaDb>(db: &D, count_insertions: bool) -> Result<RefWindow<BlockHash, Key>, Error<D::Error>> {
 		let last_pruned = db.get_meta(&to_meta_key(LAST_PRUNED, &()))
@@ -79,6 +136,31 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 			Some(buffer) => u64::decode(&mut buffer.as_slice())? + 1,
 			None => 0,
 		};
+		let retained: Vec<(u64, BlockHash)> = match db.get_meta(&to_meta_key(RETAINED_BLOCKS, &()))
+			.map_err(|e| Error::Db(e))?
+		{
+			Some(buffer) => Decode::decode(&mut buffer.as_slice())?,
+			None => Vec::new(),
+		};
+		let mut retained_keys: HashMap<Key, (u64, u32)> = HashMap::new();
+		let mut retained_journals = HashMap::new();
+		for (number, _) in retained.iter() {
+			let mut index = 0;
+			while let Some(record) = db.get_meta(&to_retained_journal_key(*number, index)).map_err(|e| Error::Db(e))? {
+				let record: RetainedRecord<Key> = Decode::decode(&mut record.as_slice())?;
+				for key in record.released.iter() {
+					retained_keys.remove(key);
+				}
+				for key in record.kept {
+					let entry = retained_keys.entry(key).or_insert((*number, 0));
+					entry.0 = *number;
+					entry.1 += 1;
+				}
+				index += 1;
+			}
+			retained_journals.insert(*number, index);
+		}
+
 		let mut block = pending_number;
 		let mut pruning = RefWindow {
 			death_rows: Default::default(),
@@ -87,7 +169,26 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 			pending_canonicalizations: 0,
 			pending_prunings: 0,
 			count_insertions,
+			mem_used: 0,
+			retained,
+			inserted_at: Default::default(),
+			retained_keys,
+			retained_journals,
+			pending_retained: None,
 		};
+		// replay the insertions since the closest retained block from the kept journal records
+		if let Some(last_retained) = pruning.retained_below(pending_number) {
+			trace!(target: "state-db", "Replaying pruned journal since retained #{}", last_retained);
+			for block in last_retained + 1 .. pending_number {
+				if let Some(record) = db.get_meta(&to_journal_key(block)).map_err(|e| Error::Db(e))? {
+					let record: JournalRecord<BlockHash, Key> = Decode::decode(&mut record.as_slice())?;
+					pruning.inserted_at.extend(record.inserted.into_iter().map(|k| (k, block)));
+					for k in record.deleted.iter() {
+						pruning.inserted_at.remove(k);
+					}
+				}
+			}
+		}
 		// read the journal
 		trace!(target: "state-db", "Reading pruning journal. Pending #{}", pending_number);
 		loop {
@@ -106,27 +207,49 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 	}
 
 	fn import<I: IntoIterator<Item=Key>>(&mut self, hash: &BlockHash, journal_key: Vec<u8>, inserted: I, deleted: Vec<Key>) {
-		if self.count_insertions {
-			// remove all re-inserted keys from death rows
-			for k in inserted {
+		let imported_block = self.pending_number + self.death_rows.len() as u64;
+		let track_insertions = !self.retained.is_empty();
+		for k in inserted {
+			if self.count_insertions {
+				// remove all re-inserted keys from death rows
 				if let Some(block) = self.death_index.remove(&k) {
 					self.death_rows[(block - self.pending_number) as usize].deleted.remove(&k);
+					self.mem_used = self.mem_used.saturating_sub(self.key_mem());
 				}
 			}
+			if track_insertions {
+				self.inserted_at.insert(k, imported_block);
+			}
+		}
 
+		if self.count_insertions {
 			// add new keys
-			let imported_block = self.pending_number + self.death_rows.len() as u64;
 			for k in deleted.iter() {
 				self.death_index.insert(k.clone(), imported_block);
 			}
 		}
-		self.death_rows.push_back(
-			DeathRow {
-				hash: hash.clone(),
-				deleted: deleted.into_iter().collect(),
-				journal_key: journal_key,
-			}
-		);
+		let row = DeathRow {
+			hash: hash.clone(),
+			deleted: deleted.into_iter().collect(),
+			journal_key: journal_key,
+		};
+		self.mem_used += self.row_mem(&row);
+		self.death_rows.push_back(row);
+	}
+
+	/// Estimated memory used by a single key in the death rows and index.
+	fn key_mem(&self) -> usize {
+		let index = if self.count_insertions {
+			std::mem::size_of::<Key>() + std::mem::size_of::<u64>()
+		} else {
+			0
+		};
+		std::mem::size_of::<Key>() + index
+	}
+
+	/// Estimated memory used by a death row.
+	fn row_mem(&self, row: &DeathRow<BlockHash, Key>) -> usize {
+		std::mem::size_of::<DeathRow<BlockHash, Key>>() + row.journal_key.len() + row.deleted.len() * self.key_mem()
 	}
 
 	pub fn window_size(&self) -> u64 {
@@ -137,8 +260,144 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 		self.death_rows.get(self.pending_prunings).map(|r| r.hash.clone())
 	}
 
+	/// Estimated memory used by the pruning window.
+	///
+	/// The nodes kept for the retained states are not part of the window and are not included.
 	pub fn mem_used(&self) -> usize {
-		0
+		self.mem_used + self.inserted_at.len() * (std::mem::size_of::<Key>() + std::mem::size_of::<u64>())
+	}
+
+	/// Returns true if the state of given block is retained.
+	pub fn is_retained(&self, hash: &BlockHash) -> bool {
+		self.retained.iter().any(|(_, h)| h == hash)
+	}
+
+	/// Retains the state of a block within the window until it is released.
+	///
+	/// Nodes inserted by blocks canonicalized between the retained block and the call
+	/// are kept as well. Returns `false` if the block is not within the window.
+	pub fn retain(&mut self, hash: &BlockHash, commit: &mut CommitSet<Key>) -> bool {
+		if self.is_retained(hash) {
+			return true;
+		}
+
+		let number = match self.death_rows.iter().skip(self.pending_prunings).position(|r| r.hash == *hash) {
+			Some(position) => self.pending() + position as u64,
+			None => return false,
+		};
+
+		trace!(target: "state-db", "Retaining state of #{} ({:?})", number, hash);
+		self.begin_retained_change();
+		let position = self.retained.iter().position(|(n, _)| *n > number).unwrap_or(self.retained.len());
+		self.retained.insert(position, (number, hash.clone()));
+		commit.meta.inserted.push((to_meta_key(RETAINED_BLOCKS, &()), self.retained.encode()));
+		true
+	}
+
+	/// Releases a retained state.
+	///
+	/// The nodes kept for it are kept for the closest retained block below it if there is one,
+	/// and deleted otherwise. Returns `false` if the state of the block is not retained.
+	pub fn release(&mut self, hash: &BlockHash, commit: &mut CommitSet<Key>) -> bool {
+		let position = match self.retained.iter().position(|(_, h)| h == hash) {
+			Some(position) => position,
+			None => return false,
+		};
+
+		self.begin_retained_change();
+		let (number, _) = self.retained.remove(position);
+		trace!(target: "state-db", "Releasing state of #{} ({:?})", number, hash);
+		commit.meta.inserted.push((to_meta_key(RETAINED_BLOCKS, &()), self.retained.encode()));
+
+		let kept: Vec<(Key, u32)> = self.retained_keys.iter()
+			.filter(|(_, (block, _))| *block == number)
+			.map(|(key, (_, count))| (key.clone(), *count))
+			.collect();
+		for index in 0 .. self.retained_journals.remove(&number).unwrap_or(0) {
+			commit.meta.deleted.push(to_retained_journal_key(number, index));
+		}
+		for (key, _) in kept.iter() {
+			self.set_retained_key(key.clone(), None);
+		}
+		match self.retained_below(number) {
+			Some(below) => {
+				// nodes inserted before the released block may belong to the state below it
+				self.keep_for(below, kept, commit);
+			},
+			None => {
+				trace!(target: "state-db", "Deleting {} nodes of released state", kept.len());
+				for (key, count) in kept {
+					for _ in 0 .. count {
+						commit.data.deleted.push(key.clone());
+					}
+				}
+				// the insertions since the released block are not needed anymore
+				let pending = self.pending();
+				if number < pending && self.retained_below(pending).is_none() {
+					for block in number + 1 .. pending {
+						commit.meta.deleted.push(to_journal_key(block));
+					}
+				}
+			},
+		}
+		if self.retained.is_empty() {
+			self.inserted_at.clear();
+		}
+		true
+	}
+
+	/// Number of the closest retained block below given one.
+	fn retained_below(&self, number: u64) -> Option<u64> {
+		self.retained.iter().rev().map(|(n, _)| *n).find(|n| *n < number)
+	}
+
+	/// Saves the retained blocks before their first pending change, so that it can be reverted.
+	fn begin_retained_change(&mut self) {
+		if self.pending_retained.is_none() {
+			self.pending_retained = Some(PendingRetained {
+				retained: self.retained.clone(),
+				retained_journals: self.retained_journals.clone(),
+				retained_keys: Vec::new(),
+			});
+		}
+	}
+
+	/// Sets or removes the entry of a kept node.
+	fn set_retained_key(&mut self, key: Key, value: Option<(u64, u32)>) {
+		self.begin_retained_change();
+		let previous = match value {
+			Some(value) => self.retained_keys.insert(key.clone(), value),
+			None => self.retained_keys.remove(&key),
+		};
+		if let Some(pending) = self.pending_retained.as_mut() {
+			pending.retained_keys.push((key, previous));
+		}
+	}
+
+	/// Keeps nodes for a retained block, each `count` more times, and journals the change.
+	///
+	/// A node kept for another block is moved to the given one together with its count.
+	fn keep_for(&mut self, number: u64, keys: Vec<(Key, u32)>, commit: &mut CommitSet<Key>) {
+		let mut records: BTreeMap<u64, RetainedRecord<Key>> = BTreeMap::new();
+		for (key, count) in keys {
+			let held = match self.retained_keys.get(&key) {
+				Some((block, held)) if *block == number => *held,
+				Some((block, held)) => {
+					records.entry(*block).or_default().released.push(key.clone());
+					let held = *held;
+					records.entry(number).or_default().kept.extend(std::iter::repeat(key.clone()).take(held as usize));
+					held
+				},
+				None => 0,
+			};
+			records.entry(number).or_default().kept.extend(std::iter::repeat(key.clone()).take(count as usize));
+			self.set_retained_key(key, Some((number, held + count)));
+		}
+		for (block, record) in records {
+			let index = self.retained_journals.entry(block).or_insert(0);
+			commit.meta.inserted.push((to_retained_journal_key(block, *index), record.encode()));
+			*index += 1;
+		}
 	}
 
 	pub fn pending(&self) -> u64 {
@@ -151,37 +410,90 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 
 	/// Prune next block. Expects at least one block in the window. Adds changes to `commit`.
 	pub fn prune_one(&mut self, commit: &mut CommitSet<Key>) {
-		if let Some(pruned) = self.death_rows.get(self.pending_prunings) {
-			trace!(target: "state-db", "Pruning {:?} ({} deleted)", pruned.hash, pruned.deleted.len());
-			let index = self.pending_number + self.pending_prunings as u64;
-			commit.data.deleted.extend(pruned.deleted.iter().cloned());
-			commit.meta.inserted.push((to_meta_key(LAST_PRUNED, &()), index.encode()));
-			commit.meta.deleted.push(pruned.journal_key.clone());
-			self.pending_prunings += 1;
-		} else {
-			warn!(target: "state-db", "Trying to prune when there's nothing to prune");
+		let index = self.pending();
+		let retained_below = self.retained_below(index);
+		let (kept, row_mem, journal_key) = match self.death_rows.get(self.pending_prunings) {
+			Some(pruned) => {
+				trace!(target: "state-db", "Pruning {:?} ({} deleted)", pruned.hash, pruned.deleted.len());
+				let mut kept = Vec::new();
+				for key in pruned.deleted.iter() {
+					let inserted_at = self.inserted_at.remove(key);
+					let keep = retained_below.map_or(false, |retained| inserted_at.map_or(true, |at| at <= retained));
+					if keep {
+						kept.push((key.clone(), 1));
+					} else {
+						commit.data.deleted.push(key.clone());
+					}
+				}
+				(kept, self.row_mem(pruned), pruned.journal_key.clone())
+			},
+			None => {
+				warn!(target: "state-db", "Trying to prune when there's nothing to prune");
+				return;
+			},
+		};
+		if let (Some(retained), false) = (retained_below, kept.is_empty()) {
+			trace!(target: "state-db", "Keeping {} nodes of retained state #{}", kept.len(), retained);
+			self.keep_for(retained, kept, commit);
+		}
+		commit.meta.inserted.push((to_meta_key(LAST_PRUNED, &()), index.encode()));
+		// the insertions after a retained block are replayed from the journal on restart
+		if retained_below.is_none() {
+			commit.meta.deleted.push(journal_key);
 		}
+		// and are not needed anymore once the next retained block is pruned
+		if let Some(retained_below) = retained_below {
+			if self.retained.iter().any(|(n, _)| *n == index) {
+				for block in retained_below + 1 ..= index {
+					commit.meta.deleted.push(to_journal_key(block));
+				}
+			}
+		}
+		self.mem_used = self.mem_used.saturating_sub(row_mem);
+		self.pending_prunings += 1;
 	}
 
-	/// Add a change set to the window. Creates a journal record and pushes it to `commit`
-	pub fn note_canonical(&mut self, hash: &BlockHash, commit: &mut CommitSet<Key>) {
+	/// Add a change set to the window. Creates a journal record and pushes it to `commit`.
+	///
+	/// Returns the number of the added block.
+	pub fn note_canonical(&mut self, hash: &BlockHash, commit: &mut CommitSet<Key>) -> u64 {
 		trace!(target: "state-db", "Adding to pruning window: {:?} ({} inserted, {} deleted)", hash, commit.data.inserted.len(), commit.data.deleted.len());
-		let inserted = if self.count_insertions {
+		let block = self.pending_number + self.death_rows.len() as u64;
+		let mut inserted: Vec<Key> = if self.count_insertions || !self.retained.is_empty() {
 			commit.data.inserted.iter().map(|(k, _)| k.clone()).collect()
 		} else {
 			Default::default()
 		};
+		if self.count_insertions && !self.retained_keys.is_empty() {
+			// a re-inserted node that was kept for a retained state is live again, and its
+			// deletion is handled like that of a node of unknown insertion
+			let (revived, live): (Vec<Key>, Vec<Key>) = inserted.into_iter()
+				.partition(|k| self.retained_keys.contains_key(k));
+			inserted = live;
+			let mut records: BTreeMap<u64, RetainedRecord<Key>> = BTreeMap::new();
+			for key in revived {
+				if let Some((retained, _)) = self.retained_keys.get(&key) {
+					records.entry(*retained).or_default().released.push(key.clone());
+				}
+				self.set_retained_key(key, None);
+			}
+			for (retained, record) in records {
+				let index = self.retained_journals.entry(retained).or_insert(0);
+				commit.meta.inserted.push((to_retained_journal_key(retained, *index), record.encode()));
+				*index += 1;
+			}
+		}
 		let deleted = ::std::mem::take(&mut commit.data.deleted);
 		let journal_record = JournalRecord {
 			hash: hash.clone(),
 			inserted,
 			deleted,
 		};
-		let block = self.pending_number + self.death_rows.len() as u64;
 		let journal_key = to_journal_key(block);
 		commit.meta.inserted.push((journal_key.clone(), journal_record.encode()));
 		self.import(&journal_record.hash, journal_key, journal_record.inserted.into_iter(), journal_record.deleted);
 		self.pending_canonicalizations += 1;
+		block
 	}
 
 	/// Apply all pending changes
@@ -198,6 +510,15 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 			self.pending_number += 1;
 		}
 		self.pending_prunings = 0;
+		self.pending_retained = None;
+
+		// insertions before the last retained block are only relevant until it's pruned
+		if let Some((last_retained, _)) = self.retained.last() {
+			if self.pending_number > *last_retained {
+				let last_retained = *last_retained;
+				self.inserted_at.retain(|_, at| *at > last_retained);
+			}
+		}
 	}
 
 	/// Revert all pending changes
@@ -213,6 +534,19 @@ impl<BlockHash: Hash, Key: Hash> RefWindow<BlockHash, Key> {
 		}
 		self.pending_canonicalizations = 0;
 		self.pending_prunings = 0;
+		self.mem_used = self.death_rows.iter().map(|row| self.row_mem(row)).sum();
+		// Insertions tracked for pending blocks are left behind as well, which only keeps
+		// more nodes for the retained states.
+		if let Some(pending) = self.pending_retained.take() {
+			self.retained = pending.retained;
+			self.retained_journals = pending.retained_journals;
+			for (key, value) in pending.retained_keys.into_iter().rev() {
+				match value {
+					Some(value) => { self.retained_keys.insert(key, value); },
+					None => { self.retained_keys.remove(&key); },
+				}
+			}
+		}
 	}
 }
 
@@ -228,6 +562,8 @@ mod tests {
 		assert_eq!(pruning.pending_number, restored.pending_number);
 		assert_eq!(pruning.death_rows, restored.death_rows);
 		assert_eq!(pruning.death_index, restored.death_index);
+		assert_eq!(pruning.retained, restored.retained);
+		assert_eq!(pruning.retained_keys, restored.retained_keys);
 	}
 
 	#[test]
@@ -420,4 +756,308 @@ mod tests {
 		assert!(pruning.death_index.is_empty());
 	}
 
+
+	#[test]
+	fn retained_state_survives() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db, true).unwrap();
+		let h = H256::random();
+		let mut commit = make_commit(&[4], &[1]);
+		pruning.note_canonical(&h, &mut commit);
+		db.commit(&commit);
+		let mut commit = CommitSet::default();
+		assert!(pruning.retain(&h, &mut commit));
+		assert!(!pruning.retain(&H256::random(), &mut commit));
+		db.commit(&commit);
+		let mut commit = make_commit(&[5], &[2, 4]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		let mut commit = make_commit(&[], &[5]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		assert!(pruning.is_retained(&h));
+		assert!(db.data_eq(&make_db(&[1, 2, 3, 4, 5])));
+		check_journal(&pruning, &db);
+
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		assert!(db.data_eq(&make_db(&[2, 3, 4])));
+		assert_eq!(pruning.pending_number, 3);
+		assert!(pruning.death_rows.is_empty());
+		assert!(pruning.is_retained(&h));
+		check_journal(&pruning, &db);
+	}
+
+	#[test]
+	fn retained_state_survives_restart() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db, true).unwrap();
+		let h = H256::random();
+		let mut commit = make_commit(&[4], &[1]);
+		pruning.note_canonical(&h, &mut commit);
+		db.commit(&commit);
+		let mut commit = CommitSet::default();
+		assert!(pruning.retain(&h, &mut commit));
+		db.commit(&commit);
+		let mut commit = make_commit(&[5], &[]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		let mut commit = make_commit(&[], &[4, 5]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		check_journal(&pruning, &db);
+
+		// the insertion of 5 after the retained block is known after a restart as well
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db, true).unwrap();
+		assert_eq!(pruning.inserted_at.get(&H256::from_low_u64_be(5)), Some(&1));
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		assert!(db.data_eq(&make_db(&[2, 3, 4])));
+		assert!(pruning.is_retained(&h));
+	}
+
+	#[test]
+	fn mem_used_follows_window() {
+		let db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db, true).unwrap();
+		assert_eq!(pruning.mem_used(), 0);
+		let mut commit = make_commit(&[4], &[1, 2]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		let used = pruning.mem_used();
+		assert!(used > 0);
+		pruning.revert_pending();
+		assert_eq!(pruning.mem_used(), 0);
+
+		let mut commit = make_commit(&[4], &[1, 2]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		pruning.apply_pending();
+		assert_eq!(pruning.mem_used(), used);
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit);
+		pruning.apply_pending();
+		assert_eq!(pruning.mem_used(), 0);
+	}
+
+	#[test]
+	fn mem_used_excludes_retained_nodes() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db, true).unwrap();
+		let h = H256::random();
+		let mut commit = make_commit(&[4], &[]);
+		pruning.note_canonical(&h, &mut commit);
+		assert!(pruning.retain(&h, &mut commit));
+		db.commit(&commit);
+		let mut commit = make_commit(&[], &[1, 2]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		assert!(db.data_eq(&make_db(&[1, 2, 3, 4])));
+		assert_eq!(pruning.retained_keys.len(), 2);
+		assert_eq!(pruning.mem_used(), 0);
+	}
+
+	#[test]
+	fn journal_is_deleted_once_not_needed() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db, true).unwrap();
+		let h1 = H256::random();
+		let h3 = H256::random();
+		let mut commit = make_commit(&[4], &[]);
+		pruning.note_canonical(&h1, &mut commit);
+		assert!(pruning.retain(&h1, &mut commit));
+		db.commit(&commit);
+		let mut commit = make_commit(&[5], &[1]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		let mut commit = make_commit(&[], &[5]);
+		pruning.note_canonical(&h3, &mut commit);
+		assert!(pruning.retain(&h3, &mut commit));
+		db.commit(&commit);
+		let mut commit = make_commit(&[], &[]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		// the insertions after the first retained block are kept for replay
+		assert!(!db.meta.contains_key(&super::to_journal_key(0)));
+		assert!(db.meta.contains_key(&super::to_journal_key(1)));
+		check_journal(&pruning, &db);
+
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		// and deleted once the next retained block is pruned
+		assert!(!db.meta.contains_key(&super::to_journal_key(1)));
+		assert!(!db.meta.contains_key(&super::to_journal_key(2)));
+		assert!(db.meta.contains_key(&super::to_journal_key(3)));
+		assert!(db.data_eq(&make_db(&[1, 2, 3, 4])));
+		check_journal(&pruning, &db);
+	}
+
+	#[test]
+	fn released_state_is_deleted() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db, true).unwrap();
+		let h1 = H256::random();
+		let h2 = H256::random();
+		let mut commit = make_commit(&[4], &[]);
+		pruning.note_canonical(&h1, &mut commit);
+		assert!(pruning.retain(&h1, &mut commit));
+		db.commit(&commit);
+		let mut commit = make_commit(&[5], &[1]);
+		pruning.note_canonical(&h2, &mut commit);
+		assert!(pruning.retain(&h2, &mut commit));
+		db.commit(&commit);
+		let mut commit = make_commit(&[], &[2, 5]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		assert!(db.data_eq(&make_db(&[1, 2, 3, 4, 5])));
+
+		// the nodes of the released state are kept for the state below it
+		let mut commit = CommitSet::default();
+		assert!(pruning.release(&h2, &mut commit));
+		assert!(!pruning.release(&h2, &mut commit));
+		db.commit(&commit);
+		pruning.apply_pending();
+		assert!(db.data_eq(&make_db(&[1, 2, 3, 4, 5])));
+		assert!(pruning.retained_keys.values().all(|(block, _)| *block == 0));
+		check_journal(&pruning, &db);
+
+		let mut commit = CommitSet::default();
+		assert!(pruning.release(&h1, &mut commit));
+		db.commit(&commit);
+		pruning.apply_pending();
+		assert!(db.data_eq(&make_db(&[3, 4])));
+		assert!(pruning.retained_keys.is_empty());
+		assert!(db.meta.keys().all(|k| !k.ends_with(super::RETAINED_JOURNAL)));
+		assert!(db.meta.keys().all(|k| !k.ends_with(super::PRUNING_JOURNAL)));
+		check_journal(&pruning, &db);
+	}
+
+	#[test]
+	fn released_state_is_deleted_with_ref_counting() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db, false).unwrap();
+		let h = H256::random();
+		let mut commit = make_commit(&[], &[]);
+		pruning.note_canonical(&h, &mut commit);
+		assert!(pruning.retain(&h, &mut commit));
+		db.commit(&commit);
+		let mut commit = make_commit(&[], &[1]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		let mut commit = make_commit(&[1], &[1]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		// only one of the two deletions is skipped for the retained state
+		assert_eq!(pruning.retained_keys.get(&H256::from_low_u64_be(1)), Some(&(0, 1)));
+		check_journal(&pruning, &db);
+
+		let mut commit = CommitSet::default();
+		assert!(pruning.release(&h, &mut commit));
+		assert_eq!(commit.data.deleted, vec![H256::from_low_u64_be(1)]);
+	}
+
+	#[test]
+	fn reverted_retain_is_undone() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db, true).unwrap();
+		let h = H256::random();
+		let mut commit = make_commit(&[], &[]);
+		pruning.note_canonical(&h, &mut commit);
+		db.commit(&commit);
+		let mut commit = make_commit(&[], &[1]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+
+		let mut commit = CommitSet::default();
+		assert!(pruning.retain(&h, &mut commit));
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		assert!(!pruning.retained_keys.is_empty());
+		pruning.revert_pending();
+		assert!(!pruning.is_retained(&h));
+		assert!(pruning.retained_keys.is_empty());
+		assert!(pruning.retained_journals.is_empty());
+		check_journal(&pruning, &db);
+	}
+
+	#[test]
+	fn reinserted_retained_node_is_deleted_once_not_needed() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256> = RefWindow::new(&db, true).unwrap();
+		let h = H256::random();
+		let mut commit = make_commit(&[], &[]);
+		pruning.note_canonical(&h, &mut commit);
+		assert!(pruning.retain(&h, &mut commit));
+		db.commit(&commit);
+		let mut commit = make_commit(&[], &[1]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit);
+		pruning.prune_one(&mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		assert!(pruning.retained_keys.contains_key(&H256::from_low_u64_be(1)));
+
+		// re-inserted, so it's not kept for the retained state anymore
+		let mut commit = make_commit(&[1], &[]);
+		pruning.note_canonical(&H256::random(), &mut commit);
+		db.commit(&commit);
+		pruning.apply_pending();
+		assert!(pruning.retained_keys.is_empty());
+		check_journal(&pruning, &db);
+
+		let mut commit = CommitSet::default();
+		assert!(pruning.release(&h, &mut commit));
+		db.commit(&commit);
+		pruning.apply_pending();
+		assert!(db.data_eq(&make_db(&[1, 2, 3])));
+	}
 }
@@ -21,7 +21,8 @@
 //! For a more full-featured pool, have a look at the `pool` module.
 
 use std::{
-	collections::HashSet,
+	cmp,
+	collections::{HashMap, HashSet},
 	fmt,
 	hash,
 	sync::Arc,
@@ -30,7 +31,7 @@ use std::{
 use log::{trace, debug, warn};
 use serde::Serialize;
 use sp_core::hexdisplay::HexDisplay;
-use sp_runtime::traits::Member;
+use sp_runtime::{traits::Member, Percent};
 use sp_runtime::transaction_validity::{
 	TransactionTag as Tag,
 	TransactionLongevity as Longevity,
@@ -261,6 +262,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		return_value
 	}
 
+	/// Sets the minimal priority bump required to replace transactions providing the same tags.
+	pub fn set_min_priority_bump(&mut self, bump: Percent) {
+		self.ready.set_min_priority_bump(bump);
+	}
+
 	/// Returns if the transaction for the given hash is already imported.
 	pub fn is_imported(&self, tx_hash: &Hash) -> bool {
 		self.future.contains(tx_hash) || self.ready.contains(tx_hash)
@@ -383,6 +389,16 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		self.future.all()
 	}
 
+	/// Returns an iterator over future transactions in the pool together with the tags they are missing.
+	pub fn futures_waiting(&self) -> impl Iterator<Item=&WaitingTransaction<Hash, Ex>> {
+		self.future.all_waiting()
+	}
+
+	/// Returns future transaction by hash, together with the tags it is missing.
+	pub fn future_by_hash(&self, hash: &Hash) -> Option<&WaitingTransaction<Hash, Ex>> {
+		self.future.waiting_by_hash(hash)
+	}
+
 	/// Returns pool transactions given list of hashes.
 	///
 	/// Includes both ready and future pool. For every hash in the `hashes`
@@ -455,6 +471,84 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		removed
 	}
 
+	/// Makes sure that no single sender occupies more than `limit.count` slots in the queues.
+	///
+	/// The most recent future transactions of an offending sender are removed first, followed
+	/// by its most recently inserted ready transactions (and all transactions that depend on them).
+	/// Transactions whose sender can't be derived from their tags are not limited.
+	pub fn enforce_sender_limit(&mut self, limit: &SenderLimit) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let mut future = self.future
+			.fold(|senders: Option<HashMap<Vec<u8>, Vec<_>>>, current| {
+				let mut senders = senders.unwrap_or_default();
+				if let Some(sender) = limit.sender_of(&current.transaction.provides) {
+					senders.entry(sender.to_vec())
+						.or_default()
+						.push((current.imported_at, current.transaction.hash.clone()));
+				}
+				Some(senders)
+			})
+			.unwrap_or_default();
+		let mut ready = self.ready
+			.fold(|senders: Option<HashMap<Vec<u8>, Vec<_>>>, current| {
+				let mut senders = senders.unwrap_or_default();
+				let transaction = &current.transaction;
+				if let Some(sender) = limit.sender_of(&transaction.transaction.provides) {
+					senders.entry(sender.to_vec())
+						.or_default()
+						.push((transaction.insertion_id, transaction.transaction.hash.clone()));
+				}
+				Some(senders)
+			})
+			.unwrap_or_default();
+
+		let senders = future.keys().chain(ready.keys()).cloned().collect::<HashSet<_>>();
+		let mut removed = vec![];
+		let mut removed_hashes = HashSet::new();
+		for sender in senders {
+			let mut future_txs = future.remove(&sender).unwrap_or_default();
+			let mut ready_txs = ready.remove(&sender).unwrap_or_default();
+			// some transactions might have been removed as dependencies of other senders
+			future_txs.retain(|(_, hash)| !removed_hashes.contains(hash));
+			ready_txs.retain(|(_, hash)| !removed_hashes.contains(hash));
+
+			let mut excess = (future_txs.len() + ready_txs.len()).saturating_sub(limit.count);
+			if excess == 0 {
+				continue;
+			}
+
+			debug!(
+				target: "txpool",
+				"Sender {} exceeds the limit of {} transactions by {}",
+				HexDisplay::from(&sender),
+				limit.count,
+				excess,
+			);
+
+			// order the candidates, so that the ones to be removed first are at the end
+			future_txs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(cmp::Ordering::Equal));
+			ready_txs.sort_by_key(|(insertion_id, _)| *insertion_id);
+			let mut candidates = ready_txs.into_iter().map(|(_, hash)| hash)
+				.chain(future_txs.into_iter().map(|(_, hash)| hash))
+				.collect::<Vec<_>>();
+
+			while excess > 0 {
+				let hash = match candidates.pop() {
+					Some(hash) => hash,
+					None => break,
+				};
+				for tx in self.remove_subtree(&[hash]) {
+					if limit.sender_of(&tx.provides) == Some(&sender[..]) {
+						excess = excess.saturating_sub(1);
+					}
+					removed_hashes.insert(tx.hash.clone());
+					removed.push(tx);
+				}
+			}
+		}
+
+		removed
+	}
+
 	/// Removes all transactions represented by the hashes and all other transactions
 	/// that depend on them.
 	///
@@ -543,6 +637,29 @@ impl Limit {
 	}
 }
 
+/// Per-sender limits.
+///
+/// The sender of a transaction is derived from the first tag it provides, by stripping
+/// the trailing `nonce_len` bytes, i.e. assuming tags are encoded `(sender, nonce)` pairs.
+#[derive(Debug, Clone)]
+pub struct SenderLimit {
+	/// Maximal number of transactions of a single sender in both queues.
+	pub count: usize,
+	/// Number of trailing bytes of a provided tag that encode the nonce.
+	pub nonce_len: usize,
+}
+
+impl SenderLimit {
+	/// Returns the sender of a transaction providing given tags.
+	///
+	/// Returns `None` if the sender can't be derived, i.e. the first tag is not longer than the nonce.
+	pub fn sender_of<'a>(&self, provides: &'a [Tag]) -> Option<&'a [u8]> {
+		provides.first()
+			.filter(|tag| tag.len() > self.nonce_len)
+			.map(|tag| &tag[..tag.len() - self.nonce_len])
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -1201,4 +1318,40 @@ source: TransactionSource::External, requires: [03,02], provides: [04], data: [4
 		assert_eq!(pool.reject_future_transactions, true);
 		assert_eq!(pool.future.len(), 1);
 	}
+
+	#[test]
+	fn should_enforce_sender_limit() {
+		// given
+		let mut pool = pool();
+		let limit = SenderLimit { count: 2, nonce_len: 1 };
+		let tx = |hash: u64, requires: Vec<Vec<u8>>, provides: Vec<u8>| Transaction {
+			data: vec![hash as u8],
+			bytes: 1,
+			hash,
+			priority: 5u64,
+			valid_till: 64u64,
+			requires,
+			provides: vec![provides],
+			propagate: true,
+			source: Source::External,
+		};
+		pool.import(tx(1, vec![], vec![1, 0])).unwrap();
+		pool.import(tx(2, vec![vec![1, 0]], vec![1, 1])).unwrap();
+		pool.import(tx(3, vec![vec![1, 1]], vec![1, 2])).unwrap();
+		pool.import(tx(4, vec![vec![1, 8]], vec![1, 9])).unwrap();
+		pool.import(tx(5, vec![], vec![2, 0])).unwrap();
+		assert_eq!(pool.ready().count(), 4);
+		assert_eq!(pool.futures().count(), 1);
+
+		// when
+		let removed = pool.enforce_sender_limit(&limit);
+
+		// then
+		let mut removed = removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>();
+		removed.sort();
+		assert_eq!(removed, vec![3, 4]);
+		assert_eq!(pool.ready().count(), 3);
+		assert_eq!(pool.futures().count(), 0);
+		assert!(pool.enforce_sender_limit(&limit).is_empty());
+	}
 }
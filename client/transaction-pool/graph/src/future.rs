@@ -231,6 +231,16 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 		self.waiting.values().map(|waiting| &*waiting.transaction)
 	}
 
+	/// Returns iterator over all future transactions together with the tags they are missing.
+	pub fn all_waiting(&self) -> impl Iterator<Item=&WaitingTransaction<Hash, Ex>> {
+		self.waiting.values()
+	}
+
+	/// Returns the waiting transaction with given hash, if it is part of the queue.
+	pub fn waiting_by_hash(&self, hash: &Hash) -> Option<&WaitingTransaction<Hash, Ex>> {
+		self.waiting.get(hash)
+	}
+
 	/// Removes and returns all future transactions.
 	pub fn clear(&mut self) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		self.wanted_tags.clear();
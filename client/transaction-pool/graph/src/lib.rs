@@ -28,6 +28,7 @@
 
 mod future;
 mod listener;
+mod metrics;
 mod pool;
 mod ready;
 mod rotator;
@@ -38,6 +39,7 @@ pub mod base_pool;
 pub mod watcher;
 
 pub use self::base_pool::Transaction;
+pub use self::rotator::RotationPolicy;
 pub use self::pool::{
 	Pool, Options, ChainApi, EventStream, ExtrinsicFor, ExtrinsicHash,
 	BlockHash, NumberFor, TransactionFor, ValidatedTransaction,
@@ -20,26 +20,48 @@
 use std::{
 	collections::HashMap, hash, fmt::Debug,
 };
+use futures::channel::mpsc::{channel, Receiver, Sender};
 use linked_hash_map::LinkedHashMap;
+use retain_mut::RetainMut;
 use serde::Serialize;
-use crate::{watcher, ChainApi, ExtrinsicHash, BlockHash};
+use crate::{watcher, metrics::MetricsLink, ChainApi, ExtrinsicHash, BlockHash};
 use log::{debug, trace, warn};
 use sp_runtime::traits;
+use sp_transaction_pool::{DropReason, PoolEvent};
 
 /// Extrinsic pool default listener.
 pub struct Listener<H: hash::Hash + Eq, C: ChainApi> {
 	watchers: HashMap<H, watcher::Sender<H, ExtrinsicHash<C>>>,
 	finality_watchers: LinkedHashMap<ExtrinsicHash<C>, Vec<H>>,
+	event_sinks: Vec<Sender<PoolEvent<H>>>,
+	metrics: MetricsLink,
 }
 
 /// Maximum number of blocks awaiting finality at any time.
 const MAX_FINALITY_WATCHERS: usize = 512;
 
+/// Size of the buffer of every pool event stream.
+const EVENT_BUFFER_SIZE: usize = 1024;
+
 impl<H: hash::Hash + Eq + Debug, C: ChainApi> Default for Listener<H, C> {
 	fn default() -> Self {
 		Listener {
 			watchers: Default::default(),
 			finality_watchers: Default::default(),
+			event_sinks: Default::default(),
+			metrics: Default::default(),
+		}
+	}
+}
+
+impl<H: hash::Hash + Eq, C: ChainApi> Listener<H, C> {
+	/// Creates a new listener reporting removals to given metrics.
+	pub fn new(metrics: MetricsLink) -> Self {
+		Listener {
+			watchers: Default::default(),
+			finality_watchers: Default::default(),
+			event_sinks: Default::default(),
+			metrics,
 		}
 	}
 }
@@ -58,6 +80,25 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 		}
 	}
 
+	fn notify(&mut self, event: PoolEvent<H>) {
+		self.event_sinks.retain_mut(|sink| match sink.try_send(event.clone()) {
+			Ok(()) => true,
+			Err(e) => if e.is_full() {
+				warn!(target: "txpool", "Trying to notify a pool event but the channel is full");
+				true
+			} else {
+				false
+			},
+		});
+	}
+
+	/// Creates a new stream of events of all transactions entering or leaving the pool.
+	pub fn event_stream(&mut self) -> Receiver<PoolEvent<H>> {
+		let (sink, stream) = channel(EVENT_BUFFER_SIZE);
+		self.event_sinks.push(sink);
+		stream
+	}
+
 	/// Creates a new watcher for given verified extrinsic.
 	///
 	/// The watcher can be used to subscribe to life-cycle events of that extrinsic.
@@ -76,8 +117,9 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 	pub fn ready(&mut self, tx: &H, old: Option<&H>) {
 		trace!(target: "txpool", "[{:?}] Ready (replaced with {:?})", tx, old);
 		self.fire(tx, |watcher| watcher.ready());
+		self.notify(PoolEvent::Ready(tx.clone()));
 		if let Some(old) = old {
-			self.fire(old, |watcher| watcher.usurped(tx.clone()));
+			self.usurped(old, tx);
 		}
 	}
 
@@ -85,15 +127,23 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 	pub fn future(&mut self, tx: &H) {
 		trace!(target: "txpool", "[{:?}] Future", tx);
 		self.fire(tx, |watcher| watcher.future());
+		self.notify(PoolEvent::Future(tx.clone()));
+	}
+
+	/// Transaction was replaced by another one providing the same tags.
+	pub fn usurped(&mut self, tx: &H, by: &H) {
+		trace!(target: "txpool", "[{:?}] Replaced with {:?}", tx, by);
+		self.metrics.report(|metrics| metrics.replaced_transactions.inc());
+		self.fire(tx, |watcher| watcher.usurped(by.clone()));
+		self.notify(PoolEvent::Usurped(tx.clone(), by.clone()));
 	}
 
-	/// Transaction was dropped from the pool because of the limit.
-	pub fn dropped(&mut self, tx: &H, by: Option<&H>) {
-		trace!(target: "txpool", "[{:?}] Dropped (replaced with {:?})", tx, by);
-		self.fire(tx, |watcher| match by {
-			Some(t) => watcher.usurped(t.clone()),
-			None => watcher.dropped(),
-		})
+	/// Transaction was dropped from the pool for given reason.
+	pub fn dropped(&mut self, tx: &H, reason: DropReason) {
+		trace!(target: "txpool", "[{:?}] Dropped ({:?})", tx, reason);
+		self.metrics.report(|metrics| metrics.dropped(reason));
+		self.fire(tx, |watcher| watcher.dropped(reason));
+		self.notify(PoolEvent::Dropped(tx.clone(), reason));
 	}
 
 	/// Transaction was removed as invalid.
@@ -104,6 +154,7 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 			debug!(target: "txpool", "[{:?}] Extrinsic invalid", tx);
 		}
 		self.fire(tx, |watcher| watcher.invalid());
+		self.notify(PoolEvent::Invalid(tx.clone()));
 	}
 
 	/// Transaction was pruned from the pool.
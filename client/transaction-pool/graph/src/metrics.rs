@@ -0,0 +1,80 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Transaction graph Prometheus metrics.
+
+use std::sync::Arc;
+
+use prometheus_endpoint::{register, Counter, CounterVec, Opts, PrometheusError, Registry, U64};
+use sp_transaction_pool::DropReason;
+
+#[derive(Clone, Default)]
+pub struct MetricsLink(Arc<Option<Metrics>>);
+
+impl MetricsLink {
+	pub fn new(registry: Option<&Registry>) -> Self {
+		Self(Arc::new(
+			registry.and_then(|registry|
+				Metrics::register(registry)
+					.map_err(|err| { log::warn!("Failed to register prometheus metrics: {}", err); })
+					.ok()
+			)
+		))
+	}
+
+	pub fn report(&self, do_this: impl FnOnce(&Metrics)) {
+		if let Some(metrics) = self.0.as_ref() {
+			do_this(metrics);
+		}
+	}
+}
+
+/// Transaction graph Prometheus metrics.
+pub struct Metrics {
+	pub dropped_transactions: CounterVec<U64>,
+	pub replaced_transactions: Counter<U64>,
+}
+
+impl Metrics {
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			dropped_transactions: register(
+				CounterVec::new(
+					Opts::new(
+						"sub_txpool_dropped_transactions",
+						"Total number of transactions dropped from the pool, by reason",
+					),
+					&["reason"],
+				)?,
+				registry,
+			)?,
+			replaced_transactions: register(
+				Counter::new(
+					"sub_txpool_replaced_transactions",
+					"Total number of transactions replaced by a higher priority transaction",
+				)?,
+				registry,
+			)?,
+		})
+	}
+
+	/// Record a transaction dropped for given reason.
+	pub fn dropped(&self, reason: DropReason) {
+		self.dropped_transactions.with_label_values(&[reason.as_str()]).inc();
+	}
+}
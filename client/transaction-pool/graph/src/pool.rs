@@ -21,11 +21,12 @@ use std::{
 	sync::Arc,
 };
 
-use crate::{base_pool as base, watcher::Watcher};
+use crate::{base_pool as base, rotator::RotationPolicy, watcher::Watcher};
 
 use futures::Future;
 use sp_runtime::{
 	generic::BlockId,
+	Percent,
 	traits::{self, SaturatedConversion, Block as BlockT},
 	transaction_validity::{
 		TransactionValidity, TransactionTag as Tag, TransactionValidityError, TransactionSource,
@@ -34,6 +35,7 @@ use sp_runtime::{
 use sp_transaction_pool::error;
 use wasm_timer::Instant;
 use futures::channel::mpsc::Receiver;
+use prometheus_endpoint::Registry;
 
 use crate::validated_pool::ValidatedPool;
 pub use crate::validated_pool::ValidatedTransaction;
@@ -105,6 +107,14 @@ pub struct Options {
 	pub ready: base::Limit,
 	/// Future queue limits.
 	pub future: base::Limit,
+	/// Per-sender limits, enforced on top of the queue limits.
+	///
+	/// `None` disables the per-sender limits.
+	pub sender: Option<base::SenderLimit>,
+	/// Minimal priority bump required to replace transactions providing the same tags.
+	pub min_priority_bump: Percent,
+	/// Ban and rotation policy.
+	pub rotation: RotationPolicy,
 	/// Reject future transactions.
 	pub reject_future_transactions: bool,
 }
@@ -120,6 +130,9 @@ impl Default for Options {
 				count: 512,
 				total_bytes: 1 * 1024 * 1024,
 			},
+			sender: None,
+			min_priority_bump: Percent::zero(),
+			rotation: Default::default(),
 			reject_future_transactions: false,
 		}
 	}
@@ -151,8 +164,13 @@ where
 impl<B: ChainApi> Pool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, api: Arc<B>) -> Self {
+		Self::with_prometheus(options, api, None)
+	}
+
+	/// Create a new transaction pool reporting to given Prometheus registry.
+	pub fn with_prometheus(options: Options, api: Arc<B>, prometheus: Option<&Registry>) -> Self {
 		Pool {
-			validated_pool: Arc::new(ValidatedPool::new(options, api)),
+			validated_pool: Arc::new(ValidatedPool::with_prometheus(options, api, prometheus)),
 		}
 	}
 
@@ -460,7 +478,7 @@ mod tests {
 	use parking_lot::Mutex;
 	use futures::executor::block_on;
 	use super::*;
-	use sp_transaction_pool::TransactionStatus;
+	use sp_transaction_pool::{DropReason, PoolEvent, TransactionQueue, TransactionStatus};
 	use sp_runtime::{
 		traits::Hash,
 		transaction_validity::{ValidTransaction, InvalidTransaction, TransactionSource},
@@ -661,6 +679,48 @@ mod tests {
 		assert_eq!(it.next(), None);
 	}
 
+	#[test]
+	fn should_inspect_transactions_and_notify_about_all_events() {
+		// given
+		let pool = pool();
+		let stream = pool.validated_pool().pool_event_stream();
+
+		// when
+		let hash0 = block_on(pool.submit_one(&BlockId::Number(0), SOURCE, uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		}))).unwrap();
+		let hash3 = block_on(pool.submit_one(&BlockId::Number(0), SOURCE, uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 3,
+		}))).unwrap();
+		pool.validated_pool().remove_invalid(&[hash0]);
+
+		// then
+		let info = pool.validated_pool().inspect();
+		assert_eq!(info.len(), 1);
+		assert_eq!(info[0].hash, hash3);
+		assert_eq!(info[0].queue, TransactionQueue::Future);
+		assert_eq!(info[0].requires, vec![vec![2]]);
+		assert_eq!(info[0].provides, vec![vec![3]]);
+		assert_eq!(info[0].missing_tags, vec![vec![2]]);
+		assert_eq!(info[0].source, SOURCE);
+		assert_eq!(pool.validated_pool().transaction_info(&hash3), Some(info[0].clone()));
+		assert_eq!(pool.validated_pool().transaction_info(&hash0), None);
+
+		drop(pool);
+		let events = futures::executor::block_on_stream(stream).collect::<Vec<_>>();
+		assert_eq!(events, vec![
+			PoolEvent::Ready(hash0),
+			PoolEvent::Future(hash3),
+			PoolEvent::Invalid(hash0),
+		]);
+	}
+
 	#[test]
 	fn should_clear_stale_transactions() {
 		// given
@@ -944,6 +1004,7 @@ mod tests {
 				future: limit.clone(),
 				..Default::default()
 			}, TestApi::default().into());
+			let events = pool.validated_pool().pool_event_stream();
 
 			let xt = uxt(Transfer {
 				from: AccountId::from_h256(H256::from_low_u64_be(1)),
@@ -952,6 +1013,7 @@ mod tests {
 				nonce: 0,
 			});
 			let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), SOURCE, xt)).unwrap();
+			let hash = *watcher.hash();
 			assert_eq!(pool.validated_pool().status().ready, 1);
 
 			// when
@@ -967,7 +1029,12 @@ mod tests {
 			// then
 			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
 			assert_eq!(stream.next(), Some(TransactionStatus::Ready));
-			assert_eq!(stream.next(), Some(TransactionStatus::Dropped));
+			assert_eq!(stream.next(), Some(TransactionStatus::Dropped(DropReason::PoolLimit)));
+
+			// the reason is only reported by the pool events
+			drop(pool);
+			let events = futures::executor::block_on_stream(events).collect::<Vec<_>>();
+			assert!(events.contains(&PoolEvent::Dropped(hash, DropReason::PoolLimit)));
 		}
 
 		#[test]
@@ -25,7 +25,7 @@ use std::{
 
 use serde::Serialize;
 use log::trace;
-use sp_runtime::traits::Member;
+use sp_runtime::{traits::Member, Percent};
 use sp_runtime::transaction_validity::{
 	TransactionTag as Tag,
 };
@@ -118,6 +118,9 @@ pub struct ReadyTransactions<Hash: hash::Hash + Eq, Ex> {
 	ready: TrackedMap<Hash, ReadyTx<Hash, Ex>>,
 	/// Best transactions that are ready to be included to the block without any other previous transaction.
 	best: BTreeSet<TransactionRef<Hash, Ex>>,
+	/// Minimal priority bump required to replace transactions providing the same tags.
+	#[ignore_malloc_size_of = "primitive"]
+	min_priority_bump: Percent,
 }
 
 impl<Hash, Ex> tracked_map::Size for ReadyTx<Hash, Ex> {
@@ -133,11 +136,20 @@ impl<Hash: hash::Hash + Eq, Ex> Default for ReadyTransactions<Hash, Ex> {
 			provided_tags: Default::default(),
 			ready: Default::default(),
 			best: Default::default(),
+			min_priority_bump: Percent::zero(),
 		}
 	}
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
+	/// Sets the minimal priority bump required to replace transactions providing the same tags.
+	///
+	/// The replacement needs a priority at least `bump` percent higher than the collective
+	/// priority of the transactions it replaces, and always strictly higher.
+	pub fn set_min_priority_bump(&mut self, bump: Percent) {
+		self.min_priority_bump = bump;
+	}
+
 	/// Borrows a map of tags that are provided by transactions in this queue.
 	pub fn provided_tags(&self) -> &HashMap<Tag, Hash> {
 		&self.provided_tags
@@ -406,7 +418,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 	/// Checks if the transaction is providing the same tags as other transactions.
 	///
 	/// In case that's true it determines if the priority of transactions that
-	/// we are about to replace is lower than the priority of the replacement transaction.
+	/// we are about to replace is lower than the priority of the replacement transaction
+	/// by at least the configured minimal bump.
 	/// We remove/replace old transactions in case they have lower priority.
 	///
 	/// In case replacement is successful returns a list of removed transactions
@@ -441,7 +454,9 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 			};
 
 			// bail - the transaction has too low priority to replace the old ones
-			if old_priority >= tx.priority {
+			let min_priority = old_priority
+				.saturating_add(cmp::max(1, self.min_priority_bump * old_priority));
+			if tx.priority < min_priority {
 				return Err(error::Error::TooLowPriority { old: old_priority, new: tx.priority })
 			}
 
@@ -605,6 +620,33 @@ mod tests {
 		assert_eq!(ready.get().count(), 1);
 	}
 
+	#[test]
+	fn should_require_min_priority_bump_for_replacement() {
+		// given
+		let mut ready = ReadyTransactions::default();
+		ready.set_min_priority_bump(Percent::from_percent(10));
+		let mut tx1 = tx(1);
+		tx1.requires.clear();
+		tx1.priority = 100;
+		let mut tx2 = tx(2);
+		tx2.requires.clear();
+		tx2.priority = 109;
+		let mut tx3 = tx(3);
+		tx3.requires.clear();
+		tx3.priority = 110;
+		import(&mut ready, tx1).unwrap();
+
+		// when
+		let err = import(&mut ready, tx2).unwrap_err();
+		let replaced = import(&mut ready, tx3).unwrap();
+
+		// then
+		assert!(matches!(err, error::Error::TooLowPriority { old: 100, new: 109 }));
+		assert_eq!(replaced.len(), 1);
+		assert_eq!(replaced[0].hash, 1);
+		assert_eq!(ready.get().count(), 1);
+	}
+
 	#[test]
 	fn should_replace_multiple_transactions_correctly() {
 		// given
@@ -35,6 +35,29 @@ use crate::base_pool::Transaction;
 /// Expected size of the banned extrinsics cache.
 const EXPECTED_SIZE: usize = 2048;
 
+/// Ban and rotation policy of the pool.
+#[derive(Debug, Clone)]
+pub struct RotationPolicy {
+	/// How long removed extrinsics are banned from re-entering the pool.
+	pub ban_time: Duration,
+	/// Expected number of banned extrinsics.
+	///
+	/// Once the cache grows twice as big, it is trimmed back to this size.
+	pub expected_banned: usize,
+	/// Whether extrinsics dropped because of the pool or sender limits are banned as well.
+	pub ban_dropped: bool,
+}
+
+impl Default for RotationPolicy {
+	fn default() -> Self {
+		RotationPolicy {
+			ban_time: Duration::from_secs(60 * 30),
+			expected_banned: EXPECTED_SIZE,
+			ban_dropped: true,
+		}
+	}
+}
+
 /// Pool rotator is responsible to only keep fresh extrinsics in the pool.
 ///
 /// Extrinsics that occupy the pool for too long are culled and temporarily banned from entering
@@ -42,14 +65,24 @@ const EXPECTED_SIZE: usize = 2048;
 pub struct PoolRotator<Hash> {
 	/// How long the extrinsic is banned for.
 	ban_time: Duration,
+	/// Expected size of the banned extrinsics cache.
+	expected_size: usize,
 	/// Currently banned extrinsics.
 	banned_until: RwLock<HashMap<Hash, Instant>>,
 }
 
 impl<Hash: hash::Hash + Eq> Default for PoolRotator<Hash> {
 	fn default() -> Self {
+		Self::new(&Default::default())
+	}
+}
+
+impl<Hash: hash::Hash + Eq> PoolRotator<Hash> {
+	/// Creates a new rotator following given policy.
+	pub fn new(policy: &RotationPolicy) -> Self {
 		PoolRotator {
-			ban_time: Duration::from_secs(60 * 30),
+			ban_time: policy.ban_time,
+			expected_size: policy.expected_banned,
 			banned_until: Default::default(),
 		}
 	}
@@ -69,8 +102,8 @@ impl<Hash: hash::Hash + Eq + Clone> PoolRotator<Hash> {
 			banned.insert(hash, *now + self.ban_time);
 		}
 
-		if banned.len() > 2 * EXPECTED_SIZE {
-			while banned.len() > EXPECTED_SIZE {
+		if banned.len() > 2 * self.expected_size {
+			while banned.len() > self.expected_size {
 				if let Some(key) = banned.keys().next().cloned() {
 					banned.remove(&key);
 				}
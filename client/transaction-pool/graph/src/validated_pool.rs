@@ -24,6 +24,7 @@ use std::{
 
 use crate::base_pool as base;
 use crate::listener::Listener;
+use crate::metrics::MetricsLink;
 use crate::rotator::PoolRotator;
 use crate::watcher::Watcher;
 use serde::Serialize;
@@ -34,9 +35,12 @@ use sp_runtime::{
 	traits::{self, SaturatedConversion},
 	transaction_validity::{TransactionTag as Tag, ValidTransaction, TransactionSource},
 };
-use sp_transaction_pool::{error, PoolStatus};
+use sp_transaction_pool::{
+	error, DropReason, PoolEvent, PoolStatus, PoolTransactionInfo, TransactionQueue,
+};
 use wasm_timer::Instant;
 use futures::channel::mpsc::{channel, Sender};
+use prometheus_endpoint::Registry;
 use retain_mut::RetainMut;
 
 use crate::base_pool::PruneStatus;
@@ -101,6 +105,7 @@ pub struct ValidatedPool<B: ChainApi> {
 	>>,
 	import_notification_sinks: Mutex<Vec<Sender<ExtrinsicHash<B>>>>,
 	rotator: PoolRotator<ExtrinsicHash<B>>,
+	submitted_at: RwLock<HashMap<ExtrinsicHash<B>, Instant>>,
 }
 
 #[cfg(not(target_os = "unknown"))]
@@ -117,14 +122,22 @@ where
 impl<B: ChainApi> ValidatedPool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, api: Arc<B>) -> Self {
-		let base_pool = base::BasePool::new(options.reject_future_transactions);
+		Self::with_prometheus(options, api, None)
+	}
+
+	/// Create a new transaction pool reporting to given Prometheus registry.
+	pub fn with_prometheus(options: Options, api: Arc<B>, prometheus: Option<&Registry>) -> Self {
+		let mut base_pool = base::BasePool::new(options.reject_future_transactions);
+		base_pool.set_min_priority_bump(options.min_priority_bump);
+		let rotator = PoolRotator::new(&options.rotation);
 		ValidatedPool {
 			options,
-			listener: Default::default(),
+			listener: RwLock::new(Listener::new(MetricsLink::new(prometheus))),
 			api,
 			pool: RwLock::new(base_pool),
 			import_notification_sinks: Default::default(),
-			rotator: Default::default(),
+			rotator,
+			submitted_at: Default::default(),
 		}
 	}
 
@@ -184,6 +197,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 		match tx {
 			ValidatedTransaction::Valid(tx) => {
 				let imported = self.pool.write().import(tx)?;
+				self.submitted_at.write().entry(imported.hash().clone()).or_insert_with(Instant::now);
 
 				if let base::Imported::Ready { ref hash, .. } = imported {
 					self.import_notification_sinks.lock()
@@ -223,6 +237,17 @@ impl<B: ChainApi> ValidatedPool<B> {
 		let future_limit = &self.options.future;
 
 		log::debug!(target: "txpool", "Pool Status: {:?}", status);
+		let mut removed = HashMap::new();
+
+		if let Some(ref sender_limit) = self.options.sender {
+			let dropped = self.pool.write().enforce_sender_limit(sender_limit);
+			if !dropped.is_empty() {
+				log::debug!(target: "txpool", "Enforcing sender limits: {} dropped", dropped.len());
+			}
+			removed.extend(dropped.into_iter().map(|x| (x.hash.clone(), DropReason::SenderLimit)));
+		}
+
+		let status = if removed.is_empty() { status } else { self.pool.read().status() };
 		if ready_limit.is_exceeded(status.ready, status.ready_bytes)
 			|| future_limit.is_exceeded(status.future, status.future_bytes)
 		{
@@ -234,28 +259,29 @@ impl<B: ChainApi> ValidatedPool<B> {
 			);
 
 			// clean up the pool
-			let removed = {
-				let mut pool = self.pool.write();
-				let removed = pool.enforce_limits(ready_limit, future_limit)
-					.into_iter().map(|x| x.hash.clone()).collect::<HashSet<_>>();
-				// ban all removed transactions
-				self.rotator.ban(&Instant::now(), removed.iter().map(|x| x.clone()));
-				removed
-			};
-			if !removed.is_empty() {
-				log::debug!(target: "txpool", "Enforcing limits: {} dropped", removed.len());
+			let dropped = self.pool.write().enforce_limits(ready_limit, future_limit);
+			if !dropped.is_empty() {
+				log::debug!(target: "txpool", "Enforcing limits: {} dropped", dropped.len());
 			}
+			removed.extend(dropped.into_iter().map(|x| (x.hash.clone(), DropReason::PoolLimit)));
+		}
 
-			// run notifications
-			let mut listener = self.listener.write();
-			for h in &removed {
-				listener.dropped(h, None);
-			}
+		if removed.is_empty() {
+			return Default::default();
+		}
 
-			removed
-		} else {
-			Default::default()
+		// ban all removed transactions
+		if self.options.rotation.ban_dropped {
+			self.rotator.ban(&Instant::now(), removed.keys().cloned());
+		}
+
+		// run notifications
+		let mut listener = self.listener.write();
+		for (h, reason) in &removed {
+			listener.dropped(h, *reason);
 		}
+
+		removed.into_iter().map(|(h, _)| h).collect()
 	}
 
 	/// Import a single extrinsic and starts to watch their progress in the pool.
@@ -285,8 +311,8 @@ impl<B: ChainApi> ValidatedPool<B> {
 	/// Removes and then submits passed transactions and all dependent transactions.
 	/// Transactions that are missing from the pool are not submitted.
 	pub fn resubmit(&self, mut updated_transactions: HashMap<ExtrinsicHash<B>, ValidatedTransactionFor<B>>) {
-		#[derive(Debug, Clone, Copy, PartialEq)]
-		enum Status { Future, Ready, Failed, Dropped };
+		#[derive(Debug, Clone, PartialEq)]
+		enum Status<H> { Future, Ready, Failed, Dropped(DropReason), Usurped(H) };
 
 		let (mut initial_statuses, final_statuses) = {
 			let mut pool = self.pool.write();
@@ -341,7 +367,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 						ValidatedTransaction::Valid(tx) => match pool.import(tx) {
 							Ok(imported) => match imported {
 								base::Imported::Ready { promoted, failed, removed, .. } => {
-									final_statuses.insert(hash, Status::Ready);
+									final_statuses.insert(hash.clone(), Status::Ready);
 									for hash in promoted {
 										final_statuses.insert(hash, Status::Ready);
 									}
@@ -349,7 +375,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 										final_statuses.insert(hash, Status::Failed);
 									}
 									for tx in removed {
-										final_statuses.insert(tx.hash.clone(), Status::Dropped);
+										final_statuses.insert(tx.hash.clone(), Status::Usurped(hash.clone()));
 									}
 								},
 								base::Imported::Future { .. } => {
@@ -379,7 +405,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 				// queue, updating final statuses as required
 				if reject_future_transactions {
 					for future_tx in pool.clear_future() {
-						final_statuses.insert(future_tx.hash.clone(), Status::Dropped);
+						final_statuses.insert(future_tx.hash.clone(), Status::Dropped(DropReason::FutureRejected));
 					}
 				}
 
@@ -391,11 +417,12 @@ impl<B: ChainApi> ValidatedPool<B> {
 		let mut listener = self.listener.write();
 		for (hash, final_status) in final_statuses {
 			let initial_status = initial_statuses.remove(&hash);
-			if initial_status.is_none() || Some(final_status) != initial_status {
+			if initial_status.is_none() || Some(&final_status) != initial_status.as_ref() {
 				match final_status {
 					Status::Future => listener.future(&hash),
 					Status::Ready => listener.ready(&hash, None),
-					Status::Dropped => listener.dropped(&hash, None),
+					Status::Dropped(reason) => listener.dropped(&hash, reason),
+					Status::Usurped(by) => listener.usurped(&hash, &by),
 					Status::Failed => listener.invalid(&hash, initial_status.is_some()),
 				}
 			}
@@ -431,7 +458,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 				fire_events(&mut *listener, promoted);
 			}
 			for f in &status.failed {
-				listener.dropped(f, None);
+				listener.dropped(f, DropReason::PromotionFailed);
 			}
 		}
 
@@ -522,6 +549,11 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.remove_invalid(&futures_to_remove);
 		// clear banned transactions timeouts
 		self.rotator.clear_timeouts(&now);
+		// forget submission times of transactions that left the pool
+		{
+			let pool = self.pool.read();
+			self.submitted_at.write().retain(|hash, _| pool.is_imported(hash));
+		}
 
 		Ok(())
 	}
@@ -591,6 +623,68 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.pool.read().ready()
 	}
 
+	/// Returns a snapshot of the transactions in the future queue.
+	pub fn futures(&self) -> Vec<base::Transaction<ExtrinsicHash<B>, ExtrinsicFor<B>>> {
+		self.pool.read().futures().map(|tx| tx.duplicate()).collect()
+	}
+
+	/// Returns the time the transaction with given hash was first submitted to the pool.
+	pub fn submitted_at(&self, hash: &ExtrinsicHash<B>) -> Option<Instant> {
+		self.submitted_at.read().get(hash).cloned()
+	}
+
+	/// Overrides the submission time of a transaction that is already in the pool.
+	///
+	/// Used when transactions are restored from a previous run of the node.
+	pub fn set_submitted_at(&self, hash: &ExtrinsicHash<B>, at: Instant) {
+		if self.pool.read().is_imported(hash) {
+			self.submitted_at.write().insert(hash.clone(), at);
+		}
+	}
+
+	/// Returns details of all transactions in the pool, ready ones first ordered by priority.
+	pub fn inspect(&self) -> Vec<PoolTransactionInfo<ExtrinsicHash<B>>> {
+		let pool = self.pool.read();
+		let submitted_at = self.submitted_at.read();
+		let now = Instant::now();
+		let ready = pool.ready()
+			.map(|tx| transaction_info(&tx, TransactionQueue::Ready, None, &submitted_at, now))
+			.collect::<Vec<_>>();
+		let future = pool.futures_waiting()
+			.map(|waiting| transaction_info(
+				&waiting.transaction,
+				TransactionQueue::Future,
+				Some(&waiting.missing_tags),
+				&submitted_at,
+				now,
+			));
+
+		ready.into_iter().chain(future).collect()
+	}
+
+	/// Returns details of the transaction with given hash, if it is in the pool.
+	pub fn transaction_info(&self, hash: &ExtrinsicHash<B>) -> Option<PoolTransactionInfo<ExtrinsicHash<B>>> {
+		let pool = self.pool.read();
+		let submitted_at = self.submitted_at.read();
+		let now = Instant::now();
+		if let Some(tx) = pool.ready_by_hash(hash) {
+			return Some(transaction_info(&tx, TransactionQueue::Ready, None, &submitted_at, now));
+		}
+
+		pool.future_by_hash(hash).map(|waiting| transaction_info(
+			&waiting.transaction,
+			TransactionQueue::Future,
+			Some(&waiting.missing_tags),
+			&submitted_at,
+			now,
+		))
+	}
+
+	/// Return an event stream of all transactions entering or leaving the pool.
+	pub fn pool_event_stream(&self) -> EventStream<PoolEvent<ExtrinsicHash<B>>> {
+		self.listener.write().event_stream()
+	}
+
 	/// Returns pool status.
 	pub fn status(&self) -> PoolStatus {
 		self.pool.read().status()
@@ -609,6 +703,28 @@ impl<B: ChainApi> ValidatedPool<B> {
 	}
 }
 
+fn transaction_info<H: Clone + hash::Hash + Eq, Ex>(
+	tx: &base::Transaction<H, Ex>,
+	queue: TransactionQueue,
+	missing_tags: Option<&HashSet<Tag>>,
+	submitted_at: &HashMap<H, Instant>,
+	now: Instant,
+) -> PoolTransactionInfo<H> {
+	let mut missing_tags = missing_tags.into_iter().flatten().cloned().collect::<Vec<_>>();
+	missing_tags.sort();
+	PoolTransactionInfo {
+		hash: tx.hash.clone(),
+		queue,
+		priority: tx.priority,
+		valid_till: tx.valid_till,
+		requires: tx.requires.clone(),
+		provides: tx.provides.clone(),
+		missing_tags,
+		source: tx.source,
+		age: submitted_at.get(&tx.hash).map(|at| now.duration_since(*at)).unwrap_or_default(),
+	}
+}
+
 fn fire_events<H, B, Ex>(
 	listener: &mut Listener<H, B>,
 	imported: &base::Imported<H, Ex>,
@@ -623,7 +739,7 @@ fn fire_events<H, B, Ex>(
 				listener.invalid(f, true);
 			}
 			for r in removed {
-				listener.dropped(&r.hash, Some(hash));
+				listener.usurped(&r.hash, hash);
 			}
 			for p in promoted {
 				listener.ready(p, None);
@@ -19,7 +19,7 @@
 //! Extrinsics status updates.
 
 use futures::Stream;
-use sp_transaction_pool::TransactionStatus;
+use sp_transaction_pool::{DropReason, TransactionStatus};
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedSender, TracingUnboundedReceiver};
 
 /// Extrinsic watcher.
@@ -117,9 +117,9 @@ impl<H: Clone, BH: Clone> Sender<H, BH> {
 		self.is_finalized = true;
 	}
 
-	/// Transaction has been dropped from the pool because of the limit.
-	pub fn dropped(&mut self) {
-		self.send(TransactionStatus::Dropped);
+	/// Transaction has been dropped from the pool for given reason.
+	pub fn dropped(&mut self, reason: DropReason) {
+		self.send(TransactionStatus::Dropped(reason));
 		self.is_finalized = true;
 	}
 
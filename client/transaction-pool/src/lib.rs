@@ -25,6 +25,8 @@
 mod api;
 mod revalidation;
 mod metrics;
+#[cfg(not(target_os = "unknown"))]
+mod persistence;
 
 pub mod error;
 
@@ -33,6 +35,8 @@ pub mod testing;
 
 pub use sc_transaction_graph as txpool;
 pub use crate::api::{FullChainApi, LightChainApi};
+#[cfg(not(target_os = "unknown"))]
+pub use crate::persistence::persistence_future;
 
 use std::{collections::{HashMap, HashSet}, sync::Arc, pin::Pin, convert::TryInto};
 use futures::{prelude::*, future::{self, ready}, channel::oneshot};
@@ -46,7 +50,7 @@ use sp_core::traits::SpawnNamed;
 use sp_transaction_pool::{
 	TransactionPool, PoolStatus, ImportNotificationStream, TxHash, TransactionFor,
 	TransactionStatusStreamFor, MaintainedTransactionPool, PoolFuture, ChainEvent,
-	TransactionSource,
+	TransactionSource, PoolTransactionInfo, PoolEventStream,
 };
 use sc_transaction_graph::{ChainApi, ExtrinsicHash};
 use wasm_timer::Instant;
@@ -189,7 +193,7 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 		revalidation_type: RevalidationType,
 		spawner: impl SpawnNamed,
 	) -> Self {
-		let pool = Arc::new(sc_transaction_graph::Pool::new(options, pool_api.clone()));
+		let pool = Arc::new(sc_transaction_graph::Pool::with_prometheus(options, pool_api.clone(), prometheus));
 		let (revalidation_queue, background_task) = match revalidation_type {
 			RevalidationType::Light => (revalidation::RevalidationQueue::new(pool_api.clone(), pool.clone()), None),
 			RevalidationType::Full => {
@@ -328,6 +332,18 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 	fn ready(&self) -> ReadyIteratorFor<PoolApi> {
 		Box::new(self.pool.validated_pool().ready())
 	}
+
+	fn inspect(&self) -> Vec<PoolTransactionInfo<TxHash<Self>>> {
+		self.pool.validated_pool().inspect()
+	}
+
+	fn transaction_info(&self, hash: &TxHash<Self>) -> Option<PoolTransactionInfo<TxHash<Self>>> {
+		self.pool.validated_pool().transaction_info(hash)
+	}
+
+	fn pool_event_stream(&self) -> PoolEventStream<TxHash<Self>> {
+		self.pool.validated_pool().pool_event_stream()
+	}
 }
 
 impl<Block, Client, Fetcher> LightPool<Block, Client, Fetcher>
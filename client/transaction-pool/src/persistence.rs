@@ -0,0 +1,289 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Persistence of the pool contents across node restarts.
+//!
+//! Ready and future transactions are written to a file together with their source and
+//! the time they were submitted at. On startup the persisted transactions are validated
+//! again before they re-enter the pool.
+
+use std::{
+	fs, io,
+	path::{Path, PathBuf},
+	sync::Arc,
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use codec::{Decode, Encode};
+use futures::prelude::*;
+use sc_transaction_graph::{ChainApi, ExtrinsicFor, Pool};
+use sp_runtime::{generic::BlockId, traits::Block as BlockT};
+use sp_transaction_pool::TransactionSource;
+
+use crate::BasicPool;
+
+/// Interval at which the pool contents are persisted.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Version of the file format.
+const VERSION: u32 = 1;
+
+/// A transaction as stored on disk.
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct PersistedTransaction<Extrinsic> {
+	/// Source the transaction was originally submitted from.
+	source: TransactionSource,
+	/// Milliseconds since the UNIX epoch at which the transaction was submitted.
+	submitted_at: u64,
+	/// The transaction itself.
+	extrinsic: Extrinsic,
+}
+
+/// Restores the transactions persisted at `path` and keeps persisting the pool there.
+///
+/// The persisted transactions are validated again at block `at` before entering the pool.
+/// Afterwards the pool contents are written every minute and once more when the returned
+/// future is dropped, i.e. when the node shuts down.
+pub async fn persistence_future<PoolApi, Block>(
+	pool: Arc<BasicPool<PoolApi, Block>>,
+	at: BlockId<Block>,
+	path: PathBuf,
+) where
+	Block: BlockT,
+	PoolApi: ChainApi<Block=Block> + 'static,
+{
+	match load(&path) {
+		Ok(transactions) => restore(pool.pool(), &at, transactions).await,
+		Err(e) => log::warn!(
+			target: "txpool",
+			"Failed to load persisted transactions from {}: {}",
+			path.display(),
+			e,
+		),
+	}
+
+	let persister = Persister { pool: pool.pool().clone(), path };
+	let interval = wasm_timer::Interval::new(PERSIST_INTERVAL);
+	futures::pin_mut!(interval);
+	while let Some(()) = interval.next().await {
+		persister.persist();
+	}
+}
+
+/// Writes the pool contents to disk, one last time when dropped.
+struct Persister<PoolApi: ChainApi> {
+	pool: Arc<Pool<PoolApi>>,
+	path: PathBuf,
+}
+
+impl<PoolApi: ChainApi> Persister<PoolApi> {
+	fn persist(&self) {
+		let transactions = collect(&self.pool);
+		match save(&self.path, &transactions) {
+			Ok(()) => log::debug!(
+				target: "txpool",
+				"Persisted {} transactions to {}",
+				transactions.len(),
+				self.path.display(),
+			),
+			Err(e) => log::warn!(
+				target: "txpool",
+				"Failed to persist transactions to {}: {}",
+				self.path.display(),
+				e,
+			),
+		}
+	}
+}
+
+impl<PoolApi: ChainApi> Drop for Persister<PoolApi> {
+	fn drop(&mut self) {
+		self.persist();
+	}
+}
+
+fn unix_millis(time: SystemTime) -> u64 {
+	time.duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or_default()
+}
+
+/// Collects the ready and future transactions of the pool.
+fn collect<PoolApi: ChainApi>(pool: &Pool<PoolApi>) -> Vec<PersistedTransaction<ExtrinsicFor<PoolApi>>> {
+	let validated_pool = pool.validated_pool();
+	let now = unix_millis(SystemTime::now());
+
+	validated_pool.ready()
+		.map(|tx| (tx.hash.clone(), tx.source, tx.data.clone()))
+		.chain(validated_pool.futures().into_iter().map(|tx| (tx.hash, tx.source, tx.data)))
+		.map(|(hash, source, extrinsic)| {
+			let age = validated_pool.submitted_at(&hash)
+				.map(|at| at.elapsed().as_millis() as u64)
+				.unwrap_or_default();
+			PersistedTransaction {
+				source,
+				submitted_at: now.saturating_sub(age),
+				extrinsic,
+			}
+		})
+		.collect()
+}
+
+/// Validates the persisted transactions at given block and imports them to the pool.
+async fn restore<PoolApi: ChainApi>(
+	pool: &Pool<PoolApi>,
+	at: &BlockId<PoolApi::Block>,
+	transactions: Vec<PersistedTransaction<ExtrinsicFor<PoolApi>>>,
+) {
+	if transactions.is_empty() {
+		return;
+	}
+
+	let now = Instant::now();
+	let now_unix = unix_millis(SystemTime::now());
+	let total = transactions.len();
+	let mut restored = 0;
+
+	for source in &[TransactionSource::InBlock, TransactionSource::Local, TransactionSource::External] {
+		let (submitted_at, xts): (Vec<_>, Vec<_>) = transactions.iter()
+			.filter(|tx| tx.source == *source)
+			.map(|tx| (tx.submitted_at, tx.extrinsic.clone()))
+			.unzip();
+		if xts.is_empty() {
+			continue;
+		}
+
+		let results = match pool.submit_at(at, *source, xts).await {
+			Ok(results) => results,
+			Err(e) => {
+				log::warn!(target: "txpool", "Failed to restore persisted transactions: {}", e);
+				continue;
+			},
+		};
+
+		for (result, submitted_at) in results.into_iter().zip(submitted_at) {
+			match result {
+				Ok(hash) => {
+					let age = Duration::from_millis(now_unix.saturating_sub(submitted_at));
+					if let Some(submitted_at) = now.checked_sub(age) {
+						pool.validated_pool().set_submitted_at(&hash, submitted_at);
+					}
+					restored += 1;
+				},
+				Err(e) => log::debug!(target: "txpool", "Dropping persisted transaction: {}", e),
+			}
+		}
+	}
+
+	log::info!(target: "txpool", "Restored {} of {} persisted transactions", restored, total);
+}
+
+/// Writes the transactions to the file at given path, replacing it atomically.
+fn save<Extrinsic: Encode>(path: &Path, transactions: &[PersistedTransaction<Extrinsic>]) -> io::Result<()> {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	let tmp = path.with_extension("tmp");
+	fs::write(&tmp, (VERSION, transactions).encode())?;
+	fs::rename(&tmp, path)
+}
+
+/// Reads the transactions from the file at given path.
+///
+/// A missing file is treated as an empty pool.
+fn load<Extrinsic: Decode>(path: &Path) -> io::Result<Vec<PersistedTransaction<Extrinsic>>> {
+	let bytes = match fs::read(path) {
+		Ok(bytes) => bytes,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(e) => return Err(e),
+	};
+
+	let (version, transactions) = <(u32, Vec<PersistedTransaction<Extrinsic>>)>::decode(&mut &bytes[..])
+		.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.what()))?;
+	if version != VERSION {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			format!("Unsupported version {}", version),
+		));
+	}
+
+	Ok(transactions)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+	use substrate_test_runtime_client::AccountKeyring::*;
+	use substrate_test_runtime_transaction_pool::{TestApi, uxt};
+
+	fn pool() -> Pool<TestApi> {
+		Pool::new(Default::default(), TestApi::with_alice_nonce(209).into())
+	}
+
+	#[test]
+	fn should_persist_and_restore_transactions() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("txpool");
+
+		// given
+		let pool = pool();
+		block_on(pool.submit_one(&BlockId::number(0), TransactionSource::Local, uxt(Alice, 209))).unwrap();
+		block_on(pool.submit_one(&BlockId::number(0), TransactionSource::External, uxt(Alice, 211))).unwrap();
+		assert_eq!(pool.validated_pool().status().ready, 1);
+		assert_eq!(pool.validated_pool().status().future, 1);
+		save(&path, &collect(&pool)).unwrap();
+
+		// when
+		let restored_pool = self::pool();
+		block_on(restore(&restored_pool, &BlockId::number(0), load(&path).unwrap()));
+
+		// then
+		let status = restored_pool.validated_pool().status();
+		assert_eq!(status.ready, 1);
+		assert_eq!(status.future, 1);
+		let ready = restored_pool.validated_pool().ready().next().unwrap();
+		assert_eq!(ready.source, TransactionSource::Local);
+		assert!(restored_pool.validated_pool().submitted_at(&ready.hash).is_some());
+	}
+
+	#[test]
+	fn should_not_restore_invalid_transactions() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("txpool");
+
+		// given
+		let pool = pool();
+		block_on(pool.submit_one(&BlockId::number(0), TransactionSource::External, uxt(Alice, 209))).unwrap();
+		save(&path, &collect(&pool)).unwrap();
+
+		// when
+		let api = TestApi::with_alice_nonce(210);
+		let restored_pool = Pool::new(Default::default(), api.into());
+		block_on(restore(&restored_pool, &BlockId::number(0), load(&path).unwrap()));
+
+		// then
+		assert!(restored_pool.validated_pool().status().is_empty());
+	}
+
+	#[test]
+	fn should_treat_missing_file_as_empty() {
+		let dir = tempfile::tempdir().unwrap();
+
+		assert_eq!(load::<Vec<u8>>(&dir.path().join("txpool")).unwrap(), Vec::new());
+	}
+}
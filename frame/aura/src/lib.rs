@@ -22,13 +22,22 @@
 //!
 //! ## Overview
 //!
-//! The Aura module extends Aura consensus by managing offline reporting.
+//! The Aura module extends Aura consensus by managing offline reporting and
+//! by validating and reporting equivocations (i.e. an authority producing two
+//! blocks for the same slot).
 //!
 //! ## Interface
 //!
 //! ### Public Functions
 //!
 //! - `slot_duration` - Determine the Aura slot-duration based on the Timestamp module configuration.
+//! - `submit_unsigned_equivocation_report` - Submit an unsigned equivocation report extrinsic from
+//! an offchain context.
+//!
+//! ### Dispatchable Functions
+//!
+//! - `report_equivocation` - Report an equivocation proven by a signed reporter.
+//! - `report_equivocation_unsigned` - Report an equivocation submitted by the local block author.
 //!
 //! ## Related Modules
 //!
@@ -49,26 +58,85 @@ use pallet_timestamp;
 use sp_std::{result, prelude::*};
 use codec::{Encode, Decode};
 use frame_support::{
-	decl_storage, decl_module, Parameter, traits::{Get, FindAuthor},
+	decl_error, decl_storage, decl_module, Parameter,
+	dispatch::DispatchResultWithPostInfo,
+	traits::{Get, FindAuthor, KeyOwnerProofSystem},
+	weights::{Pays, Weight},
 	ConsensusEngineId,
 };
+use frame_system::{ensure_none, ensure_signed};
 use sp_runtime::{
-	RuntimeAppPublic,
+	RuntimeAppPublic, KeyTypeId,
 	traits::{SaturatedConversion, Saturating, Zero, Member, IsMember}, generic::DigestItem,
 };
+use sp_session::{GetSessionNumber, GetValidatorCount};
+use sp_staking::SessionIndex;
 use sp_timestamp::OnTimestampSet;
 use sp_inherents::{InherentIdentifier, InherentData, ProvideInherent, MakeFatalError};
 use sp_consensus_aura::{
-	AURA_ENGINE_ID, ConsensusLog, AuthorityIndex,
+	AURA_ENGINE_ID, ConsensusLog, AuthorityIndex, EquivocationProof,
 	inherents::{INHERENT_IDENTIFIER, AuraInherentData},
 };
 
+mod default_weights;
+mod equivocation;
 mod mock;
 mod tests;
 
+pub use equivocation::{AuraEquivocationOffence, EquivocationHandler, HandleEquivocation, SlotNumber};
+
 pub trait Trait: pallet_timestamp::Trait {
 	/// The identifier type for an authority.
 	type AuthorityId: Member + Parameter + RuntimeAppPublic + Default;
+
+	/// The proof of key ownership, used for validating equivocation reports.
+	/// The proof must include the session index and validator count of the
+	/// session at which the equivocation occurred. The slot at which each
+	/// session starts is noted by this pallet's `OneSessionHandler` implementation.
+	type KeyOwnerProof: Parameter + GetSessionNumber + GetValidatorCount;
+
+	/// The identification of a key owner, used when reporting equivocations.
+	type KeyOwnerIdentification: Parameter;
+
+	/// A system for proving ownership of keys, i.e. that a given key was part
+	/// of a validator set, needed for validating equivocation reports.
+	type KeyOwnerProofSystem: KeyOwnerProofSystem<
+		(KeyTypeId, Self::AuthorityId),
+		Proof = Self::KeyOwnerProof,
+		IdentificationTuple = Self::KeyOwnerIdentification,
+	>;
+
+	/// The equivocation handling subsystem, defines methods to report an
+	/// offence (after the equivocation has been validated) and for submitting a
+	/// transaction to report an equivocation (from an offchain context).
+	/// NOTE: when enabling equivocation handling (i.e. this type isn't set to
+	/// `()`) you must use this pallet's `ValidateUnsigned` in the runtime
+	/// definition.
+	type HandleEquivocation: HandleEquivocation<Self>;
+
+	/// The number of past sessions whose start slot is kept, and therefore for which
+	/// equivocations can be reported. Key ownership can't be proven for older sessions
+	/// anyway, so this should cover the window of the `KeyOwnerProofSystem`, e.g. the
+	/// bonding duration in sessions when using the historical session module.
+	type SessionStartSlotsDepth: Get<SessionIndex>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
+}
+
+pub trait WeightInfo {
+	fn report_equivocation(validator_count: u32) -> Weight;
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// An equivocation proof provided as part of an equivocation report is invalid.
+		InvalidEquivocationProof,
+		/// A key ownership proof provided as part of an equivocation report is invalid.
+		InvalidKeyOwnershipProof,
+		/// A given equivocation report is valid but already previously reported.
+		DuplicateOffenceReport,
+	}
 }
 
 decl_storage! {
@@ -78,6 +146,12 @@ decl_storage! {
 
 		/// The current authorities
 		pub Authorities get(fn authorities): Vec<T::AuthorityId>;
+
+		/// A mapping from session index to the slot at which that session started.
+		/// Used to check that equivocations are reported along with a key ownership
+		/// proof for the session in which they happened. Only the last
+		/// `SessionStartSlotsDepth` sessions are kept.
+		SessionStartSlot get(fn session_start_slot): map hasher(twox_64_concat) SessionIndex => Option<SlotNumber>;
 	}
 	add_extra_genesis {
 		config(authorities): Vec<T::AuthorityId>;
@@ -86,7 +160,51 @@ decl_storage! {
 }
 
 decl_module! {
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin { }
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// Report authority equivocation/misbehavior. This method will verify
+		/// the equivocation proof and validate the given key ownership proof
+		/// against the extracted offender. If both are valid, the offence will
+		/// be reported.
+		#[weight = <T as Trait>::WeightInfo::report_equivocation(key_owner_proof.validator_count())]
+		fn report_equivocation(
+			origin,
+			equivocation_proof: EquivocationProof<T::Header, T::AuthorityId>,
+			key_owner_proof: T::KeyOwnerProof,
+		) -> DispatchResultWithPostInfo {
+			let reporter = ensure_signed(origin)?;
+
+			Self::do_report_equivocation(
+				Some(reporter),
+				equivocation_proof,
+				key_owner_proof,
+			)
+		}
+
+		/// Report authority equivocation/misbehavior. This method will verify
+		/// the equivocation proof and validate the given key ownership proof
+		/// against the extracted offender. If both are valid, the offence will
+		/// be reported.
+		/// This extrinsic must be called unsigned and it is expected that only
+		/// block authors will call it (validated in `ValidateUnsigned`), as such
+		/// if the block author is defined it will be defined as the equivocation
+		/// reporter.
+		#[weight = <T as Trait>::WeightInfo::report_equivocation(key_owner_proof.validator_count())]
+		fn report_equivocation_unsigned(
+			origin,
+			equivocation_proof: EquivocationProof<T::Header, T::AuthorityId>,
+			key_owner_proof: T::KeyOwnerProof,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+
+			Self::do_report_equivocation(
+				T::HandleEquivocation::block_author(),
+				equivocation_proof,
+				key_owner_proof,
+			)
+		}
+	}
 }
 
 impl<T: Trait> Module<T> {
@@ -106,13 +224,97 @@ impl<T: Trait> Module<T> {
 			<Authorities<T>>::put(authorities);
 		}
 	}
+
+	fn do_report_equivocation(
+		reporter: Option<T::AccountId>,
+		equivocation_proof: EquivocationProof<T::Header, T::AuthorityId>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> DispatchResultWithPostInfo {
+		let offender = equivocation_proof.offender.clone();
+		let slot_number = equivocation_proof.slot_number;
+
+		// validate the equivocation proof
+		if !sp_consensus_aura::check_equivocation_proof(equivocation_proof) {
+			return Err(Error::<T>::InvalidEquivocationProof.into());
+		}
+
+		let validator_set_count = key_owner_proof.validator_count();
+		let session_index = key_owner_proof.session();
+
+		// check that the slot number is consistent with the session index
+		// in the key ownership proof (i.e. slot is for that session)
+		if !Self::is_slot_in_session(slot_number, session_index) {
+			return Err(Error::<T>::InvalidKeyOwnershipProof.into());
+		}
+
+		// check the membership proof and extract the offender's id
+		let key = (<T::AuthorityId as RuntimeAppPublic>::ID, offender);
+		let offender = T::KeyOwnerProofSystem::check_proof(key, key_owner_proof)
+			.ok_or(Error::<T>::InvalidKeyOwnershipProof)?;
+
+		let offence = AuraEquivocationOffence {
+			slot: slot_number,
+			validator_set_count,
+			offender,
+			session_index,
+		};
+
+		let reporters = match reporter {
+			Some(id) => vec![id],
+			None => vec![],
+		};
+
+		T::HandleEquivocation::report_offence(reporters, offence)
+			.map_err(|_| Error::<T>::DuplicateOffenceReport)?;
+
+		// waive the fee since the report is valid and beneficial
+		Ok(Pays::No.into())
+	}
+
+	/// Whether the given slot was part of the given session, i.e. it is not
+	/// before the start of the session nor after the start of the next one.
+	fn is_slot_in_session(slot_number: SlotNumber, session_index: SessionIndex) -> bool {
+		let started = Self::session_start_slot(session_index)
+			.map_or(false, |start| start <= slot_number);
+		let ended = Self::session_start_slot(session_index + 1)
+			.map_or(false, |next_start| next_start <= slot_number);
+
+		started && !ended
+	}
+
+	/// The slot of the block being built, read from its Aura pre-runtime digest.
+	fn current_slot() -> Option<SlotNumber> {
+		<frame_system::Module<T>>::digest()
+			.logs
+			.iter()
+			.filter_map(|log| log.as_pre_runtime())
+			.find(|(id, _)| *id == AURA_ENGINE_ID)
+			.and_then(|(_, mut data)| SlotNumber::decode(&mut data).ok())
+	}
+
+	/// Submits an extrinsic to report an equivocation. This method will create
+	/// an unsigned extrinsic with a call to `report_equivocation_unsigned` and
+	/// will push the transaction to the pool. Only useful in an offchain
+	/// context.
+	pub fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Header, T::AuthorityId>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> Option<()> {
+		T::HandleEquivocation::submit_unsigned_equivocation_report(
+			equivocation_proof,
+			key_owner_proof,
+		)
+		.ok()
+	}
 }
 
 impl<T: Trait> sp_runtime::BoundToRuntimeAppPublic for Module<T> {
 	type Public = T::AuthorityId;
 }
 
-impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
+impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T>
+	where T: pallet_session::Trait
+{
 	type Key = T::AuthorityId;
 
 	fn on_genesis_session<'a, I: 'a>(validators: I)
@@ -120,6 +322,7 @@ impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
 	{
 		let authorities = validators.map(|(_, k)| k).collect::<Vec<_>>();
 		Self::initialize_authorities(&authorities);
+		SessionStartSlot::insert(0, 0);
 	}
 
 	fn on_new_session<'a, I: 'a>(changed: bool, validators: I, _queued_validators: I)
@@ -133,6 +336,18 @@ impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
 				Self::change_authorities(next_authorities);
 			}
 		}
+
+		// note the slot at which the new session starts, so that equivocations
+		// can be tied to the session they happened in.
+		let session_index = <pallet_session::Module<T>>::current_index();
+		if let Some(slot_number) = Self::current_slot() {
+			SessionStartSlot::insert(session_index, slot_number);
+		}
+
+		// and forget the start of the session that fell out of the reporting window.
+		if let Some(expired) = session_index.checked_sub(T::SessionStartSlotsDepth::get()) {
+			SessionStartSlot::remove(expired);
+		}
 	}
 
 	fn on_disabled(i: usize) {
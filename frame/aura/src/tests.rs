@@ -19,7 +19,24 @@
 
 #![cfg(test)]
 
-use crate::mock::{Aura, new_test_ext};
+use crate::{Call, Error, Trait, WeightInfo};
+use crate::mock::*;
+use codec::Encode;
+use frame_support::{
+	assert_err, assert_ok,
+	traits::{Currency, Get, KeyOwnerProofSystem},
+	weights::{GetDispatchInfo, Pays},
+};
+use sp_consensus_aura::{
+	AURA_ENGINE_ID, EquivocationProof, check_equivocation_proof,
+	ed25519::{AuthorityId, AuthorityPair},
+};
+use sp_core::{H256, Pair, crypto::key_types::AURA};
+use sp_runtime::{
+	Digest, DigestItem,
+	testing::Header,
+	traits::Header as _,
+};
 
 #[test]
 fn initial_values() {
@@ -28,3 +45,513 @@ fn initial_values() {
 		assert_eq!(Aura::authorities().len(), 4);
 	});
 }
+
+fn generate_equivocation_proof(
+	pair: &AuthorityPair,
+	first_slot: u64,
+	second_slot: u64,
+) -> EquivocationProof<Header, AuthorityId> {
+	let make_header = |slot_number: u64, extrinsics_root: H256| {
+		let mut header = Header::new(
+			1,
+			extrinsics_root,
+			Default::default(),
+			Default::default(),
+			Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot_number.encode())] },
+		);
+
+		let signature = pair.sign(header.hash().as_ref());
+		header.digest_mut().push(DigestItem::Seal(AURA_ENGINE_ID, signature.encode()));
+		header
+	};
+
+	EquivocationProof {
+		offender: pair.public(),
+		slot_number: first_slot,
+		first_header: make_header(first_slot, H256::repeat_byte(1)),
+		second_header: make_header(second_slot, H256::repeat_byte(2)),
+	}
+}
+
+#[test]
+fn session_start_slots_are_pruned() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		start_session(1);
+		assert!(Aura::session_start_slot(0).is_some());
+		assert!(Aura::session_start_slot(1).is_some());
+
+		// the start slots of the last `SessionStartSlotsDepth` sessions are kept.
+		start_session(SessionStartSlotsDepth::get());
+		assert!(Aura::session_start_slot(0).is_none());
+		assert!(Aura::session_start_slot(1).is_some());
+
+		start_session(SessionStartSlotsDepth::get() + 1);
+		assert!(Aura::session_start_slot(1).is_none());
+		assert!(Aura::session_start_slot(2).is_some());
+	});
+}
+
+#[test]
+fn check_equivocation_proof_works() {
+	let pair = AuthorityPair::from_seed(&[1; 32]);
+
+	// two distinct headers signed by the offender for the same slot
+	let proof = generate_equivocation_proof(&pair, 42, 42);
+	assert!(check_equivocation_proof(proof));
+
+	// the headers must be for the same slot
+	let proof = generate_equivocation_proof(&pair, 42, 43);
+	assert!(!check_equivocation_proof(proof));
+
+	// the headers must be distinct
+	let mut proof = generate_equivocation_proof(&pair, 42, 42);
+	proof.second_header = proof.first_header.clone();
+	assert!(!check_equivocation_proof(proof));
+
+	// the headers must be signed by the offender
+	let mut proof = generate_equivocation_proof(&pair, 42, 42);
+	proof.offender = AuthorityPair::from_seed(&[2; 32]).public();
+	assert!(!check_equivocation_proof(proof));
+}
+
+#[test]
+fn report_equivocation_current_session_works() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(3);
+
+	ext.execute_with(|| {
+		start_era(1);
+
+		let authorities = Aura::authorities();
+		let validators = Session::validators();
+
+		// make sure that all authorities have the same balance
+		for validator in &validators {
+			assert_eq!(Balances::total_balance(validator), 10_000_000);
+			assert_eq!(Staking::slashable_balance_of(validator), 10_000);
+
+			assert_eq!(
+				Staking::eras_stakers(1, validator),
+				pallet_staking::Exposure {
+					total: 10_000,
+					own: 10_000,
+					others: vec![],
+				},
+			);
+		}
+
+		// we will use the validator at index 0 as the offending authority
+		let offending_validator_index = 0;
+		let offending_validator_id = Session::validators()[offending_validator_index];
+		let offending_authority_pair = pairs
+			.into_iter()
+			.find(|p| p.public() == authorities[offending_validator_index])
+			.unwrap();
+
+		// generate an equivocation proof. it creates two headers at the given
+		// slot with different block hashes and signed by the given key
+		let slot_number = Aura::current_slot().unwrap();
+		let equivocation_proof =
+			generate_equivocation_proof(&offending_authority_pair, slot_number, slot_number);
+
+		// create the key ownership proof
+		let key = (AURA, &offending_authority_pair.public());
+		let key_owner_proof = Historical::prove(key).unwrap();
+
+		// report the equivocation
+		Aura::report_equivocation_unsigned(Origin::none(), equivocation_proof, key_owner_proof)
+			.unwrap();
+
+		// start a new era so that the results of the offence report
+		// are applied at era end
+		start_era(2);
+
+		// check that the balance of offending validator is slashed 100%.
+		assert_eq!(
+			Balances::total_balance(&offending_validator_id),
+			10_000_000 - 10_000
+		);
+		assert_eq!(Staking::slashable_balance_of(&offending_validator_id), 0);
+		assert_eq!(
+			Staking::eras_stakers(2, offending_validator_id),
+			pallet_staking::Exposure {
+				total: 0,
+				own: 0,
+				others: vec![],
+			},
+		);
+
+		// check that the balances of all other validators are left intact.
+		for validator in &validators {
+			if *validator == offending_validator_id {
+				continue;
+			}
+
+			assert_eq!(Balances::total_balance(validator), 10_000_000);
+			assert_eq!(Staking::slashable_balance_of(validator), 10_000);
+			assert_eq!(
+				Staking::eras_stakers(2, validator),
+				pallet_staking::Exposure {
+					total: 10_000,
+					own: 10_000,
+					others: vec![],
+				},
+			);
+		}
+	})
+}
+
+#[test]
+fn report_equivocation_old_session_works() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(3);
+
+	ext.execute_with(|| {
+		start_era(1);
+
+		let authorities = Aura::authorities();
+
+		// we will use the validator at index 0 as the offending authority
+		let offending_validator_index = 0;
+		let offending_validator_id = Session::validators()[offending_validator_index];
+		let offending_authority_pair = pairs
+			.into_iter()
+			.find(|p| p.public() == authorities[offending_validator_index])
+			.unwrap();
+
+		// generate an equivocation proof at the current slot
+		let slot_number = Aura::current_slot().unwrap();
+		let equivocation_proof =
+			generate_equivocation_proof(&offending_authority_pair, slot_number, slot_number);
+
+		// create the key ownership proof
+		let key = (AURA, &offending_authority_pair.public());
+		let key_owner_proof = Historical::prove(key).unwrap();
+
+		// start a new era and report the equivocation
+		// from the previous era
+		start_era(2);
+
+		// check the balance of the offending validator
+		assert_eq!(Balances::total_balance(&offending_validator_id), 10_000_000);
+		assert_eq!(
+			Staking::slashable_balance_of(&offending_validator_id),
+			10_000
+		);
+
+		// report the equivocation
+		Aura::report_equivocation_unsigned(Origin::none(), equivocation_proof, key_owner_proof)
+			.unwrap();
+
+		// start a new era so that the results of the offence report
+		// are applied at era end
+		start_era(3);
+
+		// check that the balance of offending validator is slashed 100%.
+		assert_eq!(
+			Balances::total_balance(&offending_validator_id),
+			10_000_000 - 10_000
+		);
+		assert_eq!(Staking::slashable_balance_of(&offending_validator_id), 0);
+		assert_eq!(
+			Staking::eras_stakers(3, offending_validator_id),
+			pallet_staking::Exposure {
+				total: 0,
+				own: 0,
+				others: vec![],
+			},
+		);
+	})
+}
+
+#[test]
+fn report_equivocation_invalid_key_owner_proof() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(3);
+
+	ext.execute_with(|| {
+		start_era(1);
+
+		let authorities = Aura::authorities();
+
+		// we will use the validator at index 0 as the offending authority
+		let offending_validator_index = 0;
+		let offending_authority_pair = pairs
+			.into_iter()
+			.find(|p| p.public() == authorities[offending_validator_index])
+			.unwrap();
+
+		// generate an equivocation proof at the current slot
+		let slot_number = Aura::current_slot().unwrap();
+		let equivocation_proof =
+			generate_equivocation_proof(&offending_authority_pair, slot_number, slot_number);
+
+		// create the key ownership proof
+		let key = (AURA, &offending_authority_pair.public());
+		let mut key_owner_proof = Historical::prove(key).unwrap();
+
+		// we change the session index in the key ownership proof
+		// which should make it invalid
+		key_owner_proof.session = 0;
+		assert_err!(
+			Aura::report_equivocation_unsigned(
+				Origin::none(),
+				equivocation_proof.clone(),
+				key_owner_proof
+			),
+			Error::<Test>::InvalidKeyOwnershipProof,
+		);
+
+		// it should fail as well if we create a key owner proof
+		// for a different authority than the offender
+		let key = (AURA, &authorities[1]);
+		let key_owner_proof = Historical::prove(key).unwrap();
+
+		// we need to progress to a new era to make sure that the key
+		// ownership proof is properly checked, otherwise since the state
+		// is still available the historical module will just check
+		// against current session data.
+		start_era(2);
+
+		assert_err!(
+			Aura::report_equivocation_unsigned(
+				Origin::none(),
+				equivocation_proof.clone(),
+				key_owner_proof
+			),
+			Error::<Test>::InvalidKeyOwnershipProof,
+		);
+
+		// an old equivocation can't be reported with a key ownership
+		// proof for the current session.
+		let key = (AURA, &offending_authority_pair.public());
+		let key_owner_proof = Historical::prove(key).unwrap();
+
+		assert_err!(
+			Aura::report_equivocation_unsigned(Origin::none(), equivocation_proof, key_owner_proof),
+			Error::<Test>::InvalidKeyOwnershipProof,
+		);
+	})
+}
+
+#[test]
+fn report_equivocation_invalid_equivocation_proof() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(3);
+
+	ext.execute_with(|| {
+		start_era(1);
+
+		let authorities = Aura::authorities();
+
+		// we will use the validator at index 0 as the offending authority
+		let offending_validator_index = 0;
+		let offending_authority_pair = pairs
+			.into_iter()
+			.find(|p| p.public() == authorities[offending_validator_index])
+			.unwrap();
+
+		// create the key ownership proof
+		let key = (AURA, &offending_authority_pair.public());
+		let key_owner_proof = Historical::prove(key).unwrap();
+
+		let assert_invalid_equivocation = |equivocation_proof| {
+			assert_err!(
+				Aura::report_equivocation_unsigned(
+					Origin::none(),
+					equivocation_proof,
+					key_owner_proof.clone(),
+				),
+				Error::<Test>::InvalidEquivocationProof,
+			)
+		};
+
+		let slot_number = Aura::current_slot().unwrap();
+
+		// both headers have the same hash, no equivocation.
+		let mut equivocation_proof =
+			generate_equivocation_proof(&offending_authority_pair, slot_number, slot_number);
+		equivocation_proof.second_header = equivocation_proof.first_header.clone();
+		assert_invalid_equivocation(equivocation_proof);
+
+		// missing preruntime digest from one header
+		let mut equivocation_proof =
+			generate_equivocation_proof(&offending_authority_pair, slot_number, slot_number);
+		equivocation_proof.first_header.digest_mut().logs.remove(0);
+		assert_invalid_equivocation(equivocation_proof);
+
+		// missing seal from one header
+		let mut equivocation_proof =
+			generate_equivocation_proof(&offending_authority_pair, slot_number, slot_number);
+		equivocation_proof.first_header.digest_mut().logs.remove(1);
+		assert_invalid_equivocation(equivocation_proof);
+
+		// invalid slot number in proof compared to runtime digest
+		let mut equivocation_proof =
+			generate_equivocation_proof(&offending_authority_pair, slot_number, slot_number);
+		equivocation_proof.slot_number = 0;
+		assert_invalid_equivocation(equivocation_proof);
+
+		// different slot numbers in headers
+		let equivocation_proof =
+			generate_equivocation_proof(&offending_authority_pair, slot_number, slot_number + 1);
+		assert_invalid_equivocation(equivocation_proof);
+
+		// headers signed by a different authority than the offender
+		let equivocation_proof = EquivocationProof {
+			offender: offending_authority_pair.public(),
+			..generate_equivocation_proof(
+				&AuthorityPair::from_seed(&[42; 32]),
+				slot_number,
+				slot_number,
+			)
+		};
+		assert_invalid_equivocation(equivocation_proof);
+	})
+}
+
+#[test]
+fn report_equivocation_validate_unsigned_prevents_duplicates() {
+	use sp_runtime::transaction_validity::{
+		InvalidTransaction, TransactionLongevity, TransactionPriority, TransactionSource,
+		TransactionValidity, ValidTransaction,
+	};
+
+	let (pairs, mut ext) = new_test_ext_with_pairs(3);
+
+	ext.execute_with(|| {
+		start_era(1);
+
+		let authorities = Aura::authorities();
+
+		// generate and report an equivocation for the validator at index 0
+		let offending_validator_index = 0;
+		let offending_authority_pair = pairs
+			.into_iter()
+			.find(|p| p.public() == authorities[offending_validator_index])
+			.unwrap();
+
+		let slot_number = Aura::current_slot().unwrap();
+		let equivocation_proof =
+			generate_equivocation_proof(&offending_authority_pair, slot_number, slot_number);
+
+		let key = (AURA, &offending_authority_pair.public());
+		let key_owner_proof = Historical::prove(key).unwrap();
+
+		let inner =
+			Call::report_equivocation_unsigned(equivocation_proof.clone(), key_owner_proof.clone());
+
+		// only local/inblock reports are allowed
+		assert_eq!(
+			<Aura as sp_runtime::traits::ValidateUnsigned>::validate_unsigned(
+				TransactionSource::External,
+				&inner,
+			),
+			InvalidTransaction::Call.into(),
+		);
+
+		// the transaction is valid when passed as local
+		let tx_tag = (offending_authority_pair.public(), slot_number);
+		assert_eq!(
+			<Aura as sp_runtime::traits::ValidateUnsigned>::validate_unsigned(
+				TransactionSource::Local,
+				&inner,
+			),
+			TransactionValidity::Ok(ValidTransaction {
+				priority: TransactionPriority::max_value(),
+				requires: vec![],
+				provides: vec![("AuraEquivocation", tx_tag).encode()],
+				longevity: TransactionLongevity::max_value(),
+				propagate: false,
+			})
+		);
+
+		// the pre dispatch checks should also pass
+		assert_ok!(<Aura as sp_runtime::traits::ValidateUnsigned>::pre_dispatch(&inner));
+
+		// we submit the report
+		Aura::report_equivocation_unsigned(Origin::none(), equivocation_proof, key_owner_proof)
+			.unwrap();
+
+		// the report should now be considered stale and the transaction is invalid
+		assert_err!(
+			<Aura as sp_runtime::traits::ValidateUnsigned>::pre_dispatch(&inner),
+			InvalidTransaction::Stale,
+		);
+	});
+}
+
+#[test]
+fn report_equivocation_has_valid_weight() {
+	// the weight depends on the size of the validator set,
+	// but there's a lower bound of 100 validators.
+	assert!(
+		(1..=100)
+			.map(<Test as Trait>::WeightInfo::report_equivocation)
+			.collect::<Vec<_>>()
+			.windows(2)
+			.all(|w| w[0] == w[1])
+	);
+
+	// after 100 validators the weight should keep increasing
+	// with every extra validator.
+	assert!(
+		(100..=1000)
+			.map(<Test as Trait>::WeightInfo::report_equivocation)
+			.collect::<Vec<_>>()
+			.windows(2)
+			.all(|w| w[0] < w[1])
+	);
+}
+
+#[test]
+fn valid_equivocation_reports_dont_pay_fees() {
+	let (pairs, mut ext) = new_test_ext_with_pairs(3);
+
+	ext.execute_with(|| {
+		start_era(1);
+
+		let offending_authority_pair = &pairs[0];
+
+		// generate an equivocation proof.
+		let slot_number = Aura::current_slot().unwrap();
+		let equivocation_proof =
+			generate_equivocation_proof(offending_authority_pair, slot_number, slot_number);
+
+		// create the key ownership proof.
+		let key_owner_proof =
+			Historical::prove((AURA, &offending_authority_pair.public())).unwrap();
+
+		// check the dispatch info for the call.
+		let info = Call::<Test>::report_equivocation_unsigned(
+			equivocation_proof.clone(),
+			key_owner_proof.clone(),
+		)
+		.get_dispatch_info();
+
+		// it should have non-zero weight and the fee has to be paid.
+		assert!(info.weight > 0);
+		assert_eq!(info.pays_fee, Pays::Yes);
+
+		// report the equivocation.
+		let post_info = Aura::report_equivocation_unsigned(
+			Origin::none(),
+			equivocation_proof.clone(),
+			key_owner_proof.clone(),
+		)
+		.unwrap();
+
+		// the original weight should be kept, but given that the report
+		// is valid the fee is waived.
+		assert!(post_info.actual_weight.is_none());
+		assert_eq!(post_info.pays_fee, Pays::No);
+
+		// report the equivocation again which is invalid now since it is
+		// duplicate.
+		let post_info =
+			Aura::report_equivocation_unsigned(Origin::none(), equivocation_proof, key_owner_proof)
+				.err()
+				.unwrap()
+				.post_info;
+
+		// the fee is not waived and the original weight is kept.
+		assert!(post_info.actual_weight.is_none());
+		assert_eq!(post_info.pays_fee, Pays::Yes);
+	})
+}
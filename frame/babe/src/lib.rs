@@ -446,10 +446,18 @@ impl<T: Trait> Module<T> {
 		// by the session module to be called before this.
 		debug_assert!(Self::initialized().is_some());
 
-		// Update epoch index
-		let epoch_index = EpochIndex::get()
-			.checked_add(1)
-			.expect("epoch indices will never reach 2^64 before the death of the universe; qed");
+		// Update epoch index.
+		//
+		// NOTE: the epoch index is derived from the slot, so it may skip ahead if the
+		// chain was offline for more than `T::EpochDuration` slots. When skipping from
+		// epoch N to e.g. N+4, the authorities and randomness that had been announced
+		// for epoch N+1 are used in epoch N+4, and the randomness collected during
+		// epoch N will be used for epoch N+5.
+		let epoch_index = sp_consensus_babe::epoch_index(
+			CurrentSlot::get(),
+			GenesisSlot::get(),
+			T::EpochDuration::get(),
+		);
 
 		EpochIndex::put(epoch_index);
 		Authorities::put(authorities);
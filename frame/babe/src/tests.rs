@@ -256,6 +256,36 @@ fn can_enact_next_config() {
 	});
 }
 
+#[test]
+fn skipping_over_epochs_works() {
+	new_test_ext(3).execute_with(|| {
+		let epoch_duration: u64 = <Test as Trait>::EpochDuration::get();
+
+		// this sets the genesis slot to 100;
+		let genesis_slot = 100;
+		go_to_block(1, genesis_slot);
+
+		// we author all blocks of epoch #0 and the first block of epoch #1, which
+		// announces the randomness that will be used in epoch #2.
+		progress_to_block(epoch_duration + 1);
+		assert_eq!(Babe::epoch_index(), 1);
+
+		let randomness_for_epoch_2 = NextRandomness::get();
+
+		// we skip epoch #2 and author a block in epoch #3.
+		go_to_block(epoch_duration + 2, genesis_slot + epoch_duration * 3);
+
+		// the randomness announced for epoch #2 is used in epoch #3.
+		assert_eq!(Babe::epoch_index(), 3);
+		assert_eq!(Babe::randomness(), randomness_for_epoch_2);
+		assert_eq!(Babe::current_epoch_start(), genesis_slot + epoch_duration * 3);
+
+		// and no further epoch change happens in the next block.
+		go_to_block(epoch_duration + 3, genesis_slot + epoch_duration * 3 + 1);
+		assert_eq!(Babe::epoch_index(), 3);
+	});
+}
+
 #[test]
 fn report_equivocation_current_session_works() {
 	let (pairs, mut ext) = new_test_ext_with_pairs(3);
@@ -0,0 +1,147 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # BEEFY module.
+//!
+//! This module manages the validator set of the BEEFY gadget (`client/beefy`). Validators
+//! register their ECDSA BEEFY key as a session key, the set follows the session validators and
+//! every change is announced in the header digest with a [`ConsensusLog::AuthoritiesChange`],
+//! so that light clients can track the set signing commitments.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Encode;
+use frame_support::{decl_module, decl_storage};
+use sp_beefy::{AuthorityId, AuthorityIndex, BEEFY_ENGINE_ID, ConsensusLog, ValidatorSet};
+use sp_runtime::generic::DigestItem;
+use sp_std::prelude::*;
+
+mod mock;
+mod tests;
+
+/// The module's config trait.
+pub trait Trait: frame_system::Trait {}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Beefy {
+		/// The current authorities.
+		pub Authorities get(fn authorities): Vec<AuthorityId>;
+
+		/// The identifier of the current validator set.
+		pub ValidatorSetId get(fn validator_set_id): sp_beefy::ValidatorSetId;
+
+		/// Authorities of the next session.
+		pub NextAuthorities get(fn next_authorities): Vec<AuthorityId>;
+	}
+	add_extra_genesis {
+		config(authorities): Vec<AuthorityId>;
+		build(|config| Module::<T>::initialize_authorities(&config.authorities))
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin { }
+}
+
+impl<T: Trait> Module<T> {
+	/// Return the current active BEEFY validator set.
+	pub fn validator_set() -> ValidatorSet<AuthorityId> {
+		ValidatorSet {
+			validators: Self::authorities(),
+			id: Self::validator_set_id(),
+		}
+	}
+
+	/// Return the BEEFY validator set of the next session.
+	pub fn next_validator_set() -> ValidatorSet<AuthorityId> {
+		let validators = Self::next_authorities();
+		// Same as in `change_authorities`, the set id is only bumped when the keys change.
+		let id = if validators == Self::authorities() {
+			Self::validator_set_id()
+		} else {
+			Self::validator_set_id() + 1
+		};
+
+		ValidatorSet { validators, id }
+	}
+
+	fn change_authorities(new: Vec<AuthorityId>, queued: Vec<AuthorityId>) {
+		// Signers are identified by their keys only, so the set id is only bumped when the keys
+		// change.
+		if new != Self::authorities() {
+			<Authorities>::put(&new);
+			let id = <ValidatorSetId>::mutate(|id| {
+				*id += 1;
+				*id
+			});
+
+			let log: DigestItem<T::Hash> = DigestItem::Consensus(
+				BEEFY_ENGINE_ID,
+				ConsensusLog::AuthoritiesChange(ValidatorSet { validators: new, id }).encode(),
+			);
+			<frame_system::Module<T>>::deposit_log(log);
+		}
+
+		<NextAuthorities>::put(&queued);
+	}
+
+	fn initialize_authorities(authorities: &[AuthorityId]) {
+		if authorities.is_empty() {
+			return;
+		}
+
+		assert!(<Authorities>::get().is_empty(), "Authorities are already initialized!");
+		<Authorities>::put(authorities);
+		<ValidatorSetId>::put(0);
+		// Like `pallet_session`, use the genesis set for the next session as well.
+		<NextAuthorities>::put(authorities);
+	}
+}
+
+impl<T: Trait> sp_runtime::BoundToRuntimeAppPublic for Module<T> {
+	type Public = AuthorityId;
+}
+
+impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
+	type Key = AuthorityId;
+
+	fn on_genesis_session<'a, I: 'a>(validators: I)
+		where I: Iterator<Item=(&'a T::AccountId, AuthorityId)>
+	{
+		let authorities = validators.map(|(_, k)| k).collect::<Vec<_>>();
+		Self::initialize_authorities(&authorities);
+	}
+
+	fn on_new_session<'a, I: 'a>(changed: bool, validators: I, queued_validators: I)
+		where I: Iterator<Item=(&'a T::AccountId, AuthorityId)>
+	{
+		if changed {
+			let next_authorities = validators.map(|(_, k)| k).collect::<Vec<_>>();
+			let next_queued_authorities = queued_validators.map(|(_, k)| k).collect::<Vec<_>>();
+			Self::change_authorities(next_authorities, next_queued_authorities);
+		}
+	}
+
+	fn on_disabled(i: usize) {
+		let log: DigestItem<T::Hash> = DigestItem::Consensus(
+			BEEFY_ENGINE_ID,
+			ConsensusLog::<AuthorityId>::OnDisabled(i as AuthorityIndex).encode(),
+		);
+
+		<frame_system::Module<T>>::deposit_log(log);
+	}
+}
@@ -0,0 +1,80 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the module.
+
+#![cfg(test)]
+
+use codec::Encode;
+use pallet_session::OneSessionHandler;
+use sp_beefy::{BEEFY_ENGINE_ID, ConsensusLog, ValidatorSet};
+use sp_runtime::generic::DigestItem;
+
+use crate::mock::{Beefy, System, authority_id, new_test_ext};
+
+fn session_keys(ids: &[u8]) -> Vec<(&'static u64, sp_beefy::AuthorityId)> {
+	ids.iter().map(|i| (&0, authority_id(*i))).collect()
+}
+
+#[test]
+fn genesis_session_initializes_authorities() {
+	new_test_ext(vec![1, 2]).execute_with(|| {
+		assert_eq!(Beefy::validator_set(), ValidatorSet { validators: vec![authority_id(1), authority_id(2)], id: 0 });
+		assert_eq!(Beefy::next_authorities(), vec![authority_id(1), authority_id(2)]);
+		assert_eq!(Beefy::next_validator_set(), Beefy::validator_set());
+	});
+}
+
+#[test]
+fn session_change_updates_authorities() {
+	new_test_ext(vec![1, 2]).execute_with(|| {
+		// no change, nothing happens
+		Beefy::on_new_session(false, session_keys(&[3]).into_iter(), session_keys(&[4]).into_iter());
+		assert_eq!(Beefy::validator_set_id(), 0);
+		assert!(System::digest().logs.is_empty());
+
+		// same keys, only the queued authorities are updated
+		Beefy::on_new_session(true, session_keys(&[1, 2]).into_iter(), session_keys(&[3]).into_iter());
+		assert_eq!(Beefy::validator_set_id(), 0);
+		assert_eq!(Beefy::next_authorities(), vec![authority_id(3)]);
+		assert_eq!(Beefy::next_validator_set(), ValidatorSet { validators: vec![authority_id(3)], id: 1 });
+		assert!(System::digest().logs.is_empty());
+
+		Beefy::on_new_session(true, session_keys(&[3]).into_iter(), session_keys(&[4]).into_iter());
+		let set = ValidatorSet { validators: vec![authority_id(3)], id: 1 };
+		assert_eq!(Beefy::validator_set(), set);
+		assert_eq!(Beefy::next_authorities(), vec![authority_id(4)]);
+		assert_eq!(
+			System::digest().logs,
+			vec![DigestItem::Consensus(BEEFY_ENGINE_ID, ConsensusLog::AuthoritiesChange(set).encode())],
+		);
+	});
+}
+
+#[test]
+fn disabling_validator_deposits_log() {
+	new_test_ext(vec![1, 2]).execute_with(|| {
+		Beefy::on_disabled(1);
+		assert_eq!(
+			System::digest().logs,
+			vec![DigestItem::Consensus(
+				BEEFY_ENGINE_ID,
+				ConsensusLog::<sp_beefy::AuthorityId>::OnDisabled(1).encode(),
+			)],
+		);
+	});
+}
@@ -0,0 +1,140 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Benchmarks for Fast Unstake Pallet
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use frame_system::{RawOrigin, EventRecord};
+use frame_benchmarking::{benchmarks, account};
+use frame_support::{storage::{StorageDoubleMap, StorageValue}, traits::OnInitialize};
+use sp_runtime::traits::{Bounded, StaticLookup};
+use pallet_staking::{Exposure, IndividualExposure, RewardDestination};
+use crate::Module as FastUnstake;
+
+const SEED: u32 = 0;
+const MAX_VALIDATORS: u32 = 1000;
+const MAX_NOMINATORS: u32 = 10_000;
+
+fn assert_last_event<T: Trait>(generic_event: <T as Trait>::Event) {
+	let events = frame_system::Module::<T>::events();
+	let system_event: <T as frame_system::Trait>::Event = generic_event.into();
+	// compare to the last event record
+	let EventRecord { event, .. } = &events[events.len() - 1];
+	assert_eq!(event, &system_event);
+}
+
+/// Bond a new stash and register it for fast unstake. Returns the stash and its controller.
+fn create_registered_stash<T: Trait>() -> Result<(T::AccountId, T::AccountId), &'static str> {
+	let (stash, controller) = create_stash::<T>()?;
+	FastUnstake::<T>::register_fast_unstake(RawOrigin::Signed(controller.clone()).into())?;
+	Ok((stash, controller))
+}
+
+fn create_stash<T: Trait>() -> Result<(T::AccountId, T::AccountId), &'static str> {
+	let stash: T::AccountId = account("stash", 0, SEED);
+	let controller: T::AccountId = account("controller", 0, SEED);
+	<T as pallet_staking::Trait>::Currency::make_free_balance_be(&stash, Bounded::max_value());
+	<T as Trait>::Currency::make_free_balance_be(&stash, Bounded::max_value());
+	let amount = <T as pallet_staking::Trait>::Currency::minimum_balance() * 10u32.into();
+	pallet_staking::Module::<T>::bond(
+		RawOrigin::Signed(stash.clone()).into(),
+		T::Lookup::unlookup(controller.clone()),
+		amount,
+		RewardDestination::Staked,
+	)?;
+	Ok((stash, controller))
+}
+
+benchmarks! {
+	_ { }
+
+	register_fast_unstake {
+		let (stash, controller) = create_stash::<T>()?;
+		pallet_staking::Module::<T>::nominate(
+			RawOrigin::Signed(controller.clone()).into(),
+			vec![T::Lookup::unlookup(account("validator", 0, SEED))],
+		)?;
+	}: _(RawOrigin::Signed(controller))
+	verify {
+		assert!(Queue::<T>::contains_key(&stash));
+		assert_last_event::<T>(RawEvent::Registered(stash).into());
+	}
+
+	deregister {
+		let (stash, controller) = create_registered_stash::<T>()?;
+	}: _(RawOrigin::Signed(controller))
+	verify {
+		assert!(!Queue::<T>::contains_key(&stash));
+		assert_last_event::<T>(RawEvent::Deregistered(stash).into());
+	}
+
+	// Check a single era in which `v` validators were exposed, backed by `n` nominators spread
+	// evenly among them.
+	on_initialize_check {
+		let v in 1 .. MAX_VALIDATORS;
+		let n in 0 .. MAX_NOMINATORS;
+		let (stash, _) = create_registered_stash::<T>()?;
+		pallet_staking::CurrentEra::put(0);
+		for i in 0 .. v {
+			let validator: T::AccountId = account("validator", i, SEED);
+			let others = (i .. n).step_by(v as usize)
+				.map(|j| IndividualExposure { who: account("nominator", j, SEED), value: 1u32.into() })
+				.collect::<Vec<_>>();
+			pallet_staking::ErasStakers::<T>::insert(0, validator, Exposure {
+				total: (others.len() as u32 + 1).into(),
+				own: 1u32.into(),
+				others,
+			});
+		}
+	}: {
+		FastUnstake::<T>::on_initialize(1u32.into());
+	}
+	verify {
+		assert_last_event::<T>(RawEvent::Checking(stash, vec![0]).into());
+	}
+
+	on_initialize_unstake {
+		let (stash, _) = create_registered_stash::<T>()?;
+		let deposit = Queue::<T>::take(&stash).ok_or("stash not queued")?;
+		pallet_staking::CurrentEra::put(0);
+		Head::<T>::put(UnstakeRequest { stash: stash.clone(), checked: vec![0], deposit });
+	}: {
+		FastUnstake::<T>::on_initialize(1u32.into());
+	}
+	verify {
+		assert_last_event::<T>(RawEvent::Unstaked(stash, Ok(())).into());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{new_test_ext, Test};
+	use frame_support::assert_ok;
+
+	#[test]
+	fn test_benchmarks() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_register_fast_unstake::<Test>());
+			assert_ok!(test_benchmark_deregister::<Test>());
+			assert_ok!(test_benchmark_on_initialize_check::<Test>());
+			assert_ok!(test_benchmark_on_initialize_unstake::<Test>());
+		});
+	}
+}
@@ -0,0 +1,402 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Fast Unstake Module
+//!
+//! A module that allows stashes which were never exposed to unbond without waiting for the full
+//! bonding duration of the staking module.
+//!
+//! - [`fast_unstake::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! Unbonding through the staking module always takes `BondingDuration` eras, since the funds of
+//! a stash that backed a validator may still be slashed for offences committed in that time.
+//! A stash that does not appear in any `ErasStakers` exposure within the last `HistoryDepth` eras
+//! cannot be slashed though, and there is no reason to keep its funds locked.
+//!
+//! The controller of such a stash may register it for fast unstake, placing a deposit of
+//! [`Trait::Deposit`] on the stash. Registration chills the stash and adds it to the [`Queue`].
+//! At the beginning of each block, the stash at the [`Head`] of the queue is checked against
+//! [`Trait::ErasToCheckPerBlock`] eras of exposures, as far as the weight left in the block
+//! allows. Once all eras in `[current_era - history_depth; current_era]` were checked without
+//! finding the stash, it is forcefully unstaked, its staking lock is removed and the deposit is
+//! returned.
+//!
+//! If the stash is found in any exposure, its deposit is slashed and it remains bonded. Note that
+//! this also applies to eras which start while the stash is waiting in the queue, so a stash that
+//! starts nominating again after registering is very likely to lose its deposit.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `register_fast_unstake` - Register the stash of the caller's controller for fast unstake.
+//! * `deregister` - Remove the stash of the caller's controller from the queue.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+// Ensure we're `no_std` when compiling for Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+mod benchmarking;
+pub mod weights;
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_runtime::{DispatchResult, traits::Zero};
+use frame_support::{
+	decl_module, decl_event, decl_error, decl_storage, ensure, RuntimeDebug,
+	traits::{Get, Currency, ReservableCurrency, OnUnbalanced, UnfilteredDispatchable},
+	weights::Weight,
+	storage::{IterableStorageMap, IterableStorageDoubleMap},
+};
+use frame_system::{ensure_signed, RawOrigin};
+use pallet_staking::EraIndex;
+pub use weights::WeightInfo;
+
+type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+
+/// Configuration trait.
+pub trait Trait: frame_system::Trait + pallet_staking::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The currency in which the deposit is reserved.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The amount of currency reserved from the stash while it is registered for fast unstake.
+	type Deposit: Get<BalanceOf<Self>>;
+
+	/// Handler for the deposit of stashes which turn out to be exposed.
+	type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// The number of eras of exposures checked for the head of the queue in each block.
+	///
+	/// Setting this to zero pauses all processing of the queue.
+	type ErasToCheckPerBlock: Get<u32>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
+}
+
+/// A stash which is currently being checked for exposures.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct UnstakeRequest<AccountId, Balance> {
+	/// The stash to unstake.
+	pub stash: AccountId,
+	/// The eras which have already been checked and in which the stash was not exposed.
+	pub checked: Vec<EraIndex>,
+	/// The amount reserved from the stash.
+	pub deposit: Balance,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as FastUnstake {
+		/// The stash which is currently being checked, if any.
+		pub Head get(fn head): Option<UnstakeRequest<T::AccountId, BalanceOf<T>>>;
+
+		/// The stashes waiting to be checked, together with the amount held on deposit.
+		pub Queue get(fn queue): map hasher(twox_64_concat) T::AccountId => Option<BalanceOf<T>>;
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The caller is not a controller.
+		NotController,
+		/// The stash is already registered for fast unstake.
+		AlreadyQueued,
+		/// The stash is not registered for fast unstake.
+		NotQueued,
+		/// The stash is currently being checked and can no longer be deregistered.
+		AlreadyHead,
+		/// The stash has funds which are being unbonded.
+		NotFullyBonded,
+		/// The stash has been slashed before and can not be fast unstaked.
+		Slashed,
+		/// The stash does not have enough free balance to pay the deposit.
+		InsufficientDeposit,
+		/// An election is ongoing and the stash can not be chilled.
+		CallNotAllowed,
+	}
+}
+
+decl_event! {
+	/// Events type.
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		Balance = BalanceOf<T>,
+	{
+		/// A stash has been registered for fast unstake. \[stash\]
+		Registered(AccountId),
+		/// A stash has been removed from the queue and its deposit returned. \[stash\]
+		Deregistered(AccountId),
+		/// A stash has not been exposed in the given eras. \[stash, eras\]
+		Checking(AccountId, Vec<EraIndex>),
+		/// A stash has been unstaked and its deposit returned. \[stash, result\]
+		Unstaked(AccountId, DispatchResult),
+		/// A stash has been exposed and its deposit was slashed. \[stash, amount\]
+		Slashed(AccountId, Balance),
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// Deposit one of this module's events by using the default implementation.
+		fn deposit_event() = default;
+
+		/// The amount of currency reserved from a stash registered for fast unstake.
+		const Deposit: BalanceOf<T> = T::Deposit::get();
+
+		/// The number of eras of exposures checked in each block.
+		const ErasToCheckPerBlock: u32 = T::ErasToCheckPerBlock::get();
+
+		fn on_initialize() -> Weight {
+			Self::process_head()
+		}
+
+		/// Register the stash of the sender for fast unstake.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
+		///
+		/// The stash must not be unbonding any funds and must have enough free balance for the
+		/// deposit. It is chilled immediately, which is why this can only be called when the
+		/// `EraElectionStatus` of the staking module is `Closed`.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Reads: Ledger, Queue, Head, SlashingSpans, EraElectionStatus, Account
+		/// - Writes: Validators, Nominators, Queue, Account
+		/// # </weight>
+		#[weight = <T as Trait>::WeightInfo::register_fast_unstake()]
+		fn register_fast_unstake(origin) {
+			let controller = ensure_signed(origin)?;
+			let ledger = <pallet_staking::Module<T>>::ledger(&controller)
+				.ok_or(Error::<T>::NotController)?;
+			let stash = ledger.stash;
+
+			ensure!(!<Queue<T>>::contains_key(&stash), Error::<T>::AlreadyQueued);
+			ensure!(
+				Self::head().map_or(true, |head| head.stash != stash),
+				Error::<T>::AlreadyQueued,
+			);
+			ensure!(
+				ledger.active == ledger.total && ledger.unlocking.is_empty(),
+				Error::<T>::NotFullyBonded,
+			);
+			ensure!(
+				<pallet_staking::Module<T>>::slashing_spans(&stash).is_none(),
+				Error::<T>::Slashed,
+			);
+			ensure!(
+				<pallet_staking::Module<T>>::era_election_status().is_closed(),
+				Error::<T>::CallNotAllowed,
+			);
+
+			let deposit = T::Deposit::get();
+			ensure!(
+				<T as Trait>::Currency::can_reserve(&stash, deposit),
+				Error::<T>::InsufficientDeposit,
+			);
+
+			pallet_staking::Call::<T>::chill()
+				.dispatch_bypass_filter(RawOrigin::Signed(controller).into())
+				.map_err(|e| e.error)?;
+			<T as Trait>::Currency::reserve(&stash, deposit)?;
+			<Queue<T>>::insert(&stash, deposit);
+
+			Self::deposit_event(RawEvent::Registered(stash));
+		}
+
+		/// Remove the stash of the sender from the fast unstake queue and return its deposit.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
+		///
+		/// A stash that is already being checked can not be deregistered.
+		///
+		/// # <weight>
+		/// - O(1).
+		/// - Reads: Ledger, Queue, Head, Account
+		/// - Writes: Queue, Account
+		/// # </weight>
+		#[weight = <T as Trait>::WeightInfo::deregister()]
+		fn deregister(origin) {
+			let controller = ensure_signed(origin)?;
+			let stash = <pallet_staking::Module<T>>::ledger(&controller)
+				.ok_or(Error::<T>::NotController)?
+				.stash;
+
+			ensure!(
+				Self::head().map_or(true, |head| head.stash != stash),
+				Error::<T>::AlreadyHead,
+			);
+			let deposit = <Queue<T>>::take(&stash).ok_or(Error::<T>::NotQueued)?;
+			<T as Trait>::Currency::unreserve(&stash, deposit);
+
+			Self::deposit_event(RawEvent::Deregistered(stash));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Make progress on the stash at the head of the queue, pulling a new one from the queue if
+	/// there is none.
+	///
+	/// Returns the weight consumed.
+	fn process_head() -> Weight {
+		let eras_to_check = T::ErasToCheckPerBlock::get();
+		if eras_to_check.is_zero() {
+			return 0;
+		}
+
+		let mut head = match <Head<T>>::take().or_else(Self::next_from_queue) {
+			Some(head) => head,
+			None => return T::DbWeight::get().reads(2),
+		};
+
+		let current_era = match <pallet_staking::Module<T>>::current_era() {
+			Some(era) => era,
+			None => {
+				<Head<T>>::put(head);
+				return T::DbWeight::get().reads_writes(3, 1);
+			}
+		};
+
+		// exposures are only kept for `[current_era - history_depth; current_era]`.
+		let earliest_era = current_era.saturating_sub(<pallet_staking::Module<T>>::history_depth());
+		head.checked.retain(|era| *era >= earliest_era);
+
+		let unchecked_eras = (earliest_era..=current_era)
+			.rev()
+			.filter(|era| !head.checked.contains(era))
+			.take(eras_to_check as usize)
+			.collect::<Vec<_>>();
+
+		// exposures are checked only as far as the weight left in the block allows, eras that
+		// could not be checked entirely are checked again in the next block.
+		let budget = T::MaximumBlockWeight::get()
+			.saturating_sub(<frame_system::Module<T>>::block_weight().total());
+
+		if unchecked_eras.is_empty() {
+			if budget < <T as Trait>::WeightInfo::on_initialize_unstake() {
+				<Head<T>>::put(head);
+				return T::DbWeight::get().reads_writes(4, 1);
+			}
+
+			let UnstakeRequest { stash, deposit, .. } = head;
+			// a stash with slashing spans is rejected on registration, and it can not have been
+			// slashed since, as it would have been exposed.
+			let result = pallet_staking::Call::<T>::force_unstake(stash.clone(), 0)
+				.dispatch_bypass_filter(RawOrigin::Root.into())
+				.map(|_| ())
+				.map_err(|e| e.error);
+			<T as Trait>::Currency::unreserve(&stash, deposit);
+
+			Self::deposit_event(RawEvent::Unstaked(stash, result));
+			return <T as Trait>::WeightInfo::on_initialize_unstake();
+		}
+
+		let mut consumed = <T as Trait>::WeightInfo::on_initialize_check(0);
+		let mut checked = Vec::new();
+		let mut is_exposed = false;
+		for era in unchecked_eras {
+			match Self::is_exposed_in_era(&head.stash, era, budget, &mut consumed) {
+				Some(true) => {
+					is_exposed = true;
+					break;
+				},
+				Some(false) => checked.push(era),
+				None => break,
+			}
+		}
+
+		if is_exposed {
+			let UnstakeRequest { stash, deposit, .. } = head;
+			let (imbalance, _) = <T as Trait>::Currency::slash_reserved(&stash, deposit);
+			<T as Trait>::Slash::on_unbalanced(imbalance);
+
+			Self::deposit_event(RawEvent::Slashed(stash, deposit));
+		} else {
+			if !checked.is_empty() {
+				head.checked.extend(checked.iter().cloned());
+				Self::deposit_event(RawEvent::Checking(head.stash.clone(), checked));
+			}
+			<Head<T>>::put(head);
+		}
+
+		consumed
+	}
+
+	/// Take an arbitrary stash out of the queue and turn it into a new request.
+	fn next_from_queue() -> Option<UnstakeRequest<T::AccountId, BalanceOf<T>>> {
+		let (stash, deposit) = <Queue<T>>::iter().next()?;
+		<Queue<T>>::remove(&stash);
+		Some(UnstakeRequest { stash, checked: Vec::new(), deposit })
+	}
+
+	/// Whether `stash` is exposed as a validator or a nominator in `era`.
+	///
+	/// The weight of every exposure read is added to `consumed`, and an exposure is only read if
+	/// its weight fits in `budget`. Returns `None` if `budget` was used up before all exposures of
+	/// the era were read.
+	fn is_exposed_in_era(
+		stash: &T::AccountId,
+		era: EraIndex,
+		budget: Weight,
+		consumed: &mut Weight,
+	) -> Option<bool> {
+		let exposure_weight = Self::exposure_weight();
+		let mut exposures = <pallet_staking::ErasStakers<T>>::iter_prefix(era);
+		loop {
+			let with_next = consumed.saturating_add(exposure_weight);
+			if with_next > budget {
+				return None;
+			}
+
+			let (validator, exposure) = match exposures.next() {
+				Some(next) => next,
+				None => return Some(false),
+			};
+			*consumed = with_next;
+
+			if validator == *stash || exposure.others.iter().any(|individual| individual.who == *stash) {
+				return Some(true);
+			}
+		}
+	}
+
+	/// The weight of checking a single exposure.
+	///
+	/// The weights in use were generated before the `n` component of the `on_initialize_check`
+	/// benchmark was added, so they don't depend on the nominators of the exposure yet.
+	fn exposure_weight() -> Weight {
+		<T as Trait>::WeightInfo::on_initialize_check(1)
+			.saturating_sub(<T as Trait>::WeightInfo::on_initialize_check(0))
+	}
+}
@@ -0,0 +1,255 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for the module.
+
+use super::*;
+use mock::*;
+use frame_support::{assert_ok, assert_noop, traits::{OnInitialize, ReservableCurrency}};
+
+#[test]
+fn register_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Staking::nominate(Origin::signed(10), vec![2]));
+		assert!(pallet_staking::Nominators::<Test>::contains_key(1));
+
+		assert_ok!(FastUnstake::register_fast_unstake(Origin::signed(10)));
+
+		assert_eq!(FastUnstake::queue(1), Some(10));
+		assert_eq!(Balances::reserved_balance(1), 10);
+		assert!(!pallet_staking::Nominators::<Test>::contains_key(1));
+		assert_eq!(fast_unstake_events(), vec![RawEvent::Registered(1)]);
+	});
+}
+
+#[test]
+fn register_fails_when_not_allowed() {
+	new_test_ext().execute_with(|| {
+		// stash is not a controller.
+		assert_noop!(FastUnstake::register_fast_unstake(Origin::signed(1)), Error::<Test>::NotController);
+
+		// can not register twice.
+		assert_ok!(FastUnstake::register_fast_unstake(Origin::signed(10)));
+		assert_noop!(FastUnstake::register_fast_unstake(Origin::signed(10)), Error::<Test>::AlreadyQueued);
+
+		// not even once the stash is being checked.
+		pallet_staking::CurrentEra::put(3);
+		run_to_block(2);
+		assert_eq!(FastUnstake::head().unwrap().stash, 1);
+		assert_noop!(FastUnstake::register_fast_unstake(Origin::signed(10)), Error::<Test>::AlreadyQueued);
+
+		// funds being unbonded.
+		assert_ok!(
+			pallet_staking::Call::<Test>::unbond(10).dispatch_bypass_filter(Origin::signed(20))
+		);
+		assert_noop!(FastUnstake::register_fast_unstake(Origin::signed(20)), Error::<Test>::NotFullyBonded);
+
+		// not enough free balance for the deposit.
+		assert_ok!(Balances::reserve(&3, 45));
+		assert_noop!(
+			FastUnstake::register_fast_unstake(Origin::signed(30)),
+			Error::<Test>::InsufficientDeposit,
+		);
+	});
+}
+
+#[test]
+fn deregister_works() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(FastUnstake::deregister(Origin::signed(10)), Error::<Test>::NotQueued);
+
+		assert_ok!(FastUnstake::register_fast_unstake(Origin::signed(10)));
+		assert_ok!(FastUnstake::register_fast_unstake(Origin::signed(20)));
+		assert_ok!(FastUnstake::deregister(Origin::signed(10)));
+
+		assert_eq!(FastUnstake::queue(1), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+
+		// the head can not be deregistered.
+		pallet_staking::CurrentEra::put(3);
+		run_to_block(2);
+		assert_eq!(FastUnstake::head().unwrap().stash, 2);
+		assert_noop!(FastUnstake::deregister(Origin::signed(20)), Error::<Test>::AlreadyHead);
+	});
+}
+
+#[test]
+fn unexposed_stash_is_unstaked() {
+	new_test_ext().execute_with(|| {
+		pallet_staking::CurrentEra::put(3);
+		expose(1, 2, Some(3));
+		assert_ok!(FastUnstake::register_fast_unstake(Origin::signed(10)));
+
+		run_to_block(2);
+		assert_eq!(
+			FastUnstake::head(),
+			Some(UnstakeRequest { stash: 1, checked: vec![3, 2], deposit: 10 }),
+		);
+		run_to_block(3);
+		assert_eq!(FastUnstake::head().unwrap().checked, vec![3, 2, 1, 0]);
+		run_to_block(4);
+
+		assert_eq!(FastUnstake::head(), None);
+		assert_eq!(Staking::bonded(1), None);
+		assert_eq!(Staking::ledger(10), None);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert!(pallet_balances::Locks::<Test>::get(1).is_empty());
+		assert_eq!(
+			fast_unstake_events(),
+			vec![
+				RawEvent::Registered(1),
+				RawEvent::Checking(1, vec![3, 2]),
+				RawEvent::Checking(1, vec![1, 0]),
+				RawEvent::Unstaked(1, Ok(())),
+			],
+		);
+	});
+}
+
+#[test]
+fn exposed_nominator_is_slashed() {
+	new_test_ext().execute_with(|| {
+		pallet_staking::CurrentEra::put(3);
+		expose(1, 2, Some(1));
+		assert_ok!(FastUnstake::register_fast_unstake(Origin::signed(10)));
+
+		run_to_block(3);
+
+		assert_eq!(FastUnstake::head(), None);
+		assert_eq!(Staking::bonded(1), Some(10));
+		assert_eq!(Balances::total_balance(&1), 90);
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(
+			fast_unstake_events(),
+			vec![
+				RawEvent::Registered(1),
+				RawEvent::Checking(1, vec![3, 2]),
+				RawEvent::Slashed(1, 10),
+			],
+		);
+	});
+}
+
+#[test]
+fn exposed_validator_is_slashed() {
+	new_test_ext().execute_with(|| {
+		pallet_staking::CurrentEra::put(3);
+		expose(3, 1, None);
+		assert_ok!(FastUnstake::register_fast_unstake(Origin::signed(10)));
+
+		run_to_block(2);
+
+		assert_eq!(FastUnstake::head(), None);
+		assert_eq!(Staking::bonded(1), Some(10));
+		assert_eq!(Balances::total_balance(&1), 90);
+		assert_eq!(
+			fast_unstake_events(),
+			vec![RawEvent::Registered(1), RawEvent::Slashed(1, 10)],
+		);
+	});
+}
+
+#[test]
+fn eras_started_while_checking_are_checked() {
+	new_test_ext().execute_with(|| {
+		pallet_staking::CurrentEra::put(3);
+		assert_ok!(FastUnstake::register_fast_unstake(Origin::signed(10)));
+
+		run_to_block(3);
+		assert_eq!(FastUnstake::head().unwrap().checked, vec![3, 2, 1, 0]);
+
+		// a new era starts in which the stash is exposed.
+		pallet_staking::CurrentEra::put(4);
+		expose(4, 2, Some(1));
+		run_to_block(4);
+
+		assert_eq!(FastUnstake::head(), None);
+		assert_eq!(Staking::bonded(1), Some(10));
+		assert_eq!(fast_unstake_events().last(), Some(&RawEvent::Slashed(1, 10)));
+	});
+}
+
+#[test]
+fn checks_are_limited_by_remaining_block_weight() {
+	new_test_ext().execute_with(|| {
+		pallet_staking::CurrentEra::put(3);
+		for validator in 4..7 {
+			expose(3, validator, Some(validator + 10));
+		}
+		assert_ok!(FastUnstake::register_fast_unstake(Origin::signed(10)));
+
+		// only two of the three exposures of era 3 fit in the block, the third one is not read
+		// even though part of its weight would fit.
+		let exposure_weight = <() as WeightInfo>::on_initialize_check(1)
+			- <() as WeightInfo>::on_initialize_check(0);
+		let consumed = <() as WeightInfo>::on_initialize_check(0) + 2 * exposure_weight;
+		let budget = consumed + exposure_weight / 2;
+		System::set_block_limits(MaximumBlockWeight::get() - budget, 0);
+		System::set_block_number(2);
+		assert_eq!(FastUnstake::on_initialize(2), consumed);
+
+		assert_eq!(
+			FastUnstake::head(),
+			Some(UnstakeRequest { stash: 1, checked: vec![], deposit: 10 }),
+		);
+		assert_eq!(fast_unstake_events(), vec![RawEvent::Registered(1)]);
+
+		// nothing is checked in a full block.
+		System::set_block_limits(MaximumBlockWeight::get(), 0);
+		run_to_block(3);
+		assert!(FastUnstake::head().unwrap().checked.is_empty());
+
+		System::set_block_limits(0, 0);
+		run_to_block(4);
+		assert_eq!(FastUnstake::head().unwrap().checked, vec![3, 2]);
+		assert_eq!(fast_unstake_events().last(), Some(&RawEvent::Checking(1, vec![3, 2])));
+	});
+}
+
+#[test]
+fn queue_is_processed_one_stash_at_a_time() {
+	new_test_ext().execute_with(|| {
+		pallet_staking::CurrentEra::put(1);
+		for (_, controller) in STASHES.iter() {
+			assert_ok!(FastUnstake::register_fast_unstake(Origin::signed(*controller)));
+		}
+
+		// one block to check and one block to unstake each stash.
+		run_to_block(7);
+
+		assert_eq!(FastUnstake::head(), None);
+		for (stash, controller) in STASHES.iter() {
+			assert_eq!(FastUnstake::queue(stash), None);
+			assert_eq!(Staking::ledger(controller), None);
+			assert_eq!(Balances::reserved_balance(stash), 0);
+		}
+	});
+}
+
+#[test]
+fn nothing_happens_without_current_era() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(FastUnstake::register_fast_unstake(Origin::signed(10)));
+		run_to_block(5);
+
+		assert_eq!(
+			FastUnstake::head(),
+			Some(UnstakeRequest { stash: 1, checked: vec![], deposit: 10 }),
+		);
+		assert_eq!(Staking::bonded(1), Some(10));
+	});
+}
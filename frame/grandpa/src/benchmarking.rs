@@ -23,6 +23,7 @@ use super::{*, Module as Grandpa};
 use frame_benchmarking::benchmarks;
 use frame_system::RawOrigin;
 use sp_core::H256;
+use sp_runtime::Percent;
 
 benchmarks! {
 	_ {	}
@@ -72,6 +73,29 @@ benchmarks! {
 	verify {
 		assert!(Grandpa::<T>::stalled().is_some());
 	}
+
+	recover_from_stall {
+		let delay = 1000u32.into();
+		let best_finalized_block_number = 1u32.into();
+
+		frame_system::Module::<T>::set_block_number(10u32.into());
+
+	}: _(RawOrigin::Root, delay, best_finalized_block_number)
+	verify {
+		assert!(Grandpa::<T>::pending_change().is_some());
+	}
+
+	set_voting_rule_parameters {
+		let parameters = VotingRuleParameters {
+			before_best_block_by: Some(2u32.into()),
+			max_unfinalized_blocks_per_round: Some(256u32.into()),
+			min_peer_availability: Some(Percent::from_percent(66)),
+		};
+
+	}: _(RawOrigin::Root, parameters.clone())
+	verify {
+		assert_eq!(Grandpa::<T>::voting_rule_parameters(), parameters);
+	}
 }
 
 #[cfg(test)]
@@ -85,6 +109,8 @@ mod tests {
 		new_test_ext(vec![(1, 1), (2, 1), (3, 1)]).execute_with(|| {
 			assert_ok!(test_benchmark_check_equivocation_proof::<Test>());
 			assert_ok!(test_benchmark_note_stalled::<Test>());
+			assert_ok!(test_benchmark_recover_from_stall::<Test>());
+			assert_ok!(test_benchmark_set_voting_rule_parameters::<Test>());
 		})
 	}
 
@@ -51,4 +51,15 @@ impl crate::WeightInfo for () {
 		(3 * WEIGHT_PER_MICROS)
 			.saturating_add(DbWeight::get().writes(1))
 	}
+
+	fn recover_from_stall() -> Weight {
+		(10 * WEIGHT_PER_MICROS)
+			.saturating_add(DbWeight::get().reads(5))
+			.saturating_add(DbWeight::get().writes(5))
+	}
+
+	fn set_voting_rule_parameters() -> Weight {
+		(3 * WEIGHT_PER_MICROS)
+			.saturating_add(DbWeight::get().writes(1))
+	}
 }
@@ -36,18 +36,18 @@ use sp_std::prelude::*;
 use codec::{self as codec, Decode, Encode};
 pub use fg_primitives::{AuthorityId, AuthorityList, AuthorityWeight, VersionedAuthorityList};
 use fg_primitives::{
-	ConsensusLog, EquivocationProof, ScheduledChange, SetId, GRANDPA_AUTHORITIES_KEY,
-	GRANDPA_ENGINE_ID,
+	ConsensusLog, EquivocationProof, ScheduledChange, SetId, VotingRuleParameters,
+	GRANDPA_AUTHORITIES_KEY, GRANDPA_ENGINE_ID,
 };
 use frame_support::{
 	decl_error, decl_event, decl_module, decl_storage, dispatch::DispatchResultWithPostInfo,
-	storage, traits::KeyOwnerProofSystem, weights::{Pays, Weight}, Parameter,
+	ensure, storage, traits::KeyOwnerProofSystem, weights::{Pays, Weight}, Parameter,
 };
 use frame_system::{ensure_none, ensure_root, ensure_signed};
 use sp_runtime::{
 	generic::DigestItem,
 	traits::Zero,
-	DispatchResult, KeyTypeId,
+	DispatchResult, KeyTypeId, PerThing,
 };
 use sp_session::{GetSessionNumber, GetValidatorCount};
 use sp_staking::SessionIndex;
@@ -105,6 +105,8 @@ pub trait Trait: frame_system::Trait {
 pub trait WeightInfo {
 	fn report_equivocation(validator_count: u32) -> Weight;
 	fn note_stalled() -> Weight;
+	fn recover_from_stall() -> Weight;
+	fn set_voting_rule_parameters() -> Weight;
 }
 
 /// A stored pending change, old format.
@@ -199,12 +201,18 @@ decl_error! {
 		ChangePending,
 		/// Cannot signal forced change so soon after last.
 		TooSoon,
+		/// The best finalized block given to recover from a stall isn't lower
+		/// than the current block.
+		InvalidBestFinalized,
 		/// A key ownership proof provided as part of an equivocation report is invalid.
 		InvalidKeyOwnershipProof,
 		/// An equivocation proof provided as part of an equivocation report is invalid.
 		InvalidEquivocationProof,
 		/// A given equivocation report is valid but already previously reported.
 		DuplicateOffenceReport,
+		/// The given voting rule parameters would prevent any votes from being
+		/// cast (i.e. zero unfinalized blocks per round or a 100% peer availability).
+		InvalidVotingRuleParameters,
 	}
 }
 
@@ -231,6 +239,10 @@ decl_storage! {
 		///
 		/// TWOX-NOTE: `SetId` is not under user control.
 		SetIdSession get(fn session_for_set): map hasher(twox_64_concat) SetId => Option<SessionIndex>;
+
+		/// The parameters of the voting rules GRANDPA voters should apply, exposed to the
+		/// nodes through the `GrandpaVotingRulesApi`.
+		VotingRules get(fn voting_rule_parameters): VotingRuleParameters<T::BlockNumber>;
 	}
 	add_extra_genesis {
 		config(authorities): AuthorityList;
@@ -307,6 +319,46 @@ decl_module! {
 			Self::on_stalled(delay, best_finalized_block_number)
 		}
 
+		/// Recover the GRANDPA finality gadget from a stall by signaling a forced
+		/// authority set change in this block, to the current authorities. The
+		/// change is enacted `delay` blocks after this one, and the GRANDPA voters
+		/// will start the new authority set using the given finalized block as
+		/// base. Unlike `note_stalled` this doesn't wait for the next session.
+		/// The best finalized block number should be taken from the nodes
+		/// (e.g. as reported by the `grandpa_finalityLag` RPC).
+		/// Only callable by root.
+		#[weight = T::WeightInfo::recover_from_stall()]
+		fn recover_from_stall(
+			origin,
+			delay: T::BlockNumber,
+			best_finalized_block_number: T::BlockNumber,
+		) {
+			ensure_root(origin)?;
+
+			Self::schedule_stall_recovery(delay, best_finalized_block_number)?;
+		}
+
+		/// Set the parameters of the voting rules GRANDPA voters should apply on
+		/// top of their local ones. The parameters take effect on the voters once
+		/// the block setting them is imported, no authority set change is needed.
+		/// Parameters that would prevent any votes from being cast are rejected.
+		/// Only callable by root.
+		#[weight = T::WeightInfo::set_voting_rule_parameters()]
+		fn set_voting_rule_parameters(
+			origin,
+			parameters: VotingRuleParameters<T::BlockNumber>,
+		) {
+			ensure_root(origin)?;
+
+			ensure!(
+				parameters.max_unfinalized_blocks_per_round.map_or(true, |n| !n.is_zero()) &&
+					parameters.min_peer_availability.map_or(true, |p| !p.is_one()),
+				Error::<T>::InvalidVotingRuleParameters,
+			);
+
+			<VotingRules<T>>::put(parameters);
+		}
+
 		fn on_finalize(block_number: T::BlockNumber) {
 			// check for scheduled pending authority set changes
 			if let Some(pending_change) = <PendingChange<T>>::get() {
@@ -462,6 +514,44 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Schedule a forced change to the current authorities in order to recover
+	/// from a finality stall, starting a new authority set.
+	///
+	/// The change is signaled in the current block and applied `delay` blocks
+	/// after it, using `best_finalized_block_number` as the median last finalized
+	/// block. Any stall previously noted with `note_stalled` is cleared since a
+	/// forced change is already scheduled.
+	pub fn schedule_stall_recovery(
+		delay: T::BlockNumber,
+		best_finalized_block_number: T::BlockNumber,
+	) -> DispatchResult {
+		if best_finalized_block_number >= <frame_system::Module<T>>::block_number() {
+			Err(Error::<T>::InvalidBestFinalized)?
+		}
+
+		Self::schedule_change(
+			Self::grandpa_authorities(),
+			delay,
+			Some(best_finalized_block_number),
+		)?;
+
+		<Stalled<T>>::kill();
+
+		// the forced change starts a new set, which is still the responsibility
+		// of the validators of the current session.
+		let previous_set_id = Self::current_set_id();
+		let current_set_id = CurrentSetId::mutate(|s| {
+			*s += 1;
+			*s
+		});
+
+		if let Some(session_index) = Self::session_for_set(previous_set_id) {
+			SetIdSession::insert(current_set_id, &session_index);
+		}
+
+		Ok(())
+	}
+
 	/// Deposit one of this module's logs.
 	fn deposit_log(log: ConsensusLog<T::BlockNumber>) {
 		let log: DigestItem<T::Hash> = DigestItem::Consensus(GRANDPA_ENGINE_ID, log.encode());
@@ -32,7 +32,7 @@ use frame_system::{EventRecord, Phase};
 use pallet_session::OneSessionHandler;
 use sp_core::H256;
 use sp_keyring::Ed25519Keyring;
-use sp_runtime::testing::Digest;
+use sp_runtime::{testing::Digest, DispatchError};
 
 #[test]
 fn authorities_change_logged() {
@@ -844,6 +844,117 @@ fn always_schedules_a_change_on_new_session_when_stalled() {
 	});
 }
 
+#[test]
+fn recover_from_stall_schedules_forced_change_to_current_authorities() {
+	new_test_ext(vec![(1, 1), (2, 1), (3, 1)]).execute_with(|| {
+		start_era(1);
+
+		let authorities = Grandpa::grandpa_authorities();
+		let set_id = Grandpa::current_set_id();
+		let session_index = Grandpa::session_for_set(set_id).unwrap();
+
+		// the pending change of the new era must be enacted first
+		assert!(Grandpa::pending_change().is_none());
+
+		// only root can recover from a stall
+		assert_err!(
+			Grandpa::recover_from_stall(Origin::signed(1), 10, 1),
+			DispatchError::BadOrigin,
+		);
+
+		// the best finalized block must be in the past
+		let block_number = System::block_number();
+		assert_err!(
+			Grandpa::recover_from_stall(Origin::root(), 10, block_number),
+			Error::<Test>::InvalidBestFinalized,
+		);
+
+		<Stalled<Test>>::put((30, 1));
+		assert_ok!(Grandpa::recover_from_stall(Origin::root(), 10, 1));
+
+		// a forced change to the current authorities is scheduled right away,
+		// starting a new set in the same session
+		let pending_change = Grandpa::pending_change().unwrap();
+		assert_eq!(pending_change.scheduled_at, block_number);
+		assert_eq!(pending_change.delay, 10);
+		assert_eq!(pending_change.forced, Some(1));
+		assert_eq!(pending_change.next_authorities, authorities);
+
+		assert_eq!(Grandpa::current_set_id(), set_id + 1);
+		assert_eq!(Grandpa::session_for_set(set_id + 1), Some(session_index));
+		assert!(Grandpa::stalled().is_none());
+
+		// the change is signaled in this block
+		Grandpa::on_finalize(block_number);
+		let header = System::finalize();
+		assert!(header.digest.logs.contains(&grandpa_log(ConsensusLog::ForcedChange(
+			1,
+			ScheduledChange { delay: 10, next_authorities: authorities },
+		))));
+
+		// and another recovery can't be scheduled while it is pending
+		initialize_block(block_number + 1, header.hash());
+		assert_err!(
+			Grandpa::recover_from_stall(Origin::root(), 10, 1),
+			Error::<Test>::ChangePending,
+		);
+	});
+}
+
+#[test]
+fn set_voting_rule_parameters_works() {
+	new_test_ext(vec![(1, 1), (2, 1), (3, 1)]).execute_with(|| {
+		// all voting rules are disabled by default
+		assert_eq!(Grandpa::voting_rule_parameters(), Default::default());
+
+		let parameters = fg_primitives::VotingRuleParameters {
+			before_best_block_by: Some(2),
+			max_unfinalized_blocks_per_round: Some(256),
+			min_peer_availability: Some(sp_runtime::Percent::from_percent(66)),
+		};
+
+		// only root can set the parameters
+		assert_err!(
+			Grandpa::set_voting_rule_parameters(Origin::signed(1), parameters.clone()),
+			DispatchError::BadOrigin,
+		);
+
+		assert_ok!(Grandpa::set_voting_rule_parameters(Origin::root(), parameters.clone()));
+		assert_eq!(Grandpa::voting_rule_parameters(), parameters);
+	});
+}
+
+#[test]
+fn set_voting_rule_parameters_rejects_parameters_preventing_votes() {
+	new_test_ext(vec![(1, 1), (2, 1), (3, 1)]).execute_with(|| {
+		// no blocks could be voted on past the round base
+		assert_err!(
+			Grandpa::set_voting_rule_parameters(
+				Origin::root(),
+				fg_primitives::VotingRuleParameters {
+					max_unfinalized_blocks_per_round: Some(0),
+					..Default::default()
+				},
+			),
+			Error::<Test>::InvalidVotingRuleParameters,
+		);
+
+		// a single lagging peer would prevent any votes from being cast
+		assert_err!(
+			Grandpa::set_voting_rule_parameters(
+				Origin::root(),
+				fg_primitives::VotingRuleParameters {
+					min_peer_availability: Some(sp_runtime::Percent::from_percent(100)),
+					..Default::default()
+				},
+			),
+			Error::<Test>::InvalidVotingRuleParameters,
+		);
+
+		assert_eq!(Grandpa::voting_rule_parameters(), Default::default());
+	});
+}
+
 #[test]
 fn report_equivocation_has_valid_weight() {
 	// the weight depends on the size of the validator set,
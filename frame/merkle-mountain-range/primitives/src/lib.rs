@@ -0,0 +1,271 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merkle Mountain Range primitive types.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+use codec::{Encode, Decode};
+use frame_support::RuntimeDebug;
+use sp_runtime::{
+	ConsensusEngineId,
+	generic::{Digest, OpaqueDigestItemId},
+	traits,
+};
+use sp_std::fmt;
+use sp_std::prelude::*;
+
+/// The engine id of digest items carrying the MMR root.
+///
+/// The MMR pallet deposits a `Consensus` digest item with this id and the SCALE-encoded root
+/// hash in every block it appends a leaf in.
+pub const MMR_ENGINE_ID: ConsensusEngineId = *b"MMRR";
+
+/// Find the MMR root deposited in the given header digest, if any.
+pub fn find_mmr_root<Hash: Decode, DigestHash>(digest: &Digest<DigestHash>) -> Option<Hash> {
+	let id = OpaqueDigestItemId::Consensus(&MMR_ENGINE_ID);
+	digest.convert_first(|log| log.try_to(id))
+}
+
+/// A provider of the MMR's leaf data.
+pub trait LeafDataProvider {
+	/// A type that should end up in the leaf of MMR.
+	type LeafData: FullLeaf;
+
+	/// The method to return leaf data that should be placed
+	/// in the leaf node appended MMR at this block.
+	///
+	/// This is being called by the `on_initialize` method of
+	/// this pallet at the very beginning of each block.
+	fn leaf_data() -> Self::LeafData;
+}
+
+impl LeafDataProvider for () {
+	type LeafData = ();
+
+	fn leaf_data() -> Self::LeafData {}
+}
+
+/// The most common use case for MMRs is to store historical block hashes,
+/// so that any point in time in the future we can receive a proof about some past
+/// blocks without using excessive on-chain storage.
+///
+/// Hence we implement the [LeafDataProvider] for [frame_system::Module], since the
+/// current block hash is not available (since the block is not finished yet),
+/// we use the `parent_hash` here.
+impl<T: frame_system::Trait> LeafDataProvider for frame_system::Module<T> {
+	type LeafData = <T as frame_system::Trait>::Hash;
+
+	fn leaf_data() -> Self::LeafData {
+		Self::parent_hash()
+	}
+}
+
+impl<A: LeafDataProvider, B: LeafDataProvider> LeafDataProvider for (A, B) {
+	type LeafData = (A::LeafData, B::LeafData);
+
+	fn leaf_data() -> Self::LeafData {
+		(A::leaf_data(), B::leaf_data())
+	}
+}
+
+/// New MMR root notification hook.
+pub trait OnNewRoot<Hash> {
+	/// Function called by the pallet in case new MMR root has been computed.
+	fn on_new_root(root: &Hash);
+}
+
+/// No-op implementation of [OnNewRoot].
+impl<Hash> OnNewRoot<Hash> for () {
+	fn on_new_root(_root: &Hash) {}
+}
+
+/// A full leaf content stored in the offchain-db.
+pub trait FullLeaf: Clone + PartialEq + fmt::Debug + Encode + Decode {}
+
+impl<T: Clone + PartialEq + fmt::Debug + Encode + Decode> FullLeaf for T {}
+
+/// An element representing either full data or it's hash.
+///
+/// This is used internally by the MMR to differentiate leaf nodes (data) and inner nodes
+/// (hashes). Leaf nodes are stored in full in the offchain database, so that proofs can be
+/// generated together with the leaf they are proving.
+#[derive(RuntimeDebug, Clone, PartialEq, Encode, Decode)]
+pub enum DataOrHash<H: traits::Hash, L> {
+	/// Arbitrary data in it's full form.
+	Data(L),
+	/// A hash of some data.
+	Hash(H::Output),
+}
+
+impl<H: traits::Hash, L> From<L> for DataOrHash<H, L> {
+	fn from(l: L) -> Self {
+		Self::Data(l)
+	}
+}
+
+impl<H: traits::Hash, L: FullLeaf> DataOrHash<H, L> {
+	/// Retrieve a hash of this item.
+	///
+	/// Depending on the node type it's going to either be a contained value for [DataOrHash::Hash]
+	/// node, or a hash of SCALE-encoded [DataOrHash::Data] data.
+	pub fn hash(&self) -> H::Output {
+		match *self {
+			Self::Data(ref leaf) => <H as traits::Hash>::hash_of(leaf),
+			Self::Hash(ref hash) => *hash,
+		}
+	}
+}
+
+/// A MMR proof data for one of the leaves.
+#[derive(codec::Encode, codec::Decode, RuntimeDebug, Clone, PartialEq, Eq)]
+pub struct Proof<Hash> {
+	/// The index of the leaf the proof is for.
+	pub leaf_index: u64,
+	/// Number of leaves in MMR, when the proof was generated.
+	pub leaf_count: u64,
+	/// Proof elements (hashes of siblings of inner nodes on the path to the leaf).
+	pub items: Vec<Hash>,
+}
+
+/// Merkle Mountain Range operation error.
+#[derive(RuntimeDebug, codec::Encode, codec::Decode, PartialEq, Eq)]
+pub enum Error {
+	/// Error while pushing new node.
+	Push,
+	/// Error getting the new root.
+	GetRoot,
+	/// Error commiting changes.
+	Commit,
+	/// Error during proof generation.
+	GenerateProof,
+	/// Proof verification error.
+	Verify,
+	/// Leaf not found in the storage.
+	LeafNotFound,
+}
+
+impl Error {
+	#![allow(unused_variables)]
+	/// Consume given error `e` with `self` and generate a native log entry with error details.
+	pub fn log_error(self, e: impl fmt::Debug) -> Self {
+		frame_support::debug::native::error!("[{:?}] MMR error: {:?}", self, e);
+		self
+	}
+
+	/// Consume given error `e` with `self` and generate a native log entry with error details.
+	pub fn log_debug(self, e: impl fmt::Debug) -> Self {
+		frame_support::debug::native::debug!("[{:?}] MMR error: {:?}", self, e);
+		self
+	}
+}
+
+/// A SCALE-encoded leaf.
+///
+/// The runtime API and the RPC deal with leaves in their encoded form, since the actual leaf
+/// type is only known to the runtime.
+#[derive(RuntimeDebug, PartialEq, Eq, Clone, Encode, Decode)]
+pub struct OpaqueLeaf(pub Vec<u8>);
+
+impl OpaqueLeaf {
+	/// Convert a concrete MMR leaf into an opaque type.
+	pub fn from_leaf<T: FullLeaf>(leaf: T) -> Self {
+		OpaqueLeaf(leaf.encode())
+	}
+
+	/// Try to decode the opaque leaf into a concrete MMR leaf.
+	pub fn into_leaf<T: FullLeaf>(self) -> Option<T> {
+		T::decode(&mut &*self.0).ok()
+	}
+}
+
+sp_api::decl_runtime_apis! {
+	/// API to interact with MMR pallet.
+	pub trait MmrApi<Hash: codec::Codec> {
+		/// Return the current MMR root hash and the number of leaves.
+		fn mmr_root() -> (Hash, u64);
+
+		/// Generate MMR proof for a leaf under given index.
+		fn generate_proof(leaf_index: u64) -> Result<(OpaqueLeaf, Proof<Hash>), Error>;
+
+		/// Verify MMR proof against on-chain MMR.
+		///
+		/// Note this function will use on-chain MMR root hash and check if the proof
+		/// matches the hash.
+		/// See [Self::verify_proof_stateless] for a stateless verifier.
+		fn verify_proof(leaf: OpaqueLeaf, proof: Proof<Hash>) -> Result<(), Error>;
+
+		/// Verify MMR proof against given root hash.
+		///
+		/// Note this function does not require any on-chain storage - the
+		/// proof is verified against given MMR root hash.
+		fn verify_proof_stateless(root: Hash, leaf: OpaqueLeaf, proof: Proof<Hash>)
+			-> Result<(), Error>;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use sp_core::H256;
+	use sp_runtime::{DigestItem, traits::{Hash, Keccak256}};
+
+	#[test]
+	fn should_find_mmr_root_in_digest() {
+		// given
+		let root = H256::repeat_byte(1);
+		let mut digest = Digest::<H256>::default();
+		digest.push(DigestItem::Other(vec![1, 2, 3]));
+		digest.push(DigestItem::Consensus(*b"FRNK", vec![1, 2, 3]));
+
+		// then
+		assert_eq!(find_mmr_root::<H256, _>(&digest), None);
+
+		// when
+		digest.push(DigestItem::Consensus(MMR_ENGINE_ID, root.encode()));
+
+		// then
+		assert_eq!(find_mmr_root::<H256, _>(&digest), Some(root));
+	}
+
+	#[test]
+	fn should_hash_data_and_pass_hashes_through() {
+		// given
+		type Node = DataOrHash<Keccak256, (u64, u32)>;
+		let data = Node::Data((5, 7));
+		let hash = Node::Hash(H256::repeat_byte(5));
+
+		// then
+		assert_eq!(data.hash(), Keccak256::hash_of(&(5u64, 7u32)));
+		assert_eq!(hash.hash(), H256::repeat_byte(5));
+	}
+
+	#[test]
+	fn opaque_leaf_should_roundtrip() {
+		// given
+		let leaf = (H256::repeat_byte(2), 5u64);
+
+		// when
+		let opaque = OpaqueLeaf::from_leaf(leaf);
+
+		// then
+		assert_eq!(opaque.clone().into_leaf::<(H256, u64)>(), Some(leaf));
+		assert_eq!(opaque.into_leaf::<(H256, u64, u8)>(), None);
+	}
+}
@@ -0,0 +1,394 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![warn(missing_docs)]
+
+//! Node-specific RPC methods for interaction with Merkle Mountain Range pallet.
+//!
+//! MMR nodes are only available in the Off-chain DB, so the node needs to be started with
+//! `--enable-offchain-indexing` for proof generation to work.
+
+use std::sync::Arc;
+
+use codec::{Codec, Encode, Decode};
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	Bytes, ExecutionContext, OpaquePeerId,
+	offchain::{
+		self, Capability, Capabilities, HttpError, HttpRequestId, HttpRequestStatus,
+		OffchainStorage, OpaqueNetworkState, StorageKind, Timestamp,
+	},
+};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT},
+};
+
+use pallet_mmr_primitives::{Error as MmrError, OpaqueLeaf, Proof};
+
+pub use pallet_mmr_primitives::MmrApi as MmrRuntimeApi;
+pub use self::gen_client::Client as MmrClient;
+
+/// Retrieved MMR leaf and its proof.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeafProof<BlockHash> {
+	/// Block hash the proof was generated for.
+	pub block_hash: BlockHash,
+	/// SCALE-encoded leaf data.
+	pub leaf: Bytes,
+	/// SCALE-encoded proof data. See [pallet_mmr_primitives::Proof].
+	pub proof: Bytes,
+}
+
+impl<BlockHash> LeafProof<BlockHash> {
+	/// Create new `LeafProof` from given concrete `leaf` and `proof`.
+	pub fn new<MmrHash: Encode>(
+		block_hash: BlockHash,
+		leaf: OpaqueLeaf,
+		proof: Proof<MmrHash>,
+	) -> Self {
+		Self {
+			block_hash,
+			leaf: Bytes(leaf.0),
+			proof: Bytes(proof.encode()),
+		}
+	}
+}
+
+/// MMR RPC methods.
+#[rpc]
+pub trait MmrApi<BlockHash, MmrHash> {
+	/// Generate MMR proof for given leaf index.
+	///
+	/// This method calls into a runtime with MMR pallet included and attempts to generate
+	/// MMR proof for leaf at given `leaf_index`.
+	/// Optionally, a block hash at which the runtime should be queried can be specified.
+	///
+	/// Returns the (full) leaf itself and a proof for this leaf (compact encoding, i.e. hash of
+	/// the leaf). Both parameters are SCALE-encoded.
+	#[rpc(name = "mmr_generateProof")]
+	fn generate_proof(
+		&self,
+		leaf_index: u64,
+		at: Option<BlockHash>,
+	) -> Result<LeafProof<BlockHash>>;
+
+	/// Verify MMR proof against the MMR root stored on-chain at `LeafProof::block_hash`.
+	///
+	/// Returns `true` if the proof is valid.
+	#[rpc(name = "mmr_verifyProof")]
+	fn verify_proof(&self, proof: LeafProof<BlockHash>) -> Result<bool>;
+
+	/// Return the MMR root hash and the number of leaves at given block.
+	#[rpc(name = "mmr_root")]
+	fn mmr_root(&self, at: Option<BlockHash>) -> Result<(MmrHash, u64)>;
+}
+
+/// An implementation of MMR specific RPC methods.
+pub struct Mmr<C, S, B> {
+	client: Arc<C>,
+	offchain_db: S,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, S, B> Mmr<C, S, B> {
+	/// Create new `Mmr` with the given reference to the client and the Off-chain DB holding the
+	/// MMR nodes.
+	pub fn new(client: Arc<C>, offchain_db: S) -> Self {
+		Self {
+			client,
+			offchain_db,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, S, Block, MmrHash> MmrApi<<Block as BlockT>::Hash, MmrHash> for Mmr<C, S, (Block, MmrHash)>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: MmrRuntimeApi<Block, MmrHash>,
+	S: OffchainStorage + 'static,
+	MmrHash: Codec + Send + Sync + 'static,
+{
+	fn generate_proof(
+		&self,
+		leaf_index: u64,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<LeafProof<<Block as BlockT>::Hash>> {
+		let api = self.client.runtime_api();
+		let block_hash = at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		);
+
+		let context = ExecutionContext::OffchainCall(Some((
+			Box::new(OffchainDb::new(self.offchain_db.clone(), block_hash.as_ref().to_vec())),
+			Capabilities::from(&[Capability::OffchainWorkerDbRead][..]),
+		)));
+		let (leaf, proof) = api
+			.generate_proof_with_context(
+				&BlockId::hash(block_hash),
+				context,
+				leaf_index,
+			)
+			.map_err(runtime_error_into_rpc_error)?
+			.map_err(mmr_error_into_rpc_error)?;
+
+		Ok(LeafProof::new(block_hash, leaf, proof))
+	}
+
+	fn verify_proof(&self, proof: LeafProof<<Block as BlockT>::Hash>) -> Result<bool> {
+		let api = self.client.runtime_api();
+		let leaf = OpaqueLeaf(proof.leaf.0);
+		let decoded_proof = Proof::<MmrHash>::decode(&mut &*proof.proof).map_err(|e| RpcError {
+			code: ErrorCode::InvalidParams,
+			message: "Unable to decode the proof.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+
+		let result = api
+			.verify_proof(&BlockId::hash(proof.block_hash), leaf, decoded_proof)
+			.map_err(runtime_error_into_rpc_error)?;
+
+		match result {
+			Ok(()) => Ok(true),
+			Err(MmrError::Verify) => Ok(false),
+			Err(e) => Err(mmr_error_into_rpc_error(e)),
+		}
+	}
+
+	fn mmr_root(&self, at: Option<<Block as BlockT>::Hash>) -> Result<(MmrHash, u64)> {
+		let api = self.client.runtime_api();
+		let block_hash = at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		);
+
+		api.mmr_root(&BlockId::hash(block_hash)).map_err(runtime_error_into_rpc_error)
+	}
+}
+
+const RUNTIME_ERROR: i64 = 8000;
+const MMR_ERROR: i64 = 8010;
+
+/// Converts a mmr-specific error into an RPC error.
+fn mmr_error_into_rpc_error(err: MmrError) -> RpcError {
+	match err {
+		MmrError::LeafNotFound => RpcError {
+			code: ErrorCode::ServerError(MMR_ERROR + 1),
+			message: "Leaf was not found".into(),
+			data: Some(format!("{:?}", err).into()),
+		},
+		MmrError::GenerateProof => RpcError {
+			code: ErrorCode::ServerError(MMR_ERROR + 2),
+			message: "Error while generating the proof".into(),
+			data: Some(format!("{:?}", err).into()),
+		},
+		_ => RpcError {
+			code: ErrorCode::ServerError(MMR_ERROR),
+			message: "Unexpected MMR error".into(),
+			data: Some(format!("{:?}", err).into()),
+		},
+	}
+}
+
+/// Converts a runtime trap into an RPC error.
+fn runtime_error_into_rpc_error(err: impl std::fmt::Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Runtime trapped".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
+
+/// Read-only access to the Off-chain DB for the runtime calls made by this RPC.
+///
+/// MMR proof generation only needs to read the nodes stored via the Indexing API, so the rest
+/// of the off-chain worker functionality is not available (and is never allowed by the
+/// capabilities the calls are made with). Fork-aware reads are made at the block the proof
+/// is generated at.
+struct OffchainDb<S> {
+	storage: S,
+	at: Vec<u8>,
+}
+
+impl<S> OffchainDb<S> {
+	fn new(storage: S, at: Vec<u8>) -> Self {
+		Self { storage, at }
+	}
+}
+
+fn unavailable_yet<R>(name: &str) -> R {
+	panic!("`{}` is not available in MMR RPC calls, only Off-chain DB reads are.", name)
+}
+
+impl<S: OffchainStorage> offchain::Externalities for OffchainDb<S> {
+	fn is_validator(&self) -> bool {
+		unavailable_yet("is_validator")
+	}
+
+	fn network_state(&self) -> std::result::Result<OpaqueNetworkState, ()> {
+		unavailable_yet("network_state")
+	}
+
+	fn timestamp(&mut self) -> Timestamp {
+		unavailable_yet("timestamp")
+	}
+
+	fn sleep_until(&mut self, _deadline: Timestamp) {
+		unavailable_yet("sleep_until")
+	}
+
+	fn random_seed(&mut self) -> [u8; 32] {
+		unavailable_yet("random_seed")
+	}
+
+	fn local_storage_set(&mut self, _kind: StorageKind, _key: &[u8], _value: &[u8]) {
+		unavailable_yet("local_storage_set")
+	}
+
+	fn local_storage_clear(&mut self, _kind: StorageKind, _key: &[u8]) {
+		unavailable_yet("local_storage_clear")
+	}
+
+	fn local_storage_compare_and_set(
+		&mut self,
+		_kind: StorageKind,
+		_key: &[u8],
+		_old_value: Option<&[u8]>,
+		_new_value: &[u8],
+	) -> bool {
+		unavailable_yet("local_storage_compare_and_set")
+	}
+
+	fn local_storage_get(&mut self, kind: StorageKind, key: &[u8]) -> Option<Vec<u8>> {
+		match kind {
+			StorageKind::PERSISTENT => self.storage.get(offchain::STORAGE_PREFIX, key),
+			StorageKind::LOCAL => self.storage.get_at(&self.at, offchain::STORAGE_PREFIX, key),
+		}
+	}
+
+	fn http_request_start(
+		&mut self,
+		_method: &str,
+		_uri: &str,
+		_meta: &[u8],
+	) -> std::result::Result<HttpRequestId, ()> {
+		unavailable_yet("http_request_start")
+	}
+
+	fn http_request_add_header(
+		&mut self,
+		_request_id: HttpRequestId,
+		_name: &str,
+		_value: &str,
+	) -> std::result::Result<(), ()> {
+		unavailable_yet("http_request_add_header")
+	}
+
+	fn http_request_write_body(
+		&mut self,
+		_request_id: HttpRequestId,
+		_chunk: &[u8],
+		_deadline: Option<Timestamp>,
+	) -> std::result::Result<(), HttpError> {
+		unavailable_yet("http_request_write_body")
+	}
+
+	fn http_response_wait(
+		&mut self,
+		_ids: &[HttpRequestId],
+		_deadline: Option<Timestamp>,
+	) -> Vec<HttpRequestStatus> {
+		unavailable_yet("http_response_wait")
+	}
+
+	fn http_response_headers(&mut self, _request_id: HttpRequestId) -> Vec<(Vec<u8>, Vec<u8>)> {
+		unavailable_yet("http_response_headers")
+	}
+
+	fn http_response_read_body(
+		&mut self,
+		_request_id: HttpRequestId,
+		_buffer: &mut [u8],
+		_deadline: Option<Timestamp>,
+	) -> std::result::Result<usize, HttpError> {
+		unavailable_yet("http_response_read_body")
+	}
+
+	fn set_authorized_nodes(&mut self, _nodes: Vec<OpaquePeerId>, _authorized_only: bool) {
+		unavailable_yet("set_authorized_nodes")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+
+	#[test]
+	fn should_serialize_leaf_proof() {
+		// given
+		let leaf = OpaqueLeaf(vec![1_u8, 2, 3, 4]);
+		let proof = Proof {
+			leaf_index: 1,
+			leaf_count: 9,
+			items: vec![H256::repeat_byte(1), H256::repeat_byte(2)],
+		};
+
+		let leaf_proof = LeafProof::new(H256::repeat_byte(0), leaf, proof);
+
+		// when
+		let actual = serde_json::to_string(&leaf_proof).unwrap();
+
+		// then
+		assert_eq!(
+			actual,
+			r#"{"blockHash":"0x0000000000000000000000000000000000000000000000000000000000000000","leaf":"0x01020304","proof":"0x010000000000000009000000000000000801010101010101010101010101010101010101010101010101010101010101010202020202020202020202020202020202020202020202020202020202020202"}"#
+		);
+	}
+
+	#[test]
+	fn should_deserialize_leaf_proof() {
+		// given
+		let expected = LeafProof {
+			block_hash: H256::repeat_byte(0),
+			leaf: Bytes(vec![1_u8, 2, 3, 4]),
+			proof: Bytes(Proof {
+				leaf_index: 1,
+				leaf_count: 9,
+				items: vec![H256::repeat_byte(1), H256::repeat_byte(2)],
+			}.encode()),
+		};
+
+		// when
+		let actual: LeafProof<H256> = serde_json::from_str(r#"{
+			"blockHash":"0x0000000000000000000000000000000000000000000000000000000000000000",
+			"leaf":"0x01020304",
+			"proof":"0x010000000000000009000000000000000801010101010101010101010101010101010101010101010101010101010101010202020202020202020202020202020202020202020202020202020202020202"
+		}"#).unwrap();
+
+		// then
+		assert_eq!(actual, expected);
+	}
+}
@@ -0,0 +1,45 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod mmr;
+pub mod storage;
+pub mod utils;
+
+use crate::primitives::FullLeaf;
+use sp_runtime::traits;
+
+pub use self::mmr::{Mmr, verify_leaf_proof};
+
+/// Node type for runtime `T`.
+pub type NodeOf<T, L> = Node<<T as crate::Trait>::Hashing, L>;
+
+/// A node stored in the MMR.
+pub type Node<H, L> = crate::primitives::DataOrHash<H, L>;
+
+/// Default Merging & Hashing behavior for MMR.
+pub struct Hasher<H, L>(sp_std::marker::PhantomData<(H, L)>);
+
+impl<H: traits::Hash, L: FullLeaf> mmr_lib::Merge for Hasher<H, L> {
+	type Item = Node<H, L>;
+
+	fn merge(left: &Self::Item, right: &Self::Item) -> Self::Item {
+		let mut concat = left.hash().as_ref().to_vec();
+		concat.extend_from_slice(right.hash().as_ref());
+
+		Node::Hash(<H as traits::Hash>::hash(&concat))
+	}
+}
@@ -0,0 +1,135 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A MMR storage implementations.
+
+use codec::Encode;
+use frame_support::StorageMap;
+use mmr_lib::helper;
+use sp_std::iter::Peekable;
+#[cfg(not(feature = "std"))]
+use sp_std::prelude::Vec;
+
+use crate::mmr::{NodeOf, Node};
+use crate::primitives;
+use crate::{Module, Trait};
+
+/// A marker type for runtime-specific storage implementation.
+///
+/// Allows appending new items to the MMR and proof verification.
+/// MMR nodes are appended to two different storages:
+/// 1. We add nodes (leaves) hashes to the on-chain storge (see [crate::Nodes]).
+/// 2. We add full leaves (and all inner nodes as well) into the `IndexingAPI` during block
+///    processing, so the values end up in the Offchain DB if indexing is enabled.
+///
+/// Only the current peaks are kept in the on-chain storage, every other node is pruned as soon
+/// as it stops being a peak.
+pub struct RuntimeStorage;
+
+/// A marker type for offchain-specific storage implementation.
+///
+/// Allows proof generation and verification, but does not support appending new items.
+/// MMR nodes are assumed to be stored in the Off-Chain DB. Note this storage type
+/// DOES NOT support adding new items to the MMR.
+pub struct OffchainStorage;
+
+/// A storage layer for MMR.
+///
+/// There are two different implementations depending on the use case.
+/// See docs for [RuntimeStorage] and [OffchainStorage].
+pub struct Storage<StorageType, T, L>(
+	sp_std::marker::PhantomData<(StorageType, T, L)>
+);
+
+impl<StorageType, T, L> Default for Storage<StorageType, T, L> {
+	fn default() -> Self {
+		Self(Default::default())
+	}
+}
+
+impl<T, L> mmr_lib::MMRStore<NodeOf<T, L>> for Storage<OffchainStorage, T, L> where
+	T: Trait,
+	L: primitives::FullLeaf,
+{
+	fn get_elem(&self, pos: u64) -> mmr_lib::Result<Option<NodeOf<T, L>>> {
+		let key = Module::<T>::offchain_key(pos);
+		// Retrieve the element from Off-chain DB. Indexed nodes are fork-aware, so we only
+		// see the ones added on the current fork.
+		Ok(sp_io::offchain
+			::local_storage_get(sp_core::offchain::StorageKind::LOCAL, &key)
+			.and_then(|v| codec::Decode::decode(&mut &*v).ok()))
+	}
+
+	fn append(&mut self, _: u64, _: Vec<NodeOf<T, L>>) -> mmr_lib::Result<()> {
+		panic!("MMR must not be altered in the off-chain context.")
+	}
+}
+
+impl<T, L> mmr_lib::MMRStore<NodeOf<T, L>> for Storage<RuntimeStorage, T, L> where
+	T: Trait,
+	L: primitives::FullLeaf,
+{
+	fn get_elem(&self, pos: u64) -> mmr_lib::Result<Option<NodeOf<T, L>>> {
+		Ok(<crate::Nodes<T>>::get(pos).map(Node::Hash))
+	}
+
+	fn append(&mut self, pos: u64, elems: Vec<NodeOf<T, L>>) -> mmr_lib::Result<()> {
+		let size_before = pos;
+		let size_after = pos + elems.len() as u64;
+		let peaks_before = peaks(size_before);
+		let peaks_after = peaks(size_after);
+
+		let mut new_peaks = peaks_after.iter().copied().peekable();
+		for (pos, elem) in (size_before..size_after).zip(elems.into_iter()) {
+			// Indexing API is used to store the full node content (both leaf and inner nodes).
+			// The nodes at given positions differ between forks, so they are fork-aware.
+			elem.using_encoded(|elem| {
+				sp_io::offchain_index::set_local(&Module::<T>::offchain_key(pos), elem)
+			});
+			// On-chain we only store the hashes of the peaks (even if the peak is a leaf).
+			if is_next(&mut new_peaks, pos) {
+				<crate::Nodes<T>>::insert(pos, elem.hash());
+			}
+		}
+
+		// Prune the nodes that are not peaks anymore.
+		let mut peaks_after = peaks_after.into_iter().peekable();
+		for pos in peaks_before {
+			if !is_next(&mut peaks_after, pos) {
+				<crate::Nodes<T>>::remove(pos);
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Return positions of the peaks of an MMR of given size.
+fn peaks(size: u64) -> Vec<u64> {
+	if size == 0 {
+		return Vec::new()
+	}
+	helper::get_peaks(size)
+}
+
+/// Advance the sorted `iter` past every position lower than `pos` and check if `pos` is next.
+fn is_next<I: Iterator<Item = u64>>(iter: &mut Peekable<I>, pos: u64) -> bool {
+	while iter.peek().map_or(false, |p| *p < pos) {
+		iter.next();
+	}
+	iter.peek() == Some(&pos)
+}
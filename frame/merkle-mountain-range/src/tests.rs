@@ -0,0 +1,303 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::*;
+use crate::mock::*;
+
+use frame_support::traits::OnInitialize;
+use pallet_mmr_primitives::{Proof, find_mmr_root};
+use sp_core::{
+	H256,
+	offchain::{
+		testing::TestOffchainExt,
+		OffchainExt,
+	},
+};
+use sp_runtime::traits::{Hash, Keccak256};
+
+pub(crate) fn new_test_ext() -> sp_io::TestExternalities {
+	frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+}
+
+fn register_offchain_ext(ext: &mut sp_io::TestExternalities) {
+	let (offchain, _offchain_state) = TestOffchainExt::with_offchain_db(ext.offchain_db());
+	ext.register_extension(OffchainExt::new(offchain));
+}
+
+fn new_block() -> u64 {
+	let number = frame_system::Module::<Test>::block_number() + 1;
+	let hash = H256::repeat_byte(number as u8);
+	LEAF_DATA.with(|r| r.borrow_mut().a = number);
+
+	frame_system::Module::<Test>::initialize(
+		&number,
+		&hash,
+		&Default::default(),
+		&Default::default(),
+		frame_system::InitKind::Full,
+	);
+	MMR::on_initialize(number)
+}
+
+fn peaks() -> Vec<u64> {
+	let mut peaks = Nodes::<Test>::iter().map(|(pos, _)| pos).collect::<Vec<_>>();
+	peaks.sort();
+	peaks
+}
+
+fn leaf(number: u64) -> (H256, LeafData) {
+	(H256::repeat_byte(number as u8), LeafData::new(number))
+}
+
+fn merge(left: H256, right: H256) -> H256 {
+	let mut concat = left.as_ref().to_vec();
+	concat.extend_from_slice(right.as_ref());
+	Keccak256::hash(&concat)
+}
+
+fn offchain_node(ext: &sp_io::TestExternalities, pos: u64) -> Option<mmr::NodeOf<Test, (H256, LeafData)>> {
+	use sp_core::offchain::OffchainStorage;
+	ext.offchain_db()
+		.get_at(&[], b"", &MMR::offchain_key(pos))
+		.map(|v| codec::Decode::decode(&mut &*v).unwrap())
+}
+
+#[test]
+fn should_start_empty() {
+	let _ = env_logger::try_init();
+	new_test_ext().execute_with(|| {
+		// given
+		assert_eq!(
+			crate::RootHash::<Test>::get(),
+			"0000000000000000000000000000000000000000000000000000000000000000".parse().unwrap()
+		);
+		assert_eq!(crate::NumberOfLeaves::get(), 0);
+		assert_eq!(peaks(), Vec::<u64>::new());
+
+		// when
+		let weight = new_block();
+
+		// then
+		let leaf_hash = Keccak256::hash_of(&leaf(1));
+		assert_eq!(crate::NumberOfLeaves::get(), 1);
+		assert_eq!(peaks(), vec![0]);
+		assert_eq!(crate::Nodes::<Test>::get(0), Some(leaf_hash));
+		assert_eq!(crate::RootHash::<Test>::get(), leaf_hash);
+		assert!(weight != 0);
+	});
+}
+
+#[test]
+fn should_append_to_mmr_when_on_initialize_is_called() {
+	let _ = env_logger::try_init();
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		// when
+		new_block();
+		new_block();
+
+		// then
+		let root = merge(Keccak256::hash_of(&leaf(1)), Keccak256::hash_of(&leaf(2)));
+		assert_eq!(crate::NumberOfLeaves::get(), 2);
+		assert_eq!(peaks(), vec![2]);
+		assert_eq!(crate::Nodes::<Test>::get(2), Some(root));
+		assert_eq!(crate::RootHash::<Test>::get(), root);
+	});
+
+	// make sure the full nodes end up in the offchain db
+	ext.persist_offchain_overlay();
+	assert_eq!(offchain_node(&ext, 0), Some(mmr::Node::Data(leaf(1))));
+	assert_eq!(offchain_node(&ext, 1), Some(mmr::Node::Data(leaf(2))));
+	assert_eq!(offchain_node(&ext, 2), Some(mmr::Node::Hash(merge(
+		Keccak256::hash_of(&leaf(1)),
+		Keccak256::hash_of(&leaf(2)),
+	))));
+	assert_eq!(offchain_node(&ext, 3), None);
+}
+
+#[test]
+fn should_only_keep_peaks_in_storage() {
+	let _ = env_logger::try_init();
+	new_test_ext().execute_with(|| {
+		// when
+		for _ in 0..7 {
+			new_block();
+		}
+
+		// then
+		assert_eq!(crate::NumberOfLeaves::get(), 7);
+		assert_eq!(peaks(), vec![6, 9, 10]);
+
+		// when
+		new_block();
+
+		// then
+		assert_eq!(crate::NumberOfLeaves::get(), 8);
+		assert_eq!(peaks(), vec![14]);
+	});
+}
+
+#[test]
+fn should_deposit_root_in_header_digest() {
+	let _ = env_logger::try_init();
+	new_test_ext().execute_with(|| {
+		// when
+		new_block();
+		new_block();
+
+		// then
+		let digest = frame_system::Module::<Test>::digest();
+		assert_eq!(find_mmr_root::<H256, _>(&digest), Some(crate::RootHash::<Test>::get()));
+	});
+}
+
+#[test]
+fn should_generate_proofs_correctly() {
+	let _ = env_logger::try_init();
+	let mut ext = new_test_ext();
+	// given
+	ext.execute_with(|| {
+		for _ in 0..7 {
+			new_block();
+		}
+	});
+	ext.persist_offchain_overlay();
+
+	// Try to generate proofs now. This requires the offchain extensions to be present
+	// to retrieve full leaf data.
+	register_offchain_ext(&mut ext);
+	ext.execute_with(|| {
+		// when generate proofs for all leaves
+		let proofs = (0_u64..crate::NumberOfLeaves::get())
+			.into_iter()
+			.map(|leaf_index| crate::Module::<Test>::generate_proof(leaf_index).unwrap())
+			.collect::<Vec<_>>();
+
+		// then
+		assert_eq!(proofs[0], (leaf(1), Proof {
+			leaf_index: 0,
+			leaf_count: 7,
+			items: vec![
+				Keccak256::hash_of(&leaf(2)),
+				merge(Keccak256::hash_of(&leaf(3)), Keccak256::hash_of(&leaf(4))),
+				// the peaks to the right are bagged right-to-left
+				merge(
+					Keccak256::hash_of(&leaf(7)),
+					merge(Keccak256::hash_of(&leaf(5)), Keccak256::hash_of(&leaf(6))),
+				),
+			],
+		}));
+		assert_eq!(proofs[6].0, leaf(7));
+		assert_eq!(proofs[6].1.items.len(), 2);
+
+		// and the proofs can be verified against the current root
+		for (leaf, proof) in proofs {
+			assert_eq!(crate::Module::<Test>::verify_leaf(leaf, proof), Ok(()));
+		}
+	});
+}
+
+#[test]
+fn should_fail_to_generate_proof_for_unknown_leaf() {
+	let _ = env_logger::try_init();
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		for _ in 0..3 {
+			new_block();
+		}
+	});
+	ext.persist_offchain_overlay();
+
+	register_offchain_ext(&mut ext);
+	ext.execute_with(|| {
+		assert_eq!(
+			crate::Module::<Test>::generate_proof(3),
+			Err(primitives::Error::LeafNotFound),
+		);
+	});
+}
+
+#[test]
+fn verification_should_be_stateless() {
+	let _ = env_logger::try_init();
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		for _ in 0..7 {
+			new_block();
+		}
+	});
+	ext.persist_offchain_overlay();
+
+	register_offchain_ext(&mut ext);
+	ext.execute_with(|| {
+		// given
+		let (leaf, proof) = crate::Module::<Test>::generate_proof(5).unwrap();
+		let root = crate::RootHash::<Test>::get();
+
+		// when the MMR grows
+		new_block();
+
+		// then the proof no longer matches on-chain root, but can be checked against the old one
+		assert_eq!(
+			crate::Module::<Test>::verify_leaf(leaf.clone(), proof.clone()),
+			Err(primitives::Error::Verify),
+		);
+		assert_eq!(crate::Module::<Test>::verify_leaf_stateless(root, leaf, proof), Ok(()));
+	});
+}
+
+#[test]
+fn should_reject_invalid_proofs() {
+	let _ = env_logger::try_init();
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		for _ in 0..7 {
+			new_block();
+		}
+	});
+	ext.persist_offchain_overlay();
+
+	register_offchain_ext(&mut ext);
+	ext.execute_with(|| {
+		// given
+		let (leaf, proof) = crate::Module::<Test>::generate_proof(5).unwrap();
+
+		// then wrong leaf data is rejected
+		let mut wrong_leaf = leaf.clone();
+		wrong_leaf.1.a = 100;
+		assert_eq!(
+			crate::Module::<Test>::verify_leaf(wrong_leaf, proof.clone()),
+			Err(primitives::Error::Verify),
+		);
+
+		// and so is a proof with tampered items
+		let mut wrong_proof = proof.clone();
+		wrong_proof.items[0] = H256::repeat_byte(42);
+		assert_eq!(
+			crate::Module::<Test>::verify_leaf(leaf.clone(), wrong_proof),
+			Err(primitives::Error::Verify),
+		);
+
+		// or a proof for a different leaf
+		let mut wrong_proof = proof;
+		wrong_proof.leaf_index = 4;
+		assert_eq!(
+			crate::Module::<Test>::verify_leaf(leaf, wrong_proof),
+			Err(primitives::Error::Verify),
+		);
+	});
+}
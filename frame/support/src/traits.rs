@@ -1508,6 +1508,176 @@ pub trait OffchainWorker<BlockNumber> {
 	fn offchain_worker(_n: BlockNumber) {}
 }
 
+/// Traits for dealing with a set of non-fungible collections, each of which contains uniquely
+/// identifiable items.
+pub mod nonfungibles {
+	use super::*;
+
+	/// Trait for providing an interface to many read-only non-fungible sets of items.
+	pub trait Inspect<AccountId> {
+		/// Type for identifying a collection.
+		type CollectionId;
+		/// Type for identifying an item within a collection.
+		type ItemId;
+
+		/// Returns the owner of `item` of `collection`, or `None` if the item doesn't exist (or
+		/// somehow has no owner).
+		fn owner(collection: &Self::CollectionId, item: &Self::ItemId) -> Option<AccountId>;
+
+		/// Returns the owner of the `collection`, if there is one. For many NFTs this may not make
+		/// any sense, so users of this API should not be surprised to find a collection results
+		/// in `None` here.
+		fn collection_owner(_collection: &Self::CollectionId) -> Option<AccountId> { None }
+
+		/// Returns the attribute value of `item` of `collection` corresponding to `key`.
+		///
+		/// By default this is `None`; no attributes are defined.
+		fn attribute(
+			_collection: &Self::CollectionId,
+			_item: &Self::ItemId,
+			_key: &[u8],
+		) -> Option<Vec<u8>> { None }
+
+		/// Returns the strongly-typed attribute value of `item` of `collection` corresponding to
+		/// `key`.
+		///
+		/// By default this just attempts to use `attribute`.
+		fn typed_attribute<K: Encode, V: Decode>(
+			collection: &Self::CollectionId,
+			item: &Self::ItemId,
+			key: &K,
+		) -> Option<V> {
+			key.using_encoded(|d| Self::attribute(collection, item, d))
+				.and_then(|v| V::decode(&mut &v[..]).ok())
+		}
+
+		/// Returns the attribute value of `collection` corresponding to `key`.
+		///
+		/// By default this is `None`; no attributes are defined.
+		fn collection_attribute(_collection: &Self::CollectionId, _key: &[u8]) -> Option<Vec<u8>> {
+			None
+		}
+
+		/// Returns the strongly-typed attribute value of `collection` corresponding to `key`.
+		///
+		/// By default this just attempts to use `collection_attribute`.
+		fn typed_collection_attribute<K: Encode, V: Decode>(
+			collection: &Self::CollectionId,
+			key: &K,
+		) -> Option<V> {
+			key.using_encoded(|d| Self::collection_attribute(collection, d))
+				.and_then(|v| V::decode(&mut &v[..]).ok())
+		}
+
+		/// Returns `true` if `item` of `collection` may be transferred.
+		///
+		/// Default implementation is that all items are transferable.
+		fn can_transfer(_collection: &Self::CollectionId, _item: &Self::ItemId) -> bool { true }
+	}
+
+	/// Interface for enumerating items in existence or owned by a given account over many
+	/// collections of NFTs.
+	pub trait InspectEnumerable<AccountId>: Inspect<AccountId> {
+		/// Returns an iterator of the items of `collection` in existence.
+		fn items(collection: &Self::CollectionId) -> Box<dyn Iterator<Item = Self::ItemId>>;
+
+		/// Returns an iterator of the items of all collections owned by `who`.
+		fn owned(who: &AccountId) -> Box<dyn Iterator<Item = (Self::CollectionId, Self::ItemId)>>;
+	}
+
+	/// Trait for providing the ability to create collections of non-fungible items.
+	pub trait Create<AccountId>: Inspect<AccountId> {
+		/// Create a `collection` of nonfungible items to be owned by `who` and managed by `admin`.
+		fn create_collection(
+			collection: &Self::CollectionId,
+			who: &AccountId,
+			admin: &AccountId,
+		) -> DispatchResult;
+	}
+
+	/// Trait for providing an interface for multiple collections of NFT-like items which may be
+	/// minted, burned and/or have attributes set on them.
+	pub trait Mutate<AccountId>: Inspect<AccountId> {
+		/// Mint some `item` of `collection` to be owned by `who`.
+		///
+		/// By default, this is not a supported operation.
+		fn mint_into(
+			_collection: &Self::CollectionId,
+			_item: &Self::ItemId,
+			_who: &AccountId,
+		) -> DispatchResult {
+			Err(DispatchError::Other("Minting is not supported"))
+		}
+
+		/// Burn some `item` of `collection`.
+		///
+		/// By default, this is not a supported operation.
+		fn burn_from(_collection: &Self::CollectionId, _item: &Self::ItemId) -> DispatchResult {
+			Err(DispatchError::Other("Burning is not supported"))
+		}
+
+		/// Set attribute `value` of `item` of `collection`'s `key`.
+		///
+		/// By default, this is not a supported operation.
+		fn set_attribute(
+			_collection: &Self::CollectionId,
+			_item: &Self::ItemId,
+			_key: &[u8],
+			_value: &[u8],
+		) -> DispatchResult {
+			Err(DispatchError::Other("Attributes are not supported"))
+		}
+
+		/// Attempt to set the strongly-typed attribute `value` of `item` of `collection`'s `key`.
+		///
+		/// By default this just attempts to use `set_attribute`.
+		fn set_typed_attribute<K: Encode, V: Encode>(
+			collection: &Self::CollectionId,
+			item: &Self::ItemId,
+			key: &K,
+			value: &V,
+		) -> DispatchResult {
+			key.using_encoded(|k| value.using_encoded(|v|
+				Self::set_attribute(collection, item, k, v)
+			))
+		}
+
+		/// Set attribute `value` of `collection`'s `key`.
+		///
+		/// By default, this is not a supported operation.
+		fn set_collection_attribute(
+			_collection: &Self::CollectionId,
+			_key: &[u8],
+			_value: &[u8],
+		) -> DispatchResult {
+			Err(DispatchError::Other("Attributes are not supported"))
+		}
+
+		/// Attempt to set the strongly-typed attribute `value` of `collection`'s `key`.
+		///
+		/// By default this just attempts to use `set_collection_attribute`.
+		fn set_typed_collection_attribute<K: Encode, V: Encode>(
+			collection: &Self::CollectionId,
+			key: &K,
+			value: &V,
+		) -> DispatchResult {
+			key.using_encoded(|k| value.using_encoded(|v|
+				Self::set_collection_attribute(collection, k, v)
+			))
+		}
+	}
+
+	/// Trait for providing a non-fungible sets of items which can only be transferred.
+	pub trait Transfer<AccountId>: Inspect<AccountId> {
+		/// Transfer `item` of `collection` into `destination` account.
+		fn transfer(
+			collection: &Self::CollectionId,
+			item: &Self::ItemId,
+			destination: &AccountId,
+		) -> DispatchResult;
+	}
+}
+
 pub mod schedule {
 	use super::*;
 
@@ -0,0 +1,99 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Benchmarks for transaction-storage Pallet
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use sp_runtime::traits::Bounded;
+use frame_system::{RawOrigin, EventRecord};
+use frame_benchmarking::{benchmarks, whitelisted_caller};
+use frame_support::traits::OnFinalize;
+use sp_transaction_storage_proof::build_proof;
+use crate::Module as TransactionStorage;
+
+fn assert_last_event<T: Trait>(generic_event: <T as Trait>::Event) {
+	let events = frame_system::Module::<T>::events();
+	let system_event: <T as frame_system::Trait>::Event = generic_event.into();
+	// compare to the last event record
+	let EventRecord { event, .. } = &events[events.len() - 1];
+	assert_eq!(event, &system_event);
+}
+
+fn store_data<T: Trait>(data: Vec<u8>) {
+	let caller: T::AccountId = whitelisted_caller();
+	T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+	frame_system::Module::<T>::set_block_number(One::one());
+	frame_system::Module::<T>::set_extrinsic_index(0);
+	assert!(TransactionStorage::<T>::store(RawOrigin::Signed(caller).into(), data).is_ok());
+	TransactionStorage::<T>::on_finalize(frame_system::Module::<T>::block_number());
+}
+
+benchmarks! {
+	_ { }
+
+	store {
+		let l in 1 .. MaxTransactionSize::get();
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+		frame_system::Module::<T>::set_extrinsic_index(0);
+	}: _(RawOrigin::Signed(caller.clone()), vec![0u8; l as usize])
+	verify {
+		assert!(!BlockTransactions::get().is_empty());
+		assert_last_event::<T>(Event::Stored(0).into());
+	}
+
+	renew {
+		let caller: T::AccountId = whitelisted_caller();
+		T::Currency::make_free_balance_be(&caller, BalanceOf::<T>::max_value());
+		store_data::<T>(vec![0u8; MaxTransactionSize::get() as usize]);
+		frame_system::Module::<T>::set_extrinsic_index(0);
+	}: _(RawOrigin::Signed(caller.clone()), frame_system::Module::<T>::block_number(), 0)
+	verify {
+		assert_last_event::<T>(Event::Renewed(0).into());
+	}
+
+	check_proof_max {
+		let data = vec![0u8; MaxTransactionSize::get() as usize];
+		store_data::<T>(data.clone());
+		let target = frame_system::Module::<T>::block_number();
+		let number = target + StoragePeriod::<T>::get();
+		frame_system::Module::<T>::set_block_number(number);
+		let parent_hash = frame_system::Module::<T>::parent_hash();
+		let proof = build_proof(parent_hash.as_ref(), vec![data]).unwrap();
+	}: check_proof(RawOrigin::None, proof)
+	verify {
+		assert_last_event::<T>(Event::ProofChecked.into());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{new_test_ext, Test};
+	use frame_support::assert_ok;
+
+	#[test]
+	fn test_benchmarks() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_store::<Test>());
+			assert_ok!(test_benchmark_renew::<Test>());
+			assert_ok!(test_benchmark_check_proof_max::<Test>());
+		});
+	}
+}
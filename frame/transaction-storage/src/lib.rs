@@ -0,0 +1,405 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Transaction Storage Module
+//!
+//! Indexes transactions and manages storage proofs.
+//!
+//! - [`transaction_storage::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! The Transaction Storage module allows arbitrary data to be kept available for a fixed
+//! retention period (`StoragePeriod` blocks) without keeping it in the state. Only the content
+//! hash, the root of the chunk trie and the size of each stored blob are kept in the state;
+//! the data itself stays in the block body and is indexed by the client, which must run with
+//! the storage chain mode enabled (`--storage-chain`).
+//!
+//! Block authors must prove that they still hold the data by including a `check_proof`
+//! inherent in every block. The inherent carries a randomly selected chunk of the data stored
+//! in the block that is about to leave the retention window, along with a trie proof of the
+//! chunk.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `store`: Store the given data for `StoragePeriod` blocks. Charges `EntryFee` and
+//!   `ByteFee` for every byte.
+//! * `renew`: Renew previously stored data for another `StoragePeriod` blocks, charging the
+//!   same fees.
+//! * `check_proof`: Check the storage proof. This is an inherent and may only be included by
+//!   the block author.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+// Ensure we're `no_std` when compiling for Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod benchmarking;
+pub mod weights;
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+use sp_std::{prelude::*, result};
+use codec::{Decode, Encode};
+use sp_core::H256;
+use sp_runtime::traits::{Saturating, Zero, One};
+use sp_inherents::{InherentData, InherentIdentifier, ProvideInherent};
+use sp_transaction_storage_proof::{
+	TransactionStorageProof, InherentError,
+	random_chunk, encode_index,
+	CHUNK_SIZE, INHERENT_IDENTIFIER, DEFAULT_STORAGE_PERIOD,
+};
+use frame_support::{
+	decl_module, decl_event, decl_error, decl_storage, ensure, RuntimeDebug,
+	dispatch::{DispatchResult, DispatchResultWithPostInfo},
+	traits::{Get, Currency, OnUnbalanced, ExistenceRequirement, WithdrawReasons},
+	weights::{Weight, DispatchClass},
+};
+use frame_system::{ensure_signed, ensure_none};
+pub use weights::WeightInfo;
+
+/// Maximum bytes that can be stored in one transaction.
+// Setting higher limit also requires raising the allocator limit.
+pub const DEFAULT_MAX_TRANSACTION_SIZE: u32 = 8 * 1024 * 1024;
+/// Maximum number of indexed transactions in the block.
+pub const DEFAULT_MAX_BLOCK_TRANSACTIONS: u32 = 512;
+
+type BalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type NegativeImbalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+
+/// State data for a stored transaction.
+#[derive(Encode, Decode, Clone, RuntimeDebug, PartialEq, Eq)]
+pub struct TransactionInfo {
+	/// Chunk trie root.
+	chunk_root: H256,
+	/// Plain hash of indexed data.
+	content_hash: H256,
+	/// Size of indexed data in bytes.
+	size: u32,
+	/// Total number of chunks added in the block with this transaction. This
+	/// is used to find the transaction that holds a given chunk with binary search.
+	block_chunks: u32,
+}
+
+fn num_chunks(bytes: u32) -> u32 {
+	((bytes as u64 + CHUNK_SIZE as u64 - 1) / CHUNK_SIZE as u64) as u32
+}
+
+/// Configuration trait.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The currency in which the storage fees are paid.
+	type Currency: Currency<Self::AccountId>;
+
+	/// Handler for the unbalanced decrease when fees are burned.
+	type FeeDestination: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as TransactionStorage {
+		/// Collection of transaction metadata by block number.
+		Transactions get(fn transaction_roots):
+			map hasher(blake2_128_concat) T::BlockNumber => Option<Vec<TransactionInfo>>;
+
+		/// Count indexed chunks for each block.
+		ChunkCount: map hasher(blake2_128_concat) T::BlockNumber => u32;
+
+		/// Storage fee per byte.
+		ByteFee get(fn byte_fee) config(): BalanceOf<T>;
+
+		/// Storage fee per transaction.
+		EntryFee get(fn entry_fee) config(): BalanceOf<T>;
+
+		/// Maximum data set in a single transaction in bytes.
+		MaxTransactionSize get(fn max_transaction_size)
+			config(): u32 = DEFAULT_MAX_TRANSACTION_SIZE;
+
+		/// Maximum number of indexed transactions in the block.
+		MaxBlockTransactions get(fn max_block_transactions)
+			config(): u32 = DEFAULT_MAX_BLOCK_TRANSACTIONS;
+
+		/// Storage period for data in blocks. Should match
+		/// `sp_transaction_storage_proof::DEFAULT_STORAGE_PERIOD`
+		/// for block authoring.
+		StoragePeriod get(fn storage_period) config(): T::BlockNumber = DEFAULT_STORAGE_PERIOD.into();
+
+		/// Transactions indexed in the current block.
+		BlockTransactions: Vec<TransactionInfo>;
+
+		/// Was the proof checked in this block?
+		ProofChecked: bool;
+	}
+}
+
+decl_event!(
+	pub enum Event {
+		/// Stored data under specified index. \[index\]
+		Stored(u32),
+		/// Renewed data under specified index. \[index\]
+		Renewed(u32),
+		/// Storage proof was successfully checked.
+		ProofChecked,
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// Insufficient account balance.
+		InsufficientFunds,
+		/// Renewed extrinsic is not found.
+		RenewedNotFound,
+		/// Attempting to store empty transaction
+		EmptyTransaction,
+		/// Proof was not expected in this block.
+		UnexpectedProof,
+		/// Proof failed verification.
+		InvalidProof,
+		/// Unable to verify proof because state data is missing.
+		MissingStateData,
+		/// Double proof check in the block.
+		DoubleCheck,
+		/// Transaction is too large.
+		TransactionTooLarge,
+		/// Too many transactions in the block.
+		TooManyTransactions,
+		/// Attempted to call `store` outside of block execution.
+		BadContext,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			// Drop obsolete roots. The proof for `obsolete` will be checked later
+			// in this block, so we drop `obsolete` - 1.
+			let period = <StoragePeriod<T>>::get();
+			let obsolete = n.saturating_sub(period.saturating_add(One::one()));
+			if obsolete > Zero::zero() {
+				<Transactions<T>>::remove(obsolete);
+				<ChunkCount<T>>::remove(obsolete);
+			}
+			// 2 writes in `on_initialize` and 2 writes + 2 reads in `on_finalize`
+			T::DbWeight::get().reads_writes(2, 4)
+		}
+
+		fn on_finalize(n: T::BlockNumber) {
+			assert!(
+				ProofChecked::take()
+					|| {
+						// Proof is not required for early or empty blocks.
+						let period = <StoragePeriod<T>>::get();
+						let target_number = n.saturating_sub(period);
+						target_number.is_zero() || <ChunkCount<T>>::get(target_number) == 0
+					},
+				"Storage proof must be checked once in the block"
+			);
+			// Insert new transactions
+			let transactions = BlockTransactions::take();
+			let total_chunks = transactions.last().map_or(0, |t| t.block_chunks);
+			if total_chunks != 0 {
+				<ChunkCount<T>>::insert(n, total_chunks);
+				<Transactions<T>>::insert(n, transactions);
+			}
+		}
+
+		/// Index and store data on chain. Minimum data size is 1 bytes, maximum is
+		/// `MaxTransactionSize`. Data will be removed after `StoragePeriod` blocks, unless `renew`
+		/// is called.
+		///
+		/// # <weight>
+		/// - n*log(n) of data size, as all data is pushed to an in-memory trie.
+		/// - Additionally contains a DB write.
+		/// # </weight>
+		#[weight = T::WeightInfo::store(data.len() as u32)]
+		fn store(origin, data: Vec<u8>) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(!data.is_empty(), Error::<T>::EmptyTransaction);
+			ensure!(data.len() <= MaxTransactionSize::get() as usize, Error::<T>::TransactionTooLarge);
+			let extrinsic_index = <frame_system::Module<T>>::extrinsic_index()
+				.ok_or(Error::<T>::BadContext)?;
+			let mut transactions = BlockTransactions::get();
+			ensure!(
+				transactions.len() < MaxBlockTransactions::get() as usize,
+				Error::<T>::TooManyTransactions,
+			);
+			Self::apply_fee(sender, data.len() as u32)?;
+
+			// Chunk data and compute storage root
+			let chunk_count = num_chunks(data.len() as u32);
+			let chunks = data.chunks(CHUNK_SIZE).map(|c| c.to_vec()).collect();
+			let root = sp_io::trie::blake2_256_ordered_root(chunks);
+
+			let content_hash = sp_io::hashing::blake2_256(&data);
+			sp_io::transaction_index::index(extrinsic_index, data.len() as u32, content_hash);
+
+			let index = transactions.len() as u32;
+			let total_chunks = transactions.last().map_or(0, |t| t.block_chunks) + chunk_count;
+			transactions.push(TransactionInfo {
+				chunk_root: root,
+				size: data.len() as u32,
+				content_hash: content_hash.into(),
+				block_chunks: total_chunks,
+			});
+			BlockTransactions::put(transactions);
+			Self::deposit_event(Event::Stored(index));
+			Ok(())
+		}
+
+		/// Renew previously stored data. Parameters are the block number that contains
+		/// previous `store` or `renew` call and transaction index within that block.
+		/// Transaction index is emitted in the `Stored` or `Renewed` event.
+		/// Applies same fees as `store`.
+		///
+		/// # <weight>
+		/// - Constant.
+		/// # </weight>
+		#[weight = T::WeightInfo::renew()]
+		fn renew(
+			origin,
+			block: T::BlockNumber,
+			index: u32,
+		) -> DispatchResultWithPostInfo {
+			let sender = ensure_signed(origin)?;
+			let transactions = <Transactions<T>>::get(block).ok_or(Error::<T>::RenewedNotFound)?;
+			let info = transactions.get(index as usize).ok_or(Error::<T>::RenewedNotFound)?;
+			let extrinsic_index = <frame_system::Module<T>>::extrinsic_index()
+				.ok_or(Error::<T>::BadContext)?;
+			let mut block_transactions = BlockTransactions::get();
+			ensure!(
+				block_transactions.len() < MaxBlockTransactions::get() as usize,
+				Error::<T>::TooManyTransactions,
+			);
+			Self::apply_fee(sender, info.size)?;
+
+			sp_io::transaction_index::renew(extrinsic_index, info.content_hash.into());
+
+			let index = block_transactions.len() as u32;
+			let chunks = num_chunks(info.size);
+			let total_chunks = block_transactions.last().map_or(0, |t| t.block_chunks) + chunks;
+			block_transactions.push(TransactionInfo {
+				chunk_root: info.chunk_root,
+				size: info.size,
+				content_hash: info.content_hash,
+				block_chunks: total_chunks,
+			});
+			BlockTransactions::put(block_transactions);
+			Self::deposit_event(Event::Renewed(index));
+			Ok(().into())
+		}
+
+		/// Check storage proof for block number `block_number() - StoragePeriod`.
+		/// If such block does not exist the proof is expected to be `None`.
+		///
+		/// # <weight>
+		/// - Linear w.r.t the number of indexed transactions in the proved block for random
+		///   probing.
+		/// There's a DB read for each transaction.
+		/// Here we assume a maximum of 100 probed transactions.
+		/// # </weight>
+		#[weight = (T::WeightInfo::check_proof_max(), DispatchClass::Mandatory)]
+		fn check_proof(
+			origin,
+			proof: TransactionStorageProof,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+			ensure!(!ProofChecked::get(), Error::<T>::DoubleCheck);
+			let number = <frame_system::Module<T>>::block_number();
+			let period = <StoragePeriod<T>>::get();
+			let target_number = number.saturating_sub(period);
+			ensure!(!target_number.is_zero(), Error::<T>::UnexpectedProof);
+			let total_chunks = <ChunkCount<T>>::get(target_number);
+			ensure!(total_chunks != 0, Error::<T>::UnexpectedProof);
+			let parent_hash = <frame_system::Module<T>>::parent_hash();
+			let selected_chunk_index = random_chunk(parent_hash.as_ref(), total_chunks);
+			let (info, chunk_index) = match <Transactions<T>>::get(target_number) {
+				Some(infos) => {
+					// `block_chunks` is the exclusive upper bound of the chunks of each transaction.
+					let index = match infos.binary_search_by_key(&selected_chunk_index, |info| info.block_chunks) {
+						Ok(index) => index + 1,
+						Err(index) => index,
+					};
+					let info = infos.get(index).ok_or(Error::<T>::MissingStateData)?.clone();
+					let chunks = num_chunks(info.size);
+					let prev_chunks = info.block_chunks - chunks;
+					(info, selected_chunk_index - prev_chunks)
+				},
+				None => Err(Error::<T>::MissingStateData)?,
+			};
+			ensure!(
+				sp_io::trie::blake2_256_verify_proof(
+					info.chunk_root,
+					&proof.proof,
+					&encode_index(chunk_index),
+					&proof.chunk,
+				),
+				Error::<T>::InvalidProof
+			);
+			ProofChecked::put(true);
+			Self::deposit_event(Event::ProofChecked);
+			Ok(().into())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	fn apply_fee(sender: T::AccountId, size: u32) -> DispatchResult {
+		let byte_fee = <ByteFee<T>>::get();
+		let entry_fee = <EntryFee<T>>::get();
+		let fee = byte_fee.saturating_mul(size.into()).saturating_add(entry_fee);
+		let imbalance = T::Currency::withdraw(
+			&sender,
+			fee,
+			WithdrawReasons::FEE,
+			ExistenceRequirement::KeepAlive,
+		).map_err(|_| Error::<T>::InsufficientFunds)?;
+		T::FeeDestination::on_unbalanced(imbalance);
+		Ok(())
+	}
+}
+
+impl<T: Trait> ProvideInherent for Module<T> {
+	type Call = Call<T>;
+	type Error = InherentError;
+	const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+	fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+		let proof = data.get_data::<TransactionStorageProof>(&Self::INHERENT_IDENTIFIER).unwrap_or(None);
+		proof.map(Call::check_proof)
+	}
+
+	fn check_inherent(_call: &Self::Call, _data: &InherentData) -> result::Result<(), Self::Error> {
+		Ok(())
+	}
+}
@@ -0,0 +1,254 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests for transaction-storage pallet.
+
+use super::*;
+use crate::mock::*;
+use frame_support::{assert_ok, assert_noop};
+use frame_system::RawOrigin;
+use sp_state_machine::IndexOperation;
+use sp_transaction_storage_proof::build_proof;
+
+const MAX_DATA_SIZE: u32 = DEFAULT_MAX_TRANSACTION_SIZE;
+
+#[test]
+fn discards_data() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+		let caller = 1;
+		System::set_extrinsic_index(0);
+		assert_ok!(TransactionStorage::store(
+			RawOrigin::Signed(caller).into(),
+			vec![0u8; 2000]
+		));
+		System::set_extrinsic_index(1);
+		assert_ok!(TransactionStorage::store(
+			RawOrigin::Signed(caller).into(),
+			vec![0u8; 2000]
+		));
+		let proof_provider = || {
+			let block_num = <frame_system::Module<Test>>::block_number();
+			if block_num == 11 {
+				let parent_hash = <frame_system::Module<Test>>::parent_hash();
+				Some(build_proof(
+					parent_hash.as_ref(),
+					vec![vec![0u8; 2000], vec![0u8; 2000]]
+				).unwrap())
+			} else {
+				None
+			}
+		};
+		run_to_block_with_proof(11, proof_provider);
+		assert!(Transactions::<Test>::get(1).is_some());
+		let transactions = Transactions::<Test>::get(1).unwrap();
+		assert_eq!(transactions.len(), 2);
+		assert_eq!(ChunkCount::<Test>::get(1), 16);
+		run_to_block_with_proof(12, proof_provider);
+		assert!(Transactions::<Test>::get(1).is_none());
+		assert_eq!(ChunkCount::<Test>::get(1), 0);
+	});
+}
+
+#[test]
+fn burns_fee() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+		let caller = 1;
+		System::set_extrinsic_index(0);
+		assert_noop!(TransactionStorage::store(
+				RawOrigin::Signed(5).into(),
+				vec![0u8; 2000]
+			),
+			Error::<Test>::InsufficientFunds,
+		);
+		assert_ok!(TransactionStorage::store(
+			RawOrigin::Signed(caller).into(),
+			vec![0u8; 2000]
+		));
+		assert_eq!(Balances::free_balance(1), 1_000_000_000 - 2000 * 2 - 200);
+	});
+}
+
+#[test]
+fn rejects_invalid_data() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+		System::set_extrinsic_index(0);
+		assert_noop!(
+			TransactionStorage::store(RawOrigin::Signed(1).into(), Vec::new()),
+			Error::<Test>::EmptyTransaction,
+		);
+		assert_noop!(
+			TransactionStorage::store(
+				RawOrigin::Signed(1).into(),
+				vec![0u8; MAX_DATA_SIZE as usize + 1],
+			),
+			Error::<Test>::TransactionTooLarge,
+		);
+	});
+}
+
+#[test]
+fn indexes_stored_data() {
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		run_to_block(1);
+		System::set_extrinsic_index(3);
+		assert_ok!(TransactionStorage::store(RawOrigin::Signed(1).into(), vec![42u8; 100]));
+	});
+	assert_eq!(
+		ext.transaction_index_ops().cloned().collect::<Vec<_>>(),
+		vec![IndexOperation::Insert {
+			extrinsic: 3,
+			hash: sp_io::hashing::blake2_256(&[42u8; 100]).to_vec(),
+			size: 100,
+		}],
+	);
+}
+
+#[test]
+fn checks_proof() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+		let caller = 1;
+		System::set_extrinsic_index(0);
+		assert_ok!(TransactionStorage::store(
+			RawOrigin::Signed(caller).into(),
+			vec![0u8; MAX_DATA_SIZE as usize]
+		));
+		run_to_block(10);
+		let parent_hash = <frame_system::Module<Test>>::parent_hash();
+		let proof = build_proof(
+			parent_hash.as_ref(),
+			vec![vec![0u8; MAX_DATA_SIZE as usize]]
+		).unwrap();
+		assert_noop!(TransactionStorage::check_proof(
+				Origin::none(),
+				proof,
+			),
+			Error::<Test>::UnexpectedProof,
+		);
+		run_to_block(11);
+		let parent_hash = <frame_system::Module<Test>>::parent_hash();
+
+		let invalid_proof = build_proof(
+			parent_hash.as_ref(),
+			vec![vec![0u8; 1000]]
+		).unwrap();
+		assert_noop!(TransactionStorage::check_proof(
+				Origin::none(),
+				invalid_proof,
+			),
+			Error::<Test>::InvalidProof,
+		);
+
+		let proof = build_proof(
+			parent_hash.as_ref(),
+			vec![vec![0u8; MAX_DATA_SIZE as usize]]
+		).unwrap();
+		assert_ok!(TransactionStorage::check_proof(Origin::none(), proof.clone()));
+		assert_noop!(
+			TransactionStorage::check_proof(Origin::none(), proof),
+			Error::<Test>::DoubleCheck,
+		);
+	});
+}
+
+#[test]
+fn checks_proof_across_transactions() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+		let data = vec![vec![1u8; 300], vec![2u8; 10], vec![3u8; 600]];
+		for (i, d) in data.iter().enumerate() {
+			System::set_extrinsic_index(i as u32);
+			assert_ok!(TransactionStorage::store(RawOrigin::Signed(1).into(), d.clone()));
+		}
+		run_to_block(10);
+		// 2 + 1 + 3 chunks, so every possible chunk gets selected at least once.
+		for seed in 0..6u8 {
+			let parent_hash = H256::repeat_byte(seed);
+			System::set_block_number(11);
+			System::set_parent_hash(parent_hash);
+			ProofChecked::kill();
+			let proof = build_proof(parent_hash.as_ref(), data.clone()).unwrap();
+			assert_ok!(TransactionStorage::check_proof(Origin::none(), proof));
+		}
+	});
+}
+
+#[test]
+#[should_panic(expected = "Storage proof must be checked once in the block")]
+fn requires_proof() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+		System::set_extrinsic_index(0);
+		assert_ok!(TransactionStorage::store(RawOrigin::Signed(1).into(), vec![0u8; 100]));
+		run_to_block(12);
+	});
+}
+
+#[test]
+fn renews_data() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+		let caller = 1;
+		System::set_extrinsic_index(0);
+		assert_ok!(TransactionStorage::store(
+			RawOrigin::Signed(caller).into(),
+			vec![0u8; 2000]
+		));
+		let info = BlockTransactions::get().last().unwrap().clone();
+		run_to_block(6);
+		assert_ok!(TransactionStorage::renew(
+			RawOrigin::Signed(caller).into(),
+			1, // block
+			0, // transaction
+		));
+		assert_eq!(Balances::free_balance(1), 1_000_000_000 - 4000 * 2 - 200 * 2);
+		assert_noop!(
+			TransactionStorage::renew(RawOrigin::Signed(caller).into(), 1, 1),
+			Error::<Test>::RenewedNotFound,
+		);
+		let proof_provider = || {
+			let block_num = <frame_system::Module<Test>>::block_number();
+			if block_num == 11 || block_num == 16 {
+				let parent_hash = <frame_system::Module<Test>>::parent_hash();
+				Some(build_proof(
+					parent_hash.as_ref(),
+					vec![vec![0u8; 2000]]
+				).unwrap())
+			} else {
+				None
+			}
+		};
+		run_to_block_with_proof(16, proof_provider);
+		assert!(Transactions::<Test>::get(1).is_none());
+		assert_eq!(Transactions::<Test>::get(6).unwrap().get(0), Some(info).as_ref());
+		run_to_block_with_proof(17, proof_provider);
+		assert!(Transactions::<Test>::get(6).is_none());
+	});
+}
+
+fn run_to_block_with_proof(n: u64, f: impl Fn() -> Option<TransactionStorageProof>) {
+	while System::block_number() < n {
+		run_to_block(System::block_number() + 1);
+		if let Some(proof) = f() {
+			assert_ok!(TransactionStorage::check_proof(Origin::none(), proof));
+		}
+	}
+}
@@ -0,0 +1,401 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Benchmarks for Uniques Pallet
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+use frame_system::{RawOrigin, EventRecord};
+use frame_benchmarking::{benchmarks, account, whitelisted_caller, whitelist_account};
+use sp_runtime::traits::Bounded;
+use crate::Module as Uniques;
+
+const SEED: u32 = 0;
+
+fn create_collection<T: Trait>()
+	-> (T::CollectionId, T::AccountId, <T::Lookup as StaticLookup>::Source)
+{
+	let caller: T::AccountId = whitelisted_caller();
+	let caller_lookup = T::Lookup::unlookup(caller.clone());
+	let collection = Default::default();
+	T::Currency::make_free_balance_be(&caller, DepositBalanceOf::<T>::max_value());
+	assert!(Uniques::<T>::create(
+		RawOrigin::Signed(caller.clone()).into(),
+		collection,
+		caller_lookup.clone(),
+	).is_ok());
+	(collection, caller, caller_lookup)
+}
+
+fn add_collection_metadata<T: Trait>()
+	-> (T::AccountId, <T::Lookup as StaticLookup>::Source)
+{
+	let caller = Collection::<T>::get(T::CollectionId::default()).unwrap().owner;
+	if caller != whitelisted_caller() {
+		whitelist_account!(caller);
+	}
+	let caller_lookup = T::Lookup::unlookup(caller.clone());
+	assert!(Uniques::<T>::set_collection_metadata(
+		RawOrigin::Signed(caller.clone()).into(),
+		Default::default(),
+		vec![0; T::StringLimit::get() as usize],
+		false,
+	).is_ok());
+	(caller, caller_lookup)
+}
+
+fn mint_item<T: Trait>(index: u32)
+	-> (T::ItemId, T::AccountId, <T::Lookup as StaticLookup>::Source)
+{
+	let caller = Collection::<T>::get(T::CollectionId::default()).unwrap().admin;
+	if caller != whitelisted_caller() {
+		whitelist_account!(caller);
+	}
+	let caller_lookup = T::Lookup::unlookup(caller.clone());
+	let item = index.into();
+	assert!(Uniques::<T>::mint(
+		RawOrigin::Signed(caller.clone()).into(),
+		Default::default(),
+		item,
+		caller_lookup.clone(),
+	).is_ok());
+	(item, caller, caller_lookup)
+}
+
+fn add_item_metadata<T: Trait>(item: T::ItemId)
+	-> (T::AccountId, <T::Lookup as StaticLookup>::Source)
+{
+	let caller = Collection::<T>::get(T::CollectionId::default()).unwrap().owner;
+	if caller != whitelisted_caller() {
+		whitelist_account!(caller);
+	}
+	let caller_lookup = T::Lookup::unlookup(caller.clone());
+	assert!(Uniques::<T>::set_metadata(
+		RawOrigin::Signed(caller.clone()).into(),
+		Default::default(),
+		item,
+		vec![0; T::StringLimit::get() as usize],
+		false,
+	).is_ok());
+	(caller, caller_lookup)
+}
+
+fn add_item_attribute<T: Trait>(item: T::ItemId, index: u32)
+	-> (Vec<u8>, T::AccountId, <T::Lookup as StaticLookup>::Source)
+{
+	let caller = Collection::<T>::get(T::CollectionId::default()).unwrap().owner;
+	if caller != whitelisted_caller() {
+		whitelist_account!(caller);
+	}
+	let caller_lookup = T::Lookup::unlookup(caller.clone());
+	// Keys of maximum length, made unique by their prefix.
+	let mut key = index.encode();
+	key.resize(T::KeyLimit::get().max(key.len() as u32) as usize, 0);
+	assert!(Uniques::<T>::set_attribute(
+		RawOrigin::Signed(caller.clone()).into(),
+		Default::default(),
+		Some(item),
+		key.clone(),
+		vec![0; T::ValueLimit::get() as usize],
+	).is_ok());
+	(key, caller, caller_lookup)
+}
+
+fn assert_last_event<T: Trait>(generic_event: <T as Trait>::Event) {
+	let events = frame_system::Module::<T>::events();
+	let system_event: <T as frame_system::Trait>::Event = generic_event.into();
+	// compare to the last event record
+	let EventRecord { event, .. } = &events[events.len() - 1];
+	assert_eq!(event, &system_event);
+}
+
+benchmarks! {
+	_ { }
+
+	create {
+		let caller: T::AccountId = whitelisted_caller();
+		let caller_lookup = T::Lookup::unlookup(caller.clone());
+		T::Currency::make_free_balance_be(&caller, DepositBalanceOf::<T>::max_value());
+	}: _(RawOrigin::Signed(caller.clone()), Default::default(), caller_lookup)
+	verify {
+		assert_last_event::<T>(RawEvent::Created(Default::default(), caller.clone(), caller).into());
+	}
+
+	force_create {
+		let caller: T::AccountId = whitelisted_caller();
+		let caller_lookup = T::Lookup::unlookup(caller.clone());
+	}: _(RawOrigin::Root, Default::default(), caller_lookup, true)
+	verify {
+		assert_last_event::<T>(RawEvent::ForceCreated(Default::default(), caller).into());
+	}
+
+	destroy {
+		let n in 0 .. 1_000;
+		let m in 0 .. 1_000;
+		let a in 0 .. 1_000;
+
+		let (collection, caller, caller_lookup) = create_collection::<T>();
+		add_collection_metadata::<T>();
+		for i in 0..n {
+			mint_item::<T>(i);
+		}
+		for i in 0..m {
+			// Metadata can only be set on existing items.
+			if i >= n {
+				mint_item::<T>(i);
+			}
+			add_item_metadata::<T>(i.into());
+		}
+		// Attributes are all set on the first item, so that they don't change the number of items.
+		if n.max(m) == 0 && a > 0 {
+			mint_item::<T>(0);
+		}
+		for i in 0..a {
+			add_item_attribute::<T>(0u32.into(), i);
+		}
+		let witness = Collection::<T>::get(collection).unwrap().destroy_witness();
+	}: _(RawOrigin::Signed(caller), collection, witness)
+	verify {
+		assert_last_event::<T>(RawEvent::Destroyed(collection).into());
+	}
+
+	mint {
+		let (collection, caller, caller_lookup) = create_collection::<T>();
+		let item = Default::default();
+	}: _(RawOrigin::Signed(caller.clone()), collection, item, caller_lookup)
+	verify {
+		assert_last_event::<T>(RawEvent::Issued(collection, item, caller).into());
+	}
+
+	burn {
+		let a in 0 .. 1_000;
+
+		let (collection, caller, caller_lookup) = create_collection::<T>();
+		let (item, ..) = mint_item::<T>(0);
+		add_item_metadata::<T>(item);
+		for i in 0..a {
+			add_item_attribute::<T>(item, i);
+		}
+	}: _(RawOrigin::Signed(caller.clone()), collection, item, Some(caller_lookup), a)
+	verify {
+		assert_last_event::<T>(RawEvent::Burned(collection, item, caller).into());
+	}
+
+	transfer {
+		let (collection, caller, caller_lookup) = create_collection::<T>();
+		let (item, ..) = mint_item::<T>(0);
+
+		let target: T::AccountId = account("target", 0, SEED);
+		let target_lookup = T::Lookup::unlookup(target.clone());
+	}: _(RawOrigin::Signed(caller.clone()), collection, item, target_lookup)
+	verify {
+		assert_last_event::<T>(RawEvent::Transferred(collection, item, caller, target).into());
+	}
+
+	freeze {
+		let (collection, caller, caller_lookup) = create_collection::<T>();
+		let (item, ..) = mint_item::<T>(0);
+	}: _(RawOrigin::Signed(caller.clone()), collection, item)
+	verify {
+		assert_last_event::<T>(RawEvent::Frozen(collection, item).into());
+	}
+
+	thaw {
+		let (collection, caller, caller_lookup) = create_collection::<T>();
+		let (item, ..) = mint_item::<T>(0);
+		Uniques::<T>::freeze(
+			RawOrigin::Signed(caller.clone()).into(),
+			collection,
+			item,
+		)?;
+	}: _(RawOrigin::Signed(caller.clone()), collection, item)
+	verify {
+		assert_last_event::<T>(RawEvent::Thawed(collection, item).into());
+	}
+
+	freeze_collection {
+		let (collection, caller, caller_lookup) = create_collection::<T>();
+	}: _(RawOrigin::Signed(caller.clone()), collection)
+	verify {
+		assert_last_event::<T>(RawEvent::CollectionFrozen(collection).into());
+	}
+
+	thaw_collection {
+		let (collection, caller, caller_lookup) = create_collection::<T>();
+		let origin = RawOrigin::Signed(caller.clone()).into();
+		Uniques::<T>::freeze_collection(origin, collection)?;
+	}: _(RawOrigin::Signed(caller.clone()), collection)
+	verify {
+		assert_last_event::<T>(RawEvent::CollectionThawed(collection).into());
+	}
+
+	transfer_ownership {
+		let (collection, caller, _) = create_collection::<T>();
+		let target: T::AccountId = account("target", 0, SEED);
+		let target_lookup = T::Lookup::unlookup(target.clone());
+		T::Currency::make_free_balance_be(&target, DepositBalanceOf::<T>::max_value());
+	}: _(RawOrigin::Signed(caller), collection, target_lookup)
+	verify {
+		assert_last_event::<T>(RawEvent::OwnerChanged(collection, target).into());
+	}
+
+	set_team {
+		let (collection, caller, _) = create_collection::<T>();
+		let target0 = T::Lookup::unlookup(account("target", 0, SEED));
+		let target1 = T::Lookup::unlookup(account("target", 1, SEED));
+		let target2 = T::Lookup::unlookup(account("target", 2, SEED));
+	}: _(RawOrigin::Signed(caller), collection, target0.clone(), target1.clone(), target2.clone())
+	verify {
+		assert_last_event::<T>(RawEvent::TeamChanged(
+			collection,
+			account("target", 0, SEED),
+			account("target", 1, SEED),
+			account("target", 2, SEED),
+		).into());
+	}
+
+	approve_transfer {
+		let (collection, caller, _) = create_collection::<T>();
+		let (item, ..) = mint_item::<T>(0);
+		let delegate: T::AccountId = account("delegate", 0, SEED);
+		let delegate_lookup = T::Lookup::unlookup(delegate.clone());
+	}: _(RawOrigin::Signed(caller.clone()), collection, item, delegate_lookup)
+	verify {
+		assert_last_event::<T>(RawEvent::ApprovedTransfer(collection, item, caller, delegate).into());
+	}
+
+	cancel_approval {
+		let (collection, caller, _) = create_collection::<T>();
+		let (item, ..) = mint_item::<T>(0);
+		let delegate: T::AccountId = account("delegate", 0, SEED);
+		let delegate_lookup = T::Lookup::unlookup(delegate.clone());
+		let origin = RawOrigin::Signed(caller.clone()).into();
+		Uniques::<T>::approve_transfer(origin, collection, item, delegate_lookup.clone())?;
+	}: _(RawOrigin::Signed(caller.clone()), collection, item, Some(delegate_lookup))
+	verify {
+		assert_last_event::<T>(RawEvent::ApprovalCancelled(collection, item, caller, delegate).into());
+	}
+
+	force_item_status {
+		let (collection, caller, caller_lookup) = create_collection::<T>();
+	}: _(
+		RawOrigin::Root,
+		collection,
+		caller_lookup.clone(),
+		caller_lookup.clone(),
+		caller_lookup.clone(),
+		caller_lookup,
+		true,
+		false
+	)
+	verify {
+		assert_last_event::<T>(RawEvent::ItemStatusChanged(collection).into());
+	}
+
+	set_attribute {
+		let key = vec![0u8; T::KeyLimit::get() as usize];
+		let value = vec![0u8; T::ValueLimit::get() as usize];
+
+		let (collection, caller, _) = create_collection::<T>();
+		let (item, ..) = mint_item::<T>(0);
+		add_item_metadata::<T>(item);
+	}: _(RawOrigin::Signed(caller), collection, Some(item), key.clone(), value.clone())
+	verify {
+		assert_last_event::<T>(RawEvent::AttributeSet(collection, Some(item), key, value).into());
+	}
+
+	clear_attribute {
+		let (collection, caller, _) = create_collection::<T>();
+		let (item, ..) = mint_item::<T>(0);
+		add_item_metadata::<T>(item);
+		let (key, ..) = add_item_attribute::<T>(item, 0);
+	}: _(RawOrigin::Signed(caller), collection, Some(item), key.clone())
+	verify {
+		assert_last_event::<T>(RawEvent::AttributeCleared(collection, Some(item), key).into());
+	}
+
+	set_metadata {
+		let data = vec![0u8; T::StringLimit::get() as usize];
+
+		let (collection, caller, _) = create_collection::<T>();
+		let (item, ..) = mint_item::<T>(0);
+	}: _(RawOrigin::Signed(caller), collection, item, data.clone(), false)
+	verify {
+		assert_last_event::<T>(RawEvent::MetadataSet(collection, item, data, false).into());
+	}
+
+	clear_metadata {
+		let (collection, caller, _) = create_collection::<T>();
+		let (item, ..) = mint_item::<T>(0);
+		add_item_metadata::<T>(item);
+	}: _(RawOrigin::Signed(caller), collection, item)
+	verify {
+		assert_last_event::<T>(RawEvent::MetadataCleared(collection, item).into());
+	}
+
+	set_collection_metadata {
+		let data = vec![0u8; T::StringLimit::get() as usize];
+
+		let (collection, caller, _) = create_collection::<T>();
+	}: _(RawOrigin::Signed(caller), collection, data.clone(), false)
+	verify {
+		assert_last_event::<T>(RawEvent::CollectionMetadataSet(collection, data, false).into());
+	}
+
+	clear_collection_metadata {
+		let (collection, caller, _) = create_collection::<T>();
+		add_collection_metadata::<T>();
+	}: _(RawOrigin::Signed(caller), collection)
+	verify {
+		assert_last_event::<T>(RawEvent::CollectionMetadataCleared(collection).into());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::tests::{new_test_ext, Test};
+	use frame_support::assert_ok;
+
+	#[test]
+	fn test_benchmarks() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(test_benchmark_create::<Test>());
+			assert_ok!(test_benchmark_force_create::<Test>());
+			assert_ok!(test_benchmark_destroy::<Test>());
+			assert_ok!(test_benchmark_mint::<Test>());
+			assert_ok!(test_benchmark_burn::<Test>());
+			assert_ok!(test_benchmark_transfer::<Test>());
+			assert_ok!(test_benchmark_freeze::<Test>());
+			assert_ok!(test_benchmark_thaw::<Test>());
+			assert_ok!(test_benchmark_freeze_collection::<Test>());
+			assert_ok!(test_benchmark_thaw_collection::<Test>());
+			assert_ok!(test_benchmark_transfer_ownership::<Test>());
+			assert_ok!(test_benchmark_set_team::<Test>());
+			assert_ok!(test_benchmark_approve_transfer::<Test>());
+			assert_ok!(test_benchmark_cancel_approval::<Test>());
+			assert_ok!(test_benchmark_force_item_status::<Test>());
+			assert_ok!(test_benchmark_set_attribute::<Test>());
+			assert_ok!(test_benchmark_clear_attribute::<Test>());
+			assert_ok!(test_benchmark_set_metadata::<Test>());
+			assert_ok!(test_benchmark_clear_metadata::<Test>());
+			assert_ok!(test_benchmark_set_collection_metadata::<Test>());
+			assert_ok!(test_benchmark_clear_collection_metadata::<Test>());
+		});
+	}
+}
@@ -0,0 +1,114 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementations for `nonfungibles` traits.
+
+use super::*;
+use frame_support::traits::nonfungibles::{Inspect, InspectEnumerable, Create, Mutate, Transfer};
+
+impl<T: Trait> Inspect<<T as frame_system::Trait>::AccountId> for Module<T> {
+	type CollectionId = T::CollectionId;
+	type ItemId = T::ItemId;
+
+	fn owner(collection: &Self::CollectionId, item: &Self::ItemId) -> Option<T::AccountId> {
+		Item::<T>::get(collection, item).map(|i| i.owner)
+	}
+
+	fn collection_owner(collection: &Self::CollectionId) -> Option<T::AccountId> {
+		Collection::<T>::get(collection).map(|c| c.owner)
+	}
+
+	fn attribute(
+		collection: &Self::CollectionId,
+		item: &Self::ItemId,
+		key: &[u8],
+	) -> Option<Vec<u8>> {
+		if key.is_empty() {
+			// We make the empty key map to the item metadata value.
+			ItemMetadataOf::<T>::get(collection, item).map(|m| m.data)
+		} else {
+			Attribute::<T>::get((*collection, Some(*item)), key).map(|a| a.0)
+		}
+	}
+
+	fn collection_attribute(collection: &Self::CollectionId, key: &[u8]) -> Option<Vec<u8>> {
+		if key.is_empty() {
+			// We make the empty key map to the collection metadata value.
+			CollectionMetadataOf::<T>::get(collection).map(|m| m.data)
+		} else {
+			Attribute::<T>::get((*collection, Option::<T::ItemId>::None), key).map(|a| a.0)
+		}
+	}
+
+	fn can_transfer(collection: &Self::CollectionId, item: &Self::ItemId) -> bool {
+		match (Collection::<T>::get(collection), Item::<T>::get(collection, item)) {
+			(Some(c), Some(i)) if !c.is_frozen && !i.is_frozen => true,
+			_ => false,
+		}
+	}
+}
+
+impl<T: Trait> InspectEnumerable<T::AccountId> for Module<T> {
+	fn items(collection: &Self::CollectionId) -> Box<dyn Iterator<Item = Self::ItemId>> {
+		Box::new(Item::<T>::iter_prefix(collection).map(|(item, _)| item))
+	}
+
+	fn owned(who: &T::AccountId) -> Box<dyn Iterator<Item = (Self::CollectionId, Self::ItemId)>> {
+		Box::new(Account::<T>::iter_prefix(who).map(|(key, _)| key))
+	}
+}
+
+impl<T: Trait> Create<<T as frame_system::Trait>::AccountId> for Module<T> {
+	fn create_collection(
+		collection: &Self::CollectionId,
+		who: &T::AccountId,
+		admin: &T::AccountId,
+	) -> DispatchResult {
+		Self::do_create_collection(
+			*collection,
+			who.clone(),
+			admin.clone(),
+			T::CollectionDeposit::get(),
+			false,
+			RawEvent::Created(*collection, who.clone(), admin.clone()),
+		)
+	}
+}
+
+impl<T: Trait> Mutate<<T as frame_system::Trait>::AccountId> for Module<T> {
+	fn mint_into(
+		collection: &Self::CollectionId,
+		item: &Self::ItemId,
+		who: &T::AccountId,
+	) -> DispatchResult {
+		Self::do_mint(*collection, *item, who.clone(), |_| Ok(()))
+	}
+
+	fn burn_from(collection: &Self::CollectionId, item: &Self::ItemId) -> DispatchResult {
+		Self::do_burn(*collection, *item, |_, _| Ok(()))
+	}
+}
+
+impl<T: Trait> Transfer<T::AccountId> for Module<T> {
+	fn transfer(
+		collection: &Self::CollectionId,
+		item: &Self::ItemId,
+		destination: &T::AccountId,
+	) -> DispatchResult {
+		Self::do_transfer(*collection, *item, destination.clone(), |_, _| Ok(()))
+	}
+}
@@ -0,0 +1,1405 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Uniques Module
+//!
+//! A simple, secure module for dealing with non-fungible assets.
+//!
+//! - [`uniques::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! The Uniques module provides functionality for non-fungible asset management, including:
+//!
+//! * Collection creation and destruction.
+//! * Item minting, burning and transfer.
+//! * Delegated transfer approvals.
+//! * Freezing of single items or whole collections.
+//! * On-chain metadata and key-value attributes for collections and items.
+//!
+//! ### Terminology
+//!
+//! * **Collection:** A set of non-fungible items, identified by a `CollectionId`.
+//! * **Item:** A single unique asset within a collection, identified by an `ItemId`.
+//! * **Owner:** The account which owns a collection or an item. Only the collection owner may
+//!   destroy it, change its team, and set its metadata and attributes.
+//! * **Issuer:** The account which may mint new items into a collection.
+//! * **Admin:** The account which may transfer and burn any item of a collection, as well as
+//!   thaw frozen items.
+//! * **Freezer:** The account which may freeze items and collections.
+//! * **Deposit:** An amount reserved from the collection owner for every piece of state the
+//!   collection occupies: the collection itself, its items, metadata and attributes. Collections
+//!   created by the `ForceOrigin` may be marked as _free holding_, in which case no deposit is
+//!   taken for items.
+//!
+//! ## Interface
+//!
+//! ### Permissionless Functions
+//!
+//! * `create`: Create a new collection, reserving `CollectionDeposit`.
+//!
+//! ### Permissioned Functions
+//!
+//! * `destroy`: Destroy a collection with all of its items. Called by the owner.
+//! * `mint`: Mint a new item. Called by the issuer.
+//! * `burn`: Burn an item. Called by the admin or the item owner.
+//! * `transfer`: Transfer an item. Called by the admin, the item owner or its approved delegate.
+//! * `freeze`/`thaw`: Freeze or thaw an item. Called by the freezer and the admin, respectively.
+//! * `freeze_collection`/`thaw_collection`: Freeze or thaw a collection. Called by the freezer
+//!   and the admin, respectively.
+//! * `transfer_ownership`: Transfer the ownership and the deposit of a collection. Called by the
+//!   owner.
+//! * `set_team`: Change the issuer, admin and freezer of a collection. Called by the owner.
+//! * `approve_transfer`/`cancel_approval`: Manage the delegate of an item. Called by the item
+//!   owner, or the admin.
+//! * `set_attribute`/`clear_attribute`: Manage attributes. Called by the owner.
+//! * `set_metadata`/`clear_metadata`: Manage item metadata. Called by the owner.
+//! * `set_collection_metadata`/`clear_collection_metadata`: Manage collection metadata. Called by
+//!   the owner.
+//!
+//! `destroy`, `approve_transfer`/`cancel_approval` and the metadata and attribute functions may
+//! also be called by the `ForceOrigin`, which is able to alter frozen metadata and attributes.
+//!
+//! ### Privileged Functions
+//!
+//! * `force_create`: Create a new collection without taking a deposit.
+//! * `force_item_status`: Alter the team, owner and status of a collection.
+//!
+//! The module also implements the traits in
+//! [`frame_support::traits::nonfungibles`](../frame_support/traits/nonfungibles/index.html).
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+// Ensure we're `no_std` when compiling for Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod tests;
+mod benchmarking;
+mod impl_nonfungibles;
+pub mod weights;
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode, HasCompact};
+use sp_runtime::{DispatchResult, traits::{Zero, StaticLookup, Saturating}};
+use frame_support::{
+	decl_module, decl_event, decl_error, decl_storage, ensure, transactional, Parameter,
+	RuntimeDebug,
+	traits::{Get, Currency, ReservableCurrency, EnsureOrigin, BalanceStatus},
+	dispatch::DispatchError,
+	storage::{IterableStorageDoubleMap, StorageDoubleMap},
+};
+use frame_system::ensure_signed;
+pub use weights::WeightInfo;
+
+type DepositBalanceOf<T> =
+	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+/// Configuration trait.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// Identifier for a collection of items.
+	type CollectionId: Parameter + HasCompact + Copy + Default;
+
+	/// The type used to identify a unique item within a collection.
+	type ItemId: Parameter + HasCompact + Copy + Default + From<u32>;
+
+	/// The currency mechanism, used for paying for reserves.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The origin which may forcibly create or destroy a collection or otherwise alter privileged
+	/// attributes.
+	type ForceOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The basic amount of funds that must be reserved for a collection.
+	type CollectionDeposit: Get<DepositBalanceOf<Self>>;
+
+	/// The basic amount of funds that must be reserved for an item.
+	type ItemDeposit: Get<DepositBalanceOf<Self>>;
+
+	/// The basic amount of funds that must be reserved when adding metadata to a collection or
+	/// an item.
+	type MetadataDepositBase: Get<DepositBalanceOf<Self>>;
+
+	/// The basic amount of funds that must be reserved when adding an attribute to a collection
+	/// or an item.
+	type AttributeDepositBase: Get<DepositBalanceOf<Self>>;
+
+	/// The additional funds that must be reserved for the number of bytes stored in metadata,
+	/// either "normal" metadata or attribute metadata.
+	type DepositPerByte: Get<DepositBalanceOf<Self>>;
+
+	/// The maximum length of data stored on-chain as metadata.
+	type StringLimit: Get<u32>;
+
+	/// The maximum length of an attribute key.
+	type KeyLimit: Get<u32>;
+
+	/// The maximum length of an attribute value.
+	type ValueLimit: Get<u32>;
+
+	/// Weight information for extrinsics in this pallet.
+	type WeightInfo: WeightInfo;
+}
+
+/// The details of a collection.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct CollectionDetails<AccountId, DepositBalance> {
+	/// Can change `owner`, `issuer`, `freezer` and `admin` accounts.
+	pub owner: AccountId,
+	/// Can mint tokens.
+	pub issuer: AccountId,
+	/// Can thaw tokens, force transfers and burn tokens from any account.
+	pub admin: AccountId,
+	/// Can freeze tokens.
+	pub freezer: AccountId,
+	/// The total balance deposited for all storage associated with this collection. Used by
+	/// `destroy`.
+	pub total_deposit: DepositBalance,
+	/// If `true`, then no deposit is needed to hold items of this collection.
+	pub free_holding: bool,
+	/// The total number of outstanding items of this collection.
+	pub items: u32,
+	/// The total number of outstanding item metadata of this collection.
+	pub item_metadatas: u32,
+	/// The total number of attributes for this collection.
+	pub attributes: u32,
+	/// Whether the collection is frozen for non-admin transfers.
+	pub is_frozen: bool,
+}
+
+/// Witness data for the destroy transactions.
+#[derive(Copy, Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct DestroyWitness {
+	/// The total number of outstanding items of this collection.
+	#[codec(compact)]
+	pub items: u32,
+	/// The total number of outstanding item metadata of this collection.
+	#[codec(compact)]
+	pub item_metadatas: u32,
+	/// The total number of attributes for this collection.
+	#[codec(compact)]
+	pub attributes: u32,
+}
+
+impl<AccountId, DepositBalance> CollectionDetails<AccountId, DepositBalance> {
+	/// The witness which must be provided to destroy this collection.
+	pub fn destroy_witness(&self) -> DestroyWitness {
+		DestroyWitness {
+			items: self.items,
+			item_metadatas: self.item_metadatas,
+			attributes: self.attributes,
+		}
+	}
+}
+
+/// Information concerning the ownership of a single unique item.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+pub struct ItemDetails<AccountId, DepositBalance> {
+	/// The owner of this item.
+	pub owner: AccountId,
+	/// The approved transferrer of this item, if one is set.
+	pub approved: Option<AccountId>,
+	/// Whether the item can be transferred or not.
+	pub is_frozen: bool,
+	/// The amount reserved from the owner of the collection for this item. Free-hold items will
+	/// have this as zero.
+	pub deposit: DepositBalance,
+	/// The number of attributes of this item.
+	pub attributes: u32,
+}
+
+/// Metadata of a collection or an item.
+#[derive(Clone, Encode, Decode, Eq, PartialEq, Default, RuntimeDebug)]
+pub struct Metadata<DepositBalance> {
+	/// The balance deposited for this metadata.
+	///
+	/// This pays for the data stored in this struct.
+	pub deposit: DepositBalance,
+	/// General information concerning this collection or item. Limited in length by
+	/// `StringLimit`. This will generally be either a JSON dump or the hash of some JSON which
+	/// can be found on a hash-addressable global publication system such as IPFS.
+	pub data: Vec<u8>,
+	/// Whether the metadata may be changed by a non Force origin.
+	pub is_frozen: bool,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Uniques {
+		/// Details of a collection.
+		pub Collection get(fn collection):
+			map hasher(blake2_128_concat) T::CollectionId
+			=> Option<CollectionDetails<T::AccountId, DepositBalanceOf<T>>>;
+
+		/// The items held by any given account; set out this way so that items owned by a single
+		/// account can be enumerated.
+		pub Account:
+			double_map hasher(blake2_128_concat) T::AccountId,
+			hasher(blake2_128_concat) (T::CollectionId, T::ItemId)
+			=> Option<()>;
+
+		/// The items in existence and their ownership details.
+		pub Item get(fn item):
+			double_map hasher(blake2_128_concat) T::CollectionId, hasher(blake2_128_concat) T::ItemId
+			=> Option<ItemDetails<T::AccountId, DepositBalanceOf<T>>>;
+
+		/// Metadata of a collection.
+		pub CollectionMetadataOf get(fn collection_metadata):
+			map hasher(blake2_128_concat) T::CollectionId => Option<Metadata<DepositBalanceOf<T>>>;
+
+		/// Metadata of an item.
+		pub ItemMetadataOf get(fn item_metadata):
+			double_map hasher(blake2_128_concat) T::CollectionId, hasher(blake2_128_concat) T::ItemId
+			=> Option<Metadata<DepositBalanceOf<T>>>;
+
+		/// Attributes of a collection, or of an item if the item is given.
+		pub Attribute:
+			double_map hasher(blake2_128_concat) (T::CollectionId, Option<T::ItemId>),
+			hasher(blake2_128_concat) Vec<u8>
+			=> Option<(Vec<u8>, DepositBalanceOf<T>)>;
+	}
+}
+
+decl_event! {
+	/// Events type.
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		CollectionId = <T as Trait>::CollectionId,
+		ItemId = <T as Trait>::ItemId,
+	{
+		/// A collection was created. \[collection, creator, owner\]
+		Created(CollectionId, AccountId, AccountId),
+		/// A collection was force-created. \[collection, owner\]
+		ForceCreated(CollectionId, AccountId),
+		/// A collection was destroyed. \[collection\]
+		Destroyed(CollectionId),
+		/// An item was issued. \[collection, item, owner\]
+		Issued(CollectionId, ItemId, AccountId),
+		/// An item was transferred. \[collection, item, from, to\]
+		Transferred(CollectionId, ItemId, AccountId, AccountId),
+		/// An item was destroyed. \[collection, item, owner\]
+		Burned(CollectionId, ItemId, AccountId),
+		/// Some item was frozen. \[collection, item\]
+		Frozen(CollectionId, ItemId),
+		/// Some item was thawed. \[collection, item\]
+		Thawed(CollectionId, ItemId),
+		/// Some collection was frozen. \[collection\]
+		CollectionFrozen(CollectionId),
+		/// Some collection was thawed. \[collection\]
+		CollectionThawed(CollectionId),
+		/// The owner changed. \[collection, new_owner\]
+		OwnerChanged(CollectionId, AccountId),
+		/// The management team changed. \[collection, issuer, admin, freezer\]
+		TeamChanged(CollectionId, AccountId, AccountId, AccountId),
+		/// An item of a collection has been approved by the owner for transfer by a delegate.
+		/// \[collection, item, owner, delegate\]
+		ApprovedTransfer(CollectionId, ItemId, AccountId, AccountId),
+		/// An approval for a delegate account to transfer an item of a collection was cancelled
+		/// by its owner. \[collection, item, owner, delegate\]
+		ApprovalCancelled(CollectionId, ItemId, AccountId, AccountId),
+		/// A collection has had its attributes changed by the `Force` origin. \[collection\]
+		ItemStatusChanged(CollectionId),
+		/// New metadata has been set for a collection. \[collection, data, is_frozen\]
+		CollectionMetadataSet(CollectionId, Vec<u8>, bool),
+		/// Metadata has been cleared for a collection. \[collection\]
+		CollectionMetadataCleared(CollectionId),
+		/// New metadata has been set for an item. \[collection, item, data, is_frozen\]
+		MetadataSet(CollectionId, ItemId, Vec<u8>, bool),
+		/// Metadata has been cleared for an item. \[collection, item\]
+		MetadataCleared(CollectionId, ItemId),
+		/// New attribute metadata has been set for a collection or an item.
+		/// \[collection, maybe_item, key, value\]
+		AttributeSet(CollectionId, Option<ItemId>, Vec<u8>, Vec<u8>),
+		/// Attribute metadata has been cleared for a collection or an item.
+		/// \[collection, maybe_item, key\]
+		AttributeCleared(CollectionId, Option<ItemId>, Vec<u8>),
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The signing account has no permission to do the operation.
+		NoPermission,
+		/// The given collection or item is unknown.
+		Unknown,
+		/// The collection or item ID has already been used.
+		AlreadyExists,
+		/// The owner turned out to be different to what was expected.
+		WrongOwner,
+		/// Invalid witness data given.
+		BadWitness,
+		/// The item or collection is frozen.
+		Frozen,
+		/// The deposit of the collection could not be moved to the new owner in full.
+		DepositNotMoved,
+		/// The delegate turned out to be different to what was expected.
+		WrongDelegate,
+		/// There is no delegate approved.
+		NoDelegate,
+		/// The given metadata, attribute key or attribute value is too long.
+		BadMetadata,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// Deposit one of this module's events by using the default implementation.
+		fn deposit_event() = default;
+
+		/// The basic amount of funds that must be reserved for a collection.
+		const CollectionDeposit: DepositBalanceOf<T> = T::CollectionDeposit::get();
+
+		/// The basic amount of funds that must be reserved for an item.
+		const ItemDeposit: DepositBalanceOf<T> = T::ItemDeposit::get();
+
+		/// The basic amount of funds that must be reserved when adding metadata.
+		const MetadataDepositBase: DepositBalanceOf<T> = T::MetadataDepositBase::get();
+
+		/// The basic amount of funds that must be reserved when adding an attribute.
+		const AttributeDepositBase: DepositBalanceOf<T> = T::AttributeDepositBase::get();
+
+		/// The additional funds that must be reserved per byte of metadata or attribute.
+		const DepositPerByte: DepositBalanceOf<T> = T::DepositPerByte::get();
+
+		/// The maximum length of data stored on-chain as metadata.
+		const StringLimit: u32 = T::StringLimit::get();
+
+		/// The maximum length of an attribute key.
+		const KeyLimit: u32 = T::KeyLimit::get();
+
+		/// The maximum length of an attribute value.
+		const ValueLimit: u32 = T::ValueLimit::get();
+
+		/// Issue a new collection of non-fungible items from a public origin.
+		///
+		/// This new collection has no items initially and its owner is the origin.
+		///
+		/// The origin must be Signed and the sender must have sufficient funds free.
+		///
+		/// `CollectionDeposit` funds of sender are reserved.
+		///
+		/// Parameters:
+		/// - `collection`: The identifier of the new collection. This must not be currently in use.
+		/// - `admin`: The admin of this collection. The admin is the initial address of each
+		/// member of the collection's admin team.
+		///
+		/// Emits `Created` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::create()]
+		fn create(origin,
+			#[compact] collection: T::CollectionId,
+			admin: <T::Lookup as StaticLookup>::Source,
+		) {
+			let owner = ensure_signed(origin)?;
+			let admin = T::Lookup::lookup(admin)?;
+
+			Self::do_create_collection(
+				collection,
+				owner.clone(),
+				admin.clone(),
+				T::CollectionDeposit::get(),
+				false,
+				RawEvent::Created(collection, owner, admin),
+			)?;
+		}
+
+		/// Issue a new collection of non-fungible items from a privileged origin.
+		///
+		/// This new collection has no items initially.
+		///
+		/// The origin must conform to `ForceOrigin`.
+		///
+		/// Unlike `create`, no funds are reserved.
+		///
+		/// - `collection`: The identifier of the new collection. This must not be currently in use.
+		/// - `owner`: The owner of this collection. The owner has full superuser permissions
+		/// over this collection, but may later change and configure the permissions using
+		/// `transfer_ownership` and `set_team`.
+		/// - `free_holding`: Whether a deposit is taken for holding an item of this collection.
+		///
+		/// Emits `ForceCreated` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::force_create()]
+		fn force_create(origin,
+			#[compact] collection: T::CollectionId,
+			owner: <T::Lookup as StaticLookup>::Source,
+			free_holding: bool,
+		) {
+			T::ForceOrigin::ensure_origin(origin)?;
+			let owner = T::Lookup::lookup(owner)?;
+
+			Self::do_create_collection(
+				collection,
+				owner.clone(),
+				owner.clone(),
+				Zero::zero(),
+				free_holding,
+				RawEvent::ForceCreated(collection, owner),
+			)?;
+		}
+
+		/// Destroy a collection of non-fungible items.
+		///
+		/// The origin must conform to `ForceOrigin` or must be `Signed` and the sender must be
+		/// the owner of the `collection`.
+		///
+		/// - `collection`: The identifier of the collection to be destroyed.
+		/// - `witness`: Information on the items minted in the collection. This must be correct.
+		///
+		/// Emits `Destroyed` event when successful.
+		///
+		/// Weight: `O(n + m + a)` where:
+		/// - `n = witness.items`
+		/// - `m = witness.item_metadatas`
+		/// - `a = witness.attributes`
+		#[weight = T::WeightInfo::destroy(witness.items, witness.item_metadatas, witness.attributes)]
+		fn destroy(origin, #[compact] collection: T::CollectionId, witness: DestroyWitness) {
+			let maybe_check_owner = Self::ensure_force_or_signed(origin)?;
+
+			let details = Collection::<T>::get(collection).ok_or(Error::<T>::Unknown)?;
+			if let Some(check_owner) = maybe_check_owner {
+				ensure!(details.owner == check_owner, Error::<T>::NoPermission);
+			}
+			ensure!(details.destroy_witness() == witness, Error::<T>::BadWitness);
+
+			for (item, details) in Item::<T>::drain_prefix(collection) {
+				Account::<T>::remove(&details.owner, (collection, item));
+				Attribute::<T>::remove_prefix((collection, Some(item)));
+			}
+			ItemMetadataOf::<T>::remove_prefix(collection);
+			CollectionMetadataOf::<T>::remove(collection);
+			Attribute::<T>::remove_prefix((collection, Option::<T::ItemId>::None));
+			Collection::<T>::remove(collection);
+			T::Currency::unreserve(&details.owner, details.total_deposit);
+
+			Self::deposit_event(RawEvent::Destroyed(collection));
+		}
+
+		/// Mint an item of a particular collection.
+		///
+		/// The origin must be Signed and the sender must be the Issuer of the collection.
+		///
+		/// - `collection`: The collection of the item to be minted.
+		/// - `item`: The item value of the item to be minted.
+		/// - `owner`: The initial owner of the minted item.
+		///
+		/// Emits `Issued` event when successful.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::mint()]
+		fn mint(origin,
+			#[compact] collection: T::CollectionId,
+			#[compact] item: T::ItemId,
+			owner: <T::Lookup as StaticLookup>::Source,
+		) {
+			let origin = ensure_signed(origin)?;
+			let owner = T::Lookup::lookup(owner)?;
+
+			Self::do_mint(collection, item, owner, |details| {
+				ensure!(details.issuer == origin, Error::<T>::NoPermission);
+				Ok(())
+			})?;
+		}
+
+		/// Destroy a single item.
+		///
+		/// The origin must be Signed and the signing account must be either:
+		/// - the Admin of the `collection`;
+		/// - the Owner of the `item`;
+		///
+		/// - `collection`: The collection of the item to be burned.
+		/// - `item`: The item of the item to be burned.
+		/// - `check_owner`: If `Some` then the operation will fail with `WrongOwner` unless the
+		///   item is owned by this value.
+		/// - `attributes`: An upper bound on the number of attributes of the item. The operation
+		///   will fail with `BadWitness` if the item has more attributes.
+		///
+		/// Any metadata and attributes of the item are cleared and their deposits are returned to
+		/// the owner of the `collection`.
+		///
+		/// Emits `Burned` with the actual amount burned.
+		///
+		/// Weight: `O(a)` where `a = attributes`.
+		#[weight = T::WeightInfo::burn(*attributes)]
+		fn burn(origin,
+			#[compact] collection: T::CollectionId,
+			#[compact] item: T::ItemId,
+			check_owner: Option<<T::Lookup as StaticLookup>::Source>,
+			#[compact] attributes: u32,
+		) {
+			let origin = ensure_signed(origin)?;
+			let check_owner = check_owner.map(T::Lookup::lookup).transpose()?;
+
+			Self::do_burn(collection, item, |collection_details, details| {
+				let is_permitted = collection_details.admin == origin || details.owner == origin;
+				ensure!(is_permitted, Error::<T>::NoPermission);
+				ensure!(
+					check_owner.map_or(true, |o| o == details.owner),
+					Error::<T>::WrongOwner,
+				);
+				ensure!(details.attributes <= attributes, Error::<T>::BadWitness);
+				Ok(())
+			})?;
+		}
+
+		/// Move an item from the sender account to another.
+		///
+		/// Origin must be Signed and the signing account must be either:
+		/// - the Admin of the `collection`;
+		/// - the Owner of the `item`;
+		/// - the approved delegate for the `item`.
+		///
+		/// Any approved delegate of the `item` is reset by the transfer.
+		///
+		/// Arguments:
+		/// - `collection`: The collection of the item to be transferred.
+		/// - `item`: The item of the item to be transferred.
+		/// - `dest`: The account to receive ownership of the item.
+		///
+		/// Emits `Transferred`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::transfer()]
+		fn transfer(origin,
+			#[compact] collection: T::CollectionId,
+			#[compact] item: T::ItemId,
+			dest: <T::Lookup as StaticLookup>::Source,
+		) {
+			let origin = ensure_signed(origin)?;
+			let dest = T::Lookup::lookup(dest)?;
+
+			Self::do_transfer(collection, item, dest, |collection_details, details| {
+				if details.owner != origin && collection_details.admin != origin {
+					let approved = details.approved.as_ref().map_or(false, |i| i == &origin);
+					ensure!(approved, Error::<T>::NoPermission);
+				}
+				Ok(())
+			})?;
+		}
+
+		/// Disallow further unprivileged transfer of an item.
+		///
+		/// Origin must be Signed and the sender should be the Freezer of the `collection`.
+		///
+		/// - `collection`: The collection of the item to be frozen.
+		/// - `item`: The item of the item to be frozen.
+		///
+		/// Emits `Frozen`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::freeze()]
+		fn freeze(origin, #[compact] collection: T::CollectionId, #[compact] item: T::ItemId) {
+			let origin = ensure_signed(origin)?;
+
+			let mut details = Item::<T>::get(collection, item).ok_or(Error::<T>::Unknown)?;
+			let collection_details = Collection::<T>::get(collection).ok_or(Error::<T>::Unknown)?;
+			ensure!(collection_details.freezer == origin, Error::<T>::NoPermission);
+
+			details.is_frozen = true;
+			Item::<T>::insert(collection, item, &details);
+
+			Self::deposit_event(RawEvent::Frozen(collection, item));
+		}
+
+		/// Re-allow unprivileged transfer of an item.
+		///
+		/// Origin must be Signed and the sender should be the Admin of the `collection`.
+		///
+		/// - `collection`: The collection of the item to be thawed.
+		/// - `item`: The item of the item to be thawed.
+		///
+		/// Emits `Thawed`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::thaw()]
+		fn thaw(origin, #[compact] collection: T::CollectionId, #[compact] item: T::ItemId) {
+			let origin = ensure_signed(origin)?;
+
+			let mut details = Item::<T>::get(collection, item).ok_or(Error::<T>::Unknown)?;
+			let collection_details = Collection::<T>::get(collection).ok_or(Error::<T>::Unknown)?;
+			ensure!(collection_details.admin == origin, Error::<T>::NoPermission);
+
+			details.is_frozen = false;
+			Item::<T>::insert(collection, item, &details);
+
+			Self::deposit_event(RawEvent::Thawed(collection, item));
+		}
+
+		/// Disallow further unprivileged transfers for a whole collection.
+		///
+		/// Origin must be Signed and the sender should be the Freezer of the `collection`.
+		///
+		/// - `collection`: The collection to be frozen.
+		///
+		/// Emits `CollectionFrozen`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::freeze_collection()]
+		fn freeze_collection(origin, #[compact] collection: T::CollectionId) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			Collection::<T>::try_mutate(collection, |maybe_details| {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				ensure!(details.freezer == origin, Error::<T>::NoPermission);
+
+				details.is_frozen = true;
+
+				Self::deposit_event(RawEvent::CollectionFrozen(collection));
+				Ok(())
+			})
+		}
+
+		/// Re-allow unprivileged transfers for a whole collection.
+		///
+		/// Origin must be Signed and the sender should be the Admin of the `collection`.
+		///
+		/// - `collection`: The collection to be thawed.
+		///
+		/// Emits `CollectionThawed`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::thaw_collection()]
+		fn thaw_collection(origin, #[compact] collection: T::CollectionId) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
+
+			Collection::<T>::try_mutate(collection, |maybe_details| {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				ensure!(details.admin == origin, Error::<T>::NoPermission);
+
+				details.is_frozen = false;
+
+				Self::deposit_event(RawEvent::CollectionThawed(collection));
+				Ok(())
+			})
+		}
+
+		/// Change the Owner of a collection.
+		///
+		/// Origin must be Signed and the sender should be the Owner of the `collection`.
+		///
+		/// - `collection`: The collection whose owner should be changed.
+		/// - `owner`: The new Owner of this collection. They must have enough free funds to take
+		///   over the deposit of the collection.
+		///
+		/// Emits `OwnerChanged`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::transfer_ownership()]
+		#[transactional]
+		fn transfer_ownership(origin,
+			#[compact] collection: T::CollectionId,
+			owner: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
+			let owner = T::Lookup::lookup(owner)?;
+
+			Collection::<T>::try_mutate(collection, |maybe_details| {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				ensure!(details.owner == origin, Error::<T>::NoPermission);
+				if details.owner == owner {
+					return Ok(());
+				}
+
+				// Move the deposit to the new owner.
+				let remainder = T::Currency::repatriate_reserved(
+					&details.owner,
+					&owner,
+					details.total_deposit,
+					BalanceStatus::Reserved,
+				)?;
+				ensure!(remainder.is_zero(), Error::<T>::DepositNotMoved);
+				details.owner = owner.clone();
+
+				Self::deposit_event(RawEvent::OwnerChanged(collection, owner));
+				Ok(())
+			})
+		}
+
+		/// Change the Issuer, Admin and Freezer of a collection.
+		///
+		/// Origin must be Signed and the sender should be the Owner of the `collection`.
+		///
+		/// - `collection`: The collection whose team should be changed.
+		/// - `issuer`: The new Issuer of this collection.
+		/// - `admin`: The new Admin of this collection.
+		/// - `freezer`: The new Freezer of this collection.
+		///
+		/// Emits `TeamChanged`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::set_team()]
+		fn set_team(origin,
+			#[compact] collection: T::CollectionId,
+			issuer: <T::Lookup as StaticLookup>::Source,
+			admin: <T::Lookup as StaticLookup>::Source,
+			freezer: <T::Lookup as StaticLookup>::Source,
+		) -> DispatchResult {
+			let origin = ensure_signed(origin)?;
+			let issuer = T::Lookup::lookup(issuer)?;
+			let admin = T::Lookup::lookup(admin)?;
+			let freezer = T::Lookup::lookup(freezer)?;
+
+			Collection::<T>::try_mutate(collection, |maybe_details| {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+				ensure!(details.owner == origin, Error::<T>::NoPermission);
+
+				details.issuer = issuer.clone();
+				details.admin = admin.clone();
+				details.freezer = freezer.clone();
+
+				Self::deposit_event(RawEvent::TeamChanged(collection, issuer, admin, freezer));
+				Ok(())
+			})
+		}
+
+		/// Approve an item to be transferred by a delegated third-party account.
+		///
+		/// Origin must be Signed and must be the owner of the `item` or the Admin of the
+		/// `collection`.
+		///
+		/// - `collection`: The collection of the item to be approved for delegated transfer.
+		/// - `item`: The item of the item to be approved for delegated transfer.
+		/// - `delegate`: The account to delegate permission to transfer the item.
+		///
+		/// Emits `ApprovedTransfer` on success.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::approve_transfer()]
+		fn approve_transfer(origin,
+			#[compact] collection: T::CollectionId,
+			#[compact] item: T::ItemId,
+			delegate: <T::Lookup as StaticLookup>::Source,
+		) {
+			let maybe_check = Self::ensure_force_or_signed(origin)?;
+			let delegate = T::Lookup::lookup(delegate)?;
+
+			let collection_details = Collection::<T>::get(collection).ok_or(Error::<T>::Unknown)?;
+			let mut details = Item::<T>::get(collection, item).ok_or(Error::<T>::Unknown)?;
+
+			if let Some(check) = maybe_check {
+				let permitted = check == collection_details.admin || check == details.owner;
+				ensure!(permitted, Error::<T>::NoPermission);
+			}
+
+			details.approved = Some(delegate.clone());
+			Item::<T>::insert(collection, item, &details);
+
+			Self::deposit_event(RawEvent::ApprovedTransfer(collection, item, details.owner, delegate));
+		}
+
+		/// Cancel the prior approval for the transfer of an item by a delegate.
+		///
+		/// Origin must be either:
+		/// - the `Force` origin;
+		/// - `Signed` with the signer being the Admin of the `collection`;
+		/// - `Signed` with the signer being the Owner of the `item`;
+		///
+		/// Arguments:
+		/// - `collection`: The collection of the item of whose approval will be cancelled.
+		/// - `item`: The item of the item of whose approval will be cancelled.
+		/// - `maybe_check_delegate`: If `Some` will ensure that the given account is the one to
+		///   which permission of transfer is delegated.
+		///
+		/// Emits `ApprovalCancelled` on success.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::cancel_approval()]
+		fn cancel_approval(origin,
+			#[compact] collection: T::CollectionId,
+			#[compact] item: T::ItemId,
+			maybe_check_delegate: Option<<T::Lookup as StaticLookup>::Source>,
+		) {
+			let maybe_check = Self::ensure_force_or_signed(origin)?;
+
+			let collection_details = Collection::<T>::get(collection).ok_or(Error::<T>::Unknown)?;
+			let mut details = Item::<T>::get(collection, item).ok_or(Error::<T>::Unknown)?;
+			if let Some(check) = maybe_check {
+				let permitted = check == collection_details.admin || check == details.owner;
+				ensure!(permitted, Error::<T>::NoPermission);
+			}
+			let old = details.approved.take().ok_or(Error::<T>::NoDelegate)?;
+			if let Some(check_delegate) = maybe_check_delegate {
+				let check_delegate = T::Lookup::lookup(check_delegate)?;
+				ensure!(check_delegate == old, Error::<T>::WrongDelegate);
+			}
+
+			Item::<T>::insert(collection, item, &details);
+			Self::deposit_event(RawEvent::ApprovalCancelled(collection, item, details.owner, old));
+		}
+
+		/// Alter the attributes of a given collection.
+		///
+		/// Origin must be `ForceOrigin`.
+		///
+		/// - `collection`: The identifier of the collection.
+		/// - `owner`: The new Owner of this collection.
+		/// - `issuer`: The new Issuer of this collection.
+		/// - `admin`: The new Admin of this collection.
+		/// - `freezer`: The new Freezer of this collection.
+		/// - `free_holding`: Whether a deposit is taken for holding an item of this collection.
+		/// - `is_frozen`: Whether this collection is frozen except for permissioned/admin
+		/// instructions.
+		///
+		/// Emits `ItemStatusChanged` with the identity of the collection.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::force_item_status()]
+		fn force_item_status(origin,
+			#[compact] collection: T::CollectionId,
+			owner: <T::Lookup as StaticLookup>::Source,
+			issuer: <T::Lookup as StaticLookup>::Source,
+			admin: <T::Lookup as StaticLookup>::Source,
+			freezer: <T::Lookup as StaticLookup>::Source,
+			free_holding: bool,
+			is_frozen: bool,
+		) -> DispatchResult {
+			T::ForceOrigin::ensure_origin(origin)?;
+			let owner = T::Lookup::lookup(owner)?;
+			let issuer = T::Lookup::lookup(issuer)?;
+			let admin = T::Lookup::lookup(admin)?;
+			let freezer = T::Lookup::lookup(freezer)?;
+
+			Collection::<T>::try_mutate(collection, |maybe_details| {
+				let details = maybe_details.as_mut().ok_or(Error::<T>::Unknown)?;
+
+				if details.owner != owner {
+					// Whatever part of the deposit can not be moved stays with the old owner.
+					let _ = T::Currency::repatriate_reserved(
+						&details.owner,
+						&owner,
+						details.total_deposit,
+						BalanceStatus::Reserved,
+					);
+				}
+				details.owner = owner;
+				details.issuer = issuer;
+				details.admin = admin;
+				details.freezer = freezer;
+				details.free_holding = free_holding;
+				details.is_frozen = is_frozen;
+
+				Self::deposit_event(RawEvent::ItemStatusChanged(collection));
+				Ok(())
+			})
+		}
+
+		/// Set an attribute for a collection or item.
+		///
+		/// Origin must be either `ForceOrigin` or Signed and the sender should be the Owner of the
+		/// `collection`.
+		///
+		/// If the origin is Signed, then funds of signer are reserved according to the formula:
+		/// `AttributeDepositBase + DepositPerByte * (key.len + value.len)` taking into
+		/// account any already reserved funds.
+		///
+		/// - `collection`: The identifier of the collection whose item's metadata to set.
+		/// - `maybe_item`: The identifier of the item whose metadata to set.
+		/// - `key`: The key of the attribute.
+		/// - `value`: The value to which to set the attribute.
+		///
+		/// Emits `AttributeSet`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::set_attribute()]
+		fn set_attribute(origin,
+			#[compact] collection: T::CollectionId,
+			maybe_item: Option<T::ItemId>,
+			key: Vec<u8>,
+			value: Vec<u8>,
+		) {
+			let maybe_check_owner = Self::ensure_force_or_signed(origin)?;
+
+			ensure!(key.len() <= T::KeyLimit::get() as usize, Error::<T>::BadMetadata);
+			ensure!(value.len() <= T::ValueLimit::get() as usize, Error::<T>::BadMetadata);
+
+			let mut collection_details = Collection::<T>::get(collection)
+				.ok_or(Error::<T>::Unknown)?;
+			if let Some(check_owner) = &maybe_check_owner {
+				ensure!(check_owner == &collection_details.owner, Error::<T>::NoPermission);
+			}
+			let mut maybe_item_details = match maybe_item {
+				Some(item) => Some(Item::<T>::get(collection, item).ok_or(Error::<T>::Unknown)?),
+				None => None,
+			};
+			Self::ensure_metadata_unfrozen(collection, maybe_item, maybe_check_owner.is_some())?;
+
+			let attribute = Attribute::<T>::get((collection, maybe_item), &key);
+			if attribute.is_none() {
+				collection_details.attributes = collection_details.attributes.saturating_add(1);
+				if let Some(details) = maybe_item_details.as_mut() {
+					details.attributes = details.attributes.saturating_add(1);
+				}
+			}
+			let old_deposit = attribute.map_or(Zero::zero(), |m| m.1);
+			collection_details.total_deposit = collection_details.total_deposit
+				.saturating_sub(old_deposit);
+			let mut deposit = Zero::zero();
+			if !collection_details.free_holding && maybe_check_owner.is_some() {
+				deposit = T::DepositPerByte::get()
+					.saturating_mul(((key.len() + value.len()) as u32).into())
+					.saturating_add(T::AttributeDepositBase::get());
+			}
+			collection_details.total_deposit = collection_details.total_deposit
+				.saturating_add(deposit);
+			Self::adjust_deposit(&collection_details.owner, old_deposit, deposit)?;
+
+			Attribute::<T>::insert((collection, maybe_item), &key, (&value, deposit));
+			if let (Some(item), Some(details)) = (maybe_item, maybe_item_details) {
+				Item::<T>::insert(collection, item, details);
+			}
+			Collection::<T>::insert(collection, &collection_details);
+			Self::deposit_event(RawEvent::AttributeSet(collection, maybe_item, key, value));
+		}
+
+		/// Clear an attribute for a collection or item.
+		///
+		/// Origin must be either `ForceOrigin` or Signed and the sender should be the Owner of the
+		/// `collection`.
+		///
+		/// Any deposit is freed for the collection's owner.
+		///
+		/// - `collection`: The identifier of the collection whose item's metadata to clear.
+		/// - `maybe_item`: The identifier of the item whose metadata to clear.
+		/// - `key`: The key of the attribute.
+		///
+		/// Emits `AttributeCleared`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::clear_attribute()]
+		fn clear_attribute(origin,
+			#[compact] collection: T::CollectionId,
+			maybe_item: Option<T::ItemId>,
+			key: Vec<u8>,
+		) {
+			let maybe_check_owner = Self::ensure_force_or_signed(origin)?;
+
+			let mut collection_details = Collection::<T>::get(collection)
+				.ok_or(Error::<T>::Unknown)?;
+			if let Some(check_owner) = &maybe_check_owner {
+				ensure!(check_owner == &collection_details.owner, Error::<T>::NoPermission);
+			}
+			Self::ensure_metadata_unfrozen(collection, maybe_item, maybe_check_owner.is_some())?;
+
+			if let Some((_, deposit)) = Attribute::<T>::take((collection, maybe_item), &key) {
+				collection_details.attributes = collection_details.attributes.saturating_sub(1);
+				if let Some(item) = maybe_item {
+					Item::<T>::mutate(collection, item, |maybe_details| {
+						if let Some(details) = maybe_details {
+							details.attributes = details.attributes.saturating_sub(1);
+						}
+					});
+				}
+				collection_details.total_deposit = collection_details.total_deposit
+					.saturating_sub(deposit);
+				T::Currency::unreserve(&collection_details.owner, deposit);
+				Collection::<T>::insert(collection, &collection_details);
+				Self::deposit_event(RawEvent::AttributeCleared(collection, maybe_item, key));
+			}
+		}
+
+		/// Set the metadata for an item.
+		///
+		/// Origin must be either `ForceOrigin` or Signed and the sender should be the Owner of the
+		/// `collection`.
+		///
+		/// If the origin is Signed, then funds of signer are reserved according to the formula:
+		/// `MetadataDepositBase + DepositPerByte * data.len` taking into
+		/// account any already reserved funds.
+		///
+		/// - `collection`: The identifier of the collection whose item's metadata to set.
+		/// - `item`: The identifier of the item whose metadata to set.
+		/// - `data`: The general information of this item. Limited in length by `StringLimit`.
+		/// - `is_frozen`: Whether the metadata should be frozen against further changes.
+		///
+		/// Emits `MetadataSet`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::set_metadata()]
+		fn set_metadata(origin,
+			#[compact] collection: T::CollectionId,
+			#[compact] item: T::ItemId,
+			data: Vec<u8>,
+			is_frozen: bool,
+		) {
+			let maybe_check_owner = Self::ensure_force_or_signed(origin)?;
+
+			ensure!(data.len() <= T::StringLimit::get() as usize, Error::<T>::BadMetadata);
+
+			let mut collection_details = Collection::<T>::get(collection)
+				.ok_or(Error::<T>::Unknown)?;
+			if let Some(check_owner) = &maybe_check_owner {
+				ensure!(check_owner == &collection_details.owner, Error::<T>::NoPermission);
+			}
+			ensure!(Item::<T>::contains_key(collection, item), Error::<T>::Unknown);
+
+			let metadata = ItemMetadataOf::<T>::get(collection, item);
+			let was_frozen = metadata.as_ref().map_or(false, |m| m.is_frozen);
+			ensure!(maybe_check_owner.is_none() || !was_frozen, Error::<T>::Frozen);
+
+			if metadata.is_none() {
+				collection_details.item_metadatas = collection_details.item_metadatas.saturating_add(1);
+			}
+			let old_deposit = metadata.map_or(Zero::zero(), |m| m.deposit);
+			collection_details.total_deposit = collection_details.total_deposit
+				.saturating_sub(old_deposit);
+			let mut deposit = Zero::zero();
+			if !collection_details.free_holding && maybe_check_owner.is_some() {
+				deposit = T::DepositPerByte::get()
+					.saturating_mul((data.len() as u32).into())
+					.saturating_add(T::MetadataDepositBase::get());
+			}
+			collection_details.total_deposit = collection_details.total_deposit
+				.saturating_add(deposit);
+			Self::adjust_deposit(&collection_details.owner, old_deposit, deposit)?;
+
+			ItemMetadataOf::<T>::insert(collection, item, Metadata {
+				deposit,
+				data: data.clone(),
+				is_frozen,
+			});
+			Collection::<T>::insert(collection, &collection_details);
+			Self::deposit_event(RawEvent::MetadataSet(collection, item, data, is_frozen));
+		}
+
+		/// Clear the metadata for an item.
+		///
+		/// Origin must be either `ForceOrigin` or Signed and the sender should be the Owner of the
+		/// `collection`.
+		///
+		/// Any deposit is freed for the collection's owner.
+		///
+		/// - `collection`: The identifier of the collection whose item's metadata to clear.
+		/// - `item`: The identifier of the item whose metadata to clear.
+		///
+		/// Emits `MetadataCleared`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::clear_metadata()]
+		fn clear_metadata(origin,
+			#[compact] collection: T::CollectionId,
+			#[compact] item: T::ItemId,
+		) {
+			let maybe_check_owner = Self::ensure_force_or_signed(origin)?;
+
+			let mut collection_details = Collection::<T>::get(collection)
+				.ok_or(Error::<T>::Unknown)?;
+			if let Some(check_owner) = &maybe_check_owner {
+				ensure!(check_owner == &collection_details.owner, Error::<T>::NoPermission);
+			}
+
+			let metadata = ItemMetadataOf::<T>::get(collection, item).ok_or(Error::<T>::Unknown)?;
+			ensure!(maybe_check_owner.is_none() || !metadata.is_frozen, Error::<T>::Frozen);
+
+			ItemMetadataOf::<T>::remove(collection, item);
+			collection_details.item_metadatas = collection_details.item_metadatas.saturating_sub(1);
+			collection_details.total_deposit = collection_details.total_deposit
+				.saturating_sub(metadata.deposit);
+			T::Currency::unreserve(&collection_details.owner, metadata.deposit);
+			Collection::<T>::insert(collection, &collection_details);
+			Self::deposit_event(RawEvent::MetadataCleared(collection, item));
+		}
+
+		/// Set the metadata for a collection.
+		///
+		/// Origin must be either `ForceOrigin` or `Signed` and the sender should be the Owner of
+		/// the `collection`.
+		///
+		/// If the origin is `Signed`, then funds of signer are reserved according to the formula:
+		/// `MetadataDepositBase + DepositPerByte * data.len` taking into
+		/// account any already reserved funds.
+		///
+		/// - `collection`: The identifier of the collection whose metadata to update.
+		/// - `data`: The general information of this collection. Limited in length by
+		///   `StringLimit`.
+		/// - `is_frozen`: Whether the metadata should be frozen against further changes.
+		///
+		/// Emits `CollectionMetadataSet`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::set_collection_metadata()]
+		fn set_collection_metadata(origin,
+			#[compact] collection: T::CollectionId,
+			data: Vec<u8>,
+			is_frozen: bool,
+		) {
+			let maybe_check_owner = Self::ensure_force_or_signed(origin)?;
+
+			ensure!(data.len() <= T::StringLimit::get() as usize, Error::<T>::BadMetadata);
+
+			let mut details = Collection::<T>::get(collection).ok_or(Error::<T>::Unknown)?;
+			if let Some(check_owner) = &maybe_check_owner {
+				ensure!(check_owner == &details.owner, Error::<T>::NoPermission);
+			}
+
+			let metadata = CollectionMetadataOf::<T>::get(collection);
+			let was_frozen = metadata.as_ref().map_or(false, |m| m.is_frozen);
+			ensure!(maybe_check_owner.is_none() || !was_frozen, Error::<T>::Frozen);
+
+			let old_deposit = metadata.map_or(Zero::zero(), |m| m.deposit);
+			details.total_deposit = details.total_deposit.saturating_sub(old_deposit);
+			let mut deposit = Zero::zero();
+			if maybe_check_owner.is_some() && !details.free_holding {
+				deposit = T::DepositPerByte::get()
+					.saturating_mul((data.len() as u32).into())
+					.saturating_add(T::MetadataDepositBase::get());
+			}
+			details.total_deposit = details.total_deposit.saturating_add(deposit);
+			Self::adjust_deposit(&details.owner, old_deposit, deposit)?;
+
+			CollectionMetadataOf::<T>::insert(collection, Metadata {
+				deposit,
+				data: data.clone(),
+				is_frozen,
+			});
+			Collection::<T>::insert(collection, &details);
+			Self::deposit_event(RawEvent::CollectionMetadataSet(collection, data, is_frozen));
+		}
+
+		/// Clear the metadata for a collection.
+		///
+		/// Origin must be either `ForceOrigin` or `Signed` and the sender should be the Owner of
+		/// the `collection`.
+		///
+		/// Any deposit is freed for the collection's owner.
+		///
+		/// - `collection`: The identifier of the collection whose metadata to clear.
+		///
+		/// Emits `CollectionMetadataCleared`.
+		///
+		/// Weight: `O(1)`
+		#[weight = T::WeightInfo::clear_collection_metadata()]
+		fn clear_collection_metadata(origin, #[compact] collection: T::CollectionId) {
+			let maybe_check_owner = Self::ensure_force_or_signed(origin)?;
+
+			let mut details = Collection::<T>::get(collection).ok_or(Error::<T>::Unknown)?;
+			if let Some(check_owner) = &maybe_check_owner {
+				ensure!(check_owner == &details.owner, Error::<T>::NoPermission);
+			}
+
+			let metadata = CollectionMetadataOf::<T>::get(collection).ok_or(Error::<T>::Unknown)?;
+			ensure!(maybe_check_owner.is_none() || !metadata.is_frozen, Error::<T>::Frozen);
+
+			CollectionMetadataOf::<T>::remove(collection);
+			details.total_deposit = details.total_deposit.saturating_sub(metadata.deposit);
+			T::Currency::unreserve(&details.owner, metadata.deposit);
+			Collection::<T>::insert(collection, &details);
+			Self::deposit_event(RawEvent::CollectionMetadataCleared(collection));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Ensure that `origin` is either `ForceOrigin`, returning `None`, or `Signed`, returning the
+	/// signer.
+	fn ensure_force_or_signed(origin: T::Origin) -> Result<Option<T::AccountId>, DispatchError> {
+		match T::ForceOrigin::try_origin(origin) {
+			Ok(_) => Ok(None),
+			Err(origin) => Ok(Some(ensure_signed(origin)?)),
+		}
+	}
+
+	/// Ensure that the metadata of the given collection or item is not frozen, unless `is_signed`
+	/// is false and the call comes from the `ForceOrigin`.
+	fn ensure_metadata_unfrozen(
+		collection: T::CollectionId,
+		maybe_item: Option<T::ItemId>,
+		is_signed: bool,
+	) -> DispatchResult {
+		let maybe_is_frozen = match maybe_item {
+			None => CollectionMetadataOf::<T>::get(collection).map(|m| m.is_frozen),
+			Some(item) => ItemMetadataOf::<T>::get(collection, item).map(|m| m.is_frozen),
+		};
+		ensure!(!is_signed || !maybe_is_frozen.unwrap_or(false), Error::<T>::Frozen);
+		Ok(())
+	}
+
+	/// Replace a deposit of `old` reserved from `who` with one of `new`.
+	fn adjust_deposit(
+		who: &T::AccountId,
+		old: DepositBalanceOf<T>,
+		new: DepositBalanceOf<T>,
+	) -> DispatchResult {
+		if new > old {
+			T::Currency::reserve(who, new - old)?;
+		} else if new < old {
+			T::Currency::unreserve(who, old - new);
+		}
+		Ok(())
+	}
+
+	/// Create `collection` owned by `owner`, reserving `deposit` from it, and deposit `event`.
+	///
+	/// The issuer, admin and freezer of the new collection are all set to `admin`.
+	pub fn do_create_collection(
+		collection: T::CollectionId,
+		owner: T::AccountId,
+		admin: T::AccountId,
+		deposit: DepositBalanceOf<T>,
+		free_holding: bool,
+		event: Event<T>,
+	) -> DispatchResult {
+		ensure!(!Collection::<T>::contains_key(collection), Error::<T>::AlreadyExists);
+
+		T::Currency::reserve(&owner, deposit)?;
+
+		Collection::<T>::insert(collection, CollectionDetails {
+			owner,
+			issuer: admin.clone(),
+			admin: admin.clone(),
+			freezer: admin,
+			total_deposit: deposit,
+			free_holding,
+			items: 0,
+			item_metadatas: 0,
+			attributes: 0,
+			is_frozen: false,
+		});
+		Self::deposit_event(event);
+		Ok(())
+	}
+
+	/// Mint `item` of `collection` into the account of `owner`.
+	///
+	/// `with_details` is called with the collection details before anything is changed and may
+	/// veto the operation.
+	pub fn do_mint(
+		collection: T::CollectionId,
+		item: T::ItemId,
+		owner: T::AccountId,
+		with_details: impl FnOnce(&CollectionDetails<T::AccountId, DepositBalanceOf<T>>) -> DispatchResult,
+	) -> DispatchResult {
+		ensure!(!Item::<T>::contains_key(collection, item), Error::<T>::AlreadyExists);
+
+		Collection::<T>::try_mutate(collection, |maybe_collection_details| -> DispatchResult {
+			let collection_details = maybe_collection_details.as_mut().ok_or(Error::<T>::Unknown)?;
+
+			with_details(collection_details)?;
+
+			let items = collection_details.items.checked_add(1)
+				.ok_or(DispatchError::Other("Overflow"))?;
+			collection_details.items = items;
+
+			let deposit = match collection_details.free_holding {
+				true => Zero::zero(),
+				false => T::ItemDeposit::get(),
+			};
+			T::Currency::reserve(&collection_details.owner, deposit)?;
+			collection_details.total_deposit = collection_details.total_deposit
+				.saturating_add(deposit);
+
+			Account::<T>::insert(&owner, (collection, item), ());
+			let details = ItemDetails {
+				owner: owner.clone(),
+				approved: None,
+				is_frozen: false,
+				deposit,
+				attributes: 0,
+			};
+			Item::<T>::insert(collection, item, details);
+			Ok(())
+		})?;
+
+		Self::deposit_event(RawEvent::Issued(collection, item, owner));
+		Ok(())
+	}
+
+	/// Burn `item` of `collection`, along with its metadata and attributes.
+	///
+	/// `with_details` is called with the collection and item details before anything is changed
+	/// and may veto the operation.
+	pub fn do_burn(
+		collection: T::CollectionId,
+		item: T::ItemId,
+		with_details: impl FnOnce(
+			&CollectionDetails<T::AccountId, DepositBalanceOf<T>>,
+			&ItemDetails<T::AccountId, DepositBalanceOf<T>>,
+		) -> DispatchResult,
+	) -> DispatchResult {
+		let owner = Collection::<T>::try_mutate(
+			collection,
+			|maybe_collection_details| -> Result<T::AccountId, DispatchError> {
+				let collection_details = maybe_collection_details.as_mut()
+					.ok_or(Error::<T>::Unknown)?;
+				let details = Item::<T>::get(collection, item).ok_or(Error::<T>::Unknown)?;
+
+				with_details(collection_details, &details)?;
+
+				// Return the deposits of the item, its metadata and its attributes.
+				let mut deposit = details.deposit;
+				if let Some(metadata) = ItemMetadataOf::<T>::take(collection, item) {
+					deposit = deposit.saturating_add(metadata.deposit);
+					collection_details.item_metadatas =
+						collection_details.item_metadatas.saturating_sub(1);
+				}
+				for (_, (_, attribute_deposit)) in Attribute::<T>::drain_prefix((collection, Some(item))) {
+					deposit = deposit.saturating_add(attribute_deposit);
+					collection_details.attributes = collection_details.attributes.saturating_sub(1);
+				}
+				T::Currency::unreserve(&collection_details.owner, deposit);
+				collection_details.total_deposit = collection_details.total_deposit
+					.saturating_sub(deposit);
+				collection_details.items = collection_details.items.saturating_sub(1);
+				Ok(details.owner)
+			},
+		)?;
+
+		Item::<T>::remove(collection, item);
+		Account::<T>::remove(&owner, (collection, item));
+
+		Self::deposit_event(RawEvent::Burned(collection, item, owner));
+		Ok(())
+	}
+
+	/// Transfer `item` of `collection` to `dest`.
+	///
+	/// `with_details` is called with the collection and item details before anything is changed
+	/// and may veto the operation. Transfers of frozen items and items of frozen collections
+	/// always fail. Any approved delegate of the item is reset.
+	pub fn do_transfer(
+		collection: T::CollectionId,
+		item: T::ItemId,
+		dest: T::AccountId,
+		with_details: impl FnOnce(
+			&CollectionDetails<T::AccountId, DepositBalanceOf<T>>,
+			&mut ItemDetails<T::AccountId, DepositBalanceOf<T>>,
+		) -> DispatchResult,
+	) -> DispatchResult {
+		let collection_details = Collection::<T>::get(collection).ok_or(Error::<T>::Unknown)?;
+		ensure!(!collection_details.is_frozen, Error::<T>::Frozen);
+
+		let mut details = Item::<T>::get(collection, item).ok_or(Error::<T>::Unknown)?;
+		ensure!(!details.is_frozen, Error::<T>::Frozen);
+		with_details(&collection_details, &mut details)?;
+
+		Account::<T>::remove(&details.owner, (collection, item));
+		Account::<T>::insert(&dest, (collection, item), ());
+		let origin = details.owner;
+		details.owner = dest;
+		details.approved = None;
+		Item::<T>::insert(collection, item, &details);
+
+		Self::deposit_event(RawEvent::Transferred(collection, item, origin, details.owner));
+		Ok(())
+	}
+}
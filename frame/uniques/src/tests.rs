@@ -0,0 +1,731 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+// Tests for Uniques Pallet
+
+#![cfg(test)]
+
+use super::*;
+
+use frame_support::{
+	assert_ok, assert_noop, impl_outer_origin, impl_outer_event, parameter_types,
+	weights::Weight, traits::nonfungibles::{Inspect, InspectEnumerable, Mutate, Transfer},
+};
+use sp_core::H256;
+use sp_runtime::{Perbill, traits::{BlakeTwo256, IdentityLookup, BadOrigin}, testing::Header};
+use crate as uniques;
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system<T>,
+		pallet_balances<T>,
+		uniques<T>,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+impl frame_system::Trait for Test {
+	type BaseCallFilter = ();
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Call = ();
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumExtrinsicWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type PalletInfo = ();
+	type AccountData = pallet_balances::AccountData<u64>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+	type SystemWeightInfo = ();
+}
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+}
+impl pallet_balances::Trait for Test {
+	type MaxLocks = ();
+	type Balance = u64;
+	type Event = TestEvent;
+	type DustRemoval = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+parameter_types! {
+	pub const CollectionDeposit: u64 = 2;
+	pub const ItemDeposit: u64 = 1;
+	pub const KeyLimit: u32 = 50;
+	pub const ValueLimit: u32 = 50;
+	pub const StringLimit: u32 = 50;
+	pub const MetadataDepositBase: u64 = 1;
+	pub const AttributeDepositBase: u64 = 1;
+	pub const MetadataDepositPerByte: u64 = 1;
+}
+impl Trait for Test {
+	type Event = TestEvent;
+	type CollectionId = u32;
+	type ItemId = u32;
+	type Currency = Balances;
+	type ForceOrigin = frame_system::EnsureRoot<u64>;
+	type CollectionDeposit = CollectionDeposit;
+	type ItemDeposit = ItemDeposit;
+	type MetadataDepositBase = MetadataDepositBase;
+	type AttributeDepositBase = AttributeDepositBase;
+	type DepositPerByte = MetadataDepositPerByte;
+	type StringLimit = StringLimit;
+	type KeyLimit = KeyLimit;
+	type ValueLimit = ValueLimit;
+	type WeightInfo = ();
+}
+
+type System = frame_system::Module<Test>;
+type Balances = pallet_balances::Module<Test>;
+type Uniques = Module<Test>;
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let mut ext = sp_io::TestExternalities::new(t);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+fn items() -> Vec<(u64, u32, u32)> {
+	let mut r: Vec<_> = Account::<Test>::iter().map(|x| (x.0, (x.1).0, (x.1).1)).collect();
+	r.sort();
+	let mut s: Vec<_> = Item::<Test>::iter().map(|x| (x.2.owner, x.0, x.1)).collect();
+	s.sort();
+	assert_eq!(r, s);
+	r
+}
+
+fn attributes(collection: u32) -> Vec<(Option<u32>, Vec<u8>, Vec<u8>)> {
+	let mut s: Vec<_> = Attribute::<Test>::iter()
+		.filter(|((c, _), ..)| *c == collection)
+		.map(|((_, item), key, (value, _))| (item, key, value))
+		.collect();
+	s.sort();
+	s
+}
+
+fn last_event() -> TestEvent {
+	System::events().pop().expect("Event expected").event
+}
+
+#[test]
+fn basic_setup_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(items(), vec![]);
+	});
+}
+
+#[test]
+fn basic_minting_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, true));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 1));
+		assert_eq!(items(), vec![(1, 0, 42)]);
+		assert_eq!(last_event(), TestEvent::uniques(RawEvent::Issued(0, 42, 1)));
+
+		assert_ok!(Uniques::force_create(Origin::root(), 1, 2, true));
+		assert_ok!(Uniques::mint(Origin::signed(2), 1, 69, 1));
+		assert_eq!(items(), vec![(1, 0, 42), (1, 1, 69)]);
+	});
+}
+
+#[test]
+fn lifecycle_should_work() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&1, 100);
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+		assert_eq!(Balances::reserved_balance(&1), 2);
+
+		assert_ok!(Uniques::set_collection_metadata(Origin::signed(1), 0, vec![0, 0], false));
+		assert_eq!(Balances::reserved_balance(&1), 5);
+		assert!(CollectionMetadataOf::<Test>::contains_key(0));
+
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 10));
+		assert_eq!(Balances::reserved_balance(&1), 6);
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 69, 20));
+		assert_eq!(Balances::reserved_balance(&1), 7);
+		assert_eq!(items(), vec![(10, 0, 42), (20, 0, 69)]);
+		assert_eq!(Collection::<Test>::get(0).unwrap().items, 2);
+		assert_eq!(Collection::<Test>::get(0).unwrap().item_metadatas, 0);
+
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![42, 42], false));
+		assert_eq!(Balances::reserved_balance(&1), 10);
+		assert!(ItemMetadataOf::<Test>::contains_key(0, 42));
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 69, vec![69, 69], false));
+		assert_eq!(Balances::reserved_balance(&1), 13);
+		assert!(ItemMetadataOf::<Test>::contains_key(0, 69));
+
+		let w = Collection::<Test>::get(0).unwrap().destroy_witness();
+		assert_eq!(w.items, 2);
+		assert_eq!(w.item_metadatas, 2);
+		assert_ok!(Uniques::destroy(Origin::signed(1), 0, w));
+		assert_eq!(Balances::reserved_balance(&1), 0);
+
+		assert!(!Collection::<Test>::contains_key(0));
+		assert!(!Item::<Test>::contains_key(0, 42));
+		assert!(!Item::<Test>::contains_key(0, 69));
+		assert!(!CollectionMetadataOf::<Test>::contains_key(0));
+		assert!(!ItemMetadataOf::<Test>::contains_key(0, 42));
+		assert!(!ItemMetadataOf::<Test>::contains_key(0, 69));
+		assert_eq!(items(), vec![]);
+	});
+}
+
+#[test]
+fn destroy_with_bad_witness_should_not_work() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&1, 100);
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+
+		let w = Collection::<Test>::get(0).unwrap().destroy_witness();
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 1));
+		assert_noop!(Uniques::destroy(Origin::signed(1), 0, w), Error::<Test>::BadWitness);
+	});
+}
+
+#[test]
+fn destroy_requires_owner_or_force_origin() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&1, 100);
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 2));
+
+		let w = Collection::<Test>::get(0).unwrap().destroy_witness();
+		assert_noop!(Uniques::destroy(Origin::signed(2), 0, w), Error::<Test>::NoPermission);
+		assert_ok!(Uniques::destroy(Origin::root(), 0, w));
+		assert_eq!(Balances::reserved_balance(&1), 0);
+	});
+}
+
+#[test]
+fn mint_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, true));
+		assert_noop!(Uniques::mint(Origin::signed(2), 0, 42, 1), Error::<Test>::NoPermission);
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 1));
+		assert_eq!(Uniques::owner(&0, &42).unwrap(), 1);
+		assert_eq!(items(), vec![(1, 0, 42)]);
+
+		assert_noop!(Uniques::mint(Origin::signed(1), 0, 42, 1), Error::<Test>::AlreadyExists);
+		assert_noop!(Uniques::mint(Origin::signed(1), 1, 42, 1), Error::<Test>::Unknown);
+	});
+}
+
+#[test]
+fn transfer_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, true));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+
+		assert_ok!(Uniques::transfer(Origin::signed(2), 0, 42, 3));
+		assert_eq!(items(), vec![(3, 0, 42)]);
+		assert_eq!(last_event(), TestEvent::uniques(RawEvent::Transferred(0, 42, 2, 3)));
+		assert_noop!(Uniques::transfer(Origin::signed(2), 0, 42, 4), Error::<Test>::NoPermission);
+
+		// the admin may transfer any item.
+		assert_ok!(Uniques::transfer(Origin::signed(1), 0, 42, 4));
+		assert_eq!(items(), vec![(4, 0, 42)]);
+
+		assert_ok!(Uniques::approve_transfer(Origin::signed(4), 0, 42, 2));
+		assert_ok!(Uniques::transfer(Origin::signed(2), 0, 42, 5));
+		assert_eq!(items(), vec![(5, 0, 42)]);
+		assert_eq!(Item::<Test>::get(0, 42).unwrap().approved, None);
+	});
+}
+
+#[test]
+fn freezing_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, true));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 1));
+
+		assert_noop!(Uniques::freeze(Origin::signed(2), 0, 42), Error::<Test>::NoPermission);
+		assert_ok!(Uniques::freeze(Origin::signed(1), 0, 42));
+		assert!(!Uniques::can_transfer(&0, &42));
+		assert_noop!(Uniques::transfer(Origin::signed(1), 0, 42, 2), Error::<Test>::Frozen);
+
+		assert_ok!(Uniques::thaw(Origin::signed(1), 0, 42));
+		assert!(Uniques::can_transfer(&0, &42));
+
+		assert_ok!(Uniques::freeze_collection(Origin::signed(1), 0));
+		assert!(!Uniques::can_transfer(&0, &42));
+		assert_noop!(Uniques::transfer(Origin::signed(1), 0, 42, 2), Error::<Test>::Frozen);
+
+		assert_ok!(Uniques::thaw_collection(Origin::signed(1), 0));
+		assert_ok!(Uniques::transfer(Origin::signed(1), 0, 42, 2));
+	});
+}
+
+#[test]
+fn origin_guards_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, true));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 1));
+		assert_noop!(Uniques::transfer_ownership(Origin::signed(2), 0, 2), Error::<Test>::NoPermission);
+		assert_noop!(Uniques::set_team(Origin::signed(2), 0, 2, 2, 2), Error::<Test>::NoPermission);
+		assert_noop!(Uniques::freeze(Origin::signed(2), 0, 42), Error::<Test>::NoPermission);
+		assert_noop!(Uniques::thaw(Origin::signed(2), 0, 42), Error::<Test>::NoPermission);
+		assert_noop!(Uniques::mint(Origin::signed(2), 0, 69, 2), Error::<Test>::NoPermission);
+		assert_noop!(Uniques::burn(Origin::signed(2), 0, 42, None, 0), Error::<Test>::NoPermission);
+		assert_noop!(Uniques::force_create(Origin::signed(2), 1, 2, true), BadOrigin);
+		assert_noop!(
+			Uniques::force_item_status(Origin::signed(2), 0, 2, 2, 2, 2, true, false),
+			BadOrigin,
+		);
+	});
+}
+
+#[test]
+fn transfer_owner_should_work() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&1, 100);
+		Balances::make_free_balance_be(&2, 100);
+		Balances::make_free_balance_be(&3, 100);
+		assert_ok!(Uniques::create(Origin::signed(1), 0, 1));
+		assert_ok!(Uniques::transfer_ownership(Origin::signed(1), 0, 2));
+		assert_eq!(Balances::total_balance(&1), 98);
+		assert_eq!(Balances::total_balance(&2), 102);
+		assert_eq!(Balances::reserved_balance(&1), 0);
+		assert_eq!(Balances::reserved_balance(&2), 2);
+		assert_eq!(Uniques::collection_owner(&0), Some(2));
+
+		assert_noop!(Uniques::transfer_ownership(Origin::signed(1), 0, 1), Error::<Test>::NoPermission);
+
+		// Mint and set metadata now and make sure that deposit gets transferred back.
+		assert_ok!(Uniques::set_collection_metadata(Origin::signed(2), 0, vec![0u8; 20], false));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 1));
+		assert_ok!(Uniques::set_metadata(Origin::signed(2), 0, 42, vec![0u8; 20], false));
+		assert_ok!(Uniques::transfer_ownership(Origin::signed(2), 0, 3));
+		assert_eq!(Balances::total_balance(&2), 57);
+		assert_eq!(Balances::total_balance(&3), 145);
+		assert_eq!(Balances::reserved_balance(&2), 0);
+		assert_eq!(Balances::reserved_balance(&3), 45);
+
+		// The deposit must be moved in full.
+		Balances::unreserve(&3, 1);
+		assert_noop!(
+			Uniques::transfer_ownership(Origin::signed(3), 0, 1),
+			Error::<Test>::DepositNotMoved,
+		);
+		assert_eq!(Uniques::collection_owner(&0), Some(3));
+	});
+}
+
+#[test]
+fn set_team_should_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, true));
+		assert_ok!(Uniques::set_team(Origin::signed(1), 0, 2, 3, 4));
+		assert_eq!(last_event(), TestEvent::uniques(RawEvent::TeamChanged(0, 2, 3, 4)));
+
+		assert_ok!(Uniques::mint(Origin::signed(2), 0, 42, 2));
+		assert_ok!(Uniques::freeze(Origin::signed(4), 0, 42));
+		assert_ok!(Uniques::thaw(Origin::signed(3), 0, 42));
+		assert_ok!(Uniques::transfer(Origin::signed(3), 0, 42, 3));
+		assert_ok!(Uniques::burn(Origin::signed(3), 0, 42, None, 0));
+	});
+}
+
+#[test]
+fn set_collection_metadata_should_work() {
+	new_test_ext().execute_with(|| {
+		// Cannot add metadata to unknown collection
+		assert_noop!(
+			Uniques::set_collection_metadata(Origin::signed(1), 0, vec![0u8; 20], false),
+			Error::<Test>::Unknown,
+		);
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, false));
+		// Cannot add metadata to unowned collection
+		assert_noop!(
+			Uniques::set_collection_metadata(Origin::signed(2), 0, vec![0u8; 20], false),
+			Error::<Test>::NoPermission,
+		);
+
+		// Successfully add metadata and take deposit
+		Balances::make_free_balance_be(&1, 30);
+		assert_ok!(Uniques::set_collection_metadata(Origin::signed(1), 0, vec![0u8; 20], false));
+		assert_eq!(Balances::free_balance(&1), 9);
+		assert!(CollectionMetadataOf::<Test>::contains_key(0));
+
+		// Force origin works, too.
+		assert_ok!(Uniques::set_collection_metadata(Origin::root(), 0, vec![0u8; 18], false));
+
+		// Update deposit
+		assert_ok!(Uniques::set_collection_metadata(Origin::signed(1), 0, vec![0u8; 15], false));
+		assert_eq!(Balances::free_balance(&1), 14);
+		assert_ok!(Uniques::set_collection_metadata(Origin::signed(1), 0, vec![0u8; 25], false));
+		assert_eq!(Balances::free_balance(&1), 4);
+
+		// Cannot over-reserve
+		assert_noop!(
+			Uniques::set_collection_metadata(Origin::signed(1), 0, vec![0u8; 40], false),
+			pallet_balances::Error::<Test, _>::InsufficientBalance,
+		);
+
+		// Can't set or clear metadata once frozen
+		assert_ok!(Uniques::set_collection_metadata(Origin::signed(1), 0, vec![0u8; 15], true));
+		assert_noop!(
+			Uniques::set_collection_metadata(Origin::signed(1), 0, vec![0u8; 15], false),
+			Error::<Test>::Frozen,
+		);
+		assert_noop!(Uniques::clear_collection_metadata(Origin::signed(1), 0), Error::<Test>::Frozen);
+
+		// Clear Metadata
+		assert_ok!(Uniques::set_collection_metadata(Origin::root(), 0, vec![0u8; 15], false));
+		assert_noop!(Uniques::clear_collection_metadata(Origin::signed(2), 0), Error::<Test>::NoPermission);
+		assert_noop!(Uniques::clear_collection_metadata(Origin::signed(1), 1), Error::<Test>::Unknown);
+		assert_ok!(Uniques::clear_collection_metadata(Origin::signed(1), 0));
+		assert!(!CollectionMetadataOf::<Test>::contains_key(0));
+	});
+}
+
+#[test]
+fn set_item_metadata_should_work() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&1, 30);
+
+		// Cannot add metadata to unknown item
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, false));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 1));
+		// Cannot add metadata to unowned item
+		assert_noop!(
+			Uniques::set_metadata(Origin::signed(2), 0, 42, vec![0u8; 20], false),
+			Error::<Test>::NoPermission,
+		);
+
+		// Successfully add metadata and take deposit
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0u8; 20], false));
+		assert_eq!(Balances::free_balance(&1), 8);
+		assert!(ItemMetadataOf::<Test>::contains_key(0, 42));
+
+		// Force origin works, too.
+		assert_ok!(Uniques::set_metadata(Origin::root(), 0, 42, vec![0u8; 18], false));
+
+		// Update deposit
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0u8; 15], false));
+		assert_eq!(Balances::free_balance(&1), 13);
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0u8; 25], false));
+		assert_eq!(Balances::free_balance(&1), 3);
+
+		// Cannot over-reserve
+		assert_noop!(
+			Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0u8; 40], false),
+			pallet_balances::Error::<Test, _>::InsufficientBalance,
+		);
+
+		// Can't set or clear metadata once frozen
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0u8; 15], true));
+		assert_noop!(
+			Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0u8; 15], false),
+			Error::<Test>::Frozen,
+		);
+		assert_noop!(Uniques::clear_metadata(Origin::signed(1), 0, 42), Error::<Test>::Frozen);
+
+		// Clear Metadata
+		assert_ok!(Uniques::set_metadata(Origin::root(), 0, 42, vec![0u8; 15], false));
+		assert_noop!(Uniques::clear_metadata(Origin::signed(2), 0, 42), Error::<Test>::NoPermission);
+		assert_noop!(Uniques::clear_metadata(Origin::signed(1), 1, 42), Error::<Test>::Unknown);
+		assert_ok!(Uniques::clear_metadata(Origin::signed(1), 0, 42));
+		assert!(!ItemMetadataOf::<Test>::contains_key(0, 42));
+	});
+}
+
+#[test]
+fn set_attribute_should_work() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&1, 100);
+
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, false));
+
+		assert_noop!(
+			Uniques::set_attribute(Origin::signed(1), 0, Some(0), vec![0], vec![0]),
+			Error::<Test>::Unknown,
+		);
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 0, 1));
+		assert_eq!(Balances::reserved_balance(1), 1);
+
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, None, vec![0], vec![0]));
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, Some(0), vec![0], vec![0]));
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, Some(0), vec![1], vec![0]));
+		assert_eq!(attributes(0), vec![
+			(None, vec![0], vec![0]),
+			(Some(0), vec![0], vec![0]),
+			(Some(0), vec![1], vec![0]),
+		]);
+		assert_eq!(Balances::reserved_balance(1), 10);
+
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, None, vec![0], vec![0; 10]));
+		assert_eq!(attributes(0), vec![
+			(None, vec![0], vec![0; 10]),
+			(Some(0), vec![0], vec![0]),
+			(Some(0), vec![1], vec![0]),
+		]);
+		assert_eq!(Balances::reserved_balance(1), 19);
+
+		assert_ok!(Uniques::clear_attribute(Origin::signed(1), 0, Some(0), vec![1]));
+		assert_eq!(attributes(0), vec![
+			(None, vec![0], vec![0; 10]),
+			(Some(0), vec![0], vec![0]),
+		]);
+		assert_eq!(Balances::reserved_balance(1), 16);
+
+		assert_noop!(
+			Uniques::set_attribute(Origin::signed(1), 0, None, vec![0; 51], vec![0]),
+			Error::<Test>::BadMetadata,
+		);
+
+		let w = Collection::<Test>::get(0).unwrap().destroy_witness();
+		assert_eq!(w.attributes, 2);
+		assert_ok!(Uniques::destroy(Origin::signed(1), 0, w));
+		assert_eq!(attributes(0), vec![]);
+		assert_eq!(Balances::reserved_balance(1), 0);
+	});
+}
+
+#[test]
+fn set_attribute_should_respect_freeze() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&1, 100);
+
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, false));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 1, 1));
+
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, None, vec![0], vec![0]));
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, Some(1), vec![0], vec![0]));
+		assert_eq!(Balances::reserved_balance(1), 7);
+
+		assert_ok!(Uniques::set_collection_metadata(Origin::signed(1), 0, vec![], true));
+		assert_noop!(
+			Uniques::set_attribute(Origin::signed(1), 0, None, vec![0], vec![0]),
+			Error::<Test>::Frozen,
+		);
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, Some(1), vec![0], vec![1]));
+
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 1, vec![], true));
+		assert_noop!(
+			Uniques::set_attribute(Origin::signed(1), 0, Some(1), vec![0], vec![1]),
+			Error::<Test>::Frozen,
+		);
+
+		// The force origin may still change frozen attributes.
+		assert_ok!(Uniques::set_attribute(Origin::root(), 0, Some(1), vec![0], vec![2]));
+		assert_eq!(Uniques::attribute(&0, &1, &[0]), Some(vec![2]));
+	});
+}
+
+#[test]
+fn force_item_status_should_work() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&1, 100);
+
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, false));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 1));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 69, 2));
+		assert_ok!(Uniques::set_collection_metadata(Origin::signed(1), 0, vec![0; 20], false));
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0; 20], false));
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 69, vec![0; 20], false));
+		assert_eq!(Balances::reserved_balance(1), 65);
+
+		// force item status to be free holding
+		assert_ok!(Uniques::force_item_status(Origin::root(), 0, 1, 1, 1, 1, true, false));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 142, 1));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 169, 2));
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 142, vec![0; 20], false));
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 169, vec![0; 20], false));
+		assert_eq!(Balances::reserved_balance(1), 65);
+
+		// an owner change moves the deposit.
+		Balances::make_free_balance_be(&2, 100);
+		assert_ok!(Uniques::force_item_status(Origin::root(), 0, 2, 1, 1, 1, true, false));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::reserved_balance(2), 65);
+	});
+}
+
+#[test]
+fn burn_works() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&1, 100);
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, false));
+		assert_ok!(Uniques::set_team(Origin::signed(1), 0, 2, 3, 4));
+
+		assert_noop!(Uniques::burn(Origin::signed(5), 0, 42, Some(5), 0), Error::<Test>::Unknown);
+
+		assert_ok!(Uniques::mint(Origin::signed(2), 0, 42, 5));
+		assert_ok!(Uniques::mint(Origin::signed(2), 0, 69, 5));
+		assert_eq!(Balances::reserved_balance(1), 2);
+
+		assert_noop!(Uniques::burn(Origin::signed(0), 0, 42, None, 0), Error::<Test>::NoPermission);
+		assert_noop!(Uniques::burn(Origin::signed(5), 0, 42, Some(6), 0), Error::<Test>::WrongOwner);
+
+		assert_ok!(Uniques::burn(Origin::signed(5), 0, 42, Some(5), 0));
+		assert_ok!(Uniques::burn(Origin::signed(3), 0, 69, Some(5), 0));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(items(), vec![]);
+	});
+}
+
+#[test]
+fn burn_clears_metadata_and_attributes() {
+	new_test_ext().execute_with(|| {
+		Balances::make_free_balance_be(&1, 100);
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, false));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 69, 2));
+
+		assert_ok!(Uniques::set_metadata(Origin::signed(1), 0, 42, vec![0; 10], false));
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, None, vec![0], vec![0]));
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, Some(42), vec![0], vec![0]));
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, Some(42), vec![1], vec![0]));
+		assert_ok!(Uniques::set_attribute(Origin::signed(1), 0, Some(69), vec![0], vec![0]));
+		assert_eq!(Item::<Test>::get(0, 42).unwrap().attributes, 2);
+		assert_eq!(Item::<Test>::get(0, 69).unwrap().attributes, 1);
+		let reserved = Balances::reserved_balance(1);
+
+		assert_noop!(
+			Uniques::burn(Origin::signed(2), 0, 42, None, 1),
+			Error::<Test>::BadWitness,
+		);
+		assert_ok!(Uniques::burn(Origin::signed(2), 0, 42, None, 2));
+		assert!(ItemMetadataOf::<Test>::get(0, 42).is_none());
+		assert_eq!(attributes(0), vec![
+			(None, vec![0], vec![0]),
+			(Some(69), vec![0], vec![0]),
+		]);
+		// item deposit of 1, metadata deposit of 11 and two attribute deposits of 3.
+		assert_eq!(Balances::reserved_balance(1), reserved - 18);
+
+		let w = Collection::<Test>::get(0).unwrap().destroy_witness();
+		assert_eq!((w.items, w.item_metadatas, w.attributes), (1, 0, 2));
+		assert_eq!(Collection::<Test>::get(0).unwrap().total_deposit, reserved - 18);
+
+		assert_ok!(Uniques::clear_attribute(Origin::signed(1), 0, Some(69), vec![0]));
+		assert_eq!(Item::<Test>::get(0, 69).unwrap().attributes, 0);
+	});
+}
+
+#[test]
+fn approval_lifecycle_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, true));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+		assert_ok!(Uniques::approve_transfer(Origin::signed(2), 0, 42, 3));
+		assert_ok!(Uniques::transfer(Origin::signed(3), 0, 42, 4));
+		assert_noop!(Uniques::transfer(Origin::signed(3), 0, 42, 3), Error::<Test>::NoPermission);
+		assert!(Item::<Test>::get(0, 42).unwrap().approved.is_none());
+
+		assert_ok!(Uniques::approve_transfer(Origin::signed(4), 0, 42, 2));
+		assert_ok!(Uniques::transfer(Origin::signed(2), 0, 42, 2));
+	});
+}
+
+#[test]
+fn transfer_by_owner_resets_approval() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, true));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+		assert_ok!(Uniques::approve_transfer(Origin::signed(2), 0, 42, 3));
+
+		// the owner transfers the item themselves.
+		assert_ok!(Uniques::transfer(Origin::signed(2), 0, 42, 4));
+		assert!(Item::<Test>::get(0, 42).unwrap().approved.is_none());
+
+		// the old delegate may no longer move the item of the new owner.
+		assert_noop!(Uniques::transfer(Origin::signed(3), 0, 42, 3), Error::<Test>::NoPermission);
+		assert_eq!(items(), vec![(4, 0, 42)]);
+	});
+}
+
+#[test]
+fn cancel_approval_works() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, true));
+		assert_ok!(Uniques::mint(Origin::signed(1), 0, 42, 2));
+
+		assert_ok!(Uniques::approve_transfer(Origin::signed(2), 0, 42, 3));
+		assert_noop!(Uniques::cancel_approval(Origin::signed(2), 1, 42, None), Error::<Test>::Unknown);
+		assert_noop!(Uniques::cancel_approval(Origin::signed(2), 0, 43, None), Error::<Test>::Unknown);
+		assert_noop!(Uniques::cancel_approval(Origin::signed(3), 0, 42, None), Error::<Test>::NoPermission);
+		assert_noop!(
+			Uniques::cancel_approval(Origin::signed(2), 0, 42, Some(4)),
+			Error::<Test>::WrongDelegate,
+		);
+
+		assert_ok!(Uniques::cancel_approval(Origin::signed(2), 0, 42, Some(3)));
+		assert_noop!(Uniques::cancel_approval(Origin::signed(2), 0, 42, None), Error::<Test>::NoDelegate);
+
+		// the admin and the force origin may cancel approvals, too.
+		assert_ok!(Uniques::approve_transfer(Origin::signed(2), 0, 42, 3));
+		assert_ok!(Uniques::cancel_approval(Origin::signed(1), 0, 42, Some(3)));
+		assert_ok!(Uniques::approve_transfer(Origin::signed(2), 0, 42, 3));
+		assert_ok!(Uniques::cancel_approval(Origin::root(), 0, 42, None));
+		assert_eq!(last_event(), TestEvent::uniques(RawEvent::ApprovalCancelled(0, 42, 2, 3)));
+	});
+}
+
+#[test]
+fn nonfungibles_traits_work() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Uniques::force_create(Origin::root(), 0, 1, true));
+		assert_ok!(<Uniques as Mutate<u64>>::mint_into(&0, &42, &2));
+		assert_ok!(<Uniques as Mutate<u64>>::mint_into(&0, &69, &2));
+		assert_eq!(Uniques::owner(&0, &42), Some(2));
+		assert_eq!(Uniques::collection_owner(&0), Some(1));
+
+		assert_ok!(<Uniques as Transfer<u64>>::transfer(&0, &42, &3));
+		assert_eq!(Uniques::owner(&0, &42), Some(3));
+
+		let mut items = Uniques::items(&0).collect::<Vec<_>>();
+		items.sort();
+		assert_eq!(items, vec![42, 69]);
+		assert_eq!(Uniques::owned(&2).collect::<Vec<_>>(), vec![(0, 69)]);
+
+		assert_ok!(Uniques::set_metadata(Origin::root(), 0, 42, vec![1, 2], false));
+		assert_ok!(Uniques::set_attribute(Origin::root(), 0, Some(42), 7u32.encode(), 8u64.encode()));
+		assert_eq!(Uniques::attribute(&0, &42, &[]), Some(vec![1, 2]));
+		assert_eq!(Uniques::typed_attribute::<u32, u64>(&0, &42, &7), Some(8));
+
+		assert_ok!(<Uniques as Mutate<u64>>::burn_from(&0, &42));
+		assert_eq!(Uniques::owner(&0, &42), None);
+		assert_eq!(Uniques::attribute(&0, &42, &[]), None);
+		assert_eq!(Uniques::typed_attribute::<u32, u64>(&0, &42, &7), None);
+	});
+}
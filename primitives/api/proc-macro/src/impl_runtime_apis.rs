@@ -289,13 +289,11 @@ fn generate_runtime_api_base_structures() -> Result<TokenStream> {
 			fn extract_proof(&mut self) -> Option<#crate_::StorageProof> {
 				self.recorder
 					.take()
-					.map(|recorder| {
-						let trie_nodes = recorder.read()
-							.iter()
-							.filter_map(|(_k, v)| v.as_ref().map(|v| v.to_vec()))
-							.collect();
-						#crate_::StorageProof::new(trie_nodes)
-					})
+					.map(|recorder| recorder.to_storage_proof())
+			}
+
+			fn estimated_proof_size(&self) -> Option<usize> {
+				self.recorder.as_ref().map(|recorder| recorder.estimate_encoded_size())
 			}
 
 			fn into_storage_changes(
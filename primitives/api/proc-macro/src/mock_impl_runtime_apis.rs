@@ -115,6 +115,10 @@ fn implement_common_api_traits(
 				unimplemented!("`extract_proof` not implemented for runtime api mocks")
 			}
 
+			fn estimated_proof_size(&self) -> Option<usize> {
+				unimplemented!("`estimated_proof_size` not implemented for runtime api mocks")
+			}
+
 			fn into_storage_changes(
 				&self,
 				_: &Self::StateBackend,
@@ -439,6 +439,11 @@ pub trait ApiExt<Block: BlockT>: ApiErrorExt {
 	/// If `record_proof` was not called before, this will return `None`.
 	fn extract_proof(&mut self) -> Option<StorageProof>;
 
+	/// Returns the estimated encoded size of the proof recorded so far.
+	///
+	/// If `record_proof` was not called before, this will return `None`.
+	fn estimated_proof_size(&self) -> Option<usize>;
+
 	/// Convert the api object into the storage changes that were done while executing runtime
 	/// api functions.
 	///
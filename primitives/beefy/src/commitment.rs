@@ -0,0 +1,84 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use codec::{Decode, Encode};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+use crate::ValidatorSetId;
+
+/// A commitment signed by BEEFY validators.
+///
+/// The commitment is what the validators actually sign, it must be compact, since light clients
+/// and bridges verify one signature of it per validator.
+#[derive(Clone, RuntimeDebug, PartialEq, Eq, Encode, Decode)]
+pub struct Commitment<TBlockNumber, TPayload> {
+	/// The payload being signed, e.g. the MMR root hash at the block.
+	pub payload: TPayload,
+	/// Number of the finalized block the payload is about.
+	pub block_number: TBlockNumber,
+	/// Identifier of the validator set signing the commitment.
+	///
+	/// The set is identified rather than included, verifiers are expected to track validator set
+	/// changes through the `AuthoritiesChange` logs of signed blocks.
+	pub validator_set_id: ValidatorSetId,
+}
+
+/// A commitment with the signatures of its validator set.
+#[derive(Clone, RuntimeDebug, PartialEq, Eq, Encode, Decode)]
+pub struct SignedCommitment<TBlockNumber, TPayload, TSignature> {
+	/// The commitment signed by the validators.
+	pub commitment: Commitment<TBlockNumber, TPayload>,
+	/// Signatures of the validators, in the order of the validator set.
+	///
+	/// A validator that did not sign has a `None` entry, so that verifiers can match signatures
+	/// with public keys without trying them all.
+	pub signatures: Vec<Option<TSignature>>,
+}
+
+impl<TBlockNumber, TPayload, TSignature> SignedCommitment<TBlockNumber, TPayload, TSignature> {
+	/// Number of validators that signed the commitment.
+	pub fn no_of_signatures(&self) -> usize {
+		self.signatures.iter().filter(|signature| signature.is_some()).count()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	type TestCommitment = Commitment<u64, [u8; 4]>;
+
+	fn commitment(block_number: u64, validator_set_id: ValidatorSetId) -> TestCommitment {
+		Commitment { payload: *b"root", block_number, validator_set_id }
+	}
+
+	#[test]
+	fn commitment_encode_decode() {
+		let unsigned = commitment(5, 0);
+		let encoded = unsigned.encode();
+		assert_eq!(TestCommitment::decode(&mut &*encoded), Ok(unsigned));
+
+		let signed = SignedCommitment {
+			commitment: commitment(5, 0),
+			signatures: vec![None, Some(vec![1u8; 65]), None],
+		};
+		let encoded = signed.encode();
+		assert_eq!(SignedCommitment::decode(&mut &*encoded), Ok(signed.clone()));
+		assert_eq!(signed.no_of_signatures(), 1);
+	}
+}
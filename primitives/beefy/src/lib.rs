@@ -0,0 +1,137 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Primitives for BEEFY, a secondary finality gadget running on top of GRANDPA.
+//!
+//! GRANDPA justifications are made of ed25519 signatures, which are expensive to verify outside
+//! of Substrate (e.g. in an Ethereum smart contract). BEEFY validators sign compact
+//! [`Commitment`]s to the MMR root of a subset of the blocks finalized by GRANDPA with ECDSA
+//! keys instead, so that bridges can verify them cheaply.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+mod commitment;
+
+pub use commitment::{Commitment, SignedCommitment};
+
+use codec::{Codec, Decode, Encode};
+use sp_core::H256;
+use sp_runtime::{ConsensusEngineId, RuntimeDebug};
+use sp_std::prelude::*;
+
+/// Key type for BEEFY module.
+pub const KEY_TYPE: sp_core::crypto::KeyTypeId = sp_core::crypto::KeyTypeId(*b"beef");
+
+/// BEEFY cryptographic types.
+///
+/// Validators sign with ECDSA (secp256k1) keys, so that their signatures can be verified by
+/// Ethereum smart contracts.
+pub mod crypto {
+	use sp_application_crypto::{app_crypto, ecdsa};
+	app_crypto!(ecdsa, crate::KEY_TYPE);
+
+	/// Identity of a BEEFY authority using ECDSA as its crypto.
+	pub type AuthorityId = Public;
+
+	/// Signature for a BEEFY authority using ECDSA as its crypto.
+	pub type AuthoritySignature = Signature;
+}
+
+sp_application_crypto::with_pair! {
+	/// A BEEFY authority keypair.
+	pub type AuthorityPair = crypto::Pair;
+}
+
+/// Identity of a BEEFY authority.
+pub type AuthorityId = crypto::AuthorityId;
+
+/// Signature of a BEEFY authority.
+pub type AuthoritySignature = crypto::AuthoritySignature;
+
+/// The `ConsensusEngineId` of BEEFY.
+pub const BEEFY_ENGINE_ID: ConsensusEngineId = *b"BEEF";
+
+/// The MMR root hash signed by BEEFY validators.
+pub type MmrRootHash = H256;
+
+/// The monotonic identifier of a BEEFY set of authorities.
+pub type ValidatorSetId = u64;
+
+/// The index of an authority in its validator set.
+pub type AuthorityIndex = u32;
+
+/// A set of BEEFY authorities, with its identifier.
+#[derive(Decode, Encode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ValidatorSet<AuthorityId> {
+	/// Public keys of the validators, in the order their signatures appear in a
+	/// [`SignedCommitment`].
+	pub validators: Vec<AuthorityId>,
+	/// Identifier of the validator set.
+	pub id: ValidatorSetId,
+}
+
+impl<AuthorityId> ValidatorSet<AuthorityId> {
+	/// Return an empty validator set with id 0.
+	pub fn empty() -> Self {
+		ValidatorSet { validators: Vec::new(), id: 0 }
+	}
+
+	/// Number of signatures needed for a commitment of this set to be final, i.e. more than
+	/// two thirds of the validators.
+	pub fn threshold(&self) -> usize {
+		let len = self.validators.len();
+		len - len.saturating_sub(1) / 3
+	}
+}
+
+/// A consensus log item for BEEFY.
+#[derive(Decode, Encode, PartialEq, Eq, Clone, RuntimeDebug)]
+pub enum ConsensusLog<AuthorityId: Codec> {
+	/// The validator set has changed. Commitments of this block and its descendants must be
+	/// signed by the new set.
+	#[codec(index = "1")]
+	AuthoritiesChange(ValidatorSet<AuthorityId>),
+	/// Disable the validator with given index.
+	#[codec(index = "2")]
+	OnDisabled(AuthorityIndex),
+}
+
+sp_api::decl_runtime_apis! {
+	/// API necessary for BEEFY voters.
+	pub trait BeefyApi {
+		/// Return the current active BEEFY validator set.
+		fn validator_set() -> ValidatorSet<AuthorityId>;
+		/// Return the BEEFY validator set of the next session.
+		fn next_validator_set() -> ValidatorSet<AuthorityId>;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn threshold_is_more_than_two_thirds() {
+		let set = |len| ValidatorSet { validators: vec![(); len], id: 0 };
+		assert_eq!(set(0).threshold(), 0);
+		assert_eq!(set(1).threshold(), 1);
+		assert_eq!(set(3).threshold(), 3);
+		assert_eq!(set(4).threshold(), 3);
+		assert_eq!(set(10).threshold(), 7);
+	}
+}
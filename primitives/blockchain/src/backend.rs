@@ -99,6 +99,23 @@ pub trait Backend<Block: BlockT>: HeaderBackend<Block> + HeaderMetadata<Block, E
 	/// Return hashes of all blocks that are children of the block with `parent_hash`.
 	fn children(&self, parent_hash: Block::Hash) -> Result<Vec<Block::Hash>>;
 
+	/// Get single indexed transaction by content hash.
+	///
+	/// Note that this will only fetch transactions that are indexed by the runtime with
+	/// `storage_index_transaction`.
+	fn indexed_transaction(&self, hash: &Block::Hash) -> Result<Option<Vec<u8>>>;
+
+	/// Check if indexed transaction exists.
+	fn has_indexed_transaction(&self, hash: &Block::Hash) -> Result<bool> {
+		Ok(self.indexed_transaction(hash)?.is_some())
+	}
+
+	/// Get all indexed transactions for a block, including renewed transactions.
+	///
+	/// Note that this will only fetch transactions that are indexed by the runtime with
+	/// `storage_index_transaction`.
+	fn block_indexed_body(&self, id: BlockId<Block>) -> Result<Option<Vec<Vec<u8>>>>;
+
 	/// Get the most recent block hash of the best (longest) chains
 	/// that contain block with the given `target_hash`.
 	///
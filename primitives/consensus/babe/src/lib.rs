@@ -120,6 +120,13 @@ pub fn make_transcript_data(
 	}
 }
 
+/// Returns the index of the epoch the given slot belongs to, for a chain
+/// starting at `genesis_slot`. Epochs in which no block was produced still
+/// count towards the index.
+pub fn epoch_index(slot_number: SlotNumber, genesis_slot: SlotNumber, epoch_duration: u64) -> u64 {
+	slot_number.saturating_sub(genesis_slot) / epoch_duration
+}
+
 /// An consensus log item for BABE.
 #[derive(Decode, Encode, Clone, PartialEq, Eq)]
 pub enum ConsensusLog {
@@ -21,7 +21,7 @@
 
 use sp_std::vec::Vec;
 use sp_runtime::ConsensusEngineId;
-use codec::Decode;
+use codec::{Decode, Encode};
 
 /// The `ConsensusEngineId` of PoW.
 pub const POW_ENGINE_ID: ConsensusEngineId = [b'p', b'o', b'w', b'_'];
@@ -29,6 +29,20 @@ pub const POW_ENGINE_ID: ConsensusEngineId = [b'p', b'o', b'w', b'_'];
 /// Type of seal.
 pub type Seal = Vec<u8>;
 
+/// Identifier of a PoW algorithm, for chains that can be mined with several algorithms.
+pub type PowAlgorithmId = [u8; 8];
+
+/// Seal of a chain mined with several algorithms. It is stored SCALE-encoded as
+/// the `Seal` of the PoW seal digest item, keyed by the algorithm that produced
+/// the inner seal.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct MultiAlgoSeal {
+	/// The algorithm that produced the inner seal.
+	pub algorithm: PowAlgorithmId,
+	/// The seal produced by the algorithm.
+	pub seal: Seal,
+}
+
 /// Define methods that total difficulty should implement.
 pub trait TotalDifficulty {
 	fn increment(&mut self, other: Self);
@@ -32,6 +32,10 @@ pub mod testing;
 /// Local storage prefix used by the Offchain Worker API to
 pub const STORAGE_PREFIX : &'static [u8] = b"storage";
 
+/// Prefix of the changes made through the offchain indexing API that go to the fork-aware
+/// [`StorageKind::LOCAL`] storage.
+pub const LOCAL_STORAGE_PREFIX : &'static [u8] = b"local_storage";
+
 /// Offchain workers local storage.
 pub trait OffchainStorage: Clone + Send + Sync {
 	/// Persist a value in storage under given key and prefix.
@@ -53,6 +57,60 @@ pub trait OffchainStorage: Clone + Send + Sync {
 		old_value: Option<&[u8]>,
 		new_value: &[u8],
 	) -> bool;
+
+	/// Persist a fork-aware value under given key and prefix.
+	///
+	/// The write is journaled against block `at` (the raw block hash) and is only visible
+	/// when reading at `at` or any of its descendants. See [`StorageKind::LOCAL`].
+	/// Writes at blocks that are unknown or already discarded are ignored.
+	///
+	/// The default implementation is not fork-aware: it ignores `at` and keeps the value apart
+	/// from the persistent ones, under [`FORK_AWARE_PREFIX`].
+	fn set_at(&mut self, at: &[u8], prefix: &[u8], key: &[u8], value: &[u8]) {
+		let _ = at;
+		self.set(&fork_aware_prefix(prefix), key, value)
+	}
+
+	/// Clear a fork-aware storage entry under given key and prefix, as seen from block `at`.
+	///
+	/// The default implementation ignores `at`, see [`Self::set_at`].
+	fn remove_at(&mut self, at: &[u8], prefix: &[u8], key: &[u8]) {
+		let _ = at;
+		self.remove(&fork_aware_prefix(prefix), key)
+	}
+
+	/// Retrieve a fork-aware value under given key and prefix, as seen from block `at`.
+	///
+	/// The default implementation ignores `at`, see [`Self::set_at`].
+	fn get_at(&self, at: &[u8], prefix: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		let _ = at;
+		self.get(&fork_aware_prefix(prefix), key)
+	}
+
+	/// Replace the fork-aware value seen from block `at` if given old_value matches it.
+	///
+	/// Returns `true` if the value has been set and false otherwise.
+	///
+	/// The default implementation ignores `at`, see [`Self::set_at`].
+	fn compare_and_set_at(
+		&mut self,
+		at: &[u8],
+		prefix: &[u8],
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool {
+		let _ = at;
+		self.compare_and_set(&fork_aware_prefix(prefix), key, old_value, new_value)
+	}
+}
+
+/// Prefix the default fork-aware [`OffchainStorage`] methods put in front of the prefix of a
+/// value, to keep it apart from the persistent values.
+pub const FORK_AWARE_PREFIX: &[u8] = b"fork_aware/";
+
+fn fork_aware_prefix(prefix: &[u8]) -> Vec<u8> {
+	FORK_AWARE_PREFIX.iter().chain(prefix).cloned().collect()
 }
 
 /// A type of supported crypto.
@@ -71,6 +129,8 @@ pub enum StorageKind {
 	/// set by the offchain worker triggered at block `N(hash1)` is reverted
 	/// if that block is reverted as non-canonical and is NOT available for the worker
 	/// that is re-run at block `N(hash2)`.
+	/// Values written through the fork-aware offchain indexing API during block import
+	/// end up in this storage as well, journaled against the imported block.
 	LOCAL = 2,
 }
 
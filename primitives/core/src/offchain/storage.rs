@@ -22,9 +22,13 @@ use crate::offchain::OffchainStorage;
 use std::iter::Iterator;
 
 /// In-memory storage for offchain workers.
+///
+/// Forks are not tracked here: fork-aware values are kept apart from the persistent ones,
+/// but the block they were written at is ignored.
 #[derive(Debug, Clone, Default)]
 pub struct InMemOffchainStorage {
 	storage: HashMap<Vec<u8>, Vec<u8>>,
+	fork_aware: HashMap<Vec<u8>, Vec<u8>>,
 }
 
 impl InMemOffchainStorage {
@@ -69,23 +73,55 @@ impl OffchainStorage for InMemOffchainStorage {
 		new_value: &[u8],
 	) -> bool {
 		let key = prefix.iter().chain(key).cloned().collect();
+		compare_and_set(&mut self.storage, key, old_value, new_value)
+	}
 
-		match self.storage.entry(key) {
-			Entry::Vacant(entry) => if old_value.is_none() {
-				entry.insert(new_value.to_vec());
-				true
-			} else { false },
-			Entry::Occupied(ref mut entry) if Some(entry.get().as_slice()) == old_value => {
-				entry.insert(new_value.to_vec());
-				true
-			},
-			_ => false,
-		}
+	fn set_at(&mut self, _at: &[u8], prefix: &[u8], key: &[u8], value: &[u8]) {
+		let key = prefix.iter().chain(key).cloned().collect();
+		self.fork_aware.insert(key, value.to_vec());
 	}
-}
 
+	fn remove_at(&mut self, _at: &[u8], prefix: &[u8], key: &[u8]) {
+		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
+		self.fork_aware.remove(&key);
+	}
 
+	fn get_at(&self, _at: &[u8], prefix: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
+		self.fork_aware.get(&key).cloned()
+	}
 
+	fn compare_and_set_at(
+		&mut self,
+		_at: &[u8],
+		prefix: &[u8],
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool {
+		let key = prefix.iter().chain(key).cloned().collect();
+		compare_and_set(&mut self.fork_aware, key, old_value, new_value)
+	}
+}
+
+fn compare_and_set(
+	storage: &mut HashMap<Vec<u8>, Vec<u8>>,
+	key: Vec<u8>,
+	old_value: Option<&[u8]>,
+	new_value: &[u8],
+) -> bool {
+	match storage.entry(key) {
+		Entry::Vacant(entry) => if old_value.is_none() {
+			entry.insert(new_value.to_vec());
+			true
+		} else { false },
+		Entry::Occupied(ref mut entry) if Some(entry.get().as_slice()) == old_value => {
+			entry.insert(new_value.to_vec());
+			true
+		},
+		_ => false,
+	}
+}
 
 /// Change to be applied to the offchain worker db in regards to a key.
 #[derive(Debug,Clone,Hash,Eq,PartialEq)]
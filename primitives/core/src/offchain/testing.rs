@@ -33,6 +33,7 @@ use crate::offchain::{
 	HttpRequestStatus as RequestStatus,
 	Timestamp,
 	StorageKind,
+	LOCAL_STORAGE_PREFIX,
 	OpaqueNetworkState,
 	TransactionPool,
 	OffchainStorage,
@@ -63,7 +64,9 @@ pub struct PendingRequest {
 	pub response_headers: Vec<(String, String)>,
 }
 
-/// Sharable "persistent" offchain storage for test.
+/// Sharable offchain storage for test.
+///
+/// Holds both the persistent and the fork-aware items, the latter without tracking any forks.
 #[derive(Debug, Clone, Default)]
 pub struct TestPersistentOffchainDB {
 	persistent: Arc<RwLock<InMemOffchainStorage>>,
@@ -78,12 +81,18 @@ impl TestPersistentOffchainDB {
 	}
 
 	/// Apply a set of off-chain changes directly to the test backend
+	///
+	/// Like on a real node, fork-aware indexed changes end up in the fork-aware storage.
 	pub fn apply_offchain_changes(&mut self, changes: &mut OffchainOverlayedChanges) {
 		let mut me = self.persistent.write();
-		for ((_prefix, key), value_operation) in changes.drain() {
-			match value_operation {
-				OffchainOverlayedChange::SetValue(val) => me.set(b"", key.as_slice(), val.as_slice()),
-				OffchainOverlayedChange::Remove => me.remove(b"", key.as_slice()),
+		for ((prefix, key), value_operation) in changes.drain() {
+			match (prefix == LOCAL_STORAGE_PREFIX, value_operation) {
+				(false, OffchainOverlayedChange::SetValue(val)) =>
+					me.set(b"", key.as_slice(), val.as_slice()),
+				(false, OffchainOverlayedChange::Remove) => me.remove(b"", key.as_slice()),
+				(true, OffchainOverlayedChange::SetValue(val)) =>
+					me.set_at(&[], b"", key.as_slice(), val.as_slice()),
+				(true, OffchainOverlayedChange::Remove) => me.remove_at(&[], b"", key.as_slice()),
 			}
 		}
 	}
@@ -111,6 +120,29 @@ impl OffchainStorage for TestPersistentOffchainDB {
 	) -> bool {
 		self.persistent.write().compare_and_set(prefix, key, old_value, new_value)
 	}
+
+	fn set_at(&mut self, at: &[u8], prefix: &[u8], key: &[u8], value: &[u8]) {
+		self.persistent.write().set_at(at, prefix, key, value);
+	}
+
+	fn remove_at(&mut self, at: &[u8], prefix: &[u8], key: &[u8]) {
+		self.persistent.write().remove_at(at, prefix, key);
+	}
+
+	fn get_at(&self, at: &[u8], prefix: &[u8], key: &[u8]) -> Option<Vec<u8>> {
+		self.persistent.read().get_at(at, prefix, key)
+	}
+
+	fn compare_and_set_at(
+		&mut self,
+		at: &[u8],
+		prefix: &[u8],
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool {
+		self.persistent.write().compare_and_set_at(at, prefix, key, old_value, new_value)
+	}
 }
 
 
@@ -123,10 +155,8 @@ pub struct OffchainState {
 	pub requests: BTreeMap<RequestId, PendingRequest>,
 	// Queue of requests that the test is expected to perform (in order).
 	expected_requests: VecDeque<PendingRequest>,
-	/// Persistent local storage
+	/// Persistent and fork-aware local storage
 	pub persistent_storage: TestPersistentOffchainDB,
-	/// Local storage
-	pub local_storage: InMemOffchainStorage,
 	/// A supposedly random seed.
 	pub seed: [u8; 32],
 	/// A timestamp simulating the current time.
@@ -236,7 +266,7 @@ impl offchain::Externalities for TestOffchainExt {
 	fn local_storage_set(&mut self, kind: StorageKind, key: &[u8], value: &[u8]) {
 		let mut state = self.0.write();
 		match kind {
-			StorageKind::LOCAL => state.local_storage.set(b"", key, value),
+			StorageKind::LOCAL => state.persistent_storage.set_at(&[], b"", key, value),
 			StorageKind::PERSISTENT => state.persistent_storage.set(b"", key, value),
 		};
 	}
@@ -244,7 +274,7 @@ impl offchain::Externalities for TestOffchainExt {
 	fn local_storage_clear(&mut self, kind: StorageKind, key: &[u8]) {
 		let mut state = self.0.write();
 		match kind {
-			StorageKind::LOCAL => state.local_storage.remove(b"", key),
+			StorageKind::LOCAL => state.persistent_storage.remove_at(&[], b"", key),
 			StorageKind::PERSISTENT => state.persistent_storage.remove(b"", key),
 		};
 	}
@@ -258,7 +288,8 @@ impl offchain::Externalities for TestOffchainExt {
 	) -> bool {
 		let mut state = self.0.write();
 		match kind {
-			StorageKind::LOCAL => state.local_storage.compare_and_set(b"", key, old_value, new_value),
+			StorageKind::LOCAL => state.persistent_storage
+				.compare_and_set_at(&[], b"", key, old_value, new_value),
 			StorageKind::PERSISTENT => state.persistent_storage.compare_and_set(b"", key, old_value, new_value),
 		}
 	}
@@ -266,7 +297,7 @@ impl offchain::Externalities for TestOffchainExt {
 	fn local_storage_get(&mut self, kind: StorageKind, key: &[u8]) -> Option<Vec<u8>> {
 		let state = self.0.read();
 		match kind {
-			StorageKind::LOCAL => state.local_storage.get(b"", key),
+			StorageKind::LOCAL => state.persistent_storage.get_at(&[], b"", key),
 			StorageKind::PERSISTENT => state.persistent_storage.get(b"", key),
 		}
 	}
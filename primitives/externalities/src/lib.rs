@@ -55,6 +55,12 @@ pub trait Externalities: ExtensionStore {
 	/// Write a key value pair to the offchain storage database.
 	fn set_offchain_storage(&mut self, key: &[u8], value: Option<&[u8]>);
 
+	/// Write a key value pair to the fork-aware offchain storage database.
+	///
+	/// Unlike `set_offchain_storage`, the write is journaled against the block that makes it,
+	/// and is discarded if that block doesn't end up being finalized.
+	fn set_offchain_local_storage(&mut self, key: &[u8], value: Option<&[u8]>);
+
 	/// Read runtime storage.
 	fn storage(&self, key: &[u8]) -> Option<Vec<u8>>;
 
@@ -220,6 +226,18 @@ pub trait Externalities: ExtensionStore {
 	/// no transaction is open that can be closed.
 	fn storage_commit_transaction(&mut self) -> Result<(), ()>;
 
+	/// Index the trailing `size` bytes of the extrinsic with the given `index` in the block
+	/// being built or imported, under the content `hash`.
+	///
+	/// Does nothing by default, for externalities that don't keep a transaction index.
+	fn storage_index_transaction(&mut self, _index: u32, _hash: &[u8], _size: u32) {}
+
+	/// Renew the already indexed data with the given content `hash`, as if it was indexed again
+	/// by the extrinsic with the given `index`.
+	///
+	/// Does nothing by default, for externalities that don't keep a transaction index.
+	fn storage_renew_transaction_index(&mut self, _index: u32, _hash: &[u8]) {}
+
 	/// !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
 	/// Benchmarking related functionality and shouldn't be used anywhere else!
 	/// !!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!
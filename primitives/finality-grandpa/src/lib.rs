@@ -26,7 +26,7 @@ extern crate alloc;
 use serde::Serialize;
 
 use codec::{Encode, Decode, Input, Codec};
-use sp_runtime::{ConsensusEngineId, RuntimeDebug, traits::NumberFor};
+use sp_runtime::{ConsensusEngineId, Percent, RuntimeDebug, traits::NumberFor};
 use sp_std::borrow::Cow;
 use sp_std::vec::Vec;
 #[cfg(feature = "std")]
@@ -86,6 +86,22 @@ pub struct ScheduledChange<N> {
 	pub delay: N,
 }
 
+/// Parameters of the voting rules that GRANDPA voters apply on top of their
+/// local ones, kept in the runtime so that they can be tuned without a node
+/// release. Each rule is disabled when its parameter is `None`.
+#[cfg_attr(feature = "std", derive(Serialize))]
+#[derive(Clone, Default, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct VotingRuleParameters<N> {
+	/// Always vote at least this many blocks behind the best block.
+	pub before_best_block_by: Option<N>,
+	/// Never vote on more than this many unfinalized blocks past the round
+	/// base in a single round.
+	pub max_unfinalized_blocks_per_round: Option<N>,
+	/// Never vote on blocks that are not yet available to at least this share
+	/// of the connected peers.
+	pub min_peer_availability: Option<Percent>,
+}
+
 /// An consensus log item for GRANDPA.
 #[cfg_attr(feature = "std", derive(Serialize))]
 #[derive(Decode, Encode, PartialEq, Eq, Clone, RuntimeDebug)]
@@ -527,4 +543,13 @@ sp_api::decl_runtime_apis! {
 			authority_id: AuthorityId,
 		) -> Option<OpaqueKeyOwnershipProof>;
 	}
+
+	/// APIs for reading the parameters of the GRANDPA voting rules from the
+	/// runtime. This is optional, voters only apply their local voting rules
+	/// if the runtime doesn't implement it.
+	pub trait GrandpaVotingRulesApi {
+		/// Get the parameters of the voting rules to apply when voting on
+		/// descendants of this block.
+		fn voting_rule_parameters() -> VotingRuleParameters<NumberFor<Block>>;
+	}
 }
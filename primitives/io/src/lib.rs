@@ -355,6 +355,24 @@ pub trait Trie {
 	fn keccak_256_ordered_root(input: Vec<Vec<u8>>) -> H256 {
 		Layout::<sp_core::KeccakHasher>::ordered_trie_root(input)
 	}
+
+	/// Verify that `key` maps to `value` in the trie with the given `root`, using `proof`.
+	fn blake2_256_verify_proof(root: H256, proof: &[Vec<u8>], key: &[u8], value: &[u8]) -> bool {
+		sp_trie::verify_trie_proof::<Layout<sp_core::Blake2Hasher>, _, _, _>(
+			&root,
+			proof,
+			&[(key, Some(value))],
+		).is_ok()
+	}
+
+	/// Verify that `key` maps to `value` in the trie with the given `root`, using `proof`.
+	fn keccak_256_verify_proof(root: H256, proof: &[Vec<u8>], key: &[u8], value: &[u8]) -> bool {
+		sp_trie::verify_trie_proof::<Layout<sp_core::KeccakHasher>, _, _, _>(
+			&root,
+			proof,
+			&[(key, Some(value))],
+		).is_ok()
+	}
 }
 
 /// Interface that provides miscellaneous functions for communicating between the runtime and the node.
@@ -750,6 +768,38 @@ pub trait OffchainIndex {
 	fn clear(&mut self, key: &[u8]) {
 		self.set_offchain_storage(key, None);
 	}
+
+	/// Write a key value pair to the fork-aware Offchain DB database in a buffered fashion.
+	///
+	/// The write is journaled against the block being imported, so it is only visible
+	/// to the offchain workers through the `StorageKind::LOCAL` storage, and is reverted
+	/// if the block doesn't end up being finalized.
+	fn set_local(&mut self, key: &[u8], value: &[u8]) {
+		self.set_offchain_local_storage(key, Some(value));
+	}
+
+	/// Remove a key and its associated value from the fork-aware Offchain DB.
+	fn clear_local(&mut self, key: &[u8]) {
+		self.set_offchain_local_storage(key, None);
+	}
+}
+
+/// Interface that provides transaction indexing API.
+#[runtime_interface]
+pub trait TransactionIndex {
+	/// Index the trailing `size` bytes of the extrinsic with the given `extrinsic` index.
+	///
+	/// The indexed data is kept by the node under its `context_hash`, so that it can be
+	/// retrieved (and proven) later on without being part of the state.
+	fn index(&mut self, extrinsic: u32, size: u32, context_hash: [u8; 32]) {
+		self.storage_index_transaction(extrinsic, &context_hash, size);
+	}
+
+	/// Renew the data indexed under `context_hash`, as if it was indexed by the extrinsic
+	/// with the given `extrinsic` index.
+	fn renew(&mut self, extrinsic: u32, context_hash: [u8; 32]) {
+		self.storage_renew_transaction_index(extrinsic, &context_hash);
+	}
 }
 
 #[cfg(feature = "std")]
@@ -1334,6 +1384,7 @@ pub type SubstrateHostFunctions = (
 	crate::trie::HostFunctions,
 	offchain_index::HostFunctions,
 	runtime_tasks::HostFunctions,
+	transaction_index::HostFunctions,
 );
 
 #[cfg(test)]
@@ -37,7 +37,7 @@ use crate::{warn, trace, log_error};
 #[cfg(feature = "std")]
 use sp_core::offchain::storage::OffchainOverlayedChanges;
 #[cfg(feature = "std")]
-use crate::changes_trie::State as ChangesTrieState;
+use crate::{changes_trie::State as ChangesTrieState, IndexOperation};
 use crate::StorageTransactionCache;
 #[cfg(feature = "std")]
 use std::error;
@@ -218,6 +218,18 @@ where
 	#[cfg(not(feature = "std"))]
 	fn set_offchain_storage(&mut self, _key: &[u8], _value: Option<&[u8]>) {}
 
+	#[cfg(feature = "std")]
+	fn set_offchain_local_storage(&mut self, key: &[u8], value: Option<&[u8]>) {
+		use ::sp_core::offchain::LOCAL_STORAGE_PREFIX;
+		match value {
+			Some(value) => self.offchain_overlay.set(LOCAL_STORAGE_PREFIX, key, value),
+			None => self.offchain_overlay.remove(LOCAL_STORAGE_PREFIX, key),
+		}
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn set_offchain_local_storage(&mut self, _key: &[u8], _value: Option<&[u8]>) {}
+
 	fn storage(&self, key: &[u8]) -> Option<StorageValue> {
 		let _guard = guard();
 		let result = self.overlay.storage(key).map(|x| x.map(|x| x.to_vec())).unwrap_or_else(||
@@ -612,6 +624,38 @@ where
 		self.overlay.commit_transaction().map_err(|_| ())
 	}
 
+	#[cfg(feature = "std")]
+	fn storage_index_transaction(&mut self, index: u32, hash: &[u8], size: u32) {
+		trace!(
+			target: "state",
+			"{:04x}: IndexTransaction ({}): {}, {} bytes",
+			self.id,
+			index,
+			HexDisplay::from(&hash),
+			size,
+		);
+		self.overlay.add_transaction_index(IndexOperation::Insert {
+			extrinsic: index,
+			hash: hash.to_vec(),
+			size,
+		});
+	}
+
+	#[cfg(feature = "std")]
+	fn storage_renew_transaction_index(&mut self, index: u32, hash: &[u8]) {
+		trace!(
+			target: "state",
+			"{:04x}: RenewTransactionIndex ({}): {}",
+			self.id,
+			index,
+			HexDisplay::from(&hash),
+		);
+		self.overlay.add_transaction_index(IndexOperation::Renew {
+			extrinsic: index,
+			hash: hash.to_vec(),
+		});
+	}
+
 	fn wipe(&mut self) {
 		for _ in 0..self.overlay.transaction_depth() {
 			self.overlay.rollback_transaction().expect(BENCHMARKING_FN);
@@ -1024,4 +1068,54 @@ mod tests {
 
 		assert_eq!(Vec::<u32>::decode(&mut &data[..]).unwrap(), vec![1, 2]);
 	}
+
+	#[test]
+	fn transaction_index_ops_end_up_in_storage_changes() {
+		let mut overlay = OverlayedChanges::default();
+		let mut offchain_overlay = OffchainOverlayedChanges::disabled();
+		let mut cache = StorageTransactionCache::default();
+		let backend = TestBackend::default();
+		let mut ext = TestExt::new(&mut overlay, &mut offchain_overlay, &mut cache, &backend, None, None);
+
+		ext.storage_index_transaction(1, &[1; 32], 100);
+		ext.storage_renew_transaction_index(2, &[2; 32]);
+		drop(ext);
+
+		let changes = overlay.drain_storage_changes::<_, _, u64>(
+			&backend,
+			None,
+			Default::default(),
+			&mut Default::default(),
+		).unwrap();
+		assert_eq!(changes.transaction_index_changes, vec![
+			IndexOperation::Insert { extrinsic: 1, hash: vec![1; 32], size: 100 },
+			IndexOperation::Renew { extrinsic: 2, hash: vec![2; 32] },
+		]);
+		assert_eq!(overlay.transaction_index_ops().count(), 0);
+	}
+
+	#[test]
+	fn transaction_index_ops_are_rolled_back_with_transactions() {
+		let mut overlay = OverlayedChanges::default();
+		let mut offchain_overlay = OffchainOverlayedChanges::disabled();
+		let mut cache = StorageTransactionCache::default();
+		let backend = TestBackend::default();
+		let mut ext = TestExt::new(&mut overlay, &mut offchain_overlay, &mut cache, &backend, None, None);
+
+		ext.storage_start_transaction();
+		ext.storage_index_transaction(1, &[1; 32], 100);
+		ext.storage_start_transaction();
+		ext.storage_renew_transaction_index(2, &[2; 32]);
+		ext.storage_rollback_transaction().unwrap();
+		ext.storage_commit_transaction().unwrap();
+
+		ext.storage_start_transaction();
+		ext.storage_index_transaction(3, &[3; 32], 100);
+		ext.storage_rollback_transaction().unwrap();
+		drop(ext);
+
+		assert_eq!(overlay.transaction_index_ops().cloned().collect::<Vec<_>>(), vec![
+			IndexOperation::Insert { extrinsic: 1, hash: vec![1; 32], size: 100 },
+		]);
+	}
 }
@@ -101,6 +101,33 @@ pub struct OverlayedChanges {
 	collect_extrinsics: bool,
 	/// Collect statistic on this execution.
 	stats: StateMachineStats,
+	/// Transaction index changes.
+	#[cfg(feature = "std")]
+	transaction_index_ops: Vec<IndexOperation>,
+	/// The number of transaction index changes when each of the open transactions was started.
+	#[cfg(feature = "std")]
+	transaction_index_checkpoints: Vec<usize>,
+}
+
+/// Transaction index operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexOperation {
+	/// Insert transaction into index.
+	Insert {
+		/// Extrinsic index in the current block.
+		extrinsic: u32,
+		/// Data content hash.
+		hash: Vec<u8>,
+		/// Indexed data size, counted from the end of the encoded extrinsic.
+		size: u32,
+	},
+	/// Renew existing transaction storage.
+	Renew {
+		/// Extrinsic index in the current block.
+		extrinsic: u32,
+		/// Referenced index hash.
+		hash: Vec<u8>,
+	},
 }
 
 /// A storage changes structure that can be generated by the data collected in [`OverlayedChanges`].
@@ -117,6 +144,9 @@ pub struct StorageChanges<Transaction, H: Hasher, N: BlockNumber> {
 	/// Offchain state changes to write to the offchain database.
 	#[cfg(feature = "std")]
 	pub offchain_storage_changes: OffchainOverlayedChanges,
+	/// Transaction index changes to apply to the block body storage.
+	#[cfg(feature = "std")]
+	pub transaction_index_changes: Vec<IndexOperation>,
 	/// A transaction for the backend that contains all changes from
 	/// [`main_storage_changes`](StorageChanges::main_storage_changes) and from
 	/// [`child_storage_changes`](StorageChanges::child_storage_changes).
@@ -144,6 +174,7 @@ impl<Transaction, H: Hasher, N: BlockNumber> StorageChanges<Transaction, H, N> {
 		Transaction,
 		H::Out,
 		Option<ChangesTrieTransaction<H, N>>,
+		Vec<IndexOperation>,
 	) {
 		(
 			self.main_storage_changes,
@@ -152,6 +183,7 @@ impl<Transaction, H: Hasher, N: BlockNumber> StorageChanges<Transaction, H, N> {
 			self.transaction,
 			self.transaction_storage_root,
 			self.changes_trie_transaction,
+			self.transaction_index_changes,
 		)
 	}
 }
@@ -204,6 +236,8 @@ impl<Transaction: Default, H: Hasher, N: BlockNumber> Default for StorageChanges
 			child_storage_changes: Default::default(),
 			#[cfg(feature = "std")]
 			offchain_storage_changes: Default::default(),
+			#[cfg(feature = "std")]
+			transaction_index_changes: Default::default(),
 			transaction: Default::default(),
 			transaction_storage_root: Default::default(),
 			#[cfg(feature = "std")]
@@ -372,6 +406,8 @@ impl OverlayedChanges {
 		for (_, (changeset, _)) in self.children.iter_mut() {
 			changeset.start_transaction();
 		}
+		#[cfg(feature = "std")]
+		self.transaction_index_checkpoints.push(self.transaction_index_ops.len());
 	}
 
 	/// Rollback the last transaction started by `start_transaction`.
@@ -385,6 +421,8 @@ impl OverlayedChanges {
 				.expect("Top and children changesets are started in lockstep; qed");
 			!changeset.is_empty()
 		});
+		#[cfg(feature = "std")]
+		self.rollback_transaction_index_ops();
 		Ok(())
 	}
 
@@ -398,6 +436,8 @@ impl OverlayedChanges {
 			changeset.commit_transaction()
 				.expect("Top and children changesets are started in lockstep; qed");
 		}
+		#[cfg(feature = "std")]
+		self.transaction_index_checkpoints.pop();
 		Ok(())
 	}
 
@@ -424,9 +464,22 @@ impl OverlayedChanges {
 			changeset.exit_runtime()
 				.expect("Top and children changesets are entering runtime in lockstep; qed");
 		}
+		// Transactions left open by the runtime were rolled back.
+		#[cfg(feature = "std")]
+		while self.transaction_index_checkpoints.len() > self.transaction_depth() {
+			self.rollback_transaction_index_ops();
+		}
 		Ok(())
 	}
 
+	/// Discard the transaction index changes of the last open transaction.
+	#[cfg(feature = "std")]
+	fn rollback_transaction_index_ops(&mut self) {
+		if let Some(checkpoint) = self.transaction_index_checkpoints.pop() {
+			self.transaction_index_ops.truncate(checkpoint);
+		}
+	}
+
 	/// Consume all changes (top + children) and return them.
 	///
 	/// After calling this function no more changes are contained in this changeset.
@@ -524,6 +577,8 @@ impl OverlayedChanges {
 			child_storage_changes: child_storage_changes.map(|(sk, it)| (sk, it.0.collect())).collect(),
 			#[cfg(feature = "std")]
 			offchain_storage_changes: Default::default(),
+			#[cfg(feature = "std")]
+			transaction_index_changes: sp_std::mem::take(&mut self.transaction_index_ops),
 			transaction,
 			transaction_storage_root,
 			#[cfg(feature = "std")]
@@ -533,6 +588,20 @@ impl OverlayedChanges {
 		})
 	}
 
+	/// Add transaction index operation.
+	///
+	/// The operation is discarded if the transaction it is added in is rolled back.
+	#[cfg(feature = "std")]
+	pub fn add_transaction_index(&mut self, op: IndexOperation) {
+		self.transaction_index_ops.push(op)
+	}
+
+	/// Get an iterator over all transaction index operations recorded so far.
+	#[cfg(feature = "std")]
+	pub fn transaction_index_ops(&self) -> impl Iterator<Item=&IndexOperation> {
+		self.transaction_index_ops.iter()
+	}
+
 	/// Inserts storage entry responsible for current extrinsic index.
 	#[cfg(test)]
 	pub(crate) fn set_extrinsic_index(&mut self, extrinsic_index: u32) {
@@ -21,7 +21,7 @@ use std::{any::{Any, TypeId}, panic::{AssertUnwindSafe, UnwindSafe}};
 
 use crate::{
 	backend::Backend, OverlayedChanges, StorageTransactionCache, ext::Ext, InMemoryBackend,
-	StorageKey, StorageValue,
+	StorageKey, StorageValue, IndexOperation,
 	changes_trie::{
 		Configuration as ChangesTrieConfiguration,
 		InMemoryStorage as ChangesTrieInMemoryStorage,
@@ -138,6 +138,11 @@ impl<H: Hasher, N: ChangesTrieBlockNumber> TestExternalities<H, N>
 		self.offchain_db.clone()
 	}
 
+	/// Returns the transaction index operations recorded so far.
+	pub fn transaction_index_ops(&self) -> impl Iterator<Item=&IndexOperation> {
+		self.overlay.transaction_index_ops()
+	}
+
 	/// Insert key/value into backend
 	pub fn insert(&mut self, k: StorageKey, v: StorageValue) {
 		self.backend.insert(vec![(None, vec![(k, Some(v))])]);
@@ -22,6 +22,7 @@ use std::{
 	hash::Hash,
 	sync::Arc,
 	pin::Pin,
+	time::Duration,
 };
 use futures::{Future, Stream};
 use serde::{Deserialize, Serialize};
@@ -118,20 +119,104 @@ pub enum TransactionStatus<Hash, BlockHash> {
 	/// Transaction has been finalized by a finality-gadget, e.g GRANDPA
 	Finalized(BlockHash),
 	/// Transaction has been replaced in the pool, by another transaction
-	/// that provides the same tags. (e.g. same (sender, nonce)).
+	/// that provides the same tags. (e.g. same (sender, nonce)) and has
+	/// a sufficiently higher priority.
 	Usurped(Hash),
-	/// Transaction has been dropped from the pool because of the limit.
-	Dropped,
+	/// Transaction has been dropped from the pool for the given reason.
+	Dropped(DropReason),
 	/// Transaction is no longer valid in the current state.
 	Invalid,
 }
 
+/// The reason a transaction was dropped from the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DropReason {
+	/// The ready or future queue exceeded its limits.
+	PoolLimit,
+	/// The sender of the transaction exceeded its per-sender limit.
+	SenderLimit,
+	/// The transaction ended up in the future queue of a pool that rejects future transactions.
+	FutureRejected,
+	/// The transaction could not be promoted from the future to the ready queue.
+	PromotionFailed,
+}
+
+impl DropReason {
+	/// Returns a short, static name of the reason, suitable for metric labels.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			DropReason::PoolLimit => "pool_limit",
+			DropReason::SenderLimit => "sender_limit",
+			DropReason::FutureRejected => "future_rejected",
+			DropReason::PromotionFailed => "promotion_failed",
+		}
+	}
+}
+
+/// The queue of the pool a transaction currently sits in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionQueue {
+	/// The transaction has all its requirements satisfied and can be included in a block.
+	Ready,
+	/// The transaction is still waiting for some of the tags it requires.
+	Future,
+}
+
+/// Detailed information about a transaction in the pool.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolTransactionInfo<Hash> {
+	/// Hash of the transaction.
+	pub hash: Hash,
+	/// The queue the transaction is in.
+	pub queue: TransactionQueue,
+	/// Priority of the transaction.
+	pub priority: TransactionPriority,
+	/// Block number at which the transaction stops being valid.
+	pub valid_till: TransactionLongevity,
+	/// Tags required by the transaction.
+	pub requires: Vec<TransactionTag>,
+	/// Tags provided by the transaction.
+	pub provides: Vec<TransactionTag>,
+	/// Required tags that are not yet provided by any transaction in the pool or on chain.
+	///
+	/// Always empty for transactions in the ready queue.
+	pub missing_tags: Vec<TransactionTag>,
+	/// Source the transaction was submitted from.
+	pub source: TransactionSource,
+	/// Time elapsed since the transaction was submitted to the pool.
+	pub age: Duration,
+}
+
+/// Events emitted when transactions enter or leave the pool.
+///
+/// Unlike `TransactionStatus` these events are emitted for all transactions in the pool,
+/// not only for the watched ones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PoolEvent<Hash> {
+	/// Transaction was added to the ready queue, either on import or promoted from the future queue.
+	Ready(Hash),
+	/// Transaction was added to the future queue.
+	Future(Hash),
+	/// Transaction was replaced by the second one, providing the same tags.
+	Usurped(Hash, Hash),
+	/// Transaction was dropped from the pool for the given reason.
+	Dropped(Hash, DropReason),
+	/// Transaction was removed from the pool as invalid.
+	Invalid(Hash),
+}
+
 /// The stream of transaction events.
 pub type TransactionStatusStream<Hash, BlockHash> = dyn Stream<Item=TransactionStatus<Hash, BlockHash>> + Send + Unpin;
 
 /// The import notification event stream.
 pub type ImportNotificationStream<H> = futures::channel::mpsc::Receiver<H>;
 
+/// The pool event stream.
+pub type PoolEventStream<H> = futures::channel::mpsc::Receiver<PoolEvent<H>>;
+
 /// Transaction hash type for a pool.
 pub type TxHash<P> = <P as TransactionPool>::Hash;
 /// Block hash type for a pool.
@@ -244,6 +329,16 @@ pub trait TransactionPool: Send + Sync {
 
 	/// Return specific ready transaction by hash, if there is one.
 	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>>;
+
+	// *** RPC
+	/// Returns details of all transactions in the pool, ready ones first ordered by priority.
+	fn inspect(&self) -> Vec<PoolTransactionInfo<TxHash<Self>>>;
+
+	/// Returns details of the transaction with given hash, if it is in the pool.
+	fn transaction_info(&self, hash: &TxHash<Self>) -> Option<PoolTransactionInfo<TxHash<Self>>>;
+
+	/// Return an event stream of transactions entering or leaving the pool.
+	fn pool_event_stream(&self) -> PoolEventStream<TxHash<Self>>;
 }
 
 /// Events that the transaction pool listens for.
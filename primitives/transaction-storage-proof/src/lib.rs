@@ -0,0 +1,258 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2020 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage proof primitives. Contains types and basic code to extract storage
+//! proofs for indexed transactions.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::{result::Result, prelude::*};
+
+use codec::{Encode, Decode};
+use sp_inherents::{InherentIdentifier, InherentData, IsFatalError};
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+use sp_trie::TrieMut;
+
+pub use sp_inherents::{Error, ProvideInherentData};
+
+/// The identifier for the proof inherent.
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"tx_proof";
+/// Default storage period for data.
+pub const DEFAULT_STORAGE_PERIOD: u32 = 100800;
+/// Proof trie value size.
+pub const CHUNK_SIZE: usize = 256;
+
+/// Errors that can occur while checking the storage proof.
+#[derive(Encode, sp_runtime::RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode))]
+pub enum InherentError {
+	InvalidProof,
+	TrieError,
+}
+
+impl IsFatalError for InherentError {
+	fn is_fatal_error(&self) -> bool {
+		true
+	}
+}
+
+/// Proof that a randomly selected chunk of data indexed by the chain is still held by the
+/// block author.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, sp_runtime::RuntimeDebug)]
+pub struct TransactionStorageProof {
+	/// Data chunk that is proved to exist.
+	pub chunk: Vec<u8>,
+	/// Trie nodes that compose the proof.
+	pub proof: Vec<Vec<u8>>,
+}
+
+sp_api::decl_runtime_apis! {
+	/// Runtime API of the transaction storage.
+	pub trait TransactionStorageApi {
+		/// Returns the number of blocks for which stored data is kept.
+		fn storage_period() -> NumberFor<Block>;
+	}
+}
+
+/// Auxiliary trait to extract storage proof.
+pub trait TransactionStorageProofInherentData {
+	/// Get the proof.
+	fn storage_proof(&self) -> Result<Option<TransactionStorageProof>, Error>;
+}
+
+impl TransactionStorageProofInherentData for InherentData {
+	fn storage_proof(&self) -> Result<Option<TransactionStorageProof>, Error> {
+		self.get_data(&INHERENT_IDENTIFIER)
+	}
+}
+
+/// Get chunk index for the given random hash and the total number of chunks.
+pub fn random_chunk(random_hash: &[u8], total_chunks: u32) -> u32 {
+	let mut buf = [0u8; 8];
+	let len = sp_std::cmp::min(buf.len(), random_hash.len());
+	buf[..len].copy_from_slice(&random_hash[..len]);
+	let random = u64::from_be_bytes(buf);
+	(random % total_chunks as u64) as u32
+}
+
+/// Encode proof trie key for the given chunk index.
+pub fn encode_index(input: u32) -> Vec<u8> {
+	codec::Encode::encode(&codec::Compact(input))
+}
+
+type Hasher = sp_runtime::traits::BlakeTwo256;
+type TrieLayout = sp_trie::Layout::<Hasher>;
+
+/// An interface to request indexed data from the client.
+pub trait IndexedBody<B: BlockT> {
+	/// Get all indexed transactions for a block,
+	/// including renewed transactions.
+	///
+	/// Note that this will only fetch transactions
+	/// that are indexed by the runtime with `storage_index_transaction`.
+	fn block_indexed_body(
+		&self,
+		number: NumberFor<B>,
+	) -> Result<Option<Vec<Vec<u8>>>, Error>;
+}
+
+/// Build a proof for a given source of randomness and indexed transactions.
+pub fn build_proof(random_hash: &[u8], transactions: Vec<Vec<u8>>)
+	-> Result<TransactionStorageProof, Error>
+{
+	let mut db = sp_trie::MemoryDB::<Hasher>::default();
+
+	let total_chunks: u32 = transactions.iter()
+		.map(|t| ((t.len() + CHUNK_SIZE - 1) / CHUNK_SIZE) as u32)
+		.sum();
+	if total_chunks == 0 {
+		return Err(Error::from("No chunks to prove"));
+	}
+	let target_chunk_index = random_chunk(random_hash, total_chunks);
+
+	// Find the transaction that holds the target chunk and build its trie.
+	let mut chunk_index = 0;
+	for transaction in transactions {
+		let chunks = (transaction.len() + CHUNK_SIZE - 1) / CHUNK_SIZE;
+		if target_chunk_index >= chunk_index + chunks as u32 {
+			chunk_index += chunks as u32;
+			continue;
+		}
+		let mut transaction_root = sp_trie::empty_child_trie_root::<TrieLayout>();
+		{
+			let mut trie = sp_trie::TrieDBMut::<TrieLayout>::new(&mut db, &mut transaction_root);
+			for (index, chunk) in transaction.chunks(CHUNK_SIZE).enumerate() {
+				trie.insert(&encode_index(index as u32), chunk)
+					.map_err(|_| Error::from("Trie error"))?;
+			}
+			trie.commit();
+		}
+		let index = (target_chunk_index - chunk_index) as usize;
+		let chunk_key = encode_index(index as u32);
+		let proof = sp_trie::generate_trie_proof::<TrieLayout, _, _, _>(
+			&db,
+			transaction_root,
+			&[chunk_key],
+		).map_err(|_| Error::from("Trie error"))?;
+		let chunk = transaction.chunks(CHUNK_SIZE)
+			.nth(index)
+			.expect("Target chunk is within the transaction chunk range; qed")
+			.to_vec();
+		return Ok(TransactionStorageProof { chunk, proof });
+	}
+
+	Err(Error::from("Target chunk not found"))
+}
+
+#[cfg(feature = "std")]
+pub mod registration {
+	use sp_api::ProvideRuntimeApi;
+	use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Saturating, Zero, One}};
+	use super::*;
+
+	/// Provides the storage proof inherent for the block that is being built on top of the
+	/// current best block.
+	///
+	/// The proof targets the block that leaves the retention window with the new block, as
+	/// given by the storage period of the runtime at the best block, and the chunk is selected
+	/// using the best block hash as the source of randomness.
+	pub struct InherentDataProvider<B, C> {
+		client: std::sync::Arc<C>,
+		_marker: std::marker::PhantomData<B>,
+	}
+
+	impl<B, C> InherentDataProvider<B, C> {
+		/// Create a new instance.
+		pub fn new(client: std::sync::Arc<C>) -> Self {
+			InherentDataProvider { client, _marker: Default::default() }
+		}
+	}
+
+	impl<B, C> ProvideInherentData for InherentDataProvider<B, C> where
+		B: BlockT,
+		C: IndexedBody<B> + sp_blockchain::HeaderBackend<B> + ProvideRuntimeApi<B> + Send + Sync,
+		C::Api: TransactionStorageApi<B>,
+	{
+		fn inherent_identifier(&self) -> &'static InherentIdentifier {
+			&INHERENT_IDENTIFIER
+		}
+
+		fn provide_inherent_data(&self, inherent_data: &mut InherentData) -> Result<(), Error> {
+			let info = self.client.info();
+			let number = info.best_number.saturating_add(One::one());
+			let period = self.client.runtime_api()
+				.storage_period(&BlockId::Hash(info.best_hash))
+				.map_err(|e| Error::from(format!("Failed to read the storage period: {:?}", e)))?;
+			if number <= period {
+				return Ok(());
+			}
+			let target = number.saturating_sub(period);
+			if target.is_zero() {
+				return Ok(());
+			}
+			let transactions = self.client.block_indexed_body(target)?;
+			let transactions = match transactions {
+				Some(t) if !t.is_empty() => t,
+				_ => return Ok(()),
+			};
+			let proof = build_proof(info.best_hash.as_ref(), transactions)?;
+			inherent_data.put_data(INHERENT_IDENTIFIER, &proof)
+		}
+
+		fn error_to_string(&self, error: &[u8]) -> Option<String> {
+			InherentError::decode(&mut &error[..]).map(|e| format!("{:?}", e)).ok()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_trie::TrieConfiguration;
+
+	#[test]
+	fn build_proof_check() {
+		let random = [0u8; 32];
+		let data = vec![vec![42u8; CHUNK_SIZE + 1], vec![7u8; 10]];
+		let proof = build_proof(&random, data.clone()).unwrap();
+		let root = TrieLayout::ordered_trie_root(
+			data[0].chunks(CHUNK_SIZE).map(|c| c.to_vec()).collect::<Vec<_>>()
+		);
+		assert_eq!(proof.chunk, vec![42u8; CHUNK_SIZE]);
+		sp_trie::verify_trie_proof::<TrieLayout, _, _, _>(
+			&root,
+			&proof.proof,
+			&[(encode_index(0), Some(proof.chunk))],
+		).unwrap();
+	}
+
+	#[test]
+	fn build_proof_selects_chunk_across_transactions() {
+		// 3 chunks in total, so this hash selects the last one.
+		let mut random = [0u8; 32];
+		random[7] = 2;
+		let data = vec![vec![42u8; CHUNK_SIZE + 1], vec![7u8; 10]];
+		let proof = build_proof(&random, data.clone()).unwrap();
+		let root = TrieLayout::ordered_trie_root(vec![data[1].clone()]);
+		assert_eq!(proof.chunk, vec![7u8; 10]);
+		sp_trie::verify_trie_proof::<TrieLayout, _, _, _>(
+			&root,
+			&proof.proof,
+			&[(encode_index(0), Some(proof.chunk))],
+		).unwrap();
+	}
+}
@@ -717,6 +717,23 @@ cfg_if! {
 						AuraId::from(authority)
 					}).collect()
 				}
+
+				fn submit_report_equivocation_unsigned_extrinsic(
+					_equivocation_proof: sp_consensus_aura::EquivocationProof<
+						<Block as BlockT>::Header,
+						AuraId,
+					>,
+					_key_owner_proof: sp_consensus_aura::OpaqueKeyOwnershipProof,
+				) -> Option<()> {
+					None
+				}
+
+				fn generate_key_ownership_proof(
+					_slot_number: u64,
+					_authority_id: AuraId,
+				) -> Option<sp_consensus_aura::OpaqueKeyOwnershipProof> {
+					None
+				}
 			}
 
 			impl sp_consensus_babe::BabeApi<Block> for Runtime {
@@ -968,6 +985,23 @@ cfg_if! {
 						AuraId::from(authority)
 					}).collect()
 				}
+
+				fn submit_report_equivocation_unsigned_extrinsic(
+					_equivocation_proof: sp_consensus_aura::EquivocationProof<
+						<Block as BlockT>::Header,
+						AuraId,
+					>,
+					_key_owner_proof: sp_consensus_aura::OpaqueKeyOwnershipProof,
+				) -> Option<()> {
+					None
+				}
+
+				fn generate_key_ownership_proof(
+					_slot_number: u64,
+					_authority_id: AuraId,
+				) -> Option<sp_consensus_aura::OpaqueKeyOwnershipProof> {
+					None
+				}
 			}
 
 			impl sp_consensus_babe::BabeApi<Block> for Runtime {
@@ -76,27 +76,34 @@ where
 			DatabaseConfig::Custom(sp_database::as_database(db))
 		},
 		keystore: KeystoreConfig::InMemory,
+		keystore_remote: None,
 		default_heap_pages: Default::default(),
 		dev_key_seed: Default::default(),
 		disable_grandpa: Default::default(),
+		grandpa_stall_threshold: Default::default(),
 		execution_strategies: Default::default(),
 		force_authoring: Default::default(),
+		backoff_authoring_blocks: true,
 		impl_name: String::from("parity-substrate"),
 		impl_version: String::from("0.0.0"),
 		offchain_worker: Default::default(),
 		prometheus_config: Default::default(),
 		pruning: Default::default(),
+		transaction_storage: Default::default(),
+		keep_blocks: Default::default(),
 		rpc_cors: Default::default(),
 		rpc_http: Default::default(),
 		rpc_ipc: Default::default(),
 		rpc_ws: Default::default(),
 		rpc_ws_max_connections: Default::default(),
+		rpc_limits: Default::default(),
 		rpc_methods: Default::default(),
 		state_cache_child_ratio: Default::default(),
 		state_cache_size: Default::default(),
 		tracing_receiver: Default::default(),
 		tracing_targets: Default::default(),
 		transaction_pool: Default::default(),
+		transaction_pool_persistence: None,
 		wasm_method: Default::default(),
 		wasm_runtime_overrides: Default::default(),
 		max_runtime_instances: 8,